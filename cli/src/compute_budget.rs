@@ -0,0 +1,121 @@
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, pubkey::Pubkey,
+};
+
+/// Most accounts `getRecentPrioritizationFees` accepts
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Priority fee paid per compute unit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriorityFee {
+    None,
+    Fixed(u64),
+    /// The 75th percentile of recent fees paid to write the same accounts
+    Auto,
+}
+
+/// `instructions` behind the compute budget instructions setting `limit`
+/// and paying `price` per compute unit
+pub fn prepend_compute_budget(
+    limit: Option<u32>,
+    price: Option<u64>,
+    instructions: &[Instruction],
+) -> Vec<Instruction> {
+    limit
+        .map(ComputeBudgetInstruction::set_compute_unit_limit)
+        .into_iter()
+        .chain(price.map(ComputeBudgetInstruction::set_compute_unit_price))
+        .chain(instructions.iter().cloned())
+        .collect()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcPrioritizationFee {
+    prioritization_fee: u64,
+}
+
+/// The 75th percentile of the priority fees recently paid by transactions
+/// writing any of `instructions`' writable accounts
+pub fn recent_priority_fee(
+    rpc: &RpcClient,
+    instructions: &[Instruction],
+) -> Result<u64, ClientError> {
+    let mut accounts: Vec<Pubkey> = vec![];
+    for meta in instructions.iter().flat_map(|instruction| &instruction.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+
+    let accounts: Vec<String> = accounts.iter().map(Pubkey::to_string).collect();
+    let fees: Vec<RpcPrioritizationFee> = rpc.send(
+        RpcRequest::Custom {
+            method: "getRecentPrioritizationFees",
+        },
+        json!([accounts]),
+    )?;
+
+    let mut fees: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+    fees.sort_unstable();
+    Ok(fees.get(fees.len().saturating_sub(1) * 3 / 4).copied().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{compute_budget, instruction::AccountMeta};
+
+    fn instruction() -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1],
+            vec![AccountMeta::new(Pubkey::new_unique(), false)],
+        )
+    }
+
+    #[test]
+    fn budget_instructions_come_first() {
+        let instructions = [instruction(), instruction()];
+
+        let budgeted = prepend_compute_budget(Some(200_000), Some(5), &instructions);
+
+        assert_eq!(
+            budgeted,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                ComputeBudgetInstruction::set_compute_unit_price(5),
+                instructions[0].clone(),
+                instructions[1].clone(),
+            ]
+        );
+        assert!(budgeted[..2]
+            .iter()
+            .all(|instruction| instruction.program_id == compute_budget::id()));
+    }
+
+    #[test]
+    fn budget_instructions_are_only_added_when_set() {
+        let instructions = [instruction()];
+
+        assert_eq!(prepend_compute_budget(None, None, &instructions), instructions);
+        assert_eq!(
+            prepend_compute_budget(None, Some(5), &instructions),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_price(5),
+                instructions[0].clone(),
+            ]
+        );
+        assert_eq!(
+            prepend_compute_budget(Some(1), None, &instructions),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+                instructions[0].clone(),
+            ]
+        );
+    }
+}
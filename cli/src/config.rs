@@ -0,0 +1,211 @@
+use std::{env, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use solana_cli_config::{load_config_file, save_config_file};
+
+/// Names the DataNexus config file, replacing the default location
+pub const CONFIG_ENV: &str = "DATANEXUS_CONFIG";
+
+const DEFAULT_COMMITMENT: &str = "finalized";
+
+/// Settings persisted by `config set`, kept apart from the solana CLI config
+/// so DataNexus can target another cluster or keypair without touching it
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct DataNexusConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_rpc_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keypair_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+}
+
+impl DataNexusConfig {
+    /// `DATANEXUS_CONFIG` when set, `~/.config/datanexus/config.yml`
+    /// otherwise
+    pub fn path() -> Option<String> {
+        env::var(CONFIG_ENV).ok().or_else(|| {
+            let home = env::var_os("HOME")?;
+            let path = Path::new(&home).join(".config/datanexus/config.yml");
+            Some(path.to_string_lossy().into_owned())
+        })
+    }
+
+    /// Loads the config at `path`, a missing file is an empty config
+    pub fn load(path: &str) -> Result<Self, io::Error> {
+        if Path::new(path).exists() {
+            load_config_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), io::Error> {
+        save_config_file(self, path)
+    }
+}
+
+/// Settings passed as flags for a single invocation
+#[derive(Default)]
+pub struct Overrides<'a> {
+    pub json_rpc_url: Option<&'a str>,
+    pub commitment: Option<&'a str>,
+}
+
+/// The cluster, keypair and commitment a command runs with
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub json_rpc_url: String,
+    pub websocket_url: String,
+    pub keypair_path: String,
+    pub commitment: String,
+}
+
+impl Settings {
+    /// Takes each setting from the first of `flags`, the DataNexus config
+    /// and the solana CLI config that sets it
+    pub fn resolve(
+        flags: &Overrides,
+        datanexus: &DataNexusConfig,
+        solana: &solana_cli_config::Config,
+    ) -> Self {
+        let json_rpc_url = flags
+            .json_rpc_url
+            .map(str::to_string)
+            .or_else(|| datanexus.json_rpc_url.clone());
+
+        // A websocket URL set alongside another cluster would point at the
+        // wrong one, so it is derived from any overridden RPC URL
+        let websocket_url = match (&datanexus.websocket_url, &json_rpc_url) {
+            (Some(websocket_url), _) if flags.json_rpc_url.is_none() => websocket_url.clone(),
+            (_, Some(json_rpc_url)) => {
+                solana_cli_config::Config::compute_websocket_url(json_rpc_url)
+            }
+            _ if solana.websocket_url.is_empty() => {
+                solana_cli_config::Config::compute_websocket_url(&solana.json_rpc_url)
+            }
+            _ => solana.websocket_url.clone(),
+        };
+
+        let commitment = flags
+            .commitment
+            .map(str::to_string)
+            .or_else(|| datanexus.commitment.clone())
+            .unwrap_or_else(|| match solana.commitment.as_str() {
+                "" => DEFAULT_COMMITMENT.to_string(),
+                commitment => commitment.to_string(),
+            });
+
+        Settings {
+            json_rpc_url: json_rpc_url.unwrap_or_else(|| solana.json_rpc_url.clone()),
+            websocket_url,
+            keypair_path: datanexus
+                .keypair_path
+                .clone()
+                .unwrap_or_else(|| solana.keypair_path.clone()),
+            commitment,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solana() -> solana_cli_config::Config {
+        solana_cli_config::Config {
+            json_rpc_url: "https://solana.rpc".to_string(),
+            websocket_url: String::new(),
+            keypair_path: "solana.json".to_string(),
+            commitment: "confirmed".to_string(),
+            ..solana_cli_config::Config::default()
+        }
+    }
+
+    fn datanexus() -> DataNexusConfig {
+        DataNexusConfig {
+            json_rpc_url: Some("https://datanexus.rpc".to_string()),
+            websocket_url: Some("wss://datanexus.ws".to_string()),
+            keypair_path: Some("datanexus.json".to_string()),
+            commitment: Some("processed".to_string()),
+        }
+    }
+
+    #[test]
+    fn flags_come_first() {
+        let flags = Overrides {
+            json_rpc_url: Some("https://flag.rpc"),
+            commitment: Some("finalized"),
+        };
+
+        let settings = Settings::resolve(&flags, &datanexus(), &solana());
+
+        assert_eq!(settings.json_rpc_url, "https://flag.rpc");
+        assert_eq!(settings.websocket_url, "wss://flag.rpc/");
+        assert_eq!(settings.commitment, "finalized");
+        assert_eq!(settings.keypair_path, "datanexus.json");
+    }
+
+    #[test]
+    fn the_datanexus_config_comes_before_the_solana_config() {
+        let settings = Settings::resolve(&Overrides::default(), &datanexus(), &solana());
+
+        assert_eq!(
+            settings,
+            Settings {
+                json_rpc_url: "https://datanexus.rpc".to_string(),
+                websocket_url: "wss://datanexus.ws".to_string(),
+                keypair_path: "datanexus.json".to_string(),
+                commitment: "processed".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn the_solana_config_is_the_fallback() {
+        let settings = Settings::resolve(
+            &Overrides::default(),
+            &DataNexusConfig::default(),
+            &solana(),
+        );
+
+        assert_eq!(
+            settings,
+            Settings {
+                json_rpc_url: "https://solana.rpc".to_string(),
+                websocket_url: "wss://solana.rpc/".to_string(),
+                keypair_path: "solana.json".to_string(),
+                commitment: "confirmed".to_string(),
+            }
+        );
+
+        let solana = solana_cli_config::Config {
+            commitment: String::new(),
+            ..solana()
+        };
+        let settings =
+            Settings::resolve(&Overrides::default(), &DataNexusConfig::default(), &solana);
+        assert_eq!(settings.commitment, DEFAULT_COMMITMENT);
+    }
+
+    #[test]
+    fn the_config_file_is_named_by_the_environment() {
+        let path = env::temp_dir().join(format!("datanexus-{}.yml", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        env::set_var(CONFIG_ENV, &path);
+
+        assert_eq!(DataNexusConfig::path(), Some(path.clone()));
+        assert_eq!(
+            DataNexusConfig::load(&path).unwrap(),
+            DataNexusConfig::default()
+        );
+        datanexus().save(&path).unwrap();
+        let loaded = DataNexusConfig::load(&path);
+
+        env::remove_var(CONFIG_ENV);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), datanexus());
+    }
+}
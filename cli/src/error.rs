@@ -0,0 +1,199 @@
+use std::fmt;
+
+use datanexus::error::DataNexusError;
+use datanexus_utils::{
+    client::FetchError, lineage::TraceError, rent::RentError, stats::StatsError,
+};
+use serde_json::{json, Value};
+use solana_client::client_error::ClientError;
+use solana_sdk::{
+    instruction::InstructionError, program_error::ProgramError, signature::Signature,
+    transaction::TransactionError,
+};
+
+use crate::send::SendError;
+
+/// Why a command failed, each kind exits with its own code so scripts can
+/// tell them apart
+#[derive(Debug)]
+pub enum CliError {
+    /// Invalid input, or on-chain state the command can't act on
+    User(String),
+    /// The cluster couldn't be reached or rejected the request
+    Rpc(ClientError),
+    /// An instruction failed inside a program
+    Program {
+        index: u8,
+        error: InstructionError,
+        logs: Vec<String>,
+    },
+    /// A transaction was sent but whether it landed is unknown, `signature`
+    /// has to be checked before sending it again
+    Unconfirmed {
+        signature: Signature,
+        error: ClientError,
+    },
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::User(_) => 2,
+            CliError::Rpc(_) => 3,
+            CliError::Program { .. } => 4,
+            CliError::Unconfirmed { .. } => 5,
+        }
+    }
+
+    /// Name of the `DataNexusError` behind a custom program error
+    fn program_error(&self) -> Option<DataNexusError> {
+        match self {
+            CliError::Program {
+                error: InstructionError::Custom(code),
+                ..
+            } => DataNexusError::from_code(*code),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let logs = match self {
+            CliError::Program { logs, .. } => logs.clone(),
+            _ => vec![],
+        };
+
+        let signature = match self {
+            CliError::Unconfirmed { signature, .. } => Some(signature.to_string()),
+            _ => None,
+        };
+
+        json!({
+            "error": self.to_string(),
+            "code": self.exit_code(),
+            "signature": signature,
+            "program_error": self.program_error().map(|e| format!("{:?}", e)),
+            "logs": logs,
+        })
+    }
+
+    pub fn from_transaction_error(error: TransactionError, logs: Vec<String>) -> Self {
+        match error {
+            TransactionError::InstructionError(index, error) => {
+                CliError::Program { index, error, logs }
+            }
+            error => CliError::Rpc(error.into()),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::User(message) => write!(f, "{}", message),
+            CliError::Rpc(e) => write!(f, "{}", e),
+            CliError::Program {
+                index,
+                error: InstructionError::Custom(code),
+                ..
+            } => match self.program_error() {
+                Some(e) => write!(f, "Instruction {} failed: {} (code {})", index, e, code),
+                None => write!(f, "Instruction {} failed: custom error {}", index, code),
+            },
+            CliError::Program { index, error, .. } => {
+                write!(f, "Instruction {} failed: {}", index, error)
+            }
+            CliError::Unconfirmed { signature, error } => write!(
+                f,
+                "Transaction {} may have landed, check its status before resending: {}",
+                signature, error
+            ),
+        }
+    }
+}
+
+impl From<ClientError> for CliError {
+    fn from(e: ClientError) -> Self {
+        match e.get_transaction_error() {
+            Some(TransactionError::InstructionError(index, error)) => CliError::Program {
+                index,
+                error,
+                logs: vec![],
+            },
+            _ => CliError::Rpc(e),
+        }
+    }
+}
+
+impl From<SendError> for CliError {
+    fn from(e: SendError) -> Self {
+        match e {
+            SendError::NotLanded(e) => e.into(),
+            SendError::Failed(e) => CliError::from_transaction_error(e, vec![]),
+            SendError::Unknown { signature, error } => CliError::Unconfirmed { signature, error },
+        }
+    }
+}
+
+impl From<FetchError> for CliError {
+    fn from(e: FetchError) -> Self {
+        match e {
+            FetchError::Client(e) => (*e).into(),
+            FetchError::AccountMissing(address) => {
+                CliError::User(format!("no account found at {}", address))
+            }
+            FetchError::AccountExists(address) => {
+                CliError::User(format!("{} already exists", address))
+            }
+            FetchError::WrongOwner(address) => {
+                CliError::User(format!("{} is not a DataNexus account", address))
+            }
+            FetchError::AccountMismatch { given, expected } => CliError::User(format!(
+                "{} was given where {} was expected",
+                given, expected
+            )),
+            FetchError::DecodeFailed(e) => {
+                CliError::User(format!("failed to decode account: {}", e))
+            }
+            FetchError::Pack(e) => CliError::User(format!("failed to pack transactions: {:?}", e)),
+        }
+    }
+}
+
+impl From<TraceError> for CliError {
+    fn from(e: TraceError) -> Self {
+        match e {
+            TraceError::Fetch(e) => e.into(),
+            TraceError::NoAccess(authority) => {
+                CliError::User(format!("{} has no access to the dataset", authority))
+            }
+            TraceError::Cycle(authority) => {
+                CliError::User(format!("share chain loops back to {}", authority))
+            }
+            TraceError::TooDeep => CliError::User("share chain is too deep to trace".to_string()),
+        }
+    }
+}
+
+impl From<StatsError> for CliError {
+    fn from(e: StatsError) -> Self {
+        match e {
+            StatsError::Client(e) => (*e).into(),
+            StatsError::Decode(e) => CliError::User(format!("failed to decode dataset: {}", e)),
+        }
+    }
+}
+
+impl From<RentError> for CliError {
+    fn from(e: RentError) -> Self {
+        match e {
+            RentError::Client(e) => (*e).into(),
+            RentError::Space(e) => e.into(),
+        }
+    }
+}
+
+impl From<ProgramError> for CliError {
+    fn from(e: ProgramError) -> Self {
+        CliError::User(e.to_string())
+    }
+}
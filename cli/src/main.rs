@@ -1,442 +1,5438 @@
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, Read, Write},
+};
+
 use solana_sdk::{
-    self, program_pack::Pack, pubkey::Pubkey, signature::Signer, system_program,
+    self,
+    account::from_account,
+    clock::Clock,
+    commitment_config::CommitmentConfig,
+    program_error::ProgramError,
+    program_pack::Pack,
+    hash::{Hash, Hasher, ParseHashError},
+    pubkey::Pubkey,
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    signature::{write_keypair_file, Keypair, Signature, Signer},
+    signer::{null_signer::NullSigner, unique_signers},
+    system_program, sysvar,
     transaction::Transaction,
 };
 
 use solana_clap_utils::{
-    input_parsers::{pubkey_of, value_of},
-    input_validators::{is_amount, is_hash, is_keypair, is_parsable, is_pubkey, is_url},
-    keypair::DefaultSigner,
+    input_parsers::{pubkey_of, pubkeys_of, value_of},
+    input_validators::{
+        is_amount, is_hash, is_parsable, is_pubkey, is_pubkey_sig, is_url, is_valid_signer,
+    },
+    keypair::{signer_from_path, signer_from_path_with_config, SignerFromPathConfig},
+    nonce::{nonce_arg, nonce_authority_arg, NONCE_ARG, NONCE_AUTHORITY_ARG},
+    offline::{blockhash_arg, sign_only_arg, SIGNER_ARG, SIGN_ONLY_ARG},
+};
+use solana_cli_output::{return_signers, OutputFormat as CliOutputFormat};
+
+use solana_client::{
+    blockhash_query::BlockhashQuery, rpc_client::RpcClient,
+    rpc_config::RpcSimulateTransactionConfig,
 };
+use solana_transaction_status::UiTransactionEncoding;
+
+use serde_json::{json, Value};
 
-use solana_client::rpc_client::RpcClient;
+use chrono::DateTime;
 
-use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
-use spl_token::state::Account;
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, StateWithExtensions},
+    state::{Account, Mint},
+};
 
-use clap::{crate_description, crate_name, crate_version, App, AppSettings, Arg, SubCommand};
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, ErrorKind,
+    Shell, SubCommand,
+};
 
 use datanexus::{
-    datanexus_program,
+    accounts::{
+        push_signer_metas, AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        CloseDatasetAccounts, CreateCollectionAccounts, InitVaultAccounts,
+        OwnershipTransferAccounts, RejectShareAccounts, RenewAccessAccounts,
+        SetAccessPreferencesAccounts, SetDataParamsAccounts, WithdrawProceedsAccounts,
+    },
     instruction::{
-        init_data_account, init_index_account, purchase_access, set_data_params, share_access,
-        AccountType, Params,
+        accept_ownership, accept_share, add_to_collection, cancel_ownership_transfer,
+        close_dataset, create_collection, init_account, init_vault, propose_ownership_transfer,
+        reject_share, renew_access, set_access_preferences, set_data_params, withdraw_proceeds,
+    },
+    prelude::{
+        AccountType, DataNexusInstruction, FieldMask, Params, ParamsBuilder, PriceTier, Tag,
+    },
+    state::{
+        pack_tag, AccessInfo, AccessState, DataNexusAccount, DatasetState, MAX_ACCESS_ENTRIES,
+        MAX_INDEX_ENTRIES, TAG_LEN, URI_LEN,
     },
-    state::AccountState,
 };
 
-use datanexus_utils::*;
+mod compute_budget;
+mod config;
+mod error;
+mod manifest;
+mod memo;
+mod output;
+mod preflight;
+mod receipt;
+mod send;
+
+use compute_budget::{recent_priority_fee, PriorityFee};
+use config::{DataNexusConfig, Overrides, Settings};
+use error::CliError;
+use manifest::{parse_hash, parse_pubkey, Manifest, Operation, PlannedRow, RowResult};
+use memo::{append_memo, parse_memo, read_memo_file};
+use output::{CommandOutput, OutputFormat};
+use preflight::{plan_missing, Requirement};
+use receipt::{fetch_confirmed, ConfirmedTransaction};
+use send::send_with_retry;
 
+use datanexus_utils::{
+    client::{
+        fetch_access_for, fetch_access_index, fetch_collection, fetch_dataset, fetch_owner_index,
+        purchase_access_instructions, purchase_collection_transactions, DataNexusClient,
+        FetchError, KnownPurchaseAccounts,
+    },
+    *,
+};
+
+/// `fee_payer` pays for transactions and new index accounts, `authority`
+/// owns the DataNexus accounts instructions act on. Either defaults to the
+/// client keypair on its own, supplying one never changes the other.
 struct Config {
-    payer: Box<dyn Signer>,
+    fee_payer: Box<dyn Signer>,
+    authority: Box<dyn Signer>,
     rpc_client: RpcClient,
+    json_rpc_url: String,
+    websocket_url: String,
+    program_id: Pubkey,
+    max_retries: usize,
+    priority_fee: PriorityFee,
+    compute_unit_limit: Option<u32>,
+    /// Appended to every transaction as an spl-memo instruction
+    memo: Option<String>,
+    dry_run: bool,
+    sign_only: bool,
+    /// Missing accounts a command can create are created in its transaction
+    auto_create: bool,
+    blockhash_query: BlockhashQuery,
+    nonce_account: Option<Pubkey>,
+    nonce_authority: Option<Box<dyn Signer>>,
+    /// Signing members of a multisig authority, which doesn't sign itself
+    multisig_signers: Vec<Box<dyn Signer>>,
+}
+
+fn instruction_json(program_id: &Pubkey, instruction: &Instruction) -> Value {
+    let accounts: Vec<_> = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            json!({
+                "pubkey": meta.pubkey.to_string(),
+                "writable": meta.is_writable,
+                "signer": meta.is_signer,
+            })
+        })
+        .collect();
+
+    // DataNexus instruction data is decoded, anything else is left as hex
+    let data = if instruction.program_id == *program_id {
+        match DataNexusInstruction::unpack(&instruction.data) {
+            Ok(decoded) => format!("{:?}", decoded),
+            Err(_) => format!("{} (undecodable)", output::hex(&instruction.data)),
+        }
+    } else {
+        output::hex(&instruction.data)
+    };
+
+    json!({
+        "program_id": instruction.program_id.to_string(),
+        "accounts": accounts,
+        "data": data,
+    })
+}
+
+/// Simulates `transaction` without verifying signatures
+fn simulate_transaction(
+    config: &Config,
+    transaction: &Transaction,
+    instructions: &[Instruction],
+) -> Result<CommandOutput, CliError> {
+    let result = config
+        .rpc_client
+        .simulate_transaction_with_config(
+            transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+    if let Some(error) = result.err {
+        return Err(CliError::from_transaction_error(error, logs));
+    }
+
+    // solana-client 1.7 doesn't report consumption directly, the runtime logs
+    // it for each top level instruction instead
+    let units_consumed: u64 = logs
+        .iter()
+        .filter_map(|log| log.split(" consumed ").nth(1))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .filter_map(|units| units.parse::<u64>().ok())
+        .sum();
+
+    Ok(CommandOutput::Simulation {
+        instructions: instructions
+            .iter()
+            .map(|instruction| instruction_json(&config.program_id, instruction))
+            .collect(),
+        units_consumed,
+        logs,
+    })
+}
+
+/// Events the program logged in a confirmed transaction, empty if the
+/// transaction can't be fetched back
+fn transaction_events(config: &Config, signature: &Signature) -> Vec<String> {
+    let logs = config
+        .rpc_client
+        .get_transaction(signature, UiTransactionEncoding::Json)
+        .ok()
+        .and_then(|transaction| transaction.transaction.meta)
+        .and_then(|meta| meta.log_messages)
+        .unwrap_or_default();
+
+    describe_events(&logs)
+}
+
+fn describe_events(logs: &[String]) -> Vec<String> {
+    parse_events(logs)
+        .iter()
+        .map(|event| format!("{:?}", event))
+        .collect()
+}
+
+/// The configured and `extra_signers` `message` needs, each only once since
+/// a hardware wallet may back several of them
+fn transaction_signers<'a>(
+    config: &'a Config,
+    message: &Message,
+    nonce_authority: Option<&'a dyn Signer>,
+    extra_signers: &[&'a dyn Signer],
+) -> Vec<&'a dyn Signer> {
+    let num_required_signatures = message.header.num_required_signatures as usize;
+    let required_signers = &message.account_keys[..num_required_signatures];
+
+    unique_signers(
+        vec![&*config.fee_payer, &*config.authority]
+            .into_iter()
+            .chain(nonce_authority)
+            .chain(config.multisig_signers.iter().map(|signer| &**signer))
+            .chain(extra_signers.iter().copied())
+            .filter(|signer| required_signers.contains(&signer.pubkey()))
+            .collect(),
+    )
+}
+
+/// `instructions` behind the configured compute budget instructions and
+/// ahead of the memo, every transaction the CLI sends is built from this
+fn with_compute_budget(
+    config: &Config,
+    instructions: &[Instruction],
+) -> Result<Vec<Instruction>, CliError> {
+    let price = match config.priority_fee {
+        PriorityFee::None => None,
+        PriorityFee::Fixed(price) => Some(price),
+        PriorityFee::Auto => Some(recent_priority_fee(&config.rpc_client, instructions)?),
+    };
+
+    Ok(append_memo(
+        config.memo.as_deref(),
+        &compute_budget::prepend_compute_budget(config.compute_unit_limit, price, instructions),
+    ))
+}
+
+/// Signs for the nonce account, the fee payer unless set
+fn nonce_authority(config: &Config) -> &dyn Signer {
+    config.nonce_authority.as_deref().unwrap_or(&*config.fee_payer)
+}
+
+/// An unsigned transaction of `instructions` from the fee payer, advancing
+/// the nonce account first when one is set, with the blockhash to sign it
+/// against
+///
+/// Fails when a memo pushes the transaction past the packet limit, so an
+/// oversize memo is rejected before anything is signed.
+fn build_transaction(
+    config: &Config,
+    instructions: &[Instruction],
+) -> Result<(Transaction, Hash), CliError> {
+    let fee_payer = config.fee_payer.pubkey();
+    let message = match config.nonce_account {
+        Some(nonce_account) => Message::new_with_nonce(
+            instructions.to_vec(),
+            Some(&fee_payer),
+            &nonce_account,
+            &nonce_authority(config).pubkey(),
+        ),
+        None => Message::new(instructions, Some(&fee_payer)),
+    };
+    if let Some(memo) = &config.memo {
+        let size = packer::message_size(message.clone());
+        if size > PACKET_DATA_SIZE {
+            return Err(CliError::User(format!(
+                "the transaction is {} bytes with its {} byte memo, over the {} byte limit",
+                size,
+                memo.len(),
+                PACKET_DATA_SIZE
+            )));
+        }
+    }
+
+    // Offline with an explicit blockhash this doesn't touch the cluster
+    let (recent_blockhash, _) = config
+        .blockhash_query
+        .get_blockhash_and_fee_calculator(&config.rpc_client, config.rpc_client.commitment())
+        .map_err(|e| CliError::User(format!("failed to resolve blockhash: {}", e)))?;
+
+    Ok((Transaction::new_unsigned(message), recent_blockhash))
+}
+
+/// A transaction of `instructions` signed by the fee payer, the authority
+/// and nonce authority where the message needs them, and `extra_signers`,
+/// with the signers to refresh its blockhash with
+fn sign_transaction<'a>(
+    config: &'a Config,
+    instructions: &[Instruction],
+    extra_signers: &[&'a dyn Signer],
+) -> Result<(Transaction, Option<Vec<&'a dyn Signer>>), CliError> {
+    let instructions = with_compute_budget(config, instructions)?;
+    let (mut transaction, recent_blockhash) = build_transaction(config, &instructions)?;
+    let signers = transaction_signers(
+        config,
+        &transaction.message,
+        Some(nonce_authority(config)),
+        extra_signers,
+    );
+    transaction
+        .try_sign(&signers, recent_blockhash)
+        .map_err(|e| CliError::User(e.to_string()))?;
+
+    // Re-signing for a fresh blockhash would void presigned signatures and a
+    // nonce never expires, so only a cluster blockhash is refreshed
+    let refresh_signers = match config.blockhash_query {
+        BlockhashQuery::All(_) if config.nonce_account.is_none() => Some(signers),
+        _ => None,
+    };
+    Ok((transaction, refresh_signers))
+}
+
+/// Sends `instructions` in a single transaction, see `sign_transaction`
+/// for who signs it
+fn send_transaction(
+    config: &Config,
+    instructions: &[Instruction],
+    extra_signers: &[&dyn Signer],
+) -> Result<Signature, CliError> {
+    let (mut transaction, refresh_signers) =
+        sign_transaction(config, instructions, extra_signers)?;
+    Ok(send_with_retry(
+        &config.rpc_client,
+        &mut transaction,
+        refresh_signers.as_deref(),
+        config.max_retries,
+        config.rpc_client.commitment(),
+    )?)
+}
+
+/// Sends `instructions` in a single transaction
+///
+/// Under `--dry-run` the transaction is only simulated, under `--sign-only`
+/// it is partially signed and its signatures returned for the online half
+/// to pass back in with `--signer`. Neither broadcasts anything.
+fn execute_or_simulate(
+    config: &Config,
+    instructions: &[Instruction],
+) -> Result<CommandOutput, CliError> {
+    if !config.dry_run && !config.sign_only {
+        let signature = send_transaction(config, instructions, &[])?;
+        return Ok(CommandOutput::Transaction {
+            signature: signature.to_string(),
+            events: transaction_events(config, &signature),
+        });
+    }
+
+    let instructions = &with_compute_budget(config, instructions)?;
+    let (mut transaction, recent_blockhash) = build_transaction(config, instructions)?;
+
+    if config.dry_run {
+        transaction.message.recent_blockhash = recent_blockhash;
+        return simulate_transaction(config, &transaction, instructions);
+    }
+
+    let signers = transaction_signers(
+        config,
+        &transaction.message,
+        Some(nonce_authority(config)),
+        &[],
+    );
+    transaction
+        .try_partial_sign(&signers, recent_blockhash)
+        .map_err(|e| CliError::User(e.to_string()))?;
+    let signed = return_signers(&transaction, &CliOutputFormat::Json)
+        .map_err(|e| CliError::User(e.to_string()))?;
+    let signed = serde_json::from_str(&signed).map_err(|e| CliError::User(e.to_string()))?;
+    Ok(CommandOutput::SignOnly(signed))
 }
 
-fn sign_and_send_transaction(config: &Config, instructions: [Instruction]) -> Signature {
-    let mut transaction = Transaction::new_with_payer(&instructions, Some(config.payer.pubkey()));
-    let recent_blockhash = config.rpc_client.get_recent_blockhash().unwrap().0;
-    transaction.sign([&*config.payer], recent_blockhash);
+/// Rent of a new account of `kind` at the capacity the program creates it
+/// with, unknown offline under `--sign-only`
+fn rent_estimate(config: &Config, kind: AccountKind) -> Result<Option<u64>, CliError> {
+    if config.sign_only {
+        return Ok(None);
+    }
+    let capacity = match kind {
+        AccountKind::AssociatedAccess => MAX_ACCESS_ENTRIES,
+        _ => MAX_INDEX_ENTRIES,
+    };
+    Ok(Some(estimate_rent(&config.rpc_client, kind, capacity)?))
+}
 
+/// The `--multisig-signer` members signing for a multisig authority
+fn multisig_signers(config: &Config) -> Vec<Pubkey> {
     config
+        .multisig_signers
+        .iter()
+        .map(|signer| signer.pubkey())
+        .collect()
+}
+
+/// A client planning instructions for the configured fee payer and
+/// authority, which `execute_or_simulate` signs with the real signers
+fn client(config: &Config) -> DataNexusClient<&RpcClient> {
+    DataNexusClient::with_sender(
+        &config.rpc_client,
+        config.program_id,
+        NullSigner::new(&config.fee_payer.pubkey()),
+    )
+    .with_authority(NullSigner::new(&config.authority.pubkey()))
+}
+
+/// Checks that every account of `requirements` exists with a single RPC
+/// call, returning the instructions creating the missing ones under
+/// `--auto-create` and failing with the command creating each otherwise
+///
+/// Nothing can be checked offline under `--sign-only`.
+fn ensure_accounts(
+    config: &Config,
+    requirements: &[Requirement],
+) -> Result<Vec<Instruction>, CliError> {
+    if config.sign_only {
+        return Ok(vec![]);
+    }
+
+    let addresses: Vec<_> = requirements
+        .iter()
+        .map(|requirement| requirement.address(&config.program_id))
+        .collect();
+    let present: Vec<bool> = config
         .rpc_client
-        .send_and_confirm_transaction_with_spinner(&transaction)
-        .unwrap();
+        .get_multiple_accounts(&addresses)?
+        .iter()
+        .map(Option::is_some)
+        .collect();
+
+    plan_missing(
+        &config.program_id,
+        config.fee_payer.pubkey(),
+        config.authority.pubkey(),
+        requirements,
+        &present,
+        config.auto_create,
+    )
 }
 
-fn command_init_index_account(config: &Config, authority: Pubkey, account_type: AccountType) {
-    let index_account = if account_type == AccountType::Owner {
-        create_owner_address(authority)
+fn command_init_index_account(
+    config: &Config,
+    authority: Pubkey,
+    account_type: AccountType,
+) -> Result<CommandOutput, CliError> {
+    let program_id = &config.program_id;
+    let (index_account, label, kind) = if account_type == AccountType::DatasetIndex {
+        (
+            find_owner_address_with_program_id(program_id, authority).0,
+            "owner index",
+            AccountKind::OwnerIndex,
+        )
     } else {
-        create_access_address(authority)
+        (
+            find_access_address_with_program_id(program_id, authority).0,
+            "access index",
+            AccountKind::AccessIndex,
+        )
     };
 
-    let instructions = [init_index_account(
-        datanexus_program,
-        config.payer.pubkey(),
-        authority,
-        index_account,
-        system_program,
-        account_type,
-    )?];
-
-    let signature = sign_and_send_transaction(config, instructions);
+    let instructions = client(config)
+        .with_authority(NullSigner::new(&authority))
+        .plan_create_index(account_type)?
+        .instructions;
 
-    println!("User {} Account Created: {}", account_type, index_account);
-    println!("Transaction Signature: {}", signature);
+    Ok(CommandOutput::Created {
+        account: label,
+        address: index_account.to_string(),
+        rent: rent_estimate(config, kind)?,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
 }
 
-fn command_init_data_account(config: &Config, hash: [u8; 32]) {
-    let owner_account = get_owner_account(config.payer.pubkey().as_ref());
-    let dataset_account = create_associated_dataset_address(&hash);
+fn command_init_data_account(config: &Config, hash: [u8; 32]) -> Result<CommandOutput, CliError> {
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let instructions = client(config).plan_register_dataset(hash)?.instructions;
+
+    Ok(CommandOutput::Created {
+        account: "dataset",
+        address: dataset_account.to_string(),
+        rent: rent_estimate(config, AccountKind::Dataset)?,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
 
-    let instructions = [init_data_account(
-        datanexus_program,
-        config.payer.pubkey(),
-        owner_account,
+/// Creates the authority's associated access account of the dataset, which
+/// it needs before access can be shared with it
+fn command_init_associated_access(
+    config: &Config,
+    hash: [u8; 32],
+) -> Result<CommandOutput, CliError> {
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let associated_access_account = find_associated_access_address_with_program_id(
+        &config.program_id,
+        config.authority.pubkey(),
         dataset_account,
-        system_program,
-        hash,
-    )?];
+    )
+    .0;
+    let instructions = client(config)
+        .plan_create_associated_access(hash)?
+        .instructions;
+
+    Ok(CommandOutput::Created {
+        account: "associated access",
+        address: associated_access_account.to_string(),
+        rent: rent_estimate(config, AccountKind::AssociatedAccess)?,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
 
-    let signature = send_and_sign_transaction(config, instructions);
+/// Sends one `SetDataParams` instruction per entry of `params`, all in the
+/// same transaction
+fn command_set_data_params(
+    config: &Config,
+    hash: [u8; 32],
+    params: Vec<Params>,
+) -> Result<CommandOutput, CliError> {
+    ensure_accounts(config, &[Requirement::Dataset(hash)])?;
+    let mut instructions = client(config).plan_set_params(hash, params)?.instructions;
+    let signers = multisig_signers(config);
+    for instruction in &mut instructions {
+        push_signer_metas(&mut instruction.accounts, &signers);
+    }
 
-    println!("Dataset Account for {} Created: {}", hash, dataset_account);
-    println!("Transaction Signature: {}", signature);
+    execute_or_simulate(config, &instructions)
 }
 
-fn command_set_data_params(config: &Config, hash: [u8; 32], params: Params) {
-    let dataset_account = get_associated_dataset_address(&hash);
-    let instructions = [set_data_params(
-        datanexus_program,
-        config.payer.pubkey(),
-        dataset_account,
-        hash,
-        params,
-    )?];
+/// Dataset field named by a `set --clear` value
+fn field_mask(field: &str) -> FieldMask {
+    match field {
+        "key" => FieldMask::KEY,
+        "value" => FieldMask::VALUE,
+        "share-limit" => FieldMask::SHARE_LIMIT,
+        "ref-data" => FieldMask::REF_DATA,
+        "uri" => FieldMask::URI,
+        _ => unreachable!("clap only accepts the listed fields"),
+    }
+}
+
+/// The fields a `set` command was given, parsed but not yet combined
+#[derive(Debug, Default)]
+struct SetFields<'a> {
+    init: bool,
+    /// Address of the dataset being set, which it cannot reference
+    dataset: Option<Pubkey>,
+    key: Option<[u8; 32]>,
+    value: Option<u64>,
+    share_limit: Option<u16>,
+    reference_data: Option<Pubkey>,
+    uri: Option<&'a str>,
+    /// `--clear` names of the fields to reset
+    clear: Vec<&'a str>,
+    /// Setters outside the `--init` fields, which nothing else conflicts with
+    others: Vec<Params>,
+}
+
+/// The params of each `SetDataParams` instruction a `set` command sends to
+/// `dataset`, with prices in whole tokens of a mint with `decimals`
+fn set_params_of(
+    args: &ArgMatches,
+    decimals: u8,
+    dataset: Pubkey,
+) -> Result<Vec<Params>, String> {
+    let mut others = vec![];
+    others.extend(value_of(args, "referral_bps").map(Params::ReferralBps));
+    others.extend(value_of(args, "derived_discount_bps").map(Params::DerivedDiscountBps));
+    others.extend(value_of(args, "access_duration").map(Params::AccessDuration));
+    others.extend(value_of(args, "size").map(Params::Size));
+    others.extend(
+        args.value_of("content_type")
+            .map(|content_type| Params::ContentType(content_type.as_bytes().to_vec())),
+    );
+    others.extend(value_of(args, "resharable").map(Params::Resharable));
+    others.extend(value_of(args, "max_share_depth").map(Params::MaxShareDepth));
+    others.extend(value_of(args, "max_holders").map(Params::MaxHolders));
+    others.extend(value_of(args, "prune_bounty").map(Params::PruneBounty));
+    if let Some(share_fee) = args.value_of("share_fee") {
+        let (mint, amount) = parse_share_fee(share_fee)?;
+        others.push(Params::ShareFee { mint, amount });
+    }
+    if let Some(tiers) = args.values_of("tier") {
+        others.push(Params::Tiers(
+            tiers
+                .map(|tier| parse_price_tier(tier, decimals))
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+    // Removals go first, freeing table entries for the prices set after
+    others.extend(
+        pubkeys_of(args, "remove_price")
+            .unwrap_or_default()
+            .into_iter()
+            .map(Params::RemovePrice),
+    );
+    if let Some(prices) = args.values_of("price") {
+        for price in prices {
+            let (mint, amount) = parse_mint_price(price)?;
+            others.push(Params::SetPrice(mint, amount));
+        }
+    }
+    // Likewise removed tags free their slots for the added ones
+    if let Some(tags) = args.values_of("remove_tag") {
+        for tag in tags {
+            others.push(Params::RemoveTag(parse_tag(tag)?));
+        }
+    }
+    if let Some(tags) = args.values_of("tag") {
+        for tag in tags {
+            others.push(Params::AddTag(parse_tag(tag)?));
+        }
+    }
+    let sale_start = timestamp_of(args, "sale_start")?;
+    let sale_end = timestamp_of(args, "sale_end")?;
+    if sale_start.is_some() || sale_end.is_some() {
+        // The window replaces both ends, a sale without a start is open now
+        others.push(Params::SaleWindow {
+            start: sale_start.unwrap_or_default(),
+            end: sale_end,
+        });
+    }
+
+    combine_set_fields(SetFields {
+        init: args.is_present("init"),
+        dataset: Some(dataset),
+        key: hash_of(args, "key"),
+        value: ui_amount_of(args, "value", decimals)?,
+        share_limit: value_of(args, "share_limit"),
+        reference_data: pubkey_of(args, "reference_data"),
+        uri: args.value_of("uri"),
+        clear: args.values_of("clear").map(Iterator::collect).unwrap_or_default(),
+        others,
+    })
+}
+
+/// Combines the fields of a `set` command into params
+///
+/// `--init` sets key, value, share limit and optionally the reference
+/// together, clap rejects any other setter alongside it. Otherwise each
+/// setter given becomes its own instruction, with at most one of the
+/// `--init` fields and no field both set and cleared. The `--init` fields
+/// are built through `ParamsBuilder`, so they are checked before paying for
+/// a transaction.
+fn combine_set_fields(fields: SetFields) -> Result<Vec<Params>, String> {
+    let mut builder = ParamsBuilder::new();
+    if let Some(key) = fields.key {
+        builder = builder.key(key);
+    }
+    if let Some(value) = fields.value {
+        builder = builder.value(value);
+    }
+    if let Some(share_limit) = fields.share_limit {
+        builder = builder.share_limit(share_limit);
+    }
+    if let Some(reference) = fields.reference_data {
+        builder = builder.reference(reference);
+    }
+    if let Some(dataset) = fields.dataset {
+        builder = builder.dataset(dataset);
+    }
+
+    if fields.init {
+        if fields.key.is_none() || fields.value.is_none() || fields.share_limit.is_none() {
+            return Err("--init requires --key, --value and --share-limit".to_string());
+        }
+        return builder
+            .build()
+            .map(|params| vec![params])
+            .map_err(|e| e.to_string());
+    }
+
+    // Flag and `--clear` name of each field and whether it was given, the
+    // first four are the ones `--init` sets together
+    let setters = [
+        ("--key", "key", fields.key.is_some()),
+        ("--value", "value", fields.value.is_some()),
+        ("--share-limit", "share-limit", fields.share_limit.is_some()),
+        ("--ref-data", "ref-data", fields.reference_data.is_some()),
+        ("--uri", "uri", fields.uri.is_some()),
+    ];
+    let init_flags: Vec<&str> = setters[..4]
+        .iter()
+        .filter(|(_, _, set)| *set)
+        .map(|(flag, _, _)| *flag)
+        .collect();
+    if let [first, second, ..] = init_flags[..] {
+        return Err(format!(
+            "`{}` and `{}` cannot be combined; run two set commands or use --init",
+            first, second
+        ));
+    }
+
+    let mut params = vec![];
+    if !fields.clear.is_empty() {
+        if let Some((field, flag)) = fields.clear.iter().find_map(|field| {
+            setters
+                .iter()
+                .find(|(_, clear, set)| clear == field && *set)
+                .map(|(flag, _, _)| (field, flag))
+        }) {
+            return Err(format!("`--clear {}` and `{}` cannot be combined", field, flag));
+        }
+        params.push(Params::Clear(
+            fields
+                .clear
+                .iter()
+                .map(|field| field_mask(field))
+                .fold(FieldMask::default(), |a, b| a | b),
+        ));
+    }
+    if !init_flags.is_empty() {
+        params.push(builder.build().map_err(|e| e.to_string())?);
+    }
+    params.extend(fields.uri.map(|uri| Params::Uri(uri.as_bytes().to_vec())));
+    params.extend(fields.others);
+
+    if params.is_empty() {
+        return Err("nothing to set, pass a field to set, --clear or --init".to_string());
+    }
+    Ok(params)
+}
+
+/// Prints what a purchase will pay and to whom, then asks for a `y` on
+/// stdin unless `--yes` was passed
+///
+/// The program charges whatever amount it is given, so an amount that
+/// doesn't match the dataset's value is flagged before anything is sent.
+#[allow(clippy::too_many_arguments)]
+fn confirm_purchase(
+    config: &Config,
+    hash: [u8; 32],
+    dataset: &DatasetState,
+    mint: Pubkey,
+    mint_state: &StateWithExtensions<Mint>,
+    amount: u64,
+    referrer_token_account: Option<Pubkey>,
+    reference_access: Option<Pubkey>,
+    yes: bool,
+) -> Result<(), CliError> {
+    let decimals = mint_state.base.decimals;
+
+    eprintln!(
+        "Purchasing access to dataset {} from owner {} for {} of mint {}",
+        Hash::new_from_array(hash),
+        dataset.owner,
+        format_ui_amount(amount, decimals),
+        mint
+    );
+    if let Ok(transfer_fee) = mint_state.get_extension::<TransferFeeConfig>() {
+        let epoch = config.rpc_client.get_epoch_info()?.epoch;
+        if let Some(fee) = transfer_fee.calculate_epoch_fee(epoch, amount) {
+            eprintln!("Transfer fee withheld from the owner: {}", format_ui_amount(fee, decimals));
+        }
+    }
+    if referrer_token_account.is_some() {
+        eprintln!(
+            "Referral share: {}.{:02}%",
+            dataset.referral_bps / 100,
+            dataset.referral_bps % 100
+        );
+    }
+    if reference_access.is_some() {
+        eprintln!(
+            "Derived discount: {}.{:02}%",
+            dataset.derived_discount_bps / 100,
+            dataset.derived_discount_bps % 100
+        );
+    }
+    match dataset.share_limit {
+        Some(share_limit) => eprintln!("Share limit: {}", share_limit),
+        None => eprintln!("Share limit: none"),
+    }
+    if let Some(access_duration) = dataset.access_duration {
+        eprintln!("Access expires after {} seconds", access_duration);
+    }
+
+    let price = dataset.price(&mint).map(|value| match reference_access {
+        Some(_) => dataset.derived_price(value),
+        None => value,
+    });
+    match price {
+        Some(value) if value != amount => eprintln!(
+            "WARNING: the dataset is priced at {}, this purchase pays {}",
+            format_ui_amount(value, decimals),
+            format_ui_amount(amount, decimals)
+        ),
+        None if dataset.prices.is_empty() => {
+            eprintln!("WARNING: the dataset has no value set and cannot be purchased")
+        }
+        None => eprintln!(
+            "WARNING: the dataset is not sold for mint {} and cannot be purchased with it",
+            mint
+        ),
+        _ => {}
+    }
+
+    if yes || config.dry_run || config.sign_only {
+        return Ok(());
+    }
 
-    let signature = sign_and_send_transaction(config, [dataset_account], instructions);
-    println!("Transaction Signature: {}", signature);
+    eprint!("Proceed? [y/N] ");
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| CliError::User(format!("failed to read confirmation: {}", e)))?;
+
+    match answer.trim() {
+        "y" | "Y" | "yes" => Ok(()),
+        _ => Err(CliError::User("purchase cancelled".to_string())),
+    }
+}
+
+/// The authority's associated access account of the dataset `dataset` is
+/// derived from, which claims the derived discount
+fn reference_access_of(config: &Config, dataset: &DatasetState) -> Result<Pubkey, CliError> {
+    let ref_data = dataset.ref_data.ok_or_else(|| {
+        CliError::User("the dataset is not derived from another, it has no discount".to_string())
+    })?;
+    Ok(find_associated_access_address_with_program_id(
+        &config.program_id,
+        config.authority.pubkey(),
+        ref_data,
+    )
+    .0)
 }
 
+/// Purchases access paying `amount`, in whole tokens of the mint of
+/// `user_token_account`, at the derived discount when `owns_reference`
+///
+/// Accounts in `known` are taken as given rather than looked up, so the
+/// purchase makes fewer requests of rate limited RPC nodes
+#[allow(clippy::too_many_arguments)]
 fn command_purchase_access(
     config: &Config,
     hash: [u8; 32],
     user_token_account: Pubkey,
-    amount: u64,
-) {
-    let dataset_address = get_dataset_address(&hash);
-    let user_access_account = get_access_address(config.payer.pubkey().as_ref());
-    let user_associated_access_account =
-        create_associated_access_address(config.payer.pubkey().as_ref(), dataset_address.as_ref());
+    amount: &str,
+    referrer_token_account: Option<Pubkey>,
+    owns_reference: bool,
+    yes: bool,
+    receipt_file: Option<&str>,
+    known: KnownPurchaseAccounts,
+) -> Result<CommandOutput, CliError> {
+    check_receipt_file(receipt_file)?;
+    let authority = config.authority.pubkey();
+    let dataset_address = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    if let Some(given) = known.dataset_account {
+        if given != dataset_address {
+            return Err(CliError::User(format!(
+                "{} is not the dataset of hash {}, expected {}",
+                given,
+                Hash::new_from_array(hash),
+                dataset_address
+            )));
+        }
+    }
 
-    let user_token_account_buf = config
-        .rpc_client
-        .get_account_data(&user_token_account)
-        .unwrap();
-    let user_token_account_state = Account::unpack_from_slice(user_token_account_buf)?;
-    let token_mint = user_token_account_state.mint;
-    let dataset_buf = config
-        .rpc_client
-        .get_account_data(&dataset_account)
-        .unwrap()
-        .clone();
-    let dataset_state = AccountState::unpack_from_slice(&dataset_buf)?;
-    let owner_token_account = get_associated_token_address(&dataset_state.owner, &token_mint);
-
-    let instructions = [purchase_access(
-        datanexus_program,
-        config.payer.pubkey(),
-        user_access_account,
-        user_associated_access_account,
-        owner_authority,
-        owner_token_account,
-        dataset_address,
-        spl_token::ID,
+    let mut requirements = vec![
+        Requirement::AccessIndex(authority),
+        Requirement::TokenAccount(user_token_account),
+    ];
+    if known.dataset_account.is_none() {
+        requirements.push(Requirement::Dataset(hash));
+    }
+    requirements.extend(referrer_token_account.map(Requirement::TokenAccount));
+    let created = ensure_accounts(config, &requirements)?;
+
+    let mint = match known.token_mint {
+        Some(mint) => mint,
+        None => {
+            let token_account = config.rpc_client.get_account(&user_token_account)?;
+            StateWithExtensions::<Account>::unpack(&token_account.data)?
+                .base
+                .mint
+        }
+    };
+    // The mint is fetched for its decimals either way, and its owner is the
+    // token program, so the plan needn't fetch the token account again
+    let mint_account = config.rpc_client.get_account(&mint)?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data)?;
+    let known = KnownPurchaseAccounts {
+        token_mint: Some(mint),
+        token_program: Some(mint_account.owner),
+        ..known
+    };
+    let amount = parse_ui_amount(amount, mint_state.base.decimals)
+        .map_err(|e| CliError::User(e.to_string()))?;
+    let dataset = fetch_dataset(&config.rpc_client, &config.program_id, &hash)?;
+    if dataset.owner == authority {
+        return Err(CliError::User(format!(
+            "{} owns dataset {} and has access to it already, owners can't purchase their own datasets",
+            authority,
+            Hash::new_from_array(hash)
+        )));
+    }
+    let reference_access = if owns_reference {
+        Some(reference_access_of(config, &dataset)?)
+    } else {
+        None
+    };
+
+    confirm_purchase(
+        config,
         hash,
+        &dataset,
+        mint,
+        &mint_state,
         amount,
-    )?];
+        referrer_token_account,
+        reference_access,
+        yes,
+    )?;
+
+    let plan = client(config).plan_purchase(
+        hash,
+        user_token_account,
+        amount,
+        referrer_token_account,
+        reference_access,
+        &known,
+    )?;
+    let instructions: Vec<_> = created.into_iter().chain(plan.instructions).collect();
+    if config.dry_run || config.sign_only {
+        return execute_or_simulate(config, &instructions);
+    }
+
+    let signature = send_transaction(config, &instructions, &[])?;
+    let confirmed = fetch_confirmed(&config.rpc_client, &signature)?;
+
+    // The referral share, if any, is paid before the proceeds
+    let paid: Vec<_> = confirmed
+        .transfers
+        .iter()
+        .filter(|transfer| transfer.source == user_token_account.to_string())
+        .collect();
+    let referral = referrer_token_account.map(|referrer| {
+        let amount = paid
+            .iter()
+            .filter(|transfer| transfer.destination == referrer.to_string())
+            .map(|transfer| transfer.amount)
+            .sum();
+        format_ui_amount(amount, mint_state.base.decimals)
+    });
+
+    let output = CommandOutput::Purchased {
+        hash: output::hex(&hash),
+        access_account: find_associated_access_address_with_program_id(
+            &config.program_id,
+            authority,
+            dataset_address,
+        )
+        .0
+        .to_string(),
+        created: plan.created.iter().map(ToString::to_string).collect(),
+        amount: format_ui_amount(
+            paid.iter().map(|transfer| transfer.amount).sum(),
+            mint_state.base.decimals,
+        ),
+        referral,
+        mint: mint.to_string(),
+        destination: paid.last().map(|transfer| transfer.destination.clone()),
+        fee: confirmed.fee,
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        memo: config.memo.clone(),
+        transaction: Box::new(confirmed_transaction(&signature, &confirmed)),
+    };
+    write_receipt(receipt_file, &output)?;
+    Ok(output)
+}
+
+fn confirmed_transaction(signature: &Signature, confirmed: &ConfirmedTransaction) -> CommandOutput {
+    CommandOutput::Transaction {
+        signature: signature.to_string(),
+        events: describe_events(&confirmed.logs),
+    }
+}
 
-    let signature = sign_and_send_transaction(config, instructions);
+/// Fails when `receipt_file` exists, checked before sending so an earlier
+/// receipt is never overwritten
+fn check_receipt_file(receipt_file: Option<&str>) -> Result<(), CliError> {
+    match receipt_file {
+        Some(path) if std::path::Path::new(path).exists() => Err(CliError::User(format!(
+            "{} already exists, choose another receipt file",
+            path
+        ))),
+        _ => Ok(()),
+    }
+}
 
-    println!("purchase test");
+/// Writes `output` as JSON to `receipt_file` when one is given
+fn write_receipt(receipt_file: Option<&str>, output: &CommandOutput) -> Result<(), CliError> {
+    let path = match receipt_file {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let document = serde_json::to_string_pretty(output)
+        .map_err(|e| CliError::User(format!("failed to encode receipt: {}", e)))?;
+    std::fs::write(path, document)
+        .map_err(|e| CliError::User(format!("failed to write {}: {}", path, e)))
 }
 
-fn command_share_access(config: &Config, recipient_authority: Pubkey, hash: [u8; 32]) {
-    let dataset_address = get_dataset_address(&hash);
-    let user_associated_access_account =
-        get_associated_access_address(config.payer.pubkey().as_ref(), dataset_address.as_ref());
-    let recipient_access_account = get_access_address(recipient_authority.as_ref());
-    let recipient_associated_access_account =
-        create_associated_access_address(recipient_authority.as_ref(), dataset_address.as_ref());
+fn command_create_collection(
+    config: &Config,
+    hash: [u8; 32],
+    value: u64,
+) -> Result<CommandOutput, CliError> {
+    let collection_account = find_collection_address_with_program_id(&config.program_id, &hash).0;
 
-    let instructions = [share_access(
-        datanexus_program,
-        config.payer.pubkey(),
-        user_associated_access_account,
-        repicient_authority,
-        recipient_access_account,
-        recipient_associated_access_account,
+    let instructions = [create_collection(
+        config.program_id,
+        CreateCollectionAccounts {
+            authority: config.authority.pubkey(),
+            collection_account,
+            system_program: system_program::id(),
+        },
         hash,
+        value,
     )?];
 
-    let signature = send_and_sign_transaction(config, instructions);
+    Ok(CommandOutput::Created {
+        account: "collection",
+        address: collection_account.to_string(),
+        rent: rent_estimate(config, AccountKind::Collection)?,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+fn command_add_to_collection(
+    config: &Config,
+    collection_hash: [u8; 32],
+    dataset_hash: [u8; 32],
+) -> Result<CommandOutput, CliError> {
+    let instructions = [add_to_collection(
+        config.program_id,
+        AddToCollectionAccounts {
+            authority: config.authority.pubkey(),
+            collection_account: find_collection_address_with_program_id(
+                &config.program_id,
+                &collection_hash,
+            )
+            .0,
+            dataset_account: find_dataset_address_with_program_id(
+                &config.program_id,
+                &dataset_hash,
+            )
+            .0,
+        },
+        collection_hash,
+        dataset_hash,
+    )?];
 
-    println!("share acess test");
+    execute_or_simulate(config, &instructions)
 }
 
-fn main() {
-    let app_matches = App::new(crate_name!())
-        .about(crate_description!())
-        .version(crate_version!())
-        .setting(AppSettings::SubcommandRequiredElseHelp)
-        .arg({
-            let arg = Arg::with_name("config_file")
-                .short("c")
-                .long("config")
-                .value_name("PATH")
-                .takes_value(true)
-                .global(true)
-                .help("Configuration file to use");
-            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
-                arg.default_value(&config_file)
-            } else {
-                arg
-            }
-        })
-        .arg(
-            Arg::with_name("rpc_url")
-                .short("u")
-                .long("url")
-                .validator(is_url)
-                .takes_value(true)
-                .global(true)
-                .help("Specifiy target Solana cluster"),
-        )
-        .arg(
-            Arg::with_name("payer")
-                .short("p")
-                .long("payer")
-                .value_name("KEYPAIR")
-                .validator(is_keypair)
-                .takes_value(true)
-                .help(
-                    "Specify payer. \
-                Defaults to client keypair.",
-                ),
-        )
-        .subcommand(
-            Subcommand::with_name("create")
-                .about("Create an account")
-                .arg(
-                    Arg::with_name("account_type")
-                        .short("t")
-                        .long("type")
-                        .value_name("ACCOUNT TYPE")
-                        .takes_value(true)
-                        .required(true)
-                        .index(1)
-                        .help(
-                            "Specify a user account type to create: \
-                    owner \
-                    access \
-                    data",
-                        ),
-                )
-                .arg(
-                    Arg::with_name("authority")
-                        .short("a")
-                        .long("authority")
-                        .value_name("PUBKEY")
-                        .validator(is_pubkey)
-                        .takes_value(true)
-                        .help("Public Key of the owner of the created account"),
-                )
-                .arg(
-                    Arg::with_name("hash")
-                        .short("h")
-                        .long("hash")
-                        .validator(is_hash)
-                        .value_name("HASH")
-                        .takes_value(true)
-                        .help("Hash for dataset accounts"),
-                ),
-        )
-        .subcommand(
-            Subcommand::with_name("set")
-                .about("Set dataset parameters")
-                .arg(
-                    Arg::with_name("hash")
-                        .short("h")
-                        .long("hash")
-                        .value_name("HASH")
-                        .validator(is_hash)
-                        .takes_value(true)
-                        .required(true)
-                        .index(1)
-                        .help("Hash of target dataset"),
-                )
-                .arg(
-                    Arg::with_name("key")
-                        .short("k")
-                        .long("key")
-                        .value_name("KEY")
-                        .takes_value(true)
-                        .help("Key for target dataset"),
-                )
-                .arg(
-                    Arg::with_name("value")
-                        .short("v")
-                        .long("value")
-                        .value_name("AMOUNT")
-                        .validator(is_amount)
-                        .takes_value(true)
-                        .help("Value of target dataset in USD"),
-                )
-                .arg(
-                    Arg::with_name("share_limit")
-                        .short("l")
-                        .long("share-limit")
-                        .value_name("AMOUNT")
-                        .validator(is_amount)
-                        .takes_value(true)
-                        .help("Number of times the target dataset can be shared"),
-                )
-                .arg(
-                    Arg::with_name("reference_data")
-                        .short("r")
-                        .long("ref-data")
-                        .value_name("PUBKEY")
-                        .validator(is_pubkey)
-                        .takes_value(true)
-                        .help("Address of the dataset the target dataset is derived from"),
-                ),
-        )
-        .subcommand(
-            Subcommand::with_name("purchase_access")
-                .about("Purchase access to a dataset")
-                .arg(
-                    Arg::with_name("hash")
-                        .short("h")
-                        .long("hash")
-                        .value_name("HASH")
-                        .validator(is_hash)
-                        .takes_value(true)
-                        .required(true)
-                        .index(1)
-                        .help("Hash of target dataset"),
-                )
-                .arg(
-                    Arg::with_name("value")
-                        .short("v")
-                        .long("value")
-                        .value_name("AMOUNT")
-                        .validator(is_amount)
-                        .takes_value(true)
-                        .is_required(true)
-                        .index(2)
-                        .help("Value of dataset to purchase access to"),
-                ),
-        )
-        .subcommand(
-            Subcommand::with_name("share_access")
-                .about("Share access to a dataset")
-                .arg(
-                    Arg::with_name("hash")
-                        .short("h")
-                        .long("hash")
-                        .value_name("HASH")
-                        .validator(is_hash)
-                        .takes_value(true)
-                        .required(true)
-                        .index(1)
-                        .help("Hash of the target dataset"),
-                )
-                .arg(
-                    Arg::with_name("recipient")
-                        .short("r")
-                        .long("recipient")
-                        .value_name("PUBKEY")
-                        .validator(is_pubkey)
-                        .takes_value(true)
-                        .required(true)
-                        .index(2)
-                        .help("Address to share access to"),
-                ),
-        )
-        .get_matches();
+/// Sends the purchase and any claims it takes to grant every member, a
+/// failed step can be picked up again by rerunning the command
+fn command_purchase_collection(
+    config: &Config,
+    hash: [u8; 32],
+    user_token_account: Pubkey,
+    amount: u64,
+) -> Result<CommandOutput, CliError> {
+    let collection = fetch_collection(&config.rpc_client, &config.program_id, &hash)?;
+    if collection.owner == config.authority.pubkey() {
+        return Err(CliError::User(format!(
+            "{} owns collection {}, owners can't purchase their own collections",
+            collection.owner,
+            Hash::new_from_array(hash)
+        )));
+    }
+    let created = ensure_accounts(
+        config,
+        &[
+            Requirement::AccessIndex(config.authority.pubkey()),
+            Requirement::TokenAccount(user_token_account),
+        ],
+    )?;
+    let mut transactions = purchase_collection_transactions(
+        &config.rpc_client,
+        &config.program_id,
+        config.authority.pubkey(),
+        &hash,
+        user_token_account,
+        amount,
+    )?;
+    if let Some(first) = transactions.first_mut() {
+        first.splice(0..0, created);
+    }
 
-    let mut wallet_manager = None;
-    let (sub_command, sub_matches) = app_matches.subcommand();
-    let matches = sub_matches.unwrap();
+    let mut steps = vec![];
+    for instructions in transactions.iter() {
+        steps.push(execute_or_simulate(config, instructions)?);
+    }
 
-    // Retrieves payer keypair and target RPC from the config file
-    let config = {
-        let cli_config = if let Some(config_file) = matches.value_of("config file") {
-            solana_cli_config::Config::load(config_file).unwrap_or_defaul()
-        } else {
-            solana_cli_config::Config::default()
-        };
+    Ok(CommandOutput::Steps(steps))
+}
 
-        let rpc_url = &cli_config.json_rpc_url;
-        let default_signer_arg_name = "owner".to_string();
-        let default_signer_path = cli_config.keypair_path.clone();
-        let default_signer = DefaultSigner::new(default_signer_arg_name, default_signer_path);
+fn command_share_access(
+    config: &Config,
+    recipient_authority: Pubkey,
+    hash: [u8; 32],
+    expires_in: Option<i64>,
+    fee_token_account: Option<Pubkey>,
+    receipt_file: Option<&str>,
+) -> Result<CommandOutput, CliError> {
+    check_share_recipients(config, hash, &[recipient_authority])?;
+    let mut instructions = ensure_accounts(
+        config,
+        &share_requirements(config, hash, &[recipient_authority]),
+    )?;
+    instructions.extend(
+        client(config)
+            .plan_share(hash, recipient_authority, expires_in, fee_token_account)?
+            .instructions,
+    );
 
-        let payer = {
-            let config = SignerFromPathConfig {
-                allow_null_signer: true,
-            };
-            let payer = default_signer
-                .signer_from_path_with_config(&matches, &mut wallet_manager, &config)
-                .unwrap_or_else(|e| {
-                    eprintln!("error: {}", e);
-                    std::process::exit(1);
-                });
-            payer
-        };
+    send_share(config, hash, &instructions, receipt_file)
+}
 
-        Config {
-            rpc_client: RpcClient::new(rpc_url.clone()),
-            payer,
-        }
-    };
+fn command_share_access_multi(
+    config: &Config,
+    recipients: &[Pubkey],
+    hash: [u8; 32],
+    receipt_file: Option<&str>,
+) -> Result<CommandOutput, CliError> {
+    check_share_recipients(config, hash, recipients)?;
+    let mut instructions = ensure_accounts(config, &share_requirements(config, hash, recipients))?;
+    instructions.push(share_access_multi(
+        config.program_id,
+        config.authority.pubkey(),
+        &hash,
+        recipients,
+    )?);
 
-    match (sub_command, sub_matches) {
-        ("create", Some(args)) => {
-            let authority = if let Some(pubkey) = value_of(args, "authority") {
-                pubkey
-            } else {
-                config.payer.pubkey()
-            };
-            match value_of(args, "account_type").unwrap() {
-                "owner" => command_init_index_account(config, authority, AccountType::Owner),
-                "access" => command_init_index_account(config, authority, AccountType::Access),
-                _ => command_init_data_account(config, hash),
-            };
-        }
-        ("set", Some(args)) => {
-            let hash = value_of(args, "hash").unwrap();
-            let key = if let Some(key) = value_of(args, "key") {
-                key
-            } else {
-                None
-            };
-            let value = if let Some(value) = value_of(args, "value") {
-                value
-            } else {
-                None
-            };
-            let share_limit = if let Some(share_limit) = value_of(args, "share_limit") {
-                share_limit
-            } else {
-                None
-            };
-            let ref_data = if let Some(ref_data) = value_of(args, "reference_data") {
-                ref_data
-            } else {
-                None
-            };
+    send_share(config, hash, &instructions, receipt_file)
+}
 
-            let params = match (key, value, share_limit, ref_data) {
-                (key, None, None, None) => Params::Key(key),
-                (None, value, None, None) => Params::Value(value),
-                (None, None, share_limit, None) => Params::ShareLimit(share_limit),
-                (None, None, None, ref_data) => Params::ReferenceData(ref_data),
-                _ => Params::Init(key, value, share_limit, ref_data),
-            };
+/// Fails when a recipient is the authority sharing or the dataset's owner,
+/// both of whom have access already and would be rejected by the program
+fn check_share_recipients(
+    config: &Config,
+    hash: [u8; 32],
+    recipients: &[Pubkey],
+) -> Result<(), CliError> {
+    let authority = config.authority.pubkey();
+    if recipients.contains(&authority) {
+        return Err(CliError::User(format!(
+            "{} is the authority sharing, access can't be shared with yourself",
+            authority
+        )));
+    }
+    let dataset = fetch_dataset(&config.rpc_client, &config.program_id, &hash)?;
+    if recipients.contains(&dataset.owner) {
+        return Err(CliError::User(format!(
+            "{} owns dataset {} and has access to it already",
+            dataset.owner,
+            Hash::new_from_array(hash)
+        )));
+    }
+    Ok(())
+}
 
-            command_set_data_params(config, hash, params);
-        }
-        ("purchse_access", Some(args)) => {
-            let hash = value_of(args, "hash").unwrap();
-            let value = value_of(args, "amount").unwrap();
-            command_purchase_access(config, hash, value);
-        }
-        ("share_access", Some(args)) => {
-            let hash = value_of(args, "hash").unwrap();
-            let recipient = pubkey_of(args, "recipient").unwrap();
-            command_share_access(config, recipient, hash);
-        }
-        _ => unreachable!(),
-    };
+/// The sharer's access to the dataset, and the access index and associated
+/// access account each recipient needs to receive it
+fn share_requirements(config: &Config, hash: [u8; 32], recipients: &[Pubkey]) -> Vec<Requirement> {
+    let mut requirements = vec![
+        Requirement::Dataset(hash),
+        Requirement::AssociatedAccess {
+            authority: config.authority.pubkey(),
+            hash,
+        },
+    ];
+    for recipient in recipients {
+        requirements.push(Requirement::AccessIndex(*recipient));
+        requirements.push(Requirement::AssociatedAccess {
+            authority: *recipient,
+            hash,
+        });
+    }
+    requirements
+}
+
+/// Sends the share `instructions` and reads back who received access and
+/// how many more shares the authority has left
+fn send_share(
+    config: &Config,
+    hash: [u8; 32],
+    instructions: &[Instruction],
+    receipt_file: Option<&str>,
+) -> Result<CommandOutput, CliError> {
+    if config.dry_run || config.sign_only {
+        return execute_or_simulate(config, instructions);
+    }
+    check_receipt_file(receipt_file)?;
+
+    let signature = send_transaction(config, instructions, &[])?;
+    let confirmed = fetch_confirmed(&config.rpc_client, &signature)?;
+    let dataset = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let remaining_shares = fetch_access_for(
+        &config.rpc_client,
+        &config.program_id,
+        config.authority.pubkey(),
+        &hash,
+    )?
+    .map(|access| access.shares_remaining());
+
+    let output = CommandOutput::Shared {
+        hash: output::hex(&hash),
+        recipients: parse_shares(&confirmed.logs, &dataset)
+            .iter()
+            .map(|share| share.recipient.to_string())
+            .collect(),
+        pending: parse_pending_shares(&confirmed.logs, &dataset)
+            .iter()
+            .map(|share| share.recipient.to_string())
+            .collect(),
+        remaining_shares,
+        fee: confirmed.fee,
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        memo: config.memo.clone(),
+        transaction: Box::new(confirmed_transaction(&signature, &confirmed)),
+    };
+    write_receipt(receipt_file, &output)?;
+    Ok(output)
+}
+
+/// Sets whether shares to the authority wait for it to accept them,
+/// creating its access index first when it has none
+fn command_set_access_preferences(
+    config: &Config,
+    require_share_approval: bool,
+) -> Result<CommandOutput, CliError> {
+    let authority = config.authority.pubkey();
+    let mut instructions = ensure_accounts(config, &[Requirement::AccessIndex(authority)])?;
+    instructions.push(set_access_preferences(
+        config.program_id,
+        SetAccessPreferencesAccounts {
+            authority,
+            access_index: find_access_address_with_program_id(&config.program_id, authority).0,
+        },
+        require_share_approval,
+    )?);
+
+    execute_or_simulate(config, &instructions)
+}
+
+fn command_accept_share(
+    config: &Config,
+    hash: [u8; 32],
+    from: Pubkey,
+) -> Result<CommandOutput, CliError> {
+    let authority = config.authority.pubkey();
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let instructions = [accept_share(
+        config.program_id,
+        AcceptShareAccounts {
+            authority,
+            access_index: find_access_address_with_program_id(&config.program_id, authority).0,
+            access_account: find_associated_access_address_with_program_id(
+                &config.program_id,
+                authority,
+                dataset_account,
+            )
+            .0,
+            dataset_account,
+        },
+        hash,
+        from,
+    )?];
+
+    execute_or_simulate(config, &instructions)
+}
+
+/// Rejects the share of the dataset from `from` pending for `recipient`,
+/// the authority unless the authority is `from` reclaiming a lapsed share
+fn command_reject_share(
+    config: &Config,
+    hash: [u8; 32],
+    from: Pubkey,
+    recipient: Option<Pubkey>,
+) -> Result<CommandOutput, CliError> {
+    let recipient = recipient.unwrap_or_else(|| config.authority.pubkey());
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let instructions = [reject_share(
+        config.program_id,
+        RejectShareAccounts {
+            authority: config.authority.pubkey(),
+            recipient_authority: recipient,
+            recipient_access_account: find_associated_access_address_with_program_id(
+                &config.program_id,
+                recipient,
+                dataset_account,
+            )
+            .0,
+            sharer_access_account: find_associated_access_address_with_program_id(
+                &config.program_id,
+                from,
+                dataset_account,
+            )
+            .0,
+            dataset_account,
+        },
+        hash,
+        from,
+    )?];
+
+    execute_or_simulate(config, &instructions)
+}
+
+fn command_close_dataset(
+    config: &Config,
+    hash: [u8; 32],
+    force: bool,
+) -> Result<CommandOutput, CliError> {
+    let owner_account =
+        find_owner_address_with_program_id(&config.program_id, config.authority.pubkey()).0;
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    ensure_accounts(
+        config,
+        &[
+            Requirement::OwnerIndex(config.authority.pubkey()),
+            Requirement::Dataset(hash),
+        ],
+    )?;
+
+    let instructions = [close_dataset(
+        config.program_id,
+        CloseDatasetAccounts {
+            authority: config.authority.pubkey(),
+            owner_index: owner_account,
+            dataset_account,
+        },
+        hash,
+        force,
+    )?];
+
+    Ok(CommandOutput::Closed {
+        dataset: dataset_account.to_string(),
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+fn command_renew_access(
+    config: &Config,
+    hash: [u8; 32],
+    user_token_account: Pubkey,
+) -> Result<CommandOutput, CliError> {
+    ensure_accounts(
+        config,
+        &[
+            Requirement::Dataset(hash),
+            Requirement::AssociatedAccess {
+                authority: config.authority.pubkey(),
+                hash,
+            },
+            Requirement::TokenAccount(user_token_account),
+        ],
+    )?;
+    let dataset_address = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let user_associated_access_account = find_associated_access_address_with_program_id(
+        &config.program_id,
+        config.authority.pubkey(),
+        dataset_address,
+    )
+    .0;
+
+    let user_token_account_info = config.rpc_client.get_account(&user_token_account)?;
+    let token_program = user_token_account_info.owner;
+    let user_token_account_state =
+        StateWithExtensions::<Account>::unpack(&user_token_account_info.data)?;
+    let token_mint = user_token_account_state.base.mint;
+    let dataset_state = fetch_dataset(&config.rpc_client, &config.program_id, &hash)?;
+    let vault_account =
+        find_vault_address_with_program_id(&config.program_id, dataset_address, token_mint).0;
+
+    // Renewals are charged at the dataset's current price in the mint
+    let amount = dataset_state.price(&token_mint).ok_or_else(|| {
+        CliError::User(format!(
+            "the dataset has no price in mint {} and cannot be renewed with it",
+            token_mint
+        ))
+    })?;
+
+    let instructions = [renew_access(
+        config.program_id,
+        RenewAccessAccounts {
+            user_authority: config.authority.pubkey(),
+            user_access_account: user_associated_access_account,
+            user_token_account,
+            proceeds_account: vault_account,
+            token_mint,
+            dataset_account: dataset_address,
+            token_program,
+            referrer_token_account: None,
+        },
+        hash,
+        amount,
+    )?];
+
+    Ok(CommandOutput::Renewed {
+        access_account: user_associated_access_account.to_string(),
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+/// Withdraws proceeds of the dataset held in the vault of the mint of
+/// `destination_token_account`, everything the vault can pay out unless
+/// `amount` is given
+fn command_withdraw_proceeds(
+    config: &Config,
+    hash: [u8; 32],
+    destination_token_account: Pubkey,
+    amount: Option<u64>,
+) -> Result<CommandOutput, CliError> {
+    ensure_accounts(
+        config,
+        &[
+            Requirement::Dataset(hash),
+            Requirement::TokenAccount(destination_token_account),
+        ],
+    )?;
+    let dataset_address = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let destination_info = config.rpc_client.get_account(&destination_token_account)?;
+    let token_program = destination_info.owner;
+    let token_mint = StateWithExtensions::<Account>::unpack(&destination_info.data)?
+        .base
+        .mint;
+    let vault_account =
+        find_vault_address_with_program_id(&config.program_id, dataset_address, token_mint).0;
+
+    let amount = match amount {
+        Some(amount) => amount,
+        None => {
+            // Transfer fees withheld on the way in can leave the vault
+            // holding less than the mint's claimable proceeds
+            let dataset_state = fetch_dataset(&config.rpc_client, &config.program_id, &hash)?;
+            let claimable = dataset_state.claimable(&token_mint).ok_or_else(|| {
+                CliError::User(format!("the dataset has no vault of mint {}", token_mint))
+            })?;
+            let vault_info = config.rpc_client.get_account(&vault_account)?;
+            let vault_balance = StateWithExtensions::<Account>::unpack(&vault_info.data)?
+                .base
+                .amount;
+            claimable.min(vault_balance)
+        }
+    };
+
+    let instructions = [withdraw_proceeds(
+        config.program_id,
+        WithdrawProceedsAccounts {
+            owner_authority: config.authority.pubkey(),
+            dataset_account: dataset_address,
+            vault_account,
+            token_mint,
+            destination_token_account,
+            token_program,
+        },
+        hash,
+        amount,
+        &multisig_signers(config),
+    )?];
+
+    Ok(CommandOutput::Withdrawn {
+        vault: vault_account.to_string(),
+        amount,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+fn command_transfer_ownership(
+    config: &Config,
+    hash: [u8; 32],
+    new_owner: Pubkey,
+) -> Result<CommandOutput, CliError> {
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let instructions = [propose_ownership_transfer(
+        config.program_id,
+        OwnershipTransferAccounts {
+            authority: config.authority.pubkey(),
+            dataset_account,
+        },
+        hash,
+        new_owner,
+        &multisig_signers(config),
+    )?];
+
+    Ok(CommandOutput::Ownership {
+        dataset: dataset_account.to_string(),
+        owner: config.authority.pubkey().to_string(),
+        pending_owner: Some(new_owner.to_string()),
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+/// Accepts the dataset as its pending owner, creating the authority's owner
+/// index first when it has none
+fn command_accept_ownership(config: &Config, hash: [u8; 32]) -> Result<CommandOutput, CliError> {
+    let authority = config.authority.pubkey();
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let dataset = fetch_dataset(&config.rpc_client, &config.program_id, &hash)?;
+    if dataset.pending_owner != Some(authority) {
+        return Err(CliError::User(format!(
+            "{} is not the pending owner of the dataset",
+            authority
+        )));
+    }
+    let new_owner_index = find_owner_address_with_program_id(&config.program_id, authority).0;
+
+    let mut instructions = vec![];
+    if owner_index_missing(config, authority)? {
+        instructions.push(init_account(
+            config.program_id,
+            Some(config.fee_payer.pubkey()),
+            authority,
+            new_owner_index,
+            None,
+            system_program::id(),
+            AccountType::DatasetIndex,
+        )?);
+    }
+    instructions.push(accept_ownership(
+        config.program_id,
+        AcceptOwnershipAccounts {
+            authority,
+            old_owner_index: find_owner_address_with_program_id(
+                &config.program_id,
+                dataset.owner,
+            )
+            .0,
+            new_owner_index,
+            dataset_account,
+        },
+        hash,
+        &multisig_signers(config),
+    )?);
+
+    Ok(CommandOutput::Ownership {
+        dataset: dataset_account.to_string(),
+        owner: authority.to_string(),
+        pending_owner: None,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+fn command_cancel_ownership_transfer(
+    config: &Config,
+    hash: [u8; 32],
+) -> Result<CommandOutput, CliError> {
+    let dataset_account = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let instructions = [cancel_ownership_transfer(
+        config.program_id,
+        OwnershipTransferAccounts {
+            authority: config.authority.pubkey(),
+            dataset_account,
+        },
+        hash,
+        &multisig_signers(config),
+    )?];
+
+    Ok(CommandOutput::Ownership {
+        dataset: dataset_account.to_string(),
+        owner: config.authority.pubkey().to_string(),
+        pending_owner: None,
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+fn command_dataset_stats(config: &Config, hash: [u8; 32]) -> Result<CommandOutput, CliError> {
+    let stats = fetch_dataset_stats(&config.rpc_client, &config.program_id, &hash)?;
+
+    Ok(CommandOutput::Stats {
+        dataset: find_dataset_address_with_program_id(&config.program_id, &hash).0.to_string(),
+        total_revenue: stats.total_revenue,
+        purchase_count: stats.purchase_count,
+        share_count: stats.share_count,
+    })
+}
+
+/// Hashes `path` in fixed size chunks so large datasets are never held in
+/// memory whole
+fn hash_file(path: &str) -> std::io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::default();
+    let mut buffer = vec![0; 1 << 20];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.hash(&buffer[..read]);
+    }
+
+    Ok(hasher.result().to_bytes())
+}
+
+/// Whether `authority` still needs an owner index before registering
+fn owner_index_missing(config: &Config, authority: Pubkey) -> Result<bool, CliError> {
+    match fetch_owner_index(&config.rpc_client, &config.program_id, authority) {
+        Ok(_) => Ok(false),
+        Err(FetchError::AccountMissing(_)) => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Instructions registering the dataset under `hash` to the authority,
+/// creating its owner index first when `init_owner_index` is set
+#[allow(clippy::too_many_arguments)]
+fn register_instructions(
+    config: &Config,
+    hash: [u8; 32],
+    key: [u8; 32],
+    value: u64,
+    share_limit: u16,
+    mint: Option<Pubkey>,
+    uri: Option<&str>,
+    init_owner_index: bool,
+) -> Result<Vec<Instruction>, CliError> {
+    let authority = config.authority.pubkey();
+    let owner_index = find_owner_address_with_program_id(&config.program_id, authority).0;
+    let dataset_address = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+
+    let mut instructions = vec![];
+
+    if init_owner_index {
+        instructions.push(init_account(
+            config.program_id,
+            Some(config.fee_payer.pubkey()),
+            authority,
+            owner_index,
+            None,
+            system_program::id(),
+            AccountType::DatasetIndex,
+        )?);
+    }
+
+    instructions.push(init_account(
+        config.program_id,
+        None,
+        authority,
+        dataset_address,
+        Some(owner_index),
+        system_program::id(),
+        AccountType::Dataset(hash),
+    )?);
+
+    // Purchases are paid into the dataset's vault of their mint, which only
+    // the owner can open
+    if let Some(mint) = mint {
+        let token_program = config.rpc_client.get_account(&mint)?.owner;
+        instructions.push(init_vault(
+            config.program_id,
+            InitVaultAccounts {
+                payer: config.fee_payer.pubkey(),
+                owner_authority: authority,
+                dataset_account: dataset_address,
+                token_mint: mint,
+                vault_account: find_vault_address_with_program_id(
+                    &config.program_id,
+                    dataset_address,
+                    mint,
+                )
+                .0,
+                token_program,
+                system_program: system_program::id(),
+            },
+            hash,
+        )?);
+    }
+
+    let params_accounts = || SetDataParamsAccounts {
+        authority,
+        dataset_account: dataset_address,
+        reference_dataset: None,
+    };
+    instructions.push(set_data_params(
+        config.program_id,
+        params_accounts(),
+        hash,
+        Params::Init(key, value, share_limit, None),
+        &[],
+    )?);
+    if let Some(uri) = uri {
+        instructions.push(set_data_params(
+            config.program_id,
+            params_accounts(),
+            hash,
+            Params::Uri(uri.as_bytes().to_vec()),
+            &[],
+        )?);
+    }
+
+    Ok(instructions)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_register(
+    config: &Config,
+    path: &str,
+    key: [u8; 32],
+    value: u64,
+    share_limit: u16,
+    mint: Option<Pubkey>,
+    uri: Option<&str>,
+) -> Result<CommandOutput, CliError> {
+    let hash = hash_file(path)
+        .map_err(|e| CliError::User(format!("failed to read {}: {}", path, e)))?;
+    let init_owner_index = owner_index_missing(config, config.authority.pubkey())?;
+    let instructions = register_instructions(
+        config,
+        hash,
+        key,
+        value,
+        share_limit,
+        mint,
+        uri,
+        init_owner_index,
+    )?;
+
+    Ok(CommandOutput::Registered {
+        hash: Hash::new_from_array(hash).to_string(),
+        dataset: find_dataset_address_with_program_id(&config.program_id, &hash).0.to_string(),
+        transaction: Box::new(execute_or_simulate(config, &instructions)?),
+    })
+}
+
+/// Validates a manifest operation and builds its instructions
+/// `init_owner_index` is cleared once a registration creates the index
+fn plan_operation(
+    config: &Config,
+    operation: &Operation,
+    init_owner_index: &mut bool,
+) -> Result<Vec<Instruction>, String> {
+    let authority = config.authority.pubkey();
+
+    match operation {
+        Operation::Register {
+            file,
+            value,
+            share_limit,
+            key,
+            mint,
+            uri,
+        } => {
+            let hash = hash_file(file).map_err(|e| format!("failed to read {}: {}", file, e))?;
+            let key = key.as_deref().map(parse_hash).transpose()?.unwrap_or([0; 32]);
+            let mint = mint.as_deref().map(parse_pubkey).transpose()?;
+            if uri.as_ref().map_or(false, |uri| uri.len() > URI_LEN) {
+                return Err(format!("uri is longer than {} bytes", URI_LEN));
+            }
+
+            let instructions = register_instructions(
+                config,
+                hash,
+                key,
+                *value,
+                *share_limit,
+                mint,
+                uri.as_deref(),
+                *init_owner_index,
+            )
+            .map_err(|e| e.to_string())?;
+            *init_owner_index = false;
+            Ok(instructions)
+        }
+        Operation::Set {
+            hash,
+            value,
+            share_limit,
+            referral_bps,
+            access_duration,
+            uri,
+            size,
+            content_type,
+        } => {
+            let hash = parse_hash(hash)?;
+            let mut params = vec![];
+            params.extend(value.map(Params::Value));
+            params.extend(share_limit.map(Params::ShareLimit));
+            params.extend(referral_bps.map(Params::ReferralBps));
+            params.extend(access_duration.map(Params::AccessDuration));
+            params.extend(uri.as_ref().map(|uri| Params::Uri(uri.as_bytes().to_vec())));
+            params.extend(size.map(Params::Size));
+            params.extend(
+                content_type
+                    .as_ref()
+                    .map(|content_type| Params::ContentType(content_type.as_bytes().to_vec())),
+            );
+            if params.is_empty() {
+                return Err("no parameters to set".to_string());
+            }
+
+            params
+                .into_iter()
+                .map(|params| {
+                    set_data_params(
+                        config.program_id,
+                        SetDataParamsAccounts {
+                            authority,
+                            dataset_account: find_dataset_address_with_program_id(
+                                &config.program_id,
+                                &hash,
+                            )
+                            .0,
+                            reference_dataset: None,
+                        },
+                        hash,
+                        params,
+                        &[],
+                    )
+                    .map_err(|e| e.to_string())
+                })
+                .collect()
+        }
+        Operation::Grant {
+            hash,
+            token_account,
+            amount,
+            referrer,
+        } => {
+            let hash = parse_hash(hash)?;
+            let token_account = parse_pubkey(token_account)?;
+            let referrer = referrer.as_deref().map(parse_pubkey).transpose()?;
+
+            purchase_access_instructions(
+                &config.rpc_client,
+                &config.program_id,
+                authority,
+                &hash,
+                token_account,
+                *amount,
+                referrer,
+                None,
+            )
+            .map_err(|e| CliError::from(e).to_string())
+        }
+        Operation::Share { hash, recipients } => {
+            let hash = parse_hash(hash)?;
+            let recipients = recipients
+                .iter()
+                .map(|recipient| parse_pubkey(recipient))
+                .collect::<Result<Vec<_>, _>>()?;
+            if recipients.is_empty() {
+                return Err("no recipients to share with".to_string());
+            }
+
+            Ok(vec![share_access_multi(
+                config.program_id,
+                authority,
+                &hash,
+                &recipients,
+            )
+            .map_err(|e| e.to_string())?])
+        }
+    }
+}
+
+fn command_batch(
+    config: &Config,
+    path: &str,
+    fail_fast: bool,
+    concurrency: usize,
+) -> Result<CommandOutput, CliError> {
+    if config.sign_only {
+        return Err(CliError::User("batch does not support --sign-only".to_string()));
+    }
+
+    let file = File::open(path)
+        .map_err(|e| CliError::User(format!("failed to read {}: {}", path, e)))?;
+    let manifest: Manifest = serde_json::from_reader(file)
+        .map_err(|e| CliError::User(format!("invalid manifest {}: {}", path, e)))?;
+
+    // The whole manifest is validated before anything is sent
+    let mut init_owner_index = manifest
+        .operations
+        .iter()
+        .any(|operation| matches!(operation, Operation::Register { .. }))
+        && owner_index_missing(config, config.authority.pubkey())?;
+    let mut rows = vec![];
+    let mut invalid = vec![];
+    for (row, operation) in manifest.operations.iter().enumerate() {
+        match plan_operation(config, operation, &mut init_owner_index) {
+            Ok(instructions) => rows.push(PlannedRow {
+                row,
+                op: operation.name(),
+                instructions,
+            }),
+            Err(e) => invalid.push(format!("row {} ({}): {}", row, operation.name(), e)),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(CliError::User(format!(
+            "invalid manifest {}:\n  {}",
+            path,
+            invalid.join("\n  ")
+        )));
+    }
+
+    // The price is only resolved per transaction, any price takes the same room
+    let placeholder_price = match config.priority_fee {
+        PriorityFee::None => None,
+        _ => Some(0),
+    };
+    let prefix =
+        compute_budget::prepend_compute_budget(config.compute_unit_limit, placeholder_price, &[]);
+    let suffix = append_memo(config.memo.as_deref(), &[]);
+    let groups = manifest::pack(&rows, &prefix, &suffix, &config.fee_payer.pubkey()).map_err(
+        |row| CliError::User(format!("row {} does not fit in a single transaction", row)),
+    )?;
+
+    let mut results: Vec<RowResult> = rows
+        .iter()
+        .map(|row| RowResult {
+            row: row.row,
+            op: row.op,
+            status: if config.dry_run { "planned" } else { "skipped" },
+            transaction: None,
+            signature: None,
+            error: None,
+        })
+        .collect();
+    for (transaction, group) in groups.iter().enumerate() {
+        for index in group {
+            results[*index].transaction = Some(transaction);
+        }
+    }
+    if config.dry_run {
+        return Ok(CommandOutput::Batch(results));
+    }
+
+    // Each wave is signed against a fresh blockhash so long runs don't expire
+    let fee_payer = config.fee_payer.pubkey();
+    let mut clients: Vec<RpcClient> = (0..concurrency.max(1))
+        .map(|_| {
+            RpcClient::new_with_commitment(
+                config.json_rpc_url.clone(),
+                config.rpc_client.commitment(),
+            )
+        })
+        .collect();
+    for wave in groups.chunks(clients.len()) {
+        let mut handles = vec![];
+        let mut failed = false;
+
+        for (group, client) in wave.iter().zip(clients.drain(..)) {
+            let instructions: Vec<Instruction> = group
+                .iter()
+                .flat_map(|index| rows[*index].instructions.iter().cloned())
+                .collect();
+            let signed = with_compute_budget(config, &instructions)
+                .and_then(|instructions| {
+                    let mut transaction =
+                        Transaction::new_with_payer(&instructions, Some(&fee_payer));
+                    let blockhash = config.rpc_client.get_latest_blockhash()?;
+                    let signers = transaction_signers(config, &transaction.message, None, &[]);
+                    transaction
+                        .try_sign(&signers, blockhash)
+                        .map_err(|e| CliError::User(e.to_string()))?;
+                    Ok(transaction)
+                })
+                .map_err(|e| e.to_string());
+
+            // Signers can't cross threads, so an expired wave is reported
+            // rather than re-signed
+            let max_retries = config.max_retries;
+            handles.push((
+                group,
+                std::thread::spawn(move || {
+                    let result = signed.and_then(|mut transaction| {
+                        let commitment = client.commitment();
+                        send_with_retry(&client, &mut transaction, None, max_retries, commitment)
+                            .map_err(|e| CliError::from(e).to_string())
+                    });
+                    (client, result)
+                }),
+            ));
+        }
+
+        for (group, handle) in handles {
+            let (client, result) = handle.join().expect("batch worker panicked");
+            clients.push(client);
+            for index in group {
+                let row = &mut results[*index];
+                match &result {
+                    Ok(signature) => {
+                        row.status = "ok";
+                        row.signature = Some(signature.to_string());
+                    }
+                    Err(e) => {
+                        row.status = "failed";
+                        row.error = Some(e.clone());
+                        failed = true;
+                    }
+                }
+            }
+        }
+
+        if failed && fail_fast {
+            break;
+        }
+    }
+
+    Ok(CommandOutput::Batch(results))
+}
+
+/// Reads a base58 hash argument
+fn hash_of(matches: &ArgMatches, name: &str) -> Option<[u8; 32]> {
+    value_of::<Hash>(matches, name).map(|hash| hash.to_bytes())
+}
+
+/// Parses a dataset hash given as 64 hex characters, base58, or
+/// `file:<path>` to hash the file's contents
+///
+/// A 32 byte hash is 43 or 44 characters in base58, so a 64 character hex
+/// digest is never mistaken for one.
+fn parse_dataset_hash(hash: &str) -> Result<[u8; 32], String> {
+    if let Some(path) = hash.strip_prefix("file:") {
+        return hash_file(path).map_err(|e| format!("failed to hash {}: {}", path, e));
+    }
+
+    let is_hex = hash.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex && hash.len() == 64 {
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hash[2 * i..2 * i + 2], 16).map_err(|e| e.to_string())?;
+        }
+        return Ok(bytes);
+    }
+
+    match hash.parse::<Hash>() {
+        Ok(hash) => Ok(hash.to_bytes()),
+        Err(_) if is_hex => Err(format!("hex hash must be 64 characters, got {}", hash.len())),
+        Err(ParseHashError::WrongSize) => {
+            Err(format!("base58 hash must decode to 32 bytes, {} doesn't", hash))
+        }
+        Err(ParseHashError::Invalid) => {
+            Err(format!("{} is not a hex or base58 hash, or a file:<path>", hash))
+        }
+    }
+}
+
+fn is_dataset_hash(hash: String) -> Result<(), String> {
+    // Files are only hashed once the command runs
+    match hash.strip_prefix("file:") {
+        Some(path) if std::path::Path::new(path).is_file() => Ok(()),
+        Some(path) => Err(format!("{} is not a file", path)),
+        None => parse_dataset_hash(&hash).map(|_| ()),
+    }
+}
+
+/// Splits a price tier given as `MIN_SEATS:UNIT_PRICE`, leaving the price
+/// to be read in units of the mint
+fn split_price_tier(tier: &str) -> Result<(u16, &str), String> {
+    let (min_seats, unit_price) = tier
+        .split_once(':')
+        .ok_or_else(|| format!("{} is not MIN_SEATS:UNIT_PRICE", tier))?;
+    let min_seats = min_seats
+        .parse()
+        .map_err(|e| format!("invalid seat count {}: {}", min_seats, e))?;
+    Ok((min_seats, unit_price))
+}
+
+/// Parses a price tier whose unit price is in whole tokens of a mint with
+/// `decimals`
+fn parse_price_tier(tier: &str, decimals: u8) -> Result<PriceTier, String> {
+    let (min_seats, unit_price) = split_price_tier(tier)?;
+    Ok(PriceTier {
+        min_seats,
+        unit_price: parse_ui_amount(unit_price, decimals)
+            .map_err(|e| format!("invalid unit price: {}", e))?,
+    })
+}
+
+fn is_price_tier(tier: String) -> Result<(), String> {
+    split_price_tier(&tier).map(|_| ())
+}
+
+/// Parses a `MINT:AMOUNT` price, the amount in base units of the mint
+fn parse_mint_price(price: &str) -> Result<(Pubkey, u64), String> {
+    let (mint, amount) = price
+        .split_once(':')
+        .ok_or_else(|| format!("{} is not MINT:AMOUNT", price))?;
+    let amount = match amount.parse() {
+        Ok(0) => return Err("a price cannot be zero, use --remove-price instead".to_string()),
+        Ok(amount) => amount,
+        Err(e) => return Err(format!("invalid amount {}: {}", amount, e)),
+    };
+    Ok((parse_pubkey(mint)?, amount))
+}
+
+fn is_mint_price(price: String) -> Result<(), String> {
+    parse_mint_price(&price).map(|_| ())
+}
+
+/// Parses a `MINT:AMOUNT` share fee, where a zero amount clears the fee
+fn parse_share_fee(fee: &str) -> Result<(Pubkey, u64), String> {
+    let (mint, amount) = fee
+        .split_once(':')
+        .ok_or_else(|| format!("{} is not MINT:AMOUNT", fee))?;
+    let amount = amount
+        .parse()
+        .map_err(|e| format!("invalid amount {}: {}", amount, e))?;
+    Ok((parse_pubkey(mint)?, amount))
+}
+
+fn is_share_fee(fee: String) -> Result<(), String> {
+    parse_share_fee(&fee).map(|_| ())
+}
+
+/// Packs a tag name into its null padded slot
+fn parse_tag(name: &str) -> Result<Tag, String> {
+    pack_tag(name).ok_or_else(|| {
+        format!(
+            "invalid tag {:?}: tags are 1 to {} bytes without null bytes",
+            name, TAG_LEN
+        )
+    })
+}
+
+fn is_tag(name: String) -> Result<(), String> {
+    parse_tag(&name).map(|_| ())
+}
+
+fn is_memo(memo: String) -> Result<(), String> {
+    parse_memo(&memo).map(|_| ())
+}
+
+/// Parses an RFC3339 timestamp into unix time
+fn parse_timestamp(timestamp: &str) -> Result<i64, String> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.timestamp())
+        .map_err(|e| format!("invalid timestamp {}: {}", timestamp, e))
+}
+
+fn is_timestamp(timestamp: String) -> Result<(), String> {
+    parse_timestamp(&timestamp).map(|_| ())
+}
+
+fn timestamp_of(matches: &ArgMatches, name: &str) -> Result<Option<i64>, String> {
+    matches.value_of(name).map(parse_timestamp).transpose()
+}
+
+/// Parses a positive duration such as `7d` or `12h 30m` into whole seconds
+fn parse_duration(duration: &str) -> Result<i64, String> {
+    let seconds = humantime::parse_duration(duration)
+        .map_err(|e| format!("invalid duration {}: {}", duration, e))?
+        .as_secs();
+    match i64::try_from(seconds) {
+        Ok(seconds) if seconds > 0 => Ok(seconds),
+        Ok(_) => Err(format!("duration {} is under a second", duration)),
+        Err(_) => Err(format!("duration {} is too long", duration)),
+    }
+}
+
+fn is_duration(duration: String) -> Result<(), String> {
+    parse_duration(&duration).map(|_| ())
+}
+
+fn duration_of(matches: &ArgMatches, name: &str) -> Result<Option<i64>, String> {
+    matches.value_of(name).map(parse_duration).transpose()
+}
+
+/// Reads an amount argument in whole tokens of a mint with `decimals`
+fn ui_amount_of(matches: &ArgMatches, name: &str, decimals: u8) -> Result<Option<u64>, String> {
+    matches
+        .value_of(name)
+        .map(|amount| parse_format_ui_amount(amount, decimals).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+fn is_ui_amount(amount: String) -> Result<(), String> {
+    // Precision is only checked once the mint's decimals are known
+    match parse_ui_amount(&amount, u8::MAX) {
+        Err(AmountError::Invalid(_)) => Err(AmountError::Invalid(amount).to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Decimals of the `--mint` argument, fetched from the cluster
+fn mint_decimals_of(config: &Config, matches: &ArgMatches) -> Result<Option<u8>, CliError> {
+    pubkey_of(matches, "mint")
+        .map(|mint| fetch_mint_decimals(&config.rpc_client, &mint))
+        .transpose()
+        .map_err(CliError::from)
+}
+
+/// Reads a dataset hash argument, see `parse_dataset_hash`
+fn dataset_hash_of(format: OutputFormat, matches: &ArgMatches, name: &str) -> Option<[u8; 32]> {
+    matches.value_of(name).map(|hash| {
+        parse_dataset_hash(hash).unwrap_or_else(|e| exit_with_error(format, CliError::User(e)))
+    })
+}
+
+/// An argument clap requires, reported instead of panicking if the
+/// definition ever stops requiring it
+fn required<T>(value: Option<T>, name: &str) -> Result<T, CliError> {
+    value.ok_or_else(|| CliError::User(format!("missing required argument {}", name)))
+}
+
+/// Echoes a dataset hash in both encodings so the user can check it
+fn print_dataset_hash(format: OutputFormat, hash: &[u8; 32]) {
+    if format == OutputFormat::Text {
+        eprintln!("Dataset hash: {} (hex {})", Hash::new_from_array(*hash), output::hex(hash));
+    }
+}
+
+/// Display name of a dataset in an index, its URI when set or its hash
+fn dataset_name(config: &Config, dataset: &Pubkey) -> String {
+    match config.rpc_client.get_account_data(dataset) {
+        Ok(data) => match DatasetState::unpack_from_slice(&data) {
+            Ok(dataset) => dataset
+                .uri()
+                .map(str::to_string)
+                .unwrap_or_else(|| output::hex(&dataset.hash)),
+            Err(_) => "<undecodable>".to_string(),
+        },
+        Err(_) => "<closed>".to_string(),
+    }
+}
+
+/// Decodes the account at `address`, with dataset amounts in whole tokens of
+/// a mint with `decimals` when given
+fn command_show(
+    config: &Config,
+    address: Pubkey,
+    raw: bool,
+    decimals: Option<u8>,
+) -> Result<CommandOutput, CliError> {
+    let account = config
+        .rpc_client
+        .get_account_with_commitment(&address, config.rpc_client.commitment())?
+        .value
+        .ok_or_else(|| CliError::User(format!("no account found at {}", address)))?;
+
+    if raw {
+        return Ok(CommandOutput::Raw {
+            address: address.to_string(),
+            data: base64::encode(&account.data),
+        });
+    }
+
+    if account.owner != config.program_id {
+        return Err(CliError::User(format!(
+            "{} is owned by {}, not a DataNexus account",
+            address, account.owner
+        )));
+    }
+
+    let decoded = match DataNexusAccount::decode(&account.data) {
+        Ok(decoded) => decoded,
+        Err(ProgramError::InvalidAccountData) => {
+            return Err(CliError::User(format!(
+                "{} has an unknown account layout of {} bytes",
+                address,
+                account.data.len()
+            )))
+        }
+        Err(e) => {
+            return Err(CliError::User(format!("failed to decode {}: {}", address, e)))
+        }
+    };
+
+    let state = match decoded {
+        DataNexusAccount::Dataset(dataset) => output::dataset_json(&address, &dataset, decimals),
+        DataNexusAccount::Access(access) => output::access_state_json(&address, &access),
+        DataNexusAccount::Index(index) => {
+            let names: Vec<String> = index
+                .datasets
+                .iter()
+                .flatten()
+                .map(|entry| dataset_name(config, &entry.dataset))
+                .collect();
+            output::index_json(&address, &index, &names)
+        }
+        other => {
+            return Err(CliError::User(format!(
+                "{} is a {} account, which show can't display",
+                address,
+                other.kind()
+            )))
+        }
+    };
+
+    Ok(CommandOutput::State(state))
+}
+
+fn command_trace(
+    config: &Config,
+    hash: [u8; 32],
+    authority: Pubkey,
+) -> Result<CommandOutput, CliError> {
+    let chain = trace_share_chain(&config.rpc_client, &config.program_id, authority, &hash)?;
+
+    Ok(CommandOutput::ShareChain {
+        hash: Hash::new_from_array(hash).to_string(),
+        authority: authority.to_string(),
+        chain: chain.iter().map(Pubkey::to_string).collect(),
+    })
+}
+
+fn command_access_status(
+    config: &Config,
+    hash: [u8; 32],
+    authority: Pubkey,
+) -> Result<CommandOutput, CliError> {
+    let dataset_address = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let access_account = find_associated_access_address_with_program_id(
+        &config.program_id,
+        authority,
+        dataset_address,
+    )
+    .0;
+    let grant = fetch_access_for(&config.rpc_client, &config.program_id, authority, &hash)?;
+
+    // Expiry is judged against the cluster's clock, not the local one
+    let clock_account = config.rpc_client.get_account(&sysvar::clock::id())?;
+    let clock: Clock = from_account(&clock_account)
+        .ok_or_else(|| CliError::User("failed to decode the clock sysvar".to_string()))?;
+
+    Ok(CommandOutput::AccessStatus {
+        hash: Hash::new_from_array(hash).to_string(),
+        authority: authority.to_string(),
+        access_account: access_account.to_string(),
+        valid: grant.map_or(false, |grant| !grant.is_expired(clock.unix_timestamp)),
+        grant: grant.as_ref().map(output::access_info_json),
+    })
+}
+
+/// Signs a claim of the authority's access to the dataset at the current
+/// slot, for a gateway to verify with `verify_access_attestation`
+fn command_attest(config: &Config, hash: [u8; 32]) -> Result<CommandOutput, CliError> {
+    let slot = config.rpc_client.get_slot()?;
+    let attestation = build_access_attestation(&*config.authority, hash, slot);
+
+    Ok(CommandOutput::Attestation {
+        hash: Hash::new_from_array(hash).to_string(),
+        authority: attestation.authority.to_string(),
+        slot,
+        attestation: attestation.to_base64(),
+    })
+}
+
+/// Prints each purchase of the dataset as the cluster confirms it, until
+/// interrupted or the websocket closes
+fn command_watch(
+    config: &Config,
+    format: OutputFormat,
+    hash: [u8; 32],
+) -> Result<CommandOutput, CliError> {
+    let dataset = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+    let subscription = PurchaseSubscription::with_program_id(
+        &config.websocket_url,
+        &config.program_id,
+        dataset,
+        config.rpc_client.commitment(),
+    )
+    .map_err(|e| {
+        CliError::User(format!("failed to subscribe at {}: {}", config.websocket_url, e))
+    })?;
+
+    if format == OutputFormat::Text {
+        eprintln!("Watching purchases of {}, press Ctrl-C to stop", dataset);
+    }
+    for purchase in subscription {
+        output::print_purchase(format, &purchase);
+    }
+
+    Err(CliError::User(format!("websocket {} closed", config.websocket_url)))
+}
+
+/// Applies `--offset` and `--limit` to a listing
+fn paginate<T>(items: Vec<T>, offset: usize, limit: Option<usize>) -> Vec<T> {
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn command_list_datasets(
+    config: &Config,
+    owner: Pubkey,
+    scan: bool,
+    offset: usize,
+    limit: Option<usize>,
+    decimals: Option<u8>,
+) -> Result<CommandOutput, CliError> {
+    let datasets = if scan {
+        find_datasets_by_owner(&config.rpc_client, &config.program_id, owner)?
+    } else {
+        let owner_index = find_owner_address_with_program_id(&config.program_id, owner).0;
+        let wanted = limit.map_or(usize::MAX, |limit| offset.saturating_add(limit));
+        let mut iter = enumerate_datasets(&config.rpc_client, &config.program_id, owner);
+        let mut datasets = vec![];
+        let mut reported = 0;
+
+        while let Some(result) = iter.next() {
+            match result {
+                Ok(dataset) => datasets.push(dataset),
+                Err(FetchError::AccountMissing(address)) if address == owner_index => {
+                    return Err(CliError::User(format!(
+                        "no owner index found for {}, run `dn-me create owner` first \
+                         or pass --scan",
+                        owner
+                    )))
+                }
+                // Entries whose account no longer holds a dataset are left out
+                Err(FetchError::WrongOwner(_)) | Err(FetchError::DecodeFailed(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            // Indexes past a single batch take a request per batch
+            if iter.discovered() > MAX_MULTIPLE_ACCOUNTS && iter.fetched() > reported {
+                reported = iter.fetched();
+                eprintln!(
+                    "Fetched {} of {}{} datasets",
+                    reported,
+                    if iter.indexes_walked() { "" } else { "at least " },
+                    iter.discovered()
+                );
+            }
+            if datasets.len() >= wanted {
+                break;
+            }
+        }
+
+        datasets
+    };
+
+    Ok(CommandOutput::Datasets(
+        paginate(datasets, offset, limit)
+            .iter()
+            .map(|(address, dataset)| output::dataset_json(address, dataset, decimals))
+            .collect(),
+    ))
+}
+
+/// Lists the datasets tagged `tag`, of `owner` only when given
+fn command_list_tagged_datasets(
+    config: &Config,
+    tag: &Tag,
+    owner: Option<Pubkey>,
+    offset: usize,
+    limit: Option<usize>,
+    decimals: Option<u8>,
+) -> Result<CommandOutput, CliError> {
+    let mut datasets = find_datasets_by_tag(&config.rpc_client, &config.program_id, tag)?;
+    if let Some(owner) = owner {
+        datasets.retain(|(_, dataset)| dataset.owner == owner);
+    }
+
+    Ok(CommandOutput::Datasets(
+        paginate(datasets, offset, limit)
+            .iter()
+            .map(|(address, dataset)| output::dataset_json(address, dataset, decimals))
+            .collect(),
+    ))
+}
+
+/// `MigrateAccount` instructions sent per transaction
+const MIGRATIONS_PER_TRANSACTION: usize = 8;
+
+/// Brings the account at `address` to its newest layout, or without one
+/// every outdated account of the authority. Accounts several layouts behind
+/// take one migration per layout, all paid for by the fee payer.
+fn command_migrate(config: &Config, address: Option<Pubkey>) -> Result<CommandOutput, CliError> {
+    let accounts = match address {
+        Some(address) => {
+            let account = config
+                .rpc_client
+                .get_account_with_commitment(&address, config.rpc_client.commitment())?
+                .value
+                .ok_or_else(|| CliError::User(format!("no account found at {}", address)))?;
+            if account.owner != config.program_id {
+                return Err(CliError::User(format!(
+                    "{} is owned by {}, not a DataNexus account",
+                    address, account.owner
+                )));
+            }
+            if !needs_migration(&account.data) {
+                return Err(CliError::User(format!(
+                    "{} is already at its newest layout",
+                    address
+                )));
+            }
+            vec![(address, account)]
+        }
+        None => find_outdated_accounts(
+            &config.rpc_client,
+            &config.program_id,
+            config.authority.pubkey(),
+        )?,
+    };
+
+    let mut instructions = vec![];
+    for (address, account) in &accounts {
+        instructions.extend(migrate_instructions(
+            config.program_id,
+            config.fee_payer.pubkey(),
+            *address,
+            &account.data,
+        )?);
+    }
+
+    let transactions = instructions
+        .chunks(MIGRATIONS_PER_TRANSACTION)
+        .map(|instructions| execute_or_simulate(config, instructions))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CommandOutput::Migrated {
+        accounts: accounts
+            .iter()
+            .map(|(address, _)| address.to_string())
+            .collect(),
+        transactions,
+    })
+}
+
+/// Writes a snapshot of the indexes, datasets and associated access
+/// accounts of `authority` to `outfile`
+fn command_export(
+    config: &Config,
+    authority: Pubkey,
+    outfile: &str,
+    force: bool,
+) -> Result<CommandOutput, CliError> {
+    if !force && std::path::Path::new(outfile).exists() {
+        return Err(CliError::User(format!(
+            "{} already exists, pass --force to overwrite it",
+            outfile
+        )));
+    }
+
+    let snapshot = take_snapshot(&config.rpc_client, &config.program_id, authority)?;
+    let accounts = snapshot.owner_indexes.len()
+        + snapshot.access_indexes.len()
+        + snapshot.datasets.len()
+        + snapshot.associated_access.len();
+    let document = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| CliError::User(format!("failed to encode snapshot: {}", e)))?;
+    std::fs::write(outfile, document)
+        .map_err(|e| CliError::User(format!("failed to write {}: {}", outfile, e)))?;
+
+    Ok(CommandOutput::Exported {
+        authority: authority.to_string(),
+        outfile: outfile.to_string(),
+        accounts,
+    })
+}
+
+/// Takes a new snapshot of the authority recorded in the snapshot at
+/// `path` and reports every field that differs from it
+fn command_verify(config: &Config, path: &str) -> Result<CommandOutput, CliError> {
+    let document = std::fs::read_to_string(path)
+        .map_err(|e| CliError::User(format!("failed to read {}: {}", path, e)))?;
+    let expected: Snapshot = serde_json::from_str(&document)
+        .map_err(|e| CliError::User(format!("{} is not a snapshot: {}", path, e)))?;
+    if expected.version != SNAPSHOT_VERSION {
+        return Err(CliError::User(format!(
+            "{} is a version {} snapshot, this CLI reads version {}",
+            path, expected.version, SNAPSHOT_VERSION
+        )));
+    }
+    if expected.program_id != config.program_id {
+        return Err(CliError::User(format!(
+            "{} was exported from program {}, not {}",
+            path, expected.program_id, config.program_id
+        )));
+    }
+
+    let actual = take_snapshot(&config.rpc_client, &config.program_id, expected.authority)?;
+
+    Ok(CommandOutput::Verified {
+        snapshot: path.to_string(),
+        divergences: diff_snapshots(&expected, &actual)
+            .into_iter()
+            .map(|divergence| {
+                json!({
+                    "path": divergence.path,
+                    "expected": divergence.expected,
+                    "actual": divergence.actual,
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Lists the datasets and hashes recorded in the access index of
+/// `authority`, or with `grants` the entries of every associated access
+/// account it points to
+fn command_list_access(
+    config: &Config,
+    authority: Pubkey,
+    grants: bool,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<CommandOutput, CliError> {
+    let index = match fetch_access_index(&config.rpc_client, &config.program_id, authority) {
+        Ok(index) => index,
+        Err(FetchError::AccountMissing(_)) => {
+            return Err(CliError::User(format!(
+                "no access index found for {}, run `dn-me create access` first",
+                authority
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if !grants {
+        let entries: Vec<_> = index.datasets.iter().flatten().collect();
+        return Ok(CommandOutput::IndexEntries(
+            paginate(entries, offset, limit)
+                .into_iter()
+                .map(output::index_entry_json)
+                .collect(),
+        ));
+    }
+
+    let grants: Vec<AccessInfo> = index
+        .datasets
+        .iter()
+        .flatten()
+        .filter_map(|entry| {
+            let address = find_associated_access_address_with_program_id(
+                &config.program_id,
+                authority,
+                entry.dataset,
+            )
+            .0;
+            let data = config.rpc_client.get_account_data(&address).ok()?;
+            AccessState::unpack_from_slice(&data).ok()
+        })
+        .flat_map(|access| access.datasets)
+        .collect();
+
+    Ok(CommandOutput::Grants(
+        paginate(grants, offset, limit)
+            .iter()
+            .map(output::access_info_json)
+            .collect(),
+    ))
+}
+
+/// The settings commands would run with, and the index addresses of the
+/// configured keypair under `program_id`
+fn command_config_get(
+    settings: &Settings,
+    datanexus_config_path: Option<&str>,
+    program_id: &Pubkey,
+    keypair: Option<Pubkey>,
+) -> Result<CommandOutput, CliError> {
+    let owner_index =
+        keypair.map(|pubkey| find_owner_address_with_program_id(program_id, pubkey).0);
+    let access_index =
+        keypair.map(|pubkey| find_access_address_with_program_id(program_id, pubkey).0);
+
+    Ok(CommandOutput::State(json!({
+        "config_file": datanexus_config_path,
+        "json_rpc_url": settings.json_rpc_url,
+        "websocket_url": settings.websocket_url,
+        "keypair_path": settings.keypair_path,
+        "commitment": settings.commitment,
+        "program_id": program_id.to_string(),
+        "keypair": keypair.map(|pubkey| pubkey.to_string()),
+        "owner_index": owner_index.map(|address| address.to_string()),
+        "access_index": access_index.map(|address| address.to_string()),
+    })))
+}
+
+fn derived_address_json((address, bump): (Pubkey, u8)) -> Value {
+    json!({ "address": address.to_string(), "bump": bump })
+}
+
+/// The DataNexus addresses derived from `authority` and `hash` under
+/// `program_id`, computed locally without a cluster
+fn command_address(
+    program_id: &Pubkey,
+    authority: Option<Pubkey>,
+    hash: Option<[u8; 32]>,
+) -> Result<CommandOutput, CliError> {
+    if authority.is_none() && hash.is_none() {
+        return Err(CliError::User(
+            "pass --authority, --hash or both, or configure a keypair".to_string(),
+        ));
+    }
+
+    let mut addresses = json!({ "program_id": program_id.to_string() });
+    if let Some(authority) = authority {
+        addresses["authority"] = json!(authority.to_string());
+        addresses["owner_index"] = derived_address_json(
+            find_owner_address_with_program_id(program_id, authority),
+        );
+        addresses["access_index"] = derived_address_json(
+            find_access_address_with_program_id(program_id, authority),
+        );
+    }
+    if let Some(hash) = hash {
+        let (dataset, bump) = find_dataset_address_with_program_id(program_id, &hash);
+        addresses["hash"] = json!(Hash::new_from_array(hash).to_string());
+        addresses["dataset"] = derived_address_json((dataset, bump));
+        if let Some(authority) = authority {
+            addresses["associated_access"] = derived_address_json(
+                find_associated_access_address_with_program_id(
+                    program_id, authority, dataset,
+                ),
+            );
+        }
+    }
+
+    Ok(CommandOutput::State(addresses))
+}
+
+/// Writes a new keypair to `outfile` and prints the addresses derived from
+/// it, refusing to replace an existing file unless `force` is set
+fn command_keygen(
+    program_id: &Pubkey,
+    outfile: &str,
+    force: bool,
+) -> Result<CommandOutput, CliError> {
+    if !force && std::path::Path::new(outfile).exists() {
+        return Err(CliError::User(format!(
+            "{} already exists, pass --force to overwrite it",
+            outfile
+        )));
+    }
+
+    let keypair = Keypair::new();
+    write_keypair_file(&keypair, outfile)
+        .map_err(|e| CliError::User(format!("failed to write {}: {}", outfile, e)))?;
+
+    let mut output = command_address(program_id, Some(keypair.pubkey()), None)?;
+    if let CommandOutput::State(addresses) = &mut output {
+        addresses["outfile"] = json!(outfile);
+    }
+    Ok(output)
+}
+
+/// Persists the given settings into the DataNexus config file, leaving the
+/// rest as they were
+fn command_config_set(
+    path: Option<&str>,
+    mut datanexus_config: DataNexusConfig,
+    json_rpc_url: Option<&str>,
+    websocket_url: Option<&str>,
+    keypair_path: Option<&str>,
+    commitment: Option<&str>,
+) -> Result<CommandOutput, CliError> {
+    let path = path.ok_or_else(|| {
+        CliError::User(format!(
+            "no config file location, set {} or HOME",
+            config::CONFIG_ENV
+        ))
+    })?;
+
+    let set = |value: Option<&str>, setting: &mut Option<String>| {
+        if let Some(value) = value {
+            *setting = Some(value.to_string());
+        }
+    };
+    set(json_rpc_url, &mut datanexus_config.json_rpc_url);
+    set(websocket_url, &mut datanexus_config.websocket_url);
+    set(keypair_path, &mut datanexus_config.keypair_path);
+    set(commitment, &mut datanexus_config.commitment);
+
+    datanexus_config
+        .save(path)
+        .map_err(|e| CliError::User(format!("failed to write {}: {}", path, e)))?;
+
+    let mut saved = serde_json::to_value(&datanexus_config)
+        .map_err(|e| CliError::User(format!("failed to encode {}: {}", path, e)))?;
+    saved["config_file"] = json!(path);
+    Ok(CommandOutput::State(saved))
+}
+
+/// Prints the result of a command and exits with its code
+fn finish(format: OutputFormat, result: Result<CommandOutput, CliError>) -> ! {
+    match result {
+        Ok(output) => {
+            output::print(format, &output);
+            std::process::exit(output.exit_code());
+        }
+        Err(error) => exit_with_error(format, error),
+    }
+}
+
+fn exit_with_error(format: OutputFormat, error: CliError) -> ! {
+    output::print_error(format, &error);
+    std::process::exit(error.exit_code());
+}
+
+/// Runs the command `sub_command` parsed into `args`
+fn run(
+    config: &Config,
+    format: OutputFormat,
+    sub_command: &str,
+    args: &ArgMatches,
+) -> Result<CommandOutput, CliError> {
+    match sub_command {
+        "create" => {
+            let authority = if let Some(pubkey) = value_of(args, "authority") {
+                pubkey
+            } else {
+                config.authority.pubkey()
+            };
+            match required(value_of(args, "account_type"), "account_type")? {
+                "owner" => command_init_index_account(config, authority, AccountType::DatasetIndex),
+                "access" => match dataset_hash_of(format, args, "hash") {
+                    Some(hash) => {
+                        print_dataset_hash(format, &hash);
+                        command_init_associated_access(config, hash)
+                    }
+                    None => command_init_index_account(config, authority, AccountType::AccessIndex),
+                },
+                _ => match dataset_hash_of(format, args, "hash") {
+                    Some(hash) => {
+                        print_dataset_hash(format, &hash);
+                        command_init_data_account(config, hash)
+                    }
+                    None => Err(CliError::User(
+                        "--hash is required for dataset accounts".to_string(),
+                    )),
+                },
+            }
+        }
+        "set" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            print_dataset_hash(format, &hash);
+            // Without a mint prices are given in base units
+            mint_decimals_of(config, args)
+                .and_then(|decimals| {
+                    let dataset = find_dataset_address_with_program_id(&config.program_id, &hash).0;
+                    set_params_of(args, decimals.unwrap_or(0), dataset).map_err(CliError::User)
+                })
+                .and_then(|params| command_set_data_params(config, hash, params))
+        }
+        "purchase_access" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            print_dataset_hash(format, &hash);
+            let token_account = required(pubkey_of(args, "token_account"), "token_account")?;
+            let value = required(args.value_of("value"), "value")?;
+            let referrer = pubkey_of(args, "referrer");
+            let known = KnownPurchaseAccounts {
+                dataset_account: pubkey_of(args, "dataset_address"),
+                token_mint: pubkey_of(args, "mint"),
+                token_program: None,
+                proceeds_account: pubkey_of(args, "owner_token_account"),
+            };
+            command_purchase_access(
+                config,
+                hash,
+                token_account,
+                value,
+                referrer,
+                args.is_present("owns_reference"),
+                args.is_present("yes"),
+                args.value_of("receipt_file"),
+                known,
+            )
+        }
+        "share_access" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            print_dataset_hash(format, &hash);
+            let recipients = required(pubkeys_of(args, "recipient"), "recipient")?;
+            let receipt_file = args.value_of("receipt_file");
+            let expires_in = duration_of(args, "expires_in").map_err(CliError::User)?;
+            if recipients.len() == 1 {
+                let fee_token_account = pubkey_of(args, "fee_token_account");
+                command_share_access(
+                    config,
+                    recipients[0],
+                    hash,
+                    expires_in,
+                    fee_token_account,
+                    receipt_file,
+                )
+            } else if expires_in.is_some() {
+                Err(CliError::User(
+                    "--expires-in only shares with a single recipient".to_string(),
+                ))
+            } else {
+                command_share_access_multi(config, &recipients, hash, receipt_file)
+            }
+        }
+        "set-access-preferences" => {
+            let require_share_approval = required(
+                value_of(args, "require_share_approval"),
+                "require_share_approval",
+            )?;
+            command_set_access_preferences(config, require_share_approval)
+        }
+        "accept-share" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let from = required(pubkey_of(args, "from"), "from")?;
+            command_accept_share(config, hash, from)
+        }
+        "reject-share" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let from = required(pubkey_of(args, "from"), "from")?;
+            command_reject_share(config, hash, from, pubkey_of(args, "recipient"))
+        }
+        "collection" => match args.subcommand() {
+            ("create", Some(create_args)) => {
+                let hash = required(dataset_hash_of(format, create_args, "hash"), "hash")?;
+                print_dataset_hash(format, &hash);
+                let value = required(value_of(create_args, "value"), "value")?;
+                command_create_collection(config, hash, value)
+            }
+            ("add", Some(add_args)) => {
+                let collection_hash = required(
+                    dataset_hash_of(format, add_args, "collection"),
+                    "collection",
+                )?;
+                let dataset_hash = required(dataset_hash_of(format, add_args, "hash"), "hash")?;
+                command_add_to_collection(config, collection_hash, dataset_hash)
+            }
+            ("purchase", Some(purchase_args)) => {
+                let hash = required(dataset_hash_of(format, purchase_args, "hash"), "hash")?;
+                let token_account =
+                    required(pubkey_of(purchase_args, "token_account"), "token_account")?;
+                let amount = required(value_of(purchase_args, "amount"), "amount")?;
+                command_purchase_collection(config, hash, token_account, amount)
+            }
+            _ => unreachable!(),
+        },
+        "close-dataset" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let force = args.is_present("force");
+            command_close_dataset(config, hash, force)
+        }
+        "transfer-ownership" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let new_owner = required(pubkey_of(args, "new_owner"), "new_owner")?;
+            command_transfer_ownership(config, hash, new_owner)
+        }
+        "accept-ownership" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            command_accept_ownership(config, hash)
+        }
+        "cancel-ownership-transfer" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            command_cancel_ownership_transfer(config, hash)
+        }
+        "renew" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let token_account = required(pubkey_of(args, "token_account"), "token_account")?;
+            command_renew_access(config, hash, token_account)
+        }
+        "withdraw" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let token_account = required(pubkey_of(args, "token_account"), "token_account")?;
+            let amount = value_of(args, "amount");
+            command_withdraw_proceeds(config, hash, token_account, amount)
+        }
+        "stats" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            command_dataset_stats(config, hash)
+        }
+        "list" => {
+            let (list_command, list_args) = match args.subcommand() {
+                (list_command, Some(list_args)) => (list_command, list_args),
+                _ => unreachable!(),
+            };
+            let offset = value_of(list_args, "offset").unwrap_or(0);
+            let limit = value_of(list_args, "limit");
+
+            match list_command {
+                "datasets" => {
+                    let owner = pubkey_of(list_args, "owner");
+                    let scan = list_args.is_present("scan");
+                    let tag = list_args.value_of("tag").and_then(pack_tag);
+                    mint_decimals_of(config, list_args).and_then(|decimals| match tag {
+                        Some(tag) => command_list_tagged_datasets(
+                            config, &tag, owner, offset, limit, decimals,
+                        ),
+                        None => command_list_datasets(
+                            config,
+                            owner.unwrap_or_else(|| config.authority.pubkey()),
+                            scan,
+                            offset,
+                            limit,
+                            decimals,
+                        ),
+                    })
+                }
+                "access" => {
+                    let authority = pubkey_of(list_args, "authority")
+                        .unwrap_or_else(|| config.authority.pubkey());
+                    let grants = list_args.is_present("grants");
+                    command_list_access(config, authority, grants, offset, limit)
+                }
+                _ => unreachable!(),
+            }
+        }
+        "register" => {
+            let key = hash_of(args, "key").unwrap_or([0; 32]);
+            command_register(
+                config,
+                required(args.value_of("file"), "file")?,
+                key,
+                required(value_of(args, "value"), "value")?,
+                value_of(args, "share_limit").unwrap_or(0),
+                pubkey_of(args, "mint"),
+                args.value_of("uri"),
+            )
+        }
+        "batch" => command_batch(
+            config,
+            required(args.value_of("manifest"), "manifest")?,
+            args.is_present("fail_fast"),
+            required(value_of(args, "concurrency"), "concurrency")?,
+        ),
+        "access-status" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let authority =
+                pubkey_of(args, "authority").unwrap_or_else(|| config.authority.pubkey());
+            command_access_status(config, hash, authority)
+        }
+        "attest" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            command_attest(config, hash)
+        }
+        "trace" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            let authority = required(pubkey_of(args, "authority"), "authority")?;
+            command_trace(config, hash, authority)
+        }
+        "watch" => {
+            let hash = required(dataset_hash_of(format, args, "hash"), "hash")?;
+            command_watch(config, format, hash)
+        }
+        "migrate" => command_migrate(config, pubkey_of(args, "address")),
+        "export" => {
+            let authority =
+                pubkey_of(args, "authority").unwrap_or_else(|| config.authority.pubkey());
+            command_export(
+                config,
+                authority,
+                required(args.value_of("out"), "out")?,
+                args.is_present("force"),
+            )
+        }
+        "verify" => command_verify(config, required(args.value_of("snapshot"), "snapshot")?),
+        "show" => {
+            let address = if let Some(hash) = dataset_hash_of(format, args, "hash") {
+                find_dataset_address_with_program_id(&config.program_id, &hash).0
+            } else if let Some(authority) = pubkey_of(args, "authority") {
+                match args.value_of("index_type") {
+                    Some("access") => {
+                        find_access_address_with_program_id(&config.program_id, authority).0
+                    }
+                    _ => find_owner_address_with_program_id(&config.program_id, authority).0,
+                }
+            } else {
+                required(pubkey_of(args, "address"), "address")?
+            };
+            let raw = args.is_present("raw");
+            mint_decimals_of(config, args)
+                .and_then(|decimals| command_show(config, address, raw, decimals))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Top-level subcommands, built apart from `app` so a mistyped one can be
+/// matched against their names
+/// Repeated `--multisig-signer`, the members signing for a multisig
+/// `--authority`
+fn multisig_signer_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("multisig_signer")
+        .long("multisig-signer")
+        .value_name("KEYPAIR")
+        .validator(is_valid_signer)
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help(
+            "Member of the multisig --authority signing in its place, repeat for each \
+             signing member",
+        )
+}
+
+fn subcommands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    vec![
+        Subcommand::with_name("create")
+            .about("Create an account")
+            .arg(
+                Arg::with_name("account_type")
+                    .short("t")
+                    .long("type")
+                    .value_name("ACCOUNT TYPE")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help(
+                        "Specify a user account type to create: \
+                owner \
+                access \
+                data",
+                    ),
+            )
+            .arg(
+                Arg::with_name("authority")
+                    .short("a")
+                    .long("authority")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Public Key of the owner of the created account"),
+            )
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .validator(is_dataset_hash)
+                    .value_name("HASH")
+                    .takes_value(true)
+                    .help(
+                        "Hash for dataset accounts, with access creates the \
+                associated access account of the dataset",
+                    ),
+            ),
+        Subcommand::with_name("set")
+            .about("Set dataset parameters")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of target dataset"),
+            )
+            .arg(
+                Arg::with_name("init")
+                    .long("init")
+                    .takes_value(false)
+                    .requires_all(&["key", "value", "share_limit"])
+                    .conflicts_with_all(&[
+                        "referral_bps",
+                        "derived_discount_bps",
+                        "access_duration",
+                        "uri",
+                        "size",
+                        "content_type",
+                        "resharable",
+                        "max_share_depth",
+                        "max_holders",
+                        "prune_bounty",
+                        "share_fee",
+                        "tier",
+                        "price",
+                        "remove_price",
+                        "tag",
+                        "remove_tag",
+                        "sale_start",
+                        "sale_end",
+                        "clear",
+                    ])
+                    .help(
+                        "Set key, value and share limit, and --ref-data when given, in one \
+                         instruction",
+                    ),
+            )
+            .arg(
+                Arg::with_name("key")
+                    .short("k")
+                    .long("key")
+                    .value_name("HASH")
+                    .validator(is_hash)
+                    .takes_value(true)
+                    .help("Key for target dataset"),
+            )
+            .arg(
+                Arg::with_name("value")
+                    .short("v")
+                    .long("value")
+                    .value_name("AMOUNT")
+                    .validator(is_ui_amount)
+                    .takes_value(true)
+                    .help("Value of target dataset, in whole tokens of --mint"),
+            )
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help(
+                        "Mint the dataset is priced in, whose decimals --value and --tier \
+                         prices are read with. Without it prices are in base units.",
+                    ),
+            )
+            .arg(
+                Arg::with_name("share_limit")
+                    .short("l")
+                    .long("share-limit")
+                    .value_name("AMOUNT")
+                    .validator(is_parsable::<u16>)
+                    .takes_value(true)
+                    .help("Number of times the target dataset can be shared"),
+            )
+            .arg(
+                Arg::with_name("reference_data")
+                    .short("r")
+                    .long("ref-data")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Address of the dataset the target dataset is derived from"),
+            )
+            .arg(
+                Arg::with_name("referral_bps")
+                    .long("referral-bps")
+                    .value_name("BPS")
+                    .validator(is_parsable::<u16>)
+                    .takes_value(true)
+                    .help("Share of each purchase paid to a referrer, in basis points"),
+            )
+            .arg(
+                Arg::with_name("derived_discount_bps")
+                    .long("derived-discount-bps")
+                    .value_name("BPS")
+                    .validator(is_parsable::<u16>)
+                    .takes_value(true)
+                    .help(
+                        "Discount for buyers holding access to the referenced dataset, in basis \
+                         points",
+                    ),
+            )
+            .arg(
+                Arg::with_name("access_duration")
+                    .long("access-duration")
+                    .value_name("SECONDS")
+                    .validator(is_parsable::<i64>)
+                    .takes_value(true)
+                    .help("Length of purchased access in seconds, 0 for perpetual access"),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .long("uri")
+                    .value_name("URI")
+                    .takes_value(true)
+                    .help("Metadata URI of the target dataset, up to 128 bytes"),
+            )
+            .arg(
+                Arg::with_name("size")
+                    .long("size")
+                    .value_name("BYTES")
+                    .validator(is_parsable::<u64>)
+                    .takes_value(true)
+                    .help("Size of the target dataset in bytes"),
+            )
+            .arg(
+                Arg::with_name("content_type")
+                    .long("content-type")
+                    .value_name("MIME")
+                    .takes_value(true)
+                    .help("MIME type of the target dataset, up to 32 bytes"),
+            )
+            .arg(
+                Arg::with_name("resharable")
+                    .long("resharable")
+                    .value_name("BOOL")
+                    .takes_value(true)
+                    .possible_values(&["true", "false"])
+                    .help("Whether recipients of a share may share the dataset on"),
+            )
+            .arg(
+                Arg::with_name("max_share_depth")
+                    .long("max-share-depth")
+                    .value_name("HOPS")
+                    .validator(is_parsable::<u8>)
+                    .takes_value(true)
+                    .help("Times a purchased grant may be shared on, 0 removes the limit"),
+            )
+            .arg(
+                Arg::with_name("max_holders")
+                    .long("max-holders")
+                    .value_name("HOLDERS")
+                    .validator(is_parsable::<u32>)
+                    .takes_value(true)
+                    .help(
+                        "Authorities that may hold access by purchase or share, 0 removes \
+                         the quota",
+                    ),
+            )
+            .arg(
+                Arg::with_name("prune_bounty")
+                    .long("prune-bounty")
+                    .value_name("LAMPORTS")
+                    .validator(is_parsable::<u64>)
+                    .takes_value(true)
+                    .help(
+                        "Lamports paid out of the bounty pool to whoever prunes an expired \
+                         access entry, 0 pays nothing",
+                    ),
+            )
+            .arg(
+                Arg::with_name("share_fee")
+                    .long("share-fee")
+                    .value_name("MINT:AMOUNT")
+                    .validator(is_share_fee)
+                    .takes_value(true)
+                    .help(
+                        "Fee in base units of MINT a holder pays the owner for each share, \
+                         an AMOUNT of 0 makes sharing free again",
+                    ),
+            )
+            .arg(
+                Arg::with_name("tier")
+                    .long("tier")
+                    .value_name("MIN_SEATS:UNIT_PRICE")
+                    .validator(is_price_tier)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help(
+                        "Volume price for seat purchases of at least MIN_SEATS, repeat for \
+                         each tier",
+                    ),
+            )
+            .arg(
+                Arg::with_name("price")
+                    .long("price")
+                    .value_name("MINT:AMOUNT")
+                    .validator(is_mint_price)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help(
+                        "Price in base units of MINT, replacing its current one; once any \
+                         mint is priced purchases must pay in a priced mint. Repeat for each \
+                         mint, at most 4",
+                    ),
+            )
+            .arg(
+                Arg::with_name("remove_price")
+                    .long("remove-price")
+                    .value_name("MINT")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Remove the price of MINT, repeat for each mint"),
+            )
+            .arg(
+                Arg::with_name("tag")
+                    .long("tag")
+                    .value_name("NAME")
+                    .validator(is_tag)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help(
+                        "Tag to find the dataset by with `list datasets --tag`, at most 16 \
+                         bytes. Repeat for each tag, a dataset holds at most 8",
+                    ),
+            )
+            .arg(
+                Arg::with_name("remove_tag")
+                    .long("remove-tag")
+                    .value_name("NAME")
+                    .validator(is_tag)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Remove a tag of the dataset, repeat for each tag"),
+            )
+            .arg(
+                Arg::with_name("sale_start")
+                    .long("sale-start")
+                    .value_name("RFC3339")
+                    .validator(is_timestamp)
+                    .takes_value(true)
+                    .help(
+                        "Time purchases open at, e.g. 2024-01-01T00:00:00Z. Replaces the \
+                         current sale window, opening it now if not given",
+                    ),
+            )
+            .arg(
+                Arg::with_name("sale_end")
+                    .long("sale-end")
+                    .value_name("RFC3339")
+                    .validator(is_timestamp)
+                    .takes_value(true)
+                    .help(
+                        "Time purchases close at, after which holders may still share. \
+                         Replaces the current sale window, leaving it open ended if not \
+                         given",
+                    ),
+            )
+            .arg(
+                Arg::with_name("clear")
+                    .long("clear")
+                    .value_name("FIELDS")
+                    .takes_value(true)
+                    .use_delimiter(true)
+                    .possible_values(&["key", "value", "share-limit", "ref-data", "uri"])
+                    .help(
+                        "Comma separated fields to unset, a dataset with no value \
+                         cannot be purchased",
+                    ),
+            )
+            .arg(multisig_signer_arg()),
+        Subcommand::with_name("purchase_access")
+            .visible_alias("purchase-access")
+            .about("Purchase access to a dataset")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of target dataset"),
+            )
+            .arg(
+                Arg::with_name("value")
+                    .short("v")
+                    .long("value")
+                    .value_name("AMOUNT")
+                    .validator(is_ui_amount)
+                    .takes_value(true)
+                    .required(true)
+                    .index(2)
+                    .help("Amount to pay, in whole tokens such as 100 or 0.5"),
+            )
+            .arg(
+                Arg::with_name("token_account")
+                    .long("token-account")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Token account to pay from, its mint sets the amount's decimals"),
+            )
+            .arg(
+                Arg::with_name("referrer")
+                    .long("referrer")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Token account of the referrer to receive the referral share"),
+            )
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Mint of the token account, skips looking the token account up"),
+            )
+            .arg(
+                Arg::with_name("owner_token_account")
+                    .long("owner-token-account")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help(
+                        "The dataset's vault of the mint receiving the proceeds, skips checking \
+                         the owner has opened it",
+                    ),
+            )
+            .arg(
+                Arg::with_name("dataset_address")
+                    .long("dataset-address")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Address of the dataset, checked against the hash rather than looked up"),
+            )
+            .arg(
+                Arg::with_name("owns_reference")
+                    .long("owns-reference")
+                    .takes_value(false)
+                    .help(
+                        "Pay the derived discount price, proving access to the dataset this one \
+                         is derived from",
+                    ),
+            )
+            .arg(
+                Arg::with_name("yes")
+                    .short("y")
+                    .long("yes")
+                    .takes_value(false)
+                    .help("Skip the confirmation prompt"),
+            )
+            .arg(
+                Arg::with_name("receipt_file")
+                    .long("receipt-file")
+                    .value_name("FILEPATH")
+                    .takes_value(true)
+                    .help("Also write the receipt as JSON to this file, which must not exist"),
+            ),
+        Subcommand::with_name("collection")
+            .about("Sell access to several datasets together")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                Subcommand::with_name("create")
+                    .about("Create a collection")
+                    .arg(
+                        Arg::with_name("hash")
+                            .value_name("HASH")
+                            .validator(is_dataset_hash)
+                            .takes_value(true)
+                            .required(true)
+                            .index(1)
+                            .help("Hash identifying the collection"),
+                    )
+                    .arg(
+                        Arg::with_name("value")
+                            .long("value")
+                            .value_name("AMOUNT")
+                            .validator(is_amount)
+                            .takes_value(true)
+                            .required(true)
+                            .help("Price of the whole collection"),
+                    ),
+            )
+            .subcommand(
+                Subcommand::with_name("add")
+                    .about("Add one of your datasets to a collection")
+                    .arg(
+                        Arg::with_name("collection")
+                            .value_name("COLLECTION_HASH")
+                            .validator(is_dataset_hash)
+                            .takes_value(true)
+                            .required(true)
+                            .index(1)
+                            .help("Hash of the target collection"),
+                    )
+                    .arg(
+                        Arg::with_name("hash")
+                            .value_name("DATASET_HASH")
+                            .validator(is_dataset_hash)
+                            .takes_value(true)
+                            .required(true)
+                            .index(2)
+                            .help("Hash of the dataset to add"),
+                    ),
+            )
+            .subcommand(
+                Subcommand::with_name("purchase")
+                    .about("Purchase access to every dataset in a collection")
+                    .arg(
+                        Arg::with_name("hash")
+                            .value_name("HASH")
+                            .validator(is_dataset_hash)
+                            .takes_value(true)
+                            .required(true)
+                            .index(1)
+                            .help("Hash of the target collection"),
+                    )
+                    .arg(
+                        Arg::with_name("token_account")
+                            .long("token-account")
+                            .value_name("PUBKEY")
+                            .validator(is_pubkey)
+                            .takes_value(true)
+                            .required(true)
+                            .help("Token account to pay for the collection from"),
+                    )
+                    .arg(
+                        Arg::with_name("amount")
+                            .long("amount")
+                            .value_name("AMOUNT")
+                            .validator(is_amount)
+                            .takes_value(true)
+                            .required(true)
+                            .help("Amount to pay, at least the collection value"),
+                    ),
+            ),
+        Subcommand::with_name("share_access")
+            .visible_alias("share-access")
+            .about("Share access to a dataset")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("recipient")
+                    .short("r")
+                    .long("recipient")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help(
+                        "Address to share access to, \
+                repeat to share with several recipients",
+                    ),
+            )
+            .arg(
+                Arg::with_name("expires_in")
+                    .long("expires-in")
+                    .value_name("DURATION")
+                    .validator(is_duration)
+                    .takes_value(true)
+                    .help(
+                        "Time the recipient's access lasts, e.g. 7d or 12h, ending earlier \
+                         when the sharer's own access does. Only with a single recipient",
+                    ),
+            )
+            .arg(
+                Arg::with_name("fee_token_account")
+                    .long("fee-token-account")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help(
+                        "Token account paying the dataset's share fee, defaults to the \
+                         associated token account of the fee mint. Only with a single \
+                         recipient, datasets with a share fee can't be shared with several",
+                    ),
+            )
+            .arg(
+                Arg::with_name("receipt_file")
+                    .long("receipt-file")
+                    .value_name("FILEPATH")
+                    .takes_value(true)
+                    .help("Also write the receipt as JSON to this file, which must not exist"),
+            ),
+        Subcommand::with_name("set-access-preferences")
+            .alias("set_access_preferences")
+            .about("Set how shares to the authority are received")
+            .arg(
+                Arg::with_name("require_share_approval")
+                    .long("require-share-approval")
+                    .value_name("BOOL")
+                    .takes_value(true)
+                    .required(true)
+                    .possible_values(&["true", "false"])
+                    .help(
+                        "Whether shares wait for the authority to accept them before \
+                         granting access",
+                    ),
+            ),
+        Subcommand::with_name("accept-share")
+            .alias("accept_share")
+            .about("Accept a share of a dataset awaiting the authority's approval")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("from")
+                    .value_name("SHARER")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .index(2)
+                    .help("Authority that shared the dataset"),
+            ),
+        Subcommand::with_name("reject-share")
+            .alias("reject_share")
+            .about(
+                "Reject a share of a dataset awaiting approval, or reclaim a share \
+                 its recipient let lapse",
+            )
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("from")
+                    .value_name("SHARER")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .index(2)
+                    .help("Authority that shared the dataset"),
+            )
+            .arg(
+                Arg::with_name("recipient")
+                    .long("recipient")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help(
+                        "Recipient of the share, defaults to the authority. Set it to \
+                         reclaim a lapsed share of your own",
+                    ),
+            ),
+        Subcommand::with_name("close-dataset")
+            .alias("close_dataset")
+            .about("Close a dataset and reclaim its rent")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("force")
+                    .short("f")
+                    .long("force")
+                    .takes_value(false)
+                    .help(
+                        "Close the dataset even if access has been purchased. \
+                Existing holders keep their access entries.",
+                    ),
+            ),
+        Subcommand::with_name("transfer-ownership")
+            .alias("transfer_ownership")
+            .about("Propose a new owner for a dataset, who takes it over by accepting")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("new_owner")
+                    .value_name("NEW_OWNER")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .index(2)
+                    .help("Authority proposed as the dataset's new owner"),
+            )
+            .arg(multisig_signer_arg()),
+        Subcommand::with_name("accept-ownership")
+            .alias("accept_ownership")
+            .about("Accept a dataset proposed to the authority as its new owner")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(multisig_signer_arg()),
+        Subcommand::with_name("cancel-ownership-transfer")
+            .alias("cancel_ownership_transfer")
+            .about("Withdraw a dataset's pending ownership transfer")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(multisig_signer_arg()),
+        Subcommand::with_name("renew")
+            .about("Renew expiring access to a dataset")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("token_account")
+                    .long("token-account")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Token account to pay for the renewal from"),
+            ),
+        Subcommand::with_name("withdraw")
+            .about("Withdraw purchase proceeds held in a dataset vault")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("token_account")
+                    .long("token-account")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Token account to withdraw to, its mint selects the vault"),
+            )
+            .arg(
+                Arg::with_name("amount")
+                    .long("amount")
+                    .value_name("AMOUNT")
+                    .validator(is_amount)
+                    .takes_value(true)
+                    .help("Amount to withdraw [default: all the vault can pay out]"),
+            )
+            .arg(multisig_signer_arg()),
+        Subcommand::with_name("register")
+            .about("Register a dataset from a local file")
+            .arg(
+                Arg::with_name("file")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("File to hash and register"),
+            )
+            .arg(
+                Arg::with_name("key")
+                    .short("k")
+                    .long("key")
+                    .value_name("HASH")
+                    .validator(is_hash)
+                    .takes_value(true)
+                    .help("Key of the dataset"),
+            )
+            .arg(
+                Arg::with_name("value")
+                    .short("v")
+                    .long("value")
+                    .value_name("AMOUNT")
+                    .validator(is_parsable::<u64>)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Price of access to the dataset"),
+            )
+            .arg(
+                Arg::with_name("share_limit")
+                    .short("l")
+                    .long("share-limit")
+                    .value_name("AMOUNT")
+                    .validator(is_parsable::<u16>)
+                    .takes_value(true)
+                    .help("Number of times access to the dataset can be shared"),
+            )
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Mint purchases are paid in, opens the dataset's vault of it"),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .long("uri")
+                    .value_name("URI")
+                    .takes_value(true)
+                    .help("Metadata URI of the dataset, up to 128 bytes"),
+            ),
+        Subcommand::with_name("config")
+            .about("Show or change the cluster, keypair and commitment used")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                Subcommand::with_name("get")
+                    .about("Print the resolved settings and the keypair's index addresses"),
+            )
+            .subcommand(
+                Subcommand::with_name("set")
+                    .about("Save settings to the DataNexus config file")
+                    .after_help("Also saves the global --url and --commitment when given")
+                    .arg(
+                        Arg::with_name("websocket_url")
+                            .long("ws")
+                            .value_name("URL")
+                            .validator(is_url)
+                            .takes_value(true)
+                            .help("Websocket URL of the cluster"),
+                    )
+                    .arg(
+                        Arg::with_name("keypair")
+                            .short("k")
+                            .long("keypair")
+                            .value_name("PATH")
+                            .takes_value(true)
+                            .help("Keypair to sign with by default"),
+                    ),
+            ),
+        Subcommand::with_name("address")
+            .about("Print the DataNexus addresses of an authority or dataset, offline")
+            .arg(
+                Arg::with_name("authority")
+                    .short("a")
+                    .long("authority")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Authority to derive index addresses of. Defaults to the keypair."),
+            )
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .help("Hash to derive the dataset address of"),
+            ),
+        Subcommand::with_name("keygen")
+            .about("Generate a keypair and print its DataNexus addresses")
+            .arg(
+                Arg::with_name("outfile")
+                    .short("o")
+                    .long("outfile")
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .required(true)
+                    .help("File to write the keypair to"),
+            )
+            .arg(
+                Arg::with_name("force")
+                    .short("f")
+                    .long("force")
+                    .takes_value(false)
+                    .help("Overwrite the outfile if it exists"),
+            ),
+        Subcommand::with_name("batch")
+            .about("Run the operations listed in a JSON manifest")
+            .arg(
+                Arg::with_name("manifest")
+                    .value_name("MANIFEST")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Manifest of register, set, grant and share operations"),
+            )
+            .arg(
+                Arg::with_name("fail_fast")
+                    .long("fail-fast")
+                    .takes_value(false)
+                    .help("Stop sending once a transaction fails"),
+            )
+            .arg(
+                Arg::with_name("concurrency")
+                    .long("concurrency")
+                    .value_name("N")
+                    .validator(is_parsable::<usize>)
+                    .takes_value(true)
+                    .default_value("4")
+                    .help("Number of transactions in flight at once"),
+            ),
+        Subcommand::with_name("access-status")
+            .alias("access_status")
+            .about("Check whether an authority currently holds access to a dataset")
+            .arg(
+                Arg::with_name("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("authority")
+                    .short("a")
+                    .long("authority")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Authority to check. Defaults to the authority."),
+            ),
+        Subcommand::with_name("attest")
+            .about("Sign a claim of the authority's access to a dataset for a gateway")
+            .arg(
+                Arg::with_name("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            ),
+        Subcommand::with_name("trace")
+            .about("Trace who an authority's access to a dataset was shared from")
+            .arg(
+                Arg::with_name("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            )
+            .arg(
+                Arg::with_name("authority")
+                    .value_name("AUTHORITY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .required(true)
+                    .index(2)
+                    .help("Authority whose access to trace"),
+            ),
+        Subcommand::with_name("watch")
+            .about("Print purchases of a dataset as they are confirmed, until Ctrl-C")
+            .arg(
+                Arg::with_name("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            ),
+        Subcommand::with_name("migrate")
+            .about("Grow accounts created under older layouts to the newest one")
+            .arg(
+                Arg::with_name("address")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .index(1)
+                    .required_unless("all_mine")
+                    .help("Address of the account to migrate"),
+            )
+            .arg(
+                Arg::with_name("all_mine")
+                    .long("all-mine")
+                    .takes_value(false)
+                    .conflicts_with("address")
+                    .help(
+                        "Migrate every outdated index, dataset and access account of \
+                         the authority",
+                    ),
+            ),
+        Subcommand::with_name("export")
+            .about("Write the indexes, datasets and access accounts of an authority to a file")
+            .arg(
+                Arg::with_name("authority")
+                    .short("a")
+                    .long("authority")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Authority to export. Defaults to the authority."),
+            )
+            .arg(
+                Arg::with_name("out")
+                    .short("o")
+                    .long("out")
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .required(true)
+                    .help("File to write the snapshot to"),
+            )
+            .arg(
+                Arg::with_name("force")
+                    .short("f")
+                    .long("force")
+                    .takes_value(false)
+                    .help("Overwrite the file if it exists"),
+            ),
+        Subcommand::with_name("verify")
+            .about("Compare a snapshot written by export with the accounts on chain")
+            .arg(
+                Arg::with_name("snapshot")
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Snapshot to compare"),
+            ),
+        Subcommand::with_name("show")
+            .about("Decode and print a DataNexus account")
+            .arg(
+                Arg::with_name("address")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .index(1)
+                    .required_unless_one(&["hash", "authority"])
+                    .help("Address of the account to show"),
+            )
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .conflicts_with("address")
+                    .help("Show the dataset account of this hash"),
+            )
+            .arg(
+                Arg::with_name("authority")
+                    .short("a")
+                    .long("authority")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .conflicts_with_all(&["address", "hash"])
+                    .requires("index_type")
+                    .help("Show an index account of this authority"),
+            )
+            .arg(
+                Arg::with_name("index_type")
+                    .short("t")
+                    .long("type")
+                    .value_name("INDEX TYPE")
+                    .possible_values(&["owner", "access"])
+                    .takes_value(true)
+                    .help("Index to show with --authority"),
+            )
+            .arg(
+                Arg::with_name("raw")
+                    .long("raw")
+                    .takes_value(false)
+                    .help("Print the raw account data as base64"),
+            )
+            .arg(
+                Arg::with_name("mint")
+                    .long("mint")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Mint to show dataset amounts in whole tokens of"),
+            ),
+        Subcommand::with_name("list")
+            .about("List datasets or access grants")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                Subcommand::with_name("datasets")
+                    .about("List the datasets registered by an owner")
+                    .arg(
+                        Arg::with_name("owner")
+                            .long("owner")
+                            .value_name("PUBKEY")
+                            .validator(is_pubkey)
+                            .takes_value(true)
+                            .help("Owner to list datasets of. Defaults to the authority."),
+                    )
+                    .arg(
+                        Arg::with_name("scan")
+                            .long("scan")
+                            .takes_value(false)
+                            .help("Scan program accounts instead of walking the owner index"),
+                    )
+                    .arg(
+                        Arg::with_name("tag")
+                            .long("tag")
+                            .value_name("NAME")
+                            .validator(is_tag)
+                            .takes_value(true)
+                            .conflicts_with("scan")
+                            .help(
+                                "List the datasets of any owner tagged NAME, or only those \
+                                 of --owner when given",
+                            ),
+                    )
+                    .arg(
+                        Arg::with_name("mint")
+                            .long("mint")
+                            .value_name("PUBKEY")
+                            .validator(is_pubkey)
+                            .takes_value(true)
+                            .help("Mint to show dataset amounts in whole tokens of"),
+                    ),
+            )
+            .subcommand(
+                Subcommand::with_name("access")
+                    .about("List the access grants held by an authority")
+                    .arg(
+                        Arg::with_name("authority")
+                            .long("authority")
+                            .value_name("PUBKEY")
+                            .validator(is_pubkey)
+                            .takes_value(true)
+                            .help("Authority to list grants of. Defaults to the authority."),
+                    )
+                    .arg(
+                        Arg::with_name("grants")
+                            .long("grants")
+                            .takes_value(false)
+                            .help(
+                                "Fetch each associated access account and list its \
+                                 grants instead of the index entries",
+                            ),
+                    ),
+            )
+            .arg(
+                Arg::with_name("offset")
+                    .long("offset")
+                    .value_name("N")
+                    .validator(is_parsable::<usize>)
+                    .takes_value(true)
+                    .global(true)
+                    .help("Number of entries to skip"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .value_name("N")
+                    .validator(is_parsable::<usize>)
+                    .takes_value(true)
+                    .global(true)
+                    .help("Maximum number of entries to print"),
+            ),
+        Subcommand::with_name("stats")
+            .about("Display the sales counters of a dataset")
+            .arg(
+                Arg::with_name("hash")
+                    .short("h")
+                    .long("hash")
+                    .value_name("HASH")
+                    .validator(is_dataset_hash)
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Hash of the target dataset"),
+            ),
+        Subcommand::with_name("completions")
+            .about("Print a completion script for a shell")
+            .arg(
+                Arg::with_name("shell")
+                    .value_name("SHELL")
+                    .possible_values(&["bash", "zsh", "fish"])
+                    .takes_value(true)
+                    .required(true)
+                    .index(1)
+                    .help("Shell to complete commands in"),
+            ),
+    ]
+}
+
+/// The CLI's arguments, built apart from `main` so completion scripts can be
+/// generated from them
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("c")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(&config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("rpc_url")
+                .short("u")
+                .long("url")
+                .validator(is_url)
+                .takes_value(true)
+                .global(true)
+                .help("Specifiy target Solana cluster"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .validator(is_pubkey)
+                .takes_value(true)
+                .global(true)
+                .help("DataNexus program to target, defaults to the mainnet deployment"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .value_name("FORMAT")
+                .possible_values(&["text", "json"])
+                .takes_value(true)
+                .global(true)
+                .help("Print results as text or as JSON"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .takes_value(false)
+                .global(true)
+                .help("Simulate the transaction and print what it would do without sending it"),
+        )
+        .arg(
+            Arg::with_name("auto_create")
+                .long("auto-create")
+                .takes_value(false)
+                .global(true)
+                .help(
+                    "Create missing index and associated access accounts the command needs \
+                in the same transaction",
+                ),
+        )
+        .arg(
+            Arg::with_name("commitment")
+                .long("commitment")
+                .value_name("LEVEL")
+                .possible_values(&["processed", "confirmed", "finalized"])
+                .takes_value(true)
+                .global(true)
+                .help("Commitment to read state at and to wait for sent transactions to reach"),
+        )
+        .arg(
+            Arg::with_name("priority_fee")
+                .long("priority-fee")
+                .value_name("MICROLAMPORTS")
+                .validator(is_parsable::<u64>)
+                .takes_value(true)
+                .global(true)
+                .conflicts_with("auto_priority_fee")
+                .help("Priority fee to pay per compute unit, in micro-lamports"),
+        )
+        .arg(
+            Arg::with_name("auto_priority_fee")
+                .long("auto-priority-fee")
+                .takes_value(false)
+                .global(true)
+                .help("Pay the 75th percentile of recent priority fees for the same accounts"),
+        )
+        .arg(
+            Arg::with_name("compute_unit_limit")
+                .long("compute-unit-limit")
+                .value_name("N")
+                .validator(is_parsable::<u32>)
+                .takes_value(true)
+                .global(true)
+                .help("Compute units to request for each transaction"),
+        )
+        .arg(
+            Arg::with_name("memo")
+                .long("memo")
+                .value_name("STRING")
+                .validator(is_memo)
+                .takes_value(true)
+                .global(true)
+                .conflicts_with("memo_file")
+                .help("Attach a memo to every transaction sent, such as an invoice reference"),
+        )
+        .arg(
+            Arg::with_name("memo_file")
+                .long("memo-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Attach the contents of a UTF-8 file as the memo"),
+        )
+        .arg(
+            Arg::with_name("max_retries")
+                .long("max-retries")
+                .value_name("N")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .default_value("5")
+                .global(true)
+                .help("Times to retry a send after a network error or an expired blockhash"),
+        )
+        .arg(sign_only_arg().global(true))
+        .arg(blockhash_arg().global(true))
+        .arg(
+            Arg::with_name(SIGNER_ARG.name)
+                .long(SIGNER_ARG.long)
+                .value_name("PUBKEY=SIGNATURE")
+                .validator(is_pubkey_sig)
+                .takes_value(true)
+                .multiple(true)
+                .global(true)
+                .help(SIGNER_ARG.help),
+        )
+        .arg(nonce_arg().global(true))
+        .arg(nonce_authority_arg().global(true))
+        .arg(
+            Arg::with_name("fee_payer")
+                .short("p")
+                .long("fee-payer")
+                .alias("payer")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .global(true)
+                .help(
+                    "Specify the fee payer, a keypair file or a signer URI such as usb://ledger. \
+                Defaults to client keypair, even when --authority is set.",
+                ),
+        )
+        .arg(
+            Arg::with_name("authority")
+                .long("authority")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .help(
+                    "Specify the authority over DataNexus accounts, a keypair file or a \
+                signer URI. Defaults to client keypair, even when --fee-payer is set.",
+                ),
+        )
+        .subcommands(subcommands())
+}
+
+/// Name the CLI is installed under, which completion scripts complete
+const BIN_NAME: &str = "dn-me";
+
+/// Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == *b {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Name in `names` closest to `word`, with `_` and `-` read alike as
+/// subcommands are spelled with either
+fn nearest_name<'a>(word: &str, names: &[&'a str]) -> Option<&'a str> {
+    let word = word.replace('_', "-");
+    names
+        .iter()
+        .copied()
+        .min_by_key(|name| edit_distance(&word, &name.replace('_', "-")))
+}
+
+/// Exits with clap's `error`, suggesting the nearest subcommand when an
+/// unknown one is rejected without clap finding a match close enough to
+/// suggest itself
+fn exit_with_suggestion(error: clap::Error) -> ! {
+    let word = match error.info.as_ref().and_then(|info| info.first()) {
+        Some(word) if error.kind == ErrorKind::UnknownArgument && !word.starts_with('-') => word,
+        _ => error.exit(),
+    };
+
+    // Once a subcommand is given, unknown words are its own arguments
+    let commands = subcommands();
+    let names: Vec<&str> = commands.iter().map(App::get_name).collect();
+    let given = std::env::args().any(|arg| {
+        names
+            .iter()
+            .any(|name| arg.replace('_', "-") == name.replace('_', "-"))
+    });
+    match nearest_name(word, &names) {
+        Some(nearest) if !given => clap::Error::with_description(
+            &format!(
+                "The subcommand '{}' wasn't recognized\n\tDid you mean '{}'?\n\n\
+                 For more information try --help",
+                word, nearest
+            ),
+            ErrorKind::InvalidSubcommand,
+        )
+        .exit(),
+        _ => error.exit(),
+    }
+}
+
+fn main() {
+    let app_matches = app()
+        .get_matches_safe()
+        .unwrap_or_else(|error| exit_with_suggestion(error));
+
+    let mut wallet_manager = None;
+    let (sub_command, sub_matches) = app_matches.subcommand();
+    // `SubcommandRequiredElseHelp` prints the help when none is given
+    let matches = match sub_matches {
+        Some(matches) => matches,
+        None => unreachable!(),
+    };
+    let format = OutputFormat::from_arg(matches.value_of("output"));
+
+    // Completion scripts are generated from the arguments alone
+    if let ("completions", Some(args)) = (sub_command, sub_matches) {
+        match required(args.value_of("shell"), "shell")
+            .and_then(|shell| shell.parse::<Shell>().map_err(CliError::User))
+        {
+            Ok(shell) => {
+                app().gen_completions_to(BIN_NAME, shell, &mut io::stdout());
+                std::process::exit(0);
+            }
+            Err(error) => exit_with_error(format, error),
+        }
+    }
+
+    // Flags win over the DataNexus config, which wins over the solana config
+    let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+        solana_cli_config::Config::load(config_file).unwrap_or_default()
+    } else {
+        solana_cli_config::Config::default()
+    };
+    let datanexus_config_path = DataNexusConfig::path();
+    let datanexus_config = match &datanexus_config_path {
+        Some(path) => DataNexusConfig::load(path).unwrap_or_else(|e| {
+            exit_with_error(format, CliError::User(format!("failed to load {}: {}", path, e)))
+        }),
+        None => DataNexusConfig::default(),
+    };
+    let settings = Settings::resolve(
+        &Overrides {
+            json_rpc_url: matches.value_of("rpc_url"),
+            commitment: matches.value_of("commitment"),
+        },
+        &datanexus_config,
+        &cli_config,
+    );
+
+    let program_id = pubkey_of(matches, "program_id").unwrap_or_else(datanexus::id);
+
+    // Config commands run before any signer is resolved, they must work
+    // while the keypair is missing or misconfigured
+    if let ("config", Some(args)) = (sub_command, sub_matches) {
+        let result = match args.subcommand() {
+            ("get", Some(_)) => {
+                let keypair_path = settings.keypair_path.as_str();
+                let keypair =
+                    signer_from_path(matches, keypair_path, "keypair", &mut wallet_manager)
+                        .ok()
+                        .map(|signer| signer.pubkey());
+                command_config_get(
+                    &settings,
+                    datanexus_config_path.as_deref(),
+                    &program_id,
+                    keypair,
+                )
+            }
+            ("set", Some(set_args)) => {
+                let setting = |name| set_args.value_of(name).or_else(|| args.value_of(name));
+                command_config_set(
+                    datanexus_config_path.as_deref(),
+                    datanexus_config,
+                    setting("rpc_url"),
+                    setting("websocket_url"),
+                    setting("keypair"),
+                    setting("commitment"),
+                )
+            }
+            _ => unreachable!(),
+        };
+        finish(format, result);
+    }
+
+    // Addresses are derived locally, these never need a cluster
+    match (sub_command, sub_matches) {
+        ("address", Some(args)) => {
+            let authority = pubkey_of(args, "authority").or_else(|| {
+                signer_from_path(
+                    matches,
+                    &settings.keypair_path,
+                    "keypair",
+                    &mut wallet_manager,
+                )
+                .ok()
+                .map(|signer| signer.pubkey())
+            });
+            let hash = dataset_hash_of(format, args, "hash");
+            finish(format, command_address(&program_id, authority, hash));
+        }
+        ("keygen", Some(args)) => finish(
+            format,
+            required(args.value_of("outfile"), "outfile")
+                .and_then(|outfile| command_keygen(&program_id, outfile, args.is_present("force"))),
+        ),
+        _ => {}
+    }
+
+    // Retrieves signers and target RPC from the resolved settings
+    let config = {
+        let rpc_url = &settings.json_rpc_url;
+        let commitment = settings.commitment.parse::<CommitmentConfig>().unwrap_or_else(|_| {
+            exit_with_error(
+                format,
+                CliError::User(format!("invalid commitment {}", settings.commitment)),
+            )
+        });
+
+        // `--authority` comes before the subcommand, but the presigners and
+        // `--sign-only` it resolves against are global and gathered on the
+        // subcommand's matches
+        let default_signer_path = settings.keypair_path.as_str();
+        let mut resolve_signer = |name: &str| {
+            let path = matches
+                .value_of(name)
+                .or_else(|| app_matches.value_of(name))
+                .unwrap_or(default_signer_path);
+            let config = SignerFromPathConfig {
+                allow_null_signer: true,
+            };
+            signer_from_path_with_config(matches, path, name, &mut wallet_manager, &config)
+                .unwrap_or_else(|e| exit_with_error(format, CliError::User(e.to_string())))
+        };
+        let fee_payer = resolve_signer("fee_payer");
+        let authority = resolve_signer("authority");
+
+        let signer_config = SignerFromPathConfig {
+            allow_null_signer: true,
+        };
+        let multisig_signers = matches
+            .values_of("multisig_signer")
+            .into_iter()
+            .flatten()
+            .map(|path| {
+                signer_from_path_with_config(
+                    matches,
+                    path,
+                    "multisig_signer",
+                    &mut wallet_manager,
+                    &signer_config,
+                )
+                .unwrap_or_else(|e| exit_with_error(format, CliError::User(e.to_string())))
+            })
+            .collect();
+
+        // Defaults to the fee payer when unset
+        let nonce_authority = matches.value_of(NONCE_AUTHORITY_ARG.name).map(|path| {
+            signer_from_path(matches, path, NONCE_AUTHORITY_ARG.name, &mut wallet_manager)
+                .unwrap_or_else(|e| exit_with_error(format, CliError::User(e.to_string())))
+        });
+
+        Config {
+            rpc_client: RpcClient::new_with_commitment(rpc_url.clone(), commitment),
+            json_rpc_url: rpc_url.clone(),
+            websocket_url: settings.websocket_url.clone(),
+            program_id,
+            max_retries: required(value_of(matches, "max_retries"), "max_retries")
+                .unwrap_or_else(|e| exit_with_error(format, e)),
+            priority_fee: match value_of(matches, "priority_fee") {
+                Some(price) => PriorityFee::Fixed(price),
+                None if matches.is_present("auto_priority_fee") => PriorityFee::Auto,
+                None => PriorityFee::None,
+            },
+            compute_unit_limit: value_of(matches, "compute_unit_limit"),
+            memo: match matches.value_of("memo_file") {
+                Some(path) => Some(
+                    read_memo_file(path)
+                        .unwrap_or_else(|e| exit_with_error(format, CliError::User(e))),
+                ),
+                None => matches.value_of("memo").map(String::from),
+            },
+            fee_payer,
+            authority,
+            dry_run: matches.is_present("dry_run"),
+            sign_only: matches.is_present(SIGN_ONLY_ARG.name),
+            auto_create: matches.is_present("auto_create"),
+            blockhash_query: BlockhashQuery::new_from_matches(matches),
+            nonce_account: pubkey_of(matches, NONCE_ARG.name),
+            nonce_authority,
+            multisig_signers,
+        }
+    };
+
+    finish(format, run(&config, format, sub_command, matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        instruction::{AccountMeta, InstructionError},
+        signer::presigner::Presigner,
+    };
+
+    /// A config sending to a mock cluster at `url`, which confirms every
+    /// transaction unless it names a failure
+    fn config(url: &str, fee_payer: &Keypair, authority: &Keypair) -> Config {
+        let copy = |keypair: &Keypair| Box::new(Keypair::from_bytes(&keypair.to_bytes()).unwrap());
+        Config {
+            fee_payer: copy(fee_payer),
+            authority: copy(authority),
+            rpc_client: RpcClient::new_mock(url.to_string()),
+            json_rpc_url: url.to_string(),
+            websocket_url: String::new(),
+            program_id: datanexus::id(),
+            max_retries: 0,
+            priority_fee: PriorityFee::None,
+            compute_unit_limit: None,
+            memo: None,
+            dry_run: false,
+            sign_only: false,
+            auto_create: false,
+            blockhash_query: BlockhashQuery::default(),
+            nonce_account: None,
+            nonce_authority: None,
+            multisig_signers: vec![],
+        }
+    }
+
+    /// An instruction `signers` have to sign
+    fn signed_by(signers: &[&Keypair]) -> Instruction {
+        Instruction::new_with_bytes(
+            datanexus::id(),
+            &[],
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new(signer.pubkey(), true))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn transactions_carry_every_signature_they_need() {
+        let (fee_payer, authority, extra) = (Keypair::new(), Keypair::new(), Keypair::new());
+        let config = config("succeeds", &fee_payer, &authority);
+
+        let (transaction, _) =
+            sign_transaction(&config, &[signed_by(&[&authority, &extra])], &[&extra]).unwrap();
+
+        let message = &transaction.message;
+        assert_eq!(message.header.num_required_signatures, 3);
+        assert_eq!(message.account_keys[0], fee_payer.pubkey());
+        for signer in &[&fee_payer, &authority, &extra] {
+            assert!(message.account_keys[..3].contains(&signer.pubkey()));
+        }
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn a_fee_payer_that_is_the_authority_signs_once() {
+        let keypair = Keypair::new();
+        let config = config("succeeds", &keypair, &keypair);
+
+        let (transaction, _) = sign_transaction(&config, &[signed_by(&[&keypair])], &[]).unwrap();
+
+        assert_eq!(transaction.signatures.len(), 1);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn signers_the_message_lacks_are_not_asked() {
+        let (fee_payer, authority, extra) = (Keypair::new(), Keypair::new(), Keypair::new());
+        let config = config("succeeds", &fee_payer, &authority);
+
+        let (transaction, _) = sign_transaction(&config, &[signed_by(&[])], &[&extra]).unwrap();
+
+        assert_eq!(transaction.signatures.len(), 1);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn missing_signers_are_reported() {
+        let (fee_payer, authority, extra) = (Keypair::new(), Keypair::new(), Keypair::new());
+        let config = config("succeeds", &fee_payer, &authority);
+
+        let result = send_transaction(&config, &[signed_by(&[&extra])], &[]);
+
+        assert!(matches!(result, Err(CliError::User(_))));
+    }
+
+    #[test]
+    fn memos_are_the_last_instruction() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let mut config = config("succeeds", &fee_payer, &authority);
+        config.memo = Some("invoice 42".to_string());
+
+        let (transaction, _) = sign_transaction(&config, &[signed_by(&[&authority])], &[]).unwrap();
+
+        let message = &transaction.message;
+        let memo = message.instructions.last().unwrap();
+        assert_eq!(message.instructions.len(), 2);
+        assert_eq!(
+            message.account_keys[memo.program_id_index as usize],
+            spl_memo::id()
+        );
+        assert_eq!(memo.data, b"invoice 42");
+        assert!(memo.accounts.is_empty());
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn oversize_memos_are_rejected_before_signing() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let mut config = config("succeeds", &fee_payer, &authority);
+        let mut instruction = signed_by(&[&authority]);
+        instruction
+            .accounts
+            .extend((0..30).map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false)));
+        // Fits on its own
+        assert!(sign_transaction(&config, &[instruction.clone()], &[]).is_ok());
+
+        config.memo = Some("a".repeat(memo::MAX_MEMO_LEN));
+        let result = sign_transaction(&config, &[instruction], &[]);
+
+        match result {
+            Err(CliError::User(message)) => assert!(message.contains("byte memo")),
+            other => panic!(
+                "expected the memo to be refused, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    #[test]
+    fn sent_transactions_return_the_fee_payer_signature() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let config = config("succeeds", &fee_payer, &authority);
+        let instructions = [signed_by(&[&authority])];
+
+        let signature = send_transaction(&config, &instructions, &[]).unwrap();
+
+        // Signing is deterministic and the mock's blockhash fixed
+        let (transaction, _) = sign_transaction(&config, &instructions, &[]).unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+    }
+
+    #[test]
+    fn failed_transactions_report_the_instruction_error() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let config = config("instruction_error", &fee_payer, &authority);
+
+        let result = send_transaction(&config, &[signed_by(&[&authority])], &[]);
+
+        assert!(matches!(
+            result,
+            Err(CliError::Program {
+                index: 0,
+                error: InstructionError::UninitializedAccount,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn offline_signatures_are_absorbed_online() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let instructions = [signed_by(&[&authority])];
+        let blockhash = Hash::new_unique();
+
+        // The air-gapped half holds the authority and only knows the fee
+        // payer's address
+        let mut offline = config("succeeds", &fee_payer, &authority);
+        offline.fee_payer = Box::new(NullSigner::new(&fee_payer.pubkey()));
+        offline.sign_only = true;
+        offline.blockhash_query = BlockhashQuery::new(Some(blockhash), true, None);
+        let signed = match execute_or_simulate(&offline, &instructions).unwrap() {
+            CommandOutput::SignOnly(signed) => signed,
+            other => panic!("expected signatures, got {:?}", other),
+        };
+        assert_eq!(signed["blockhash"], blockhash.to_string());
+        assert_eq!(signed["absent"], json!([fee_payer.pubkey().to_string()]));
+        let (pubkey, signature) = signed["signers"][0]
+            .as_str()
+            .unwrap()
+            .split_once('=')
+            .unwrap();
+        assert_eq!(pubkey, authority.pubkey().to_string());
+
+        // The online half holds the fee payer and passes the authority's
+        // signature back in as `--signer`
+        let mut online = config("succeeds", &fee_payer, &authority);
+        online.authority = Box::new(Presigner::new(
+            &authority.pubkey(),
+            &signature.parse().unwrap(),
+        ));
+        online.blockhash_query = BlockhashQuery::new(Some(blockhash), false, None);
+        let (transaction, refresh_signers) = sign_transaction(&online, &instructions, &[]).unwrap();
+
+        assert_eq!(transaction.message.recent_blockhash, blockhash);
+        assert!(transaction.verify().is_ok());
+        assert!(refresh_signers.is_none());
+        assert_eq!(
+            send_transaction(&online, &instructions, &[]).unwrap(),
+            transaction.signatures[0]
+        );
+    }
+
+    #[test]
+    fn presigned_authorities_sign_alongside_the_fee_payer() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let instructions = [signed_by(&[&authority])];
+
+        // A hardware wallet signs the same message the CLI builds, which
+        // only depends on the mock's fixed blockhash
+        let local = config("succeeds", &fee_payer, &authority);
+        let (signed, _) = sign_transaction(&local, &instructions, &[]).unwrap();
+        let mut wallet = config("succeeds", &fee_payer, &authority);
+        wallet.authority = Box::new(Presigner::new(&authority.pubkey(), &signed.signatures[1]));
+
+        let (transaction, _) = sign_transaction(&wallet, &instructions, &[]).unwrap();
+
+        assert_eq!(transaction.signatures, signed.signatures);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn presigners_of_another_message_are_rejected() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let mut wallet = config("succeeds", &fee_payer, &authority);
+        wallet.authority = Box::new(Presigner::new(
+            &authority.pubkey(),
+            &authority.sign_message(b"another message"),
+        ));
+
+        let result = sign_transaction(&wallet, &[signed_by(&[&authority])], &[]);
+
+        assert!(matches!(result, Err(CliError::User(_))));
+    }
+
+    #[test]
+    fn instructions_act_for_the_authority_the_fee_payer_pays_for() {
+        let (fee_payer, authority) = (Keypair::new(), Keypair::new());
+        let config = config("succeeds", &fee_payer, &authority);
+
+        let instructions = client(&config)
+            .plan_set_params([1; 32], vec![Params::Value(100)])
+            .unwrap()
+            .instructions;
+        let (transaction, _) = sign_transaction(&config, &instructions, &[]).unwrap();
+
+        let message = &transaction.message;
+        assert_eq!(message.header.num_required_signatures, 2);
+        assert_eq!(message.account_keys[0], fee_payer.pubkey());
+        assert_eq!(message.account_keys[1], authority.pubkey());
+        assert_eq!(instructions[0].accounts[0].pubkey, authority.pubkey());
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn hex_hashes_are_parsed() {
+        let hash = [0xab; 32];
+
+        assert_eq!(parse_dataset_hash(&output::hex(&hash)), Ok(hash));
+        assert_eq!(parse_dataset_hash(&"AB".repeat(32)), Ok(hash));
+    }
+
+    #[test]
+    fn base58_hashes_are_parsed() {
+        let hash = [7; 32];
+
+        assert_eq!(
+            parse_dataset_hash(&Hash::new_from_array(hash).to_string()),
+            Ok(hash)
+        );
+    }
+
+    #[test]
+    fn file_hashes_are_the_hash_of_the_contents() {
+        let path = std::env::temp_dir().join(format!("dataset-{}", Pubkey::new_unique()));
+        std::fs::write(&path, b"dataset contents").unwrap();
+
+        let parsed = parse_dataset_hash(&format!("file:{}", path.display()));
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            parsed,
+            Ok(solana_sdk::hash::hash(b"dataset contents").to_bytes())
+        );
+    }
+
+    #[test]
+    fn hex_is_preferred_at_64_characters_and_base58_otherwise() {
+        // Only hex digits, but 32 characters long so it can only be base58
+        let zeroes = "1".repeat(32);
+        assert_eq!(parse_dataset_hash(&zeroes), Ok([0; 32]));
+
+        // Valid base58 too, but decoding to more than 32 bytes
+        assert_eq!(parse_dataset_hash(&"1".repeat(64)), Ok([0x11; 32]));
+    }
+
+    #[test]
+    fn malformed_hashes_are_explained() {
+        assert_eq!(
+            parse_dataset_hash(&"ab".repeat(31)),
+            Err("hex hash must be 64 characters, got 62".to_string())
+        );
+        assert_eq!(
+            parse_dataset_hash("z"),
+            Err("base58 hash must decode to 32 bytes, z doesn't".to_string())
+        );
+        assert_eq!(
+            parse_dataset_hash("not a hash"),
+            Err("not a hash is not a hex or base58 hash, or a file:<path>".to_string())
+        );
+        assert!(parse_dataset_hash("file:/nonexistent/dataset")
+            .unwrap_err()
+            .starts_with("failed to hash /nonexistent/dataset"));
+    }
+
+    #[test]
+    fn init_sets_its_fields_together() {
+        let reference = Pubkey::new_unique();
+        let fields = SetFields {
+            init: true,
+            key: Some([1; 32]),
+            value: Some(100),
+            share_limit: Some(3),
+            reference_data: Some(reference),
+            ..SetFields::default()
+        };
+
+        assert_eq!(
+            combine_set_fields(fields),
+            Ok(vec![Params::Init([1; 32], 100, 3, Some(reference))])
+        );
+    }
+
+    #[test]
+    fn init_requires_key_value_and_share_limit() {
+        let fields = SetFields {
+            init: true,
+            key: Some([1; 32]),
+            value: Some(100),
+            ..SetFields::default()
+        };
+
+        assert_eq!(
+            combine_set_fields(fields),
+            Err("--init requires --key, --value and --share-limit".to_string())
+        );
+    }
+
+    #[test]
+    fn init_fields_are_checked_before_sending() {
+        let dataset = Pubkey::new_unique();
+        let fields = SetFields {
+            init: true,
+            dataset: Some(dataset),
+            key: Some([1; 32]),
+            value: Some(100),
+            share_limit: Some(3),
+            reference_data: Some(dataset),
+            ..SetFields::default()
+        };
+        assert_eq!(
+            combine_set_fields(fields),
+            Err("a dataset cannot reference itself".to_string())
+        );
+
+        let fields = SetFields {
+            value: Some(0),
+            ..SetFields::default()
+        };
+        assert_eq!(
+            combine_set_fields(fields),
+            Err("a zero value reads back as no value".to_string())
+        );
+    }
+
+    #[test]
+    fn single_init_fields_are_set_alone() {
+        let cases = vec![
+            (
+                SetFields {
+                    key: Some([1; 32]),
+                    ..SetFields::default()
+                },
+                Params::Key([1; 32]),
+            ),
+            (
+                SetFields {
+                    value: Some(100),
+                    ..SetFields::default()
+                },
+                Params::Value(100),
+            ),
+            (
+                SetFields {
+                    share_limit: Some(3),
+                    ..SetFields::default()
+                },
+                Params::ShareLimit(3),
+            ),
+        ];
+
+        for (fields, params) in cases {
+            assert_eq!(combine_set_fields(fields), Ok(vec![params]));
+        }
+    }
+
+    #[test]
+    fn two_init_fields_cannot_be_combined() {
+        let fields = SetFields {
+            value: Some(100),
+            share_limit: Some(3),
+            ..SetFields::default()
+        };
+
+        let error = combine_set_fields(fields).unwrap_err();
+        assert!(error.starts_with("`--value` and `--share-limit` cannot be combined"));
+    }
+
+    #[test]
+    fn independent_setters_become_one_instruction_each() {
+        let fields = SetFields {
+            value: Some(100),
+            uri: Some("ipfs://data"),
+            clear: vec!["key"],
+            others: vec![Params::ReferralBps(250), Params::Resharable(true)],
+            ..SetFields::default()
+        };
+
+        assert_eq!(
+            combine_set_fields(fields),
+            Ok(vec![
+                Params::Clear(FieldMask::KEY),
+                Params::Value(100),
+                Params::Uri(b"ipfs://data".to_vec()),
+                Params::ReferralBps(250),
+                Params::Resharable(true),
+            ])
+        );
+    }
+
+    #[test]
+    fn cleared_fields_cannot_be_set() {
+        let fields = SetFields {
+            uri: Some("ipfs://data"),
+            clear: vec!["value", "uri"],
+            ..SetFields::default()
+        };
+
+        assert_eq!(
+            combine_set_fields(fields),
+            Err("`--clear uri` and `--uri` cannot be combined".to_string())
+        );
+    }
+
+    #[test]
+    fn cleared_fields_are_masked_together() {
+        let fields = SetFields {
+            clear: vec!["value", "ref-data"],
+            ..SetFields::default()
+        };
+
+        assert_eq!(
+            combine_set_fields(fields),
+            Ok(vec![Params::Clear(FieldMask::VALUE | FieldMask::REF_DATA)])
+        );
+    }
+
+    #[test]
+    fn mint_prices_are_parsed() {
+        let mint = Pubkey::new_unique();
+
+        assert_eq!(parse_mint_price(&format!("{}:2500", mint)), Ok((mint, 2500)));
+        assert!(parse_mint_price(&mint.to_string()).is_err());
+        assert!(parse_mint_price(&format!("{}:0", mint)).is_err());
+        assert!(parse_mint_price(&format!("{}:1.5", mint)).is_err());
+        assert!(parse_mint_price("mint:1").is_err());
+    }
+
+    #[test]
+    fn share_fees_of_zero_clear_the_fee() {
+        let mint = Pubkey::new_unique();
+
+        assert_eq!(parse_share_fee(&format!("{}:25", mint)), Ok((mint, 25)));
+        assert_eq!(parse_share_fee(&format!("{}:0", mint)), Ok((mint, 0)));
+        assert!(parse_share_fee(&mint.to_string()).is_err());
+        assert!(parse_share_fee(&format!("{}:-1", mint)).is_err());
+    }
+
+    #[test]
+    fn tags_are_null_padded_to_their_slot() {
+        let mut finance = [0; TAG_LEN];
+        finance[..7].copy_from_slice(b"finance");
+
+        assert_eq!(parse_tag("finance"), Ok(finance));
+        assert!(parse_tag(&"x".repeat(TAG_LEN)).is_ok());
+        assert!(parse_tag(&"x".repeat(TAG_LEN + 1)).is_err());
+        assert!(parse_tag("").is_err());
+    }
+
+    #[test]
+    fn removed_tags_are_sent_before_added_ones() {
+        let hash = "ab".repeat(32);
+        let matches = app().get_matches_from(vec![
+            "dn-me",
+            "set",
+            &hash,
+            "--tag",
+            "finance",
+            "--remove-tag",
+            "daily",
+            "--tag",
+            "weekly",
+        ]);
+        let args = matches.subcommand_matches("set").unwrap();
+
+        assert_eq!(
+            set_params_of(args, 0, Pubkey::new_unique()),
+            Ok(vec![
+                Params::RemoveTag(parse_tag("daily").unwrap()),
+                Params::AddTag(parse_tag("finance").unwrap()),
+                Params::AddTag(parse_tag("weekly").unwrap()),
+            ])
+        );
+    }
+
+    #[test]
+    fn derived_discounts_are_set_in_basis_points() {
+        let hash = "ab".repeat(32);
+        let matches = app().get_matches_from(vec![
+            "dn-me",
+            "set",
+            &hash,
+            "--derived-discount-bps",
+            "2500",
+        ]);
+        let args = matches.subcommand_matches("set").unwrap();
+
+        assert_eq!(
+            set_params_of(args, 0, Pubkey::new_unique()),
+            Ok(vec![Params::DerivedDiscountBps(2_500)])
+        );
+    }
+
+    /// Name of each `Params` variant, which stops compiling when a variant
+    /// is added until `set_params_round_trip_through_the_program` sets it
+    fn param_name(params: &Params) -> &'static str {
+        match params {
+            Params::Init(..) => "Init",
+            Params::Key(_) => "Key",
+            Params::Value(_) => "Value",
+            Params::ShareLimit(_) => "ShareLimit",
+            Params::ReferenceData(_) => "ReferenceData",
+            Params::ReferralBps(_) => "ReferralBps",
+            Params::AccessDuration(_) => "AccessDuration",
+            Params::Uri(_) => "Uri",
+            Params::Size(_) => "Size",
+            Params::ContentType(_) => "ContentType",
+            Params::Clear(_) => "Clear",
+            Params::Resharable(_) => "Resharable",
+            Params::MaxShareDepth(_) => "MaxShareDepth",
+            Params::Tiers(_) => "Tiers",
+            Params::MaxHolders(_) => "MaxHolders",
+            Params::SetPrice(..) => "SetPrice",
+            Params::RemovePrice(_) => "RemovePrice",
+            Params::SaleWindow { .. } => "SaleWindow",
+            Params::PruneBounty(_) => "PruneBounty",
+            Params::ShareFee { .. } => "ShareFee",
+            Params::AddTag(_) => "AddTag",
+            Params::RemoveTag(_) => "RemoveTag",
+            Params::DerivedDiscountBps(_) => "DerivedDiscountBps",
+        }
+    }
+
+    #[test]
+    fn set_params_round_trip_through_the_program() {
+        let hash = "ab".repeat(32);
+        let key = Hash::new_from_array([0xcd; 32]).to_string();
+        let reference = Pubkey::new_unique().to_string();
+        let mint = Pubkey::new_unique().to_string();
+        let price = format!("{}:9", mint);
+        let share_fee = format!("{}:12", mint);
+        let commands: Vec<Vec<&str>> = vec![
+            vec![
+                "--init",
+                "--key",
+                &key,
+                "--value",
+                "1",
+                "--share-limit",
+                "2",
+                "--ref-data",
+                &reference,
+            ],
+            vec!["--key", &key],
+            vec!["--value", "3"],
+            vec!["--share-limit", "4"],
+            vec!["--ref-data", &reference],
+            vec!["--referral-bps", "500"],
+            vec!["--access-duration", "60"],
+            vec!["--uri", "https://example.com/data.csv"],
+            vec!["--size", "5"],
+            vec!["--content-type", "text/csv"],
+            vec!["--clear", "key,uri"],
+            vec!["--resharable", "true"],
+            vec!["--max-share-depth", "6"],
+            vec!["--tier", "10:7", "--tier", "20:6"],
+            vec!["--max-holders", "8"],
+            vec!["--price", &price],
+            vec!["--remove-price", &mint],
+            vec![
+                "--sale-start",
+                "1970-01-01T00:00:10Z",
+                "--sale-end",
+                "1970-01-01T00:00:11Z",
+            ],
+            vec!["--prune-bounty", "11"],
+            vec!["--share-fee", &share_fee],
+            vec!["--tag", "finance"],
+            vec!["--remove-tag", "daily"],
+            vec!["--derived-discount-bps", "2500"],
+        ];
+
+        let mut names = std::collections::BTreeSet::new();
+        for command in commands {
+            let matches = app().get_matches_from(
+                ["dn-me", "set", hash.as_str()]
+                    .iter()
+                    .chain(command.iter())
+                    .copied(),
+            );
+            let args = matches.subcommand_matches("set").unwrap();
+            for params in set_params_of(args, 0, Pubkey::new_unique()).unwrap() {
+                names.insert(param_name(&params));
+                let instruction = DataNexusInstruction::SetDataParams {
+                    hash: [0xab; 32],
+                    params,
+                };
+
+                assert_eq!(
+                    DataNexusInstruction::unpack(&instruction.pack()),
+                    Ok(instruction)
+                );
+            }
+        }
+        // One per arm of `param_name`
+        assert_eq!(names.len(), 23);
+    }
+
+    #[test]
+    fn timestamps_are_parsed_as_unix_time() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(
+            parse_timestamp("2024-01-01T01:00:00+01:00"),
+            Ok(1_704_067_200)
+        );
+        assert!(parse_timestamp("2024-01-01").is_err());
+        assert!(parse_timestamp("1704067200").is_err());
+    }
+
+    #[test]
+    fn durations_are_parsed_as_whole_seconds() {
+        assert_eq!(parse_duration("7d"), Ok(604_800));
+        assert_eq!(parse_duration("12h 30m"), Ok(45_000));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("500ms").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("7").is_err());
+    }
+
+    #[test]
+    fn setting_nothing_fails() {
+        assert_eq!(
+            combine_set_fields(SetFields::default()),
+            Err("nothing to set, pass a field to set, --clear or --init".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_subcommands_suggest_the_nearest_name() {
+        assert_eq!(edit_distance("purchse", "purchase"), 1);
+        assert_eq!(edit_distance("", "status"), 6);
+
+        let commands = subcommands();
+        let names: Vec<&str> = commands.iter().map(App::get_name).collect();
+        assert_eq!(nearest_name("purchse-acess", &names), Some("purchase_access"));
+        assert_eq!(nearest_name("close_datset", &names), Some("close-dataset"));
+        assert_eq!(nearest_name("completion", &names), Some("completions"));
+    }
+
+    #[test]
+    fn bash_completion_matches_the_snapshot() {
+        let mut script = Vec::new();
+        app().gen_completions_to(BIN_NAME, Shell::Bash, &mut script);
+
+        assert_eq!(
+            String::from_utf8(script).unwrap(),
+            include_str!("../snapshots/completions.bash")
+        );
+    }
 }
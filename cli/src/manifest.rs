@@ -0,0 +1,250 @@
+use std::str::FromStr;
+
+use datanexus_utils::packer::{PackError, PackItem, TransactionPacker};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::Hash, instruction::Instruction, pubkey::Pubkey};
+
+/// Operations run by `batch`, read from a JSON manifest
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// Registers the dataset hashed from `file`
+    Register {
+        file: String,
+        value: u64,
+        #[serde(default)]
+        share_limit: u16,
+        key: Option<String>,
+        mint: Option<String>,
+        uri: Option<String>,
+    },
+    /// Updates the parameters of a registered dataset, one instruction per
+    /// field that is set
+    Set {
+        hash: String,
+        value: Option<u64>,
+        share_limit: Option<u16>,
+        referral_bps: Option<u16>,
+        access_duration: Option<i64>,
+        uri: Option<String>,
+        size: Option<u64>,
+        content_type: Option<String>,
+    },
+    /// Purchases an access grant to a dataset, paying from `token_account`
+    Grant {
+        hash: String,
+        token_account: String,
+        amount: u64,
+        referrer: Option<String>,
+    },
+    /// Shares access to a dataset with each of `recipients`
+    Share {
+        hash: String,
+        recipients: Vec<String>,
+    },
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Register { .. } => "register",
+            Operation::Set { .. } => "set",
+            Operation::Grant { .. } => "grant",
+            Operation::Share { .. } => "share",
+        }
+    }
+}
+
+pub fn parse_hash(hash: &str) -> Result<[u8; 32], String> {
+    Hash::from_str(hash)
+        .map(|hash| hash.to_bytes())
+        .map_err(|_| format!("invalid hash {}", hash))
+}
+
+pub fn parse_pubkey(pubkey: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(pubkey).map_err(|_| format!("invalid pubkey {}", pubkey))
+}
+
+/// A validated manifest row and the instructions that carry it out
+pub struct PlannedRow {
+    pub row: usize,
+    pub op: &'static str,
+    pub instructions: Vec<Instruction>,
+}
+
+/// Outcome of a manifest row, the report `batch` prints
+#[derive(Debug, Serialize)]
+pub struct RowResult {
+    pub row: usize,
+    pub op: &'static str,
+    pub status: &'static str,
+    pub transaction: Option<usize>,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Groups rows into transactions with `TransactionPacker`, returning the
+/// indices into `rows` of each group
+///
+/// Room is left for `prefix`, the compute budget instructions each
+/// transaction is sent with, and for `suffix`, the memo it ends with.
+///
+/// A row's instructions always land in the same transaction so each row
+/// succeeds or fails as a whole. Fails with the manifest row number of a row
+/// that doesn't fit in a transaction on its own.
+pub fn pack(
+    rows: &[PlannedRow],
+    prefix: &[Instruction],
+    suffix: &[Instruction],
+    fee_payer: &Pubkey,
+) -> Result<Vec<Vec<usize>>, usize> {
+    let items: Vec<PackItem> = rows
+        .iter()
+        .map(|row| PackItem::new(row.instructions.clone()))
+        .collect();
+
+    let packed = TransactionPacker::new(*fee_payer)
+        .with_prefix(prefix.to_vec())
+        .with_suffix(suffix.to_vec())
+        .pack(&items);
+
+    match packed {
+        Ok(transactions) => Ok(transactions
+            .into_iter()
+            .map(|transaction| transaction.items)
+            .collect()),
+        Err(PackError::TooLarge { item, .. }) => Err(rows[item].row),
+        // Rows declare no created accounts, so they never form a cycle
+        Err(PackError::DependencyCycle(items)) => Err(rows[items[0]].row),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datanexus_utils::packer::{
+        transaction_size, DEFAULT_TRANSACTION_UNITS, ESTIMATED_UNITS_PER_INSTRUCTION,
+    };
+    use solana_sdk::packet::PACKET_DATA_SIZE;
+
+    use super::*;
+
+    /// An instruction carrying `data_len` bytes of data
+    fn instruction(data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &vec![0; data_len], vec![])
+    }
+
+    fn row(row: usize, instructions: Vec<Instruction>) -> PlannedRow {
+        PlannedRow {
+            row,
+            op: "set",
+            instructions,
+        }
+    }
+
+    #[test]
+    fn small_rows_share_a_transaction() {
+        let rows = [row(1, vec![instruction(8)]), row(2, vec![instruction(8)])];
+
+        assert_eq!(
+            pack(&rows, &[], &[], &Pubkey::new_unique()),
+            Ok(vec![vec![0, 1]])
+        );
+    }
+
+    #[test]
+    fn transactions_stay_under_the_compute_limit() {
+        let per_transaction =
+            (DEFAULT_TRANSACTION_UNITS / ESTIMATED_UNITS_PER_INSTRUCTION) as usize;
+        let rows: Vec<_> = (0..=per_transaction)
+            .map(|index| row(index + 1, vec![instruction(8)]))
+            .collect();
+
+        let groups = pack(&rows, &[], &[], &Pubkey::new_unique()).unwrap();
+
+        assert_eq!(
+            groups,
+            [(0..per_transaction).collect(), vec![per_transaction]]
+        );
+    }
+
+    #[test]
+    fn transactions_stay_under_the_packet_size() {
+        let fee_payer = Pubkey::new_unique();
+        let rows: Vec<_> = (0..3)
+            .map(|index| row(index + 1, vec![instruction(PACKET_DATA_SIZE / 3)]))
+            .collect();
+
+        let groups = pack(&rows, &[], &[], &fee_payer).unwrap();
+
+        assert_eq!(groups, [vec![0, 1], vec![2]]);
+        for group in groups {
+            let instructions: Vec<_> = group
+                .iter()
+                .flat_map(|index| rows[*index].instructions.iter().cloned())
+                .collect();
+            assert!(transaction_size(&instructions, &fee_payer) <= PACKET_DATA_SIZE);
+        }
+    }
+
+    #[test]
+    fn rows_are_never_split() {
+        let rows = [
+            row(1, vec![instruction(8), instruction(8), instruction(8)]),
+            row(2, vec![instruction(8), instruction(8), instruction(8)]),
+        ];
+
+        assert_eq!(
+            pack(&rows, &[], &[], &Pubkey::new_unique()),
+            Ok(vec![vec![0], vec![1]])
+        );
+    }
+
+    #[test]
+    fn the_prefix_counts_towards_the_size() {
+        let rows = [row(1, vec![instruction(PACKET_DATA_SIZE / 2)])];
+
+        assert_eq!(
+            pack(&rows, &[], &[], &Pubkey::new_unique()),
+            Ok(vec![vec![0]])
+        );
+        assert_eq!(
+            pack(
+                &rows,
+                &[instruction(PACKET_DATA_SIZE / 2)],
+                &[],
+                &Pubkey::new_unique()
+            ),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn the_suffix_counts_towards_the_size() {
+        let rows = [row(1, vec![instruction(PACKET_DATA_SIZE / 2)])];
+
+        assert_eq!(
+            pack(
+                &rows,
+                &[],
+                &[instruction(PACKET_DATA_SIZE / 2)],
+                &Pubkey::new_unique()
+            ),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn rows_too_large_for_a_transaction_are_reported() {
+        let rows = [
+            row(1, vec![instruction(8)]),
+            row(7, vec![instruction(PACKET_DATA_SIZE)]),
+        ];
+
+        assert_eq!(pack(&rows, &[], &[], &Pubkey::new_unique()), Err(7));
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use solana_sdk::instruction::Instruction;
+
+/// Longest memo accepted, in bytes. Leaves a purchase with a referrer, a
+/// receipt, compute budget instructions and a nonce room within the packet
+/// limit, transactions are still checked once built
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Checks a memo is 1 to `MAX_MEMO_LEN` bytes
+pub fn parse_memo(memo: &str) -> Result<String, String> {
+    if memo.is_empty() || memo.len() > MAX_MEMO_LEN {
+        return Err(format!(
+            "memo is {} bytes, memos are 1 to {} bytes",
+            memo.len(),
+            MAX_MEMO_LEN
+        ));
+    }
+    Ok(memo.to_string())
+}
+
+/// Reads the memo held in the file at `path`, which must be UTF-8. A
+/// trailing newline is not part of the memo
+pub fn read_memo_file(path: &str) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let memo =
+        String::from_utf8(bytes).map_err(|e| format!("memo in {} is not UTF-8: {}", path, e))?;
+    parse_memo(memo.trim_end_matches(&['\r', '\n'][..]))
+}
+
+/// `instructions` followed by an spl-memo instruction carrying `memo`, when
+/// one is set. The memo needs no signers
+pub fn append_memo(memo: Option<&str>, instructions: &[Instruction]) -> Vec<Instruction> {
+    instructions
+        .iter()
+        .cloned()
+        .chain(memo.map(|memo| spl_memo::build_memo(memo.as_bytes(), &[])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn instruction() -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![])
+    }
+
+    #[test]
+    fn the_memo_comes_last() {
+        let instructions = [instruction(), instruction()];
+
+        let memoed = append_memo(Some("invoice 42"), &instructions);
+
+        assert_eq!(memoed.len(), 3);
+        assert_eq!(memoed[..2], instructions);
+        assert_eq!(memoed[2].program_id, spl_memo::id());
+        assert_eq!(memoed[2].data, b"invoice 42");
+        assert!(memoed[2].accounts.is_empty());
+        assert_eq!(append_memo(None, &instructions), instructions);
+    }
+
+    #[test]
+    fn memos_are_length_capped() {
+        assert!(parse_memo(&"a".repeat(MAX_MEMO_LEN)).is_ok());
+        assert!(parse_memo(&"a".repeat(MAX_MEMO_LEN + 1)).is_err());
+        assert!(parse_memo("").is_err());
+        // Capped in bytes, not characters
+        assert!(parse_memo(&"é".repeat(MAX_MEMO_LEN / 2 + 1)).is_err());
+    }
+
+    #[test]
+    fn memo_files_must_be_utf8() {
+        let path = std::env::temp_dir().join(format!("memo-{}", Pubkey::new_unique()));
+        let path_str = path.to_str().unwrap();
+
+        fs::write(&path, "invoice 42\n").unwrap();
+        assert_eq!(read_memo_file(path_str).unwrap(), "invoice 42");
+
+        fs::write(&path, [0x69, 0xff, 0x6e]).unwrap();
+        assert!(read_memo_file(path_str).unwrap_err().contains("not UTF-8"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
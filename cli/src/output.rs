@@ -0,0 +1,633 @@
+use datanexus::{
+    events::PurchaseEvent,
+    state::{AccessInfo, AccessState, AccountIndex, DatasetState, IndexEntry},
+};
+use datanexus_utils::format_ui_amount;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+
+use crate::{error::CliError, manifest::RowResult};
+
+/// How command results are printed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn from_arg(arg: Option<&str>) -> Self {
+        match arg {
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// Result of a command, serialized as is for JSON output
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum CommandOutput {
+    /// A transaction that was sent and confirmed
+    Transaction {
+        signature: String,
+        events: Vec<String>,
+    },
+    /// A transaction simulated under `--dry-run`
+    Simulation {
+        instructions: Vec<Value>,
+        units_consumed: u64,
+        logs: Vec<String>,
+    },
+    /// Signatures of a transaction signed under `--sign-only`
+    SignOnly(Value),
+    /// A new account, with the lamports it took to make it rent exempt
+    /// unless the command ran offline under `--sign-only`
+    Created {
+        account: &'static str,
+        address: String,
+        rent: Option<u64>,
+        transaction: Box<CommandOutput>,
+    },
+    Registered {
+        hash: String,
+        dataset: String,
+        transaction: Box<CommandOutput>,
+    },
+    Closed {
+        dataset: String,
+        transaction: Box<CommandOutput>,
+    },
+    Renewed {
+        access_account: String,
+        transaction: Box<CommandOutput>,
+    },
+    /// A purchase read back once confirmed, `amount` is what left the
+    /// buyer's token account in whole tokens, `referral` the part of it the
+    /// referrer received and `fee` the lamports the fee payer was charged.
+    /// `memo` is the one sent with `--memo`
+    Purchased {
+        hash: String,
+        access_account: String,
+        created: Vec<String>,
+        amount: String,
+        referral: Option<String>,
+        mint: String,
+        destination: Option<String>,
+        fee: u64,
+        slot: u64,
+        block_time: Option<i64>,
+        memo: Option<String>,
+        transaction: Box<CommandOutput>,
+    },
+    /// A share read back once confirmed, with the shares the sharer has left
+    /// unless it holds no access entry of its own
+    Shared {
+        hash: String,
+        recipients: Vec<String>,
+        /// Recipients the share waits on to approve it
+        pending: Vec<String>,
+        remaining_shares: Option<u16>,
+        fee: u64,
+        slot: u64,
+        block_time: Option<i64>,
+        memo: Option<String>,
+        transaction: Box<CommandOutput>,
+    },
+    Withdrawn {
+        vault: String,
+        amount: u64,
+        transaction: Box<CommandOutput>,
+    },
+    /// A dataset's owner and pending owner after an ownership transfer step
+    Ownership {
+        dataset: String,
+        owner: String,
+        pending_owner: Option<String>,
+        transaction: Box<CommandOutput>,
+    },
+    Stats {
+        dataset: String,
+        total_revenue: u64,
+        purchase_count: u32,
+        share_count: u32,
+    },
+    /// A decoded account
+    State(Value),
+    /// Undecoded account data as base64
+    Raw {
+        address: String,
+        data: String,
+    },
+    Datasets(Vec<Value>),
+    /// Datasets recorded in an index, read without fetching them
+    IndexEntries(Vec<Value>),
+    Grants(Vec<Value>),
+    AccessStatus {
+        hash: String,
+        authority: String,
+        access_account: String,
+        valid: bool,
+        grant: Option<Value>,
+    },
+    /// A signed claim of access for a gateway, as base64
+    Attestation {
+        hash: String,
+        authority: String,
+        slot: u64,
+        attestation: String,
+    },
+    /// Authorities a grant was shared through, from the holder back to the
+    /// purchaser or owner
+    ShareChain {
+        hash: String,
+        authority: String,
+        chain: Vec<String>,
+    },
+    /// Per row report of a manifest run
+    Batch(Vec<RowResult>),
+    /// Transactions of a command that needed more than one, in the order
+    /// they were sent
+    Steps(Vec<CommandOutput>),
+    /// Accounts brought to their newest layout, and the transactions that
+    /// migrated them
+    Migrated {
+        accounts: Vec<String>,
+        transactions: Vec<CommandOutput>,
+    },
+    /// A snapshot written by `export`, with the number of accounts it holds
+    Exported {
+        authority: String,
+        outfile: String,
+        accounts: usize,
+    },
+    /// Fields of a snapshot that no longer match the accounts on chain
+    Verified {
+        snapshot: String,
+        divergences: Vec<Value>,
+    },
+}
+
+impl CommandOutput {
+    /// Exit code of a command that succeeded, nonzero for an access check
+    /// without valid access, a batch with failed rows or a snapshot that
+    /// diverged so any of them can gate scripts
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandOutput::AccessStatus { valid: false, .. } => 1,
+            CommandOutput::Batch(rows) if rows.iter().any(|row| row.status == "failed") => 1,
+            CommandOutput::Verified { divergences, .. } if !divergences.is_empty() => 1,
+            _ => 0,
+        }
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn optional<T: ToString>(value: Option<T>) -> Value {
+    match value {
+        Some(value) => Value::String(value.to_string()),
+        None => Value::Null,
+    }
+}
+
+/// `state` in the JSON shape of its serde derives, led by the `type` and
+/// `address` every account is printed with
+fn account_json<T: Serialize>(kind: &str, address: &Pubkey, state: &T) -> Value {
+    let mut json = json!({ "type": kind, "address": address.to_string() });
+    if let (Value::Object(fields), Value::Object(state)) = (
+        &mut json,
+        serde_json::to_value(state).expect("state serializes to JSON"),
+    ) {
+        fields.extend(state);
+    }
+    json
+}
+
+/// Amounts are in whole tokens of a mint with `decimals` when given, in base
+/// units otherwise
+pub fn dataset_json(address: &Pubkey, dataset: &DatasetState, decimals: Option<u8>) -> Value {
+    let mut json = account_json("dataset", address, dataset);
+    if let Some(slots_remaining) = dataset.slots_remaining() {
+        json["slots_remaining"] = json!(slots_remaining);
+    }
+    // Vaults and mint prices are in their own mints, so those stay in base
+    // units
+    if let Some(decimals) = decimals {
+        if let Some(value) = dataset.value {
+            json["value"] = json!(format_ui_amount(value, decimals));
+        }
+        json["total_revenue"] = json!(format_ui_amount(dataset.total_revenue, decimals));
+        if let Some(tiers) = json["tiers"].as_array_mut() {
+            for (tier, entry) in dataset.tiers.iter().zip(tiers) {
+                entry["unit_price"] = json!(format_ui_amount(tier.unit_price, decimals));
+            }
+        }
+    }
+    json
+}
+
+pub fn access_info_json(access: &AccessInfo) -> Value {
+    serde_json::to_value(access).expect("grant serializes to JSON")
+}
+
+pub fn access_state_json(address: &Pubkey, access: &AccessState) -> Value {
+    account_json("access", address, access)
+}
+
+/// Legacy index entries have no hash
+pub fn index_entry_json(entry: &IndexEntry) -> Value {
+    let mut json = serde_json::to_value(entry).expect("index entry serializes to JSON");
+    if entry.hash == [0u8; 32] {
+        if let Value::Object(fields) = &mut json {
+            fields.remove("hash");
+        }
+    }
+    json
+}
+
+/// `names` holds a display name for each dataset in the index, in order
+pub fn index_json(address: &Pubkey, index: &AccountIndex, names: &[String]) -> Value {
+    let datasets = index
+        .datasets
+        .iter()
+        .flatten()
+        .zip(names)
+        .map(|(entry, name)| {
+            let mut entry = index_entry_json(entry);
+            entry["name"] = json!(name);
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    let mut json = account_json("index", address, index);
+    json["capacity"] = json!(index.capacity());
+    json["datasets"] = json!(datasets);
+    json
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, display_value(value)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        other => other.to_string(),
+    }
+}
+
+/// Prints one field per line, array items indented below their count
+fn print_fields(value: &Value) {
+    if let Value::Object(fields) = value {
+        for (name, value) in fields {
+            match value {
+                Value::Array(items) => {
+                    println!("{}: {}", name, items.len());
+                    for item in items {
+                        println!("  {}", display_value(item));
+                    }
+                }
+                value => println!("{}: {}", name, display_value(value)),
+            }
+        }
+    } else {
+        println!("{}", display_value(value));
+    }
+}
+
+/// Prints `rows` as a table of the given `(header, field, width)` columns
+fn print_table(rows: &[Value], columns: &[(&str, &str, usize)]) {
+    let header: Vec<_> = columns
+        .iter()
+        .map(|(header, _, width)| format!("{:<width$}", header, width = width))
+        .collect();
+    println!("{}", header.join("  "));
+
+    for row in rows {
+        let cells: Vec<_> = columns
+            .iter()
+            .map(|(_, field, width)| {
+                format!("{:<width$}", display_value(&row[*field]), width = width)
+            })
+            .collect();
+        println!("{}", cells.join("  "));
+    }
+}
+
+fn print_text(output: &CommandOutput) {
+    match output {
+        CommandOutput::Transaction { signature, events } => {
+            println!("Transaction Signature: {}", signature);
+            for event in events {
+                println!("Event: {}", event);
+            }
+        }
+        CommandOutput::Simulation {
+            instructions,
+            units_consumed,
+            logs,
+        } => {
+            for (index, instruction) in instructions.iter().enumerate() {
+                println!("Instruction {}: {}", index, display_value(&instruction["program_id"]));
+                if let Value::Array(accounts) = &instruction["accounts"] {
+                    for meta in accounts {
+                        println!("  {}", display_value(meta));
+                    }
+                }
+                println!("  data: {}", display_value(&instruction["data"]));
+            }
+            println!("Compute Units Consumed: {}", units_consumed);
+            println!("Logs:");
+            for log in logs {
+                println!("  {}", log);
+            }
+        }
+        CommandOutput::SignOnly(value) | CommandOutput::State(value) => print_fields(value),
+        CommandOutput::Created {
+            account,
+            address,
+            rent,
+            transaction,
+        } => {
+            println!("Created {}: {}", account, address);
+            if let Some(rent) = rent {
+                println!("Rent: {} SOL ({} lamports)", lamports_to_sol(*rent), rent);
+            }
+            print_text(transaction);
+        }
+        CommandOutput::Registered {
+            hash,
+            dataset,
+            transaction,
+        } => {
+            println!("Hash: {}", hash);
+            println!("Dataset Address: {}", dataset);
+            print_text(transaction);
+        }
+        CommandOutput::Closed {
+            dataset,
+            transaction,
+        } => {
+            println!("Dataset Account Closed: {}", dataset);
+            print_text(transaction);
+        }
+        CommandOutput::Renewed {
+            access_account,
+            transaction,
+        } => {
+            println!("Access Renewed: {}", access_account);
+            print_text(transaction);
+        }
+        CommandOutput::Purchased {
+            hash,
+            access_account,
+            created,
+            amount,
+            referral,
+            mint,
+            destination,
+            fee,
+            slot,
+            block_time,
+            memo,
+            transaction,
+        } => {
+            println!("Hash: {}", hash);
+            println!("Access Account: {}", access_account);
+            for account in created {
+                println!("Created: {}", account);
+            }
+            println!("Paid: {} of {}", amount, mint);
+            if let Some(referral) = referral {
+                println!("Referral: {}", referral);
+            }
+            println!("Paid To: {}", destination.as_deref().unwrap_or("-"));
+            println!("Fee: {} SOL ({} lamports)", lamports_to_sol(*fee), fee);
+            println!("Slot: {}", slot);
+            println!("Block Time: {}", display_value(&optional(*block_time)));
+            if let Some(memo) = memo {
+                println!("Memo: {}", memo);
+            }
+            print_text(transaction);
+        }
+        CommandOutput::Shared {
+            hash,
+            recipients,
+            pending,
+            remaining_shares,
+            fee,
+            slot,
+            block_time,
+            memo,
+            transaction,
+        } => {
+            println!("Hash: {}", hash);
+            for recipient in recipients {
+                println!("Shared With: {}", recipient);
+            }
+            for recipient in pending {
+                println!("Awaiting Approval: {}", recipient);
+            }
+            println!(
+                "Remaining Shares: {}",
+                display_value(&optional(*remaining_shares))
+            );
+            println!("Fee: {} SOL ({} lamports)", lamports_to_sol(*fee), fee);
+            println!("Slot: {}", slot);
+            println!("Block Time: {}", display_value(&optional(*block_time)));
+            if let Some(memo) = memo {
+                println!("Memo: {}", memo);
+            }
+            print_text(transaction);
+        }
+        CommandOutput::Withdrawn {
+            vault,
+            amount,
+            transaction,
+        } => {
+            println!("Withdrawn: {} from {}", amount, vault);
+            print_text(transaction);
+        }
+        CommandOutput::Ownership {
+            dataset,
+            owner,
+            pending_owner,
+            transaction,
+        } => {
+            println!("Dataset: {}", dataset);
+            println!("Owner: {}", owner);
+            if let Some(pending_owner) = pending_owner {
+                println!("Pending Owner: {}", pending_owner);
+            }
+            print_text(transaction);
+        }
+        CommandOutput::Stats {
+            dataset,
+            total_revenue,
+            purchase_count,
+            share_count,
+        } => {
+            println!("Dataset: {}", dataset);
+            println!("Total Revenue: {}", total_revenue);
+            println!("Purchase Count: {}", purchase_count);
+            println!("Share Count: {}", share_count);
+        }
+        CommandOutput::Raw { data, .. } => println!("{}", data),
+        CommandOutput::Steps(steps) => {
+            for (index, step) in steps.iter().enumerate() {
+                println!("Step {} of {}", index + 1, steps.len());
+                print_text(step);
+            }
+        }
+        CommandOutput::Migrated {
+            accounts,
+            transactions,
+        } => {
+            if accounts.is_empty() {
+                println!("No accounts to migrate");
+            }
+            for account in accounts {
+                println!("Migrated: {}", account);
+            }
+            for transaction in transactions {
+                print_text(transaction);
+            }
+        }
+        CommandOutput::Exported {
+            authority,
+            outfile,
+            accounts,
+        } => println!("Exported {} accounts of {} to {}", accounts, authority, outfile),
+        CommandOutput::Verified {
+            snapshot,
+            divergences,
+        } => {
+            if divergences.is_empty() {
+                println!("{} matches the accounts on chain", snapshot);
+            }
+            for divergence in divergences {
+                println!("{}", display_value(&divergence["path"]));
+                println!("  snapshot: {}", display_value(&divergence["expected"]));
+                println!("  on chain: {}", display_value(&divergence["actual"]));
+            }
+        }
+        CommandOutput::Datasets(datasets) => print_table(
+            datasets,
+            &[
+                ("Address", "address", 44),
+                ("Hash", "hash", 64),
+                ("Value", "value", 20),
+                ("Share Limit", "share_limit", 11),
+                ("Purchases", "purchase_count", 9),
+            ],
+        ),
+        CommandOutput::AccessStatus {
+            hash,
+            authority,
+            valid,
+            grant,
+            ..
+        } => match grant {
+            Some(grant) if *valid => {
+                println!("Access: valid");
+                print_fields(grant);
+            }
+            Some(grant) => {
+                println!("Access: expired");
+                print_fields(grant);
+                println!("Renew with: dn-me renew {} --token-account <TOKEN ACCOUNT>", hash);
+            }
+            None => {
+                println!("No access for {}", authority);
+                println!(
+                    "Purchase with: dn-me purchase_access {} <AMOUNT> --token-account <TOKEN ACCOUNT>",
+                    hash
+                );
+            }
+        },
+        CommandOutput::Attestation { attestation, .. } => println!("{}", attestation),
+        CommandOutput::ShareChain { chain, .. } => {
+            let rows: Vec<Value> = chain
+                .iter()
+                .enumerate()
+                .map(|(hop, authority)| json!({ "hop": hop, "authority": authority }))
+                .collect();
+            print_table(&rows, &[("Hop", "hop", 5), ("Authority", "authority", 44)]);
+        }
+        CommandOutput::Batch(rows) => {
+            let rows: Vec<Value> = rows
+                .iter()
+                .map(|row| serde_json::to_value(row).unwrap())
+                .collect();
+            print_table(
+                &rows,
+                &[
+                    ("Row", "row", 5),
+                    ("Op", "op", 8),
+                    ("Status", "status", 7),
+                    ("Tx", "transaction", 4),
+                    ("Signature", "signature", 88),
+                    ("Error", "error", 0),
+                ],
+            )
+        }
+        CommandOutput::IndexEntries(entries) => print_table(
+            entries,
+            &[("Hash", "hash", 64), ("Dataset", "dataset", 44)],
+        ),
+        CommandOutput::Grants(grants) => print_table(
+            grants,
+            &[
+                ("Hash", "hash", 64),
+                ("Shared From", "shared_from", 44),
+                ("Share Quota", "share_quota", 11),
+                ("Shares Used", "shares_used", 11),
+                ("Expires At", "expires_at", 20),
+            ],
+        ),
+    }
+}
+
+pub fn print(format: OutputFormat, output: &CommandOutput) {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(output).unwrap()),
+        OutputFormat::Text => print_text(output),
+    }
+}
+
+/// Prints a purchase reported by `watch`, one line per purchase in either
+/// format so the output can be streamed
+pub fn print_purchase(format: OutputFormat, purchase: &PurchaseEvent) {
+    let purchase = json!({
+        "slot": purchase.slot,
+        "buyer": purchase.buyer.to_string(),
+        "amount": purchase.amount,
+        "mint": purchase.mint.to_string(),
+    });
+
+    match format {
+        OutputFormat::Json => println!("{}", purchase),
+        OutputFormat::Text => println!("{}", display_value(&purchase)),
+    }
+}
+
+/// Prints `error` to stderr, as a JSON object for JSON output
+pub fn print_error(format: OutputFormat, error: &CliError) {
+    match format {
+        OutputFormat::Json => eprintln!("{}", error.to_json()),
+        OutputFormat::Text => {
+            eprintln!("error: {}", error);
+            if let CliError::Program { logs, .. } = error {
+                for log in logs {
+                    eprintln!("  {}", log);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use datanexus::{
+    accounts::InitAssociatedAccessAccounts,
+    instruction::{init_account, init_associated_access, AccountType},
+};
+use datanexus_utils::{
+    find_access_address_with_program_id, find_associated_access_address_with_program_id,
+    find_dataset_address_with_program_id, find_owner_address_with_program_id,
+};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_program};
+
+use crate::{error::CliError, output::hex};
+
+/// An account a command reads or writes, which has to exist before the
+/// command is sent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Requirement {
+    OwnerIndex(Pubkey),
+    AccessIndex(Pubkey),
+    /// The dataset registered under a hash
+    Dataset([u8; 32]),
+    AssociatedAccess {
+        authority: Pubkey,
+        hash: [u8; 32],
+    },
+    TokenAccount(Pubkey),
+}
+
+impl Requirement {
+    pub fn address(&self, program_id: &Pubkey) -> Pubkey {
+        match *self {
+            Requirement::OwnerIndex(authority) => {
+                find_owner_address_with_program_id(program_id, authority).0
+            }
+            Requirement::AccessIndex(authority) => {
+                find_access_address_with_program_id(program_id, authority).0
+            }
+            Requirement::Dataset(hash) => find_dataset_address_with_program_id(program_id, &hash).0,
+            Requirement::AssociatedAccess { authority, hash } => {
+                find_associated_access_address_with_program_id(
+                    program_id,
+                    authority,
+                    find_dataset_address_with_program_id(program_id, &hash).0,
+                )
+                .0
+            }
+            Requirement::TokenAccount(address) => address,
+        }
+    }
+
+    /// What is missing and how to create it
+    fn missing(&self, program_id: &Pubkey) -> String {
+        let address = self.address(program_id);
+        match self {
+            Requirement::OwnerIndex(authority) => format!(
+                "owner index {} of {} does not exist, create it with \
+                 `dn-me create owner --authority {}`",
+                address, authority, authority
+            ),
+            Requirement::AccessIndex(authority) => format!(
+                "access index {} of {} does not exist, create it with \
+                 `dn-me create access --authority {}`",
+                address, authority, authority
+            ),
+            Requirement::Dataset(hash) => {
+                format!("no dataset {} is registered under {}", address, hex(hash))
+            }
+            Requirement::AssociatedAccess { authority, hash } => format!(
+                "associated access account {} of {} does not exist, {} has to create it \
+                 with `dn-me create access --hash {}`",
+                address,
+                authority,
+                authority,
+                hex(hash)
+            ),
+            Requirement::TokenAccount(address) => {
+                format!("token account {} does not exist", address)
+            }
+        }
+    }
+
+    /// The instruction creating the account paid for by `payer`, `None`
+    /// when it can't be created in a transaction `authority` signs
+    fn create(
+        &self,
+        program_id: &Pubkey,
+        payer: Pubkey,
+        authority: Pubkey,
+    ) -> Result<Option<Instruction>, CliError> {
+        let address = self.address(program_id);
+        let instruction = match *self {
+            // Indexes can be created for any authority, without its signature
+            Requirement::OwnerIndex(owner) => init_account(
+                *program_id,
+                Some(payer),
+                owner,
+                address,
+                None,
+                system_program::id(),
+                AccountType::DatasetIndex,
+            )?,
+            Requirement::AccessIndex(holder) => init_account(
+                *program_id,
+                Some(payer),
+                holder,
+                address,
+                None,
+                system_program::id(),
+                AccountType::AccessIndex,
+            )?,
+            Requirement::AssociatedAccess {
+                authority: holder,
+                hash,
+            } if holder == authority => init_associated_access(
+                *program_id,
+                InitAssociatedAccessAccounts {
+                    payer,
+                    authority,
+                    access_index: find_access_address_with_program_id(program_id, authority).0,
+                    associated_access_account: address,
+                    dataset_account: find_dataset_address_with_program_id(program_id, &hash).0,
+                    system_program: system_program::id(),
+                },
+                hash,
+            )?,
+            _ => return Ok(None),
+        };
+        Ok(Some(instruction))
+    }
+}
+
+/// The instructions creating the accounts of `requirements` that `present`
+/// marks missing, in the order they are required, `payer` paying for them
+/// and `authority` signing
+///
+/// Without `auto_create`, or when any missing account can't be created this
+/// way, fails listing every missing account and how to create it instead.
+pub fn plan_missing(
+    program_id: &Pubkey,
+    payer: Pubkey,
+    authority: Pubkey,
+    requirements: &[Requirement],
+    present: &[bool],
+    auto_create: bool,
+) -> Result<Vec<Instruction>, CliError> {
+    let missing: Vec<_> = requirements
+        .iter()
+        .zip(present)
+        .filter(|(_, present)| !**present)
+        .map(|(requirement, _)| requirement)
+        .collect();
+
+    let mut instructions = vec![];
+    let mut unresolved = vec![];
+    for requirement in missing {
+        match requirement.create(program_id, payer, authority)? {
+            Some(instruction) if auto_create => instructions.push(instruction),
+            Some(_) => unresolved.push(format!(
+                "{} (or pass --auto-create)",
+                requirement.missing(program_id)
+            )),
+            None => unresolved.push(requirement.missing(program_id)),
+        }
+    }
+
+    if !unresolved.is_empty() {
+        return Err(CliError::User(unresolved.join("\n")));
+    }
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: [u8; 32] = [1; 32];
+
+    fn requirements(authority: Pubkey, other: Pubkey) -> Vec<Requirement> {
+        vec![
+            Requirement::OwnerIndex(authority),
+            Requirement::AccessIndex(other),
+            Requirement::Dataset(HASH),
+            Requirement::AssociatedAccess {
+                authority,
+                hash: HASH,
+            },
+            Requirement::AssociatedAccess {
+                authority: other,
+                hash: HASH,
+            },
+            Requirement::TokenAccount(Pubkey::new_unique()),
+        ]
+    }
+
+    /// Whether the creation of each requirement can be prepended for
+    /// `authority`
+    const CREATABLE: [bool; 6] = [true, true, false, true, false, false];
+
+    #[test]
+    fn every_combination_of_missing_accounts() {
+        let program_id = datanexus::id();
+        let (payer, authority, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let requirements = requirements(authority, other);
+
+        for mask in 0..1u32 << requirements.len() {
+            let present: Vec<bool> = (0..requirements.len())
+                .map(|i| mask & (1 << i) != 0)
+                .collect();
+            let creatable = present
+                .iter()
+                .zip(CREATABLE.iter())
+                .all(|(present, creatable)| *present || *creatable);
+            let missing: Vec<_> = requirements
+                .iter()
+                .zip(&present)
+                .zip(CREATABLE.iter())
+                .filter(|((_, present), _)| !**present)
+                .map(|((requirement, _), creatable)| (requirement.address(&program_id), *creatable))
+                .collect();
+
+            for auto_create in [false, true] {
+                let result = plan_missing(
+                    &program_id,
+                    payer,
+                    authority,
+                    &requirements,
+                    &present,
+                    auto_create,
+                );
+
+                match result {
+                    Ok(instructions) => {
+                        assert!(auto_create || missing.is_empty(), "mask {:b}", mask);
+                        assert!(creatable, "mask {:b}", mask);
+                        // Each creation writes the account it creates
+                        let created: Vec<_> = instructions
+                            .iter()
+                            .map(|instruction| {
+                                missing
+                                    .iter()
+                                    .map(|(address, _)| *address)
+                                    .find(|address| {
+                                        instruction
+                                            .accounts
+                                            .iter()
+                                            .any(|meta| meta.pubkey == *address && meta.is_writable)
+                                    })
+                                    .unwrap()
+                            })
+                            .collect();
+                        let missing: Vec<_> = missing.iter().map(|(address, _)| *address).collect();
+                        assert_eq!(created, missing, "mask {:b}", mask);
+                    }
+                    Err(CliError::User(message)) => {
+                        assert!(!auto_create || !creatable, "mask {:b}", mask);
+                        // Only what can't be created is reported under
+                        // --auto-create
+                        let reported: Vec<_> = missing
+                            .iter()
+                            .filter(|(_, creatable)| !auto_create || !creatable)
+                            .collect();
+                        assert_eq!(message.lines().count(), reported.len());
+                        for (address, _) in reported {
+                            assert!(message.contains(&address.to_string()), "{}", message);
+                        }
+                    }
+                    Err(e) => panic!("unexpected error {:?}", e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn present_accounts_need_nothing() {
+        let requirements = requirements(Pubkey::new_unique(), Pubkey::new_unique());
+        let present = vec![true; requirements.len()];
+
+        for auto_create in [false, true] {
+            let instructions = plan_missing(
+                &datanexus::id(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                &requirements,
+                &present,
+                auto_create,
+            )
+            .unwrap();
+            assert!(instructions.is_empty());
+        }
+    }
+
+    #[test]
+    fn missing_accounts_name_the_command_creating_them() {
+        let authority = Pubkey::new_unique();
+        let requirements = [
+            Requirement::AccessIndex(authority),
+            Requirement::AssociatedAccess {
+                authority,
+                hash: HASH,
+            },
+        ];
+
+        let error = plan_missing(
+            &datanexus::id(),
+            authority,
+            authority,
+            &requirements,
+            &[false, false],
+            false,
+        )
+        .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains(&format!("dn-me create access --authority {}", authority)));
+        assert!(message.contains(&format!("dn-me create access --hash {}", hex(&HASH))));
+        assert!(message.contains("--auto-create"));
+    }
+}
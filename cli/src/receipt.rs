@@ -0,0 +1,139 @@
+use serde_json::Value;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{clock::UnixTimestamp, signature::Signature};
+use solana_transaction_status::{
+    UiInnerInstructions, UiInstruction, UiParsedInstruction, UiTransactionEncoding,
+};
+
+use crate::error::CliError;
+
+/// A token transfer made inside a transaction, as the RPC node parses
+/// `transfer_checked` instructions of spl-token and spl-token-2022
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenTransfer {
+    pub source: String,
+    pub destination: String,
+    pub mint: String,
+    pub amount: u64,
+}
+
+/// What a confirmed transaction did, read back from the cluster
+#[derive(Debug)]
+pub struct ConfirmedTransaction {
+    pub slot: u64,
+    pub block_time: Option<UnixTimestamp>,
+    /// Lamports the fee payer was charged
+    pub fee: u64,
+    pub logs: Vec<String>,
+    /// Token transfers of the transaction's inner instructions, in the order
+    /// they were made
+    pub transfers: Vec<TokenTransfer>,
+}
+
+/// Decodes a parsed `transferChecked` instruction, `None` for any other
+pub fn parse_transfer(parsed: &Value) -> Option<TokenTransfer> {
+    if parsed["type"] != "transferChecked" {
+        return None;
+    }
+    let info = &parsed["info"];
+
+    Some(TokenTransfer {
+        source: info["source"].as_str()?.to_string(),
+        destination: info["destination"].as_str()?.to_string(),
+        mint: info["mint"].as_str()?.to_string(),
+        amount: info["tokenAmount"]["amount"].as_str()?.parse().ok()?,
+    })
+}
+
+fn token_transfers(inner_instructions: &[UiInnerInstructions]) -> Vec<TokenTransfer> {
+    inner_instructions
+        .iter()
+        .flat_map(|inner| inner.instructions.iter())
+        .filter_map(|instruction| match instruction {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(instruction)) => {
+                parse_transfer(&instruction.parsed)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Fetches the transaction of `signature` at the client's commitment, which
+/// the transaction has to have reached already
+pub fn fetch_confirmed(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+) -> Result<ConfirmedTransaction, CliError> {
+    let transaction = rpc_client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::JsonParsed),
+                commitment: Some(rpc_client.commitment()),
+                ..RpcTransactionConfig::default()
+            },
+        )
+        .map_err(|e| {
+            CliError::User(format!(
+                "transaction {} was confirmed but could not be fetched: {}",
+                signature, e
+            ))
+        })?;
+    let meta = transaction.transaction.meta.ok_or_else(|| {
+        CliError::User(format!("transaction {} has no status metadata", signature))
+    })?;
+
+    Ok(ConfirmedTransaction {
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        fee: meta.fee,
+        logs: meta.log_messages.unwrap_or_default(),
+        transfers: token_transfers(&meta.inner_instructions.unwrap_or_default()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn checked_transfers_are_decoded() {
+        let parsed = json!({
+            "type": "transferChecked",
+            "info": {
+                "source": "source",
+                "mint": "mint",
+                "destination": "destination",
+                "authority": "authority",
+                "tokenAmount": {
+                    "amount": "1500000",
+                    "decimals": 6,
+                    "uiAmount": 1.5,
+                    "uiAmountString": "1.5",
+                },
+            },
+        });
+
+        assert_eq!(
+            parse_transfer(&parsed),
+            Some(TokenTransfer {
+                source: "source".to_string(),
+                destination: "destination".to_string(),
+                mint: "mint".to_string(),
+                amount: 1_500_000,
+            })
+        );
+    }
+
+    #[test]
+    fn other_instructions_are_not_transfers() {
+        let parsed = json!({
+            "type": "transfer",
+            "info": { "source": "source", "destination": "destination", "lamports": 5 },
+        });
+
+        assert_eq!(parse_transfer(&parsed), None);
+        assert_eq!(parse_transfer(&json!({ "type": "transferChecked" })), None);
+    }
+}
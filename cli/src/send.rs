@@ -0,0 +1,440 @@
+use std::{thread::sleep, time::Duration};
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    message::Message,
+    signature::{Signature, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Why a transaction couldn't be confirmed
+#[derive(Debug)]
+pub enum SendError {
+    /// The transaction was never processed, sending it again is safe
+    NotLanded(ClientError),
+    /// The transaction was processed and failed
+    Failed(TransactionError),
+    /// The transaction may have been processed, `signature` has to be
+    /// queried before sending again
+    Unknown {
+        signature: Signature,
+        error: ClientError,
+    },
+}
+
+/// The RPC calls sending relies on, so the retry logic can run against a
+/// scripted cluster
+pub trait Cluster {
+    fn send(
+        &self,
+        transaction: &Transaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, ClientError>;
+
+    fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<Result<(), TransactionError>>, ClientError>;
+
+    /// Whether `message` can still be processed, false once its blockhash
+    /// expired
+    fn is_blockhash_valid(&self, message: &Message) -> Result<bool, ClientError>;
+
+    fn latest_blockhash(&self, commitment: CommitmentConfig) -> Result<Hash, ClientError>;
+}
+
+impl Cluster for RpcClient {
+    fn send(
+        &self,
+        transaction: &Transaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, ClientError> {
+        let config = RpcSendTransactionConfig {
+            preflight_commitment: Some(commitment.commitment),
+            ..RpcSendTransactionConfig::default()
+        };
+        self.send_transaction_with_config(transaction, config)
+    }
+
+    fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<Result<(), TransactionError>>, ClientError> {
+        self.get_signature_status_with_commitment(signature, commitment)
+    }
+
+    fn is_blockhash_valid(&self, message: &Message) -> Result<bool, ClientError> {
+        match self.get_fee_for_message(message) {
+            Ok(_) => Ok(true),
+            // The fee of a message is only known while its blockhash is
+            Err(error) if matches!(error.kind(), ClientErrorKind::Custom(_)) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn latest_blockhash(&self, commitment: CommitmentConfig) -> Result<Hash, ClientError> {
+        Ok(self.get_latest_blockhash_with_commitment(commitment)?.0)
+    }
+}
+
+/// Where a sent transaction ended up
+enum Confirmation {
+    Landed(Result<(), TransactionError>),
+    /// Its blockhash expired before it was processed
+    Expired,
+}
+
+fn is_transport_error(error: &ClientError) -> bool {
+    matches!(error.kind(), ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_))
+}
+
+fn is_blockhash_not_found(error: &ClientError) -> bool {
+    error.get_transaction_error() == Some(TransactionError::BlockhashNotFound)
+}
+
+/// Waits for `signature` to reach the client's commitment, or for the
+/// transaction's blockhash to expire
+fn confirm<C: Cluster>(
+    rpc: &C,
+    transaction: &Transaction,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<Confirmation, ClientError> {
+    loop {
+        if let Some(result) = rpc.signature_status(signature, commitment)? {
+            return Ok(Confirmation::Landed(result));
+        }
+
+        if !rpc.is_blockhash_valid(&transaction.message)? {
+            // The transaction could have landed in the last valid slot
+            return Ok(match rpc.signature_status(signature, commitment)? {
+                Some(result) => Confirmation::Landed(result),
+                None => Confirmation::Expired,
+            });
+        }
+
+        sleep(POLL_INTERVAL);
+    }
+}
+
+/// Sends `transaction` and waits for it to reach `commitment`
+///
+/// Transport errors are retried with exponential backoff, up to
+/// `max_retries` times. When `signers` are given, a transaction whose
+/// blockhash expired is re-signed against a fresh one and sent again,
+/// without them the expiry is final. Resending the same signed transaction
+/// can't process it twice, so a retry never duplicates a transfer.
+pub fn send_with_retry<C: Cluster>(
+    rpc: &C,
+    transaction: &mut Transaction,
+    signers: Option<&[&dyn Signer]>,
+    max_retries: usize,
+    commitment: CommitmentConfig,
+) -> Result<Signature, SendError> {
+    // Signature of the last attempt that may have reached the cluster
+    let mut forwarded = None;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 0;
+
+    loop {
+        let error = match rpc.send(transaction, commitment) {
+            Ok(signature) => {
+                forwarded = Some(signature);
+                match confirm(rpc, transaction, &signature, commitment) {
+                    Ok(Confirmation::Landed(Ok(()))) => return Ok(signature),
+                    Ok(Confirmation::Landed(Err(error))) => return Err(SendError::Failed(error)),
+                    Ok(Confirmation::Expired) => {
+                        forwarded = None;
+                        None
+                    }
+                    Err(error) => Some(error),
+                }
+            }
+            Err(error) if is_blockhash_not_found(&error) => None,
+            Err(error) if is_transport_error(&error) => {
+                forwarded = transaction.signatures.first().copied();
+                Some(error)
+            }
+            // Rejected in preflight
+            Err(error) => return Err(SendError::NotLanded(error)),
+        };
+
+        match error {
+            // The blockhash is gone, so nothing sent with it can land anymore
+            // and only an earlier attempt may have
+            None => {
+                if let Some(signature) = forwarded.take() {
+                    match rpc.signature_status(&signature, commitment) {
+                        Ok(Some(Ok(()))) => return Ok(signature),
+                        Ok(Some(Err(error))) => return Err(SendError::Failed(error)),
+                        Ok(None) => {}
+                        Err(error) => return Err(SendError::Unknown { signature, error }),
+                    }
+                }
+
+                let signers = match signers {
+                    Some(signers) => signers,
+                    None => {
+                        return Err(SendError::NotLanded(
+                            TransactionError::BlockhashNotFound.into(),
+                        ))
+                    }
+                };
+                if attempt >= max_retries {
+                    return Err(SendError::NotLanded(
+                        TransactionError::BlockhashNotFound.into(),
+                    ));
+                }
+
+                let blockhash = rpc
+                    .latest_blockhash(commitment)
+                    .map_err(SendError::NotLanded)?;
+                transaction
+                    .try_sign(signers, blockhash)
+                    .map_err(|e| SendError::NotLanded(e.into()))?;
+            }
+            Some(error) => {
+                if attempt >= max_retries {
+                    return Err(match forwarded {
+                        Some(signature) => SendError::Unknown { signature, error },
+                        None => SendError::NotLanded(error),
+                    });
+                }
+
+                sleep(backoff);
+                backoff *= 2;
+            }
+        }
+
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{pubkey::Pubkey, signature::Keypair, system_instruction};
+    use std::{cell::RefCell, collections::VecDeque, io};
+
+    /// Replies to each call with the next scripted result. Once a script
+    /// runs out sends succeed, statuses stay unknown and blockhashes valid
+    #[derive(Default)]
+    struct Scripted {
+        sends: RefCell<VecDeque<Result<(), ClientError>>>,
+        statuses: RefCell<VecDeque<Option<Result<(), TransactionError>>>>,
+        valid: RefCell<VecDeque<bool>>,
+        /// Blockhashes of the transactions sent, in order
+        sent: RefCell<Vec<Hash>>,
+        latest: Hash,
+    }
+
+    impl Cluster for Scripted {
+        fn send(
+            &self,
+            transaction: &Transaction,
+            _commitment: CommitmentConfig,
+        ) -> Result<Signature, ClientError> {
+            self.sent
+                .borrow_mut()
+                .push(transaction.message.recent_blockhash);
+            self.sends
+                .borrow_mut()
+                .pop_front()
+                .unwrap_or(Ok(()))
+                .map(|()| transaction.signatures[0])
+        }
+
+        fn signature_status(
+            &self,
+            _signature: &Signature,
+            _commitment: CommitmentConfig,
+        ) -> Result<Option<Result<(), TransactionError>>, ClientError> {
+            Ok(self.statuses.borrow_mut().pop_front().flatten())
+        }
+
+        fn is_blockhash_valid(&self, _message: &Message) -> Result<bool, ClientError> {
+            Ok(self.valid.borrow_mut().pop_front().unwrap_or(true))
+        }
+
+        fn latest_blockhash(&self, _commitment: CommitmentConfig) -> Result<Hash, ClientError> {
+            Ok(self.latest)
+        }
+    }
+
+    impl Scripted {
+        fn new(
+            sends: Vec<Result<(), ClientError>>,
+            statuses: Vec<Option<Result<(), TransactionError>>>,
+            valid: Vec<bool>,
+        ) -> Self {
+            Self {
+                sends: RefCell::new(sends.into()),
+                statuses: RefCell::new(statuses.into()),
+                valid: RefCell::new(valid.into()),
+                sent: RefCell::default(),
+                latest: Hash::new_unique(),
+            }
+        }
+
+        fn sent(&self) -> Vec<Hash> {
+            self.sent.borrow().clone()
+        }
+    }
+
+    fn transport_error() -> ClientError {
+        ClientErrorKind::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset")).into()
+    }
+
+    fn transfer(payer: &Keypair) -> Transaction {
+        Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        )
+    }
+
+    fn send(
+        rpc: &Scripted,
+        transaction: &mut Transaction,
+        signers: Option<&[&dyn Signer]>,
+    ) -> Result<Signature, SendError> {
+        send_with_retry(rpc, transaction, signers, 1, CommitmentConfig::confirmed())
+    }
+
+    #[test]
+    fn landed_transactions_are_sent_once() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let rpc = Scripted::new(vec![], vec![Some(Ok(()))], vec![]);
+
+        let signature = send(&rpc, &mut transaction, None).unwrap();
+
+        assert_eq!(signature, transaction.signatures[0]);
+        assert_eq!(rpc.sent(), vec![transaction.message.recent_blockhash]);
+    }
+
+    #[test]
+    fn transport_errors_are_retried() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let blockhash = transaction.message.recent_blockhash;
+        let rpc = Scripted::new(vec![Err(transport_error())], vec![Some(Ok(()))], vec![]);
+
+        let signature = send(&rpc, &mut transaction, None).unwrap();
+
+        assert_eq!(signature, transaction.signatures[0]);
+        assert_eq!(rpc.sent(), vec![blockhash, blockhash]);
+    }
+
+    #[test]
+    fn exhausted_transport_retries_leave_the_signature_to_query() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let rpc = Scripted::new(
+            vec![Err(transport_error()), Err(transport_error())],
+            vec![],
+            vec![],
+        );
+
+        let result = send(&rpc, &mut transaction, None);
+
+        match result {
+            Err(SendError::Unknown { signature, .. }) => {
+                assert_eq!(signature, transaction.signatures[0])
+            }
+            other => panic!("expected an unknown outcome, got {:?}", other),
+        }
+        assert_eq!(rpc.sent().len(), 2);
+    }
+
+    #[test]
+    fn preflight_rejections_are_not_retried() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let rejection = ClientErrorKind::Custom("rejected".to_string()).into();
+        let rpc = Scripted::new(vec![Err(rejection)], vec![], vec![]);
+
+        let result = send(&rpc, &mut transaction, None);
+
+        assert!(matches!(result, Err(SendError::NotLanded(_))));
+        assert_eq!(rpc.sent().len(), 1);
+    }
+
+    #[test]
+    fn failed_transactions_are_not_retried() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let failure = TransactionError::InsufficientFundsForFee;
+        let rpc = Scripted::new(vec![], vec![Some(Err(failure.clone()))], vec![]);
+
+        let result = send(&rpc, &mut transaction, None);
+
+        assert!(matches!(result, Err(SendError::Failed(error)) if error == failure));
+        assert_eq!(rpc.sent().len(), 1);
+    }
+
+    #[test]
+    fn expired_transactions_are_resigned_against_a_fresh_blockhash() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let expired = transaction.message.recent_blockhash;
+        // Unknown until the blockhash expires and right after, then landed
+        // under the fresh one
+        let rpc = Scripted::new(vec![], vec![None, None, Some(Ok(()))], vec![false]);
+
+        let signature = send(&rpc, &mut transaction, Some(&[&payer])).unwrap();
+
+        assert_eq!(rpc.sent(), vec![expired, rpc.latest]);
+        assert_eq!(transaction.message.recent_blockhash, rpc.latest);
+        assert_eq!(signature, transaction.signatures[0]);
+        assert!(transaction.verify().is_ok());
+    }
+
+    #[test]
+    fn expired_transactions_without_signers_are_not_resent() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let rpc = Scripted::new(vec![], vec![], vec![false]);
+
+        let result = send(&rpc, &mut transaction, None);
+
+        assert!(matches!(
+            result,
+            Err(SendError::NotLanded(error)) if is_blockhash_not_found(&error)
+        ));
+        assert_eq!(rpc.sent().len(), 1);
+    }
+
+    #[test]
+    fn unknown_blockhashes_are_resigned_before_sending_again() {
+        let payer = Keypair::new();
+        let mut transaction = transfer(&payer);
+        let stale = transaction.message.recent_blockhash;
+        let rpc = Scripted::new(
+            vec![Err(TransactionError::BlockhashNotFound.into())],
+            vec![Some(Ok(()))],
+            vec![],
+        );
+
+        send(&rpc, &mut transaction, Some(&[&payer])).unwrap();
+
+        assert_eq!(rpc.sent(), vec![stale, rpc.latest]);
+    }
+}
@@ -0,0 +1,1789 @@
+//! Account ordering for each instruction
+//!
+//! Builders fill these with `Pubkey`s and the processor with `AccountInfo`s,
+//! so the order of every instruction's accounts is defined once, here.
+//! Each also states how many accounts it takes as its `ACCOUNT_COUNT`,
+//! which handlers check before reading any.
+
+use std::fmt;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    instruction::AccountMeta,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::DataNexusError;
+
+/// Number of accounts an instruction takes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountCount {
+    Exact(usize),
+    /// Any of these, for instructions with optional account groups
+    OneOf(&'static [usize]),
+    /// `fixed` accounts followed by at least `min` groups of `group`
+    Groups {
+        fixed: usize,
+        group: usize,
+        min: usize,
+    },
+}
+
+impl AccountCount {
+    pub fn accepts(self, len: usize) -> bool {
+        match self {
+            Self::Exact(count) => len == count,
+            Self::OneOf(counts) => counts.contains(&len),
+            Self::Groups { fixed, group, min } => match len.checked_sub(fixed) {
+                Some(rest) => {
+                    rest.checked_rem(group) == Some(0) && rest >= min.saturating_mul(group)
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Fails with `InvalidAccountCount` unless `accounts` are as many as
+    /// `instruction` takes, so that none is missing or silently ignored
+    pub fn check(self, instruction: &str, accounts: &[AccountInfo]) -> Result<(), ProgramError> {
+        if !self.accepts(accounts.len()) {
+            msg!(
+                "{} expects {} accounts, got {}",
+                instruction,
+                self,
+                accounts.len()
+            );
+            return Err(DataNexusError::InvalidAccountCount.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AccountCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Exact(count) => write!(f, "{}", count),
+            Self::OneOf(counts) => match counts.split_last() {
+                Some((last, [])) => write!(f, "{}", last),
+                Some((last, rest)) => {
+                    let rest: Vec<String> = rest.iter().map(ToString::to_string).collect();
+                    write!(f, "{} or {}", rest.join(", "), last)
+                }
+                None => write!(f, "no"),
+            },
+            Self::Groups { fixed, group, min } => {
+                write!(f, "{} plus {} or more groups of {}", fixed, min, group)
+            }
+        }
+    }
+}
+
+/// Most members signing for a multisig owner, as many as an SPL Token
+/// multisig holds
+pub const MAX_SIGNERS: usize = spl_token_2022::instruction::MAX_SIGNERS;
+
+/// Appends the members signing for a multisig owner to the `accounts` of an
+/// instruction taking the owner first, the owner then no longer signing
+/// itself
+pub fn push_signer_metas(accounts: &mut Vec<AccountMeta>, signers: &[Pubkey]) {
+    if signers.is_empty() {
+        return;
+    }
+    if let Some(owner) = accounts.first_mut() {
+        owner.is_signer = false;
+    }
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true)),
+    );
+}
+
+/// Splits the signers trailing an instruction's own accounts off them, the
+/// members signing for a multisig owner. At most `MAX_SIGNERS` are split
+/// off, any further ones stay to fail the account count
+pub fn split_signers<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+) -> (&'a [AccountInfo<'b>], &'a [AccountInfo<'b>]) {
+    let signers = accounts
+        .iter()
+        .rev()
+        .take(MAX_SIGNERS)
+        .take_while(|account| account.is_signer)
+        .count();
+    accounts.split_at(accounts.len().saturating_sub(signers))
+}
+
+/// Accounts for `InitAccount` with an index account type
+pub struct InitIndexAccounts<T> {
+    pub payer: T,
+    pub authority: T,
+    pub index_account: T,
+    pub system_program: T,
+}
+
+impl InitIndexAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(4);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new(self.index_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> InitIndexAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter)?,
+            index_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `InitAccount` with the dataset account type, the registry
+/// group lists the dataset under its hash prefix when passed
+pub struct InitDatasetAccounts<T> {
+    pub authority: T,
+    pub owner_index: T,
+    pub dataset_account: T,
+    pub system_program: T,
+    pub registry: Option<RegistryAccounts<T>>,
+}
+
+impl InitDatasetAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[4, 5, 6]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.owner_index, false),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        if let Some(registry) = &self.registry {
+            accounts.push(AccountMeta::new(registry.registry_account, false));
+            if let Some(overflow_account) = registry.overflow_account {
+                accounts.push(AccountMeta::new(overflow_account, false));
+            }
+        }
+        accounts
+    }
+}
+
+impl<'a, 'b> InitDatasetAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            owner_index: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+            registry: match accounts_iter.as_slice() {
+                [] => None,
+                [registry_account] => Some(RegistryAccounts {
+                    registry_account,
+                    overflow_account: None,
+                }),
+                [registry_account, overflow_account] => Some(RegistryAccounts {
+                    registry_account,
+                    overflow_account: Some(overflow_account),
+                }),
+                _ => {
+                    msg!("Expected at most a registry page and its overflow page");
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                }
+            },
+        })
+    }
+}
+
+/// Registry page a new dataset is listed on
+///
+/// The page is the last of its prefix's chain, created when the prefix has
+/// none yet. Once it is full the overflow page is created after it and
+/// takes the entry instead
+pub struct RegistryAccounts<T> {
+    pub registry_account: T,
+    pub overflow_account: Option<T>,
+}
+
+/// Accounts for `SetDataParams`, the reference dataset is required when the
+/// params set reference data
+pub struct SetDataParamsAccounts<T> {
+    pub authority: T,
+    pub dataset_account: T,
+    pub reference_dataset: Option<T>,
+}
+
+impl SetDataParamsAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[2, 3]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.dataset_account, false),
+        ];
+
+        if let Some(reference_dataset) = self.reference_dataset {
+            accounts.push(AccountMeta::new_readonly(reference_dataset, false));
+        }
+
+        accounts
+    }
+}
+
+impl<'a, 'b> SetDataParamsAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            reference_dataset: next_account_info(accounts_iter).ok(),
+        })
+    }
+}
+
+/// Accounts minting an access receipt on purchase or burning it on revoke
+///
+/// The receipt token account belongs to the holder, usually as their
+/// associated token account of the receipt mint
+pub struct ReceiptAccounts<T> {
+    pub receipt_mint: T,
+    pub receipt_token_account: T,
+    pub token_program: T,
+}
+
+/// Number of accounts in a `ReceiptAccounts` group
+pub const RECEIPT_ACCOUNTS_LEN: usize = 3;
+
+impl ReceiptAccounts<Pubkey> {
+    fn push_account_metas(&self, accounts: &mut Vec<AccountMeta>) {
+        accounts.push(AccountMeta::new(self.receipt_mint, false));
+        accounts.push(AccountMeta::new(self.receipt_token_account, false));
+        accounts.push(AccountMeta::new_readonly(self.token_program, false));
+    }
+}
+
+/// Reads the receipt group from the accounts left after an instruction's
+/// other accounts, fails unless they are absent or form exactly one group
+fn receipt_accounts<'a, 'b>(
+    remaining: &'a [AccountInfo<'b>],
+) -> Result<Option<ReceiptAccounts<&'a AccountInfo<'b>>>, ProgramError> {
+    match remaining {
+        [] => Ok(None),
+        [receipt_mint, receipt_token_account, token_program] => Ok(Some(ReceiptAccounts {
+            receipt_mint,
+            receipt_token_account,
+            token_program,
+        })),
+        _ => {
+            msg!("Expected receipt accounts in a group of three");
+            Err(ProgramError::NotEnoughAccountKeys)
+        }
+    }
+}
+
+/// Accounts for `PurchaseAccess`, the proceeds account is the dataset's
+/// vault for the payment mint, or the owner's token account for datasets
+/// created before vaults
+pub struct PurchaseAccessAccounts<T> {
+    pub user_authority: T,
+    pub user_access_index: T,
+    pub user_access_account: T,
+    pub user_token_account: T,
+    pub owner_authority: T,
+    pub proceeds_account: T,
+    pub token_mint: T,
+    pub dataset_account: T,
+    pub token_program: T,
+    pub referrer_token_account: Option<T>,
+    pub receipt: Option<ReceiptAccounts<T>>,
+    /// Buyer's associated access account of the dataset's `ref_data`, claims
+    /// the derived discount
+    pub reference_access: Option<T>,
+}
+
+impl PurchaseAccessAccounts<Pubkey> {
+    /// With or without a referrer, the receipt group and the reference access
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[
+        9,
+        10,
+        11,
+        9 + RECEIPT_ACCOUNTS_LEN,
+        10 + RECEIPT_ACCOUNTS_LEN,
+        11 + RECEIPT_ACCOUNTS_LEN,
+    ]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.user_authority, true),
+            AccountMeta::new(self.user_access_index, false),
+            AccountMeta::new(self.user_access_account, false),
+            AccountMeta::new(self.user_token_account, false),
+            AccountMeta::new_readonly(self.owner_authority, false),
+            AccountMeta::new(self.proceeds_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+
+        if let Some(referrer_token_account) = self.referrer_token_account {
+            accounts.push(AccountMeta::new(referrer_token_account, false));
+        }
+        if let Some(receipt) = &self.receipt {
+            receipt.push_account_metas(&mut accounts);
+        }
+        if let Some(reference_access) = self.reference_access {
+            accounts.push(AccountMeta::new_readonly(reference_access, false));
+        }
+
+        accounts
+    }
+}
+
+impl<'a, 'b> PurchaseAccessAccounts<&'a AccountInfo<'b>> {
+    /// The referrer, receipt and reference access accounts are all optional.
+    /// The last account is the reference access when it has the dataset's
+    /// owner, no token account does, and a referrer is present when one
+    /// account is left over from the receipt group
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        let user_authority = next_account_info(accounts_iter)?;
+        let user_access_index = next_account_info(accounts_iter)?;
+        let user_access_account = next_account_info(accounts_iter)?;
+        let user_token_account = next_account_info(accounts_iter)?;
+        let owner_authority = next_account_info(accounts_iter)?;
+        let proceeds_account = next_account_info(accounts_iter)?;
+        let token_mint = next_account_info(accounts_iter)?;
+        let dataset_account = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+
+        let remaining = accounts_iter.as_slice();
+        let (reference_access, remaining) = match remaining.split_last() {
+            Some((reference, rest)) if reference.owner == dataset_account.owner => {
+                (Some(reference), rest)
+            }
+            _ => (None, remaining),
+        };
+        let (referrer_token_account, remaining) = match remaining.split_first() {
+            Some((referrer, rest)) if rest.len() % RECEIPT_ACCOUNTS_LEN == 0 => {
+                (Some(referrer), rest)
+            }
+            _ => (None, remaining),
+        };
+
+        Ok(Self {
+            user_authority,
+            user_access_index,
+            user_access_account,
+            user_token_account,
+            owner_authority,
+            proceeds_account,
+            token_mint,
+            dataset_account,
+            token_program,
+            referrer_token_account,
+            receipt: receipt_accounts(remaining)?,
+            reference_access,
+        })
+    }
+}
+
+/// Accounts for `ShareAccess`
+pub struct ShareAccessAccounts<T> {
+    pub user_authority: T,
+    pub user_access_account: T,
+    pub recipient_authority: T,
+    pub recipient_access_index: T,
+    pub recipient_access_account: T,
+    pub dataset_account: T,
+}
+
+impl ShareAccessAccounts<Pubkey> {
+    /// With or without the `ShareFeeAccounts` group after these
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[
+        SHARE_ACCESS_ACCOUNTS_LEN,
+        SHARE_ACCESS_ACCOUNTS_LEN + SHARE_FEE_ACCOUNTS_LEN,
+    ]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.user_authority, true),
+            AccountMeta::new(self.user_access_account, false),
+            AccountMeta::new_readonly(self.recipient_authority, false),
+            AccountMeta::new(self.recipient_access_index, false),
+            AccountMeta::new(self.recipient_access_account, false),
+            AccountMeta::new(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> ShareAccessAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            user_authority: next_account_info(accounts_iter)?,
+            user_access_account: next_account_info(accounts_iter)?,
+            recipient_authority: next_account_info(accounts_iter)?,
+            recipient_access_index: next_account_info(accounts_iter)?,
+            recipient_access_account: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts paying a dataset's share fee, passed after `ShareAccess`'s own
+/// when the dataset charges one
+pub struct ShareFeeAccounts<T> {
+    pub sharer_token_account: T,
+    pub owner_token_account: T,
+    pub token_mint: T,
+    pub token_program: T,
+}
+
+/// Number of accounts `ShareAccess` takes without the fee group
+pub const SHARE_ACCESS_ACCOUNTS_LEN: usize = 6;
+
+/// Number of accounts in a `ShareFeeAccounts` group
+pub const SHARE_FEE_ACCOUNTS_LEN: usize = 4;
+
+impl ShareFeeAccounts<Pubkey> {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.sharer_token_account, false),
+            AccountMeta::new(self.owner_token_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> ShareFeeAccounts<&'a AccountInfo<'b>> {
+    /// Reads the fee group from the accounts of a `ShareAccess`, `None` when
+    /// they end with its own. Fails unless the group is absent or whole
+    pub fn from_account_infos(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Option<Self>, ProgramError> {
+        match accounts
+            .get(SHARE_ACCESS_ACCOUNTS_LEN..)
+            .unwrap_or_default()
+        {
+            [] => Ok(None),
+            [sharer_token_account, owner_token_account, token_mint, token_program] => {
+                Ok(Some(Self {
+                    sharer_token_account,
+                    owner_token_account,
+                    token_mint,
+                    token_program,
+                }))
+            }
+            _ => {
+                msg!("Expected share fee accounts in a group of four");
+                Err(ProgramError::NotEnoughAccountKeys)
+            }
+        }
+    }
+}
+
+/// Accounts for `CloseDataset`
+pub struct CloseDatasetAccounts<T> {
+    pub authority: T,
+    pub owner_index: T,
+    pub dataset_account: T,
+}
+
+impl CloseDatasetAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.owner_index, false),
+            AccountMeta::new(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> CloseDatasetAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            owner_index: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `RenewAccess`
+pub struct RenewAccessAccounts<T> {
+    pub user_authority: T,
+    pub user_access_account: T,
+    pub user_token_account: T,
+    pub proceeds_account: T,
+    pub token_mint: T,
+    pub dataset_account: T,
+    pub token_program: T,
+    pub referrer_token_account: Option<T>,
+}
+
+impl RenewAccessAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[7, 8]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.user_authority, true),
+            AccountMeta::new(self.user_access_account, false),
+            AccountMeta::new(self.user_token_account, false),
+            AccountMeta::new(self.proceeds_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+
+        if let Some(referrer_token_account) = self.referrer_token_account {
+            accounts.push(AccountMeta::new(referrer_token_account, false));
+        }
+
+        accounts
+    }
+}
+
+impl<'a, 'b> RenewAccessAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            user_authority: next_account_info(accounts_iter)?,
+            user_access_account: next_account_info(accounts_iter)?,
+            user_token_account: next_account_info(accounts_iter)?,
+            proceeds_account: next_account_info(accounts_iter)?,
+            token_mint: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            referrer_token_account: next_account_info(accounts_iter).ok(),
+        })
+    }
+}
+
+/// Accounts of a single recipient of `ShareAccessMulti`
+pub struct RecipientAccounts<T> {
+    pub authority: T,
+    pub access_index: T,
+    pub access_account: T,
+}
+
+/// Accounts for `ShareAccessMulti`
+pub struct ShareAccessMultiAccounts<T> {
+    pub user_authority: T,
+    pub user_access_account: T,
+    pub dataset_account: T,
+    pub recipients: Vec<RecipientAccounts<T>>,
+}
+
+impl ShareAccessMultiAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Groups {
+        fixed: 3,
+        group: 3,
+        min: 1,
+    };
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.user_authority, true),
+            AccountMeta::new(self.user_access_account, false),
+            AccountMeta::new(self.dataset_account, false),
+        ];
+
+        for recipient in self.recipients.iter() {
+            accounts.push(AccountMeta::new_readonly(recipient.authority, false));
+            accounts.push(AccountMeta::new(recipient.access_index, false));
+            accounts.push(AccountMeta::new(recipient.access_account, false));
+        }
+
+        accounts
+    }
+}
+
+impl<'a, 'b> ShareAccessMultiAccounts<&'a AccountInfo<'b>> {
+    /// Fails with `NotEnoughAccountKeys` unless the remaining accounts form
+    /// at least one complete recipient group
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        let user_authority = next_account_info(accounts_iter)?;
+        let user_access_account = next_account_info(accounts_iter)?;
+        let dataset_account = next_account_info(accounts_iter)?;
+
+        let remaining = accounts_iter.as_slice();
+        if remaining.is_empty() || !remaining.len().is_multiple_of(3) {
+            msg!("Expected recipient accounts in groups of three");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let recipients = remaining
+            .chunks_exact(3)
+            .filter_map(|recipient| match recipient {
+                [authority, access_index, access_account] => Some(RecipientAccounts {
+                    authority,
+                    access_index,
+                    access_account,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            user_authority,
+            user_access_account,
+            dataset_account,
+            recipients,
+        })
+    }
+}
+
+/// Accounts for `InitAssociatedAccess`
+pub struct InitAssociatedAccessAccounts<T> {
+    pub payer: T,
+    pub authority: T,
+    pub access_index: T,
+    pub associated_access_account: T,
+    pub dataset_account: T,
+    pub system_program: T,
+}
+
+impl InitAssociatedAccessAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(6);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.access_index, false),
+            AccountMeta::new(self.associated_access_account, false),
+            AccountMeta::new_readonly(self.dataset_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> InitAssociatedAccessAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter)?,
+            access_index: next_account_info(accounts_iter)?,
+            associated_access_account: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `ResizeIndex`
+pub struct ResizeIndexAccounts<T> {
+    pub payer: T,
+    pub authority: T,
+    pub index_account: T,
+    pub system_program: T,
+}
+
+impl ResizeIndexAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(4);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.index_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> ResizeIndexAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter)?,
+            index_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `CreateCollection`
+pub struct CreateCollectionAccounts<T> {
+    pub authority: T,
+    pub collection_account: T,
+    pub system_program: T,
+}
+
+impl CreateCollectionAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.collection_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> CreateCollectionAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            collection_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `AddToCollection`
+pub struct AddToCollectionAccounts<T> {
+    pub authority: T,
+    pub collection_account: T,
+    pub dataset_account: T,
+}
+
+impl AddToCollectionAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.collection_account, false),
+            AccountMeta::new_readonly(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> AddToCollectionAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            collection_account: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts of a single collection member granted to a buyer
+pub struct MemberAccounts<T> {
+    pub dataset_account: T,
+    pub access_account: T,
+}
+
+/// Splits the accounts left after an instruction's fixed accounts into
+/// member pairs, fails unless they pair up
+fn member_accounts<'a, 'b>(
+    remaining: &'a [AccountInfo<'b>],
+) -> Result<Vec<MemberAccounts<&'a AccountInfo<'b>>>, ProgramError> {
+    if !remaining.len().is_multiple_of(2) {
+        msg!("Expected member accounts in pairs");
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    Ok(remaining
+        .chunks_exact(2)
+        .filter_map(|member| match member {
+            [dataset_account, access_account] => Some(MemberAccounts {
+                dataset_account,
+                access_account,
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+fn push_member_metas(accounts: &mut Vec<AccountMeta>, members: &[MemberAccounts<Pubkey>]) {
+    for member in members.iter() {
+        accounts.push(AccountMeta::new_readonly(member.dataset_account, false));
+        accounts.push(AccountMeta::new(member.access_account, false));
+    }
+}
+
+/// Accounts for `PurchaseAccess` of a collection, which takes no referrer
+pub struct PurchaseCollectionAccounts<T> {
+    pub user_authority: T,
+    pub user_access_index: T,
+    pub collection_access_account: T,
+    pub user_token_account: T,
+    pub owner_authority: T,
+    pub owner_token_account: T,
+    pub token_mint: T,
+    pub collection_account: T,
+    pub token_program: T,
+    pub members: Vec<MemberAccounts<T>>,
+}
+
+impl PurchaseCollectionAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Groups {
+        fixed: 9,
+        group: 2,
+        min: 0,
+    };
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.user_authority, true),
+            AccountMeta::new(self.user_access_index, false),
+            AccountMeta::new(self.collection_access_account, false),
+            AccountMeta::new(self.user_token_account, false),
+            AccountMeta::new_readonly(self.owner_authority, false),
+            AccountMeta::new(self.owner_token_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new(self.collection_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        push_member_metas(&mut accounts, &self.members);
+
+        accounts
+    }
+}
+
+impl<'a, 'b> PurchaseCollectionAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            user_authority: next_account_info(accounts_iter)?,
+            user_access_index: next_account_info(accounts_iter)?,
+            collection_access_account: next_account_info(accounts_iter)?,
+            user_token_account: next_account_info(accounts_iter)?,
+            owner_authority: next_account_info(accounts_iter)?,
+            owner_token_account: next_account_info(accounts_iter)?,
+            token_mint: next_account_info(accounts_iter)?,
+            collection_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            members: member_accounts(accounts_iter.as_slice())?,
+        })
+    }
+}
+
+/// Accounts for `ClaimCollectionAccess`
+pub struct ClaimCollectionAccessAccounts<T> {
+    pub user_authority: T,
+    pub user_access_index: T,
+    pub collection_access_account: T,
+    pub collection_account: T,
+    pub members: Vec<MemberAccounts<T>>,
+}
+
+impl ClaimCollectionAccessAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Groups {
+        fixed: 4,
+        group: 2,
+        min: 0,
+    };
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.user_authority, true),
+            AccountMeta::new(self.user_access_index, false),
+            AccountMeta::new(self.collection_access_account, false),
+            AccountMeta::new_readonly(self.collection_account, false),
+        ];
+        push_member_metas(&mut accounts, &self.members);
+
+        accounts
+    }
+}
+
+impl<'a, 'b> ClaimCollectionAccessAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            user_authority: next_account_info(accounts_iter)?,
+            user_access_index: next_account_info(accounts_iter)?,
+            collection_access_account: next_account_info(accounts_iter)?,
+            collection_account: next_account_info(accounts_iter)?,
+            members: member_accounts(accounts_iter.as_slice())?,
+        })
+    }
+}
+
+/// Accounts for `InitVault`
+pub struct InitVaultAccounts<T> {
+    pub payer: T,
+    pub owner_authority: T,
+    pub dataset_account: T,
+    pub token_mint: T,
+    pub vault_account: T,
+    pub token_program: T,
+    pub system_program: T,
+}
+
+impl InitVaultAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(7);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.owner_authority, true),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new(self.vault_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> InitVaultAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            owner_authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            token_mint: next_account_info(accounts_iter)?,
+            vault_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `WithdrawProceeds`
+pub struct WithdrawProceedsAccounts<T> {
+    pub owner_authority: T,
+    pub dataset_account: T,
+    pub vault_account: T,
+    pub token_mint: T,
+    pub destination_token_account: T,
+    pub token_program: T,
+}
+
+impl WithdrawProceedsAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(6);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.owner_authority, true),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new(self.vault_account, false),
+            AccountMeta::new_readonly(self.token_mint, false),
+            AccountMeta::new(self.destination_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> WithdrawProceedsAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            owner_authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            vault_account: next_account_info(accounts_iter)?,
+            token_mint: next_account_info(accounts_iter)?,
+            destination_token_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `InitReceiptMint`
+pub struct InitReceiptMintAccounts<T> {
+    pub payer: T,
+    pub dataset_account: T,
+    pub receipt_mint: T,
+    pub token_program: T,
+    pub system_program: T,
+}
+
+impl InitReceiptMintAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(5);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.dataset_account, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> InitReceiptMintAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            receipt_mint: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `RevokeAccess`, the receipt group burns the holder's
+/// receipt when one was minted and is required while the dataset has any
+/// receipts out
+pub struct RevokeAccessAccounts<T> {
+    pub owner_authority: T,
+    pub dataset_account: T,
+    pub holder_authority: T,
+    pub holder_access_account: T,
+    pub receipt: Option<ReceiptAccounts<T>>,
+}
+
+impl RevokeAccessAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::OneOf(&[4, 4 + RECEIPT_ACCOUNTS_LEN]);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.owner_authority, true),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.holder_authority, false),
+            AccountMeta::new(self.holder_access_account, false),
+        ];
+
+        if let Some(receipt) = &self.receipt {
+            receipt.push_account_metas(&mut accounts);
+        }
+
+        accounts
+    }
+}
+
+impl<'a, 'b> RevokeAccessAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            owner_authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            holder_authority: next_account_info(accounts_iter)?,
+            holder_access_account: next_account_info(accounts_iter)?,
+            receipt: receipt_accounts(accounts_iter.as_slice())?,
+        })
+    }
+}
+
+/// Accounts for `MigrateAccount`
+pub struct MigrateAccountAccounts<T> {
+    pub payer: T,
+    pub account: T,
+    pub system_program: T,
+}
+
+impl MigrateAccountAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new(self.account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> MigrateAccountAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            payer: next_account_info(accounts_iter)?,
+            account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `ProposeOwnershipTransfer` and `CancelOwnershipTransfer`
+pub struct OwnershipTransferAccounts<T> {
+    pub authority: T,
+    pub dataset_account: T,
+}
+
+impl OwnershipTransferAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(2);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> OwnershipTransferAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `AcceptOwnership`
+pub struct AcceptOwnershipAccounts<T> {
+    pub authority: T,
+    pub old_owner_index: T,
+    pub new_owner_index: T,
+    pub dataset_account: T,
+}
+
+impl AcceptOwnershipAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(4);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.old_owner_index, false),
+            AccountMeta::new(self.new_owner_index, false),
+            AccountMeta::new(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> AcceptOwnershipAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            old_owner_index: next_account_info(accounts_iter)?,
+            new_owner_index: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `CrankPrune`
+pub struct CrankPruneAccounts<T> {
+    pub cranker: T,
+    pub dataset_account: T,
+    pub holder_access_account: T,
+}
+
+impl CrankPruneAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.cranker, true),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new(self.holder_access_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> CrankPruneAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            cranker: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            holder_access_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `FundBounty`
+pub struct FundBountyAccounts<T> {
+    pub authority: T,
+    pub dataset_account: T,
+    pub system_program: T,
+}
+
+impl FundBountyAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(3);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.dataset_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+impl<'a, 'b> FundBountyAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `CompactIndex`
+pub struct CompactIndexAccounts<T> {
+    pub authority: T,
+    pub index_account: T,
+}
+
+impl CompactIndexAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(2);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.index_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> CompactIndexAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            index_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `SetAccessPreferences`
+pub struct SetAccessPreferencesAccounts<T> {
+    pub authority: T,
+    pub access_index: T,
+}
+
+impl SetAccessPreferencesAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(2);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.access_index, false),
+        ]
+    }
+}
+
+impl<'a, 'b> SetAccessPreferencesAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            access_index: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `AcceptShare`
+pub struct AcceptShareAccounts<T> {
+    pub authority: T,
+    pub access_index: T,
+    pub access_account: T,
+    pub dataset_account: T,
+}
+
+impl AcceptShareAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(4);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.access_index, false),
+            AccountMeta::new(self.access_account, false),
+            AccountMeta::new(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> AcceptShareAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            access_index: next_account_info(accounts_iter)?,
+            access_account: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+/// Accounts for `RejectShare`
+pub struct RejectShareAccounts<T> {
+    pub authority: T,
+    pub recipient_authority: T,
+    pub recipient_access_account: T,
+    pub sharer_access_account: T,
+    pub dataset_account: T,
+}
+
+impl RejectShareAccounts<Pubkey> {
+    pub const ACCOUNT_COUNT: AccountCount = AccountCount::Exact(5);
+
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new_readonly(self.recipient_authority, false),
+            AccountMeta::new(self.recipient_access_account, false),
+            AccountMeta::new(self.sharer_access_account, false),
+            AccountMeta::new_readonly(self.dataset_account, false),
+        ]
+    }
+}
+
+impl<'a, 'b> RejectShareAccounts<&'a AccountInfo<'b>> {
+    pub fn from_account_infos(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+
+        Ok(Self {
+            authority: next_account_info(accounts_iter)?,
+            recipient_authority: next_account_info(accounts_iter)?,
+            recipient_access_account: next_account_info(accounts_iter)?,
+            sharer_access_account: next_account_info(accounts_iter)?,
+            dataset_account: next_account_info(accounts_iter)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `check` over account infos of the keys and flags of `metas`, as
+    /// the runtime hands them to the processor
+    fn with_infos(metas: &[AccountMeta], check: impl FnOnce(&[AccountInfo])) {
+        with_owned_infos(metas, &[], check)
+    }
+
+    /// Same as `with_infos`, with the accounts of `program_owned` owned by
+    /// the program and the others by the system program
+    fn with_owned_infos(
+        metas: &[AccountMeta],
+        program_owned: &[Pubkey],
+        check: impl FnOnce(&[AccountInfo]),
+    ) {
+        let program_id = crate::datanexus_program::id();
+        let system_program = Pubkey::default();
+        let mut lamports = vec![0; metas.len()];
+        let infos: Vec<AccountInfo> = metas
+            .iter()
+            .zip(lamports.iter_mut())
+            .map(|(meta, lamports)| {
+                let owner = if program_owned.contains(&meta.pubkey) {
+                    &program_id
+                } else {
+                    &system_program
+                };
+                AccountInfo::new(
+                    &meta.pubkey,
+                    meta.is_signer,
+                    meta.is_writable,
+                    lamports,
+                    &mut [],
+                    owner,
+                    false,
+                    0,
+                )
+            })
+            .collect();
+        check(&infos);
+    }
+
+    fn receipt() -> ReceiptAccounts<Pubkey> {
+        ReceiptAccounts {
+            receipt_mint: Pubkey::new_unique(),
+            receipt_token_account: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+        }
+    }
+
+    fn purchase(
+        referrer_token_account: Option<Pubkey>,
+        receipt: Option<ReceiptAccounts<Pubkey>>,
+        reference_access: Option<Pubkey>,
+    ) -> PurchaseAccessAccounts<Pubkey> {
+        PurchaseAccessAccounts {
+            user_authority: Pubkey::new_unique(),
+            user_access_index: Pubkey::new_unique(),
+            user_access_account: Pubkey::new_unique(),
+            user_token_account: Pubkey::new_unique(),
+            owner_authority: Pubkey::new_unique(),
+            proceeds_account: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            dataset_account: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+            referrer_token_account,
+            receipt,
+            reference_access,
+        }
+    }
+
+    /// Purchases with and without each of the optional accounts
+    fn every_purchase() -> Vec<PurchaseAccessAccounts<Pubkey>> {
+        let mut purchases = Vec::new();
+        for referrer_token_account in [None, Some(Pubkey::new_unique())].iter() {
+            for with_receipt in [false, true].iter() {
+                for reference_access in [None, Some(Pubkey::new_unique())].iter() {
+                    purchases.push(purchase(
+                        *referrer_token_account,
+                        Some(receipt()).filter(|_| *with_receipt),
+                        *reference_access,
+                    ));
+                }
+            }
+        }
+        purchases
+    }
+
+    fn recipient() -> RecipientAccounts<Pubkey> {
+        RecipientAccounts {
+            authority: Pubkey::new_unique(),
+            access_index: Pubkey::new_unique(),
+            access_account: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn init_index_accounts_agree() {
+        let accounts = InitIndexAccounts {
+            payer: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            index_account: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+        };
+
+        with_infos(&accounts.to_account_metas(), |infos| {
+            let parsed = InitIndexAccounts::from_account_infos(infos).unwrap();
+            assert_eq!(parsed.payer.key, &accounts.payer);
+            assert_eq!(parsed.authority.key, &accounts.authority);
+            assert_eq!(parsed.index_account.key, &accounts.index_account);
+            assert_eq!(parsed.system_program.key, &accounts.system_program);
+            assert!(parsed.payer.is_signer);
+        });
+    }
+
+    #[test]
+    fn init_dataset_accounts_agree() {
+        let accounts = InitDatasetAccounts {
+            authority: Pubkey::new_unique(),
+            owner_index: Pubkey::new_unique(),
+            dataset_account: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+            registry: None,
+        };
+
+        with_infos(&accounts.to_account_metas(), |infos| {
+            let parsed = InitDatasetAccounts::from_account_infos(infos).unwrap();
+            assert_eq!(parsed.authority.key, &accounts.authority);
+            assert_eq!(parsed.owner_index.key, &accounts.owner_index);
+            assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+            assert_eq!(parsed.system_program.key, &accounts.system_program);
+            assert!(parsed.registry.is_none());
+            assert!(parsed.authority.is_signer);
+        });
+    }
+
+    #[test]
+    fn init_dataset_registry_accounts_agree() {
+        for overflow_account in [None, Some(Pubkey::new_unique())].iter() {
+            let accounts = InitDatasetAccounts {
+                authority: Pubkey::new_unique(),
+                owner_index: Pubkey::new_unique(),
+                dataset_account: Pubkey::new_unique(),
+                system_program: Pubkey::new_unique(),
+                registry: Some(RegistryAccounts {
+                    registry_account: Pubkey::new_unique(),
+                    overflow_account: *overflow_account,
+                }),
+            };
+
+            with_infos(&accounts.to_account_metas(), |infos| {
+                let parsed = InitDatasetAccounts::from_account_infos(infos).unwrap();
+                let registry = parsed.registry.unwrap();
+                let expected = accounts.registry.as_ref().unwrap();
+                assert_eq!(registry.registry_account.key, &expected.registry_account);
+                assert!(registry.registry_account.is_writable);
+                assert_eq!(
+                    registry.overflow_account.map(|info| *info.key),
+                    expected.overflow_account
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn set_data_params_accounts_agree() {
+        for reference_dataset in [None, Some(Pubkey::new_unique())].iter() {
+            let accounts = SetDataParamsAccounts {
+                authority: Pubkey::new_unique(),
+                dataset_account: Pubkey::new_unique(),
+                reference_dataset: *reference_dataset,
+            };
+
+            with_infos(&accounts.to_account_metas(), |infos| {
+                let parsed = SetDataParamsAccounts::from_account_infos(infos).unwrap();
+                assert_eq!(parsed.authority.key, &accounts.authority);
+                assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+                assert_eq!(
+                    parsed.reference_dataset.map(|info| *info.key),
+                    accounts.reference_dataset
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn purchase_access_accounts_agree() {
+        for accounts in every_purchase().iter() {
+            let mut program_owned = vec![accounts.dataset_account];
+            program_owned.extend(accounts.reference_access);
+            with_owned_infos(&accounts.to_account_metas(), &program_owned, |infos| {
+                let parsed = PurchaseAccessAccounts::from_account_infos(infos).unwrap();
+                assert_eq!(parsed.user_authority.key, &accounts.user_authority);
+                assert_eq!(parsed.user_access_index.key, &accounts.user_access_index);
+                assert_eq!(
+                    parsed.user_access_account.key,
+                    &accounts.user_access_account
+                );
+                assert_eq!(parsed.user_token_account.key, &accounts.user_token_account);
+                assert_eq!(parsed.owner_authority.key, &accounts.owner_authority);
+                assert_eq!(parsed.proceeds_account.key, &accounts.proceeds_account);
+                assert_eq!(parsed.token_mint.key, &accounts.token_mint);
+                assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+                assert_eq!(parsed.token_program.key, &accounts.token_program);
+                assert_eq!(
+                    parsed.referrer_token_account.map(|info| *info.key),
+                    accounts.referrer_token_account
+                );
+                let receipt = parsed
+                    .receipt
+                    .map(|receipt| *receipt.receipt_token_account.key);
+                assert_eq!(
+                    receipt,
+                    accounts
+                        .receipt
+                        .as_ref()
+                        .map(|receipt| receipt.receipt_token_account)
+                );
+                assert_eq!(
+                    parsed.reference_access.map(|info| *info.key),
+                    accounts.reference_access
+                );
+                assert!(parsed.user_authority.is_signer);
+            });
+        }
+    }
+
+    #[test]
+    fn purchase_access_accounts_reject_partial_receipts() {
+        let accounts = purchase(None, Some(receipt()), None);
+        let mut metas = accounts.to_account_metas();
+        metas.pop();
+
+        with_owned_infos(&metas, &[accounts.dataset_account], |infos| {
+            assert_eq!(
+                PurchaseAccessAccounts::from_account_infos(infos).err(),
+                Some(ProgramError::NotEnoughAccountKeys)
+            );
+        });
+    }
+
+    #[test]
+    fn share_access_accounts_agree() {
+        let accounts = ShareAccessAccounts {
+            user_authority: Pubkey::new_unique(),
+            user_access_account: Pubkey::new_unique(),
+            recipient_authority: Pubkey::new_unique(),
+            recipient_access_index: Pubkey::new_unique(),
+            recipient_access_account: Pubkey::new_unique(),
+            dataset_account: Pubkey::new_unique(),
+        };
+
+        with_infos(&accounts.to_account_metas(), |infos| {
+            let parsed = ShareAccessAccounts::from_account_infos(infos).unwrap();
+            assert_eq!(parsed.user_authority.key, &accounts.user_authority);
+            assert_eq!(
+                parsed.user_access_account.key,
+                &accounts.user_access_account
+            );
+            assert_eq!(
+                parsed.recipient_authority.key,
+                &accounts.recipient_authority
+            );
+            assert_eq!(
+                parsed.recipient_access_index.key,
+                &accounts.recipient_access_index
+            );
+            assert_eq!(
+                parsed.recipient_access_account.key,
+                &accounts.recipient_access_account
+            );
+            assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+            assert!(parsed.user_authority.is_signer);
+        });
+    }
+
+    #[test]
+    fn share_access_multi_accounts_agree() {
+        let accounts = ShareAccessMultiAccounts {
+            user_authority: Pubkey::new_unique(),
+            user_access_account: Pubkey::new_unique(),
+            dataset_account: Pubkey::new_unique(),
+            recipients: vec![recipient(), recipient()],
+        };
+
+        with_infos(&accounts.to_account_metas(), |infos| {
+            let parsed = ShareAccessMultiAccounts::from_account_infos(infos).unwrap();
+            assert_eq!(parsed.user_authority.key, &accounts.user_authority);
+            assert_eq!(
+                parsed.user_access_account.key,
+                &accounts.user_access_account
+            );
+            assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+            assert_eq!(parsed.recipients.len(), accounts.recipients.len());
+            for (parsed, recipient) in parsed.recipients.iter().zip(&accounts.recipients) {
+                assert_eq!(parsed.authority.key, &recipient.authority);
+                assert_eq!(parsed.access_index.key, &recipient.access_index);
+                assert_eq!(parsed.access_account.key, &recipient.access_account);
+            }
+        });
+    }
+
+    #[test]
+    fn renew_access_accounts_agree() {
+        for referrer_token_account in [None, Some(Pubkey::new_unique())].iter() {
+            let accounts = RenewAccessAccounts {
+                user_authority: Pubkey::new_unique(),
+                user_access_account: Pubkey::new_unique(),
+                user_token_account: Pubkey::new_unique(),
+                proceeds_account: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                dataset_account: Pubkey::new_unique(),
+                token_program: Pubkey::new_unique(),
+                referrer_token_account: *referrer_token_account,
+            };
+
+            with_infos(&accounts.to_account_metas(), |infos| {
+                let parsed = RenewAccessAccounts::from_account_infos(infos).unwrap();
+                assert_eq!(parsed.user_authority.key, &accounts.user_authority);
+                assert_eq!(
+                    parsed.user_access_account.key,
+                    &accounts.user_access_account
+                );
+                assert_eq!(parsed.user_token_account.key, &accounts.user_token_account);
+                assert_eq!(parsed.proceeds_account.key, &accounts.proceeds_account);
+                assert_eq!(parsed.token_mint.key, &accounts.token_mint);
+                assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+                assert_eq!(parsed.token_program.key, &accounts.token_program);
+                assert_eq!(
+                    parsed.referrer_token_account.map(|info| *info.key),
+                    accounts.referrer_token_account
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn init_associated_access_accounts_agree() {
+        let accounts = InitAssociatedAccessAccounts {
+            payer: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            access_index: Pubkey::new_unique(),
+            associated_access_account: Pubkey::new_unique(),
+            dataset_account: Pubkey::new_unique(),
+            system_program: Pubkey::new_unique(),
+        };
+
+        with_infos(&accounts.to_account_metas(), |infos| {
+            let parsed = InitAssociatedAccessAccounts::from_account_infos(infos).unwrap();
+            assert_eq!(parsed.payer.key, &accounts.payer);
+            assert_eq!(parsed.authority.key, &accounts.authority);
+            assert_eq!(parsed.access_index.key, &accounts.access_index);
+            assert_eq!(
+                parsed.associated_access_account.key,
+                &accounts.associated_access_account
+            );
+            assert_eq!(parsed.dataset_account.key, &accounts.dataset_account);
+            assert_eq!(parsed.system_program.key, &accounts.system_program);
+            assert!(parsed.authority.is_signer);
+        });
+    }
+
+    #[test]
+    fn purchase_account_counts_match_the_builder() {
+        for accounts in every_purchase().iter() {
+            let len = accounts.to_account_metas().len();
+            assert!(PurchaseAccessAccounts::ACCOUNT_COUNT.accepts(len));
+        }
+
+        for len in [8, 15].iter() {
+            assert!(!PurchaseAccessAccounts::ACCOUNT_COUNT.accepts(*len));
+        }
+    }
+
+    #[test]
+    fn recipient_groups_are_counted_whole() {
+        for recipients in 1..4 {
+            let accounts = ShareAccessMultiAccounts {
+                user_authority: Pubkey::new_unique(),
+                user_access_account: Pubkey::new_unique(),
+                dataset_account: Pubkey::new_unique(),
+                recipients: (0..recipients).map(|_| recipient()).collect(),
+            };
+            let len = accounts.to_account_metas().len();
+            assert!(ShareAccessMultiAccounts::ACCOUNT_COUNT.accepts(len));
+            assert!(!ShareAccessMultiAccounts::ACCOUNT_COUNT.accepts(len - 1));
+            assert!(!ShareAccessMultiAccounts::ACCOUNT_COUNT.accepts(len + 1));
+        }
+
+        // At least one recipient
+        assert!(!ShareAccessMultiAccounts::ACCOUNT_COUNT.accepts(3));
+        // Collections are purchased with any number of members
+        assert!(PurchaseCollectionAccounts::ACCOUNT_COUNT.accepts(9));
+        assert!(!PurchaseCollectionAccounts::ACCOUNT_COUNT.accepts(8));
+    }
+
+    #[test]
+    fn account_counts_name_what_they_accept() {
+        assert_eq!(InitIndexAccounts::ACCOUNT_COUNT.to_string(), "4");
+        assert_eq!(
+            PurchaseAccessAccounts::ACCOUNT_COUNT.to_string(),
+            "9, 10, 11, 12, 13 or 14"
+        );
+        assert_eq!(
+            ShareAccessMultiAccounts::ACCOUNT_COUNT.to_string(),
+            "3 plus 1 or more groups of 3"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! Prints the program's IDL as JSON
+//!
+//! `cargo run -p datanexus --features idl --bin datanexus-idl > datanexus.json`
+
+fn main() {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&datanexus::idl::idl()).unwrap()
+    );
+}
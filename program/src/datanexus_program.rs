@@ -1 +1 @@
-declare_id!("dnmeD9A4zfkXS8FDwrE4G5zTNxnv3yLYXiYQCeNVx8f");
+solana_program::declare_id!("dnmeD9A4zfkXS8FDwrE4G5zTNxnv3yLYXiYQCeNVx8f");
@@ -1,5 +1,10 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+    account_info::AccountInfo,
+    entrypoint,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+    pubkey::Pubkey,
 };
 
 use crate::{error::DataNexusError, processor::Processor};
@@ -7,14 +12,18 @@ use crate::{error::DataNexusError, processor::Processor};
 entrypoint!(datanexus_entrypoint);
 
 pub fn datanexus_entrypoint(
-    program_id: Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    msg!("DataNexus Entrypoint");
-
-    if let Err(error) = Processor::process_instruction(program_id, accounts, data) {
-        print.error::<DataNexusError>();
+    if let Err(error) = Processor::process_instruction(*program_id, accounts, data) {
+        match error {
+            ProgramError::Custom(code) => match DataNexusError::from_code(code) {
+                Some(error) => error.print::<DataNexusError>(),
+                None => msg!("Unknown error code {}", code),
+            },
+            _ => msg!("{}", error),
+        }
         return Err(error);
     }
 
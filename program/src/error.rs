@@ -9,10 +9,129 @@ use num_traits::FromPrimitive as FromPrimitiveTrait;
 
 use thiserror::Error;
 
+/// Errors returned by the DataNexus program
+///
+/// Discriminants are part of the on-chain interface, new variants must be
+/// appended with the next free number and existing numbers never reused
 #[derive(Clone, Copy, Debug, Error, Eq, FromPrimitive, PartialEq)]
 pub enum DataNexusError {
     #[error("Invalid Instruction")]
-    InvalidInstruction,
+    InvalidInstruction = 0,
+    #[error("Dataset Has Holders")]
+    DatasetHasHolders = 1,
+    #[error("Referrer Mint Mismatch")]
+    ReferrerMintMismatch = 2,
+    #[error("Invalid Basis Points")]
+    InvalidBasisPoints = 3,
+    #[error("No Existing Access")]
+    NoExistingAccess = 4,
+    #[error("Access Not Renewable")]
+    AccessNotRenewable = 5,
+    #[error("Insufficient Payment")]
+    InsufficientPayment = 6,
+    #[error("Share Limit Exceeded")]
+    ShareLimitExceeded = 7,
+    #[error("Index Full")]
+    IndexFull = 8,
+    #[error("Metadata Too Long")]
+    MetadataTooLong = 9,
+    #[error("Invalid UTF-8 Metadata")]
+    InvalidUtf8 = 10,
+    #[error("Numerical Overflow")]
+    NumericalOverflow = 11,
+    #[error("Not Rent Exempt")]
+    NotRentExempt = 12,
+    #[error("Account Not Initialized")]
+    AccountNotInitialized = 13,
+    #[error("Account Already Initialized")]
+    AccountAlreadyInitialized = 14,
+    #[error("Invalid Account Owner")]
+    InvalidAccountOwner = 15,
+    #[error("Invalid Seeds")]
+    InvalidSeeds = 16,
+    #[error("Missing Signer")]
+    MissingSigner = 17,
+    #[error("Incorrect Owner")]
+    IncorrectOwner = 18,
+    #[error("Access Already Granted")]
+    AccessAlreadyGranted = 19,
+    #[error("Access Not Found")]
+    AccessNotFound = 20,
+    #[error("Dataset Frozen")]
+    DatasetFrozen = 21,
+    #[error("Invalid Reference Dataset")]
+    InvalidReferenceDataset = 22,
+    #[error("Hash Mismatch")]
+    HashMismatch = 23,
+    #[error("Dataset Not Priced")]
+    DatasetNotPriced = 24,
+    #[error("Not Resharable")]
+    NotResharable = 25,
+    #[error("Share Depth Exceeded")]
+    ShareDepthExceeded = 26,
+    #[error("Resize Too Large")]
+    ResizeTooLarge = 27,
+    #[error("Collection Full")]
+    CollectionFull = 28,
+    #[error("Already In Collection")]
+    AlreadyInCollection = 29,
+    #[error("Collection Member Mismatch")]
+    CollectionMemberMismatch = 30,
+    #[error("Invalid Price Tiers")]
+    InvalidPriceTiers = 31,
+    #[error("Insufficient Proceeds")]
+    InsufficientProceeds = 32,
+    #[error("Outdated Layout")]
+    OutdatedLayout = 33,
+    #[error("Vault Limit Reached")]
+    VaultLimitReached = 34,
+    #[error("Unclaimed Proceeds")]
+    UnclaimedProceeds = 35,
+    #[error("Self Referral")]
+    SelfReferral = 36,
+    #[error("Access Expired")]
+    AccessExpired = 37,
+    #[error("Max Holders Reached")]
+    MaxHoldersReached = 38,
+    #[error("No Pending Transfer")]
+    NoPendingTransfer = 39,
+    #[error("Unsupported Mint")]
+    UnsupportedMint = 40,
+    #[error("Price Table Full")]
+    PriceTableFull = 41,
+    #[error("Sale Not Active")]
+    SaleNotActive = 42,
+    #[error("Access Not Expired")]
+    AccessNotExpired = 43,
+    #[error("Share Fee Required")]
+    ShareFeeRequired = 44,
+    #[error("Registry Full")]
+    RegistryFull = 45,
+    #[error("Tags Full")]
+    TagsFull = 46,
+    #[error("Duplicate Tag")]
+    DuplicateTag = 47,
+    #[error("Tag Not Found")]
+    TagNotFound = 48,
+    #[error("Invalid Tag")]
+    InvalidTag = 49,
+    #[error("Invalid Account Count")]
+    InvalidAccountCount = 50,
+    #[error("Cannot Share To Self")]
+    CannotShareToSelf = 51,
+    #[error("Owner Cannot Purchase")]
+    OwnerCannotPurchase = 52,
+    #[error("No Pending Share")]
+    NoPendingShare = 53,
+    #[error("Receipt Required")]
+    ReceiptRequired = 54,
+}
+
+impl DataNexusError {
+    /// Maps a `ProgramError::Custom` code back to its `DataNexusError`
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::from_u32(code)
+    }
 }
 
 impl From<DataNexusError> for ProgramError {
@@ -34,6 +153,60 @@ impl PrintProgramError for DataNexusError {
     {
         match self {
             DataNexusError::InvalidInstruction => msg!("Invalid Instruction"),
+            DataNexusError::DatasetHasHolders => msg!("Dataset Has Holders"),
+            DataNexusError::ReferrerMintMismatch => msg!("Referrer Mint Mismatch"),
+            DataNexusError::InvalidBasisPoints => msg!("Invalid Basis Points"),
+            DataNexusError::NoExistingAccess => msg!("No Existing Access"),
+            DataNexusError::AccessNotRenewable => msg!("Access Not Renewable"),
+            DataNexusError::InsufficientPayment => msg!("Insufficient Payment"),
+            DataNexusError::ShareLimitExceeded => msg!("Share Limit Exceeded"),
+            DataNexusError::IndexFull => msg!("Index Full"),
+            DataNexusError::MetadataTooLong => msg!("Metadata Too Long"),
+            DataNexusError::InvalidUtf8 => msg!("Invalid UTF-8 Metadata"),
+            DataNexusError::NumericalOverflow => msg!("Numerical Overflow"),
+            DataNexusError::NotRentExempt => msg!("Not Rent Exempt"),
+            DataNexusError::AccountNotInitialized => msg!("Account Not Initialized"),
+            DataNexusError::AccountAlreadyInitialized => msg!("Account Already Initialized"),
+            DataNexusError::InvalidAccountOwner => msg!("Invalid Account Owner"),
+            DataNexusError::InvalidSeeds => msg!("Invalid Seeds"),
+            DataNexusError::MissingSigner => msg!("Missing Signer"),
+            DataNexusError::IncorrectOwner => msg!("Incorrect Owner"),
+            DataNexusError::AccessAlreadyGranted => msg!("Access Already Granted"),
+            DataNexusError::AccessNotFound => msg!("Access Not Found"),
+            DataNexusError::DatasetFrozen => msg!("Dataset Frozen"),
+            DataNexusError::InvalidReferenceDataset => msg!("Invalid Reference Dataset"),
+            DataNexusError::HashMismatch => msg!("Hash Mismatch"),
+            DataNexusError::DatasetNotPriced => msg!("Dataset Not Priced"),
+            DataNexusError::NotResharable => msg!("Not Resharable"),
+            DataNexusError::ShareDepthExceeded => msg!("Share Depth Exceeded"),
+            DataNexusError::ResizeTooLarge => msg!("Resize Too Large"),
+            DataNexusError::CollectionFull => msg!("Collection Full"),
+            DataNexusError::AlreadyInCollection => msg!("Already In Collection"),
+            DataNexusError::CollectionMemberMismatch => msg!("Collection Member Mismatch"),
+            DataNexusError::InvalidPriceTiers => msg!("Invalid Price Tiers"),
+            DataNexusError::InsufficientProceeds => msg!("Insufficient Proceeds"),
+            DataNexusError::OutdatedLayout => msg!("Outdated Layout"),
+            DataNexusError::VaultLimitReached => msg!("Vault Limit Reached"),
+            DataNexusError::UnclaimedProceeds => msg!("Unclaimed Proceeds"),
+            DataNexusError::SelfReferral => msg!("Self Referral"),
+            DataNexusError::AccessExpired => msg!("Access Expired"),
+            DataNexusError::MaxHoldersReached => msg!("Max Holders Reached"),
+            DataNexusError::NoPendingTransfer => msg!("No Pending Transfer"),
+            DataNexusError::UnsupportedMint => msg!("Unsupported Mint"),
+            DataNexusError::PriceTableFull => msg!("Price Table Full"),
+            DataNexusError::SaleNotActive => msg!("Sale Not Active"),
+            DataNexusError::AccessNotExpired => msg!("Access Not Expired"),
+            DataNexusError::ShareFeeRequired => msg!("Share Fee Required"),
+            DataNexusError::RegistryFull => msg!("Registry Full"),
+            DataNexusError::TagsFull => msg!("Tags Full"),
+            DataNexusError::DuplicateTag => msg!("Duplicate Tag"),
+            DataNexusError::TagNotFound => msg!("Tag Not Found"),
+            DataNexusError::InvalidTag => msg!("Invalid Tag"),
+            DataNexusError::InvalidAccountCount => msg!("Invalid Account Count"),
+            DataNexusError::CannotShareToSelf => msg!("Cannot Share To Self"),
+            DataNexusError::OwnerCannotPurchase => msg!("Owner Cannot Purchase"),
+            DataNexusError::NoPendingShare => msg!("No Pending Share"),
+            DataNexusError::ReceiptRequired => msg!("Receipt Required"),
         }
     }
 }
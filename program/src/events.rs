@@ -0,0 +1,59 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{clock::Slot, log::sol_log_data, pubkey::Pubkey};
+
+/// Emitted when access to a dataset is purchased
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PurchaseEvent {
+    pub dataset: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub slot: Slot,
+}
+
+/// Emitted when a holder shares access to a dataset
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ShareEvent {
+    pub dataset: Pubkey,
+    pub sharer: Pubkey,
+    pub recipient: Pubkey,
+    pub slot: Slot,
+}
+
+/// Emitted when an owner updates dataset parameters
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ParamsUpdatedEvent {
+    pub dataset: Pubkey,
+    pub authority: Pubkey,
+    pub slot: Slot,
+}
+
+/// Emitted when a holder's access to a dataset is revoked
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct AccessRevokedEvent {
+    pub dataset: Pubkey,
+    pub holder: Pubkey,
+    pub slot: Slot,
+}
+
+/// Events logged by the program, the borsh variant index acts as the
+/// event discriminator
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum DataNexusEvent {
+    Purchase(PurchaseEvent),
+    Share(ShareEvent),
+    ParamsUpdated(ParamsUpdatedEvent),
+    AccessRevoked(AccessRevokedEvent),
+    /// A share held for the recipient's approval, `Share` follows once it
+    /// is accepted
+    SharePending(ShareEvent),
+}
+
+impl DataNexusEvent {
+    /// Writes the serialized event to the program log
+    pub fn emit(&self) {
+        if let Ok(data) = self.try_to_vec() {
+            sol_log_data(&[&data]);
+        }
+    }
+}
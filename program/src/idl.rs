@@ -0,0 +1,1144 @@
+//! Anchor-style IDL describing the program's interface
+//!
+//! Instructions are described in their borsh encoding, which is what
+//! TypeScript clients build, and their account lists are read back from the
+//! builders in `instruction` so they follow any change to the ordering in
+//! `accounts`. Account layouts describe the `Pack` encoding, whose optional
+//! fields are stored as all-zero values rather than borsh options.
+
+use serde_json::{json, Value};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+
+use crate::{
+    accounts::{
+        AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitDatasetAccounts, InitIndexAccounts,
+        InitReceiptMintAccounts, InitVaultAccounts, MemberAccounts, MigrateAccountAccounts,
+        OwnershipTransferAccounts, PurchaseAccessAccounts, PurchaseCollectionAccounts,
+        ReceiptAccounts, RecipientAccounts, RegistryAccounts, RejectShareAccounts,
+        RenewAccessAccounts, ResizeIndexAccounts, RevokeAccessAccounts,
+        SetAccessPreferencesAccounts, SetDataParamsAccounts, ShareAccessAccounts,
+        ShareAccessMultiAccounts, ShareFeeAccounts, WithdrawProceedsAccounts, RECEIPT_ACCOUNTS_LEN,
+        SHARE_FEE_ACCOUNTS_LEN,
+    },
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{tag, BORSH_ENCODING},
+};
+
+/// Field and argument types, named as in Anchor IDLs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Type {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I64,
+    PublicKey,
+    /// Borsh byte string with a `u32` length prefix
+    Bytes,
+    Array(&'static Type, usize),
+    /// Borsh vector with a `u32` length prefix
+    Vec(&'static Type),
+    Option(&'static Type),
+    Defined(&'static str),
+}
+
+const HASH: Type = Type::Array(&Type::U8, 32);
+
+/// Doc of the instructions a multisig authority can sign through its members
+const MULTISIG_SIGNERS: &str =
+    "A multisig authority leaves itself unsigned and is followed by its signing members";
+
+impl Type {
+    /// Encoded size in bytes, `None` for variable length types
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Type::Bool | Type::U8 => Some(1),
+            Type::U16 => Some(2),
+            Type::U32 => Some(4),
+            Type::U64 | Type::I64 => Some(8),
+            Type::PublicKey => Some(32),
+            Type::Bytes | Type::Vec(_) | Type::Option(_) => None,
+            Type::Array(ty, len) => ty.size().and_then(|size| size.checked_mul(*len)),
+            Type::Defined(name) => type_def(name)?.size(),
+        }
+    }
+
+    fn to_json(self) -> Value {
+        match self {
+            Type::Bool => json!("bool"),
+            Type::U8 => json!("u8"),
+            Type::U16 => json!("u16"),
+            Type::U32 => json!("u32"),
+            Type::U64 => json!("u64"),
+            Type::I64 => json!("i64"),
+            Type::PublicKey => json!("publicKey"),
+            Type::Bytes => json!("bytes"),
+            Type::Array(ty, len) => json!({ "array": [ty.to_json(), len] }),
+            Type::Vec(ty) => json!({ "vec": ty.to_json() }),
+            Type::Option(ty) => json!({ "option": ty.to_json() }),
+            Type::Defined(name) => json!({ "defined": name }),
+        }
+    }
+}
+
+pub enum Kind {
+    Struct(&'static [(&'static str, Type)]),
+    /// Variants with their tuple fields
+    Enum(&'static [(&'static str, &'static [Type])]),
+}
+
+pub struct TypeDef {
+    pub name: &'static str,
+    pub docs: &'static [&'static str],
+    pub kind: Kind,
+}
+
+impl TypeDef {
+    /// Encoded size in bytes, enums only have one when no variant has fields
+    pub fn size(&self) -> Option<usize> {
+        match self.kind {
+            Kind::Struct(fields) => fields.iter().map(|(_, ty)| ty.size()).sum(),
+            Kind::Enum(variants) if variants.iter().all(|(_, fields)| fields.is_empty()) => Some(1),
+            Kind::Enum(_) => None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let ty = match self.kind {
+            Kind::Struct(fields) => json!({
+                "kind": "struct",
+                "fields": fields
+                    .iter()
+                    .map(|(name, ty)| json!({ "name": name, "type": ty.to_json() }))
+                    .collect::<Vec<_>>(),
+            }),
+            Kind::Enum(variants) => json!({
+                "kind": "enum",
+                "variants": variants
+                    .iter()
+                    .map(|(name, fields)| match fields {
+                        [] => json!({ "name": name }),
+                        fields => json!({
+                            "name": name,
+                            "fields": fields.iter().copied().map(Type::to_json).collect::<Vec<_>>(),
+                        }),
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+        };
+
+        json!({ "name": self.name, "docs": self.docs, "type": ty })
+    }
+}
+
+/// Layouts of the program's accounts
+pub const ACCOUNTS: &[TypeDef] = &[
+    TypeDef {
+        name: "AccountIndex",
+        docs: &[
+            "Owner or access index, the first `count` slots are in use",
+            "Created with 128 slots, `resizeIndex` grows `datasets` to `capacity` slots and `compactIndex` shrinks it back",
+            "Version 1 indexes lack `requireShareApproval` and must be migrated to set it",
+        ],
+        kind: Kind::Struct(&[
+            ("version", Type::U8),
+            ("isInitialized", Type::Bool),
+            ("pointer", Type::PublicKey),
+            ("count", Type::U16),
+            ("capacity", Type::U16),
+            ("requireShareApproval", Type::Bool),
+            (
+                "datasets",
+                Type::Array(
+                    &Type::Defined("IndexEntry"),
+                    crate::state::MAX_INDEX_ENTRIES,
+                ),
+            ),
+        ]),
+    },
+    TypeDef {
+        name: "DatasetState",
+        docs: &[
+            "Unset key, value, share limit, duration, reference, depth limit, quota, pending owner, sale start and end and share fee are all-zero",
+            "Datasets written before `version` are 538 bytes, version 1 ones 539, version 2 ones 547, version 3 ones 579, version 4 ones 740, version 5 ones 756, version 6 ones 772, version 7 ones 812, version 8 ones 940 and version 9 ones 942, and must be migrated",
+            "Only the first `tierCount` tiers, `vaultCount` claimable entries and `priceCount` prices are in use",
+            "Tags in use fill the first slots from offset 812, the rest are all-zero",
+        ],
+        kind: Kind::Struct(&[
+            ("version", Type::U8),
+            ("isInitialized", Type::Bool),
+            ("flag", Type::Defined("AccountFlag")),
+            ("owner", Type::PublicKey),
+            ("hash", HASH),
+            ("key", HASH),
+            ("value", Type::U64),
+            ("shareLimit", Type::U16),
+            ("purchaseCount", Type::U32),
+            ("referralBps", Type::U16),
+            ("accessDuration", Type::I64),
+            ("uri", Type::Array(&Type::U8, crate::state::URI_LEN)),
+            ("sizeBytes", Type::U64),
+            (
+                "contentType",
+                Type::Array(&Type::U8, crate::state::CONTENT_TYPE_LEN),
+            ),
+            ("totalRevenue", Type::U64),
+            ("shareCount", Type::U32),
+            ("refData", Type::PublicKey),
+            ("resharable", Type::Bool),
+            ("maxShareDepth", Type::U8),
+            ("tierCount", Type::U8),
+            (
+                "tiers",
+                Type::Array(&Type::Defined("PriceTier"), crate::state::MAX_PRICE_TIERS),
+            ),
+            ("vaultCount", Type::U8),
+            (
+                "claimable",
+                Type::Array(&Type::Defined("Claimable"), crate::state::MAX_VAULTS),
+            ),
+            ("holderCount", Type::U32),
+            ("maxHolders", Type::U32),
+            ("pendingOwner", Type::PublicKey),
+            ("priceCount", Type::U8),
+            (
+                "prices",
+                Type::Array(&Type::Defined("MintPrice"), crate::state::MAX_PRICES),
+            ),
+            ("saleStart", Type::I64),
+            ("saleEnd", Type::I64),
+            ("pruneBounty", Type::U64),
+            ("bountyPool", Type::U64),
+            ("shareFee", Type::Defined("MintPrice")),
+            (
+                "tags",
+                Type::Array(
+                    &Type::Array(&Type::U8, crate::state::TAG_LEN),
+                    crate::state::MAX_TAGS,
+                ),
+            ),
+            ("derivedDiscountBps", Type::U16),
+            ("receiptCount", Type::U32),
+        ]),
+    },
+    TypeDef {
+        name: "AccessState",
+        docs: &[
+            "Only the first `count` entries and `pendingCount` pending shares are in use",
+            "Accounts written before `version` are 1746 bytes, version 1 accounts 1747 bytes and version 2 accounts 1779 bytes, all must be migrated",
+        ],
+        kind: Kind::Struct(&[
+            ("version", Type::U8),
+            ("isInitialized", Type::Bool),
+            ("pointer", Type::PublicKey),
+            ("count", Type::U8),
+            (
+                "datasets",
+                Type::Array(
+                    &Type::Defined("AccessInfo"),
+                    crate::state::MAX_ACCESS_ENTRIES,
+                ),
+            ),
+            ("pendingCount", Type::U8),
+            (
+                "pending",
+                Type::Array(
+                    &Type::Defined("PendingShare"),
+                    crate::state::MAX_PENDING_SHARES,
+                ),
+            ),
+        ]),
+    },
+    TypeDef {
+        name: "CollectionState",
+        docs: &[
+            "Only the first `count` members are in use, an unset value is zero",
+            "`flag` is always `Collection`",
+        ],
+        kind: Kind::Struct(&[
+            ("isInitialized", Type::Bool),
+            ("flag", Type::Defined("AccountFlag")),
+            ("owner", Type::PublicKey),
+            ("hash", HASH),
+            ("value", Type::U64),
+            ("count", Type::U16),
+            (
+                "members",
+                Type::Array(&Type::PublicKey, crate::state::MAX_COLLECTION_MEMBERS),
+            ),
+        ]),
+    },
+    TypeDef {
+        name: "CollectionAccess",
+        docs: &["The next member granted is `members[cursor]` of the collection"],
+        kind: Kind::Struct(&[
+            ("isInitialized", Type::Bool),
+            ("collection", Type::PublicKey),
+            ("purchased", Type::Bool),
+            ("cursor", Type::U16),
+        ]),
+    },
+    TypeDef {
+        name: "RegistryState",
+        docs: &[
+            "Page of the datasets whose hash starts with `prefix`, the first `count` entries are in use",
+            "Created with one entry and grown by one with each dataset listed, up to 128 before `next` links to the overflow page",
+            "`flag` is always `Registry`",
+        ],
+        kind: Kind::Struct(&[
+            ("version", Type::U8),
+            ("isInitialized", Type::Bool),
+            ("flag", Type::Defined("AccountFlag")),
+            ("prefix", Type::Array(&Type::U8, 2)),
+            ("page", Type::U8),
+            ("next", Type::PublicKey),
+            ("count", Type::U16),
+            (
+                "entries",
+                Type::Array(
+                    &Type::Defined("RegistryEntry"),
+                    crate::state::MAX_REGISTRY_ENTRIES,
+                ),
+            ),
+        ]),
+    },
+];
+
+/// Types referenced by accounts and instruction arguments
+pub const TYPES: &[TypeDef] = &[
+    TypeDef {
+        name: "AccountFlag",
+        docs: &[],
+        kind: Kind::Enum(&[
+            ("Access", &[]),
+            ("Dataset", &[]),
+            ("Collection", &[]),
+            ("Registry", &[]),
+        ]),
+    },
+    TypeDef {
+        name: "FieldMask",
+        docs: &["Bits key 1, value 2, shareLimit 4, refData 8 and uri 16"],
+        kind: Kind::Struct(&[("bits", Type::U8)]),
+    },
+    TypeDef {
+        name: "IndexEntry",
+        docs: &[],
+        kind: Kind::Struct(&[("dataset", Type::PublicKey), ("hash", HASH)]),
+    },
+    TypeDef {
+        name: "RegistryEntry",
+        docs: &[],
+        kind: Kind::Struct(&[("hash", HASH), ("dataset", Type::PublicKey)]),
+    },
+    TypeDef {
+        name: "AccessInfo",
+        docs: &[
+            "Key, sharer and expiry are all-zero when unset",
+            "Shares left are `shareQuota` less `sharesUsed`",
+        ],
+        kind: Kind::Struct(&[
+            ("hash", HASH),
+            ("key", HASH),
+            ("sharedFrom", Type::PublicKey),
+            ("shareQuota", Type::U16),
+            ("expiresAt", Type::I64),
+            ("depth", Type::U8),
+            ("sharesUsed", Type::U16),
+        ]),
+    },
+    TypeDef {
+        name: "PendingShare",
+        docs: &["Share awaiting the recipient's approval, `access.sharedFrom` made it and it lapses a week after `sharedAt`"],
+        kind: Kind::Struct(&[
+            ("access", Type::Defined("AccessInfo")),
+            ("sharedAt", Type::I64),
+        ]),
+    },
+    TypeDef {
+        name: "PriceTier",
+        docs: &["Unit price of seat purchases of at least `minSeats` seats"],
+        kind: Kind::Struct(&[("minSeats", Type::U16), ("unitPrice", Type::U64)]),
+    },
+    TypeDef {
+        name: "Claimable",
+        docs: &["Proceeds in `mint` held in the dataset's vault of it"],
+        kind: Kind::Struct(&[("mint", Type::PublicKey), ("amount", Type::U64)]),
+    },
+    TypeDef {
+        name: "MintPrice",
+        docs: &["Price of the dataset when paid in `mint`"],
+        kind: Kind::Struct(&[("mint", Type::PublicKey), ("amount", Type::U64)]),
+    },
+    TypeDef {
+        name: "AccountType",
+        docs: &[],
+        kind: Kind::Enum(&[
+            ("DatasetIndex", &[]),
+            ("AccessIndex", &[]),
+            ("Dataset", &[HASH]),
+            ("Access", &[HASH]),
+        ]),
+    },
+    TypeDef {
+        name: "Params",
+        docs: &[],
+        kind: Kind::Enum(&[
+            (
+                "Init",
+                &[HASH, Type::U64, Type::U16, Type::Option(&Type::PublicKey)],
+            ),
+            ("Key", &[HASH]),
+            ("Value", &[Type::U64]),
+            ("ShareLimit", &[Type::U16]),
+            ("ReferenceData", &[Type::PublicKey]),
+            ("ReferralBps", &[Type::U16]),
+            ("AccessDuration", &[Type::I64]),
+            ("Uri", &[Type::Bytes]),
+            ("Size", &[Type::U64]),
+            ("ContentType", &[Type::Bytes]),
+            ("Clear", &[Type::Defined("FieldMask")]),
+            ("Resharable", &[Type::Bool]),
+            ("MaxShareDepth", &[Type::U8]),
+            ("Tiers", &[Type::Vec(&Type::Defined("PriceTier"))]),
+            ("MaxHolders", &[Type::U32]),
+            ("SetPrice", &[Type::PublicKey, Type::U64]),
+            ("RemovePrice", &[Type::PublicKey]),
+            ("SaleWindow", &[Type::I64, Type::Option(&Type::I64)]),
+            ("PruneBounty", &[Type::U64]),
+            ("ShareFee", &[Type::PublicKey, Type::U64]),
+            ("AddTag", &[Type::Array(&Type::U8, crate::state::TAG_LEN)]),
+            (
+                "RemoveTag",
+                &[Type::Array(&Type::U8, crate::state::TAG_LEN)],
+            ),
+            ("DerivedDiscountBps", &[Type::U16]),
+        ]),
+    },
+];
+
+/// Looks up an account layout or type by name
+pub fn type_def(name: &str) -> Option<&'static TypeDef> {
+    ACCOUNTS.iter().chain(TYPES).find(|def| def.name == name)
+}
+
+/// Hands out a distinct placeholder key per account name, so the metas a
+/// builder returns can be mapped back to the names they were built from
+#[derive(Default)]
+struct Names(Vec<&'static str>);
+
+impl Names {
+    fn key(&mut self, name: &'static str) -> Pubkey {
+        self.0.push(name);
+        Pubkey::new_from_array([self.0.len() as u8; 32])
+    }
+
+    fn accounts(&self, metas: &[AccountMeta], optional: &[&str]) -> Vec<Value> {
+        metas
+            .iter()
+            .map(|meta| {
+                let name = self
+                    .0
+                    .get(usize::from(meta.pubkey.to_bytes()[0]).saturating_sub(1))
+                    .copied()
+                    .expect("account keys are named by `Names::key`");
+                json!({
+                    "name": name,
+                    "isMut": meta.is_writable,
+                    "isSigner": meta.is_signer,
+                    "isOptional": optional.contains(&name),
+                })
+            })
+            .collect()
+    }
+}
+
+/// An instruction variant, `accounts` fills in the account list of the
+/// builder creating it
+pub struct InstructionDef {
+    pub name: &'static str,
+    pub tag: u8,
+    pub docs: &'static [&'static str],
+    pub args: &'static [(&'static str, Type)],
+    accounts: fn() -> Vec<Value>,
+}
+
+impl InstructionDef {
+    /// Leading bytes of the instruction data, the encoding byte followed by
+    /// the borsh variant index
+    pub fn discriminant(&self) -> [u8; 2] {
+        [BORSH_ENCODING, self.tag]
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "docs": self.docs,
+            "discriminant": {
+                "type": Type::Array(&Type::U8, 2).to_json(),
+                "value": self.discriminant(),
+            },
+            "accounts": (self.accounts)(),
+            "args": self
+                .args
+                .iter()
+                .map(|(name, ty)| json!({ "name": name, "type": ty.to_json() }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn init_index_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = InitIndexAccounts {
+        payer: names.key("payer"),
+        authority: names.key("authority"),
+        index_account: names.key("indexAccount"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn init_dataset_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = InitDatasetAccounts {
+        authority: names.key("authority"),
+        owner_index: names.key("ownerIndex"),
+        dataset_account: names.key("datasetAccount"),
+        system_program: names.key("systemProgram"),
+        registry: Some(RegistryAccounts {
+            registry_account: names.key("registryAccount"),
+            overflow_account: Some(names.key("overflowAccount")),
+        }),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &["registryAccount", "overflowAccount"])
+}
+
+fn set_data_params_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = SetDataParamsAccounts {
+        authority: names.key("authority"),
+        dataset_account: names.key("datasetAccount"),
+        reference_dataset: Some(names.key("referenceDataset")),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &["referenceDataset"])
+}
+
+fn purchase_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = PurchaseAccessAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_index: names.key("userAccessIndex"),
+        user_access_account: names.key("userAccessAccount"),
+        user_token_account: names.key("userTokenAccount"),
+        owner_authority: names.key("ownerAuthority"),
+        proceeds_account: names.key("proceedsAccount"),
+        token_mint: names.key("tokenMint"),
+        dataset_account: names.key("datasetAccount"),
+        token_program: names.key("tokenProgram"),
+        referrer_token_account: Some(names.key("referrerTokenAccount")),
+        receipt: Some(receipt_accounts(&mut names)),
+        reference_access: Some(names.key("referenceAccess")),
+    }
+    .to_account_metas();
+    let mut optional = vec!["referrerTokenAccount"];
+    optional.extend_from_slice(&RECEIPT_ACCOUNTS);
+    optional.push("referenceAccess");
+    names.accounts(&metas, &optional)
+}
+
+/// The optional receipt group, which is passed whole or not at all
+const RECEIPT_ACCOUNTS: [&str; RECEIPT_ACCOUNTS_LEN] =
+    ["receiptMint", "receiptTokenAccount", "receiptTokenProgram"];
+
+fn receipt_accounts(names: &mut Names) -> ReceiptAccounts<Pubkey> {
+    ReceiptAccounts {
+        receipt_mint: names.key("receiptMint"),
+        receipt_token_account: names.key("receiptTokenAccount"),
+        token_program: names.key("receiptTokenProgram"),
+    }
+}
+
+fn share_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = ShareAccessAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_account: names.key("userAccessAccount"),
+        recipient_authority: names.key("recipientAuthority"),
+        recipient_access_index: names.key("recipientAccessIndex"),
+        recipient_access_account: names.key("recipientAccessAccount"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    let fee_metas = ShareFeeAccounts {
+        sharer_token_account: names.key("userTokenAccount"),
+        owner_token_account: names.key("ownerTokenAccount"),
+        token_mint: names.key("feeMint"),
+        token_program: names.key("tokenProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&[metas, fee_metas].concat(), &SHARE_FEE_ACCOUNTS)
+}
+
+/// The share fee group, passed whole when the dataset charges a fee
+const SHARE_FEE_ACCOUNTS: [&str; SHARE_FEE_ACCOUNTS_LEN] = [
+    "userTokenAccount",
+    "ownerTokenAccount",
+    "feeMint",
+    "tokenProgram",
+];
+
+fn close_dataset_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = CloseDatasetAccounts {
+        authority: names.key("authority"),
+        owner_index: names.key("ownerIndex"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn renew_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = RenewAccessAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_account: names.key("userAccessAccount"),
+        user_token_account: names.key("userTokenAccount"),
+        proceeds_account: names.key("proceedsAccount"),
+        token_mint: names.key("tokenMint"),
+        dataset_account: names.key("datasetAccount"),
+        token_program: names.key("tokenProgram"),
+        referrer_token_account: Some(names.key("referrerTokenAccount")),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &["referrerTokenAccount"])
+}
+
+fn share_access_multi_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = ShareAccessMultiAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_account: names.key("userAccessAccount"),
+        dataset_account: names.key("datasetAccount"),
+        recipients: vec![RecipientAccounts {
+            authority: names.key("recipientAuthority"),
+            access_index: names.key("recipientAccessIndex"),
+            access_account: names.key("recipientAccessAccount"),
+        }],
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn init_associated_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = InitAssociatedAccessAccounts {
+        payer: names.key("payer"),
+        authority: names.key("authority"),
+        access_index: names.key("accessIndex"),
+        associated_access_account: names.key("associatedAccessAccount"),
+        dataset_account: names.key("datasetAccount"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn resize_index_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = ResizeIndexAccounts {
+        payer: names.key("payer"),
+        authority: names.key("authority"),
+        index_account: names.key("indexAccount"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn purchase_collection_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = PurchaseCollectionAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_index: names.key("userAccessIndex"),
+        collection_access_account: names.key("collectionAccessAccount"),
+        user_token_account: names.key("userTokenAccount"),
+        owner_authority: names.key("ownerAuthority"),
+        owner_token_account: names.key("ownerTokenAccount"),
+        token_mint: names.key("tokenMint"),
+        collection_account: names.key("collectionAccount"),
+        token_program: names.key("tokenProgram"),
+        members: vec![MemberAccounts {
+            dataset_account: names.key("memberDataset"),
+            access_account: names.key("memberAccessAccount"),
+        }],
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn create_collection_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = CreateCollectionAccounts {
+        authority: names.key("authority"),
+        collection_account: names.key("collectionAccount"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn add_to_collection_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = AddToCollectionAccounts {
+        authority: names.key("authority"),
+        collection_account: names.key("collectionAccount"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn claim_collection_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = ClaimCollectionAccessAccounts {
+        user_authority: names.key("userAuthority"),
+        user_access_index: names.key("userAccessIndex"),
+        collection_access_account: names.key("collectionAccessAccount"),
+        collection_account: names.key("collectionAccount"),
+        members: vec![MemberAccounts {
+            dataset_account: names.key("memberDataset"),
+            access_account: names.key("memberAccessAccount"),
+        }],
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn init_vault_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = InitVaultAccounts {
+        payer: names.key("payer"),
+        owner_authority: names.key("ownerAuthority"),
+        dataset_account: names.key("datasetAccount"),
+        token_mint: names.key("tokenMint"),
+        vault_account: names.key("vaultAccount"),
+        token_program: names.key("tokenProgram"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn withdraw_proceeds_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = WithdrawProceedsAccounts {
+        owner_authority: names.key("ownerAuthority"),
+        dataset_account: names.key("datasetAccount"),
+        vault_account: names.key("vaultAccount"),
+        token_mint: names.key("tokenMint"),
+        destination_token_account: names.key("destinationTokenAccount"),
+        token_program: names.key("tokenProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn init_receipt_mint_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = InitReceiptMintAccounts {
+        payer: names.key("payer"),
+        dataset_account: names.key("datasetAccount"),
+        receipt_mint: names.key("receiptMint"),
+        token_program: names.key("tokenProgram"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn revoke_access_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = RevokeAccessAccounts {
+        owner_authority: names.key("ownerAuthority"),
+        dataset_account: names.key("datasetAccount"),
+        holder_authority: names.key("holderAuthority"),
+        holder_access_account: names.key("holderAccessAccount"),
+        receipt: Some(receipt_accounts(&mut names)),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &RECEIPT_ACCOUNTS)
+}
+
+fn migrate_account_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = MigrateAccountAccounts {
+        payer: names.key("payer"),
+        account: names.key("account"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn ownership_transfer_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = OwnershipTransferAccounts {
+        authority: names.key("authority"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn accept_ownership_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = AcceptOwnershipAccounts {
+        authority: names.key("authority"),
+        old_owner_index: names.key("oldOwnerIndex"),
+        new_owner_index: names.key("newOwnerIndex"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn crank_prune_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = CrankPruneAccounts {
+        cranker: names.key("cranker"),
+        dataset_account: names.key("datasetAccount"),
+        holder_access_account: names.key("holderAccessAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn fund_bounty_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = FundBountyAccounts {
+        authority: names.key("authority"),
+        dataset_account: names.key("datasetAccount"),
+        system_program: names.key("systemProgram"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn compact_index_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = CompactIndexAccounts {
+        authority: names.key("authority"),
+        index_account: names.key("indexAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn set_access_preferences_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = SetAccessPreferencesAccounts {
+        authority: names.key("authority"),
+        access_index: names.key("accessIndex"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn accept_share_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = AcceptShareAccounts {
+        authority: names.key("authority"),
+        access_index: names.key("accessIndex"),
+        access_account: names.key("accessAccount"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+fn reject_share_accounts() -> Vec<Value> {
+    let mut names = Names::default();
+    let metas = RejectShareAccounts {
+        authority: names.key("authority"),
+        recipient_authority: names.key("recipientAuthority"),
+        recipient_access_account: names.key("recipientAccessAccount"),
+        sharer_access_account: names.key("sharerAccessAccount"),
+        dataset_account: names.key("datasetAccount"),
+    }
+    .to_account_metas();
+    names.accounts(&metas, &[])
+}
+
+/// `InitAccount` takes different accounts per account type, so it is listed
+/// once per account list
+pub const INSTRUCTIONS: &[InstructionDef] = &[
+    InstructionDef {
+        name: "initIndex",
+        tag: tag::INIT_ACCOUNT,
+        docs: &["`InitAccount` with the `DatasetIndex` or `AccessIndex` account type"],
+        args: &[("accountType", Type::Defined("AccountType"))],
+        accounts: init_index_accounts,
+    },
+    InstructionDef {
+        name: "initDataset",
+        tag: tag::INIT_ACCOUNT,
+        docs: &["`InitAccount` with the `Dataset` account type"],
+        args: &[("accountType", Type::Defined("AccountType"))],
+        accounts: init_dataset_accounts,
+    },
+    InstructionDef {
+        name: "setDataParams",
+        tag: tag::SET_DATA_PARAMS,
+        docs: &[MULTISIG_SIGNERS],
+        args: &[("hash", HASH), ("params", Type::Defined("Params"))],
+        accounts: set_data_params_accounts,
+    },
+    InstructionDef {
+        name: "purchaseAccess",
+        tag: tag::PURCHASE_ACCESS,
+        docs: &[
+            "The three receipt accounts are passed together or not at all",
+            "A reference access account, the buyer's associated access account of the dataset's reference, takes the derived discount off the price",
+        ],
+        args: &[("hash", HASH), ("amount", Type::U64)],
+        accounts: purchase_access_accounts,
+    },
+    InstructionDef {
+        name: "shareAccess",
+        tag: tag::SHARE_ACCESS,
+        docs: &[
+            "The four share fee accounts are required when the dataset has a share fee",
+            "The recipient's access expires `expiresIn` seconds from now, or with the sharer's access when that expires first",
+        ],
+        args: &[("hash", HASH), ("expiresIn", Type::Option(&Type::I64))],
+        accounts: share_access_accounts,
+    },
+    InstructionDef {
+        name: "closeDataset",
+        tag: tag::CLOSE_DATASET,
+        docs: &[],
+        args: &[("hash", HASH), ("force", Type::Bool)],
+        accounts: close_dataset_accounts,
+    },
+    InstructionDef {
+        name: "renewAccess",
+        tag: tag::RENEW_ACCESS,
+        docs: &[],
+        args: &[("hash", HASH), ("amount", Type::U64)],
+        accounts: renew_access_accounts,
+    },
+    InstructionDef {
+        name: "shareAccessMulti",
+        tag: tag::SHARE_ACCESS_MULTI,
+        docs: &["The three recipient accounts are repeated once per recipient"],
+        args: &[("hash", HASH)],
+        accounts: share_access_multi_accounts,
+    },
+    InstructionDef {
+        name: "initAssociatedAccess",
+        tag: tag::INIT_ASSOCIATED_ACCESS,
+        docs: &[],
+        args: &[("hash", HASH)],
+        accounts: init_associated_access_accounts,
+    },
+    InstructionDef {
+        name: "resizeIndex",
+        tag: tag::RESIZE_INDEX,
+        docs: &["Grows the index by at most 10 KiB per call"],
+        args: &[("additionalSlots", Type::U16)],
+        accounts: resize_index_accounts,
+    },
+    InstructionDef {
+        name: "purchaseCollection",
+        tag: tag::PURCHASE_ACCESS,
+        docs: &[
+            "`PurchaseAccess` given a collection in place of the dataset",
+            "The two member accounts are repeated once per member granted",
+        ],
+        args: &[("hash", HASH), ("amount", Type::U64)],
+        accounts: purchase_collection_accounts,
+    },
+    InstructionDef {
+        name: "createCollection",
+        tag: tag::CREATE_COLLECTION,
+        docs: &[],
+        args: &[("hash", HASH), ("value", Type::U64)],
+        accounts: create_collection_accounts,
+    },
+    InstructionDef {
+        name: "addToCollection",
+        tag: tag::ADD_TO_COLLECTION,
+        docs: &[],
+        args: &[("collectionHash", HASH), ("datasetHash", HASH)],
+        accounts: add_to_collection_accounts,
+    },
+    InstructionDef {
+        name: "claimCollectionAccess",
+        tag: tag::CLAIM_COLLECTION_ACCESS,
+        docs: &["The two member accounts are repeated once per member granted"],
+        args: &[("hash", HASH)],
+        accounts: claim_collection_access_accounts,
+    },
+    InstructionDef {
+        name: "purchaseSeats",
+        tag: tag::PURCHASE_SEATS,
+        docs: &["Pays for `seats` seats at the dataset's volume price"],
+        args: &[("hash", HASH), ("seats", Type::U16), ("amount", Type::U64)],
+        accounts: purchase_access_accounts,
+    },
+    InstructionDef {
+        name: "initVault",
+        tag: tag::INIT_VAULT,
+        docs: &["Creates the dataset's vault for `tokenMint`, accepting the mint as payment"],
+        args: &[("hash", HASH)],
+        accounts: init_vault_accounts,
+    },
+    InstructionDef {
+        name: "withdrawProceeds",
+        tag: tag::WITHDRAW_PROCEEDS,
+        docs: &[
+            "Moves `amount` of the claimable proceeds of `tokenMint` out of its vault",
+            MULTISIG_SIGNERS,
+        ],
+        args: &[("hash", HASH), ("amount", Type::U64)],
+        accounts: withdraw_proceeds_accounts,
+    },
+    InstructionDef {
+        name: "initReceiptMint",
+        tag: tag::INIT_RECEIPT_MINT,
+        docs: &["Creates the mint of the dataset's access receipts"],
+        args: &[("hash", HASH)],
+        accounts: init_receipt_mint_accounts,
+    },
+    InstructionDef {
+        name: "revokeAccess",
+        tag: tag::REVOKE_ACCESS,
+        docs: &[
+            "Removes a holder's access, burning their receipt when the receipt accounts are passed",
+        ],
+        args: &[("hash", HASH)],
+        accounts: revoke_access_accounts,
+    },
+    InstructionDef {
+        name: "migrateAccount",
+        tag: tag::MIGRATE_ACCOUNT,
+        docs: &["Grows an account of an outdated layout to the next layout version"],
+        args: &[],
+        accounts: migrate_account_accounts,
+    },
+    InstructionDef {
+        name: "proposeOwnershipTransfer",
+        tag: tag::PROPOSE_OWNERSHIP_TRANSFER,
+        docs: &[
+            "Names `newOwner` as the dataset's pending owner",
+            MULTISIG_SIGNERS,
+        ],
+        args: &[("hash", HASH), ("newOwner", Type::PublicKey)],
+        accounts: ownership_transfer_accounts,
+    },
+    InstructionDef {
+        name: "acceptOwnership",
+        tag: tag::ACCEPT_OWNERSHIP,
+        docs: &[
+            "Signed by the pending owner, moves the dataset to their owner index",
+            MULTISIG_SIGNERS,
+        ],
+        args: &[("hash", HASH)],
+        accounts: accept_ownership_accounts,
+    },
+    InstructionDef {
+        name: "cancelOwnershipTransfer",
+        tag: tag::CANCEL_OWNERSHIP_TRANSFER,
+        docs: &[
+            "Clears the dataset's pending owner",
+            MULTISIG_SIGNERS,
+        ],
+        args: &[("hash", HASH)],
+        accounts: ownership_transfer_accounts,
+    },
+    InstructionDef {
+        name: "crankPrune",
+        tag: tag::CRANK_PRUNE,
+        docs: &["Removes `holder`'s expired access, paying the cranker the dataset's prune bounty"],
+        args: &[("hash", HASH), ("holder", Type::PublicKey)],
+        accounts: crank_prune_accounts,
+    },
+    InstructionDef {
+        name: "fundBounty",
+        tag: tag::FUND_BOUNTY,
+        docs: &["Adds `amount` lamports from the owner to the dataset's bounty pool"],
+        args: &[("hash", HASH), ("amount", Type::U64)],
+        accounts: fund_bounty_accounts,
+    },
+    InstructionDef {
+        name: "compactIndex",
+        tag: tag::COMPACT_INDEX,
+        docs: &["Moves the index's entries ahead of its free slots, with `shrink` reallocating it down to them plus `headroom` slots"],
+        args: &[("shrink", Type::Bool), ("headroom", Type::U16)],
+        accounts: compact_index_accounts,
+    },
+    InstructionDef {
+        name: "setAccessPreferences",
+        tag: tag::SET_ACCESS_PREFERENCES,
+        docs: &["With `requireShareApproval` set, shares to the authority are held until it accepts them"],
+        args: &[("requireShareApproval", Type::Bool)],
+        accounts: set_access_preferences_accounts,
+    },
+    InstructionDef {
+        name: "acceptShare",
+        tag: tag::ACCEPT_SHARE,
+        docs: &["Grants the authority the share of the dataset `from` holds for its approval"],
+        args: &[("hash", HASH), ("from", Type::PublicKey)],
+        accounts: accept_share_accounts,
+    },
+    InstructionDef {
+        name: "rejectShare",
+        tag: tag::REJECT_SHARE,
+        docs: &[
+            "Clears the share `from` holds for the recipient's approval, giving the share back to `from`'s quota",
+            "Signed by the recipient, or by `from` once the share has lapsed",
+        ],
+        args: &[("hash", HASH), ("from", Type::PublicKey)],
+        accounts: reject_share_accounts,
+    },
+];
+
+fn errors() -> Vec<Value> {
+    (0..)
+        .map(DataNexusError::from_code)
+        .take_while(Option::is_some)
+        .flatten()
+        .map(|error| {
+            json!({
+                "code": error as u32,
+                "name": format!("{:?}", error),
+                "msg": error.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The complete IDL
+pub fn idl() -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": "datanexus",
+        "instructions": INSTRUCTIONS.iter().map(InstructionDef::to_json).collect::<Vec<_>>(),
+        "accounts": ACCOUNTS.iter().map(TypeDef::to_json).collect::<Vec<_>>(),
+        "types": TYPES.iter().map(TypeDef::to_json).collect::<Vec<_>>(),
+        "errors": errors(),
+        "metadata": {
+            "address": datanexus_program::id().to_string(),
+        },
+    })
+}
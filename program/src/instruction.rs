@@ -1,32 +1,297 @@
-use solana_program::{
-    instruction::{AccountMeta, Instruction},
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::size_of,
+};
+
+use crate::{
+    accounts::{
+        push_signer_metas, AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitDatasetAccounts, InitIndexAccounts,
+        InitReceiptMintAccounts, InitVaultAccounts, MigrateAccountAccounts,
+        OwnershipTransferAccounts, PurchaseAccessAccounts, PurchaseCollectionAccounts,
+        RejectShareAccounts, RenewAccessAccounts, ResizeIndexAccounts, RevokeAccessAccounts,
+        SetAccessPreferencesAccounts, SetDataParamsAccounts, ShareAccessAccounts,
+        ShareAccessMultiAccounts, ShareFeeAccounts, WithdrawProceedsAccounts,
+    },
+    error::DataNexusError::InvalidInstruction,
+    state::{PriceTier, Tag, TAG_LEN},
 };
 
-use crate::error::DataNexusError;
+#[cfg(feature = "serde")]
+use crate::serde_fields;
+#[cfg(feature = "borsh-encoding")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Leading byte of instruction data selecting the borsh encoding, no
+/// hand-packed tag uses it so unprefixed legacy data still unpacks
+#[cfg(feature = "borsh-encoding")]
+pub const BORSH_ENCODING: u8 = u8::MAX;
 
-enum AccountType {
+/// Instruction tags, the single source of truth for both `pack` and `unpack`
+pub(crate) mod tag {
+    pub const INIT_ACCOUNT: u8 = 0;
+    pub const SET_DATA_PARAMS: u8 = 1;
+    pub const PURCHASE_ACCESS: u8 = 2;
+    pub const SHARE_ACCESS: u8 = 3;
+    pub const CLOSE_DATASET: u8 = 4;
+    pub const RENEW_ACCESS: u8 = 5;
+    pub const SHARE_ACCESS_MULTI: u8 = 6;
+    pub const INIT_ASSOCIATED_ACCESS: u8 = 7;
+    pub const RESIZE_INDEX: u8 = 8;
+    pub const CREATE_COLLECTION: u8 = 9;
+    pub const ADD_TO_COLLECTION: u8 = 10;
+    pub const CLAIM_COLLECTION_ACCESS: u8 = 11;
+    pub const PURCHASE_SEATS: u8 = 12;
+    pub const INIT_VAULT: u8 = 13;
+    pub const WITHDRAW_PROCEEDS: u8 = 14;
+    pub const INIT_RECEIPT_MINT: u8 = 15;
+    pub const REVOKE_ACCESS: u8 = 16;
+    pub const MIGRATE_ACCOUNT: u8 = 17;
+    pub const PROPOSE_OWNERSHIP_TRANSFER: u8 = 18;
+    pub const ACCEPT_OWNERSHIP: u8 = 19;
+    pub const CANCEL_OWNERSHIP_TRANSFER: u8 = 20;
+    pub const CRANK_PRUNE: u8 = 21;
+    pub const FUND_BOUNTY: u8 = 22;
+    pub const COMPACT_INDEX: u8 = 23;
+    pub const SET_ACCESS_PREFERENCES: u8 = 24;
+    pub const ACCEPT_SHARE: u8 = 25;
+    pub const REJECT_SHARE: u8 = 26;
+}
+
+/// Account created by `InitAccount`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AccountType {
     DatasetIndex,
     AccessIndex,
-    Dataset([u8; 32]),
-    Access([u8; 32]),
+    Dataset(#[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))] [u8; 32]),
+    Access(#[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))] [u8; 32]),
+}
+
+/// Optional dataset fields reset to `None` by `Params::Clear`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldMask(pub u8);
+
+impl FieldMask {
+    pub const KEY: Self = FieldMask(1);
+    pub const VALUE: Self = FieldMask(1 << 1);
+    pub const SHARE_LIMIT: Self = FieldMask(1 << 2);
+    pub const REF_DATA: Self = FieldMask(1 << 3);
+    pub const URI: Self = FieldMask(1 << 4);
+
+    /// Every field that can be cleared
+    pub const ALL: Self = FieldMask(0b1_1111);
+
+    /// Whether every field of `other` is in the mask
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether the mask is non-empty and names only clearable fields
+    pub fn is_valid(self) -> bool {
+        self.0 != 0 && Self::ALL.contains(self)
+    }
+}
+
+impl std::ops::BitOr for FieldMask {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        FieldMask(self.0 | other.0)
+    }
 }
 
-enum Params {
+/// Dataset fields written by `SetDataParams`
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Params {
+    /// Key, value, share limit and optional reference data, an all-zero
+    /// reference is encoded for `None`
     Init(
-        Self::Key,
-        Self::Value,
-        Self::ShareLimit,
-        Option<Self::ReferenceData>,
+        #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))] [u8; 32],
+        u64,
+        u16,
+        #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey::option"))] Option<Pubkey>,
     ),
-    Key([u8; 32]),
+    Key(#[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))] [u8; 32]),
     Value(u64),
     ShareLimit(u16),
-    ReferenceData([u8; 32]),
+    ReferenceData(#[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))] Pubkey),
+    ReferralBps(u16),
+    AccessDuration(i64),
+    Uri(#[cfg_attr(feature = "serde", serde(with = "serde_fields::byte_str"))] Vec<u8>),
+    Size(u64),
+    ContentType(#[cfg_attr(feature = "serde", serde(with = "serde_fields::byte_str"))] Vec<u8>),
+    /// Resets the masked fields to `None`, a dataset with no value cannot be
+    /// purchased
+    Clear(FieldMask),
+    /// Whether shared grants may be shared on
+    Resharable(bool),
+    /// Hops a grant may be shared on from its purchaser, zero removes the
+    /// limit
+    MaxShareDepth(u8),
+    /// Volume prices for `PurchaseSeats`, at most `MAX_PRICE_TIERS` in
+    /// increasing `min_seats` order, an empty table removes them
+    Tiers(Vec<PriceTier>),
+    /// Holders the dataset admits through purchases and shares, zero
+    /// removes the quota
+    MaxHolders(u32),
+    /// Prices the dataset in a mint, replacing its price when it has one.
+    /// Once any mint is priced purchases are only paid in priced mints
+    SetPrice(
+        #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))] Pubkey,
+        u64,
+    ),
+    /// Removes the price of a mint, an emptied table leaves `value` charged
+    /// in any mint
+    RemovePrice(#[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))] Pubkey),
+    /// Unix timestamps purchases open and close at, a zero start opens them
+    /// right away and no end keeps them open. Encoded as two `i64`s, zero
+    /// for no end
+    SaleWindow {
+        start: i64,
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        end: Option<i64>,
+    },
+    /// Lamports `CrankPrune` pays out of the bounty pool per pruned entry,
+    /// zero prunes for nothing
+    PruneBounty(u64),
+    /// Fee in base units of `mint` every `ShareAccess` pays the owner, zero
+    /// makes sharing free again
+    ShareFee {
+        #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Labels the dataset with a tag, up to `MAX_TAGS` distinct ones
+    AddTag(#[cfg_attr(feature = "serde", serde(with = "serde_fields::padded_str"))] Tag),
+    /// Removes a tag, the tags after it move up in its place
+    RemoveTag(#[cfg_attr(feature = "serde", serde(with = "serde_fields::padded_str"))] Tag),
+    /// Basis points off the price for buyers holding unexpired access to
+    /// the dataset's `ref_data`, zero removes the discount
+    DerivedDiscountBps(u16),
+}
+
+impl Params {
+    /// Dataset these params reference, which must be passed alongside them
+    pub fn reference_data(&self) -> Option<Pubkey> {
+        match self {
+            Params::ReferenceData(ref_data) | Params::Init(_, _, _, Some(ref_data)) => {
+                Some(*ref_data)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Largest share limit `ParamsBuilder` accepts, the program itself takes any
+/// `u16`
+pub const MAX_SHARE_LIMIT: u16 = 1_000;
+
+/// Params rejected by `ParamsBuilder::build` before they are sent
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+pub enum ParamsError {
+    #[error("nothing to set")]
+    Empty,
+    #[error("key, value and share limit are set together or one at a time")]
+    Incomplete,
+    #[error("an all-zero key reads back as no key")]
+    ZeroKey,
+    #[error("a zero value reads back as no value")]
+    ZeroValue,
+    #[error("share limit {0} is above the maximum of {}", MAX_SHARE_LIMIT)]
+    ShareLimitTooLarge(u16),
+    #[error("a dataset cannot reference itself")]
+    SelfReference,
+}
+
+/// Builds the `Params` of the key, value, share limit and reference data
+/// fields, checking them before a transaction pays for the program to
+///
+/// All of key, value and share limit build `Params::Init`, with the
+/// reference if one is set. Any single field builds its own setter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParamsBuilder {
+    key: Option<[u8; 32]>,
+    value: Option<u64>,
+    share_limit: Option<u16>,
+    reference: Option<Pubkey>,
+    dataset: Option<Pubkey>,
+}
+
+impl ParamsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: [u8; 32]) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn value(mut self, value: u64) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    pub fn share_limit(mut self, share_limit: u16) -> Self {
+        self.share_limit = Some(share_limit);
+        self
+    }
+
+    pub fn reference(mut self, reference: Pubkey) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Address of the dataset the params are for, which the reference must
+    /// differ from
+    pub fn dataset(mut self, dataset: Pubkey) -> Self {
+        self.dataset = Some(dataset);
+        self
+    }
+
+    pub fn build(self) -> Result<Params, ParamsError> {
+        if self.key == Some([0; 32]) {
+            return Err(ParamsError::ZeroKey);
+        }
+        if self.value == Some(0) {
+            return Err(ParamsError::ZeroValue);
+        }
+        if let Some(share_limit) = self.share_limit.filter(|limit| *limit > MAX_SHARE_LIMIT) {
+            return Err(ParamsError::ShareLimitTooLarge(share_limit));
+        }
+        if self.reference.is_some() && self.reference == self.dataset {
+            return Err(ParamsError::SelfReference);
+        }
+
+        match (self.key, self.value, self.share_limit, self.reference) {
+            (Some(key), Some(value), Some(share_limit), reference) => {
+                Ok(Params::Init(key, value, share_limit, reference))
+            }
+            (Some(key), None, None, None) => Ok(Params::Key(key)),
+            (None, Some(value), None, None) => Ok(Params::Value(value)),
+            (None, None, Some(share_limit), None) => Ok(Params::ShareLimit(share_limit)),
+            (None, None, None, Some(reference)) => Ok(Params::ReferenceData(reference)),
+            (None, None, None, None) => Err(ParamsError::Empty),
+            _ => Err(ParamsError::Incomplete),
+        }
+    }
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
 pub enum DataNexusInstruction {
     /// Initialize Dataset Account
     ///
@@ -34,19 +299,24 @@ pub enum DataNexusInstruction {
     ///
     /// Distinct Payer account required when sharing access
     /// As the sharer will pay for initializing the recipient's
-    /// AccessIndex and Access accounts
+    /// AccessIndex
     ///
     /// Index Accounts:
     /// `[w,s]` Payer
-    /// `[w]` Authority
+    /// `[]` Authority
+    /// `[w]` Index Account
     /// `[]` System Program
     ///
-    /// Dataset/Access:
-    /// `[w,s]` Payer
-    /// `[w]` Authority
-    /// `[w]` Dataset Index Account
+    /// Dataset:
+    /// `[w,s]` Authority
+    /// `[w]` Owner Index Account
     /// `[w]` Dataset Account
     /// `[]` System Program
+    /// `[w]` (Optional) Registry Account, the last page of the hash prefix
+    /// `[w]` (Optional) Overflow Registry Account, when the page is full
+    ///
+    /// Access:
+    /// Rejected, access accounts are created by `InitAssociatedAccess`
     InitAccount(AccountType),
 
     /// Write Dataset Parameters to Buffer
@@ -55,69 +325,524 @@ pub enum DataNexusInstruction {
     ///
     /// `[w,s]` Authority
     /// `[w]` Dataset Account
+    ///
+    /// A multisig owner doesn't sign, its signing members follow the
+    /// accounts above instead:
+    /// `[s]` Multisig Signer, at least `m` of them
     SetDataParams { hash: [u8; 32], params: Params },
 
     /// Purchase Dataset Access
     ///
+    /// Pays into the dataset's vault for the payment mint, created by
+    /// `InitVault`, adding the owner's share to the claimable proceeds of
+    /// that mint. Mints the owner has no vault of are not accepted. A new
+    /// buyer takes one of the dataset's holder slots when it has a quota.
+    ///
+    /// A collection account may be passed in place of the dataset, see
+    /// `CreateCollection` for the accounts it takes instead.
+    ///
     /// Accounts expected:
     ///
     /// `[w,s]` User Authority
     /// `[w]` User Access Index
     /// `[w]` User Access Account
     /// `[w]` User Token Account
-    /// `[w]` Owner Account
-    /// `[w]` Owner Token Account
-    /// `[]` Dataset Account
-    /// `[]` Token Program
+    /// `[]` Owner Account
+    /// `[w]` Dataset Vault
+    /// `[]` Token Mint
+    /// `[w]` Dataset Account
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    ///
+    /// Optional:
+    /// `[w]` Referrer Token Account not owned by the buyer, receives
+    /// `referral_bps` of the payment
+    ///
+    /// Optional, mints an access receipt to the buyer unless they hold one:
+    /// `[w]` Receipt Mint, created by `InitReceiptMint`
+    /// `[w]` Buyer Receipt Token Account
+    /// `[]` Receipt Token Program (spl-token or spl-token-2022)
+    ///
+    /// Optional, last, takes `derived_discount_bps` off the price:
+    /// `[]` Buyer's Associated Access Account of the dataset's `ref_data`
     PurchaseAccess { hash: [u8; 32], amount: u64 },
 
     /// Share Dataset Access
     ///
+    /// The access index and accounts must be their authority's, and the
+    /// recipient cannot be the sharer. The recipient takes one of the
+    /// dataset's holder slots when it has a quota.
+    ///
+    /// The recipient's access expires `expires_in` seconds from now, or with
+    /// the sharer's access if that expires first. Without `expires_in` it
+    /// expires with the sharer's access. Encoded as a presence byte followed
+    /// by the `i64` when present.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` User Authority
+    /// `[w]` User Access Account
+    /// `[]` Recipient Authority
+    /// `[w]` Recipient Access Index
+    /// `[w]` Recipient Access Account
+    /// `[w]` Dataset Account
+    ///
+    /// Required when the dataset has a `share_fee`, pays it to the owner:
+    /// `[w]` User Token Account
+    /// `[w]` Owner Token Account
+    /// `[]` Fee Mint
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    ShareAccess {
+        hash: [u8; 32],
+        expires_in: Option<i64>,
+    },
+
+    /// Close Dataset Account
+    ///
+    /// Removes the dataset from the owner index and returns the
+    /// dataset account's lamports to the authority, less the rent of the
+    /// `DatasetState::CLOSED_LEN` tombstone left at its address. Fails if
+    /// the dataset has been purchased unless `force` is set, or while any
+    /// vault has proceeds to withdraw.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Authority
+    /// `[w]` Owner Index Account
+    /// `[w]` Dataset Account
+    CloseDataset { hash: [u8; 32], force: bool },
+
+    /// Renew Dataset Access
+    ///
+    /// Extends an existing access entry by the dataset's access duration,
+    /// starting from the later of now and the current expiry. Pays into
+    /// the dataset's vault for the payment mint, as purchases do.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` User Authority
+    /// `[w]` User Access Account
+    /// `[w]` User Token Account
+    /// `[w]` Dataset Vault
+    /// `[]` Token Mint
+    /// `[w]` Dataset Account
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    ///
+    /// Optional:
+    /// `[w]` Referrer Token Account not owned by the buyer, receives
+    /// `referral_bps` of the payment
+    RenewAccess { hash: [u8; 32], amount: u64 },
+
+    /// Share Dataset Access with Multiple Recipients
+    ///
+    /// The access index and accounts must be their authority's, and no
+    /// recipient can be the sharer. Each recipient takes one of the
+    /// dataset's holder slots when it has a quota. Datasets with a share fee
+    /// can only be shared through `ShareAccess`.
+    ///
     /// Accounts expected:
     ///
     /// `[w,s]` User Authority
-    /// `[w]` User Access Index
     /// `[w]` User Access Account
-    /// `[w]` Recipient Authority
+    /// `[w]` Dataset Account
+    ///
+    /// Followed by one group per recipient:
+    /// `[]` Recipient Authority
     /// `[w]` Recipient Access Index
     /// `[w]` Recipient Access Account
+    ShareAccessMulti { hash: [u8; 32] },
+
+    /// Initialize Associated Access Account
+    ///
+    /// Creates the per-dataset access account of an authority and
+    /// registers the dataset in the authority's access index. The
+    /// authority signs, so no one else can fill its index.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Payer
+    /// `[s]` Authority
+    /// `[w]` Access Index Account
+    /// `[w]` Associated Access Account
+    /// `[]` Dataset Account
+    /// `[]` System Program
+    InitAssociatedAccess { hash: [u8; 32] },
+
+    /// Resize Index Account
+    ///
+    /// Grows an owner or access index by `additional_slots` entries, the
+    /// payer funding the extra rent. Each call may grow the index by at
+    /// most 10 KiB.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Payer
+    /// `[s]` Authority
+    /// `[w]` Index Account
+    /// `[]` System Program
+    ResizeIndex { additional_slots: u16 },
+
+    /// Create Collection Account
+    ///
+    /// Creates a collection selling access to each of its member datasets
+    /// for a single payment of `value`.
+    ///
+    /// A collection is bought with `PurchaseAccess`, passing:
+    ///
+    /// `[w,s]` User Authority
+    /// `[w]` User Access Index
+    /// `[w]` Collection Access Account, from `InitAssociatedAccess`
+    /// `[w]` User Token Account
+    /// `[]` Owner Account
+    /// `[w]` Owner Token Account
+    /// `[]` Token Mint
+    /// `[w]` Collection Account
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    ///
+    /// Followed by a pair per member granted, at most
+    /// `MAX_COLLECTION_GRANTS`, in collection order:
+    /// `[]` Member Dataset Account
+    /// `[w]` User Associated Access Account of the member
+    ///
+    /// Members not granted by the purchase are granted by
+    /// `ClaimCollectionAccess`. Members given a holder quota since they
+    /// were added are passed over.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Authority
+    /// `[w]` Collection Account
+    /// `[]` System Program
+    CreateCollection { hash: [u8; 32], value: u64 },
+
+    /// Add Dataset to Collection
+    ///
+    /// The authority must own both the collection and the dataset, which
+    /// cannot have a holder quota.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Authority
+    /// `[w]` Collection Account
+    /// `[]` Dataset Account
+    AddToCollection {
+        collection_hash: [u8; 32],
+        dataset_hash: [u8; 32],
+    },
+
+    /// Claim Collection Access
+    ///
+    /// Grants the next members of a purchased collection, resuming from
+    /// where the purchase or the previous claim stopped.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` User Authority
+    /// `[w]` User Access Index
+    /// `[w]` Collection Access Account
+    /// `[]` Collection Account
+    ///
+    /// Followed by a pair per member granted, as for `PurchaseAccess`
+    ClaimCollectionAccess { hash: [u8; 32] },
+
+    /// Purchase Dataset Seats
+    ///
+    /// Buys access for `seats` users at once, paying `seats` times the unit
+    /// price of the largest tier reached, or of the dataset value below
+    /// every tier. The buyer may then share access `seats` times whatever
+    /// the dataset's share limit.
+    ///
+    /// Accounts expected:
+    ///
+    /// As for `PurchaseAccess`
+    PurchaseSeats {
+        hash: [u8; 32],
+        seats: u16,
+        amount: u64,
+    },
+
+    /// Initialize Dataset Vault
+    ///
+    /// Creates the token account holding a dataset's proceeds in one mint,
+    /// at the PDA of the dataset and mint and with itself as authority, and
+    /// starts the mint's claimable proceeds at zero. Only the owner may open
+    /// a vault, which accepts the mint as payment, up to `MAX_VAULTS`.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Payer
+    /// `[s]` Owner Authority
+    /// `[w]` Dataset Account
+    /// `[]` Token Mint
+    /// `[w]` Dataset Vault
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    /// `[]` System Program
+    InitVault { hash: [u8; 32] },
+
+    /// Withdraw Dataset Proceeds
+    ///
+    /// Transfers `amount` out of the dataset's vault for a mint, up to the
+    /// claimable proceeds of the mint and the vault's balance.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Owner Authority
+    /// `[w]` Dataset Account
+    /// `[w]` Dataset Vault
+    /// `[]` Token Mint
+    /// `[w]` Destination Token Account
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    ///
+    /// A multisig owner doesn't sign, its signing members follow the
+    /// accounts above instead:
+    /// `[s]` Multisig Signer, at least `m` of them
+    WithdrawProceeds { hash: [u8; 32], amount: u64 },
+
+    /// Initialize Receipt Mint
+    ///
+    /// Creates the mint of a dataset's access receipts with no decimals, at
+    /// the PDA of the dataset and with itself as mint and freeze authority.
+    /// Anyone may pay for it.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Payer
+    /// `[]` Dataset Account
+    /// `[w]` Receipt Mint
+    /// `[]` Token Program (spl-token or spl-token-2022)
+    /// `[]` System Program
+    InitReceiptMint { hash: [u8; 32] },
+
+    /// Revoke Dataset Access
+    ///
+    /// Removes a holder's access entry for the dataset, freeing its holder
+    /// slot. Receipts are minted frozen with the mint as delegate, the
+    /// receipt accounts thaw and burn the holder's receipt. They are
+    /// required while the dataset has receipts out, else the instruction
+    /// fails with `ReceiptRequired`.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Owner Authority
+    /// `[w]` Dataset Account
+    /// `[]` Holder Authority
+    /// `[w]` Holder Access Account
+    ///
+    /// Optional while no receipt is out:
+    /// `[w]` Receipt Mint
+    /// `[w]` Holder Receipt Token Account
+    /// `[]` Receipt Token Program (spl-token or spl-token-2022)
+    RevokeAccess { hash: [u8; 32] },
+
+    /// Migrate Account Layout
+    ///
+    /// Grows a dataset, access or index account created under an older
+    /// layout to the size of the next layout version and rewrites it in
+    /// that layout, new fields taking their defaults. Accounts several
+    /// versions behind take one call per version. Anyone may pay for it.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Payer
+    /// `[w]` Account
+    /// `[]` System Program
+    MigrateAccount,
+
+    /// Propose Dataset Ownership Transfer
+    ///
+    /// Names `new_owner` as the dataset's pending owner, replacing any
+    /// earlier proposal. The owner is unchanged until the pending owner
+    /// accepts.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Owner Authority
+    /// `[w]` Dataset Account
+    ///
+    /// A multisig owner doesn't sign, its signing members follow the
+    /// accounts above instead:
+    /// `[s]` Multisig Signer, at least `m` of them
+    ProposeOwnershipTransfer { hash: [u8; 32], new_owner: Pubkey },
+
+    /// Accept Dataset Ownership
+    ///
+    /// Makes the pending owner the dataset's owner and moves the dataset
+    /// from the old owner's index to theirs. Proceeds not yet withdrawn go
+    /// with the dataset.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Pending Owner Authority
+    /// `[w]` Old Owner Index Account
+    /// `[w]` Pending Owner Index Account
+    /// `[w]` Dataset Account
+    ///
+    /// A multisig pending owner doesn't sign, its signing members follow the
+    /// accounts above instead:
+    /// `[s]` Multisig Signer, at least `m` of them
+    AcceptOwnership { hash: [u8; 32] },
+
+    /// Cancel Dataset Ownership Transfer
+    ///
+    /// Clears the dataset's pending owner.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Owner Authority
+    /// `[w]` Dataset Account
+    ///
+    /// A multisig owner doesn't sign, its signing members follow the
+    /// accounts above instead:
+    /// `[s]` Multisig Signer, at least `m` of them
+    CancelOwnershipTransfer { hash: [u8; 32] },
+
+    /// Prune Expired Access
+    ///
+    /// Removes `holder`'s expired access entry for the dataset, freeing its
+    /// holder slot, and pays the cranker the dataset's prune bounty out of
+    /// its bounty pool, or what is left of the pool when it holds less.
+    /// Anyone may crank. Access accounts keep their size, so no rent is
+    /// reclaimed, and revoked entries are already removed by `RevokeAccess`.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Cranker
+    /// `[w]` Dataset Account
+    /// `[w]` Holder Access Account
+    CrankPrune { hash: [u8; 32], holder: Pubkey },
+
+    /// Fund Prune Bounties
+    ///
+    /// Transfers `amount` lamports from the owner into the dataset account,
+    /// adding them to its bounty pool.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Owner Authority
+    /// `[w]` Dataset Account
+    /// `[]` System Program
+    FundBounty { hash: [u8; 32], amount: u64 },
+
+    /// Compact Index Account
+    ///
+    /// Moves the entries of an owner or access index ahead of the free slots
+    /// removals left between them. With `shrink` set the index is then
+    /// reallocated down to its entries plus `headroom` free slots, though
+    /// never below the `MAX_INDEX_ENTRIES` slots it was created with, and
+    /// the rent it no longer needs is refunded to the authority. Legacy
+    /// indexes are compacted but cannot shrink.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[w,s]` Authority
+    /// `[w]` Index Account
+    CompactIndex { shrink: bool, headroom: u16 },
+
+    /// Set Access Preferences
+    ///
+    /// With `require_share_approval` set, `ShareAccess` and
+    /// `ShareAccessMulti` hold shares to the authority as pending until it
+    /// accepts them with `AcceptShare`, instead of granting them. Shares
+    /// already pending are unaffected by clearing it. Indexes created before
+    /// the preferences existed need `MigrateAccount` first.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Authority
+    /// `[w]` Access Index Account
+    SetAccessPreferences { require_share_approval: bool },
+
+    /// Accept Pending Share
+    ///
+    /// Grants the authority the share of the dataset `from` holds for its
+    /// approval, as `ShareAccess` would have without it. Shares pending for
+    /// `PENDING_SHARE_TTL` seconds have lapsed and can only be rejected.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Authority
+    /// `[w]` Access Index Account
+    /// `[w]` Associated Access Account
+    /// `[w]` Dataset Account
+    AcceptShare { hash: [u8; 32], from: Pubkey },
+
+    /// Reject Pending Share
+    ///
+    /// Clears the share of the dataset `from` holds for the recipient's
+    /// approval and gives the share back to `from`'s quota. The recipient
+    /// may reject at any time, `from` only once the share has lapsed. A
+    /// share fee went to the dataset owner when sharing and is forfeited.
+    ///
+    /// Accounts expected:
+    ///
+    /// `[s]` Recipient or Sharer Authority
+    /// `[]` Recipient Authority
+    /// `[w]` Recipient Associated Access Account
+    /// `[w]` Sharer Associated Access Account
     /// `[]` Dataset Account
-    ShareAccess { hash: [u8; 32] },
+    RejectShare { hash: [u8; 32], from: Pubkey },
 }
 
 impl DataNexusInstruction {
+    /// Packs the instruction with the hand-packed encoding
     pub fn pack(&self) -> Vec<u8> {
+        self.pack_legacy()
+    }
+
+    /// Packs the instruction with the borsh encoding
+    #[cfg(feature = "borsh-encoding")]
+    pub fn pack_borsh(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut buf = vec![BORSH_ENCODING];
+        buf.extend_from_slice(
+            &self
+                .try_to_vec()
+                .map_err(|e| ProgramError::BorshIoError(e.to_string()))?,
+        );
+        Ok(buf)
+    }
+
+    /// Unpacks instruction data in either encoding, borsh data leads with
+    /// `BORSH_ENCODING` and anything else is hand-packed
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.split_first() {
+            #[cfg(feature = "borsh-encoding")]
+            Some((&BORSH_ENCODING, rest)) => {
+                Self::try_from_slice(rest).map_err(|_| InvalidInstruction.into())
+            }
+            _ => Self::unpack_legacy(data),
+        }
+    }
+
+    fn pack_legacy(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
 
         match self {
-            Self::InitAccount(_) => {
-                buf.push(0);
-                match self {
-                    Self::InitAccount(AccountType::DatasetIndex()) => buf.push(0),
-                    Self::InitAccount(AccountType::AccessIndex()) => buf.push(1),
-                    Self::InitAccount(AccountType::Dataset(hash)) => {
+            Self::InitAccount(account_type) => {
+                buf.push(tag::INIT_ACCOUNT);
+                match account_type {
+                    AccountType::DatasetIndex => buf.push(0),
+                    AccountType::AccessIndex => buf.push(1),
+                    AccountType::Dataset(hash) => {
                         buf.push(2);
                         buf.extend_from_slice(hash);
                     }
-                    Self::InitAccount(AccountType::Access(hash)) => {
+                    AccountType::Access(hash) => {
                         buf.push(3);
                         buf.extend_from_slice(hash);
                     }
-                    _ => return Err(InvalidInstruction.into()),
                 }
             }
             Self::SetDataParams { hash, params } => {
-                buf.push(2);
+                buf.push(tag::SET_DATA_PARAMS);
                 buf.extend_from_slice(hash);
                 match params {
                     Params::Init(key, value, share_limit, ref_data) => {
                         buf.push(0);
                         buf.extend_from_slice(key);
-                        buf.extend_from_slice(value.to_le_bytes());
-                        buf.extend_from_slice(share_limit.to_le_bytes());
+                        buf.extend_from_slice(&value.to_le_bytes());
+                        buf.extend_from_slice(&share_limit.to_le_bytes());
                         match ref_data {
-                            Some(d) => buf.extend_from_slice(d.to_bytes()),
-                            None => buf.extend_from_slice([0u8; 32]),
+                            Some(d) => buf.extend_from_slice(d.as_ref()),
+                            None => buf.extend_from_slice(&[0u8; 32]),
                         }
                     }
                     Params::Key(k) => {
@@ -126,334 +851,1812 @@ impl DataNexusInstruction {
                     }
                     Params::Value(v) => {
                         buf.push(2);
-                        buf.extend_from_slice(v.to_le_bytes());
+                        buf.extend_from_slice(&v.to_le_bytes());
                     }
                     Params::ShareLimit(n) => {
                         buf.push(3);
-                        buf.extend_from_slice(n.to_le_bytes());
+                        buf.extend_from_slice(&n.to_le_bytes());
                     }
                     Params::ReferenceData(pk) => {
                         buf.push(4);
-                        buf.extend_from_slice(pk.to_bytes());
+                        buf.extend_from_slice(pk.as_ref());
                     }
-                    _ => return Err(InvalidInstruction.into()),
-                }
-            }
-            Self::PurchaseAccess { hash, amount } => {
-                buf.push(3);
-                buf.extend_from_slice(hash);
-                buf.extend_from_slice(amount.to_le_bytes());
-            }
-            Self::ShareAccess { hash } => {
-                buf.push(4);
+                    Params::ReferralBps(bps) => {
+                        buf.push(5);
+                        buf.extend_from_slice(&bps.to_le_bytes());
+                    }
+                    Params::AccessDuration(duration) => {
+                        buf.push(6);
+                        buf.extend_from_slice(&duration.to_le_bytes());
+                    }
+                    Params::Uri(uri) => {
+                        buf.push(7);
+                        buf.extend_from_slice(&(uri.len() as u16).to_le_bytes());
+                        buf.extend_from_slice(uri);
+                    }
+                    Params::Size(size) => {
+                        buf.push(8);
+                        buf.extend_from_slice(&size.to_le_bytes());
+                    }
+                    Params::ContentType(content_type) => {
+                        buf.push(9);
+                        buf.extend_from_slice(&(content_type.len() as u16).to_le_bytes());
+                        buf.extend_from_slice(content_type);
+                    }
+                    Params::Clear(mask) => {
+                        buf.push(10);
+                        buf.push(mask.0);
+                    }
+                    Params::Resharable(resharable) => {
+                        buf.push(11);
+                        buf.push(*resharable as u8);
+                    }
+                    Params::MaxShareDepth(max_share_depth) => {
+                        buf.push(12);
+                        buf.push(*max_share_depth);
+                    }
+                    Params::Tiers(tiers) => {
+                        buf.push(13);
+                        buf.push(tiers.len() as u8);
+                        for tier in tiers {
+                            buf.extend_from_slice(&tier.min_seats.to_le_bytes());
+                            buf.extend_from_slice(&tier.unit_price.to_le_bytes());
+                        }
+                    }
+                    Params::MaxHolders(max_holders) => {
+                        buf.push(14);
+                        buf.extend_from_slice(&max_holders.to_le_bytes());
+                    }
+                    Params::SetPrice(mint, amount) => {
+                        buf.push(15);
+                        buf.extend_from_slice(mint.as_ref());
+                        buf.extend_from_slice(&amount.to_le_bytes());
+                    }
+                    Params::RemovePrice(mint) => {
+                        buf.push(16);
+                        buf.extend_from_slice(mint.as_ref());
+                    }
+                    Params::SaleWindow { start, end } => {
+                        buf.push(17);
+                        buf.extend_from_slice(&start.to_le_bytes());
+                        buf.extend_from_slice(&end.unwrap_or(0).to_le_bytes());
+                    }
+                    Params::PruneBounty(bounty) => {
+                        buf.push(18);
+                        buf.extend_from_slice(&bounty.to_le_bytes());
+                    }
+                    Params::ShareFee { mint, amount } => {
+                        buf.push(19);
+                        buf.extend_from_slice(mint.as_ref());
+                        buf.extend_from_slice(&amount.to_le_bytes());
+                    }
+                    Params::AddTag(tag) => {
+                        buf.push(20);
+                        buf.extend_from_slice(tag);
+                    }
+                    Params::RemoveTag(tag) => {
+                        buf.push(21);
+                        buf.extend_from_slice(tag);
+                    }
+                    Params::DerivedDiscountBps(bps) => {
+                        buf.push(22);
+                        buf.extend_from_slice(&bps.to_le_bytes());
+                    }
+                }
+            }
+            Self::PurchaseAccess { hash, amount } => {
+                buf.push(tag::PURCHASE_ACCESS);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::ShareAccess { hash, expires_in } => {
+                buf.push(tag::SHARE_ACCESS);
+                buf.extend_from_slice(hash);
+                match expires_in {
+                    Some(expires_in) => {
+                        buf.push(1);
+                        buf.extend_from_slice(&expires_in.to_le_bytes());
+                    }
+                    None => buf.push(0),
+                }
+            }
+            Self::CloseDataset { hash, force } => {
+                buf.push(tag::CLOSE_DATASET);
+                buf.extend_from_slice(hash);
+                buf.push(*force as u8);
+            }
+            Self::RenewAccess { hash, amount } => {
+                buf.push(tag::RENEW_ACCESS);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::ShareAccessMulti { hash } => {
+                buf.push(tag::SHARE_ACCESS_MULTI);
+                buf.extend_from_slice(hash);
+            }
+            Self::InitAssociatedAccess { hash } => {
+                buf.push(tag::INIT_ASSOCIATED_ACCESS);
+                buf.extend_from_slice(hash);
+            }
+            Self::ResizeIndex { additional_slots } => {
+                buf.push(tag::RESIZE_INDEX);
+                buf.extend_from_slice(&additional_slots.to_le_bytes());
+            }
+            Self::CreateCollection { hash, value } => {
+                buf.push(tag::CREATE_COLLECTION);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            Self::AddToCollection {
+                collection_hash,
+                dataset_hash,
+            } => {
+                buf.push(tag::ADD_TO_COLLECTION);
+                buf.extend_from_slice(collection_hash);
+                buf.extend_from_slice(dataset_hash);
+            }
+            Self::ClaimCollectionAccess { hash } => {
+                buf.push(tag::CLAIM_COLLECTION_ACCESS);
+                buf.extend_from_slice(hash);
+            }
+            Self::PurchaseSeats {
+                hash,
+                seats,
+                amount,
+            } => {
+                buf.push(tag::PURCHASE_SEATS);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&seats.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::InitVault { hash } => {
+                buf.push(tag::INIT_VAULT);
+                buf.extend_from_slice(hash);
+            }
+            Self::WithdrawProceeds { hash, amount } => {
+                buf.push(tag::WITHDRAW_PROCEEDS);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::InitReceiptMint { hash } => {
+                buf.push(tag::INIT_RECEIPT_MINT);
+                buf.extend_from_slice(hash);
+            }
+            Self::RevokeAccess { hash } => {
+                buf.push(tag::REVOKE_ACCESS);
+                buf.extend_from_slice(hash);
+            }
+            Self::MigrateAccount => buf.push(tag::MIGRATE_ACCOUNT),
+            Self::ProposeOwnershipTransfer { hash, new_owner } => {
+                buf.push(tag::PROPOSE_OWNERSHIP_TRANSFER);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(new_owner.as_ref());
+            }
+            Self::AcceptOwnership { hash } => {
+                buf.push(tag::ACCEPT_OWNERSHIP);
+                buf.extend_from_slice(hash);
+            }
+            Self::CancelOwnershipTransfer { hash } => {
+                buf.push(tag::CANCEL_OWNERSHIP_TRANSFER);
+                buf.extend_from_slice(hash);
+            }
+            Self::CrankPrune { hash, holder } => {
+                buf.push(tag::CRANK_PRUNE);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(holder.as_ref());
+            }
+            Self::FundBounty { hash, amount } => {
+                buf.push(tag::FUND_BOUNTY);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::CompactIndex { shrink, headroom } => {
+                buf.push(tag::COMPACT_INDEX);
+                buf.push(*shrink as u8);
+                buf.extend_from_slice(&headroom.to_le_bytes());
+            }
+            Self::SetAccessPreferences {
+                require_share_approval,
+            } => {
+                buf.push(tag::SET_ACCESS_PREFERENCES);
+                buf.push(*require_share_approval as u8);
+            }
+            Self::AcceptShare { hash, from } => {
+                buf.push(tag::ACCEPT_SHARE);
+                buf.extend_from_slice(hash);
+                buf.extend_from_slice(from.as_ref());
+            }
+            Self::RejectShare { hash, from } => {
+                buf.push(tag::REJECT_SHARE);
                 buf.extend_from_slice(hash);
+                buf.extend_from_slice(from.as_ref());
             }
-            _ => return Err(InvalidInstruction.into()),
         }
         buf
     }
 
-    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
-        let (tag, rest) = data.split_first().ok_or(InvalidInstruction)?;
+    fn unpack_legacy(data: &[u8]) -> Result<Self, ProgramError> {
+        let (tag, rest) = data.split_first().ok_or(InvalidInstruction)?;
+
+        match *tag {
+            tag::INIT_ACCOUNT => {
+                let (account_type, rest) = rest.split_first().ok_or(InvalidInstruction)?;
+                Ok(Self::InitAccount(match account_type {
+                    0 => AccountType::DatasetIndex,
+                    1 => AccountType::AccessIndex,
+                    2 => AccountType::Dataset(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    3 => AccountType::Access(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    _ => return Err(InvalidInstruction.into()),
+                }))
+            }
+            tag::SET_DATA_PARAMS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let (tag, rest) = rest
+                    .get(32..)
+                    .and_then(|rest| rest.split_first())
+                    .ok_or(InvalidInstruction)?;
+                let params = match tag {
+                    0 => {
+                        let key = rest
+                            .get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?;
+                        let value = rest
+                            .get(32..40)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?;
+                        let share_limit = rest
+                            .get(40..42)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u16::from_le_bytes)
+                            .ok_or(InvalidInstruction)?;
+                        let ref_data = rest
+                            .get(42..74)
+                            .and_then(|slice| <[u8; 32]>::try_from(slice).ok())
+                            .ok_or(InvalidInstruction)?;
+                        let ref_data = if ref_data == [0u8; 32] {
+                            None
+                        } else {
+                            Some(Pubkey::new_from_array(ref_data))
+                        };
+                        Params::Init(key, value, share_limit, ref_data)
+                    }
+                    1 => Params::Key(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    2 => Params::Value(
+                        rest.get(..8)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    3 => Params::ShareLimit(
+                        rest.get(..2)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u16::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    4 => Params::ReferenceData(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(Pubkey::new_from_array)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    5 => Params::ReferralBps(
+                        rest.get(..2)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u16::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    6 => Params::AccessDuration(
+                        rest.get(..8)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(i64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    7 => Params::Uri(unpack_bytes(rest)?),
+                    8 => Params::Size(
+                        rest.get(..8)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    9 => Params::ContentType(unpack_bytes(rest)?),
+                    10 => Params::Clear(FieldMask(*rest.first().ok_or(InvalidInstruction)?)),
+                    11 => Params::Resharable(match rest.first() {
+                        Some(0) => false,
+                        Some(1) => true,
+                        _ => return Err(InvalidInstruction.into()),
+                    }),
+                    12 => Params::MaxShareDepth(*rest.first().ok_or(InvalidInstruction)?),
+                    13 => Params::Tiers(unpack_tiers(rest)?),
+                    14 => Params::MaxHolders(
+                        rest.get(..4)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u32::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    15 => Params::SetPrice(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(Pubkey::new_from_array)
+                            .ok_or(InvalidInstruction)?,
+                        rest.get(32..40)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    16 => Params::RemovePrice(
+                        rest.get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(Pubkey::new_from_array)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    17 => Params::SaleWindow {
+                        start: rest
+                            .get(..8)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(i64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                        end: match rest
+                            .get(8..16)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(i64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?
+                        {
+                            0 => None,
+                            n => Some(n),
+                        },
+                    },
+                    18 => Params::PruneBounty(
+                        rest.get(..8)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    19 => Params::ShareFee {
+                        mint: rest
+                            .get(..32)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(Pubkey::new_from_array)
+                            .ok_or(InvalidInstruction)?,
+                        amount: rest
+                            .get(32..40)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u64::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    },
+                    20 => Params::AddTag(
+                        rest.get(..TAG_LEN)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    21 => Params::RemoveTag(
+                        rest.get(..TAG_LEN)
+                            .and_then(|slice| slice.try_into().ok())
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    22 => Params::DerivedDiscountBps(
+                        rest.get(..2)
+                            .and_then(|slice| slice.try_into().ok())
+                            .map(u16::from_le_bytes)
+                            .ok_or(InvalidInstruction)?,
+                    ),
+                    _ => return Err(InvalidInstruction.into()),
+                };
+                Ok(Self::SetDataParams { hash, params })
+            }
+            tag::PURCHASE_ACCESS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::PurchaseAccess { hash, amount })
+            }
+            tag::SHARE_ACCESS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let expires_in = match rest.get(32) {
+                    Some(0) => None,
+                    Some(1) => rest
+                        .get(33..41)
+                        .and_then(|slice| slice.try_into().ok())
+                        .map(i64::from_le_bytes)
+                        .map(Some)
+                        .ok_or(InvalidInstruction)?,
+                    _ => return Err(InvalidInstruction.into()),
+                };
+                Ok(Self::ShareAccess { hash, expires_in })
+            }
+            tag::CLOSE_DATASET => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let force = match rest.get(32) {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(InvalidInstruction.into()),
+                };
+                Ok(Self::CloseDataset { hash, force })
+            }
+            tag::RENEW_ACCESS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::RenewAccess { hash, amount })
+            }
+            tag::SHARE_ACCESS_MULTI => Ok(Self::ShareAccessMulti {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::INIT_ASSOCIATED_ACCESS => Ok(Self::InitAssociatedAccess {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::RESIZE_INDEX => Ok(Self::ResizeIndex {
+                additional_slots: rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::CREATE_COLLECTION => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let value = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::CreateCollection { hash, value })
+            }
+            tag::ADD_TO_COLLECTION => {
+                let collection_hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let dataset_hash = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::AddToCollection {
+                    collection_hash,
+                    dataset_hash,
+                })
+            }
+            tag::CLAIM_COLLECTION_ACCESS => Ok(Self::ClaimCollectionAccess {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::PURCHASE_SEATS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let seats = rest
+                    .get(32..34)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let amount = rest
+                    .get(34..42)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::PurchaseSeats {
+                    hash,
+                    seats,
+                    amount,
+                })
+            }
+            tag::INIT_VAULT => Ok(Self::InitVault {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::WITHDRAW_PROCEEDS => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::WithdrawProceeds { hash, amount })
+            }
+            tag::INIT_RECEIPT_MINT => Ok(Self::InitReceiptMint {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::REVOKE_ACCESS => Ok(Self::RevokeAccess {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::MIGRATE_ACCOUNT => Ok(Self::MigrateAccount),
+            tag::PROPOSE_OWNERSHIP_TRANSFER => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let new_owner = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::ProposeOwnershipTransfer { hash, new_owner })
+            }
+            tag::ACCEPT_OWNERSHIP => Ok(Self::AcceptOwnership {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::CANCEL_OWNERSHIP_TRANSFER => Ok(Self::CancelOwnershipTransfer {
+                hash: rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?,
+            }),
+            tag::CRANK_PRUNE => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let holder = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::CrankPrune { hash, holder })
+            }
+            tag::FUND_BOUNTY => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let amount = rest
+                    .get(32..40)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::FundBounty { hash, amount })
+            }
+            tag::COMPACT_INDEX => {
+                let shrink = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(InvalidInstruction.into()),
+                };
+                let headroom = rest
+                    .get(1..3)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::CompactIndex { shrink, headroom })
+            }
+            tag::SET_ACCESS_PREFERENCES => {
+                let require_share_approval = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(InvalidInstruction.into()),
+                };
+                Ok(Self::SetAccessPreferences {
+                    require_share_approval,
+                })
+            }
+            tag::ACCEPT_SHARE => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let from = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::AcceptShare { hash, from })
+            }
+            tag::REJECT_SHARE => {
+                let hash = rest
+                    .get(..32)
+                    .and_then(|slice| slice.try_into().ok())
+                    .ok_or(InvalidInstruction)?;
+                let from = rest
+                    .get(32..64)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(Pubkey::new_from_array)
+                    .ok_or(InvalidInstruction)?;
+                Ok(Self::RejectShare { hash, from })
+            }
+            _ => Err(InvalidInstruction.into()),
+        }
+    }
+}
+
+/// Reads a `u16` length-prefixed byte string
+fn unpack_bytes(input: &[u8]) -> Result<Vec<u8>, ProgramError> {
+    let len = input
+        .get(..2)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(InvalidInstruction)? as usize;
+    let bytes = input
+        .get(2..)
+        .and_then(|input| input.get(..len))
+        .ok_or(InvalidInstruction)?;
+    Ok(bytes.to_vec())
+}
+
+/// Reads a `u8` count followed by that many price tiers
+fn unpack_tiers(input: &[u8]) -> Result<Vec<PriceTier>, ProgramError> {
+    let (count, rest) = input.split_first().ok_or(InvalidInstruction)?;
+    let count = usize::from(*count);
+    let tiers: Vec<_> = rest
+        .chunks_exact(PriceTier::LEN)
+        .take(count)
+        .map(|tier| {
+            Some(PriceTier {
+                min_seats: tier
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)?,
+                unit_price: tier
+                    .get(2..10)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)?,
+            })
+        })
+        .collect::<Option<_>>()
+        .ok_or(InvalidInstruction)?;
+    if tiers.len() != count {
+        return Err(InvalidInstruction.into());
+    }
+
+    Ok(tiers)
+}
+
+/// Creates an `InitAccount` instruction
+pub fn init_account(
+    program_id: Pubkey,
+    payer: Option<Pubkey>,
+    authority: Pubkey,
+    new_account: Pubkey,
+    index_account: Option<Pubkey>,
+    system_program: Pubkey,
+    account_type: AccountType,
+) -> Result<Instruction, ProgramError> {
+    let payer = payer.unwrap_or(authority);
+    let accounts = match account_type {
+        AccountType::DatasetIndex | AccountType::AccessIndex => InitIndexAccounts {
+            payer,
+            authority,
+            index_account: new_account,
+            system_program,
+        }
+        .to_account_metas(),
+        AccountType::Dataset(_) => InitDatasetAccounts {
+            authority,
+            owner_index: index_account.ok_or(ProgramError::NotEnoughAccountKeys)?,
+            dataset_account: new_account,
+            system_program,
+            registry: None,
+        }
+        .to_account_metas(),
+        // Access accounts are created by `init_associated_access`
+        AccountType::Access(_) => return Err(InvalidInstruction.into()),
+    };
+
+    let data = DataNexusInstruction::InitAccount(account_type).pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitAccount` instruction creating the dataset of `hash`,
+/// listed in the registry when `accounts` carries a registry page
+pub fn init_dataset_account(
+    program_id: Pubkey,
+    accounts: InitDatasetAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::InitAccount(AccountType::Dataset(hash)).pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `SetDataParams` instruction, signed by the `signers` of a
+/// multisig owner when there are any
+pub fn set_data_params(
+    program_id: Pubkey,
+    accounts: SetDataParamsAccounts<Pubkey>,
+    hash: [u8; 32],
+    params: Params,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::SetDataParams { hash, params }.pack();
+    let mut accounts = accounts.to_account_metas();
+    push_signer_metas(&mut accounts, signers);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `PurchaseAccess` instruction
+pub fn purchase_access(
+    program_id: Pubkey,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::PurchaseAccess { hash, amount }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `ShareAccess` instruction, the recipient's access expiring
+/// `expires_in` seconds after it lands when set
+pub fn share_access(
+    program_id: Pubkey,
+    accounts: ShareAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    expires_in: Option<i64>,
+    fee_accounts: Option<ShareFeeAccounts<Pubkey>>,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::ShareAccess { hash, expires_in }.pack();
+    let mut accounts = accounts.to_account_metas();
+    if let Some(fee_accounts) = fee_accounts {
+        accounts.extend(fee_accounts.to_account_metas());
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CloseDataset` instruction
+pub fn close_dataset(
+    program_id: Pubkey,
+    accounts: CloseDatasetAccounts<Pubkey>,
+    hash: [u8; 32],
+    force: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::CloseDataset { hash, force }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `RenewAccess` instruction
+pub fn renew_access(
+    program_id: Pubkey,
+    accounts: RenewAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::RenewAccess { hash, amount }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `ShareAccessMulti` instruction
+pub fn share_access_multi(
+    program_id: Pubkey,
+    accounts: ShareAccessMultiAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::ShareAccessMulti { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates an `InitAssociatedAccess` instruction
+pub fn init_associated_access(
+    program_id: Pubkey,
+    accounts: InitAssociatedAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::InitAssociatedAccess { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `ResizeIndex` instruction
+pub fn resize_index(
+    program_id: Pubkey,
+    accounts: ResizeIndexAccounts<Pubkey>,
+    additional_slots: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::ResizeIndex { additional_slots }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `PurchaseAccess` instruction buying a collection
+pub fn purchase_collection(
+    program_id: Pubkey,
+    accounts: PurchaseCollectionAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::PurchaseAccess { hash, amount }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `CreateCollection` instruction
+pub fn create_collection(
+    program_id: Pubkey,
+    accounts: CreateCollectionAccounts<Pubkey>,
+    hash: [u8; 32],
+    value: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::CreateCollection { hash, value }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates an `AddToCollection` instruction
+pub fn add_to_collection(
+    program_id: Pubkey,
+    accounts: AddToCollectionAccounts<Pubkey>,
+    collection_hash: [u8; 32],
+    dataset_hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::AddToCollection {
+        collection_hash,
+        dataset_hash,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `ClaimCollectionAccess` instruction
+pub fn claim_collection_access(
+    program_id: Pubkey,
+    accounts: ClaimCollectionAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::ClaimCollectionAccess { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `PurchaseSeats` instruction
+pub fn purchase_seats(
+    program_id: Pubkey,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    seats: u16,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::PurchaseSeats {
+        hash,
+        seats,
+        amount,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates an `InitVault` instruction
+pub fn init_vault(
+    program_id: Pubkey,
+    accounts: InitVaultAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::InitVault { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `WithdrawProceeds` instruction, signed by the `signers` of a
+/// multisig owner when there are any
+pub fn withdraw_proceeds(
+    program_id: Pubkey,
+    accounts: WithdrawProceedsAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::WithdrawProceeds { hash, amount }.pack();
+    let mut accounts = accounts.to_account_metas();
+    push_signer_metas(&mut accounts, signers);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitReceiptMint` instruction
+pub fn init_receipt_mint(
+    program_id: Pubkey,
+    accounts: InitReceiptMintAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::InitReceiptMint { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `RevokeAccess` instruction
+pub fn revoke_access(
+    program_id: Pubkey,
+    accounts: RevokeAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::RevokeAccess { hash }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `MigrateAccount` instruction
+pub fn migrate_account(
+    program_id: Pubkey,
+    accounts: MigrateAccountAccounts<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::MigrateAccount.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `ProposeOwnershipTransfer` instruction, signed by the
+/// `signers` of a multisig owner when there are any
+pub fn propose_ownership_transfer(
+    program_id: Pubkey,
+    accounts: OwnershipTransferAccounts<Pubkey>,
+    hash: [u8; 32],
+    new_owner: Pubkey,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::ProposeOwnershipTransfer { hash, new_owner }.pack();
+    let mut accounts = accounts.to_account_metas();
+    push_signer_metas(&mut accounts, signers);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AcceptOwnership` instruction, signed by the `signers` of a
+/// multisig pending owner when there are any
+pub fn accept_ownership(
+    program_id: Pubkey,
+    accounts: AcceptOwnershipAccounts<Pubkey>,
+    hash: [u8; 32],
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::AcceptOwnership { hash }.pack();
+    let mut accounts = accounts.to_account_metas();
+    push_signer_metas(&mut accounts, signers);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CancelOwnershipTransfer` instruction, signed by the
+/// `signers` of a multisig owner when there are any
+pub fn cancel_ownership_transfer(
+    program_id: Pubkey,
+    accounts: OwnershipTransferAccounts<Pubkey>,
+    hash: [u8; 32],
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::CancelOwnershipTransfer { hash }.pack();
+    let mut accounts = accounts.to_account_metas();
+    push_signer_metas(&mut accounts, signers);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `CrankPrune` instruction
+pub fn crank_prune(
+    program_id: Pubkey,
+    accounts: CrankPruneAccounts<Pubkey>,
+    hash: [u8; 32],
+    holder: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::CrankPrune { hash, holder }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `FundBounty` instruction
+pub fn fund_bounty(
+    program_id: Pubkey,
+    accounts: FundBountyAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::FundBounty { hash, amount }.pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `CompactIndex` instruction
+pub fn compact_index(
+    program_id: Pubkey,
+    accounts: CompactIndexAccounts<Pubkey>,
+    shrink: bool,
+    headroom: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::CompactIndex { shrink, headroom }.pack();
 
-        match tag {
-            0 => Ok(match rest {
-                0 => Self::InitAccount(AccountType::DatasetIndex),
-                1 => Self::InitAccount(AccountType::AccessIndex),
-                2 => Self::InitAccount(AccountType::DatasetAccount(
-                    rest.get(1..33)
-                        .and_then(|slice| slice.try_into().ok())
-                        .unwrap(),
-                )),
-                3 => Self::InitAccount(AccountType::AccessAccount(
-                    rest.get(1..33)
-                        .and_then(|slice| slice.try_into().ok())
-                        .unwrap(),
-                )),
-                _ => return Err(InvalidInstruction.into()),
-            }),
-            1 => {
-                let (tag, rest) = rest.split_first().ok_or(InvalidInstruction)?;
-                let params = match tag {
-                    0 => {
-                        let key = Params::Key(rest.get(..32).unwrap());
-                        let value = Params::Value(
-                            rest.get(32..40)
-                                .and_then(|slice| slice.try_into().ok())
-                                .map(u64::from_le_bytes)
-                                .ok_or(InvalidInstruction)?,
-                        );
-                        let share_limit = Params::ShareLimit(
-                            rest.get(40..42)
-                                .and_then(|slice| slice.try_into().ok())
-                                .map(u16::from_le_bytes)
-                                .ok_or(InvalidInstruction)?,
-                        );
-                        let ref_data = Params::ReferenceData(
-                            rest.get(42..50)
-                                .and_then(|slice| slice.try_into().ok())
-                                .map(Pubkey::new)
-                                .ok_or(InvalidInstruction)?,
-                        );
-                        Params::Init(key, value, share_limit, ref_data)
-                    }
-                    1 => Params::Key(rest.get(..).unwrap()),
-                    2 => Params::Value(
-                        rest.get(..)
-                            .and_then(|slice| slice.try_into().ok())
-                            .map(u64::from_le_bytes)
-                            .ok_or(InvalidInstruction)?,
-                    ),
-                    3 => Params::ShareLimit(
-                        rest.get(..)
-                            .and_then(|slice| slice.try_into().ok())
-                            .map(u16::from_le_bytes)
-                            .ok_or(InvalidInstruction)?,
-                    ),
-                    4 => Params::ReferenceData(
-                        rest.get(..)
-                            .and_then(|slice| slice.try_into().ok())
-                            .unwrap(),
-                    ),
-                    _ => return Err(InvalidInstruction.into()),
-                };
-                Ok(Self::SetDataParams { hash, params })
-            }
-            2 => {
-                let hash = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let amount = rest
-                    .get(32..40)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Ok(Self::PurchaseAccess { hash, amount })
-            }
-            3 => Ok(Self::ShareAccess {
-                hash: rest
-                    .get(..)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap(),
-            }),
-            _ => return Err(InvalidInstruction.into()),
-        }
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
+
+/// Creates a `SetAccessPreferences` instruction
+pub fn set_access_preferences(
+    program_id: Pubkey,
+    accounts: SetAccessPreferencesAccounts<Pubkey>,
+    require_share_approval: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::SetAccessPreferences {
+        require_share_approval,
     }
+    .pack();
+
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
 }
 
-/// Creates an `InitAccount` instruction
-pub fn init_account(
+/// Creates an `AcceptShare` instruction
+pub fn accept_share(
     program_id: Pubkey,
-    payer: Option<Pubkey>,
-    authority: Pubkey,
-    new_account: Pubkey,
-    index_account: Option<Pubkey>,
-    system_program: Pubkey,
-    account_type: AccountType,
-    hash: Option<[u8; 32]>,
+    accounts: AcceptShareAccounts<Pubkey>,
+    hash: [u8; 32],
+    from: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let (accounts, data) = match account_type {
-        AccountType::DatasetIndex => init_dataset_index(authority, new_account, system_program),
-        AccountType::AccessIndex => init_access_index(
-            if let Some(n) = payer { n } else { authority },
-            authority,
-            new_account,
-            system_program,
-        ),
-        AccountType::Dataset(_) => init_dataset_account(
-            authority,
-            index_account.unwrap(),
-            new_account,
-            system_program,
-            hash.unwrap(),
-        ),
-        AccountType::Access(_) => init_access_account(
-            if let Some(n) = payer { n } else { authority },
-            authority,
-            index_account.unwrap(),
-            new_account,
-            system_program,
-            hash.unwrap(),
-        ),
-        _ => unreachable!(),
-    };
+    let data = DataNexusInstruction::AcceptShare { hash, from }.pack();
 
     Ok(Instruction {
         program_id,
-        accounts,
+        accounts: accounts.to_account_metas(),
         data,
     })
 }
 
-fn init_dataset_index(
-    authority: Pubkey,
-    dataset_index: Pubkey,
-    system_program: Pubkey,
-) -> (
-    Vec<AccountMeta>,
-    DataNexusInstruction::InitAccount(AccountType::DatasetIndex),
-) {
-    let accounts = vec![
-        AccountMeta::new(authority, true),
-        AccountMeta::new(dataset_index, true),
-        AccountMeta::new_readonly(system_program, false),
-    ];
+/// Creates a `RejectShare` instruction
+pub fn reject_share(
+    program_id: Pubkey,
+    accounts: RejectShareAccounts<Pubkey>,
+    hash: [u8; 32],
+    from: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = DataNexusInstruction::RejectShare { hash, from }.pack();
 
-    let data = DataNexusInstruction::InitAccount(AccountType::DatasetIndex).pack();
+    Ok(Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(),
+        data,
+    })
+}
 
-    (accounts, data)
+/// Re-encodes an instruction from one of the builders above with borsh
+#[cfg(feature = "borsh-encoding")]
+fn into_borsh(mut instruction: Instruction) -> Result<Instruction, ProgramError> {
+    instruction.data = DataNexusInstruction::unpack(&instruction.data)?.pack_borsh()?;
+    Ok(instruction)
 }
 
-fn init_access_index(
-    payer: Pubkey,
+/// Creates a borsh encoded `InitAccount` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn init_account_borsh(
+    program_id: Pubkey,
+    payer: Option<Pubkey>,
     authority: Pubkey,
-    access_index: Pubkey,
+    new_account: Pubkey,
+    index_account: Option<Pubkey>,
     system_program: Pubkey,
-) -> (
-    Vec<AccountMeta>,
-    DataNexusInstruction::InitAccount(AccountType::AccessIndex),
-) {
-    let accounts = vec![
-        AccountMeta::new(payer, true),
-        AccountMeta::new(authority, false),
-        AccountMeta::new(access_index, false),
-        AccountMeta::new_readonly(system_program, false),
-    ];
+    account_type: AccountType,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(init_account(
+        program_id,
+        payer,
+        authority,
+        new_account,
+        index_account,
+        system_program,
+        account_type,
+    )?)
+}
+
+/// Creates a borsh encoded `InitAccount` instruction creating a dataset
+#[cfg(feature = "borsh-encoding")]
+pub fn init_dataset_account_borsh(
+    program_id: Pubkey,
+    accounts: InitDatasetAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(init_dataset_account(program_id, accounts, hash)?)
+}
 
-    let data = DataNexusInstruction::InitAccount(AccountType::AccessIndex).pack();
+/// Creates a borsh encoded `SetDataParams` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn set_data_params_borsh(
+    program_id: Pubkey,
+    accounts: SetDataParamsAccounts<Pubkey>,
+    hash: [u8; 32],
+    params: Params,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(set_data_params(
+        program_id, accounts, hash, params, signers,
+    )?)
+}
 
-    (accounts, data)
+/// Creates a borsh encoded `PurchaseAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn purchase_access_borsh(
+    program_id: Pubkey,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(purchase_access(program_id, accounts, hash, amount)?)
 }
 
-fn init_dataset_account(
-    authority: Pubkey,
-    dataset_account: Pubkey,
-    dataset_index: Pubkey,
-    system_program: Pubkey,
+/// Creates a borsh encoded `ShareAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn share_access_borsh(
+    program_id: Pubkey,
+    accounts: ShareAccessAccounts<Pubkey>,
     hash: [u8; 32],
-) -> (
-    Vec<AccountMeta>,
-    DataNexusInstruction::InitAccount(AccountType::Dataset([u8; 32])),
-) {
-    let accounts = vec![
-        AccountMeta::new(authority, true),
-        AccountMeta::new(dataset_account, false),
-        AccountMeta::new(dataset_index, false),
-        AccountMeta::new_readonly(system_program, false),
-    ];
+    expires_in: Option<i64>,
+    fee_accounts: Option<ShareFeeAccounts<Pubkey>>,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(share_access(
+        program_id,
+        accounts,
+        hash,
+        expires_in,
+        fee_accounts,
+    )?)
+}
 
-    let data = DataNexusInstruction::InitAccount(AccountType::Dataset(hash)).pack();
+/// Creates a borsh encoded `CloseDataset` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn close_dataset_borsh(
+    program_id: Pubkey,
+    accounts: CloseDatasetAccounts<Pubkey>,
+    hash: [u8; 32],
+    force: bool,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(close_dataset(program_id, accounts, hash, force)?)
+}
 
-    (accounts, data)
+/// Creates a borsh encoded `RenewAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn renew_access_borsh(
+    program_id: Pubkey,
+    accounts: RenewAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(renew_access(program_id, accounts, hash, amount)?)
 }
 
-fn init_access_account(
-    payer: Pubkey,
-    authority: Pubkey,
-    access_account: Pukey,
-    access_index: Pubkey,
-    system_program: Pubkey,
+/// Creates a borsh encoded `ShareAccessMulti` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn share_access_multi_borsh(
+    program_id: Pubkey,
+    accounts: ShareAccessMultiAccounts<Pubkey>,
     hash: [u8; 32],
-) -> (
-    Vec<AccountMeta>,
-    DataNexusInstruction::InitAccount(AccountType::Access([u8; 32])),
-) {
-    let accounts = vec![
-        AccountMeta::new(payer, true),
-        AccountMeta::new(authority, false),
-        AccountMeta::new(access_account, false),
-        AccountMeta::new(access_index, false),
-        AccountMeta::new_readonly(system_program, false),
-    ];
+) -> Result<Instruction, ProgramError> {
+    into_borsh(share_access_multi(program_id, accounts, hash)?)
+}
 
-    let data = DataNexusInstruction::InitAccount(AccountType::Access(hash)).pack();
+/// Creates a borsh encoded `InitAssociatedAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn init_associated_access_borsh(
+    program_id: Pubkey,
+    accounts: InitAssociatedAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(init_associated_access(program_id, accounts, hash)?)
+}
 
-    (accounts, data)
+/// Creates a borsh encoded `ResizeIndex` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn resize_index_borsh(
+    program_id: Pubkey,
+    accounts: ResizeIndexAccounts<Pubkey>,
+    additional_slots: u16,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(resize_index(program_id, accounts, additional_slots)?)
 }
 
-/// Creates a `SetDataParams` instruction
-pub fn set_data_params(
+/// Creates a borsh encoded `PurchaseAccess` instruction buying a collection
+#[cfg(feature = "borsh-encoding")]
+pub fn purchase_collection_borsh(
     program_id: Pubkey,
-    authority: Pubkey,
-    dataset_account: Pubkey,
+    accounts: PurchaseCollectionAccounts<Pubkey>,
     hash: [u8; 32],
-    params: Params,
+    amount: u64,
 ) -> Result<Instruction, ProgramError> {
-    let accounts = vec![
-        AccountMeta::new(authority, true),
-        AccountMeta::new(owner_account, false),
-        AccountMeta::new(dataset_account, false),
-    ];
+    into_borsh(purchase_collection(program_id, accounts, hash, amount)?)
+}
 
-    let data = DataNexusInstruction::SetDataParams { hash, params }.pack();
+/// Creates a borsh encoded `CreateCollection` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn create_collection_borsh(
+    program_id: Pubkey,
+    accounts: CreateCollectionAccounts<Pubkey>,
+    hash: [u8; 32],
+    value: u64,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(create_collection(program_id, accounts, hash, value)?)
+}
 
-    Ok(Instruction {
+/// Creates a borsh encoded `AddToCollection` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn add_to_collection_borsh(
+    program_id: Pubkey,
+    accounts: AddToCollectionAccounts<Pubkey>,
+    collection_hash: [u8; 32],
+    dataset_hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(add_to_collection(
         program_id,
         accounts,
-        data,
-    })
+        collection_hash,
+        dataset_hash,
+    )?)
 }
 
-/// Creates a `PurchaseAccess` instruction
-pub fn purchase_access(
+/// Creates a borsh encoded `ClaimCollectionAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn claim_collection_access_borsh(
     program_id: Pubkey,
-    user_authority: Pubkey,
-    user_access_index: Pubkey,
-    user_access_account: Pubkey,
-    user_token_account: Pubkey,
-    owner_authority: Pubkey,
-    owner_token_account: Pubkey,
-    dataset_account: Pubkey,
-    token_program: Pubkey,
+    accounts: ClaimCollectionAccessAccounts<Pubkey>,
     hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(claim_collection_access(program_id, accounts, hash)?)
+}
+
+/// Creates a borsh encoded `PurchaseSeats` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn purchase_seats_borsh(
+    program_id: Pubkey,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    hash: [u8; 32],
+    seats: u16,
     amount: u64,
 ) -> Result<Instruction, ProgramError> {
-    let accounts = vec![
-        AccountMeta::new(user_authority, true),
-        AccountMeta::new(user_access_index, false),
-        AccountMeta::new(user_access_account, false),
-        AccountMeta::new(user_token_account, false),
-        AccountMeta::new(owner_authority, false),
-        AccountMeta::new(owner_token_account, false),
-        AccountMeta::new_readonly(dataset_account, false),
-        AccountMeta::new_readonly(token_program, false),
-    ];
+    into_borsh(purchase_seats(program_id, accounts, hash, seats, amount)?)
+}
 
-    let data = DataNexusInstruction::PurchaseAccess { hash, amount }.pack();
+/// Creates a borsh encoded `InitVault` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn init_vault_borsh(
+    program_id: Pubkey,
+    accounts: InitVaultAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(init_vault(program_id, accounts, hash)?)
+}
 
-    Ok(Instruction {
-        program_id,
-        accounts,
-        data,
-    })
+/// Creates a borsh encoded `WithdrawProceeds` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn withdraw_proceeds_borsh(
+    program_id: Pubkey,
+    accounts: WithdrawProceedsAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(withdraw_proceeds(
+        program_id, accounts, hash, amount, signers,
+    )?)
 }
 
-/// Creates a `ShareAccess` instruction
-pub fn share_access(
+/// Creates a borsh encoded `InitReceiptMint` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn init_receipt_mint_borsh(
+    program_id: Pubkey,
+    accounts: InitReceiptMintAccounts<Pubkey>,
+    hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(init_receipt_mint(program_id, accounts, hash)?)
+}
+
+/// Creates a borsh encoded `RevokeAccess` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn revoke_access_borsh(
     program_id: Pubkey,
-    user_authority: Pubkey,
-    user_access_account: Pubkey,
-    recipient_authority: Pubkey,
-    recipient_access_index: Pubkey,
-    recipient_access_account: Pubkey,
-    dataset_account: Pubkey,
+    accounts: RevokeAccessAccounts<Pubkey>,
     hash: [u8; 32],
 ) -> Result<Instruction, ProgramError> {
-    let accounts = vec![
-        AccountMeta::new(user_authority, true),
-        AccountMeta::new(user_access_account, false),
-        AccountMeta::new(recipient_authority, false),
-        AccountMeta::new(recipient_access_index, false),
-        AccountMeta::new(recipient_access_account, false),
-        AccountMeta::new_readonly(dataset_account, false),
-    ];
+    into_borsh(revoke_access(program_id, accounts, hash)?)
+}
 
-    let data = DataNexusInstruction { hash }.pack();
+/// Creates a borsh encoded `MigrateAccount` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn migrate_account_borsh(
+    program_id: Pubkey,
+    accounts: MigrateAccountAccounts<Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(migrate_account(program_id, accounts)?)
+}
 
-    Ok(Instruction {
+/// Creates a borsh encoded `ProposeOwnershipTransfer` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn propose_ownership_transfer_borsh(
+    program_id: Pubkey,
+    accounts: OwnershipTransferAccounts<Pubkey>,
+    hash: [u8; 32],
+    new_owner: Pubkey,
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(propose_ownership_transfer(
+        program_id, accounts, hash, new_owner, signers,
+    )?)
+}
+
+/// Creates a borsh encoded `AcceptOwnership` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn accept_ownership_borsh(
+    program_id: Pubkey,
+    accounts: AcceptOwnershipAccounts<Pubkey>,
+    hash: [u8; 32],
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(accept_ownership(program_id, accounts, hash, signers)?)
+}
+
+/// Creates a borsh encoded `CancelOwnershipTransfer` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn cancel_ownership_transfer_borsh(
+    program_id: Pubkey,
+    accounts: OwnershipTransferAccounts<Pubkey>,
+    hash: [u8; 32],
+    signers: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    into_borsh(cancel_ownership_transfer(
+        program_id, accounts, hash, signers,
+    )?)
+}
+
+/// Creates a borsh encoded `CrankPrune` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn crank_prune_borsh(
+    program_id: Pubkey,
+    accounts: CrankPruneAccounts<Pubkey>,
+    hash: [u8; 32],
+    holder: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(crank_prune(program_id, accounts, hash, holder)?)
+}
+
+/// Creates a borsh encoded `FundBounty` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn fund_bounty_borsh(
+    program_id: Pubkey,
+    accounts: FundBountyAccounts<Pubkey>,
+    hash: [u8; 32],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(fund_bounty(program_id, accounts, hash, amount)?)
+}
+
+/// Creates a borsh encoded `CompactIndex` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn compact_index_borsh(
+    program_id: Pubkey,
+    accounts: CompactIndexAccounts<Pubkey>,
+    shrink: bool,
+    headroom: u16,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(compact_index(program_id, accounts, shrink, headroom)?)
+}
+
+/// Creates a borsh encoded `SetAccessPreferences` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn set_access_preferences_borsh(
+    program_id: Pubkey,
+    accounts: SetAccessPreferencesAccounts<Pubkey>,
+    require_share_approval: bool,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(set_access_preferences(
         program_id,
         accounts,
-        data,
-    })
+        require_share_approval,
+    )?)
+}
+
+/// Creates a borsh encoded `AcceptShare` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn accept_share_borsh(
+    program_id: Pubkey,
+    accounts: AcceptShareAccounts<Pubkey>,
+    hash: [u8; 32],
+    from: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(accept_share(program_id, accounts, hash, from)?)
+}
+
+/// Creates a borsh encoded `RejectShare` instruction
+#[cfg(feature = "borsh-encoding")]
+pub fn reject_share_borsh(
+    program_id: Pubkey,
+    accounts: RejectShareAccounts<Pubkey>,
+    hash: [u8; 32],
+    from: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    into_borsh(reject_share(program_id, accounts, hash, from)?)
+}
+
+#[cfg(test)]
+#[allow(clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    const HASH: [u8; 32] = [1; 32];
+
+    /// Tag every variant packs behind, the match has no wildcard so a new
+    /// variant fails to build until it is listed here
+    fn expected_tag(instruction: &DataNexusInstruction) -> u8 {
+        match instruction {
+            DataNexusInstruction::InitAccount(_) => tag::INIT_ACCOUNT,
+            DataNexusInstruction::SetDataParams { .. } => tag::SET_DATA_PARAMS,
+            DataNexusInstruction::PurchaseAccess { .. } => tag::PURCHASE_ACCESS,
+            DataNexusInstruction::ShareAccess { .. } => tag::SHARE_ACCESS,
+            DataNexusInstruction::CloseDataset { .. } => tag::CLOSE_DATASET,
+            DataNexusInstruction::RenewAccess { .. } => tag::RENEW_ACCESS,
+            DataNexusInstruction::ShareAccessMulti { .. } => tag::SHARE_ACCESS_MULTI,
+            DataNexusInstruction::InitAssociatedAccess { .. } => tag::INIT_ASSOCIATED_ACCESS,
+            DataNexusInstruction::ResizeIndex { .. } => tag::RESIZE_INDEX,
+            DataNexusInstruction::CreateCollection { .. } => tag::CREATE_COLLECTION,
+            DataNexusInstruction::AddToCollection { .. } => tag::ADD_TO_COLLECTION,
+            DataNexusInstruction::ClaimCollectionAccess { .. } => tag::CLAIM_COLLECTION_ACCESS,
+            DataNexusInstruction::PurchaseSeats { .. } => tag::PURCHASE_SEATS,
+            DataNexusInstruction::InitVault { .. } => tag::INIT_VAULT,
+            DataNexusInstruction::WithdrawProceeds { .. } => tag::WITHDRAW_PROCEEDS,
+            DataNexusInstruction::InitReceiptMint { .. } => tag::INIT_RECEIPT_MINT,
+            DataNexusInstruction::RevokeAccess { .. } => tag::REVOKE_ACCESS,
+            DataNexusInstruction::MigrateAccount => tag::MIGRATE_ACCOUNT,
+            DataNexusInstruction::ProposeOwnershipTransfer { .. } => {
+                tag::PROPOSE_OWNERSHIP_TRANSFER
+            }
+            DataNexusInstruction::AcceptOwnership { .. } => tag::ACCEPT_OWNERSHIP,
+            DataNexusInstruction::CancelOwnershipTransfer { .. } => tag::CANCEL_OWNERSHIP_TRANSFER,
+            DataNexusInstruction::CrankPrune { .. } => tag::CRANK_PRUNE,
+            DataNexusInstruction::FundBounty { .. } => tag::FUND_BOUNTY,
+            DataNexusInstruction::CompactIndex { .. } => tag::COMPACT_INDEX,
+            DataNexusInstruction::SetAccessPreferences { .. } => tag::SET_ACCESS_PREFERENCES,
+            DataNexusInstruction::AcceptShare { .. } => tag::ACCEPT_SHARE,
+            DataNexusInstruction::RejectShare { .. } => tag::REJECT_SHARE,
+        }
+    }
+
+    fn every_variant() -> Vec<DataNexusInstruction> {
+        vec![
+            DataNexusInstruction::InitAccount(AccountType::Dataset(HASH)),
+            DataNexusInstruction::SetDataParams {
+                hash: HASH,
+                params: Params::Value(100),
+            },
+            DataNexusInstruction::PurchaseAccess {
+                hash: HASH,
+                amount: 100,
+            },
+            DataNexusInstruction::ShareAccess {
+                hash: HASH,
+                expires_in: Some(86_400),
+            },
+            DataNexusInstruction::CloseDataset {
+                hash: HASH,
+                force: false,
+            },
+            DataNexusInstruction::RenewAccess {
+                hash: HASH,
+                amount: 100,
+            },
+            DataNexusInstruction::ShareAccessMulti { hash: HASH },
+            DataNexusInstruction::InitAssociatedAccess { hash: HASH },
+            DataNexusInstruction::ResizeIndex {
+                additional_slots: 8,
+            },
+            DataNexusInstruction::CreateCollection {
+                hash: HASH,
+                value: 100,
+            },
+            DataNexusInstruction::AddToCollection {
+                collection_hash: HASH,
+                dataset_hash: [2; 32],
+            },
+            DataNexusInstruction::ClaimCollectionAccess { hash: HASH },
+            DataNexusInstruction::PurchaseSeats {
+                hash: HASH,
+                seats: 4,
+                amount: 400,
+            },
+            DataNexusInstruction::InitVault { hash: HASH },
+            DataNexusInstruction::WithdrawProceeds {
+                hash: HASH,
+                amount: 100,
+            },
+            DataNexusInstruction::InitReceiptMint { hash: HASH },
+            DataNexusInstruction::RevokeAccess { hash: HASH },
+            DataNexusInstruction::MigrateAccount,
+            DataNexusInstruction::ProposeOwnershipTransfer {
+                hash: HASH,
+                new_owner: Pubkey::new_from_array([2; 32]),
+            },
+            DataNexusInstruction::AcceptOwnership { hash: HASH },
+            DataNexusInstruction::CancelOwnershipTransfer { hash: HASH },
+            DataNexusInstruction::CrankPrune {
+                hash: HASH,
+                holder: Pubkey::new_from_array([2; 32]),
+            },
+            DataNexusInstruction::FundBounty {
+                hash: HASH,
+                amount: 100,
+            },
+            DataNexusInstruction::CompactIndex {
+                shrink: true,
+                headroom: 16,
+            },
+            DataNexusInstruction::SetAccessPreferences {
+                require_share_approval: true,
+            },
+            DataNexusInstruction::AcceptShare {
+                hash: HASH,
+                from: Pubkey::new_from_array([2; 32]),
+            },
+            DataNexusInstruction::RejectShare {
+                hash: HASH,
+                from: Pubkey::new_from_array([2; 32]),
+            },
+        ]
+    }
+
+    #[test]
+    fn every_variant_packs_behind_its_tag() {
+        let instructions = every_variant();
+        assert_eq!(instructions.len(), usize::from(tag::REJECT_SHARE) + 1);
+
+        for instruction in instructions {
+            let packed = instruction.pack_legacy();
+            assert_eq!(packed[0], expected_tag(&instruction), "{:?}", instruction);
+            assert_eq!(
+                DataNexusInstruction::unpack_legacy(&packed).unwrap(),
+                instruction
+            );
+        }
+    }
+
+    #[test]
+    fn params_build_init_or_a_single_setter() {
+        let reference = Pubkey::new_unique();
+        let init = ParamsBuilder::new().key(HASH).value(100).share_limit(3);
+
+        assert_eq!(init.build(), Ok(Params::Init(HASH, 100, 3, None)));
+        assert_eq!(
+            init.reference(reference).build(),
+            Ok(Params::Init(HASH, 100, 3, Some(reference)))
+        );
+        assert_eq!(
+            ParamsBuilder::new().key(HASH).build(),
+            Ok(Params::Key(HASH))
+        );
+        assert_eq!(
+            ParamsBuilder::new().value(100).build(),
+            Ok(Params::Value(100))
+        );
+        assert_eq!(
+            ParamsBuilder::new().share_limit(MAX_SHARE_LIMIT).build(),
+            Ok(Params::ShareLimit(MAX_SHARE_LIMIT))
+        );
+        assert_eq!(
+            ParamsBuilder::new()
+                .reference(reference)
+                .dataset(Pubkey::new_unique())
+                .build(),
+            Ok(Params::ReferenceData(reference))
+        );
+    }
+
+    #[test]
+    fn params_missing_or_partial_fields_are_rejected() {
+        assert_eq!(ParamsBuilder::new().build(), Err(ParamsError::Empty));
+        assert_eq!(
+            ParamsBuilder::new().key(HASH).value(100).build(),
+            Err(ParamsError::Incomplete)
+        );
+        assert_eq!(
+            ParamsBuilder::new()
+                .share_limit(3)
+                .reference(Pubkey::new_unique())
+                .build(),
+            Err(ParamsError::Incomplete)
+        );
+    }
+
+    #[test]
+    fn params_colliding_with_the_none_sentinels_are_rejected() {
+        assert_eq!(
+            ParamsBuilder::new().key([0; 32]).build(),
+            Err(ParamsError::ZeroKey)
+        );
+        assert_eq!(
+            ParamsBuilder::new()
+                .key(HASH)
+                .value(0)
+                .share_limit(3)
+                .build(),
+            Err(ParamsError::ZeroValue)
+        );
+    }
+
+    #[test]
+    fn params_share_limits_above_the_maximum_are_rejected() {
+        let share_limit = MAX_SHARE_LIMIT + 1;
+
+        assert_eq!(
+            ParamsBuilder::new().share_limit(share_limit).build(),
+            Err(ParamsError::ShareLimitTooLarge(share_limit))
+        );
+    }
+
+    #[test]
+    fn params_referencing_their_own_dataset_are_rejected() {
+        let dataset = Pubkey::new_unique();
+        let builder = ParamsBuilder::new().reference(dataset);
+
+        assert_eq!(
+            builder.dataset(dataset).build(),
+            Err(ParamsError::SelfReference)
+        );
+        // Without the dataset known the reference is left to the program
+        assert_eq!(builder.build(), Ok(Params::ReferenceData(dataset)));
+    }
+
+    #[test]
+    fn unknown_tags_are_rejected() {
+        for unknown in usize::from(tag::REJECT_SHARE) + 1..=usize::from(u8::MAX) {
+            assert!(DataNexusInstruction::unpack_legacy(&[unknown as u8]).is_err());
+        }
+    }
 }
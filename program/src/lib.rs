@@ -1,8 +1,28 @@
-#[cfg(not(feature = "no-entrypoint"))]
+#![deny(clippy::arithmetic_side_effects)]
+#![deny(clippy::indexing_slicing)]
+
+// Clients build with `no-entrypoint`, so linking several programs into one
+// binary doesn't clash on the `entrypoint` symbol. `exclude_entrypoint` is
+// the spelling other program crates use for the same build
+pub mod accounts;
+#[cfg(not(any(feature = "no-entrypoint", feature = "exclude_entrypoint")))]
 pub mod entrypoint;
 pub mod error;
+pub mod events;
+#[cfg(feature = "idl")]
+pub mod idl;
 pub mod instruction;
+#[macro_use]
+pub mod log;
+pub mod pda;
+pub mod prelude;
 pub mod processor;
+#[cfg(feature = "serde")]
+pub mod serde_fields;
 pub mod state;
+#[cfg(feature = "zero-copy")]
+pub mod zero_copy;
 
 pub mod datanexus_program;
+
+pub use datanexus_program::{check_id, id, ID};
@@ -0,0 +1,200 @@
+//! Structured program logs, compiled in with the `verbose-logs` feature
+//!
+//! Every instruction logs a `DataNexus instruction:` line with its name and
+//! key parameters, and failed account checks log a `DataNexus check failed:`
+//! line naming the offending account. Without the feature instructions log
+//! nothing and failed checks log their plain message, saving the compute
+//! units formatting costs.
+
+use std::fmt;
+
+use crate::instruction::{AccountType, DataNexusInstruction, Params};
+
+/// Logs a failed account check with the message of `msg!`, followed by the
+/// offending `key` with `verbose-logs`
+macro_rules! check_failed {
+    ($key:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        if cfg!(feature = "verbose-logs") {
+            solana_program::msg!(
+                concat!("DataNexus check failed: ", $fmt, ", account={}")
+                $(, $arg)*,
+                $key
+            )
+        } else {
+            solana_program::msg!($fmt $(, $arg)*)
+        }
+    };
+}
+
+/// First 4 bytes of a hash as 8 hex characters, enough to tell datasets
+/// apart in a log
+pub struct ShortHash<'a>(pub &'a [u8; 32]);
+
+impl fmt::Display for ShortHash<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0
+            .iter()
+            .take(4)
+            .try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Name of the dataset field `params` writes
+fn params_name(params: &Params) -> &'static str {
+    match params {
+        Params::Init(..) => "Init",
+        Params::Key(_) => "Key",
+        Params::Value(_) => "Value",
+        Params::ShareLimit(_) => "ShareLimit",
+        Params::ReferenceData(_) => "ReferenceData",
+        Params::ReferralBps(_) => "ReferralBps",
+        Params::AccessDuration(_) => "AccessDuration",
+        Params::Uri(_) => "Uri",
+        Params::Size(_) => "Size",
+        Params::ContentType(_) => "ContentType",
+        Params::Clear(_) => "Clear",
+        Params::Resharable(_) => "Resharable",
+        Params::MaxShareDepth(_) => "MaxShareDepth",
+        Params::Tiers(_) => "Tiers",
+        Params::MaxHolders(_) => "MaxHolders",
+        Params::SetPrice(..) => "SetPrice",
+        Params::RemovePrice(_) => "RemovePrice",
+        Params::SaleWindow { .. } => "SaleWindow",
+        Params::PruneBounty(_) => "PruneBounty",
+        Params::ShareFee { .. } => "ShareFee",
+        Params::AddTag(_) => "AddTag",
+        Params::RemoveTag(_) => "RemoveTag",
+        Params::DerivedDiscountBps(_) => "DerivedDiscountBps",
+    }
+}
+
+/// Logs the name and key parameters of `instruction`
+pub fn log_instruction(instruction: &DataNexusInstruction) {
+    macro_rules! log {
+        ($($arg:tt)+) => {
+            solana_program::msg!("DataNexus instruction: {}", format_args!($($arg)+))
+        };
+    }
+
+    match instruction {
+        DataNexusInstruction::InitAccount(account_type) => match account_type {
+            AccountType::DatasetIndex => log!("InitAccount type=DatasetIndex"),
+            AccountType::AccessIndex => log!("InitAccount type=AccessIndex"),
+            AccountType::Dataset(hash) => log!("InitAccount type=Dataset hash={}", ShortHash(hash)),
+            AccountType::Access(hash) => log!("InitAccount type=Access hash={}", ShortHash(hash)),
+        },
+        DataNexusInstruction::SetDataParams { hash, params } => log!(
+            "SetDataParams hash={} params={}",
+            ShortHash(hash),
+            params_name(params)
+        ),
+        DataNexusInstruction::PurchaseAccess { hash, amount } => {
+            log!("PurchaseAccess hash={} amount={}", ShortHash(hash), amount)
+        }
+        DataNexusInstruction::ShareAccess { hash, expires_in } => log!(
+            "ShareAccess hash={} expires_in={:?}",
+            ShortHash(hash),
+            expires_in
+        ),
+        DataNexusInstruction::CloseDataset { hash, force } => {
+            log!("CloseDataset hash={} force={}", ShortHash(hash), force)
+        }
+        DataNexusInstruction::RenewAccess { hash, amount } => {
+            log!("RenewAccess hash={} amount={}", ShortHash(hash), amount)
+        }
+        DataNexusInstruction::ShareAccessMulti { hash } => {
+            log!("ShareAccessMulti hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::InitAssociatedAccess { hash } => {
+            log!("InitAssociatedAccess hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::ResizeIndex { additional_slots } => {
+            log!("ResizeIndex additional_slots={}", additional_slots)
+        }
+        DataNexusInstruction::CreateCollection { hash, value } => {
+            log!("CreateCollection hash={} value={}", ShortHash(hash), value)
+        }
+        DataNexusInstruction::AddToCollection {
+            collection_hash,
+            dataset_hash,
+        } => log!(
+            "AddToCollection collection={} dataset={}",
+            ShortHash(collection_hash),
+            ShortHash(dataset_hash)
+        ),
+        DataNexusInstruction::ClaimCollectionAccess { hash } => {
+            log!("ClaimCollectionAccess hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::PurchaseSeats {
+            hash,
+            seats,
+            amount,
+        } => log!(
+            "PurchaseSeats hash={} seats={} amount={}",
+            ShortHash(hash),
+            seats,
+            amount
+        ),
+        DataNexusInstruction::InitVault { hash } => log!("InitVault hash={}", ShortHash(hash)),
+        DataNexusInstruction::WithdrawProceeds { hash, amount } => {
+            log!(
+                "WithdrawProceeds hash={} amount={}",
+                ShortHash(hash),
+                amount
+            )
+        }
+        DataNexusInstruction::InitReceiptMint { hash } => {
+            log!("InitReceiptMint hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::RevokeAccess { hash } => {
+            log!("RevokeAccess hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::MigrateAccount => log!("MigrateAccount"),
+        DataNexusInstruction::ProposeOwnershipTransfer { hash, new_owner } => log!(
+            "ProposeOwnershipTransfer hash={} new_owner={}",
+            ShortHash(hash),
+            new_owner
+        ),
+        DataNexusInstruction::AcceptOwnership { hash } => {
+            log!("AcceptOwnership hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::CancelOwnershipTransfer { hash } => {
+            log!("CancelOwnershipTransfer hash={}", ShortHash(hash))
+        }
+        DataNexusInstruction::CrankPrune { hash, holder } => {
+            log!("CrankPrune hash={} holder={}", ShortHash(hash), holder)
+        }
+        DataNexusInstruction::FundBounty { hash, amount } => {
+            log!("FundBounty hash={} amount={}", ShortHash(hash), amount)
+        }
+        DataNexusInstruction::CompactIndex { shrink, headroom } => {
+            log!("CompactIndex shrink={} headroom={}", shrink, headroom)
+        }
+        DataNexusInstruction::SetAccessPreferences {
+            require_share_approval,
+        } => log!(
+            "SetAccessPreferences require_share_approval={}",
+            require_share_approval
+        ),
+        DataNexusInstruction::AcceptShare { hash, from } => {
+            log!("AcceptShare hash={} from={}", ShortHash(hash), from)
+        }
+        DataNexusInstruction::RejectShare { hash, from } => {
+            log!("RejectShare hash={} from={}", ShortHash(hash), from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_hashes_are_eight_hex_characters() {
+        let mut hash = [0xff; 32];
+        hash[0] = 0x01;
+        hash[1] = 0xab;
+
+        assert_eq!(ShortHash(&hash).to_string(), "01abffff");
+    }
+}
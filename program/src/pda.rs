@@ -0,0 +1,171 @@
+use solana_program::pubkey::{Pubkey, PubkeyError};
+
+/// Seed prefix of an authority's owner index
+pub const OWNER_MARKER: &[u8] = b"owner";
+
+/// Seed prefix of an authority's access index
+pub const ACCESS_MARKER: &[u8] = b"access";
+
+/// Seed prefix of dataset accounts
+pub const DATASET_MARKER: &[u8] = b"dataset";
+
+/// Seed prefix of collection accounts
+pub const COLLECTION_MARKER: &[u8] = b"collection";
+
+/// Seed prefix of the token accounts holding a dataset's proceeds
+pub const VAULT_MARKER: &[u8] = b"vault";
+
+/// Seed prefix of a dataset's access receipt mint
+pub const RECEIPT_MARKER: &[u8] = b"receipt";
+
+/// Seed prefix of an authority's associated access account for a dataset or
+/// collection, which keeps it apart from other derivations over two keys
+pub const ASSOCIATED_ACCESS_MARKER: &[u8] = b"assoc-access";
+
+/// Seed prefix of the registry pages listing datasets by hash prefix
+pub const REGISTRY_MARKER: &[u8] = b"registry";
+
+pub fn find_owner_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OWNER_MARKER, authority.as_ref()], program_id)
+}
+
+pub fn create_owner_address_with_bump(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[OWNER_MARKER, authority.as_ref(), &[bump]], program_id)
+}
+
+pub fn find_access_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ACCESS_MARKER, authority.as_ref()], program_id)
+}
+
+pub fn create_access_address_with_bump(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[ACCESS_MARKER, authority.as_ref(), &[bump]], program_id)
+}
+
+pub fn find_dataset_address(program_id: &Pubkey, hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DATASET_MARKER, hash], program_id)
+}
+
+pub fn create_dataset_address_with_bump(
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[DATASET_MARKER, hash, &[bump]], program_id)
+}
+
+pub fn find_collection_address(program_id: &Pubkey, hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COLLECTION_MARKER, hash], program_id)
+}
+
+pub fn create_collection_address_with_bump(
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[COLLECTION_MARKER, hash, &[bump]], program_id)
+}
+
+/// Vault holding the proceeds in `mint` of the dataset at `dataset`, the
+/// token account is its own authority
+pub fn find_vault_address(program_id: &Pubkey, dataset: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_MARKER, dataset.as_ref(), mint.as_ref()], program_id)
+}
+
+pub fn create_vault_address_with_bump(
+    program_id: &Pubkey,
+    dataset: &Pubkey,
+    mint: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[VAULT_MARKER, dataset.as_ref(), mint.as_ref(), &[bump]],
+        program_id,
+    )
+}
+
+/// Mint of the access receipts of the dataset at `dataset`, the mint is its
+/// own mint and freeze authority
+pub fn find_receipt_mint_address(program_id: &Pubkey, dataset: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_MARKER, dataset.as_ref()], program_id)
+}
+
+pub fn create_receipt_mint_address_with_bump(
+    program_id: &Pubkey,
+    dataset: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[RECEIPT_MARKER, dataset.as_ref(), &[bump]], program_id)
+}
+
+/// Leading bytes of `hash` its dataset is listed under in the registry
+pub fn registry_prefix(hash: &[u8; 32]) -> [u8; 2] {
+    let [first, second, ..] = *hash;
+    [first, second]
+}
+
+/// Registry page `page` of datasets whose hash starts with `prefix`. The
+/// first page is derived from the prefix alone, overflow pages chained
+/// after it add their number
+pub fn find_registry_address(program_id: &Pubkey, prefix: &[u8; 2], page: u8) -> (Pubkey, u8) {
+    if page == 0 {
+        Pubkey::find_program_address(&[REGISTRY_MARKER, prefix], program_id)
+    } else {
+        Pubkey::find_program_address(&[REGISTRY_MARKER, prefix, &[page]], program_id)
+    }
+}
+
+/// Dataset address derived from the bare hash, as used before
+/// `DATASET_MARKER` was introduced
+pub fn find_legacy_dataset_address(program_id: &Pubkey, hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[hash], program_id)
+}
+
+pub fn find_associated_access_address(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    dataset: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            ASSOCIATED_ACCESS_MARKER,
+            authority.as_ref(),
+            dataset.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+pub fn create_associated_access_address_with_bump(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    dataset: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            ASSOCIATED_ACCESS_MARKER,
+            authority.as_ref(),
+            dataset.as_ref(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
+
+/// Associated access address derived from the bare authority and dataset
+/// keys, as used before `ASSOCIATED_ACCESS_MARKER` was introduced
+pub fn find_legacy_associated_access_address(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    dataset: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[authority.as_ref(), dataset.as_ref()], program_id)
+}
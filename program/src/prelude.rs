@@ -0,0 +1,8 @@
+//! The types clients build instructions and read accounts with, so a
+//! client crate can take them from one place
+
+pub use crate::{
+    error::DataNexusError,
+    instruction::{AccountType, DataNexusInstruction, FieldMask, Params, ParamsBuilder},
+    state::{PriceTier, Tag},
+};
@@ -1,17 +1,48 @@
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
-    system_instruction::create_account,
-    sysvar::rent::Rent,
+    system_instruction::{allocate, assign, create_account, transfer},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
-use spl_token::state::Account;
+use spl_token_2022::{
+    extension::{ExtensionType, StateWithExtensions},
+    state::{Account, Mint, Multisig},
+};
+
+use crate::{
+    accounts::{
+        split_signers, AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitDatasetAccounts, InitIndexAccounts,
+        InitReceiptMintAccounts, InitVaultAccounts, MemberAccounts, MigrateAccountAccounts,
+        OwnershipTransferAccounts, PurchaseAccessAccounts, PurchaseCollectionAccounts,
+        ReceiptAccounts, RecipientAccounts, RegistryAccounts, RejectShareAccounts,
+        RenewAccessAccounts, ResizeIndexAccounts, RevokeAccessAccounts,
+        SetAccessPreferencesAccounts, SetDataParamsAccounts, ShareAccessAccounts,
+        ShareAccessMultiAccounts, ShareFeeAccounts, WithdrawProceedsAccounts,
+    },
+    error::DataNexusError,
+    events::{AccessRevokedEvent, DataNexusEvent, ParamsUpdatedEvent, PurchaseEvent, ShareEvent},
+    instruction::{AccountType, DataNexusInstruction, FieldMask, Params},
+    log::log_instruction,
+    pda::{
+        find_access_address, find_associated_access_address, find_collection_address,
+        find_dataset_address, find_owner_address, find_receipt_mint_address, find_registry_address,
+        find_vault_address, registry_prefix, ACCESS_MARKER, ASSOCIATED_ACCESS_MARKER,
+        COLLECTION_MARKER, DATASET_MARKER, OWNER_MARKER, RECEIPT_MARKER, REGISTRY_MARKER,
+        VAULT_MARKER,
+    },
+    state::*,
+};
 
-use crate::{error::DataNexusError, instruction::DataNexusInstruction, state::*};
+use std::convert::TryFrom;
 
 pub struct Processor {}
 
@@ -22,27 +53,107 @@ impl Processor {
         data: &[u8],
     ) -> ProgramResult {
         let instruction = DataNexusInstruction::unpack(data)?;
+        if cfg!(feature = "verbose-logs") {
+            log_instruction(&instruction);
+        }
 
         match instruction {
-            DataNexusInstruction::InitIndexAccount(account_type) => {
-                Self::process_init_index_account(program_id, accounts, account_type)
-            }
-            DataNexusInstruction::InitDataAccount { hash } => {
-                Self::process_init_data_account(program_id, accounts, hash)
-            }
+            DataNexusInstruction::InitAccount(account_type) => match account_type {
+                AccountType::DatasetIndex | AccountType::AccessIndex => {
+                    Self::process_init_index_account(program_id, accounts, account_type)
+                }
+                AccountType::Dataset(hash) => {
+                    Self::process_init_dataset_account(program_id, accounts, hash)
+                }
+                AccountType::Access(_) => {
+                    msg!("Access accounts are created by InitAssociatedAccess");
+                    Err(DataNexusError::InvalidInstruction.into())
+                }
+            },
             DataNexusInstruction::SetDataParams { hash, params } => {
                 Self::process_set_params(program_id, accounts, hash, params)
             }
             DataNexusInstruction::PurchaseAccess { hash, amount } => {
-                Self::process_purchase_access(program_id, accounts, hash, amount)
+                Self::process_purchase_access(program_id, accounts, hash, amount, None)
+            }
+            DataNexusInstruction::ShareAccess { hash, expires_in } => {
+                Self::process_share_access(program_id, accounts, hash, expires_in)
+            }
+            DataNexusInstruction::CloseDataset { hash, force } => {
+                Self::process_close_dataset(program_id, accounts, hash, force)
+            }
+            DataNexusInstruction::RenewAccess { hash, amount } => {
+                Self::process_renew_access(program_id, accounts, hash, amount)
+            }
+            DataNexusInstruction::ShareAccessMulti { hash } => {
+                Self::process_share_access_multi(program_id, accounts, hash)
+            }
+            DataNexusInstruction::InitAssociatedAccess { hash } => {
+                Self::process_init_associated_access(program_id, accounts, hash)
+            }
+            DataNexusInstruction::ResizeIndex { additional_slots } => {
+                Self::process_resize_index(program_id, accounts, additional_slots)
+            }
+            DataNexusInstruction::CreateCollection { hash, value } => {
+                Self::process_create_collection(program_id, accounts, hash, value)
+            }
+            DataNexusInstruction::AddToCollection {
+                collection_hash,
+                dataset_hash,
+            } => {
+                Self::process_add_to_collection(program_id, accounts, collection_hash, dataset_hash)
+            }
+            DataNexusInstruction::ClaimCollectionAccess { hash } => {
+                Self::process_claim_collection_access(program_id, accounts, hash)
+            }
+            DataNexusInstruction::PurchaseSeats {
+                hash,
+                seats,
+                amount,
+            } => Self::process_purchase_access(program_id, accounts, hash, amount, Some(seats)),
+            DataNexusInstruction::InitVault { hash } => {
+                Self::process_init_vault(program_id, accounts, hash)
             }
-            DataNexusInstruction::ShareAccess { hash } => {
-                Self::process_share_access(program_id, accounts, hash)
+            DataNexusInstruction::WithdrawProceeds { hash, amount } => {
+                Self::process_withdraw_proceeds(program_id, accounts, hash, amount)
+            }
+            DataNexusInstruction::InitReceiptMint { hash } => {
+                Self::process_init_receipt_mint(program_id, accounts, hash)
+            }
+            DataNexusInstruction::RevokeAccess { hash } => {
+                Self::process_revoke_access(program_id, accounts, hash)
+            }
+            DataNexusInstruction::MigrateAccount => {
+                Self::process_migrate_account(program_id, accounts)
+            }
+            DataNexusInstruction::ProposeOwnershipTransfer { hash, new_owner } => {
+                Self::process_propose_ownership_transfer(program_id, accounts, hash, new_owner)
+            }
+            DataNexusInstruction::AcceptOwnership { hash } => {
+                Self::process_accept_ownership(program_id, accounts, hash)
+            }
+            DataNexusInstruction::CancelOwnershipTransfer { hash } => {
+                Self::process_cancel_ownership_transfer(program_id, accounts, hash)
+            }
+            DataNexusInstruction::CrankPrune { hash, holder } => {
+                Self::process_crank_prune(program_id, accounts, hash, holder)
+            }
+            DataNexusInstruction::FundBounty { hash, amount } => {
+                Self::process_fund_bounty(program_id, accounts, hash, amount)
+            }
+            DataNexusInstruction::CompactIndex { shrink, headroom } => {
+                Self::process_compact_index(program_id, accounts, shrink, headroom)
+            }
+            DataNexusInstruction::SetAccessPreferences {
+                require_share_approval,
+            } => Self::process_set_access_preferences(program_id, accounts, require_share_approval),
+            DataNexusInstruction::AcceptShare { hash, from } => {
+                Self::process_accept_share(program_id, accounts, hash, from)
+            }
+            DataNexusInstruction::RejectShare { hash, from } => {
+                Self::process_reject_share(program_id, accounts, hash, from)
             }
-            _ => return Err(DataNexusError::InvalidInstruction.into()),
         }
-
-        Ok(())
     }
 
     fn process_init_index_account(
@@ -50,51 +161,52 @@ impl Processor {
         accounts: &[AccountInfo],
         account_type: AccountType,
     ) -> ProgramResult {
-        let accounts_iter = &mut accounts.iter();
-        let payer = next_account_info(accounts_iter)?;
-        let index_account = next_account_info(accounts_iter)?;
-        let system_program = next_account_info(accounts_iter)?;
-        let rent = Rent::get();
+        InitIndexAccounts::ACCOUNT_COUNT.check("InitAccount", accounts)?;
+        let InitIndexAccounts {
+            payer,
+            authority,
+            index_account,
+            system_program,
+        } = InitIndexAccounts::from_account_infos(accounts)?;
+        let rent = Rent::get()?;
 
-        let state_size = if account_type == AccountType::Owner {
-            AccountIndex::LEN
+        let (marker, (index_address, bump)) = if account_type == AccountType::DatasetIndex {
+            (OWNER_MARKER, find_owner_address(&program_id, authority.key))
         } else {
-            AccessState::LEN
+            (
+                ACCESS_MARKER,
+                find_access_address(&program_id, authority.key),
+            )
         };
+        if index_address != *index_account.key {
+            check_failed!(
+                index_account.key,
+                "Index account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
 
-        let create_account_ix = create_account(
-            payer.key,
-            index_account.key,
-            rent.mininum_balance(state_size),
-            state_size,
-            program_id,
-        );
-
-        invoke(
-            &create_account_ix,
-            &[system_program.clone(), payer.clone(), owner_account.clone()],
+        Self::create_pda_account(
+            &program_id,
+            payer,
+            index_account,
+            system_program,
+            &rent,
+            AccountIndex::space(MAX_INDEX_ENTRIES)?,
+            &[marker, authority.key.as_ref()],
+            bump,
         )?;
 
-        let account_data = index_account.data.borrow_mut();
-        let is_initialized = true;
-        let pointer = None;
-        let datasets: [Option<_>; 128] = [None; 128];
+        Self::assert_rent_exempt(&rent, index_account)?;
 
-        if account_type == AccountType::Owner {
-            AccountIndex {
-                is_initialized,
-                pointer,
-                datasets,
-            }
-            .pack_into_slice(account_data);
-        } else {
-            AccessState {
-                is_initialized,
-                pointer,
-                datasets,
-            }
-            .pack_into_slice(account_data);
+        // Owner and access indexes share a layout, only their seeds differ
+        AccountIndex {
+            is_initialized: true,
+            pointer: None,
+            require_share_approval: false,
+            datasets: vec![None; MAX_INDEX_ENTRIES],
         }
+        .pack_into_slice(&mut index_account.data.borrow_mut());
 
         Ok(())
     }
@@ -104,192 +216,4075 @@ impl Processor {
         accounts: &[AccountInfo],
         hash: [u8; 32],
     ) -> ProgramResult {
-        let accounts_iter = &mut accounts.iter();
+        InitDatasetAccounts::ACCOUNT_COUNT.check("InitAccount", accounts)?;
+        let InitDatasetAccounts {
+            authority,
+            owner_index: owner_account,
+            dataset_account,
+            system_program,
+            registry,
+        } = InitDatasetAccounts::from_account_infos(accounts)?;
 
-        let authority = next_account_info(accounts_iter)?;
-        let owner_account = next_account_info(accounts_iter)?;
-        let dataset_account = next_account_info(accounts_iter)?;
-        let system_program = next_account_info(accounts_iter)?;
-        let rent = Rent::get();
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
 
-        let create_account_ix = create_account(
+        Self::assert_index(
+            &program_id,
+            owner_account,
             authority.key,
-            dataset_account.key,
-            rent.minimum_balance(AccountState::LEN),
-            AccountState::LEN,
-            program_id,
-        );
+            AccountType::DatasetIndex,
+        )?;
 
-        invoke(
-            &create_account,
-            &[authority.clone(), dataset_account.clone()],
+        let rent = Rent::get()?;
+
+        let (dataset_seeds, bump) =
+            Self::dataset_signer_seeds(&program_id, &hash, dataset_account.key)?;
+
+        Self::create_pda_account(
+            &program_id,
+            authority,
+            dataset_account,
+            system_program,
+            &rent,
+            DatasetState::space(),
+            &dataset_seeds,
+            bump,
         )?;
 
-        let owner_account_data = owner_account.data.borrow_mut();
-        let unpacked_owner_account_data = AccountIndex::unpack_from_slice(owner_account_data)?;
+        Self::assert_rent_exempt(&rent, dataset_account)?;
+        Self::assert_rent_exempt(&rent, owner_account)?;
 
-        unpacked_owner_account_data
-            .datasets
-            .push(dataset_account.key);
+        AccountIndex::append_raw(
+            &mut owner_account.data.borrow_mut(),
+            dataset_account.key,
+            &hash,
+        )?;
 
-        unpacked_owner_account_data.pack_into_slice(owner_account_data);
+        if let Some(registry) = registry {
+            Self::list_in_registry(
+                &program_id,
+                authority,
+                system_program,
+                registry,
+                &rent,
+                RegistryEntry {
+                    hash,
+                    dataset: *dataset_account.key,
+                },
+            )?;
+        }
 
-        let dataset_account_data = dataset_account.data.borrow_mut();
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
         let is_initialized = true;
-        let owner = authority.key;
+        let flag = AccountFlag::Dataset;
+        let owner = *authority.key;
         let key = None;
         let value = None;
         let share_limit = None;
+        let purchase_count = 0;
+        let referral_bps = 0;
+        let access_duration = None;
+        let uri = [0u8; URI_LEN];
+        let size_bytes = 0;
+        let content_type = [0u8; CONTENT_TYPE_LEN];
+        let total_revenue = 0;
+        let share_count = 0;
+        let ref_data = None;
+        let resharable = false;
+        let max_share_depth = None;
+        let tiers = vec![];
+        let claimable = vec![];
+        let holder_count = 0;
+        let max_holders = None;
+        let pending_owner = None;
+        let prices = vec![];
+        let sale_start = None;
+        let sale_end = None;
+        let prune_bounty = 0;
+        let bounty_pool = 0;
+        let share_fee = None;
+        let tags = vec![];
+        let derived_discount_bps = 0;
+        let receipt_count = 0;
 
-        AccountState {
+        DatasetState {
             is_initialized,
+            flag,
             owner,
             hash,
             key,
             value,
             share_limit,
+            purchase_count,
+            referral_bps,
+            access_duration,
+            uri,
+            size_bytes,
+            content_type,
+            total_revenue,
+            share_count,
+            ref_data,
+            resharable,
+            max_share_depth,
+            tiers,
+            claimable,
+            holder_count,
+            max_holders,
+            pending_owner,
+            prices,
+            sale_start,
+            sale_end,
+            prune_bounty,
+            bounty_pool,
+            share_fee,
+            tags,
+            derived_discount_bps,
+            receipt_count,
         }
-        .pack_into_slice(dataset_account_data);
+        .pack_into_slice(&mut dataset_account_data[..]);
 
         Ok(())
     }
 
-    fn process_set_params(
-        program_id: Pubkey,
-        accounts: &[AccountInfo],
-        hash: [u8; 32],
-        params: Params,
+    /// Lists `entry` on the registry page passed, creating the first page of
+    /// its prefix or growing the page by one entry at the authority's
+    /// expense. A full page takes no more entries, the overflow page passed
+    /// with it is created and chained after it instead
+    fn list_in_registry<'a>(
+        program_id: &Pubkey,
+        authority: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        registry: RegistryAccounts<&AccountInfo<'a>>,
+        rent: &Rent,
+        entry: RegistryEntry,
     ) -> ProgramResult {
-        let accounts_iter = &mut accounts.iter();
+        let RegistryAccounts {
+            registry_account,
+            overflow_account,
+        } = registry;
+        let prefix = registry_prefix(&entry.hash);
 
-        let authority = next_account_info(accounts_iter)?;
-        let dataset_account = next_account_info(accounts_iter)?;
+        if registry_account.data_len() == 0 {
+            if overflow_account.is_some() {
+                msg!("Only a full registry page is chained to an overflow page");
+                return Err(ProgramError::InvalidArgument);
+            }
+            return Self::create_registry_page(
+                program_id,
+                authority,
+                registry_account,
+                system_program,
+                rent,
+                prefix,
+                0,
+                entry,
+            );
+        }
 
-        let dataset_account_data = dataset_account.data.borrow_mut();
-        let unpacked_dataset_data = AccountState::unpack_from_slice(dataset_account_data)?;
+        if registry_account.owner != program_id {
+            check_failed!(
+                registry_account.key,
+                "Registry account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
 
-        if authority.key != unpacked_dataset_data.owner {
-            msg!("Incorrect Dataset Owner");
-            return Err(ProgramError::InvalidArgument);
+        let mut page = RegistryState::unpack_from_slice(&registry_account.data.borrow())?;
+        if page.prefix != prefix
+            || find_registry_address(program_id, &page.prefix, page.page).0 != *registry_account.key
+        {
+            check_failed!(
+                registry_account.key,
+                "Registry account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
         }
 
-        match params {
-            Params::Init => {
-                let AccountState {
-                    mut key,
-                    mut value,
-                    mut share_limit,
-                    mut ref_data,
-                    ..
-                } = unpacked_dataset_data;
-
-                key = Some(params.0);
-                value = Some(params.1);
-                share_limit = Some(params.2);
-                ref_data = match params.3 {
-                    Some(n) => Some(n),
-                    None => None,
-                };
+        match overflow_account {
+            None => {
+                if page.is_full() {
+                    msg!("Registry page is full, its prefix continues on an overflow page");
+                    return Err(DataNexusError::RegistryFull.into());
+                }
+
+                let new_len = RegistryState::space(page.entries.len().saturating_add(1))?;
+                let shortfall = rent
+                    .minimum_balance(new_len)
+                    .saturating_sub(registry_account.lamports());
+                if shortfall > 0 {
+                    invoke(
+                        &transfer(authority.key, registry_account.key, shortfall),
+                        &[
+                            authority.clone(),
+                            registry_account.clone(),
+                            system_program.clone(),
+                        ],
+                    )?;
+                }
+                registry_account.realloc(new_len, false)?;
+                page.entries.push(entry);
+            }
+            Some(overflow_account) => {
+                if !page.is_full() || page.next.is_some() {
+                    msg!("Only the last registry page, once full, is chained to an overflow page");
+                    return Err(ProgramError::InvalidArgument);
+                }
+
+                let next_page = page
+                    .page
+                    .checked_add(1)
+                    .ok_or(DataNexusError::NumericalOverflow)?;
+                Self::create_registry_page(
+                    program_id,
+                    authority,
+                    overflow_account,
+                    system_program,
+                    rent,
+                    prefix,
+                    next_page,
+                    entry,
+                )?;
+                page.next = Some(*overflow_account.key);
             }
-            Params::Key => unpacked_dataset_data.key = Some(params.0),
-            Params::Value => unpacked_dataset_data.value = Some(params.0),
-            Params::ShareLimit => unpacked_dataset_data.share_limit = Some(params.0),
-            Params::ReferenceData => unpacked_dataset_data.ref_data = Some(params.0),
-            _ => return Err(ProgramError::InvalidArgument),
         }
 
-        unpacked_dataset_data.pack_into_slice(dataset_account_data);
+        page.pack_into_slice(&mut registry_account.data.borrow_mut());
 
         Ok(())
     }
 
-    fn process_purchase_access(
+    /// Creates registry page `page` of `prefix` at `account` holding `entry`
+    #[allow(clippy::too_many_arguments)]
+    fn create_registry_page<'a>(
+        program_id: &Pubkey,
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        rent: &Rent,
+        prefix: [u8; 2],
+        page: u8,
+        entry: RegistryEntry,
+    ) -> ProgramResult {
+        let (address, bump) = find_registry_address(program_id, &prefix, page);
+        if address != *account.key {
+            check_failed!(
+                account.key,
+                "Registry account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        let page_seed = [page];
+        let mut seeds: Vec<&[u8]> = vec![REGISTRY_MARKER, &prefix];
+        if page > 0 {
+            seeds.push(&page_seed);
+        }
+        Self::create_pda_account(
+            program_id,
+            payer,
+            account,
+            system_program,
+            rent,
+            RegistryState::space(1)?,
+            &seeds,
+            bump,
+        )?;
+
+        RegistryState {
+            is_initialized: true,
+            prefix,
+            page,
+            next: None,
+            entries: vec![entry],
+        }
+        .pack_into_slice(&mut account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_init_associated_access(
         program_id: Pubkey,
         accounts: &[AccountInfo],
         hash: [u8; 32],
-        amount: u64,
     ) -> ProgramResult {
-        let accounts_iter = &mut accounts.iter();
+        InitAssociatedAccessAccounts::ACCOUNT_COUNT.check("InitAssociatedAccess", accounts)?;
+        let InitAssociatedAccessAccounts {
+            payer,
+            authority,
+            access_index,
+            associated_access_account,
+            dataset_account,
+            system_program,
+        } = InitAssociatedAccessAccounts::from_account_infos(accounts)?;
 
-        let user_authority = next_account_info(accounts_iter)?;
-        let user_access_account = next_account_info(accounts_iter)?;
-        let user_token_account = next_account_info(accounts_iter)?;
-        let owner_authority = next_account_info(accounts_iter)?;
-        let owner_token_account = next_account_info(accounts_iter)?;
-        let dataset_account = next_account_info(accounts_iter)?;
-        let token_program = next_account_info(accounts_iter)?;
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
 
-        if token_program.key != spl_token::ID {
-            msg!("Incorrect token program ID");
-            return Err(ProgramError::IncorrectProgramId);
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
         }
 
-        let transfer_ix = spl_token::instruction::transfer(
-            token_program.key,
-            user_token_account.key,
-            owner_token_account.key,
-            user_authority.key,
-            &[user_authority.key],
-            amount,
+        if CollectionState::is_collection(&dataset_account.data.borrow()) {
+            return Self::process_init_collection_access(
+                program_id,
+                payer,
+                authority,
+                associated_access_account,
+                dataset_account,
+                system_program,
+                hash,
+            );
+        }
+
+        let unpacked_dataset_data =
+            DatasetState::unpack_from_slice(&dataset_account.data.borrow())?;
+        if !unpacked_dataset_data.is_initialized() {
+            check_failed!(dataset_account.key, "Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+
+        let bump = Self::assert_new_associated_access_pda(
+            &program_id,
+            authority.key,
+            dataset_account.key,
+            associated_access_account,
         )?;
 
-        invoke(
-            &transfer_ix,
+        Self::assert_index(
+            &program_id,
+            access_index,
+            authority.key,
+            AccountType::AccessIndex,
+        )?;
+
+        let rent = Rent::get()?;
+        Self::create_pda_account(
+            &program_id,
+            payer,
+            associated_access_account,
+            system_program,
+            &rent,
+            AccessState::space(MAX_ACCESS_ENTRIES)?,
             &[
-                user_token_account.clone(),
-                owner_token_account.clone(),
-                user_authority.clone(),
-                &[user_authority.clone()],
+                ASSOCIATED_ACCESS_MARKER,
+                authority.key.as_ref(),
+                dataset_account.key.as_ref(),
             ],
+            bump,
         )?;
 
-        let user_access_account_data = user_access_account.data.borrow_mut();
-        let unpacked_user_access_data = AccessState::unpack_from_slice(user_access_account_data)?;
-        let dataset_account_data = dataset_account.data.borrow();
-        let unpacked_dataset_data = AccountState::unpack_from_slice(dataset_account_data)?;
-        let new_access = AccessInfo {
-            hash,
-            key: unpacked_dataset_data.key,
-            shared_from: None,
-            share_limit: unpacked_dataset_data.share_limit,
+        Self::assert_rent_exempt(&rent, associated_access_account)?;
+        Self::assert_rent_exempt(&rent, access_index)?;
+
+        AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: Vec::new(),
+            pending: Vec::new(),
         }
-        .pack();
+        .pack_into_slice(&mut associated_access_account.data.borrow_mut());
+
+        AccountIndex::append_raw(
+            &mut access_index.data.borrow_mut(),
+            dataset_account.key,
+            &hash,
+        )?;
 
         Ok(())
     }
 
-    fn process_share_access(
+    /// `InitAssociatedAccess` given a collection, creates the account
+    /// tracking the authority's purchase of it
+    fn process_init_collection_access<'a>(
+        program_id: Pubkey,
+        payer: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        collection_access_account: &AccountInfo<'a>,
+        collection_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        let collection = CollectionState::unpack_from_slice(&collection_account.data.borrow())?;
+        if !collection.is_initialized() {
+            check_failed!(collection_account.key, "Collection account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&collection.hash, &hash)?;
+
+        let bump = Self::assert_new_associated_access_pda(
+            &program_id,
+            authority.key,
+            collection_account.key,
+            collection_access_account,
+        )?;
+
+        let rent = Rent::get()?;
+        Self::create_pda_account(
+            &program_id,
+            payer,
+            collection_access_account,
+            system_program,
+            &rent,
+            CollectionAccess::space(),
+            &[
+                ASSOCIATED_ACCESS_MARKER,
+                authority.key.as_ref(),
+                collection_account.key.as_ref(),
+            ],
+            bump,
+        )?;
+
+        CollectionAccess {
+            is_initialized: true,
+            collection: *collection_account.key,
+            purchased: false,
+            cursor: 0,
+        }
+        .pack_into_slice(&mut collection_access_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_resize_index(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        additional_slots: u16,
+    ) -> ProgramResult {
+        ResizeIndexAccounts::ACCOUNT_COUNT.check("ResizeIndex", accounts)?;
+        let ResizeIndexAccounts {
+            payer,
+            authority,
+            index_account,
+            system_program,
+        } = ResizeIndexAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_index(&program_id, authority, index_account)?;
+
+        if index_account.data_len() == AccountIndex::LEGACY_LEN {
+            msg!("Legacy indexes cannot be resized");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if additional_slots == 0 {
+            msg!("Index must grow by at least one slot");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // The runtime caps how far one instruction may grow an account
+        let additional_len = usize::from(additional_slots)
+            .checked_mul(AccountIndex::ENTRY_LEN)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        if additional_len > MAX_PERMITTED_DATA_INCREASE {
+            msg!(
+                "Growing the index by {} bytes exceeds the limit of {}",
+                additional_len,
+                MAX_PERMITTED_DATA_INCREASE
+            );
+            return Err(DataNexusError::ResizeTooLarge.into());
+        }
+
+        let capacity = AccountIndex::capacity_raw(&index_account.data.borrow())?;
+        let capacity = u16::try_from(capacity)
+            .ok()
+            .and_then(|capacity| capacity.checked_add(additional_slots))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let new_len = AccountIndex::space_raw(&index_account.data.borrow(), capacity.into())?;
+
+        let rent = Rent::get()?;
+        let shortfall = rent
+            .minimum_balance(new_len)
+            .saturating_sub(index_account.lamports());
+        if shortfall > 0 {
+            invoke(
+                &transfer(payer.key, index_account.key, shortfall),
+                &[payer.clone(), index_account.clone(), system_program.clone()],
+            )?;
+        }
+
+        index_account.realloc(new_len, false)?;
+        AccountIndex::set_capacity_raw(&mut index_account.data.borrow_mut(), capacity)?;
+
+        Ok(())
+    }
+
+    fn process_compact_index(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        shrink: bool,
+        headroom: u16,
+    ) -> ProgramResult {
+        CompactIndexAccounts::ACCOUNT_COUNT.check("CompactIndex", accounts)?;
+        let CompactIndexAccounts {
+            authority,
+            index_account,
+        } = CompactIndexAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_index(&program_id, authority, index_account)?;
+
+        if shrink && index_account.data_len() == AccountIndex::LEGACY_LEN {
+            msg!("Legacy indexes cannot be resized");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let entries = AccountIndex::compact_raw(&mut index_account.data.borrow_mut())?;
+        msg!("Compacted the index to {} entries", entries);
+        if !shrink {
+            return Ok(());
+        }
+
+        // Shrinking stops at the size indexes are created with, and never
+        // grows one that already has less headroom
+        let capacity = entries
+            .checked_add(headroom.into())
+            .ok_or(DataNexusError::NumericalOverflow)?
+            .max(MAX_INDEX_ENTRIES);
+        if capacity >= AccountIndex::capacity_raw(&index_account.data.borrow())? {
+            return Ok(());
+        }
+        let capacity = u16::try_from(capacity).map_err(|_| DataNexusError::NumericalOverflow)?;
+        let new_len = AccountIndex::space_raw(&index_account.data.borrow(), capacity.into())?;
+
+        AccountIndex::set_capacity_raw(&mut index_account.data.borrow_mut(), capacity)?;
+        index_account.realloc(new_len, false)?;
+
+        let refund = index_account
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(new_len));
+        let index_lamports = index_account
+            .lamports()
+            .checked_sub(refund)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let authority_lamports = authority
+            .lamports()
+            .checked_add(refund)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        **index_account.lamports.borrow_mut() = index_lamports;
+        **authority.lamports.borrow_mut() = authority_lamports;
+        msg!(
+            "Shrank the index to {} slots, refunding {} lamports",
+            capacity,
+            refund
+        );
+
+        Ok(())
+    }
+
+    fn process_set_access_preferences(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        require_share_approval: bool,
+    ) -> ProgramResult {
+        SetAccessPreferencesAccounts::ACCOUNT_COUNT.check("SetAccessPreferences", accounts)?;
+        let SetAccessPreferencesAccounts {
+            authority,
+            access_index,
+        } = SetAccessPreferencesAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        Self::assert_index(
+            &program_id,
+            access_index,
+            authority.key,
+            AccountType::AccessIndex,
+        )?;
+
+        AccountIndex::set_require_share_approval_raw(
+            &mut access_index.data.borrow_mut(),
+            require_share_approval,
+        )
+    }
+
+    fn process_migrate_account(program_id: Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        MigrateAccountAccounts::ACCOUNT_COUNT.check("MigrateAccount", accounts)?;
+        let MigrateAccountAccounts {
+            payer,
+            account,
+            system_program,
+        } = MigrateAccountAccounts::from_account_infos(accounts)?;
+
+        if account.owner != &program_id {
+            check_failed!(account.key, "Account not owned by DataNexus");
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        // The old layout is read back after the realloc, so keep a copy
+        let old_data = account.data.borrow().to_vec();
+        let new_len = match migrated_len(&old_data) {
+            Some(new_len) => new_len,
+            None => {
+                msg!("Account is not of an outdated layout");
+                return Err(ProgramError::InvalidAccountData);
+            }
+        };
+
+        let rent = Rent::get()?;
+        let shortfall = rent
+            .minimum_balance(new_len)
+            .saturating_sub(account.lamports());
+        if shortfall > 0 {
+            invoke(
+                &transfer(payer.key, account.key, shortfall),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+
+        account.realloc(new_len, false)?;
+        migrate_layout(&old_data, &mut account.data.borrow_mut())?;
+        msg!(
+            "Migrated account from {} to {} bytes",
+            old_data.len(),
+            new_len
+        );
+
+        Ok(())
+    }
+
+    fn process_set_params(
         program_id: Pubkey,
         accounts: &[AccountInfo],
         hash: [u8; 32],
+        params: Params,
     ) -> ProgramResult {
-        let accounts_iter = &mut accounts.iter();
+        let (accounts, signers) = split_signers(accounts);
+        SetDataParamsAccounts::ACCOUNT_COUNT.check("SetDataParams", accounts)?;
+        let SetDataParamsAccounts {
+            authority,
+            dataset_account,
+            reference_dataset,
+        } = SetDataParamsAccounts::from_account_infos(accounts)?;
 
-        let user_authority = next_account_info(accounts_iter)?;
-        let user_access_account = next_account_info(accounts_iter)?;
-        let recipient_authority = next_account_info(accounts_iter)?;
-        let recipient_access_account = next_account_info(accounts_iter)?;
-        let dataset_account = next_account_info(accounts_iter)?;
+        Self::assert_authority_signed(authority, signers)?;
 
-        let dataset_account_data = dataset_account.data.borrow();
-        let unpacked_dataset_data = AccountState::unpack_from_slice(dataset_account_data)?;
-        let user_access_data = user_access_account.data.borrow_mut();
-        let unpacked_user_access_data = AccessState::unpack_from_slice(user_access_account_data)?;
-        let recipient_access_data = recipient_access_account.data.borrow_mut();
-        let unpacked_recipient_access_data = AccessState::unpack_from_slice(recipient_access_data)?;
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
 
-        let new_access = AccessInfo {
-            hash,
-            key: unpacked_user_access_data.key,
-            shared_from: user_authority.key,
-            share_limit: 0,
+        Self::assert_rent_exempt(&Rent::get()?, dataset_account)?;
+
+        match params {
+            Params::ReferralBps(bps) if bps > MAX_BPS => {
+                msg!("Referral basis points exceed {}", MAX_BPS);
+                return Err(DataNexusError::InvalidBasisPoints.into());
+            }
+            Params::DerivedDiscountBps(bps) if bps > MAX_BPS => {
+                msg!("Derived discount basis points exceed {}", MAX_BPS);
+                return Err(DataNexusError::InvalidBasisPoints.into());
+            }
+            Params::AccessDuration(duration) if duration < 0 => {
+                msg!("Access duration cannot be negative");
+                return Err(ProgramError::InvalidArgument);
+            }
+            Params::Clear(mask) if !mask.is_valid() => {
+                msg!("Invalid field mask {:#b}", mask.0);
+                return Err(ProgramError::InvalidArgument);
+            }
+            Params::SetPrice(_, 0) => {
+                msg!("Prices cannot be zero, remove the price instead");
+                return Err(ProgramError::InvalidArgument);
+            }
+            Params::SaleWindow { start, end }
+                if start < 0 || end.is_some_and(|end| end <= start) =>
+            {
+                msg!("Sales must start at a non-negative time before they end");
+                return Err(ProgramError::InvalidArgument);
+            }
+            _ => {}
+        }
+
+        if let Params::Tiers(tiers) = &params {
+            Self::assert_price_tiers(tiers)?;
+        }
+
+        if let Some(ref_data) = params.reference_data() {
+            Self::assert_reference_dataset(
+                &program_id,
+                dataset_account,
+                reference_dataset,
+                &ref_data,
+            )?;
+        }
+
+        Self::write_params(
+            &mut dataset_account.data.borrow_mut(),
+            authority.key,
+            &hash,
+            params,
+        )?;
+
+        DataNexusEvent::ParamsUpdated(ParamsUpdatedEvent {
+            dataset: *dataset_account.key,
+            authority: *authority.key,
+            slot: Clock::get()?.slot,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    /// Fails unless `reference_dataset` is the initialized dataset at
+    /// `ref_data`, and not `dataset_account` itself
+    fn assert_reference_dataset(
+        program_id: &Pubkey,
+        dataset_account: &AccountInfo,
+        reference_dataset: Option<&AccountInfo>,
+        ref_data: &Pubkey,
+    ) -> ProgramResult {
+        if ref_data == dataset_account.key {
+            msg!("Dataset cannot reference itself");
+            return Err(DataNexusError::InvalidReferenceDataset.into());
+        }
+
+        let reference_dataset = match reference_dataset {
+            Some(account) if account.key == ref_data => account,
+            Some(account) => {
+                check_failed!(
+                    account.key,
+                    "Reference dataset account does not match reference data"
+                );
+                return Err(DataNexusError::InvalidReferenceDataset.into());
+            }
+            None => {
+                msg!("Missing reference dataset account");
+                return Err(DataNexusError::InvalidReferenceDataset.into());
+            }
+        };
+
+        if reference_dataset.owner != program_id {
+            check_failed!(
+                reference_dataset.key,
+                "Reference dataset not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidReferenceDataset.into());
+        }
+
+        let data = reference_dataset.data.borrow();
+        let is_dataset = match DatasetState::unpack_from_slice(&data) {
+            Ok(dataset) => dataset.is_initialized && matches!(dataset.flag, AccountFlag::Dataset),
+            Err(_) => false,
+        };
+        if !is_dataset {
+            msg!("Reference account is not an initialized dataset");
+            return Err(DataNexusError::InvalidReferenceDataset.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `tiers` fit the dataset and strictly increase in
+    /// `min_seats`, each tier selling at least one seat at a nonzero price
+    fn assert_price_tiers(tiers: &[PriceTier]) -> ProgramResult {
+        if tiers.len() > MAX_PRICE_TIERS {
+            msg!("At most {} price tiers can be set", MAX_PRICE_TIERS);
+            return Err(DataNexusError::InvalidPriceTiers.into());
+        }
+
+        let mut min_seats = 0;
+        for tier in tiers.iter() {
+            if tier.min_seats <= min_seats || tier.unit_price == 0 {
+                msg!("Price tiers must increase in seats and have a price");
+                return Err(DataNexusError::InvalidPriceTiers.into());
+            }
+            min_seats = tier.min_seats;
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless the dataset account passed is the one registered under
+    /// the instruction's `hash`
+    fn assert_dataset_hash(dataset_hash: &[u8; 32], hash: &[u8; 32]) -> ProgramResult {
+        if dataset_hash != hash {
+            msg!("Dataset Hash Mismatch");
+            return Err(DataNexusError::HashMismatch.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `index` is the owner or access index of `authority`, as
+    /// `account_type` selects, and is owned by the program
+    fn assert_index(
+        program_id: &Pubkey,
+        index: &AccountInfo,
+        authority: &Pubkey,
+        account_type: AccountType,
+    ) -> ProgramResult {
+        let (index_address, _) = if account_type == AccountType::DatasetIndex {
+            find_owner_address(program_id, authority)
+        } else {
+            find_access_address(program_id, authority)
         };
+        if index_address != *index.key {
+            check_failed!(index.key, "Index account does not match derived address");
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        if index.owner != program_id {
+            check_failed!(index.key, "Index account not owned by DataNexus");
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `authority` signed and `index_account` is its owner or
+    /// access index, owned by the program
+    fn assert_authority_index(
+        program_id: &Pubkey,
+        authority: &AccountInfo,
+        index_account: &AccountInfo,
+    ) -> ProgramResult {
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if index_account.owner != program_id {
+            check_failed!(index_account.key, "Index account not owned by DataNexus");
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        if *index_account.key != find_owner_address(program_id, authority.key).0
+            && *index_account.key != find_access_address(program_id, authority.key).0
+        {
+            check_failed!(
+                index_account.key,
+                "Index account does not belong to the authority"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        Ok(())
+    }
 
-        unpacked_recipient_access_data.datasets.push(new_access);
-        unpacked_recipient_access_data.pack_into_slice(recipient_access_data);
+    /// Fails with `InvalidSeeds` unless `account` is the associated access
+    /// account of `authority` for the dataset or collection at `dataset`,
+    /// returning its bump
+    ///
+    /// With `legacy-derivation` the bare derivation used before
+    /// `ASSOCIATED_ACCESS_MARKER` is accepted as well
+    fn assert_associated_access_pda(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        dataset: &Pubkey,
+        account: &AccountInfo,
+    ) -> Result<u8, ProgramError> {
+        #[cfg(feature = "legacy-derivation")]
+        {
+            let (legacy_address, bump) =
+                crate::pda::find_legacy_associated_access_address(program_id, authority, dataset);
+            if legacy_address == *account.key {
+                return Ok(bump);
+            }
+        }
+
+        Self::assert_new_associated_access_pda(program_id, authority, dataset, account)
+    }
 
-        unpacked_user_access_data.share_limit -= 1;
-        unpacked_user_access_data.pack_into_slice(user_access_data);
+    /// `assert_associated_access_pda` for accounts about to be created,
+    /// which only ever live at the marked address
+    fn assert_new_associated_access_pda(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        dataset: &Pubkey,
+        account: &AccountInfo,
+    ) -> Result<u8, ProgramError> {
+        let (address, bump) = find_associated_access_address(program_id, authority, dataset);
+        if address != *account.key {
+            check_failed!(
+                account.key,
+                "Associated access account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        Ok(bump)
+    }
+
+    /// Fails unless `authority` signs or is a multisig with `m` members among `signers`
+    fn assert_authority_signed(authority: &AccountInfo, signers: &[AccountInfo]) -> ProgramResult {
+        let multisig =
+            if *authority.owner == spl_token::ID || *authority.owner == spl_token_2022::ID {
+                Multisig::unpack(&authority.data.borrow()).ok()
+            } else {
+                None
+            };
+
+        match multisig {
+            Some(multisig) => {
+                let members = multisig
+                    .signers
+                    .get(..usize::from(multisig.n))
+                    .unwrap_or_default();
+                // Like spl-token, members count once however often they are
+                // passed and other signers are ignored
+                let signed = members
+                    .iter()
+                    .filter(|member| {
+                        signers
+                            .iter()
+                            .any(|signer| signer.is_signer && signer.key == *member)
+                    })
+                    .count();
+                if signed < usize::from(multisig.m) {
+                    check_failed!(
+                        authority.key,
+                        "Missing Multisig Signatures, {} of {} signed",
+                        signed,
+                        multisig.m
+                    );
+                    return Err(DataNexusError::MissingSigner.into());
+                }
+            }
+            None => {
+                if !authority.is_signer {
+                    check_failed!(authority.key, "Missing Authority Signature");
+                    return Err(DataNexusError::MissingSigner.into());
+                }
+                if !signers.is_empty() {
+                    msg!("Only multisig authorities take signer accounts");
+                    return Err(DataNexusError::InvalidAccountCount.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless the dataset is initialized, holds `hash` and is owned by
+    /// `authority`
+    fn assert_dataset_owner(
+        is_initialized: bool,
+        dataset_hash: &[u8; 32],
+        owner: &Pubkey,
+        hash: &[u8; 32],
+        authority: &Pubkey,
+    ) -> ProgramResult {
+        if !is_initialized {
+            msg!("Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        Self::assert_dataset_hash(dataset_hash, hash)?;
+
+        if authority != owner {
+            check_failed!(authority, "Incorrect Dataset Owner");
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Ok(())
+    }
+
+    /// Writes validated `params` to the dataset in `data`, only the fields
+    /// they set are touched
+    #[cfg(feature = "zero-copy")]
+    fn write_params(
+        data: &mut [u8],
+        authority: &Pubkey,
+        hash: &[u8; 32],
+        params: Params,
+    ) -> ProgramResult {
+        let dataset = DatasetState::load_mut(data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized(),
+            &dataset.hash,
+            &dataset.owner(),
+            hash,
+            authority,
+        )?;
+
+        match params {
+            Params::Init(key, value, share_limit, ref_data) => {
+                dataset.set_key(Some(key));
+                dataset.set_value(Some(value));
+                dataset.set_share_limit(Some(share_limit));
+                dataset.set_ref_data(ref_data);
+            }
+            Params::Key(key) => dataset.set_key(Some(key)),
+            Params::Value(value) => dataset.set_value(Some(value)),
+            Params::ShareLimit(share_limit) => dataset.set_share_limit(Some(share_limit)),
+            Params::ReferralBps(bps) => dataset.referral_bps.set(bps),
+            Params::DerivedDiscountBps(bps) => dataset.derived_discount_bps.set(bps),
+            Params::AccessDuration(duration) => dataset.access_duration.set(duration),
+            Params::Uri(uri) => dataset.uri = Self::null_padded(&uri)?,
+            Params::Size(size) => dataset.size_bytes.set(size),
+            Params::ContentType(content_type) => {
+                dataset.content_type = Self::null_padded(&content_type)?;
+            }
+            Params::ReferenceData(ref_data) => dataset.set_ref_data(Some(ref_data)),
+            Params::Clear(mask) => {
+                if mask.contains(FieldMask::KEY) {
+                    dataset.set_key(None);
+                }
+                if mask.contains(FieldMask::VALUE) {
+                    dataset.set_value(None);
+                }
+                if mask.contains(FieldMask::SHARE_LIMIT) {
+                    dataset.set_share_limit(None);
+                }
+                if mask.contains(FieldMask::REF_DATA) {
+                    dataset.set_ref_data(None);
+                }
+                if mask.contains(FieldMask::URI) {
+                    dataset.uri = [0u8; URI_LEN];
+                }
+            }
+            Params::Resharable(resharable) => dataset.set_resharable(resharable),
+            Params::MaxShareDepth(max_share_depth) => {
+                dataset.max_share_depth = max_share_depth;
+            }
+            Params::Tiers(tiers) => dataset.set_tiers(&tiers)?,
+            Params::MaxHolders(max_holders) => dataset.max_holders.set(max_holders),
+            Params::SetPrice(mint, amount) => dataset.set_price(mint, amount)?,
+            Params::RemovePrice(mint) => dataset.remove_price(&mint)?,
+            Params::SaleWindow { start, end } => {
+                dataset.sale_start.set(start);
+                dataset.set_sale_end(end);
+            }
+            Params::PruneBounty(bounty) => dataset.prune_bounty.set(bounty),
+            Params::ShareFee { mint, amount } => {
+                dataset.set_share_fee((amount > 0).then_some(MintPrice { mint, amount }));
+            }
+            Params::AddTag(tag) => dataset.add_tag(tag)?,
+            Params::RemoveTag(tag) => dataset.remove_tag(&tag)?,
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "zero-copy"))]
+    fn write_params(
+        data: &mut [u8],
+        authority: &Pubkey,
+        hash: &[u8; 32],
+        params: Params,
+    ) -> ProgramResult {
+        Self::repack_params(data, authority, hash, params)
+    }
+
+    /// Writes validated `params` to the dataset in `data` by repacking it
+    #[cfg(not(feature = "zero-copy"))]
+    fn repack_params(
+        data: &mut [u8],
+        authority: &Pubkey,
+        hash: &[u8; 32],
+        params: Params,
+    ) -> ProgramResult {
+        let mut dataset = DatasetState::unpack_from_slice(data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized(),
+            &dataset.hash,
+            &dataset.owner,
+            hash,
+            authority,
+        )?;
+
+        match params {
+            Params::Init(key, value, share_limit, ref_data) => {
+                dataset.key = Some(key);
+                dataset.value = Some(value);
+                dataset.share_limit = Some(share_limit);
+                dataset.ref_data = ref_data;
+            }
+            Params::Key(key) => dataset.key = Some(key),
+            Params::Value(value) => dataset.value = Some(value),
+            Params::ShareLimit(share_limit) => dataset.share_limit = Some(share_limit),
+            Params::ReferralBps(bps) => dataset.referral_bps = bps,
+            Params::DerivedDiscountBps(bps) => dataset.derived_discount_bps = bps,
+            Params::AccessDuration(duration) => {
+                dataset.access_duration = match duration {
+                    0 => None,
+                    n => Some(n),
+                };
+            }
+            Params::Uri(uri) => dataset.uri = Self::null_padded(&uri)?,
+            Params::Size(size) => dataset.size_bytes = size,
+            Params::ContentType(content_type) => {
+                dataset.content_type = Self::null_padded(&content_type)?;
+            }
+            Params::ReferenceData(ref_data) => dataset.ref_data = Some(ref_data),
+            Params::Clear(mask) => {
+                if mask.contains(FieldMask::KEY) {
+                    dataset.key = None;
+                }
+                if mask.contains(FieldMask::VALUE) {
+                    dataset.value = None;
+                }
+                if mask.contains(FieldMask::SHARE_LIMIT) {
+                    dataset.share_limit = None;
+                }
+                if mask.contains(FieldMask::REF_DATA) {
+                    dataset.ref_data = None;
+                }
+                if mask.contains(FieldMask::URI) {
+                    dataset.uri = [0u8; URI_LEN];
+                }
+            }
+            Params::Resharable(resharable) => dataset.resharable = resharable,
+            Params::MaxShareDepth(max_share_depth) => {
+                dataset.max_share_depth = match max_share_depth {
+                    0 => None,
+                    n => Some(n),
+                };
+            }
+            Params::Tiers(tiers) => dataset.tiers = tiers,
+            Params::MaxHolders(max_holders) => {
+                dataset.max_holders = match max_holders {
+                    0 => None,
+                    n => Some(n),
+                };
+            }
+            Params::SetPrice(mint, amount) => dataset.set_price(mint, amount)?,
+            Params::RemovePrice(mint) => dataset.remove_price(&mint)?,
+            Params::SaleWindow { start, end } => {
+                dataset.sale_start = match start {
+                    0 => None,
+                    n => Some(n),
+                };
+                dataset.sale_end = end;
+            }
+            Params::PruneBounty(bounty) => dataset.prune_bounty = bounty,
+            Params::ShareFee { mint, amount } => {
+                dataset.share_fee = (amount > 0).then_some(MintPrice { mint, amount });
+            }
+            Params::AddTag(tag) => dataset.add_tag(tag)?,
+            Params::RemoveTag(tag) => dataset.remove_tag(&tag)?,
+        }
+
+        dataset.pack_into_slice(data);
+
+        Ok(())
+    }
+
+    /// Creates the PDA `new_account` of `space` bytes owned by `program_id`,
+    /// signing for it with `seeds` and `bump`, failing with
+    /// `AccountAlreadyInitialized` if the account is already in use
+    ///
+    /// `create_account` fails on addresses that already hold lamports, so
+    /// pre-funded accounts are topped up to the rent-exempt minimum,
+    /// allocated and assigned instead
+    #[allow(clippy::too_many_arguments)]
+    fn create_pda_account<'a>(
+        program_id: &Pubkey,
+        payer: &AccountInfo<'a>,
+        new_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        rent: &Rent,
+        space: usize,
+        seeds: &[&[u8]],
+        bump: u8,
+    ) -> ProgramResult {
+        if new_account.owner != &solana_program::system_program::ID || new_account.data_len() != 0 {
+            msg!("Account {} is already initialized", new_account.key);
+            return Err(DataNexusError::AccountAlreadyInitialized.into());
+        }
+
+        let bump = [bump];
+        let mut signer_seeds = seeds.to_vec();
+        signer_seeds.push(&bump);
+        let signer_seeds: &[&[&[u8]]] = &[&signer_seeds];
+
+        let required_lamports = rent.minimum_balance(space);
+
+        if new_account.lamports() == 0 {
+            return invoke_signed(
+                &create_account(
+                    payer.key,
+                    new_account.key,
+                    required_lamports,
+                    space as u64,
+                    program_id,
+                ),
+                &[payer.clone(), new_account.clone(), system_program.clone()],
+                signer_seeds,
+            );
+        }
+
+        let shortfall = required_lamports.saturating_sub(new_account.lamports());
+        if shortfall > 0 {
+            invoke_signed(
+                &transfer(payer.key, new_account.key, shortfall),
+                &[payer.clone(), new_account.clone(), system_program.clone()],
+                signer_seeds,
+            )?;
+        }
+
+        invoke_signed(
+            &allocate(new_account.key, space as u64),
+            &[new_account.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+
+        invoke_signed(
+            &assign(new_account.key, program_id),
+            &[new_account.clone(), system_program.clone()],
+            signer_seeds,
+        )
+    }
+
+    /// Resolves the signer seeds of the dataset PDA at `address`
+    ///
+    /// With `legacy-dataset-address` the bare hash derivation used before
+    /// `DATASET_MARKER` is accepted as well
+    fn dataset_signer_seeds<'a>(
+        program_id: &Pubkey,
+        hash: &'a [u8; 32],
+        address: &Pubkey,
+    ) -> Result<(Vec<&'a [u8]>, u8), ProgramError> {
+        let (dataset_address, bump) = find_dataset_address(program_id, hash);
+        if dataset_address == *address {
+            return Ok((vec![DATASET_MARKER, hash], bump));
+        }
+
+        #[cfg(feature = "legacy-dataset-address")]
+        {
+            let (legacy_address, bump) = crate::pda::find_legacy_dataset_address(program_id, hash);
+            if legacy_address == *address {
+                return Ok((vec![hash], bump));
+            }
+        }
+
+        check_failed!(address, "Dataset account does not match derived address");
+        Err(DataNexusError::InvalidSeeds.into())
+    }
+
+    /// Fails when `account` holds less than the rent-exempt minimum for its
+    /// current data length
+    fn assert_rent_exempt(rent: &Rent, account: &AccountInfo) -> ProgramResult {
+        if !rent.is_exempt(account.lamports(), account.data_len()) {
+            msg!("Account {} is not rent exempt", account.key);
+            return Err(DataNexusError::NotRentExempt.into());
+        }
+
+        Ok(())
+    }
+
+    /// Validates UTF-8 metadata and null-pads it to a fixed width field
+    fn null_padded<const N: usize>(input: &[u8]) -> Result<[u8; N], ProgramError> {
+        if input.len() > N {
+            msg!("Metadata exceeds {} bytes", N);
+            return Err(DataNexusError::MetadataTooLong.into());
+        }
+
+        if std::str::from_utf8(input).is_err() || input.contains(&0) {
+            msg!("Metadata is not valid UTF-8");
+            return Err(DataNexusError::InvalidUtf8.into());
+        }
+
+        let mut padded = [0u8; N];
+        padded[..input.len()].copy_from_slice(input);
+        Ok(padded)
+    }
+
+    /// `PurchaseAccess`, or `PurchaseSeats` when `seats` is set
+    fn process_purchase_access(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        amount: u64,
+        seats: Option<u16>,
+    ) -> ProgramResult {
+        let is_collection = accounts
+            .get(7)
+            .is_some_and(|account| CollectionState::is_collection(&account.data.borrow()));
+        if is_collection && seats.is_none() {
+            return Self::process_purchase_collection(program_id, accounts, hash, amount);
+        }
+
+        let instruction = if seats.is_some() {
+            "PurchaseSeats"
+        } else {
+            "PurchaseAccess"
+        };
+        PurchaseAccessAccounts::ACCOUNT_COUNT.check(instruction, accounts)?;
+
+        let PurchaseAccessAccounts {
+            user_authority,
+            user_access_index,
+            user_access_account,
+            user_token_account,
+            owner_authority,
+            proceeds_account,
+            token_mint,
+            dataset_account,
+            token_program,
+            referrer_token_account,
+            receipt,
+            reference_access,
+        } = PurchaseAccessAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let rent = Rent::get()?;
+        Self::assert_rent_exempt(&rent, user_access_index)?;
+        Self::assert_rent_exempt(&rent, user_access_account)?;
+        Self::assert_rent_exempt(&rent, dataset_account)?;
+
+        Self::assert_index(
+            &program_id,
+            user_access_index,
+            user_authority.key,
+            AccountType::AccessIndex,
+        )?;
+
+        Self::assert_associated_access_pda(
+            &program_id,
+            user_authority.key,
+            dataset_account.key,
+            user_access_account,
+        )?;
+
+        let mut user_access_account_data = user_access_account.data.borrow_mut();
+        let mut unpacked_user_access_data =
+            AccessState::unpack_from_slice(&user_access_account_data)?;
+
+        // The associated access account is created by `InitAssociatedAccess`
+        if user_access_account.owner != &program_id || !unpacked_user_access_data.is_initialized() {
+            check_failed!(
+                user_access_account.key,
+                "Associated access account not initialized"
+            );
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+
+        if !unpacked_dataset_data.is_initialized() {
+            check_failed!(dataset_account.key, "Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+        if *owner_authority.key != unpacked_dataset_data.owner {
+            check_failed!(owner_authority.key, "Incorrect Dataset Owner");
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+        // Owners have access to their datasets without buying it
+        if user_authority.key == owner_authority.key {
+            check_failed!(user_authority.key, "Dataset owner cannot purchase access");
+            return Err(DataNexusError::OwnerCannotPurchase.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_on_sale(&unpacked_dataset_data, now)?;
+        if let Some(reference_access) = reference_access {
+            Self::assert_reference_access(
+                &program_id,
+                &unpacked_dataset_data,
+                user_authority.key,
+                reference_access,
+                now,
+            )?;
+        }
+        let discounted = reference_access.is_some();
+        if let Some(seats) = seats {
+            Self::assert_seat_payment(&unpacked_dataset_data, seats, amount, discounted)?;
+        } else {
+            Self::assert_payment(&unpacked_dataset_data, token_mint.key, amount, discounted)?;
+        }
+
+        // Expired entries may be bought again and are replaced in place
+        let existing_entry = unpacked_user_access_data.find_entry(&hash);
+        if let Some(index) = existing_entry {
+            let granted = unpacked_user_access_data.datasets.get(index);
+            if granted.is_some_and(|access| !access.is_expired(now)) {
+                msg!("Access to the dataset has already been granted");
+                return Err(DataNexusError::AccessAlreadyGranted.into());
+            }
+        } else if unpacked_user_access_data.datasets.len() >= MAX_ACCESS_ENTRIES {
+            msg!("Associated access account is full");
+            return Err(DataNexusError::IndexFull.into());
+        } else {
+            // Buying back an expired grant keeps the buyer's holder slot
+            Self::add_holders(&mut unpacked_dataset_data, 1)?;
+        }
+
+        let (vault_address, _) =
+            find_vault_address(&program_id, dataset_account.key, token_mint.key);
+        if vault_address != *proceeds_account.key {
+            check_failed!(
+                proceeds_account.key,
+                "Proceeds account is not the dataset vault for the payment mint"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+        if unpacked_dataset_data.claimable(token_mint.key).is_none() {
+            msg!("Dataset has no vault of the payment mint");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        let owner_amount = Self::pay_dataset_owner(
+            &unpacked_dataset_data,
+            token_program,
+            user_token_account,
+            token_mint,
+            proceeds_account,
+            referrer_token_account,
+            user_authority,
+            amount,
+        )?;
+        Self::credit_vault(&mut unpacked_dataset_data, token_mint.key, owner_amount)?;
+
+        let expires_at = match unpacked_dataset_data.access_duration {
+            Some(duration) => Some(
+                now.checked_add(duration)
+                    .ok_or(DataNexusError::NumericalOverflow)?,
+            ),
+            None => None,
+        };
+        // Seats replace the dataset's share limit, each one a share the
+        // buyer can hand out
+        let share_quota = match seats {
+            Some(seats) => seats,
+            None => unpacked_dataset_data.share_limit.unwrap_or(0),
+        };
+        let new_access = AccessInfo {
+            hash,
+            key: unpacked_dataset_data.key,
+            shared_from: None,
+            share_quota,
+            expires_at,
+            depth: 0,
+            shares_used: 0,
+        };
+        match existing_entry.and_then(|index| unpacked_user_access_data.datasets.get_mut(index)) {
+            Some(access) => *access = new_access,
+            None => unpacked_user_access_data.datasets.push(new_access),
+        }
+        unpacked_user_access_data.pack_into_slice(&mut user_access_account_data);
+
+        // The index is appended to in place, unpacking all of its slots
+        // would cost more than the rest of the purchase
+        AccountIndex::append_raw(
+            &mut user_access_index.data.borrow_mut(),
+            dataset_account.key,
+            &unpacked_dataset_data.hash,
+        )?;
+
+        unpacked_dataset_data.purchase_count = unpacked_dataset_data
+            .purchase_count
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        unpacked_dataset_data.total_revenue = unpacked_dataset_data
+            .total_revenue
+            .checked_add(amount)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        if let Some(receipt) = receipt {
+            if Self::mint_receipt(&program_id, dataset_account, user_authority, receipt)? {
+                unpacked_dataset_data.receipt_count = unpacked_dataset_data
+                    .receipt_count
+                    .checked_add(1)
+                    .ok_or(DataNexusError::NumericalOverflow)?;
+            }
+        }
+        unpacked_dataset_data.pack_into_slice(&mut dataset_account_data);
+
+        DataNexusEvent::Purchase(PurchaseEvent {
+            dataset: *dataset_account.key,
+            buyer: *user_authority.key,
+            amount,
+            mint: *token_mint.key,
+            slot: Clock::get()?.slot,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    fn process_renew_access(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        amount: u64,
+    ) -> ProgramResult {
+        RenewAccessAccounts::ACCOUNT_COUNT.check("RenewAccess", accounts)?;
+        let RenewAccessAccounts {
+            user_authority,
+            user_access_account,
+            user_token_account,
+            proceeds_account,
+            token_mint,
+            dataset_account,
+            token_program,
+            referrer_token_account,
+        } = RenewAccessAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        Self::assert_rent_exempt(&Rent::get()?, user_access_account)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        if !unpacked_dataset_data.is_initialized() {
+            check_failed!(dataset_account.key, "Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+
+        let duration = match unpacked_dataset_data.access_duration {
+            Some(duration) => duration,
+            None => {
+                msg!("Dataset access does not expire");
+                return Err(DataNexusError::AccessNotRenewable.into());
+            }
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        Self::assert_on_sale(&unpacked_dataset_data, now)?;
+        Self::assert_payment(&unpacked_dataset_data, token_mint.key, amount, false)?;
+
+        Self::assert_associated_access_pda(
+            &program_id,
+            user_authority.key,
+            dataset_account.key,
+            user_access_account,
+        )?;
+        if user_access_account.owner != &program_id {
+            check_failed!(
+                user_access_account.key,
+                "Associated access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut user_access_data = user_access_account.data.borrow_mut();
+        let mut unpacked_user_access_data = AccessState::unpack_from_slice(&user_access_data)?;
+
+        let access = match unpacked_user_access_data
+            .find_entry(&hash)
+            .and_then(|index| unpacked_user_access_data.datasets.get_mut(index))
+        {
+            Some(access) => access,
+            None => {
+                msg!("No existing access to renew");
+                return Err(DataNexusError::NoExistingAccess.into());
+            }
+        };
+
+        let (vault_address, _) =
+            find_vault_address(&program_id, dataset_account.key, token_mint.key);
+        if vault_address != *proceeds_account.key {
+            check_failed!(
+                proceeds_account.key,
+                "Proceeds account is not the dataset vault for the payment mint"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+        if unpacked_dataset_data.claimable(token_mint.key).is_none() {
+            msg!("Dataset has no vault of the payment mint");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        let owner_amount = Self::pay_dataset_owner(
+            &unpacked_dataset_data,
+            token_program,
+            user_token_account,
+            token_mint,
+            proceeds_account,
+            referrer_token_account,
+            user_authority,
+            amount,
+        )?;
+        Self::credit_vault(&mut unpacked_dataset_data, token_mint.key, owner_amount)?;
+        unpacked_dataset_data.total_revenue = unpacked_dataset_data
+            .total_revenue
+            .checked_add(amount)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        unpacked_dataset_data.pack_into_slice(&mut dataset_account_data);
+
+        // Extend from the current expiry if it is still in the future so
+        // renewing early does not forfeit the remaining time
+        let base = match access.expires_at {
+            Some(expires_at) => expires_at.max(now),
+            None => now,
+        };
+        access.expires_at = Some(
+            base.checked_add(duration)
+                .ok_or(DataNexusError::NumericalOverflow)?,
+        );
+
+        unpacked_user_access_data.pack_into_slice(&mut user_access_data);
+
+        Ok(())
+    }
+
+    /// Fails with `SaleNotActive` unless `dataset` is on sale at `now`
+    fn assert_on_sale(dataset: &DatasetState, now: i64) -> ProgramResult {
+        if !dataset.is_on_sale(now) {
+            msg!("Dataset is not on sale at {}", now);
+            return Err(DataNexusError::SaleNotActive.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `account` is the associated access account of
+    /// `authority` for the reference dataset of `dataset`, holding an
+    /// unexpired grant at `now`
+    fn assert_reference_access(
+        program_id: &Pubkey,
+        dataset: &DatasetState,
+        authority: &Pubkey,
+        account: &AccountInfo,
+        now: i64,
+    ) -> ProgramResult {
+        let ref_data = match dataset.ref_data {
+            Some(ref_data) => ref_data,
+            None => {
+                msg!("Dataset has no reference dataset to discount against");
+                return Err(DataNexusError::InvalidReferenceDataset.into());
+            }
+        };
+
+        Self::assert_associated_access_pda(program_id, authority, &ref_data, account)?;
+
+        if account.owner != program_id {
+            check_failed!(
+                account.key,
+                "Reference access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+        let access = AccessState::unpack_from_slice(&account.data.borrow())?;
+        if !access.is_initialized() {
+            check_failed!(account.key, "Reference access account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        if access.datasets.iter().all(|grant| grant.is_expired(now)) {
+            msg!("No unexpired access to the reference dataset");
+            return Err(DataNexusError::NoExistingAccess.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `amount` covers the price of `dataset` in `mint`,
+    /// datasets with no value and no price table cannot be bought, and ones
+    /// with a table only with the mints in it. `discounted` takes the
+    /// dataset's derived discount off the price
+    fn assert_payment(
+        dataset: &DatasetState,
+        mint: &Pubkey,
+        amount: u64,
+        discounted: bool,
+    ) -> ProgramResult {
+        let value = match dataset.price(mint) {
+            Some(value) => value,
+            None if dataset.prices.is_empty() => {
+                msg!("Dataset has no value set");
+                return Err(DataNexusError::DatasetNotPriced.into());
+            }
+            None => {
+                msg!("Dataset is not sold for mint {}", mint);
+                return Err(DataNexusError::UnsupportedMint.into());
+            }
+        };
+
+        let value = if discounted {
+            dataset.derived_price(value)
+        } else {
+            value
+        };
+
+        if amount < value {
+            msg!("Payment is below the dataset value");
+            return Err(DataNexusError::InsufficientPayment.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless `amount` covers `seats` at the dataset's unit price for
+    /// that many seats, less the derived discount when `discounted`
+    fn assert_seat_payment(
+        dataset: &DatasetState,
+        seats: u16,
+        amount: u64,
+        discounted: bool,
+    ) -> ProgramResult {
+        if seats == 0 {
+            msg!("At least one seat must be purchased");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let unit_price = match dataset.unit_price(seats) {
+            Some(unit_price) => unit_price,
+            None => {
+                msg!("Dataset has no value set");
+                return Err(DataNexusError::DatasetNotPriced.into());
+            }
+        };
+        let price = unit_price
+            .checked_mul(u64::from(seats))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let price = if discounted {
+            dataset.derived_price(price)
+        } else {
+            price
+        };
+
+        if amount < price {
+            msg!("Payment is below the price of {} seats", seats);
+            return Err(DataNexusError::InsufficientPayment.into());
+        }
+
+        Ok(())
+    }
+
+    /// Pays `amount` from the buyer to the dataset owner, splitting off the
+    /// dataset's referral share when a referrer token account is supplied,
+    /// and returns what the owner's token account received of its share
+    #[allow(clippy::too_many_arguments)]
+    fn pay_dataset_owner<'a>(
+        dataset: &DatasetState,
+        token_program: &AccountInfo<'a>,
+        user_token_account: &AccountInfo<'a>,
+        token_mint: &AccountInfo<'a>,
+        owner_token_account: &AccountInfo<'a>,
+        referrer_token_account: Option<&AccountInfo<'a>>,
+        user_authority: &AccountInfo<'a>,
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let owner_amount = match referrer_token_account {
+            Some(referrer_token_account) => {
+                let referrer = {
+                    let referrer_data = referrer_token_account.data.borrow();
+                    StateWithExtensions::<Account>::unpack(&referrer_data)?.base
+                };
+
+                if referrer.mint != *token_mint.key {
+                    check_failed!(
+                        referrer_token_account.key,
+                        "Referrer token account mint does not match payment mint"
+                    );
+                    return Err(DataNexusError::ReferrerMintMismatch.into());
+                }
+
+                // Buyers naming themselves would take the referral share as a
+                // discount
+                if referrer.owner == *user_authority.key {
+                    msg!("Referrer token account is owned by the buyer");
+                    return Err(DataNexusError::SelfReferral.into());
+                }
+
+                // Basis points never exceed `MAX_BPS`, so the share fits
+                // back into a `u64`
+                let referral_amount = (amount as u128)
+                    .checked_mul(dataset.referral_bps as u128)
+                    .and_then(|n| n.checked_div(MAX_BPS as u128))
+                    .ok_or(DataNexusError::NumericalOverflow)?
+                    as u64;
+
+                Self::transfer_tokens(
+                    token_program,
+                    user_token_account,
+                    token_mint,
+                    referrer_token_account,
+                    user_authority,
+                    referral_amount,
+                    &[],
+                )?;
+
+                amount
+                    .checked_sub(referral_amount)
+                    .ok_or(DataNexusError::NumericalOverflow)?
+            }
+            None => amount,
+        };
+
+        // Transfer-fee mints withhold part of the share at the destination,
+        // so the received amount is measured rather than assumed
+        let balance_before = Self::token_balance(owner_token_account)?;
+        Self::transfer_tokens(
+            token_program,
+            user_token_account,
+            token_mint,
+            owner_token_account,
+            user_authority,
+            owner_amount,
+            &[],
+        )?;
+        let balance_after = Self::token_balance(owner_token_account)?;
+
+        Ok(balance_after
+            .checked_sub(balance_before)
+            .ok_or(DataNexusError::NumericalOverflow)?)
+    }
+
+    /// Token amount held by `token_account` of spl-token or spl-token-2022
+    fn token_balance(token_account: &AccountInfo) -> Result<u64, ProgramError> {
+        let token_account_data = token_account.data.borrow();
+        Ok(StateWithExtensions::<Account>::unpack(&token_account_data)?
+            .base
+            .amount)
+    }
+
+    /// Pays `share_fee` from the sharer to the dataset owner's token account
+    /// of the fee mint
+    fn pay_share_fee<'a>(
+        owner: &Pubkey,
+        share_fee: &MintPrice,
+        fee_accounts: &ShareFeeAccounts<&AccountInfo<'a>>,
+        user_authority: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        if *fee_accounts.token_mint.key != share_fee.mint {
+            msg!("Share fee is charged in mint {}", share_fee.mint);
+            return Err(DataNexusError::UnsupportedMint.into());
+        }
+
+        let payee = {
+            let owner_token_data = fee_accounts.owner_token_account.data.borrow();
+            StateWithExtensions::<Account>::unpack(&owner_token_data)?.base
+        };
+        if payee.owner != *owner || payee.mint != share_fee.mint {
+            check_failed!(
+                fee_accounts.owner_token_account.key,
+                "Owner token account is not the dataset owner's account of the fee mint"
+            );
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Self::transfer_tokens(
+            fee_accounts.token_program,
+            fee_accounts.sharer_token_account,
+            fee_accounts.token_mint,
+            fee_accounts.owner_token_account,
+            user_authority,
+            share_fee.amount,
+            &[],
+        )
+    }
+
+    /// Adds `amount` to the claimable proceeds of the dataset's vault of
+    /// `mint`
+    fn credit_vault(dataset: &mut DatasetState, mint: &Pubkey, amount: u64) -> ProgramResult {
+        let claimable = dataset
+            .claimable_mut(mint)
+            .ok_or(DataNexusError::AccountNotInitialized)?;
+        *claimable = claimable
+            .checked_add(amount)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+
+        Ok(())
+    }
+
+    /// Transfers `amount` of `mint` from `source` to `destination` through
+    /// either spl-token or spl-token-2022, signing with `signer_seeds` when
+    /// the authority is a PDA
+    fn transfer_tokens<'a>(
+        token_program: &AccountInfo<'a>,
+        source: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        Self::assert_token_program(token_program, mint)?;
+
+        if amount == 0 {
+            return Ok(());
+        }
+
+        // Token-2022 mints may carry extensions after the base layout,
+        // `StateWithExtensions` also reads plain spl-token mints
+        let decimals = {
+            let mint_data = mint.data.borrow();
+            StateWithExtensions::<Mint>::unpack(&mint_data)?
+                .base
+                .decimals
+        };
+
+        // `transfer_checked` is required for mints with the transfer-fee
+        // extension, the destination receives `amount` less any withheld fee
+        let transfer_ix = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            source.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &transfer_ix,
+            &[
+                source.clone(),
+                mint.clone(),
+                destination.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            signer_seeds,
+        )
+    }
+
+    /// Fails unless `token_program` is spl-token or spl-token-2022 and owns
+    /// `mint`
+    fn assert_token_program(token_program: &AccountInfo, mint: &AccountInfo) -> ProgramResult {
+        if *token_program.key != spl_token::ID && *token_program.key != spl_token_2022::ID {
+            check_failed!(token_program.key, "Incorrect token program ID");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if mint.owner != token_program.key {
+            check_failed!(mint.key, "Token mint not owned by token program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Ok(())
+    }
+
+    fn process_init_vault(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        InitVaultAccounts::ACCOUNT_COUNT.check("InitVault", accounts)?;
+        let InitVaultAccounts {
+            payer,
+            owner_authority,
+            dataset_account,
+            token_mint,
+            vault_account,
+            token_program,
+            system_program,
+        } = InitVaultAccounts::from_account_infos(accounts)?;
+
+        if !owner_authority.is_signer {
+            check_failed!(owner_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        // Opening a vault is what lets buyers pay in its mint, so only the
+        // owner may choose the mints
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &hash,
+            owner_authority.key,
+        )?;
+
+        Self::assert_token_program(token_program, token_mint)?;
+
+        let (vault_address, bump) =
+            find_vault_address(&program_id, dataset_account.key, token_mint.key);
+        if vault_address != *vault_account.key {
+            check_failed!(
+                vault_account.key,
+                "Vault account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        if dataset.claimable(token_mint.key).is_some() {
+            msg!("Dataset already has a vault of the mint");
+            return Err(DataNexusError::AccountAlreadyInitialized.into());
+        }
+        if dataset.claimable.len() >= MAX_VAULTS {
+            msg!("Dataset already has a vault of {} mints", MAX_VAULTS);
+            return Err(DataNexusError::VaultLimitReached.into());
+        }
+        dataset.claimable.push(Claimable {
+            mint: *token_mint.key,
+            amount: 0,
+        });
+        dataset.pack_into_slice(&mut dataset_account_data);
+
+        // Token-2022 mints may require extensions on their accounts, such
+        // as the withheld amount of the transfer fee extension
+        let space = {
+            let mint_data = token_mint.data.borrow();
+            let mint_extensions =
+                StateWithExtensions::<Mint>::unpack(&mint_data)?.get_extension_types()?;
+            ExtensionType::get_account_len::<Account>(
+                &ExtensionType::get_required_init_account_extensions(&mint_extensions),
+            )
+        };
+
+        Self::create_pda_account(
+            token_program.key,
+            payer,
+            vault_account,
+            system_program,
+            &Rent::get()?,
+            space,
+            &[
+                VAULT_MARKER,
+                dataset_account.key.as_ref(),
+                token_mint.key.as_ref(),
+            ],
+            bump,
+        )?;
+
+        // The vault is its own authority, so only this program can sign
+        // transfers out of it
+        invoke(
+            &spl_token_2022::instruction::initialize_account3(
+                token_program.key,
+                vault_account.key,
+                token_mint.key,
+                vault_account.key,
+            )?,
+            &[
+                vault_account.clone(),
+                token_mint.clone(),
+                token_program.clone(),
+            ],
+        )
+    }
+
+    fn process_withdraw_proceeds(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        amount: u64,
+    ) -> ProgramResult {
+        let (accounts, signers) = split_signers(accounts);
+        WithdrawProceedsAccounts::ACCOUNT_COUNT.check("WithdrawProceeds", accounts)?;
+        let WithdrawProceedsAccounts {
+            owner_authority,
+            dataset_account,
+            vault_account,
+            token_mint,
+            destination_token_account,
+            token_program,
+        } = WithdrawProceedsAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_signed(owner_authority, signers)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &hash,
+            owner_authority.key,
+        )?;
+
+        let (vault_address, bump) =
+            find_vault_address(&program_id, dataset_account.key, token_mint.key);
+        if vault_address != *vault_account.key {
+            check_failed!(
+                vault_account.key,
+                "Vault account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        // Proceeds are credited as the vault receives them, the balance is
+        // checked all the same
+        let vault_balance = Self::token_balance(vault_account)?;
+        let claimable = match dataset.claimable_mut(token_mint.key) {
+            Some(claimable) => claimable,
+            None => {
+                msg!("Dataset has no vault of the mint");
+                return Err(DataNexusError::AccountNotInitialized.into());
+            }
+        };
+        if amount > *claimable || amount > vault_balance {
+            msg!(
+                "Withdrawal exceeds the claimable proceeds of {} and the vault balance of {}",
+                claimable,
+                vault_balance
+            );
+            return Err(DataNexusError::InsufficientProceeds.into());
+        }
+
+        *claimable = claimable
+            .checked_sub(amount)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        dataset.pack_into_slice(&mut dataset_account_data);
+
+        Self::transfer_tokens(
+            token_program,
+            vault_account,
+            token_mint,
+            destination_token_account,
+            vault_account,
+            amount,
+            &[&[
+                VAULT_MARKER,
+                dataset_account.key.as_ref(),
+                token_mint.key.as_ref(),
+                &[bump],
+            ]],
+        )
+    }
+
+    fn process_init_receipt_mint(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        InitReceiptMintAccounts::ACCOUNT_COUNT.check("InitReceiptMint", accounts)?;
+        let InitReceiptMintAccounts {
+            payer,
+            dataset_account,
+            receipt_mint,
+            token_program,
+            system_program,
+        } = InitReceiptMintAccounts::from_account_infos(accounts)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        {
+            let dataset = DatasetState::unpack_from_slice(&dataset_account.data.borrow())?;
+            if !dataset.is_initialized() {
+                check_failed!(dataset_account.key, "Dataset account not initialized");
+                return Err(DataNexusError::AccountNotInitialized.into());
+            }
+            Self::assert_dataset_hash(&dataset.hash, &hash)?;
+        }
+
+        if *token_program.key != spl_token::ID && *token_program.key != spl_token_2022::ID {
+            check_failed!(token_program.key, "Incorrect token program ID");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let (receipt_mint_address, bump) =
+            find_receipt_mint_address(&program_id, dataset_account.key);
+        if receipt_mint_address != *receipt_mint.key {
+            check_failed!(
+                receipt_mint.key,
+                "Receipt mint does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        Self::create_pda_account(
+            token_program.key,
+            payer,
+            receipt_mint,
+            system_program,
+            &Rent::get()?,
+            ExtensionType::get_account_len::<Mint>(&[]),
+            &[RECEIPT_MARKER, dataset_account.key.as_ref()],
+            bump,
+        )?;
+
+        // The mint is its own mint and freeze authority, so only this
+        // program issues and burns receipts
+        invoke(
+            &spl_token_2022::instruction::initialize_mint2(
+                token_program.key,
+                receipt_mint.key,
+                receipt_mint.key,
+                Some(receipt_mint.key),
+                0,
+            )?,
+            &[receipt_mint.clone(), token_program.clone()],
+        )
+    }
+
+    /// Fails unless the receipt accounts hold the receipt mint of `dataset`
+    /// and a token account of it owned by `holder`, returning the mint's
+    /// bump and the receipts `holder` has
+    fn assert_receipt_accounts(
+        program_id: &Pubkey,
+        dataset: &Pubkey,
+        holder: &Pubkey,
+        receipt: &ReceiptAccounts<&AccountInfo>,
+    ) -> Result<(u8, u64), ProgramError> {
+        Self::assert_token_program(receipt.token_program, receipt.receipt_mint)?;
+
+        let (receipt_mint_address, bump) = find_receipt_mint_address(program_id, dataset);
+        if receipt_mint_address != *receipt.receipt_mint.key {
+            check_failed!(
+                receipt.receipt_mint.key,
+                "Receipt mint does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        let receipt_account_data = receipt.receipt_token_account.data.borrow();
+        let receipt_account = StateWithExtensions::<Account>::unpack(&receipt_account_data)?;
+        if receipt_account.base.mint != receipt_mint_address
+            || receipt_account.base.owner != *holder
+        {
+            check_failed!(
+                receipt.receipt_token_account.key,
+                "Receipt token account is not the holder's account of the receipt mint"
+            );
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Ok((bump, receipt_account.base.amount))
+    }
+
+    /// Mints a receipt to `holder` unless they hold one already, and
+    /// returns whether it did. It is left frozen with the receipt mint as
+    /// delegate, so it cannot be passed on and `RevokeAccess` can burn it
+    fn mint_receipt<'a>(
+        program_id: &Pubkey,
+        dataset_account: &AccountInfo<'a>,
+        holder: &AccountInfo<'a>,
+        receipt: ReceiptAccounts<&AccountInfo<'a>>,
+    ) -> Result<bool, ProgramError> {
+        let (bump, held) =
+            Self::assert_receipt_accounts(program_id, dataset_account.key, holder.key, &receipt)?;
+        // Access bought again after expiring keeps its first receipt
+        if held > 0 {
+            return Ok(false);
+        }
+
+        let ReceiptAccounts {
+            receipt_mint,
+            receipt_token_account,
+            token_program,
+        } = receipt;
+        let signer_seeds: &[&[u8]] = &[RECEIPT_MARKER, dataset_account.key.as_ref(), &[bump]];
+
+        invoke_signed(
+            &spl_token_2022::instruction::mint_to(
+                token_program.key,
+                receipt_mint.key,
+                receipt_token_account.key,
+                receipt_mint.key,
+                &[],
+                1,
+            )?,
+            &[
+                receipt_mint.clone(),
+                receipt_token_account.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        // The holder signed the purchase, which lets the program approve
+        // the delegate on their behalf
+        invoke(
+            &spl_token_2022::instruction::approve(
+                token_program.key,
+                receipt_token_account.key,
+                receipt_mint.key,
+                holder.key,
+                &[],
+                1,
+            )?,
+            &[
+                receipt_token_account.clone(),
+                receipt_mint.clone(),
+                holder.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        invoke_signed(
+            &spl_token_2022::instruction::freeze_account(
+                token_program.key,
+                receipt_token_account.key,
+                receipt_mint.key,
+                receipt_mint.key,
+                &[],
+            )?,
+            &[
+                receipt_token_account.clone(),
+                receipt_mint.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        Ok(true)
+    }
+
+    /// Thaws and burns the receipt minted to `holder`, as its delegate, and
+    /// returns whether it did. Holders without a receipt are left as they
+    /// are
+    fn burn_receipt<'a>(
+        program_id: &Pubkey,
+        dataset_account: &AccountInfo<'a>,
+        holder: &AccountInfo<'a>,
+        receipt: ReceiptAccounts<&AccountInfo<'a>>,
+    ) -> Result<bool, ProgramError> {
+        let (bump, held) =
+            Self::assert_receipt_accounts(program_id, dataset_account.key, holder.key, &receipt)?;
+        if held == 0 {
+            return Ok(false);
+        }
+
+        let ReceiptAccounts {
+            receipt_mint,
+            receipt_token_account,
+            token_program,
+        } = receipt;
+        let signer_seeds: &[&[u8]] = &[RECEIPT_MARKER, dataset_account.key.as_ref(), &[bump]];
+
+        invoke_signed(
+            &spl_token_2022::instruction::thaw_account(
+                token_program.key,
+                receipt_token_account.key,
+                receipt_mint.key,
+                receipt_mint.key,
+                &[],
+            )?,
+            &[
+                receipt_token_account.clone(),
+                receipt_mint.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        invoke_signed(
+            &spl_token_2022::instruction::burn(
+                token_program.key,
+                receipt_token_account.key,
+                receipt_mint.key,
+                receipt_mint.key,
+                &[],
+                1,
+            )?,
+            &[
+                receipt_token_account.clone(),
+                receipt_mint.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+
+        Ok(true)
+    }
+
+    fn process_revoke_access(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        RevokeAccessAccounts::ACCOUNT_COUNT.check("RevokeAccess", accounts)?;
+        let RevokeAccessAccounts {
+            owner_authority,
+            dataset_account,
+            holder_authority,
+            holder_access_account,
+            receipt,
+        } = RevokeAccessAccounts::from_account_infos(accounts)?;
+
+        if !owner_authority.is_signer {
+            check_failed!(owner_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &hash,
+            owner_authority.key,
+        )?;
+
+        Self::assert_associated_access_pda(
+            &program_id,
+            holder_authority.key,
+            dataset_account.key,
+            holder_access_account,
+        )?;
+        if holder_access_account.owner != &program_id {
+            check_failed!(
+                holder_access_account.key,
+                "Holder access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        {
+            let mut holder_access_data = holder_access_account.data.borrow_mut();
+            let mut holder_access = AccessState::unpack_from_slice(&holder_access_data)?;
+            match holder_access.find_entry(&hash) {
+                Some(index) => {
+                    holder_access.datasets.remove(index);
+                }
+                None => {
+                    msg!("Holder has no access to the dataset");
+                    return Err(DataNexusError::AccessNotFound.into());
+                }
+            }
+            holder_access.pack_into_slice(&mut holder_access_data);
+        }
+
+        // Datasets migrated with a seeded count may have had holders revoked
+        // before it was kept
+        dataset.holder_count = dataset.holder_count.saturating_sub(1);
+
+        // Without the receipt accounts the holder's receipt would outlive
+        // their access, they are only optional while no receipt is out
+        let burned = match receipt {
+            Some(receipt) => {
+                Self::burn_receipt(&program_id, dataset_account, holder_authority, receipt)?
+            }
+            None if dataset.receipt_count > 0 => {
+                msg!("Dataset has issued receipts, the receipt accounts are required");
+                return Err(DataNexusError::ReceiptRequired.into());
+            }
+            None => false,
+        };
+        if burned {
+            dataset.receipt_count = dataset.receipt_count.saturating_sub(1);
+        }
+        dataset.pack_into_slice(&mut dataset_data);
+        drop(dataset_data);
+
+        DataNexusEvent::AccessRevoked(AccessRevokedEvent {
+            dataset: *dataset_account.key,
+            holder: *holder_authority.key,
+            slot: Clock::get()?.slot,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    fn process_share_access(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        expires_in: Option<i64>,
+    ) -> ProgramResult {
+        ShareAccessAccounts::ACCOUNT_COUNT.check("ShareAccess", accounts)?;
+        let ShareAccessAccounts {
+            user_authority,
+            user_access_account,
+            recipient_authority,
+            recipient_access_index,
+            recipient_access_account,
+            dataset_account,
+        } = ShareAccessAccounts::from_account_infos(accounts)?;
+        let fee_accounts = ShareFeeAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if matches!(expires_in, Some(expires_in) if expires_in <= 0) {
+            msg!("Shared access must expire in a positive duration");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let rent = Rent::get()?;
+        Self::assert_rent_exempt(&rent, user_access_account)?;
+        Self::assert_rent_exempt(&rent, recipient_access_index)?;
+        Self::assert_rent_exempt(&rent, recipient_access_account)?;
+        Self::assert_rent_exempt(&rent, dataset_account)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        // The sharer's and recipient's access accounts would alias
+        if recipient_authority.key == user_authority.key {
+            msg!("Access cannot be shared with the sharer");
+            return Err(DataNexusError::CannotShareToSelf.into());
+        }
+
+        Self::assert_share_accounts(
+            &program_id,
+            dataset_account.key,
+            user_authority.key,
+            user_access_account,
+            &[RecipientAccounts {
+                authority: recipient_authority,
+                access_index: recipient_access_index,
+                access_account: recipient_access_account,
+            }],
+        )?;
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+        Self::assert_not_owner(&unpacked_dataset_data, &[recipient_authority])?;
+        let mut user_access_data = user_access_account.data.borrow_mut();
+        let mut unpacked_user_access_data = AccessState::unpack_from_slice(&user_access_data)?;
+
+        let user_access = match unpacked_user_access_data
+            .find_entry(&hash)
+            .and_then(|index| unpacked_user_access_data.datasets.get_mut(index))
+        {
+            Some(access) => access,
+            None => {
+                msg!("Sharer has no access to the dataset");
+                return Err(DataNexusError::AccessNotFound.into());
+            }
+        };
+
+        let clock = Clock::get()?;
+        if user_access.is_expired(clock.unix_timestamp) {
+            msg!("Sharer's access to the dataset has expired");
+            return Err(DataNexusError::AccessExpired.into());
+        }
+
+        Self::assert_resharable(&unpacked_dataset_data, user_access)?;
+        let depth = Self::shared_depth(&unpacked_dataset_data, user_access)?;
+
+        if user_access.shares_remaining() == 0 {
+            msg!("Sharer has no remaining shares");
+            return Err(DataNexusError::ShareLimitExceeded.into());
+        }
+
+        let mut recipient_access_data = recipient_access_account.data.borrow_mut();
+        let mut unpacked_recipient_access_data =
+            AccessState::unpack_from_slice(&recipient_access_data)?;
+
+        if unpacked_recipient_access_data.find_entry(&hash).is_some() {
+            msg!("Recipient already has access to the dataset");
+            return Err(DataNexusError::AccessAlreadyGranted.into());
+        }
+
+        if unpacked_recipient_access_data.datasets.len() >= MAX_ACCESS_ENTRIES {
+            return Err(DataNexusError::IndexFull.into());
+        }
+
+        let expires_at =
+            Self::shared_expiry(user_access.expires_at, expires_in, clock.unix_timestamp)?;
+
+        // Recipients holding shares for approval become holders on accepting
+        let pending =
+            AccountIndex::require_share_approval_raw(&recipient_access_index.data.borrow())?;
+        if !pending {
+            Self::add_holders(&mut unpacked_dataset_data, 1)?;
+        }
+
+        if let Some(share_fee) = unpacked_dataset_data.share_fee {
+            let fee_accounts = match fee_accounts {
+                Some(fee_accounts) => fee_accounts,
+                None => {
+                    msg!("Dataset charges a share fee, pass the fee accounts");
+                    return Err(DataNexusError::ShareFeeRequired.into());
+                }
+            };
+            Self::pay_share_fee(
+                &unpacked_dataset_data.owner,
+                &share_fee,
+                &fee_accounts,
+                user_authority,
+            )?;
+        }
+
+        let access = AccessInfo {
+            hash,
+            key: user_access.key,
+            shared_from: Some(*user_authority.key),
+            share_quota: Self::shared_share_quota(&unpacked_dataset_data),
+            expires_at,
+            depth,
+            shares_used: 0,
+        };
+        if pending {
+            Self::hold_share(
+                &mut unpacked_recipient_access_data,
+                access,
+                clock.unix_timestamp,
+            )?;
+        } else {
+            unpacked_recipient_access_data.datasets.push(access);
+            AccountIndex::append_raw(
+                &mut recipient_access_index.data.borrow_mut(),
+                dataset_account.key,
+                &unpacked_dataset_data.hash,
+            )?;
+            unpacked_dataset_data.share_count = unpacked_dataset_data
+                .share_count
+                .checked_add(1)
+                .ok_or(DataNexusError::NumericalOverflow)?;
+        }
+        unpacked_recipient_access_data.pack_into_slice(&mut recipient_access_data);
+
+        // A pending share takes its share out of the quota until rejected
+        user_access.shares_used = user_access
+            .shares_used
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        unpacked_user_access_data.pack_into_slice(&mut user_access_data);
+
+        unpacked_dataset_data.pack_into_slice(&mut dataset_account_data);
+
+        Self::share_event(
+            pending,
+            ShareEvent {
+                dataset: *dataset_account.key,
+                sharer: *user_authority.key,
+                recipient: *recipient_authority.key,
+                slot: clock.slot,
+            },
+        )
+        .emit();
+
+        Ok(())
+    }
+
+    fn process_share_access_multi(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        ShareAccessMultiAccounts::ACCOUNT_COUNT.check("ShareAccessMulti", accounts)?;
+        let ShareAccessMultiAccounts {
+            user_authority,
+            user_access_account,
+            dataset_account,
+            recipients,
+        } = ShareAccessMultiAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        let rent = Rent::get()?;
+        Self::assert_rent_exempt(&rent, user_access_account)?;
+        Self::assert_rent_exempt(&rent, dataset_account)?;
+        for recipient in recipients.iter() {
+            Self::assert_rent_exempt(&rent, recipient.access_index)?;
+            Self::assert_rent_exempt(&rent, recipient.access_account)?;
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        // The sharer's and a recipient's access accounts would alias
+        if recipients
+            .iter()
+            .any(|recipient| recipient.authority.key == user_authority.key)
+        {
+            msg!("Access cannot be shared with the sharer");
+            return Err(DataNexusError::CannotShareToSelf.into());
+        }
+
+        Self::assert_share_accounts(
+            &program_id,
+            dataset_account.key,
+            user_authority.key,
+            user_access_account,
+            &recipients,
+        )?;
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+        let recipient_authorities: Vec<_> = recipients
+            .iter()
+            .map(|recipient| recipient.authority)
+            .collect();
+        Self::assert_not_owner(&unpacked_dataset_data, &recipient_authorities)?;
+        // The fee group is passed once per share, which a batch can't do
+        if unpacked_dataset_data.share_fee.is_some() {
+            msg!("Dataset charges a share fee, share with ShareAccess instead");
+            return Err(DataNexusError::ShareFeeRequired.into());
+        }
+
+        let mut user_access_data = user_access_account.data.borrow_mut();
+        let mut unpacked_user_access_data = AccessState::unpack_from_slice(&user_access_data)?;
+
+        let user_access = match unpacked_user_access_data
+            .find_entry(&hash)
+            .and_then(|index| unpacked_user_access_data.datasets.get_mut(index))
+        {
+            Some(access) => access,
+            None => {
+                msg!("Sharer has no access to the dataset");
+                return Err(DataNexusError::AccessNotFound.into());
+            }
+        };
+
+        let clock = Clock::get()?;
+        if user_access.is_expired(clock.unix_timestamp) {
+            msg!("Sharer's access to the dataset has expired");
+            return Err(DataNexusError::AccessExpired.into());
+        }
+
+        Self::assert_resharable(&unpacked_dataset_data, user_access)?;
+        let depth = Self::shared_depth(&unpacked_dataset_data, user_access)?;
+
+        // Check the whole batch up front so no recipient is written when
+        // the limit would be exceeded partway through
+        let recipient_count = recipients.len();
+        if recipient_count > usize::from(user_access.shares_remaining()) {
+            msg!(
+                "Sharing with {} recipients exceeds the {} remaining shares",
+                recipient_count,
+                user_access.shares_remaining()
+            );
+            return Err(DataNexusError::ShareLimitExceeded.into());
+        }
+
+        // Recipients holding shares for approval are granted on accepting
+        let pending = recipients
+            .iter()
+            .map(|recipient| {
+                AccountIndex::require_share_approval_raw(&recipient.access_index.data.borrow())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let granted = pending.iter().filter(|pending| !**pending).count() as u32;
+        unpacked_dataset_data.share_count = unpacked_dataset_data
+            .share_count
+            .checked_add(granted)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        Self::add_holders(&mut unpacked_dataset_data, granted)?;
+        unpacked_dataset_data.pack_into_slice(&mut dataset_account_data);
+
+        for (
+            RecipientAccounts {
+                authority: recipient_authority,
+                access_index: recipient_access_index,
+                access_account: recipient_access_account,
+            },
+            pending,
+        ) in recipients.into_iter().zip(pending)
+        {
+            if !pending {
+                AccountIndex::append_raw(
+                    &mut recipient_access_index.data.borrow_mut(),
+                    dataset_account.key,
+                    &unpacked_dataset_data.hash,
+                )?;
+            }
+
+            let mut recipient_access_data = recipient_access_account.data.borrow_mut();
+            let mut unpacked_recipient_access_data =
+                AccessState::unpack_from_slice(&recipient_access_data)?;
+
+            if unpacked_recipient_access_data.find_entry(&hash).is_some() {
+                msg!(
+                    "Recipient {} already has access to the dataset",
+                    recipient_authority.key
+                );
+                return Err(DataNexusError::AccessAlreadyGranted.into());
+            }
+
+            if unpacked_recipient_access_data.datasets.len() >= MAX_ACCESS_ENTRIES {
+                return Err(DataNexusError::IndexFull.into());
+            }
+
+            let access = AccessInfo {
+                hash,
+                key: user_access.key,
+                shared_from: Some(*user_authority.key),
+                share_quota: Self::shared_share_quota(&unpacked_dataset_data),
+                expires_at: user_access.expires_at,
+                depth,
+                shares_used: 0,
+            };
+            if pending {
+                Self::hold_share(
+                    &mut unpacked_recipient_access_data,
+                    access,
+                    clock.unix_timestamp,
+                )?;
+            } else {
+                unpacked_recipient_access_data.datasets.push(access);
+            }
+            unpacked_recipient_access_data.pack_into_slice(&mut recipient_access_data);
+
+            user_access.shares_used = user_access
+                .shares_used
+                .checked_add(1)
+                .ok_or(DataNexusError::NumericalOverflow)?;
+
+            Self::share_event(
+                pending,
+                ShareEvent {
+                    dataset: *dataset_account.key,
+                    sharer: *user_authority.key,
+                    recipient: *recipient_authority.key,
+                    slot: clock.slot,
+                },
+            )
+            .emit();
+        }
+
+        unpacked_user_access_data.pack_into_slice(&mut user_access_data);
+
+        Ok(())
+    }
+
+    /// Fails unless the sharer's access account and each recipient's access
+    /// index and access account are theirs of the dataset at `dataset`
+    fn assert_share_accounts(
+        program_id: &Pubkey,
+        dataset: &Pubkey,
+        user_authority: &Pubkey,
+        user_access_account: &AccountInfo,
+        recipients: &[RecipientAccounts<&AccountInfo>],
+    ) -> ProgramResult {
+        Self::assert_associated_access_pda(
+            program_id,
+            user_authority,
+            dataset,
+            user_access_account,
+        )?;
+        if user_access_account.owner != program_id {
+            check_failed!(
+                user_access_account.key,
+                "User access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        for recipient in recipients {
+            Self::assert_index(
+                program_id,
+                recipient.access_index,
+                recipient.authority.key,
+                AccountType::AccessIndex,
+            )?;
+
+            Self::assert_associated_access_pda(
+                program_id,
+                recipient.authority.key,
+                dataset,
+                recipient.access_account,
+            )?;
+            if recipient.access_account.owner != program_id {
+                check_failed!(
+                    recipient.access_account.key,
+                    "Recipient access account not owned by DataNexus"
+                );
+                return Err(DataNexusError::InvalidSeeds.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails when any of `recipients` owns `dataset`, owners have access to
+    /// their datasets without being granted it
+    fn assert_not_owner(dataset: &DatasetState, recipients: &[&AccountInfo]) -> ProgramResult {
+        if let Some(owner) = recipients
+            .iter()
+            .find(|recipient| *recipient.key == dataset.owner)
+        {
+            check_failed!(owner.key, "The dataset owner has access to it already");
+            return Err(DataNexusError::AccessAlreadyGranted.into());
+        }
+        Ok(())
+    }
+
+    /// Fails when `access` was itself shared and the dataset does not allow
+    /// shared grants to be shared on
+    fn assert_resharable(dataset: &DatasetState, access: &AccessInfo) -> ProgramResult {
+        if access.shared_from.is_some() && !dataset.resharable {
+            msg!("Shared access to the dataset cannot be shared on");
+            return Err(DataNexusError::NotResharable.into());
+        }
+
+        Ok(())
+    }
+
+    /// Depth of a grant shared on from `access`, failing when it would pass
+    /// the dataset's share depth limit
+    fn shared_depth(dataset: &DatasetState, access: &AccessInfo) -> Result<u8, ProgramError> {
+        let depth = access
+            .depth
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        if let Some(max_share_depth) = dataset.max_share_depth {
+            if depth > max_share_depth {
+                msg!(
+                    "Sharing on would pass the dataset's share depth limit of {}",
+                    max_share_depth
+                );
+                return Err(DataNexusError::ShareDepthExceeded.into());
+            }
+        }
+
+        Ok(depth)
+    }
+
+    /// Counts `holders` new holders of the dataset, failing with
+    /// `MaxHoldersReached` when they don't fit its quota
+    fn add_holders(dataset: &mut DatasetState, holders: u32) -> ProgramResult {
+        let holder_count = dataset
+            .holder_count
+            .checked_add(holders)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        if let Some(max_holders) = dataset.max_holders {
+            if holder_count > max_holders {
+                msg!("Dataset admits at most {} holders", max_holders);
+                return Err(DataNexusError::MaxHoldersReached.into());
+            }
+        }
+        dataset.holder_count = holder_count;
+
+        Ok(())
+    }
+
+    /// Share quota of a recipient's grant, the dataset's share limit when
+    /// shared grants may be shared on and none otherwise
+    fn shared_share_quota(dataset: &DatasetState) -> u16 {
+        if dataset.resharable {
+            dataset.share_limit.unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    /// Expiry of a shared grant, `expires_in` seconds from `now` but never
+    /// past the sharer's own expiry
+    fn shared_expiry(
+        sharer_expires_at: Option<i64>,
+        expires_in: Option<i64>,
+        now: i64,
+    ) -> Result<Option<i64>, ProgramError> {
+        let expires_at = match expires_in {
+            Some(expires_in) => Some(
+                now.checked_add(expires_in)
+                    .ok_or(DataNexusError::NumericalOverflow)?,
+            ),
+            None => None,
+        };
+        Ok(match (sharer_expires_at, expires_at) {
+            (Some(sharer), Some(shared)) => Some(sharer.min(shared)),
+            (sharer, shared) => sharer.or(shared),
+        })
+    }
+
+    /// Holds `access` among the recipient's pending shares until it accepts
+    /// it, failing when the same sharer's share is pending already or the
+    /// recipient has no room for another
+    fn hold_share(recipient: &mut AccessState, access: AccessInfo, now: i64) -> ProgramResult {
+        if recipient.pending.iter().any(|pending| {
+            pending.access.hash == access.hash && pending.sharer() == access.shared_from
+        }) {
+            msg!("A share of the dataset to the recipient is pending already");
+            return Err(DataNexusError::AccessAlreadyGranted.into());
+        }
+
+        if recipient.pending.len() >= MAX_PENDING_SHARES {
+            msg!(
+                "Recipient has {} shares pending already",
+                MAX_PENDING_SHARES
+            );
+            return Err(DataNexusError::IndexFull.into());
+        }
+
+        recipient.pending.push(PendingShare {
+            access,
+            shared_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Event logged for a share, `SharePending` when it is held for approval
+    fn share_event(pending: bool, share: ShareEvent) -> DataNexusEvent {
+        if pending {
+            DataNexusEvent::SharePending(share)
+        } else {
+            DataNexusEvent::Share(share)
+        }
+    }
+
+    fn process_accept_share(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        from: Pubkey,
+    ) -> ProgramResult {
+        AcceptShareAccounts::ACCOUNT_COUNT.check("AcceptShare", accounts)?;
+        let AcceptShareAccounts {
+            authority,
+            access_index,
+            access_account,
+            dataset_account,
+        } = AcceptShareAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        Self::assert_index(
+            &program_id,
+            access_index,
+            authority.key,
+            AccountType::AccessIndex,
+        )?;
+        Self::assert_associated_access_pda(
+            &program_id,
+            authority.key,
+            dataset_account.key,
+            access_account,
+        )?;
+        if access_account.owner != &program_id {
+            check_failed!(access_account.key, "Access account not owned by DataNexus");
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let mut unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+
+        let mut access_data = access_account.data.borrow_mut();
+        let mut unpacked_access_data = AccessState::unpack_from_slice(&access_data)?;
+        let pending = match unpacked_access_data.find_pending(&hash, &from) {
+            Some(position) => unpacked_access_data.pending.remove(position),
+            None => {
+                msg!("No share of the dataset from {} is pending", from);
+                return Err(DataNexusError::NoPendingShare.into());
+            }
+        };
+
+        let clock = Clock::get()?;
+        if pending.is_lapsed(clock.unix_timestamp) {
+            msg!("Pending share has lapsed and can only be rejected");
+            return Err(DataNexusError::AccessExpired.into());
+        }
+
+        if unpacked_access_data.find_entry(&hash).is_some() {
+            msg!("Authority already has access to the dataset, reject the share instead");
+            return Err(DataNexusError::AccessAlreadyGranted.into());
+        }
+
+        if unpacked_access_data.datasets.len() >= MAX_ACCESS_ENTRIES {
+            return Err(DataNexusError::IndexFull.into());
+        }
+
+        Self::add_holders(&mut unpacked_dataset_data, 1)?;
+        unpacked_dataset_data.share_count = unpacked_dataset_data
+            .share_count
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        unpacked_dataset_data.pack_into_slice(&mut dataset_account_data);
+
+        unpacked_access_data.datasets.push(pending.access);
+        unpacked_access_data.pack_into_slice(&mut access_data);
+
+        AccountIndex::append_raw(
+            &mut access_index.data.borrow_mut(),
+            dataset_account.key,
+            &unpacked_dataset_data.hash,
+        )?;
+
+        DataNexusEvent::Share(ShareEvent {
+            dataset: *dataset_account.key,
+            sharer: from,
+            recipient: *authority.key,
+            slot: clock.slot,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    fn process_reject_share(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        from: Pubkey,
+    ) -> ProgramResult {
+        RejectShareAccounts::ACCOUNT_COUNT.check("RejectShare", accounts)?;
+        let RejectShareAccounts {
+            authority,
+            recipient_authority,
+            recipient_access_account,
+            sharer_access_account,
+            dataset_account,
+        } = RejectShareAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        let by_recipient = authority.key == recipient_authority.key;
+        if !by_recipient && *authority.key != from {
+            check_failed!(
+                authority.key,
+                "Only the recipient or the sharer may reject a share"
+            );
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        // The dataset may have been closed since, only its address is read
+        Self::assert_associated_access_pda(
+            &program_id,
+            recipient_authority.key,
+            dataset_account.key,
+            recipient_access_account,
+        )?;
+        if recipient_access_account.owner != &program_id {
+            check_failed!(
+                recipient_access_account.key,
+                "Recipient access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+        Self::assert_associated_access_pda(
+            &program_id,
+            &from,
+            dataset_account.key,
+            sharer_access_account,
+        )?;
+
+        let mut recipient_access_data = recipient_access_account.data.borrow_mut();
+        let mut unpacked_recipient_access_data =
+            AccessState::unpack_from_slice(&recipient_access_data)?;
+        let pending = match unpacked_recipient_access_data.find_pending(&hash, &from) {
+            Some(position) => unpacked_recipient_access_data.pending.remove(position),
+            None => {
+                msg!("No share of the dataset from {} is pending", from);
+                return Err(DataNexusError::NoPendingShare.into());
+            }
+        };
+
+        // Sharers reclaim their share only once the recipient let it lapse
+        if !by_recipient && !pending.is_lapsed(Clock::get()?.unix_timestamp) {
+            msg!("Pending share has not lapsed, only the recipient may reject it");
+            return Err(DataNexusError::AccessNotExpired.into());
+        }
+        unpacked_recipient_access_data.pack_into_slice(&mut recipient_access_data);
+
+        // Only the quota comes back, a share fee was paid out to the owner
+        // when sharing and stays forfeited. A sharer whose grant has gone
+        // since has no quota to give it back to
+        if sharer_access_account.owner == &program_id {
+            let mut sharer_access_data = sharer_access_account.data.borrow_mut();
+            let mut unpacked_sharer_access_data =
+                AccessState::unpack_from_slice(&sharer_access_data)?;
+            if let Some(sharer_access) = unpacked_sharer_access_data
+                .find_entry(&hash)
+                .and_then(|index| unpacked_sharer_access_data.datasets.get_mut(index))
+            {
+                sharer_access.shares_used = sharer_access.shares_used.saturating_sub(1);
+                unpacked_sharer_access_data.pack_into_slice(&mut sharer_access_data);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_close_dataset(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        force: bool,
+    ) -> ProgramResult {
+        CloseDatasetAccounts::ACCOUNT_COUNT.check("CloseDataset", accounts)?;
+        let CloseDatasetAccounts {
+            authority,
+            owner_index: owner_account,
+            dataset_account,
+        } = CloseDatasetAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_account_data = dataset_account.data.borrow_mut();
+        let unpacked_dataset_data = DatasetState::unpack_from_slice(&dataset_account_data)?;
+
+        if *authority.key != unpacked_dataset_data.owner {
+            check_failed!(authority.key, "Incorrect Dataset Owner");
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Self::assert_dataset_hash(&unpacked_dataset_data.hash, &hash)?;
+
+        // Holders keep their stale access entries on a forced close, new
+        // purchases fail as the dataset account no longer unpacks
+        if unpacked_dataset_data.purchase_count > 0 && !force {
+            msg!("Dataset Has Holders");
+            return Err(DataNexusError::DatasetHasHolders.into());
+        }
+
+        // Withdrawals need the dataset, proceeds left behind would be locked
+        // in the vaults for good
+        if !unpacked_dataset_data.is_withdrawn() {
+            msg!("Dataset proceeds must be withdrawn before closing");
+            return Err(DataNexusError::UnclaimedProceeds.into());
+        }
+
+        Self::assert_index(
+            &program_id,
+            owner_account,
+            authority.key,
+            AccountType::DatasetIndex,
+        )?;
+
+        let rent = Rent::get()?;
+        Self::assert_rent_exempt(&rent, owner_account)?;
+
+        let mut owner_account_data = owner_account.data.borrow_mut();
+        let mut unpacked_owner_account_data = AccountIndex::unpack_from_slice(&owner_account_data)?;
+
+        unpacked_owner_account_data.remove(dataset_account.key);
+        unpacked_owner_account_data.pack_into_slice(&mut owner_account_data);
+
+        // A reclaimed account could be created again under the same hash,
+        // so the dataset is shrunk to a tombstone that keeps the address
+        dataset_account_data.fill(0);
+        drop(dataset_account_data);
+        dataset_account.realloc(DatasetState::CLOSED_LEN, false)?;
+
+        let refund = dataset_account
+            .lamports()
+            .saturating_sub(rent.minimum_balance(DatasetState::CLOSED_LEN));
+        let dataset_lamports = dataset_account
+            .lamports()
+            .checked_sub(refund)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let authority_lamports = authority
+            .lamports()
+            .checked_add(refund)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        **dataset_account.lamports.borrow_mut() = dataset_lamports;
+        **authority.lamports.borrow_mut() = authority_lamports;
+
+        Ok(())
+    }
+
+    fn process_propose_ownership_transfer(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        new_owner: Pubkey,
+    ) -> ProgramResult {
+        let (accounts, signers) = split_signers(accounts);
+        OwnershipTransferAccounts::ACCOUNT_COUNT.check("ProposeOwnershipTransfer", accounts)?;
+        let OwnershipTransferAccounts {
+            authority,
+            dataset_account,
+        } = OwnershipTransferAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_signed(authority, signers)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &hash,
+            authority.key,
+        )?;
+
+        // The default key packs as no pending owner
+        if new_owner == dataset.owner || new_owner == Pubkey::default() {
+            msg!("New owner must be another account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        dataset.pending_owner = Some(new_owner);
+        dataset.pack_into_slice(&mut dataset_data);
+
+        Ok(())
+    }
+
+    fn process_accept_ownership(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        let (accounts, signers) = split_signers(accounts);
+        AcceptOwnershipAccounts::ACCOUNT_COUNT.check("AcceptOwnership", accounts)?;
+        let AcceptOwnershipAccounts {
+            authority,
+            old_owner_index,
+            new_owner_index,
+            dataset_account,
+        } = AcceptOwnershipAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_signed(authority, signers)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+        if !dataset.is_initialized {
+            check_failed!(dataset_account.key, "Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        Self::assert_dataset_hash(&dataset.hash, &hash)?;
+
+        if dataset.pending_owner != Some(*authority.key) {
+            check_failed!(authority.key, "Signer is not the pending dataset owner");
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Self::assert_index(
+            &program_id,
+            old_owner_index,
+            &dataset.owner,
+            AccountType::DatasetIndex,
+        )?;
+        Self::assert_index(
+            &program_id,
+            new_owner_index,
+            authority.key,
+            AccountType::DatasetIndex,
+        )?;
+        Self::assert_rent_exempt(&Rent::get()?, new_owner_index)?;
+
+        let mut old_index_data = old_owner_index.data.borrow_mut();
+        let mut old_index = AccountIndex::unpack_from_slice(&old_index_data)?;
+        old_index.remove(dataset_account.key);
+        old_index.pack_into_slice(&mut old_index_data);
+
+        AccountIndex::append_raw(
+            &mut new_owner_index.data.borrow_mut(),
+            dataset_account.key,
+            &hash,
+        )?;
+
+        msg!(
+            "Dataset ownership moved from {} to {}",
+            dataset.owner,
+            authority.key
+        );
+        dataset.owner = *authority.key;
+        dataset.pending_owner = None;
+        dataset.pack_into_slice(&mut dataset_data);
+
+        Ok(())
+    }
+
+    fn process_cancel_ownership_transfer(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        let (accounts, signers) = split_signers(accounts);
+        OwnershipTransferAccounts::ACCOUNT_COUNT.check("CancelOwnershipTransfer", accounts)?;
+        let OwnershipTransferAccounts {
+            authority,
+            dataset_account,
+        } = OwnershipTransferAccounts::from_account_infos(accounts)?;
+
+        Self::assert_authority_signed(authority, signers)?;
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &hash,
+            authority.key,
+        )?;
+
+        if dataset.pending_owner.is_none() {
+            msg!("Dataset has no pending owner");
+            return Err(DataNexusError::NoPendingTransfer.into());
+        }
+
+        dataset.pending_owner = None;
+        dataset.pack_into_slice(&mut dataset_data);
+
+        Ok(())
+    }
+
+    fn process_crank_prune(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        holder: Pubkey,
+    ) -> ProgramResult {
+        CrankPruneAccounts::ACCOUNT_COUNT.check("CrankPrune", accounts)?;
+        let CrankPruneAccounts {
+            cranker,
+            dataset_account,
+            holder_access_account,
+        } = CrankPruneAccounts::from_account_infos(accounts)?;
+
+        if !cranker.is_signer {
+            check_failed!(cranker.key, "Missing Cranker Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let mut dataset_data = dataset_account.data.borrow_mut();
+        let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+        if !dataset.is_initialized {
+            check_failed!(dataset_account.key, "Dataset account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&dataset.hash, &hash)?;
+
+        Self::assert_associated_access_pda(
+            &program_id,
+            &holder,
+            dataset_account.key,
+            holder_access_account,
+        )?;
+        if holder_access_account.owner != &program_id {
+            check_failed!(
+                holder_access_account.key,
+                "Holder access account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        {
+            let now = Clock::get()?.unix_timestamp;
+            let mut holder_access_data = holder_access_account.data.borrow_mut();
+            let mut holder_access = AccessState::unpack_from_slice(&holder_access_data)?;
+            let index = holder_access.find_entry(&hash).ok_or_else(|| {
+                msg!("Holder has no access to the dataset");
+                DataNexusError::AccessNotFound
+            })?;
+            if !matches!(holder_access.datasets.get(index), Some(entry) if entry.is_expired(now)) {
+                msg!("Access to the dataset has not expired");
+                return Err(DataNexusError::AccessNotExpired.into());
+            }
+            holder_access.datasets.remove(index);
+            holder_access.pack_into_slice(&mut holder_access_data);
+        }
+
+        // A pool short of the bounty pays out what it has left
+        let bounty = dataset.prune_bounty.min(dataset.bounty_pool);
+        dataset.bounty_pool = dataset
+            .bounty_pool
+            .checked_sub(bounty)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        dataset.holder_count = dataset.holder_count.saturating_sub(1);
+        dataset.pack_into_slice(&mut dataset_data);
+        drop(dataset_data);
+
+        let dataset_lamports = dataset_account
+            .lamports()
+            .checked_sub(bounty)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let cranker_lamports = cranker
+            .lamports()
+            .checked_add(bounty)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        **dataset_account.lamports.borrow_mut() = dataset_lamports;
+        **cranker.lamports.borrow_mut() = cranker_lamports;
+        msg!("Pruned expired access for a bounty of {} lamports", bounty);
+
+        Ok(())
+    }
+
+    fn process_fund_bounty(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        amount: u64,
+    ) -> ProgramResult {
+        FundBountyAccounts::ACCOUNT_COUNT.check("FundBounty", accounts)?;
+        let FundBountyAccounts {
+            authority,
+            dataset_account,
+            system_program,
+        } = FundBountyAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if dataset_account.owner != &program_id {
+            check_failed!(
+                dataset_account.key,
+                "Dataset account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        {
+            let mut dataset_data = dataset_account.data.borrow_mut();
+            let mut dataset = DatasetState::unpack_from_slice(&dataset_data)?;
+            Self::assert_dataset_owner(
+                dataset.is_initialized,
+                &dataset.hash,
+                &dataset.owner,
+                &hash,
+                authority.key,
+            )?;
+            dataset.bounty_pool = dataset
+                .bounty_pool
+                .checked_add(amount)
+                .ok_or(DataNexusError::NumericalOverflow)?;
+            dataset.pack_into_slice(&mut dataset_data);
+        }
+
+        invoke(
+            &transfer(authority.key, dataset_account.key, amount),
+            &[
+                authority.clone(),
+                dataset_account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn process_create_collection(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        value: u64,
+    ) -> ProgramResult {
+        CreateCollectionAccounts::ACCOUNT_COUNT.check("CreateCollection", accounts)?;
+        let CreateCollectionAccounts {
+            authority,
+            collection_account,
+            system_program,
+        } = CreateCollectionAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        let (collection_address, bump) = find_collection_address(&program_id, &hash);
+        if collection_address != *collection_account.key {
+            check_failed!(
+                collection_account.key,
+                "Collection account does not match derived address"
+            );
+            return Err(DataNexusError::InvalidSeeds.into());
+        }
+
+        Self::create_pda_account(
+            &program_id,
+            authority,
+            collection_account,
+            system_program,
+            &Rent::get()?,
+            CollectionState::space(),
+            &[COLLECTION_MARKER, &hash],
+            bump,
+        )?;
+
+        CollectionState {
+            is_initialized: true,
+            owner: *authority.key,
+            hash,
+            value: if value == 0 { None } else { Some(value) },
+            members: Vec::new(),
+        }
+        .pack_into_slice(&mut collection_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_add_to_collection(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        collection_hash: [u8; 32],
+        dataset_hash: [u8; 32],
+    ) -> ProgramResult {
+        AddToCollectionAccounts::ACCOUNT_COUNT.check("AddToCollection", accounts)?;
+        let AddToCollectionAccounts {
+            authority,
+            collection_account,
+            dataset_account,
+        } = AddToCollectionAccounts::from_account_infos(accounts)?;
+
+        if !authority.is_signer {
+            check_failed!(authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        for account in [collection_account, dataset_account].iter() {
+            if account.owner != &program_id {
+                check_failed!(
+                    account.key,
+                    "Collection or dataset account not owned by DataNexus"
+                );
+                return Err(DataNexusError::InvalidAccountOwner.into());
+            }
+        }
+
+        let mut collection_data = collection_account.data.borrow_mut();
+        let mut collection = CollectionState::unpack_from_slice(&collection_data)?;
+        Self::assert_dataset_owner(
+            collection.is_initialized,
+            &collection.hash,
+            &collection.owner,
+            &collection_hash,
+            authority.key,
+        )?;
+
+        let dataset = DatasetState::unpack_from_slice(&dataset_account.data.borrow())?;
+        Self::assert_dataset_owner(
+            dataset.is_initialized,
+            &dataset.hash,
+            &dataset.owner,
+            &dataset_hash,
+            authority.key,
+        )?;
+
+        // Collection grants leave member datasets as they are, so they can't
+        // count holders
+        if dataset.max_holders.is_some() {
+            msg!("Datasets with a holder quota are sold on their own");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if collection.members.contains(dataset_account.key) {
+            msg!("Dataset is already in the collection");
+            return Err(DataNexusError::AlreadyInCollection.into());
+        }
+
+        if collection.members.len() >= MAX_COLLECTION_MEMBERS {
+            msg!("Collection is full");
+            return Err(DataNexusError::CollectionFull.into());
+        }
+
+        collection.members.push(*dataset_account.key);
+        collection.pack_into_slice(&mut collection_data);
+
+        Ok(())
+    }
+
+    /// `PurchaseAccess` given a collection, pays the collection owner and
+    /// grants the members passed along
+    fn process_purchase_collection(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+        amount: u64,
+    ) -> ProgramResult {
+        PurchaseCollectionAccounts::ACCOUNT_COUNT.check("PurchaseAccess", accounts)?;
+        let PurchaseCollectionAccounts {
+            user_authority,
+            user_access_index,
+            collection_access_account,
+            user_token_account,
+            owner_token_account,
+            token_mint,
+            collection_account,
+            token_program,
+            members,
+            ..
+        } = PurchaseCollectionAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if collection_account.owner != &program_id {
+            check_failed!(
+                collection_account.key,
+                "Collection account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let collection = CollectionState::unpack_from_slice(&collection_account.data.borrow())?;
+        if !collection.is_initialized() {
+            check_failed!(collection_account.key, "Collection account not initialized");
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+        Self::assert_dataset_hash(&collection.hash, &hash)?;
+        if *user_authority.key == collection.owner {
+            check_failed!(user_authority.key, "Collection owner cannot purchase it");
+            return Err(DataNexusError::OwnerCannotPurchase.into());
+        }
+
+        let mut collection_access = Self::collection_access(
+            &program_id,
+            user_authority,
+            collection_access_account,
+            collection_account,
+        )?;
+        if collection_access.purchased {
+            msg!("Collection has already been purchased");
+            return Err(DataNexusError::AccessAlreadyGranted.into());
+        }
+
+        match collection.value {
+            Some(value) if amount >= value => {}
+            Some(_) => {
+                msg!("Payment is below the collection value");
+                return Err(DataNexusError::InsufficientPayment.into());
+            }
+            None => {
+                msg!("Collection has no value set");
+                return Err(DataNexusError::DatasetNotPriced.into());
+            }
+        }
+
+        let payee = {
+            let owner_token_data = owner_token_account.data.borrow();
+            StateWithExtensions::<Account>::unpack(&owner_token_data)?
+                .base
+                .owner
+        };
+        if payee != collection.owner {
+            check_failed!(
+                owner_token_account.key,
+                "Owner token account does not belong to the collection owner"
+            );
+            return Err(DataNexusError::IncorrectOwner.into());
+        }
+
+        Self::transfer_tokens(
+            token_program,
+            user_token_account,
+            token_mint,
+            owner_token_account,
+            user_authority,
+            amount,
+            &[],
+        )?;
+
+        collection_access.purchased = true;
+        Self::grant_collection_members(
+            &program_id,
+            user_authority,
+            user_access_index,
+            &collection,
+            &mut collection_access,
+            &members,
+        )?;
+        collection_access.pack_into_slice(&mut collection_access_account.data.borrow_mut());
+
+        DataNexusEvent::Purchase(PurchaseEvent {
+            dataset: *collection_account.key,
+            buyer: *user_authority.key,
+            amount,
+            mint: *token_mint.key,
+            slot: Clock::get()?.slot,
+        })
+        .emit();
+
+        Ok(())
+    }
+
+    fn process_claim_collection_access(
+        program_id: Pubkey,
+        accounts: &[AccountInfo],
+        hash: [u8; 32],
+    ) -> ProgramResult {
+        ClaimCollectionAccessAccounts::ACCOUNT_COUNT.check("ClaimCollectionAccess", accounts)?;
+        let ClaimCollectionAccessAccounts {
+            user_authority,
+            user_access_index,
+            collection_access_account,
+            collection_account,
+            members,
+        } = ClaimCollectionAccessAccounts::from_account_infos(accounts)?;
+
+        if !user_authority.is_signer {
+            check_failed!(user_authority.key, "Missing Authority Signature");
+            return Err(DataNexusError::MissingSigner.into());
+        }
+
+        if collection_account.owner != &program_id {
+            check_failed!(
+                collection_account.key,
+                "Collection account not owned by DataNexus"
+            );
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let collection = CollectionState::unpack_from_slice(&collection_account.data.borrow())?;
+        Self::assert_dataset_hash(&collection.hash, &hash)?;
+
+        let mut collection_access = Self::collection_access(
+            &program_id,
+            user_authority,
+            collection_access_account,
+            collection_account,
+        )?;
+        if !collection_access.purchased {
+            msg!("Collection has not been purchased");
+            return Err(DataNexusError::NoExistingAccess.into());
+        }
+
+        Self::grant_collection_members(
+            &program_id,
+            user_authority,
+            user_access_index,
+            &collection,
+            &mut collection_access,
+            &members,
+        )?;
+        collection_access.pack_into_slice(&mut collection_access_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    /// Unpacks the collection access account of `authority`, checking it is
+    /// the one derived for the collection
+    fn collection_access(
+        program_id: &Pubkey,
+        authority: &AccountInfo,
+        collection_access_account: &AccountInfo,
+        collection_account: &AccountInfo,
+    ) -> Result<CollectionAccess, ProgramError> {
+        Self::assert_associated_access_pda(
+            program_id,
+            authority.key,
+            collection_account.key,
+            collection_access_account,
+        )?;
+
+        if collection_access_account.owner != program_id {
+            check_failed!(
+                collection_access_account.key,
+                "Collection access account not initialized"
+            );
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        let collection_access =
+            CollectionAccess::unpack_from_slice(&collection_access_account.data.borrow())?;
+        if !collection_access.is_initialized() {
+            check_failed!(
+                collection_access_account.key,
+                "Collection access account not initialized"
+            );
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        Ok(collection_access)
+    }
+
+    /// Grants `members` in collection order from the cursor on, each to the
+    /// buyer's associated access account for that member
+    ///
+    /// Members closed since they were added are passed over. Grants the
+    /// buyer already holds are kept as they are
+    fn grant_collection_members(
+        program_id: &Pubkey,
+        user_authority: &AccountInfo,
+        user_access_index: &AccountInfo,
+        collection: &CollectionState,
+        collection_access: &mut CollectionAccess,
+        members: &[MemberAccounts<&AccountInfo>],
+    ) -> ProgramResult {
+        if members.len() > MAX_COLLECTION_GRANTS {
+            msg!(
+                "At most {} members can be granted at once",
+                MAX_COLLECTION_GRANTS
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if !members.is_empty() && user_access_index.owner != program_id {
+            check_failed!(user_access_index.key, "Access index not owned by DataNexus");
+            return Err(DataNexusError::InvalidAccountOwner.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        for member in members.iter() {
+            let expected = collection
+                .members
+                .get(usize::from(collection_access.cursor))
+                .ok_or(DataNexusError::CollectionMemberMismatch)?;
+            if member.dataset_account.key != expected {
+                msg!("Expected collection member {}", expected);
+                return Err(DataNexusError::CollectionMemberMismatch.into());
+            }
+            collection_access.cursor = collection_access
+                .cursor
+                .checked_add(1)
+                .ok_or(DataNexusError::NumericalOverflow)?;
+
+            // Closed datasets are left as tombstones
+            if member.dataset_account.owner != program_id
+                || DatasetState::is_closed(&member.dataset_account.data.borrow())
+            {
+                msg!("Skipping closed member {}", expected);
+                continue;
+            }
+            let dataset = DatasetState::unpack_from_slice(&member.dataset_account.data.borrow())?;
+            if !dataset.is_initialized() {
+                msg!("Skipping closed member {}", expected);
+                continue;
+            }
+            if dataset.max_holders.is_some() {
+                msg!("Skipping member {} with a holder quota", expected);
+                continue;
+            }
+
+            Self::assert_associated_access_pda(
+                program_id,
+                user_authority.key,
+                expected,
+                member.access_account,
+            )?;
+
+            let mut access_data = member.access_account.data.borrow_mut();
+            let mut access = AccessState::unpack_from_slice(&access_data)?;
+            if member.access_account.owner != program_id || !access.is_initialized() {
+                check_failed!(
+                    member.access_account.key,
+                    "Associated access account not initialized"
+                );
+                return Err(DataNexusError::AccountNotInitialized.into());
+            }
+
+            let existing_entry = access.find_entry(&dataset.hash);
+            if let Some(index) = existing_entry {
+                let granted = access.datasets.get(index);
+                if granted.is_some_and(|granted| !granted.is_expired(now)) {
+                    continue;
+                }
+            } else if access.datasets.len() >= MAX_ACCESS_ENTRIES {
+                msg!("Associated access account is full");
+                return Err(DataNexusError::IndexFull.into());
+            }
+
+            let expires_at = match dataset.access_duration {
+                Some(duration) => Some(
+                    now.checked_add(duration)
+                        .ok_or(DataNexusError::NumericalOverflow)?,
+                ),
+                None => None,
+            };
+            let grant = AccessInfo {
+                hash: dataset.hash,
+                key: dataset.key,
+                shared_from: None,
+                share_quota: dataset.share_limit.unwrap_or(0),
+                expires_at,
+                depth: 0,
+                shares_used: 0,
+            };
+            match existing_entry.and_then(|index| access.datasets.get_mut(index)) {
+                Some(entry) => *entry = grant,
+                None => access.datasets.push(grant),
+            }
+            access.pack_into_slice(&mut access_data);
+
+            AccountIndex::append_raw(
+                &mut user_access_index.data.borrow_mut(),
+                expected,
+                &dataset.hash,
+            )?;
+        }
 
         Ok(())
     }
@@ -0,0 +1,205 @@
+//! Field formats for the `serde` derives of the public types, which keep
+//! their JSON readable and stable: pubkeys as base58 strings, hashes and
+//! keys as lowercase hex and null padded text as the string before the
+//! padding
+//!
+//! Used through `#[serde(with = "...")]`, `Option` fields pair the `option`
+//! module of a format with `skip_serializing_if` so `None` is left out.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use solana_program::pubkey::Pubkey;
+use std::{convert::TryInto, str::FromStr};
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<E: Error, const N: usize>(hex: &str) -> Result<[u8; N], E> {
+    let bytes = hex
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [_, _] => std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok()),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| E::custom(format!("invalid hex string {}", hex)))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| E::invalid_length(len, &"as many bytes as the field holds"))
+}
+
+fn to_padded<E: Error, const N: usize>(text: &str) -> Result<[u8; N], E> {
+    let mut padded = [0u8; N];
+    padded
+        .get_mut(..text.len())
+        .ok_or_else(|| E::invalid_length(text.len(), &"at most as many bytes as the field holds"))?
+        .copy_from_slice(text.as_bytes());
+    Ok(padded)
+}
+
+fn from_padded(bytes: &[u8]) -> String {
+    let text = bytes.split(|b| *b == 0).next().unwrap_or_default();
+    String::from_utf8_lossy(text).into_owned()
+}
+
+/// `Pubkey` as its base58 string
+pub mod pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let pubkey = String::deserialize(deserializer)?;
+        Pubkey::from_str(&pubkey).map_err(|e| D::Error::custom(format!("{}: {}", e, pubkey)))
+    }
+
+    /// `Option<Pubkey>` as a base58 string when set
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            pubkey: &Option<Pubkey>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match pubkey {
+                Some(pubkey) => serializer.collect_str(pubkey),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Pubkey>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|pubkey| {
+                    Pubkey::from_str(&pubkey)
+                        .map_err(|e| D::Error::custom(format!("{}: {}", e, pubkey)))
+                })
+                .transpose()
+        }
+    }
+
+    /// `Vec<Pubkey>` as base58 strings
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            pubkeys: &[Pubkey],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(pubkeys.iter().map(Pubkey::to_string))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Pubkey>, D::Error> {
+            Vec::<String>::deserialize(deserializer)?
+                .iter()
+                .map(|pubkey| {
+                    Pubkey::from_str(pubkey)
+                        .map_err(|e| D::Error::custom(format!("{}: {}", e, pubkey)))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Byte array such as a hash or key as lowercase hex
+pub mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        from_hex(&String::deserialize(deserializer)?)
+    }
+
+    /// Optional byte array as lowercase hex when set
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer, const N: usize>(
+            bytes: &Option<[u8; N]>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => serializer.serialize_str(&to_hex(bytes)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+            deserializer: D,
+        ) -> Result<Option<[u8; N]>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|hex| from_hex(&hex))
+                .transpose()
+        }
+    }
+}
+
+/// Null padded text as the string before its padding, bytes that aren't
+/// UTF-8 are replaced
+pub mod padded_str {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&from_padded(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        to_padded(&String::deserialize(deserializer)?)
+    }
+
+    /// Null padded texts such as tags as strings
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<S: Serializer, const N: usize>(
+            texts: &[[u8; N]],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(texts.iter().map(|text| from_padded(text)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+            deserializer: D,
+        ) -> Result<Vec<[u8; N]>, D::Error> {
+            Vec::<String>::deserialize(deserializer)?
+                .iter()
+                .map(|text| to_padded(text))
+                .collect()
+        }
+    }
+}
+
+/// Unpadded text held as bytes, such as a URI being set, as a string
+pub mod byte_str {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        Ok(String::deserialize(deserializer)?.into_bytes())
+    }
+}
@@ -4,19 +4,534 @@ use solana_program::{
     pubkey::Pubkey,
 };
 
-use crate::error::DataNexusError::InvalidInstruction;
+use crate::error::DataNexusError;
 
-use std::convert::TryInto;
+#[cfg(feature = "borsh-encoding")]
+use borsh::{BorshDeserialize, BorshSerialize};
 
+#[cfg(feature = "serde")]
+use crate::serde_fields;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    convert::{TryFrom, TryInto},
+    slice::SliceIndex,
+};
+
+/// Denominator for basis point fields
+pub const MAX_BPS: u16 = 10_000;
+
+/// Length of the null-padded metadata URI
+pub const URI_LEN: usize = 128;
+
+/// Length of the null-padded MIME content type
+pub const CONTENT_TYPE_LEN: usize = 32;
+
+/// Byte of `src` at `offset`, `InvalidAccountData` past its end
+pub(crate) fn read_u8(src: &[u8], offset: usize) -> Result<u8, ProgramError> {
+    src.get(offset)
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// The `N` bytes of `src` from `offset`, `InvalidAccountData` when `src`
+/// ends before them
+pub(crate) fn read_array<const N: usize>(
+    src: &[u8],
+    offset: usize,
+) -> Result<[u8; N], ProgramError> {
+    offset
+        .checked_add(N)
+        .and_then(|end| src.get(offset..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// The bytes of `src` in `range`, `InvalidAccountData` when `src` ends
+/// before them
+pub(crate) fn read_slice<R: SliceIndex<[u8], Output = [u8]>>(
+    src: &[u8],
+    range: R,
+) -> Result<&[u8], ProgramError> {
+    src.get(range).ok_or(ProgramError::InvalidAccountData)
+}
+
+/// A flag byte of `src`, `InvalidAccountData` unless it is 0 or 1
+fn read_bool(src: &[u8], offset: usize) -> Result<bool, ProgramError> {
+    match read_u8(src, offset)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// The 32 bytes of `src` from `offset`, `None` when all-zero
+fn read_optional_key(src: &[u8], offset: usize) -> Result<Option<[u8; 32]>, ProgramError> {
+    match read_array(src, offset)? {
+        n if n == [0u8; 32] => Ok(None),
+        n => Ok(Some(n)),
+    }
+}
+
+/// Copies `bytes` into `dst` from `offset`
+///
+/// Packing is only handed buffers of the packed length, checked by
+/// `Pack::pack` or by unpacking the same account first, and leaves out a
+/// field that doesn't fit rather than panicking.
+pub(crate) fn write_bytes(dst: &mut [u8], offset: usize, bytes: &[u8]) {
+    if let Some(dst) = offset
+        .checked_add(bytes.len())
+        .and_then(|end| dst.get_mut(offset..end))
+    {
+        dst.copy_from_slice(bytes);
+    }
+}
+
+/// Zeroes `dst` from `offset` to its end
+fn clear_from(dst: &mut [u8], offset: usize) {
+    if let Some(dst) = dst.get_mut(offset..) {
+        dst.fill(0);
+    }
+}
+
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AccountFlag {
     Access,
     Dataset,
+    Collection,
+    Registry,
 }
 
+/// Number of entries an `AccountIndex` holds when created, `ResizeIndex`
+/// grows it beyond that
+pub const MAX_INDEX_ENTRIES: usize = 128;
+
+/// Layout version in the first byte of every `AccountIndex`
+pub const INDEX_LAYOUT_VERSION: u8 = 2;
+
+/// Dataset recorded in an `AccountIndex` together with its hash
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexEntry {
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub dataset: Pubkey,
+    /// All-zero for entries decoded from the legacy layout, which only
+    /// recorded dataset keys
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
+    pub hash: [u8; 32],
+}
+
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AccountIndex {
     pub is_initialized: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
     pub pointer: Option<Pubkey>,
-    pub datasets: [Option<Pubkey>; 128],
+    /// Whether shares to the authority wait for it to accept them, set on
+    /// access indexes with `SetAccessPreferences`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub require_share_approval: bool,
+    /// One slot per entry the index has capacity for
+    pub datasets: Vec<Option<IndexEntry>>,
+}
+
+/// Byte offsets of one version of the packed `AccountIndex` layout
+struct IndexLayout {
+    /// Version byte written first, 0 for the legacy layout which has none
+    version: u8,
+    is_initialized: usize,
+    pointer: usize,
+    /// Offset of the `u16` entry count, without one entries may sit in any
+    /// slot and free slots are all-zero
+    count: Option<usize>,
+    /// Offset of the `u16` slot capacity, without one an index holds
+    /// `MAX_INDEX_ENTRIES` slots
+    capacity: Option<usize>,
+    /// Offset of the `require_share_approval` byte, without one shares are
+    /// never held for approval
+    preferences: Option<usize>,
+    entries: usize,
+    has_hashes: bool,
+    entry_len: usize,
+    /// Size of an index in this layout holding `MAX_INDEX_ENTRIES` slots
+    len: usize,
+}
+
+impl IndexLayout {
+    /// Dataset keys only, written before the layout version byte existed
+    const LEGACY: Self = IndexLayout {
+        version: 0,
+        is_initialized: 0,
+        pointer: 1,
+        count: None,
+        capacity: None,
+        preferences: None,
+        entries: 33,
+        has_hashes: false,
+        entry_len: 32,
+        len: 33 + 32 * MAX_INDEX_ENTRIES,
+    };
+
+    /// Version 1, entries are contiguous and each is a dataset key followed
+    /// by its hash
+    const V1: Self = IndexLayout {
+        version: 1,
+        is_initialized: 1,
+        pointer: 2,
+        count: Some(34),
+        capacity: Some(36),
+        preferences: None,
+        entries: 38,
+        has_hashes: true,
+        entry_len: 64,
+        len: 38 + 64 * MAX_INDEX_ENTRIES,
+    };
+
+    /// `INDEX_LAYOUT_VERSION`, version 1 with the authority's preferences
+    /// ahead of the entries
+    const CURRENT: Self = IndexLayout {
+        version: INDEX_LAYOUT_VERSION,
+        is_initialized: 1,
+        pointer: 2,
+        count: Some(34),
+        capacity: Some(36),
+        preferences: Some(38),
+        entries: 39,
+        has_hashes: true,
+        entry_len: 64,
+        len: 39 + 64 * MAX_INDEX_ENTRIES,
+    };
+
+    /// Layout of the packed index in `data`
+    ///
+    /// Legacy accounts have no version byte and are told apart by length
+    fn of(data: &[u8]) -> Result<&'static Self, ProgramError> {
+        if data.len() == Self::LEGACY.len {
+            return Ok(&Self::LEGACY);
+        }
+
+        let layout = match data.first() {
+            Some(1) => &Self::V1,
+            Some(&INDEX_LAYOUT_VERSION) => &Self::CURRENT,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if data.len() >= layout.entries
+            && layout
+                .capacity(data)
+                .is_ok_and(|capacity| layout.fits(data, capacity))
+        {
+            Ok(layout)
+        } else {
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+
+    /// Bytes an index in this layout holding `capacity` slots takes up
+    fn space(&self, capacity: usize) -> Result<usize, ProgramError> {
+        let len = capacity
+            .checked_mul(self.entry_len)
+            .and_then(|len| len.checked_add(self.entries))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+
+        Ok(len)
+    }
+
+    /// Slots the index in `data` holds
+    fn capacity(&self, data: &[u8]) -> Result<usize, ProgramError> {
+        match self.capacity {
+            Some(offset) => Ok(usize::from(u16::from_le_bytes(read_array(data, offset)?))),
+            None => Ok(MAX_INDEX_ENTRIES),
+        }
+    }
+
+    /// Whether `data` is long enough to hold `capacity` slots
+    fn fits(&self, data: &[u8], capacity: usize) -> bool {
+        let end = capacity
+            .checked_mul(self.entry_len)
+            .and_then(|len| len.checked_add(self.entries));
+        match end {
+            Some(end) => end <= data.len(),
+            None => false,
+        }
+    }
+
+    /// Slots that may hold an entry in `data`
+    fn slots<'a>(&self, data: &'a [u8]) -> Result<impl Iterator<Item = &'a [u8]>, ProgramError> {
+        let count = match self.count {
+            Some(offset) => usize::from(u16::from_le_bytes(read_array(data, offset)?)),
+            None => MAX_INDEX_ENTRIES,
+        };
+
+        Ok(read_slice(data, self.entries..)?
+            .chunks_exact(self.entry_len)
+            .take(count.min(self.capacity(data)?)))
+    }
+
+    /// Slot holding `key` in `data`
+    fn position(&self, data: &[u8], key: &Pubkey) -> Result<Option<usize>, ProgramError> {
+        Ok(self
+            .slots(data)?
+            .position(|entry| entry.starts_with(key.as_ref())))
+    }
+
+    /// Slot the next entry is written to, `None` when `data` is full
+    fn free_slot(&self, data: &[u8]) -> Result<Option<usize>, ProgramError> {
+        let slot = match self.count {
+            Some(_) => self.slots(data)?.count(),
+            None => match self
+                .slots(data)?
+                .position(|entry| entry.starts_with(&[0u8; 32]))
+            {
+                Some(slot) => slot,
+                None => return Ok(None),
+            },
+        };
+
+        let capacity = self.capacity(data)?;
+        Ok(Some(slot).filter(|slot| *slot < capacity))
+    }
+
+    fn write_entry(&self, data: &mut [u8], slot: usize, entry: &IndexEntry) {
+        let offset = slot
+            .checked_mul(self.entry_len)
+            .and_then(|offset| offset.checked_add(self.entries));
+        if let Some(offset) = offset {
+            write_bytes(data, offset, entry.dataset.as_ref());
+            if self.has_hashes {
+                write_bytes(data, offset.saturating_add(32), &entry.hash);
+            }
+        }
+    }
+
+    fn set_count(&self, data: &mut [u8], count: usize) {
+        if let Some(offset) = self.count {
+            write_bytes(data, offset, &(count as u16).to_le_bytes());
+        }
+    }
+
+    fn set_capacity(&self, data: &mut [u8], capacity: usize) {
+        if let Some(offset) = self.capacity {
+            write_bytes(data, offset, &(capacity as u16).to_le_bytes());
+        }
+    }
+}
+
+impl AccountIndex {
+    /// Size of indexes created before `INDEX_LAYOUT_VERSION`, which can
+    /// still be read and written but record no hashes
+    pub const LEGACY_LEN: usize = IndexLayout::LEGACY.len;
+
+    /// Bytes each slot adds to an index
+    pub const ENTRY_LEN: usize = IndexLayout::CURRENT.entry_len;
+
+    /// Layout version of the packed index in `data`, 0 for the legacy
+    /// layout, `None` when it is not an index
+    pub fn layout_version(data: &[u8]) -> Option<u8> {
+        IndexLayout::of(data).ok().map(|layout| layout.version)
+    }
+
+    /// Bytes an owner or access index holding `capacity` slots takes up,
+    /// as created by `InitAccount` and grown by `ResizeIndex`
+    pub fn space(capacity: usize) -> Result<usize, ProgramError> {
+        IndexLayout::CURRENT.space(capacity)
+    }
+
+    /// Bytes the packed index in `data` takes up once it holds `capacity`
+    /// slots, in the layout it is already in
+    pub fn space_raw(data: &[u8], capacity: usize) -> Result<usize, ProgramError> {
+        IndexLayout::of(data)?.space(capacity)
+    }
+
+    /// Slots the index holds
+    pub fn capacity(&self) -> usize {
+        self.datasets.len()
+    }
+
+    /// Records `dataset` and its `hash` in the first free slot within the
+    /// index's capacity, returning the slot used
+    ///
+    /// Datasets already present are not inserted twice, their existing slot
+    /// is returned instead
+    pub fn insert(&mut self, dataset: Pubkey, hash: [u8; 32]) -> Result<usize, DataNexusError> {
+        if let Some(slot) = self.position(&dataset) {
+            return Ok(slot);
+        }
+
+        let (slot, entry) = self
+            .datasets
+            .iter_mut()
+            .enumerate()
+            .find(|(_, entry)| entry.is_none())
+            .ok_or(DataNexusError::IndexFull)?;
+        *entry = Some(IndexEntry { dataset, hash });
+
+        Ok(slot)
+    }
+
+    /// Clears the slot holding `key`, returning the slot cleared
+    pub fn remove(&mut self, key: &Pubkey) -> Option<usize> {
+        let slot = self.position(key)?;
+        *self.datasets.get_mut(slot)? = None;
+
+        Some(slot)
+    }
+
+    /// Slot holding `key`
+    pub fn position(&self, key: &Pubkey) -> Option<usize> {
+        self.datasets
+            .iter()
+            .position(|entry| entry.map(|entry| entry.dataset) == Some(*key))
+    }
+
+    /// Dataset recorded with `hash`
+    pub fn find_by_hash(&self, hash: &[u8; 32]) -> Option<Pubkey> {
+        self.datasets
+            .iter()
+            .flatten()
+            .find(|entry| entry.hash == *hash)
+            .map(|entry| entry.dataset)
+    }
+
+    /// Appends `key` and its `hash` to the packed index in `data` without
+    /// unpacking it, returning the slot used
+    ///
+    /// Like `insert`, keys already present keep their existing slot. The
+    /// hash is dropped for legacy indexes
+    pub fn append_raw(
+        data: &mut [u8],
+        key: &Pubkey,
+        hash: &[u8; 32],
+    ) -> Result<usize, ProgramError> {
+        let layout = IndexLayout::of(data)?;
+
+        if read_u8(data, layout.is_initialized)? != 1 {
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        if let Some(slot) = layout.position(data, key)? {
+            return Ok(slot);
+        }
+
+        let slot = layout.free_slot(data)?.ok_or(DataNexusError::IndexFull)?;
+        let entry = IndexEntry {
+            dataset: *key,
+            hash: *hash,
+        };
+        layout.write_entry(data, slot, &entry);
+        let count = slot
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        layout.set_count(data, count);
+
+        Ok(slot)
+    }
+
+    /// Whether the packed index in `data` holds `key`, without unpacking it
+    pub fn contains_raw(data: &[u8], key: &Pubkey) -> bool {
+        match IndexLayout::of(data).and_then(|layout| layout.position(data, key)) {
+            Ok(slot) => slot.is_some(),
+            Err(_) => false,
+        }
+    }
+
+    /// Moves the entries of the packed index in `data` ahead of the free
+    /// slots left between them, keeping their order, and returns how many
+    /// there are
+    ///
+    /// Works on the bytes in place so even large indexes compact within the
+    /// compute budget. The freed slots are zeroed and the count updated
+    pub fn compact_raw(data: &mut [u8]) -> Result<usize, ProgramError> {
+        let layout = IndexLayout::of(data)?;
+
+        if read_u8(data, layout.is_initialized)? != 1 {
+            return Err(DataNexusError::AccountNotInitialized.into());
+        }
+
+        let slots = layout.slots(data)?.count();
+        let end = slots
+            .checked_mul(layout.entry_len)
+            .and_then(|len| len.checked_add(layout.entries))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+        let entries = data
+            .get_mut(layout.entries..end)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        // Every slot lies within `entries`, so the offsets can't overflow
+        let mut kept = 0usize;
+        for slot in 0..slots {
+            let offset = slot.saturating_mul(layout.entry_len);
+            if read_optional_key(entries, offset)?.is_none() {
+                continue;
+            }
+            if kept != slot {
+                entries.copy_within(
+                    offset..offset.saturating_add(layout.entry_len),
+                    kept.saturating_mul(layout.entry_len),
+                );
+            }
+            kept = kept.saturating_add(1);
+        }
+        clear_from(entries, kept.saturating_mul(layout.entry_len));
+        layout.set_count(data, kept);
+
+        Ok(kept)
+    }
+
+    /// Slots the packed index in `data` holds, without unpacking it
+    pub fn capacity_raw(data: &[u8]) -> Result<usize, ProgramError> {
+        IndexLayout::of(data)?.capacity(data)
+    }
+
+    /// Records `capacity` in the header of the packed index in `data`, which
+    /// must already be long enough to hold that many slots
+    ///
+    /// Legacy indexes have no capacity field and cannot be resized
+    pub fn set_capacity_raw(data: &mut [u8], capacity: u16) -> Result<(), ProgramError> {
+        let layout = IndexLayout::of(data)?;
+        if layout.capacity.is_none() || !layout.fits(data, capacity.into()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        layout.set_capacity(data, capacity.into());
+
+        Ok(())
+    }
+
+    /// Whether the authority of the packed index in `data` holds shares for
+    /// approval, without unpacking it. Indexes of older layouts never do
+    pub fn require_share_approval_raw(data: &[u8]) -> Result<bool, ProgramError> {
+        match IndexLayout::of(data)?.preferences {
+            Some(offset) => read_bool(data, offset),
+            None => Ok(false),
+        }
+    }
+
+    /// Records whether the authority of the packed index in `data` holds
+    /// shares for approval
+    ///
+    /// Indexes of older layouts fail with `OutdatedLayout` until
+    /// `MigrateAccount` has rewritten them
+    pub fn set_require_share_approval_raw(
+        data: &mut [u8],
+        require_share_approval: bool,
+    ) -> Result<(), ProgramError> {
+        let offset = IndexLayout::of(data)?
+            .preferences
+            .ok_or(DataNexusError::OutdatedLayout)?;
+        write_bytes(data, offset, &[require_share_approval as u8]);
+
+        Ok(())
+    }
 }
 
 impl IsInitialized for AccountIndex {
@@ -28,109 +543,738 @@ impl IsInitialized for AccountIndex {
 impl Sealed for AccountIndex {}
 
 impl Pack for AccountIndex {
-    const LEN: usize = 33 + 4096;
+    const LEN: usize = IndexLayout::CURRENT.len;
 
+    /// Entries are written contiguously, so any slots freed by `remove` are
+    /// compacted away. Legacy and version 1 sized accounts keep their layout
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        dst[0] = self.is_initialized as u8;
-        dst[1..33].copy_from_slice(match self.pointer {
-            Some(n) => n.to_bytes(),
-            None => [0u8; 32],
-        });
-        dst[33..Self::LEN].copy_from_slice({
-            let dataset_vec = Vec::with_capacity(Self::LEN - 33);
-            for dataset in self.datasets {
-                dataset_vec.push(match dataset {
-                    Some(n) => n.to_bytes(),
-                    None => [0u8; 32],
-                })
-            }
-            dataset_vec
-        });
+        let layout = if dst.len() == Self::LEGACY_LEN {
+            &IndexLayout::LEGACY
+        } else if IndexLayout::V1.space(self.capacity()).ok() == Some(dst.len()) {
+            &IndexLayout::V1
+        } else {
+            &IndexLayout::CURRENT
+        };
+
+        if layout.version != 0 {
+            write_bytes(dst, 0, &[layout.version]);
+        }
+        write_bytes(dst, layout.is_initialized, &[self.is_initialized as u8]);
+        write_bytes(
+            dst,
+            layout.pointer,
+            match self.pointer {
+                Some(ref n) => n.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        clear_from(dst, layout.entries);
+        for (slot, entry) in self.datasets.iter().flatten().enumerate() {
+            layout.write_entry(dst, slot, entry);
+        }
+        layout.set_count(dst, self.datasets.iter().flatten().count());
+        layout.set_capacity(dst, self.capacity());
+        if let Some(offset) = layout.preferences {
+            write_bytes(dst, offset, &[self.require_share_approval as u8]);
+        }
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let is_initialized = match src[0] {
-            0 => false,
-            1 => true,
-            _ => return Err(ProgramError::InvalidAccountData),
-        };
-        let pointer = match src[1..33].try_into().unwrap() {
-            x if x = [0u8; 32] => None,
-            _ => Some(Pubkey::new_from_array(src[1..33].try_into().unwrap())),
-        };
-        let datasets = {
-            let dataset_vec = Vec::with_capacity(Self::LEN - 33);
-            let mut offset = 33;
-            while offset < Self::LEN {
-                dataset_vec.push(match src[offset..offset + 32].try_into().ok() {
-                    x if x = [0u8; 32] => None,
-                    _ => Some(Pubkey::new_from_array(
-                        src[offset..offset + 32].try_into().unwrap(),
-                    )),
-                });
-                offset += 32;
-            }
-            dataset_vec.collect()
+        let layout = IndexLayout::of(src)?;
+
+        let is_initialized = read_bool(src, layout.is_initialized)?;
+        let pointer = read_optional_key(src, layout.pointer)?.map(Pubkey::new_from_array);
+        let require_share_approval = match layout.preferences {
+            Some(offset) => read_bool(src, offset)?,
+            None => false,
         };
+        let mut datasets = vec![None; layout.capacity(src)?];
+        for (dataset, slot) in datasets.iter_mut().zip(layout.slots(src)?) {
+            let key = match read_optional_key(slot, 0)? {
+                Some(key) => key,
+                None => continue,
+            };
+
+            *dataset = Some(IndexEntry {
+                dataset: Pubkey::new_from_array(key),
+                hash: if layout.has_hashes {
+                    read_array(slot, 32)?
+                } else {
+                    [0u8; 32]
+                },
+            });
+        }
 
         Ok(Self {
             is_initialized,
             pointer,
+            require_share_approval,
             datasets,
         })
     }
 }
 
-pub struct AccountState {
+/// Maximum number of `PriceTier` entries a dataset holds
+pub const MAX_PRICE_TIERS: usize = 4;
+
+/// Volume price of a dataset, purchases of at least `min_seats` seats pay
+/// `unit_price` per seat
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PriceTier {
+    pub min_seats: u16,
+    pub unit_price: u64,
+}
+
+impl PriceTier {
+    pub const LEN: usize = 10;
+}
+
+/// Maximum number of mints a dataset holds a vault of
+pub const MAX_VAULTS: usize = 4;
+
+/// Purchase proceeds held in the dataset's vault of `mint` and not yet
+/// withdrawn, in base units of the mint
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Claimable {
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl Claimable {
+    pub const LEN: usize = 40;
+}
+
+/// Maximum number of mints a dataset is priced in
+pub const MAX_PRICES: usize = 4;
+
+/// Price of the dataset when paid in `mint`, in base units of the mint
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MintPrice {
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl MintPrice {
+    pub const LEN: usize = 40;
+}
+
+/// Maximum number of tags a dataset is labelled with
+pub const MAX_TAGS: usize = 8;
+
+/// Size of a tag, shorter ones are null padded
+pub const TAG_LEN: usize = 16;
+
+/// Label set on a dataset to find it by, null padded
+pub type Tag = [u8; TAG_LEN];
+
+/// Packs `name` into a tag, `None` when it is empty, longer than `TAG_LEN`
+/// bytes or holds a null byte
+pub fn pack_tag(name: &str) -> Option<Tag> {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes.len() > TAG_LEN || bytes.contains(&0) {
+        return None;
+    }
+
+    let mut tag = [0u8; TAG_LEN];
+    write_bytes(&mut tag, 0, bytes);
+    Some(tag)
+}
+
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DatasetState {
     pub is_initialized: bool,
     pub flag: AccountFlag,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
     pub owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
     pub hash: [u8; 32],
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::hex::option"
+        )
+    )]
     pub key: Option<[u8; 32]>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub value: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub share_limit: Option<u16>,
+    pub purchase_count: u32,
+    pub referral_bps: u16,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub access_duration: Option<i64>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::padded_str"))]
+    pub uri: [u8; URI_LEN],
+    pub size_bytes: u64,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::padded_str"))]
+    pub content_type: [u8; CONTENT_TYPE_LEN],
+    pub total_revenue: u64,
+    pub share_count: u32,
+    /// Dataset this one is derived from
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
+    pub ref_data: Option<Pubkey>,
+    /// Whether shared grants may be shared on, each recipient getting the
+    /// dataset's share limit
+    pub resharable: bool,
+    /// Hops a grant may be shared on from its purchaser, unbounded when unset
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_share_depth: Option<u8>,
+    /// Volume prices for `PurchaseSeats`, ordered by `min_seats`
+    pub tiers: Vec<PriceTier>,
+    /// Proceeds of every mint the owner has created a vault of, in the
+    /// order the vaults were created
+    pub claimable: Vec<Claimable>,
+    /// Authorities holding access, bought or shared, until revoked
+    pub holder_count: u32,
+    /// Holders the dataset admits, unlimited when unset
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_holders: Option<u32>,
+    /// Authority proposed as the next owner, who becomes it by accepting
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
+    pub pending_owner: Option<Pubkey>,
+    /// Price in each mint the dataset is sold for, in the order they were
+    /// set. `value` is charged in any mint while it is empty
+    pub prices: Vec<MintPrice>,
+    /// Unix timestamp purchases open at, on sale from creation when unset
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub sale_start: Option<i64>,
+    /// Unix timestamp purchases close at, on sale indefinitely when unset
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub sale_end: Option<i64>,
+    /// Lamports paid out of `bounty_pool` to whoever prunes an expired entry
+    pub prune_bounty: u64,
+    /// Lamports funded by the owner for prune bounties, held in the dataset
+    /// account on top of its rent
+    pub bounty_pool: u64,
+    /// Fee a holder pays the owner for each share, sharing is free when unset
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub share_fee: Option<MintPrice>,
+    /// Labels the dataset is found by, in the order they were added
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::padded_str::vec"))]
+    pub tags: Vec<Tag>,
+    /// Basis points off the price for buyers holding access to `ref_data`
+    pub derived_discount_bps: u16,
+    /// Receipts minted to holders and not yet burned, `RevokeAccess` needs
+    /// the receipt accounts while any are
+    pub receipt_count: u32,
+}
+
+impl DatasetState {
+    /// Size of datasets written before the version header, which only
+    /// unpack once migrated
+    pub const LEGACY_LEN: usize = 538;
+
+    /// Size of version 1 datasets, which lack the holder quota
+    pub const V1_LEN: usize = Self::LEGACY_LEN + 1;
+
+    /// Size of version 2 datasets, which lack the pending owner
+    pub const V2_LEN: usize = Self::V1_LEN + 8;
+
+    /// Size of version 3 datasets, which lack the price table
+    pub const V3_LEN: usize = Self::V2_LEN + 32;
+
+    /// Size of version 4 datasets, which lack the sale window
+    pub const V4_LEN: usize = Self::V3_LEN + 161;
+
+    /// Size of version 5 datasets, which lack the prune bounty
+    pub const V5_LEN: usize = Self::V4_LEN + 16;
+
+    /// Size of version 6 datasets, which lack the share fee
+    pub const V6_LEN: usize = Self::V5_LEN + 16;
+
+    /// Size of version 7 datasets, which lack the tags
+    pub const V7_LEN: usize = Self::V6_LEN + 40;
+
+    /// Offset of the tags in a packed dataset, header included. The
+    /// `MAX_TAGS` slots of `TAG_LEN` bytes follow each other from here with
+    /// the tags in the first ones and the rest all-zero, so that clients can
+    /// match a tag in any slot with a memcmp filter. Stable across layouts,
+    /// new fields are appended after the tags
+    pub const TAGS_OFFSET: usize = Self::V7_LEN;
+
+    /// Size of version 8 datasets, which lack the derived discount
+    pub const V8_LEN: usize = Self::V7_LEN + 128;
+
+    /// Size of version 9 datasets, which lack the receipt count
+    pub const V9_LEN: usize = Self::V8_LEN + 2;
+
+    /// Layout version in the first byte of every dataset
+    pub const LAYOUT_VERSION: u8 = 10;
+
+    /// Size of each headed layout by version, each appends fields to the
+    /// one before it
+    const LAYOUT_LENS: [usize; 10] = [
+        Self::V1_LEN,
+        Self::V2_LEN,
+        Self::V3_LEN,
+        Self::V4_LEN,
+        Self::V5_LEN,
+        Self::V6_LEN,
+        Self::V7_LEN,
+        Self::V8_LEN,
+        Self::V9_LEN,
+        Self::LEN,
+    ];
+
+    /// Size closed datasets are shrunk to. The account is kept rather than
+    /// reclaimed, so its address can't be taken by a new dataset that
+    /// stale access entries would then match
+    pub const CLOSED_LEN: usize = 1;
+
+    /// Whether `data` is what `CloseDataset` leaves of a dataset
+    pub fn is_closed(data: &[u8]) -> bool {
+        data.len() == Self::CLOSED_LEN
+    }
+
+    /// Layout version of the packed dataset in `data`, read from its header.
+    /// Legacy datasets have no header and are told apart by length, `None`
+    /// when `data` is of no dataset layout
+    pub fn layout_version(data: &[u8]) -> Option<u8> {
+        if data.len() == Self::LEGACY_LEN {
+            return Some(0);
+        }
+
+        let version = *data.first()?;
+        let len = Self::LAYOUT_LENS.get(usize::from(version).checked_sub(1)?)?;
+        (*len == data.len()).then_some(version)
+    }
+
+    /// Fails unless `data` holds a dataset of the current layout, with
+    /// `OutdatedLayout` for older ones
+    pub fn check_layout(data: &[u8]) -> Result<(), ProgramError> {
+        match Self::layout_version(data) {
+            Some(Self::LAYOUT_VERSION) => Ok(()),
+            Some(_) => Err(DataNexusError::OutdatedLayout.into()),
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Bytes a dataset account takes up, as created by `InitAccount`
+    pub const fn space() -> usize {
+        Self::LEN
+    }
+
+    /// Metadata URI with the null padding stripped, `None` when unset
+    pub fn uri(&self) -> Option<&str> {
+        trim_null_padded(&self.uri)
+    }
+
+    /// MIME content type with the null padding stripped, `None` when unset
+    pub fn content_type(&self) -> Option<&str> {
+        trim_null_padded(&self.content_type)
+    }
+
+    /// Price per seat when buying `seats` at once, from the largest tier
+    /// they reach or the dataset value below every tier
+    pub fn unit_price(&self, seats: u16) -> Option<u64> {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.min_seats <= seats)
+            .max_by_key(|tier| tier.min_seats)
+            .map(|tier| tier.unit_price)
+            .or(self.value)
+    }
+
+    /// `price` less the derived discount, for buyers holding access to
+    /// `ref_data`. The discount is rounded down, so the buyer pays any
+    /// fraction of a base unit
+    pub fn derived_price(&self, price: u64) -> u64 {
+        let discount = u128::from(price)
+            .checked_mul(u128::from(self.derived_discount_bps.min(MAX_BPS)))
+            .and_then(|n| n.checked_div(u128::from(MAX_BPS)))
+            .and_then(|n| u64::try_from(n).ok())
+            .unwrap_or(0);
+        price.saturating_sub(discount)
+    }
+
+    /// Proceeds held in the vault of `mint`, `None` when the dataset has no
+    /// vault of it
+    pub fn claimable(&self, mint: &Pubkey) -> Option<u64> {
+        self.claimable
+            .iter()
+            .find(|claimable| claimable.mint == *mint)
+            .map(|claimable| claimable.amount)
+    }
+
+    /// Mutable `claimable` of the vault of `mint`
+    pub fn claimable_mut(&mut self, mint: &Pubkey) -> Option<&mut u64> {
+        self.claimable
+            .iter_mut()
+            .find(|claimable| claimable.mint == *mint)
+            .map(|claimable| &mut claimable.amount)
+    }
+
+    /// Whether every vault of the dataset has been withdrawn from in full
+    pub fn is_withdrawn(&self) -> bool {
+        self.claimable.iter().all(|claimable| claimable.amount == 0)
+    }
+
+    /// Holders the dataset still admits, `None` when it has no quota
+    pub fn slots_remaining(&self) -> Option<u32> {
+        self.max_holders
+            .map(|max_holders| max_holders.saturating_sub(self.holder_count))
+    }
+
+    /// Price of the dataset paid in `mint`, from the price table once it has
+    /// entries and the dataset value in any mint before. `None` when the
+    /// dataset can't be bought with `mint`
+    pub fn price(&self, mint: &Pubkey) -> Option<u64> {
+        if self.prices.is_empty() {
+            return self.value;
+        }
+
+        self.prices
+            .iter()
+            .find(|price| price.mint == *mint)
+            .map(|price| price.amount)
+    }
+
+    /// Prices the dataset at `amount` of `mint`, replacing the price of
+    /// `mint` when it has one. Fails with `PriceTableFull` when `mint` would
+    /// take a fifth entry
+    pub fn set_price(&mut self, mint: Pubkey, amount: u64) -> Result<(), DataNexusError> {
+        if let Some(price) = self.prices.iter_mut().find(|price| price.mint == mint) {
+            price.amount = amount;
+        } else if self.prices.len() < MAX_PRICES {
+            self.prices.push(MintPrice { mint, amount });
+        } else {
+            return Err(DataNexusError::PriceTableFull);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the price of `mint`, failing with `UnsupportedMint` when it
+    /// has none
+    pub fn remove_price(&mut self, mint: &Pubkey) -> Result<(), DataNexusError> {
+        let index = self
+            .prices
+            .iter()
+            .position(|price| price.mint == *mint)
+            .ok_or(DataNexusError::UnsupportedMint)?;
+        self.prices.remove(index);
+
+        Ok(())
+    }
+
+    /// Tags of the dataset with the null padding stripped, tags that aren't
+    /// UTF-8 are left out
+    pub fn tag_names(&self) -> Vec<&str> {
+        self.tags
+            .iter()
+            .filter_map(|tag| trim_null_padded(tag))
+            .collect()
+    }
+
+    /// Labels the dataset with `tag`, failing with `InvalidTag` when it is
+    /// all-zero, `DuplicateTag` when the dataset has it already and
+    /// `TagsFull` when it would take a ninth slot
+    pub fn add_tag(&mut self, tag: Tag) -> Result<(), DataNexusError> {
+        if tag == [0u8; TAG_LEN] {
+            return Err(DataNexusError::InvalidTag);
+        }
+        if self.tags.contains(&tag) {
+            return Err(DataNexusError::DuplicateTag);
+        }
+        if self.tags.len() >= MAX_TAGS {
+            return Err(DataNexusError::TagsFull);
+        }
+        self.tags.push(tag);
+
+        Ok(())
+    }
+
+    /// Removes `tag`, failing with `TagNotFound` when the dataset doesn't
+    /// have it
+    pub fn remove_tag(&mut self, tag: &Tag) -> Result<(), DataNexusError> {
+        let index = self
+            .tags
+            .iter()
+            .position(|t| t == tag)
+            .ok_or(DataNexusError::TagNotFound)?;
+        self.tags.remove(index);
+
+        Ok(())
+    }
+
+    /// Whether the dataset can be bought at unix timestamp `now`, from
+    /// `sale_start` up to but excluding `sale_end`
+    pub fn is_on_sale(&self, now: i64) -> bool {
+        self.sale_start.is_none_or(|start| start <= now)
+            && self.sale_end.is_none_or(|end| now < end)
+    }
+}
+
+fn trim_null_padded(bytes: &[u8]) -> Option<&str> {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    match len {
+        0 => None,
+        n => std::str::from_utf8(bytes.get(..n)?).ok(),
+    }
 }
 
-impl IsInitialized for AccountState {
+impl IsInitialized for DatasetState {
     fn is_initialized(&self) -> bool {
         self.is_initialized
     }
 }
 
-impl Sealed for AccountState {}
+impl Sealed for DatasetState {}
 
-impl Pack for AccountState {
-    const LEN: usize = 108;
+impl Pack for DatasetState {
+    const LEN: usize = 946;
 
+    /// Writes the version header followed by the fields, unset key, value,
+    /// share limit, access duration and reference are written as all-zero
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        dst[0] = self.is_initialized as u8;
-        dst[1] = match self.flag {
+        if let Some((version, fields)) = dst.split_first_mut() {
+            *version = Self::LAYOUT_VERSION;
+            self.pack_fields(fields);
+        }
+    }
+
+    /// Datasets of an older layout fail with `OutdatedLayout` until
+    /// `MigrateAccount` has rewritten them
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::check_layout(src)?;
+        Self::unpack_fields(read_slice(src, 1..)?)
+    }
+}
+
+impl DatasetState {
+    /// Packs the fields behind the version header into `dst`
+    fn pack_fields(&self, dst: &mut [u8]) {
+        let flag = match self.flag {
             AccountFlag::Access => 0u8,
             AccountFlag::Dataset => 1u8,
+            AccountFlag::Collection => 2u8,
+            AccountFlag::Registry => 3u8,
         };
-        dst[2..34].copy_from_slice(self.owner.as_ref());
-        dst[34..66].copy_from_slice(&self.hash);
-        dst[66..98].copy_from_slice(&self.key);
-        dst[98..106].copy_from_slice(&self.value.to_le_bytes());
-        dst[106..108].copy_from_slice(&self.share_limit.to_le_bytes());
+        write_bytes(dst, 0, &[self.is_initialized as u8, flag]);
+        write_bytes(dst, 2, self.owner.as_ref());
+        write_bytes(dst, 34, &self.hash);
+        write_bytes(dst, 66, &self.key.unwrap_or([0u8; 32]));
+        write_bytes(dst, 98, &self.value.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 106, &self.share_limit.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 108, &self.purchase_count.to_le_bytes());
+        write_bytes(dst, 112, &self.referral_bps.to_le_bytes());
+        write_bytes(dst, 114, &self.access_duration.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 122, &self.uri);
+        write_bytes(dst, 250, &self.size_bytes.to_le_bytes());
+        write_bytes(dst, 258, &self.content_type);
+        write_bytes(dst, 290, &self.total_revenue.to_le_bytes());
+        write_bytes(dst, 298, &self.share_count.to_le_bytes());
+        write_bytes(
+            dst,
+            302,
+            match self.ref_data {
+                Some(ref n) => n.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        write_bytes(
+            dst,
+            334,
+            &[
+                self.resharable as u8,
+                self.max_share_depth.unwrap_or(0),
+                self.tiers.len() as u8,
+            ],
+        );
+        let mut tiers = [0u8; PriceTier::LEN * MAX_PRICE_TIERS];
+        for (tier, dst) in self
+            .tiers
+            .iter()
+            .zip(tiers.chunks_exact_mut(PriceTier::LEN))
+        {
+            write_bytes(dst, 0, &tier.min_seats.to_le_bytes());
+            write_bytes(dst, 2, &tier.unit_price.to_le_bytes());
+        }
+        write_bytes(dst, 337, &tiers);
+        write_bytes(dst, 377, &[self.claimable.len() as u8]);
+        let mut vaults = [0u8; Claimable::LEN * MAX_VAULTS];
+        for (claimable, dst) in self
+            .claimable
+            .iter()
+            .zip(vaults.chunks_exact_mut(Claimable::LEN))
+        {
+            write_bytes(dst, 0, claimable.mint.as_ref());
+            write_bytes(dst, 32, &claimable.amount.to_le_bytes());
+        }
+        write_bytes(dst, 378, &vaults);
+        write_bytes(dst, 538, &self.holder_count.to_le_bytes());
+        write_bytes(dst, 542, &self.max_holders.unwrap_or(0).to_le_bytes());
+        write_bytes(
+            dst,
+            546,
+            match self.pending_owner {
+                Some(ref n) => n.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        write_bytes(dst, 578, &[self.prices.len() as u8]);
+        let mut prices = [0u8; MintPrice::LEN * MAX_PRICES];
+        for (price, dst) in self
+            .prices
+            .iter()
+            .zip(prices.chunks_exact_mut(MintPrice::LEN))
+        {
+            write_bytes(dst, 0, price.mint.as_ref());
+            write_bytes(dst, 32, &price.amount.to_le_bytes());
+        }
+        write_bytes(dst, 579, &prices);
+        write_bytes(dst, 739, &self.sale_start.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 747, &self.sale_end.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 755, &self.prune_bounty.to_le_bytes());
+        write_bytes(dst, 763, &self.bounty_pool.to_le_bytes());
+        if let Some(ref share_fee) = self.share_fee {
+            write_bytes(dst, 771, share_fee.mint.as_ref());
+            write_bytes(dst, 803, &share_fee.amount.to_le_bytes());
+        } else {
+            write_bytes(dst, 771, &[0u8; MintPrice::LEN]);
+        }
+        let mut tags = [0u8; TAG_LEN * MAX_TAGS];
+        for (tag, dst) in self.tags.iter().zip(tags.chunks_exact_mut(TAG_LEN)) {
+            write_bytes(dst, 0, tag);
+        }
+        write_bytes(dst, 811, &tags);
+        write_bytes(dst, 939, &self.derived_discount_bps.to_le_bytes());
+        write_bytes(dst, 941, &self.receipt_count.to_le_bytes());
     }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let is_initialized = match src[0] {
-            0 => false,
-            1 => true,
-            _ => return Err(ProgramError::InvalidAccountData),
-        };
-        let flag = match [1] {
+    /// Unpacks the fields behind the version header from `src`
+    fn unpack_fields(src: &[u8]) -> Result<Self, ProgramError> {
+        let is_initialized = read_bool(src, 0)?;
+        let flag = match read_u8(src, 1)? {
             0 => AccountFlag::Access,
             1 => AccountFlag::Dataset,
             _ => return Err(ProgramError::InvalidAccountData),
         };
-        let owner = Pubkey::new_from_array(&src[2..34].try_into().unwrap());
-        let hash = src[34..66].try_into().unwrap();
-        let key = src[66..98].try_into().unwrap();
-        let value = u64::from_le_bytes(&src[98..106].try_into().unwrap());
-        let share_limit = u16::from_le_bytes(&src[106..108].try_into().unwrap());
+        let owner = Pubkey::new_from_array(read_array(src, 2)?);
+        let hash = read_array(src, 34)?;
+        let key = read_optional_key(src, 66)?;
+        let value = match u64::from_le_bytes(read_array(src, 98)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let share_limit = match u16::from_le_bytes(read_array(src, 106)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let purchase_count = u32::from_le_bytes(read_array(src, 108)?);
+        let referral_bps = u16::from_le_bytes(read_array(src, 112)?);
+        let access_duration = match i64::from_le_bytes(read_array(src, 114)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let uri = read_array(src, 122)?;
+        let size_bytes = u64::from_le_bytes(read_array(src, 250)?);
+        let content_type = read_array(src, 258)?;
+        let total_revenue = u64::from_le_bytes(read_array(src, 290)?);
+        let share_count = u32::from_le_bytes(read_array(src, 298)?);
+        let ref_data = read_optional_key(src, 302)?.map(Pubkey::new_from_array);
+        let resharable = read_bool(src, 334)?;
+        let max_share_depth = match read_u8(src, 335)? {
+            0 => None,
+            n => Some(n),
+        };
+        let tier_count = usize::from(read_u8(src, 336)?);
+        if tier_count > MAX_PRICE_TIERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let tiers = read_slice(src, 337..377)?
+            .chunks_exact(PriceTier::LEN)
+            .take(tier_count)
+            .map(|tier| {
+                Ok(PriceTier {
+                    min_seats: u16::from_le_bytes(read_array(tier, 0)?),
+                    unit_price: u64::from_le_bytes(read_array(tier, 2)?),
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+        let vault_count = usize::from(read_u8(src, 377)?);
+        if vault_count > MAX_VAULTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let claimable = read_slice(src, 378..538)?
+            .chunks_exact(Claimable::LEN)
+            .take(vault_count)
+            .map(|claimable| {
+                Ok(Claimable {
+                    mint: Pubkey::new_from_array(read_array(claimable, 0)?),
+                    amount: u64::from_le_bytes(read_array(claimable, 32)?),
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+        let holder_count = u32::from_le_bytes(read_array(src, 538)?);
+        let max_holders = match u32::from_le_bytes(read_array(src, 542)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let pending_owner = read_optional_key(src, 546)?.map(Pubkey::new_from_array);
+        let price_count = usize::from(read_u8(src, 578)?);
+        if price_count > MAX_PRICES {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let prices = read_slice(src, 579..739)?
+            .chunks_exact(MintPrice::LEN)
+            .take(price_count)
+            .map(|price| {
+                Ok(MintPrice {
+                    mint: Pubkey::new_from_array(read_array(price, 0)?),
+                    amount: u64::from_le_bytes(read_array(price, 32)?),
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+        let sale_start = match i64::from_le_bytes(read_array(src, 739)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let sale_end = match i64::from_le_bytes(read_array(src, 747)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let prune_bounty = u64::from_le_bytes(read_array(src, 755)?);
+        let bounty_pool = u64::from_le_bytes(read_array(src, 763)?);
+        let share_fee = match u64::from_le_bytes(read_array(src, 803)?) {
+            0 => None,
+            amount => Some(MintPrice {
+                mint: Pubkey::new_from_array(read_array(src, 771)?),
+                amount,
+            }),
+        };
+        let tags = read_slice(src, 811..939)?
+            .chunks_exact(TAG_LEN)
+            .filter(|tag| tag.iter().any(|b| *b != 0))
+            .map(|tag| read_array(tag, 0))
+            .collect::<Result<_, ProgramError>>()?;
+        let derived_discount_bps = u16::from_le_bytes(read_array(src, 939)?);
+        let receipt_count = u32::from_le_bytes(read_array(src, 941)?);
 
         Ok(Self {
             is_initialized,
@@ -140,6 +1284,1667 @@ impl Pack for AccountState {
             key,
             value,
             share_limit,
+            purchase_count,
+            referral_bps,
+            access_duration,
+            uri,
+            size_bytes,
+            content_type,
+            total_revenue,
+            share_count,
+            ref_data,
+            resharable,
+            max_share_depth,
+            tiers,
+            claimable,
+            holder_count,
+            max_holders,
+            pending_owner,
+            prices,
+            sale_start,
+            sale_end,
+            prune_bounty,
+            bounty_pool,
+            share_fee,
+            tags,
+            derived_discount_bps,
+            receipt_count,
+        })
+    }
+}
+
+/// A single grant of access to a dataset
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccessInfo {
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
+    pub hash: [u8; 32],
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::hex::option"
+        )
+    )]
+    pub key: Option<[u8; 32]>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
+    pub shared_from: Option<Pubkey>,
+    /// Shares the grant allows, fixed when granted: the dataset's share
+    /// limit, or the seats bought when purchased with `PurchaseSeats`
+    pub share_quota: u16,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub expires_at: Option<i64>,
+    /// Shares between the purchaser and this grant, zero when purchased
+    pub depth: u8,
+    /// Shares made from the grant so far
+    pub shares_used: u16,
+}
+
+impl AccessInfo {
+    /// Size of entries of version 1 access accounts and older, which lack
+    /// `shares_used`
+    pub const V1_LEN: usize = 107;
+
+    /// Shares the grant has left
+    pub fn shares_remaining(&self) -> u16 {
+        self.share_quota.saturating_sub(self.shares_used)
+    }
+
+    /// Whether the grant has lapsed at unix timestamp `now`, perpetual
+    /// grants never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at <= now,
+            None => false,
+        }
+    }
+}
+
+impl Sealed for AccessInfo {}
+
+impl Pack for AccessInfo {
+    const LEN: usize = Self::V1_LEN + 2;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        write_bytes(dst, 0, &self.hash);
+        write_bytes(dst, 32, &self.key.unwrap_or([0u8; 32]));
+        write_bytes(
+            dst,
+            64,
+            match self.shared_from {
+                Some(ref n) => n.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        write_bytes(dst, 96, &self.share_quota.to_le_bytes());
+        write_bytes(dst, 98, &self.expires_at.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 106, &[self.depth]);
+        write_bytes(dst, 107, &self.shares_used.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let hash = read_array(src, 0)?;
+        let key = read_optional_key(src, 32)?;
+        let shared_from = read_optional_key(src, 64)?.map(Pubkey::new_from_array);
+        let share_quota = u16::from_le_bytes(read_array(src, 96)?);
+        let expires_at = match i64::from_le_bytes(read_array(src, 98)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let depth = read_u8(src, 106)?;
+        let shares_used = u16::from_le_bytes(read_array(src, 107)?);
+
+        Ok(Self {
+            hash,
+            key,
+            shared_from,
+            share_quota,
+            expires_at,
+            depth,
+            shares_used,
         })
     }
 }
+
+/// Maximum number of `AccessInfo` entries held by an `AccessState`
+pub const MAX_ACCESS_ENTRIES: usize = 16;
+
+/// Maximum number of shares an `AccessState` holds for approval at once
+pub const MAX_PENDING_SHARES: usize = 4;
+
+/// Seconds a pending share waits for its recipient, after which it can
+/// only be rejected and the sharer may reclaim it
+pub const PENDING_SHARE_TTL: i64 = 7 * 24 * 60 * 60;
+
+/// Share held until its recipient accepts or rejects it
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PendingShare {
+    /// Grant the recipient is given on accepting, `shared_from` holds the
+    /// sharer
+    pub access: AccessInfo,
+    /// Unix timestamp the share was made at
+    pub shared_at: i64,
+}
+
+impl PendingShare {
+    /// Authority that made the share
+    pub fn sharer(&self) -> Option<Pubkey> {
+        self.access.shared_from
+    }
+
+    /// Whether the share has waited `PENDING_SHARE_TTL` at unix timestamp
+    /// `now`, lapsed shares can no longer be accepted
+    pub fn is_lapsed(&self, now: i64) -> bool {
+        self.shared_at.saturating_add(PENDING_SHARE_TTL) <= now
+    }
+}
+
+impl Sealed for PendingShare {}
+
+impl Pack for PendingShare {
+    const LEN: usize = AccessInfo::LEN + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        if let Some(access) = dst.get_mut(..AccessInfo::LEN) {
+            self.access.pack_into_slice(access);
+        }
+        write_bytes(dst, AccessInfo::LEN, &self.shared_at.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let access = AccessInfo::unpack_from_slice(read_slice(src, ..AccessInfo::LEN)?)?;
+        let shared_at = i64::from_le_bytes(read_array(src, AccessInfo::LEN)?);
+
+        Ok(Self { access, shared_at })
+    }
+}
+
+/// Access entries held by a user
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccessState {
+    pub is_initialized: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
+    pub pointer: Option<Pubkey>,
+    pub datasets: Vec<AccessInfo>,
+    /// Shares awaiting the authority's approval, in the order they were made
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pending: Vec<PendingShare>,
+}
+
+impl AccessState {
+    /// Size of access accounts written before the version header, which
+    /// hold the version 1 fields without it and only unpack once migrated
+    pub const LEGACY_LEN: usize = Self::V1_LEN - 1;
+
+    /// Size of version 1 access accounts, whose entries lack `shares_used`
+    pub const V1_LEN: usize = Self::HEADER_LEN + AccessInfo::V1_LEN * MAX_ACCESS_ENTRIES;
+
+    /// Size of version 2 access accounts, which lack the pending shares
+    pub const V2_LEN: usize = Self::HEADER_LEN + AccessInfo::LEN * MAX_ACCESS_ENTRIES;
+
+    /// Layout version in the first byte of every access account
+    pub const LAYOUT_VERSION: u8 = 3;
+
+    /// Size of each headed layout by version
+    const LAYOUT_LENS: [usize; 3] = [Self::V1_LEN, Self::V2_LEN, Self::LEN];
+
+    /// Bytes ahead of the entries: the version header, `is_initialized`,
+    /// `pointer` and the entry count
+    const HEADER_LEN: usize = 35;
+
+    /// Bytes after the entries: the pending share count and the shares
+    const PENDING_LEN: usize = 1 + PendingShare::LEN * MAX_PENDING_SHARES;
+
+    /// Offset of the pending share count in the fields behind the version
+    /// header, past room for `MAX_ACCESS_ENTRIES` entries
+    const PENDING_OFFSET: usize = Self::V2_LEN - 1;
+
+    /// Offset of the first pending share in the fields behind the header
+    const PENDING_SHARES_OFFSET: usize = Self::PENDING_OFFSET + 1;
+
+    /// Layout version of the packed access account in `data`, read from its
+    /// header. Legacy accounts have no header and are told apart by length,
+    /// `None` when `data` is of no access layout
+    pub fn layout_version(data: &[u8]) -> Option<u8> {
+        if data.len() == Self::LEGACY_LEN {
+            return Some(0);
+        }
+
+        let version = *data.first()?;
+        let len = Self::LAYOUT_LENS.get(usize::from(version).checked_sub(1)?)?;
+        (*len == data.len()).then_some(version)
+    }
+
+    /// Fails unless `data` holds an access account of the current layout,
+    /// with `OutdatedLayout` for older ones
+    pub fn check_layout(data: &[u8]) -> Result<(), ProgramError> {
+        match Self::layout_version(data) {
+            Some(Self::LAYOUT_VERSION) => Ok(()),
+            Some(_) => Err(DataNexusError::OutdatedLayout.into()),
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Bytes an associated access account holding `capacity` entries takes
+    /// up, `InitAssociatedAccess` creates them at `MAX_ACCESS_ENTRIES`, the
+    /// most the layout records
+    pub fn space(capacity: usize) -> Result<usize, ProgramError> {
+        if capacity > MAX_ACCESS_ENTRIES {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let len = capacity
+            .checked_mul(AccessInfo::LEN)
+            .and_then(|len| len.checked_add(Self::HEADER_LEN))
+            .and_then(|len| len.checked_add(Self::PENDING_LEN))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+
+        Ok(len)
+    }
+
+    /// Position of the entry granting access to `hash`
+    pub fn find_entry(&self, hash: &[u8; 32]) -> Option<usize> {
+        self.datasets.iter().position(|access| access.hash == *hash)
+    }
+
+    /// Position of the share of `hash` from `sharer` awaiting approval
+    pub fn find_pending(&self, hash: &[u8; 32], sharer: &Pubkey) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|pending| pending.access.hash == *hash && pending.sharer() == Some(*sharer))
+    }
+}
+
+impl IsInitialized for AccessState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for AccessState {}
+
+impl Pack for AccessState {
+    const LEN: usize = Self::V2_LEN + Self::PENDING_LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        if let Some((version, fields)) = dst.split_first_mut() {
+            *version = Self::LAYOUT_VERSION;
+            self.pack_fields(fields);
+        }
+    }
+
+    /// Accounts of an older layout fail with `OutdatedLayout` until
+    /// `MigrateAccount` has rewritten them
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::check_layout(src)?;
+        Self::unpack_fields(read_slice(src, 1..)?, Self::LAYOUT_VERSION)
+    }
+}
+
+impl AccessState {
+    /// Packs the fields behind the version header into `dst`
+    fn pack_fields(&self, dst: &mut [u8]) {
+        write_bytes(dst, 0, &[self.is_initialized as u8]);
+        write_bytes(
+            dst,
+            1,
+            match self.pointer {
+                Some(ref n) => n.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        write_bytes(dst, 33, &[self.datasets.len() as u8]);
+        clear_from(dst, 34);
+        if let Some(entries) = dst.get_mut(34..) {
+            for (access, dst) in self
+                .datasets
+                .iter()
+                .zip(entries.chunks_exact_mut(AccessInfo::LEN))
+            {
+                access.pack_into_slice(dst);
+            }
+        }
+        write_bytes(dst, Self::PENDING_OFFSET, &[self.pending.len() as u8]);
+        if let Some(shares) = dst.get_mut(Self::PENDING_SHARES_OFFSET..) {
+            for (pending, dst) in self
+                .pending
+                .iter()
+                .zip(shares.chunks_exact_mut(PendingShare::LEN))
+            {
+                pending.pack_into_slice(dst);
+            }
+        }
+    }
+
+    /// Unpacks the fields behind the version header from `src`, laid out as
+    /// layout `version` lays them out, 0 for the legacy layout
+    fn unpack_fields(src: &[u8], version: u8) -> Result<Self, ProgramError> {
+        let entry_len = if version < 2 {
+            AccessInfo::V1_LEN
+        } else {
+            AccessInfo::LEN
+        };
+        let is_initialized = read_bool(src, 0)?;
+        let pointer = read_optional_key(src, 1)?.map(Pubkey::new_from_array);
+        let count = usize::from(read_u8(src, 33)?);
+        if count > MAX_ACCESS_ENTRIES {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let datasets = read_slice(src, 34..)?
+            .chunks_exact(entry_len)
+            .take(count)
+            .map(|entry| {
+                if entry.len() == AccessInfo::LEN {
+                    return AccessInfo::unpack_from_slice(entry);
+                }
+                // Older entries lack the fields appended since, which an
+                // all-zero tail leaves unset
+                let mut data = [0; AccessInfo::LEN];
+                data.get_mut(..entry.len())
+                    .ok_or(ProgramError::InvalidAccountData)?
+                    .copy_from_slice(entry);
+                AccessInfo::unpack_from_slice(&data)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if datasets.len() != count {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Shares were only held for approval from version 3 on
+        let pending = if version < 3 {
+            Vec::new()
+        } else {
+            let count = usize::from(read_u8(src, Self::PENDING_OFFSET)?);
+            if count > MAX_PENDING_SHARES {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let pending = read_slice(src, Self::PENDING_SHARES_OFFSET..)?
+                .chunks_exact(PendingShare::LEN)
+                .take(count)
+                .map(PendingShare::unpack_from_slice)
+                .collect::<Result<Vec<_>, _>>()?;
+            if pending.len() != count {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            pending
+        };
+
+        Ok(Self {
+            is_initialized,
+            pointer,
+            datasets,
+            pending,
+        })
+    }
+}
+
+/// Size `MigrateAccount` grows the account in `data` to, that of the
+/// current layout of its kind
+///
+/// `None` when the account is at its kind's current layout or is not a
+/// dataset, access or index account
+pub fn migrated_len(data: &[u8]) -> Option<usize> {
+    match (
+        DatasetState::layout_version(data),
+        AccessState::layout_version(data),
+        AccountIndex::layout_version(data),
+    ) {
+        (Some(version), _, _) if version < DatasetState::LAYOUT_VERSION => Some(DatasetState::LEN),
+        (_, Some(version), _) if version < AccessState::LAYOUT_VERSION => Some(AccessState::LEN),
+        (_, _, Some(version)) if version < INDEX_LAYOUT_VERSION => AccountIndex::capacity_raw(data)
+            .and_then(AccountIndex::space)
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Repacks the account in `src` into `dst`, sized by `migrated_len`, behind
+/// the version header of the current layout
+pub fn migrate_layout(src: &[u8], dst: &mut [u8]) -> Result<(), ProgramError> {
+    if dst.len() != migrated_len(src).ok_or(ProgramError::InvalidAccountData)? {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if let Some(version) = DatasetState::layout_version(src) {
+        // Later layouts append fields, which an all-zero tail leaves unset
+        let fields = if version == 0 {
+            src
+        } else {
+            read_slice(src, 1..)?
+        };
+        let mut data = vec![0; DatasetState::LEN - 1];
+        data.get_mut(..fields.len())
+            .ok_or(ProgramError::InvalidAccountData)?
+            .copy_from_slice(fields);
+        let mut dataset = DatasetState::unpack_fields(&data)?;
+        // Holders bought or were shared their access before version 2 kept
+        // count, revocations since then can't be told apart
+        if version < 2 {
+            dataset.holder_count = dataset.purchase_count.saturating_add(dataset.share_count);
+        }
+        dataset.pack_into_slice(dst);
+    } else if let Some(version) = AccessState::layout_version(src) {
+        // Version 1 entries held the shares left, which become the quota
+        // with none used, and no shares were pending before version 3
+        let fields = if version == 0 {
+            src
+        } else {
+            read_slice(src, 1..)?
+        };
+        AccessState::unpack_fields(fields, version)?.pack_into_slice(dst);
+    } else {
+        AccountIndex::unpack_from_slice(src)?.pack_into_slice(dst);
+    }
+
+    Ok(())
+}
+
+/// Maximum number of datasets a `CollectionState` bundles
+pub const MAX_COLLECTION_MEMBERS: usize = 64;
+
+/// Members granted by a single `PurchaseAccess` or `ClaimCollectionAccess`,
+/// larger collections are granted over several transactions
+pub const MAX_COLLECTION_GRANTS: usize = 6;
+
+/// Bundle of datasets sold together for a single payment
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CollectionState {
+    pub is_initialized: bool,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub owner: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
+    pub hash: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub value: Option<u64>,
+    /// Member datasets, in the order buyers are granted them
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey::vec"))]
+    pub members: Vec<Pubkey>,
+}
+
+impl CollectionState {
+    /// Bytes a collection account takes up, as created by `CreateCollection`
+    pub const fn space() -> usize {
+        Self::LEN
+    }
+
+    /// Whether `data` holds a packed collection rather than a dataset or
+    /// access account
+    pub fn is_collection(data: &[u8]) -> bool {
+        data.len() == Self::LEN && data.get(1) == Some(&2)
+    }
+}
+
+impl IsInitialized for CollectionState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for CollectionState {}
+
+impl Pack for CollectionState {
+    const LEN: usize = 76 + 32 * MAX_COLLECTION_MEMBERS;
+
+    /// The flag byte is always `AccountFlag::Collection`, an unset value is
+    /// written as zero
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        write_bytes(dst, 0, &[self.is_initialized as u8, 2]);
+        write_bytes(dst, 2, self.owner.as_ref());
+        write_bytes(dst, 34, &self.hash);
+        write_bytes(dst, 66, &self.value.unwrap_or(0).to_le_bytes());
+        write_bytes(dst, 74, &(self.members.len() as u16).to_le_bytes());
+        let mut members = [0u8; 32 * MAX_COLLECTION_MEMBERS];
+        for (member, dst) in self.members.iter().zip(members.chunks_exact_mut(32)) {
+            dst.copy_from_slice(member.as_ref());
+        }
+        write_bytes(dst, 76, &members);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if !Self::is_collection(src) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let is_initialized = read_bool(src, 0)?;
+        let owner = Pubkey::new_from_array(read_array(src, 2)?);
+        let hash = read_array(src, 34)?;
+        let value = match u64::from_le_bytes(read_array(src, 66)?) {
+            0 => None,
+            n => Some(n),
+        };
+        let count = usize::from(u16::from_le_bytes(read_array(src, 74)?));
+        if count > MAX_COLLECTION_MEMBERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let members = read_slice(src, 76..Self::LEN)?
+            .chunks_exact(32)
+            .take(count)
+            .map(|member| Ok(Pubkey::new_from_array(read_array(member, 0)?)))
+            .collect::<Result<_, ProgramError>>()?;
+
+        Ok(Self {
+            is_initialized,
+            owner,
+            hash,
+            value,
+            members,
+        })
+    }
+}
+
+/// A buyer's progress through a collection, created by
+/// `InitAssociatedAccess` at the address its access to a dataset would have
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CollectionAccess {
+    pub is_initialized: bool,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub collection: Pubkey,
+    /// Whether the collection has been paid for
+    pub purchased: bool,
+    /// Members granted so far, the next grant is `members[cursor]`
+    pub cursor: u16,
+}
+
+impl CollectionAccess {
+    /// Bytes a collection access account takes up
+    pub const fn space() -> usize {
+        Self::LEN
+    }
+
+    /// Members of `collection` paid for but not yet granted, including any
+    /// added after the purchase
+    pub fn pending(&self, collection: &CollectionState) -> usize {
+        collection
+            .members
+            .len()
+            .saturating_sub(usize::from(self.cursor))
+    }
+}
+
+impl IsInitialized for CollectionAccess {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for CollectionAccess {}
+
+impl Pack for CollectionAccess {
+    const LEN: usize = 36;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        write_bytes(dst, 0, &[self.is_initialized as u8]);
+        write_bytes(dst, 1, self.collection.as_ref());
+        write_bytes(dst, 33, &[self.purchased as u8]);
+        write_bytes(dst, 34, &self.cursor.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let is_initialized = read_bool(src, 0)?;
+        let collection = Pubkey::new_from_array(read_array(src, 1)?);
+        let purchased = read_bool(src, 33)?;
+        let cursor = u16::from_le_bytes(read_array(src, 34)?);
+
+        Ok(Self {
+            is_initialized,
+            collection,
+            purchased,
+            cursor,
+        })
+    }
+}
+
+/// Entries a registry page holds before further datasets under its prefix
+/// are chained to an overflow page
+pub const MAX_REGISTRY_ENTRIES: usize = 128;
+
+/// Layout version in the first byte of every `RegistryState`
+pub const REGISTRY_LAYOUT_VERSION: u8 = 1;
+
+/// Dataset listed in the registry together with its hash
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RegistryEntry {
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
+    pub hash: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::pubkey"))]
+    pub dataset: Pubkey,
+}
+
+impl RegistryEntry {
+    /// Bytes each entry adds to a registry page
+    pub const LEN: usize = 64;
+}
+
+/// Page of the global registry listing datasets whose hash starts with
+/// `prefix`, appended to by `InitAccount` and grown one entry at a time
+///
+/// A full page links to the overflow page taking the prefix's further
+/// entries, so a lookup walks the chain from page 0.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "borsh-encoding", derive(BorshSerialize, BorshDeserialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RegistryState {
+    pub is_initialized: bool,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fields::hex"))]
+    pub prefix: [u8; 2],
+    /// Position of this page in its prefix's chain, 0 for the first
+    pub page: u8,
+    /// Overflow page created once this one filled up
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde_fields::pubkey::option"
+        )
+    )]
+    pub next: Option<Pubkey>,
+    pub entries: Vec<RegistryEntry>,
+}
+
+impl RegistryState {
+    /// Offset of the first entry in a packed page
+    const ENTRIES_OFFSET: usize = 40;
+
+    /// Bytes a registry page holding `entries` entries takes up
+    pub fn space(entries: usize) -> Result<usize, ProgramError> {
+        let len = entries
+            .checked_mul(RegistryEntry::LEN)
+            .and_then(|len| len.checked_add(Self::ENTRIES_OFFSET))
+            .ok_or(DataNexusError::NumericalOverflow)?;
+
+        Ok(len)
+    }
+
+    /// Whether `data` holds a packed registry page. Its length is a whole
+    /// number of entries past the header, which no other layout shares
+    pub fn is_registry(data: &[u8]) -> bool {
+        data.first() == Some(&REGISTRY_LAYOUT_VERSION)
+            && data.get(2) == Some(&3)
+            && data
+                .len()
+                .checked_sub(Self::ENTRIES_OFFSET)
+                .and_then(|len| len.checked_rem(RegistryEntry::LEN))
+                == Some(0)
+    }
+
+    /// Whether the page holds `MAX_REGISTRY_ENTRIES` and further entries go
+    /// to an overflow page
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= MAX_REGISTRY_ENTRIES
+    }
+
+    /// Dataset listed under `hash` on this page
+    pub fn find(&self, hash: &[u8; 32]) -> Option<Pubkey> {
+        self.entries
+            .iter()
+            .find(|entry| entry.hash == *hash)
+            .map(|entry| entry.dataset)
+    }
+}
+
+impl IsInitialized for RegistryState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for RegistryState {}
+
+impl Pack for RegistryState {
+    /// Size of a full page, pages are created with one entry and grow to it
+    const LEN: usize = 40 + RegistryEntry::LEN * MAX_REGISTRY_ENTRIES;
+
+    /// The flag byte is always `AccountFlag::Registry`. Entries beyond what
+    /// `dst` holds are left out
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        write_bytes(
+            dst,
+            0,
+            &[REGISTRY_LAYOUT_VERSION, self.is_initialized as u8, 3],
+        );
+        write_bytes(dst, 3, &self.prefix);
+        write_bytes(dst, 5, &[self.page]);
+        write_bytes(
+            dst,
+            6,
+            match self.next {
+                Some(ref next) => next.as_ref(),
+                None => &[0u8; 32],
+            },
+        );
+        write_bytes(dst, 38, &(self.entries.len() as u16).to_le_bytes());
+        clear_from(dst, Self::ENTRIES_OFFSET);
+        if let Some(entries) = dst.get_mut(Self::ENTRIES_OFFSET..) {
+            for (entry, dst) in self
+                .entries
+                .iter()
+                .zip(entries.chunks_exact_mut(RegistryEntry::LEN))
+            {
+                write_bytes(dst, 0, &entry.hash);
+                write_bytes(dst, 32, entry.dataset.as_ref());
+            }
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if !Self::is_registry(src) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let is_initialized = read_bool(src, 1)?;
+        let prefix = read_array(src, 3)?;
+        let page = read_u8(src, 5)?;
+        let next = read_optional_key(src, 6)?.map(Pubkey::new_from_array);
+        let count = usize::from(u16::from_le_bytes(read_array(src, 38)?));
+        if count > MAX_REGISTRY_ENTRIES || Self::space(count)? > src.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let entries = read_slice(src, Self::ENTRIES_OFFSET..)?
+            .chunks_exact(RegistryEntry::LEN)
+            .take(count)
+            .map(|entry| {
+                Ok(RegistryEntry {
+                    hash: read_array(entry, 0)?,
+                    dataset: Pubkey::new_from_array(read_array(entry, 32)?),
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+
+        Ok(Self {
+            is_initialized,
+            prefix,
+            page,
+            next,
+            entries,
+        })
+    }
+}
+
+/// Any account the program owns, decoded from its data alone
+///
+/// Owner and access indexes share one layout, so both decode to `Index` and
+/// only their address tells them apart. Closed datasets hold no state and
+/// don't decode.
+pub enum DataNexusAccount {
+    Index(AccountIndex),
+    /// Boxed, a dataset is several times the size of any other account
+    Dataset(Box<DatasetState>),
+    Access(AccessState),
+    Collection(CollectionState),
+    CollectionAccess(CollectionAccess),
+    Registry(RegistryState),
+}
+
+impl DataNexusAccount {
+    /// Decodes `data` as whichever account it holds, told apart by the
+    /// version header and length of each layout and by the flag byte of
+    /// collections and registry pages
+    ///
+    /// Datasets and access accounts of an older layout fail with
+    /// `OutdatedLayout` like their own unpacking does, data of no known
+    /// layout with `InvalidAccountData`.
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
+        if DatasetState::layout_version(data).is_some() {
+            DatasetState::unpack_from_slice(data).map(|dataset| Self::Dataset(Box::new(dataset)))
+        } else if AccessState::layout_version(data).is_some() {
+            AccessState::unpack_from_slice(data).map(Self::Access)
+        } else if CollectionState::is_collection(data) {
+            CollectionState::unpack_from_slice(data).map(Self::Collection)
+        } else if data.len() == CollectionAccess::LEN {
+            CollectionAccess::unpack_from_slice(data).map(Self::CollectionAccess)
+        } else if RegistryState::is_registry(data) {
+            RegistryState::unpack_from_slice(data).map(Self::Registry)
+        } else if AccountIndex::layout_version(data).is_some() {
+            AccountIndex::unpack_from_slice(data).map(Self::Index)
+        } else {
+            Err(ProgramError::InvalidAccountData)
+        }
+    }
+
+    /// Name of the kind of account held
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Index(_) => "index",
+            Self::Dataset(_) => "dataset",
+            Self::Access(_) => "access",
+            Self::Collection(_) => "collection",
+            Self::CollectionAccess(_) => "collection access",
+            Self::Registry(_) => "registry",
+        }
+    }
+
+    pub fn as_index(&self) -> Option<&AccountIndex> {
+        match self {
+            Self::Index(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_dataset(&self) -> Option<&DatasetState> {
+        match self {
+            Self::Dataset(dataset) => Some(dataset.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn as_access(&self) -> Option<&AccessState> {
+        match self {
+            Self::Access(access) => Some(access),
+            _ => None,
+        }
+    }
+
+    pub fn as_collection(&self) -> Option<&CollectionState> {
+        match self {
+            Self::Collection(collection) => Some(collection),
+            _ => None,
+        }
+    }
+
+    pub fn as_collection_access(&self) -> Option<&CollectionAccess> {
+        match self {
+            Self::CollectionAccess(collection_access) => Some(collection_access),
+            _ => None,
+        }
+    }
+
+    pub fn as_registry(&self) -> Option<&RegistryState> {
+        match self {
+            Self::Registry(registry) => Some(registry),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::indexing_slicing)]
+mod tests {
+    use super::*;
+
+    fn access(hash: [u8; 32]) -> AccessInfo {
+        AccessInfo {
+            hash,
+            key: None,
+            shared_from: None,
+            share_quota: 0,
+            expires_at: None,
+            depth: 0,
+            shares_used: 0,
+        }
+    }
+
+    fn access_state(hashes: &[[u8; 32]]) -> AccessState {
+        AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: hashes.iter().copied().map(access).collect(),
+            pending: vec![],
+        }
+    }
+
+    fn index(capacity: usize) -> AccountIndex {
+        AccountIndex {
+            is_initialized: true,
+            pointer: None,
+            require_share_approval: false,
+            datasets: vec![None; capacity],
+        }
+    }
+
+    /// Registry page of the `[1, 2]` prefix listing one dataset per hash
+    fn registry(hashes: &[[u8; 32]]) -> RegistryState {
+        RegistryState {
+            is_initialized: true,
+            prefix: [1, 2],
+            page: 0,
+            next: None,
+            entries: hashes
+                .iter()
+                .map(|hash| RegistryEntry {
+                    hash: *hash,
+                    dataset: Pubkey::new_unique(),
+                })
+                .collect(),
+        }
+    }
+
+    fn dataset() -> DatasetState {
+        DatasetState {
+            is_initialized: true,
+            flag: AccountFlag::Dataset,
+            owner: Pubkey::new_unique(),
+            hash: [1; 32],
+            key: Some([7; 32]),
+            value: Some(100),
+            share_limit: Some(2),
+            purchase_count: 0,
+            referral_bps: 0,
+            access_duration: None,
+            uri: [0; URI_LEN],
+            size_bytes: 0,
+            content_type: [0; CONTENT_TYPE_LEN],
+            total_revenue: 0,
+            share_count: 0,
+            ref_data: None,
+            resharable: false,
+            max_share_depth: None,
+            tiers: vec![],
+            claimable: vec![],
+            holder_count: 0,
+            max_holders: None,
+            pending_owner: None,
+            prices: vec![],
+            sale_start: None,
+            sale_end: None,
+            prune_bounty: 0,
+            bounty_pool: 0,
+            share_fee: None,
+            tags: vec![],
+            derived_discount_bps: 0,
+            receipt_count: 0,
+        }
+    }
+
+    fn repack(state: &DatasetState) -> DatasetState {
+        let mut data = vec![0; DatasetState::LEN];
+        state.pack_into_slice(&mut data);
+        DatasetState::unpack_from_slice(&data).unwrap()
+    }
+
+    #[test]
+    fn dataset_counters_survive_packing() {
+        let mut state = dataset();
+        state.total_revenue = 1_234_567;
+        state.purchase_count = 42;
+        state.share_count = 17;
+
+        let unpacked = repack(&state);
+        assert_eq!(unpacked.total_revenue, 1_234_567);
+        assert_eq!(unpacked.purchase_count, 42);
+        assert_eq!(unpacked.share_count, 17);
+        assert_eq!(unpacked.owner, state.owner);
+        assert_eq!(unpacked.value, Some(100));
+    }
+
+    #[test]
+    fn dataset_counters_pack_their_full_width() {
+        let mut state = dataset();
+        state.total_revenue = u64::MAX;
+        state.purchase_count = u32::MAX;
+        state.share_count = u32::MAX;
+        state.bounty_pool = u64::MAX;
+
+        let unpacked = repack(&state);
+        assert_eq!(unpacked.total_revenue, u64::MAX);
+        assert_eq!(unpacked.purchase_count, u32::MAX);
+        assert_eq!(unpacked.share_count, u32::MAX);
+        assert_eq!(unpacked.bounty_pool, u64::MAX);
+        assert_eq!(unpacked.share_limit, Some(2));
+    }
+
+    #[test]
+    fn slots_remaining_count_down_to_the_quota() {
+        let mut state = dataset();
+        assert_eq!(state.slots_remaining(), None);
+
+        state.max_holders = Some(10);
+        state.holder_count = 4;
+        assert_eq!(repack(&state).slots_remaining(), Some(6));
+
+        // Lowering the quota below the holders admits no one new
+        state.max_holders = Some(3);
+        assert_eq!(state.slots_remaining(), Some(0));
+    }
+
+    #[test]
+    fn sales_run_from_their_start_until_their_end() {
+        let mut state = dataset();
+        assert!(state.is_on_sale(i64::MIN));
+
+        state.sale_start = Some(100);
+        state.sale_end = Some(200);
+        let state = repack(&state);
+        assert!(!state.is_on_sale(99));
+        assert!(state.is_on_sale(100));
+        assert!(state.is_on_sale(199));
+        assert!(!state.is_on_sale(200));
+    }
+
+    #[test]
+    fn prices_replace_the_value_once_set() {
+        let mut state = dataset();
+        let (usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(state.price(&usdc), Some(100));
+
+        state.set_price(usdc, 5).unwrap();
+        let state = repack(&state);
+        assert_eq!(state.price(&usdc), Some(5));
+        assert_eq!(state.price(&bonk), None);
+    }
+
+    #[test]
+    fn setting_a_priced_mint_updates_its_entry() {
+        let mut state = dataset();
+        let mint = Pubkey::new_unique();
+        state.set_price(mint, 5).unwrap();
+        state.set_price(mint, 7).unwrap();
+
+        assert_eq!(state.prices, vec![MintPrice { mint, amount: 7 }]);
+    }
+
+    #[test]
+    fn a_full_price_table_takes_no_new_mint() {
+        let mut state = dataset();
+        for amount in 1..=MAX_PRICES as u64 {
+            state.set_price(Pubkey::new_unique(), amount).unwrap();
+        }
+
+        assert_eq!(
+            state.set_price(Pubkey::new_unique(), 1),
+            Err(DataNexusError::PriceTableFull)
+        );
+        // Priced mints can still be updated
+        let mint = state.prices[0].mint;
+        state.set_price(mint, 9).unwrap();
+        assert_eq!(repack(&state).price(&mint), Some(9));
+    }
+
+    #[test]
+    fn removing_a_price_keeps_the_order_of_the_rest() {
+        let mut state = dataset();
+        let mints: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        for mint in &mints {
+            state.set_price(*mint, 1).unwrap();
+        }
+
+        state.remove_price(&mints[0]).unwrap();
+        let state = repack(&state);
+        let priced: Vec<_> = state.prices.iter().map(|price| price.mint).collect();
+        assert_eq!(priced, mints[1..]);
+
+        let mut state = state;
+        assert_eq!(
+            state.remove_price(&mints[0]),
+            Err(DataNexusError::UnsupportedMint)
+        );
+    }
+
+    #[test]
+    fn tags_survive_adding_and_removing() {
+        let mut state = dataset();
+        let tags: Vec<_> = ["finance", "weekly", "eu"]
+            .iter()
+            .map(|name| pack_tag(name).unwrap())
+            .collect();
+        for tag in &tags {
+            state.add_tag(*tag).unwrap();
+        }
+        assert_eq!(repack(&state).tag_names(), ["finance", "weekly", "eu"]);
+
+        state.remove_tag(&tags[0]).unwrap();
+        let mut state = repack(&state);
+        assert_eq!(state.tag_names(), ["weekly", "eu"]);
+        assert_eq!(state.remove_tag(&tags[0]), Err(DataNexusError::TagNotFound));
+
+        state.add_tag(tags[0]).unwrap();
+        assert_eq!(repack(&state).tag_names(), ["weekly", "eu", "finance"]);
+    }
+
+    #[test]
+    fn tags_are_packed_contiguously_at_their_offset() {
+        let mut state = dataset();
+        state.add_tag([1; TAG_LEN]).unwrap();
+        state.add_tag([2; TAG_LEN]).unwrap();
+        state.add_tag([3; TAG_LEN]).unwrap();
+        state.remove_tag(&[2; TAG_LEN]).unwrap();
+
+        let mut data = vec![0; DatasetState::LEN];
+        state.pack_into_slice(&mut data);
+
+        let tags = &data[DatasetState::TAGS_OFFSET..DatasetState::V8_LEN];
+        let mut expected = [0u8; TAG_LEN * MAX_TAGS];
+        expected[..TAG_LEN].copy_from_slice(&[1; TAG_LEN]);
+        expected[TAG_LEN..2 * TAG_LEN].copy_from_slice(&[3; TAG_LEN]);
+        assert_eq!(tags, expected);
+    }
+
+    #[test]
+    fn duplicate_zero_and_ninth_tags_are_refused() {
+        let mut state = dataset();
+        state.add_tag([1; TAG_LEN]).unwrap();
+        assert_eq!(
+            state.add_tag([1; TAG_LEN]),
+            Err(DataNexusError::DuplicateTag)
+        );
+        assert_eq!(state.add_tag([0; TAG_LEN]), Err(DataNexusError::InvalidTag));
+
+        for byte in 2..=MAX_TAGS as u8 {
+            state.add_tag([byte; TAG_LEN]).unwrap();
+        }
+        assert_eq!(
+            state.add_tag([0xff; TAG_LEN]),
+            Err(DataNexusError::TagsFull)
+        );
+        assert_eq!(repack(&state).tags.len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn tag_names_fit_in_a_slot() {
+        assert_eq!(
+            pack_tag("finance").map(|tag| tag[..7] == *b"finance"),
+            Some(true)
+        );
+        assert!(pack_tag(&"x".repeat(TAG_LEN)).is_some());
+        assert_eq!(pack_tag(&"x".repeat(TAG_LEN + 1)), None);
+        assert_eq!(pack_tag(""), None);
+        assert_eq!(pack_tag("a\0b"), None);
+    }
+
+    #[test]
+    fn derived_discounts_round_in_the_owners_favour() {
+        let mut state = dataset();
+        assert_eq!(state.derived_price(1_000), 1_000);
+
+        state.derived_discount_bps = 2_500;
+        assert_eq!(state.derived_price(1_000), 750);
+        // 25% of 3 is 0.75, the buyer pays the fraction
+        assert_eq!(state.derived_price(3), 3);
+        assert_eq!(state.derived_price(7), 6);
+        assert_eq!(state.derived_price(u64::MAX), u64::MAX - u64::MAX / 4);
+
+        state.derived_discount_bps = MAX_BPS;
+        assert_eq!(state.derived_price(1_000), 0);
+        assert_eq!(repack(&state).derived_discount_bps, MAX_BPS);
+    }
+
+    #[test]
+    fn insert_takes_the_first_free_slot() {
+        let mut index = index(3);
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert_eq!(index.insert(first, [1; 32]), Ok(0));
+        assert_eq!(index.insert(second, [2; 32]), Ok(1));
+        assert_eq!(index.position(&second), Some(1));
+        assert_eq!(index.find_by_hash(&[2; 32]), Some(second));
+    }
+
+    #[test]
+    fn insert_returns_the_slot_of_a_duplicate() {
+        let mut index = index(3);
+        let key = Pubkey::new_unique();
+        index.insert(key, [1; 32]).unwrap();
+
+        assert_eq!(index.insert(key, [1; 32]), Ok(0));
+        assert_eq!(index.datasets.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn insert_into_a_full_index_fails() {
+        let mut index = index(2);
+        index.insert(Pubkey::new_unique(), [1; 32]).unwrap();
+        index.insert(Pubkey::new_unique(), [2; 32]).unwrap();
+
+        assert_eq!(
+            index.insert(Pubkey::new_unique(), [3; 32]),
+            Err(DataNexusError::IndexFull)
+        );
+    }
+
+    #[test]
+    fn remove_frees_the_slot_for_reuse() {
+        let mut index = index(2);
+        let (first, second, third) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        index.insert(first, [1; 32]).unwrap();
+        index.insert(second, [2; 32]).unwrap();
+
+        assert_eq!(index.remove(&first), Some(0));
+        assert_eq!(index.position(&first), None);
+        assert_eq!(index.insert(third, [3; 32]), Ok(0));
+    }
+
+    #[test]
+    fn remove_of_an_absent_key_is_none() {
+        let mut index = index(2);
+        index.insert(Pubkey::new_unique(), [1; 32]).unwrap();
+
+        assert_eq!(index.remove(&Pubkey::new_unique()), None);
+        assert_eq!(index.datasets.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn find_entry_locates_the_hash() {
+        let state = access_state(&[[1; 32], [2; 32], [3; 32]]);
+
+        assert_eq!(state.find_entry(&[1; 32]), Some(0));
+        assert_eq!(state.find_entry(&[3; 32]), Some(2));
+    }
+
+    #[test]
+    fn find_entry_misses_absent_hashes() {
+        assert_eq!(access_state(&[]).find_entry(&[1; 32]), None);
+        assert_eq!(access_state(&[[2; 32]]).find_entry(&[1; 32]), None);
+    }
+
+    #[test]
+    fn find_entry_returns_the_first_duplicate() {
+        let state = access_state(&[[2; 32], [1; 32], [1; 32]]);
+
+        assert_eq!(state.find_entry(&[1; 32]), Some(1));
+    }
+
+    #[test]
+    fn find_entry_survives_packing() {
+        let state = access_state(&[[1; 32], [2; 32]]);
+        let mut data = vec![0; AccessState::LEN];
+        state.pack_into_slice(&mut data);
+
+        let unpacked = AccessState::unpack_from_slice(&data).unwrap();
+        assert_eq!(unpacked.find_entry(&[2; 32]), Some(1));
+    }
+
+    /// Feeds `unpack` every prefix of `packed` short of its full length
+    fn assert_truncations_fail<T>(
+        packed: &[u8],
+        unpack: impl Fn(&[u8]) -> Result<T, ProgramError>,
+    ) {
+        for len in 0..packed.len() {
+            let mut truncated = packed.to_vec();
+            truncated.truncate(len);
+            assert!(
+                unpack(&truncated).is_err(),
+                "unpacked {} of {} bytes",
+                len,
+                packed.len()
+            );
+        }
+    }
+
+    fn packed<T: Pack>(state: &T, len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        state.pack_into_slice(&mut data);
+        data
+    }
+
+    /// Numbers from a fixed xorshift sequence, so hole patterns vary across
+    /// cases but every run sees the same ones
+    struct Xorshift(u64);
+
+    impl Xorshift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Packed index of `capacity` slots holding `len` entries, with the
+    /// slots for which `hole` holds zeroed as removals leave them
+    fn holed_index(capacity: usize, len: usize, mut hole: impl FnMut(usize) -> bool) -> Vec<u8> {
+        let mut index = index(capacity);
+        for slot in 0..len {
+            index
+                .insert(Pubkey::new_unique(), [slot as u8; 32])
+                .unwrap();
+        }
+        let mut data = packed(&index, AccountIndex::space(capacity).unwrap());
+        data[IndexLayout::CURRENT.entries..]
+            .chunks_exact_mut(AccountIndex::ENTRY_LEN)
+            .take(len)
+            .enumerate()
+            .filter(|(slot, _)| hole(*slot))
+            .for_each(|(_, entry)| entry.fill(0));
+        data
+    }
+
+    fn entries(data: &[u8]) -> Vec<IndexEntry> {
+        AccountIndex::unpack_from_slice(data)
+            .unwrap()
+            .datasets
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn compacting_keeps_every_entry_in_order() {
+        let mut rng = Xorshift(0x2545_f491_4f6c_dd1d);
+        for case in 0..64 {
+            let capacity = 1 + (rng.next() % 200) as usize;
+            let len = (rng.next() as usize) % (capacity + 1);
+            // Sparse to dense hole patterns
+            let density = case % 4 + 1;
+            let mut data = holed_index(capacity, len, |_| rng.next() % 5 < density);
+            let before = entries(&data);
+
+            let kept = AccountIndex::compact_raw(&mut data).unwrap();
+
+            assert_eq!(kept, before.len());
+            assert_eq!(entries(&data), before);
+            let index = AccountIndex::unpack_from_slice(&data).unwrap();
+            assert!(index.datasets[..kept].iter().all(Option::is_some));
+            assert!(index.datasets[kept..].iter().all(Option::is_none));
+            assert_eq!(index.capacity(), capacity);
+            // Compacted indexes append right after their last entry
+            if kept < capacity {
+                let key = Pubkey::new_unique();
+                assert_eq!(
+                    AccountIndex::append_raw(&mut data, &key, &[9; 32]),
+                    Ok(kept)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compacting_legacy_indexes_moves_keys_forward() {
+        let keys: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut data = vec![0; AccountIndex::LEGACY_LEN];
+        data[0] = 1;
+        let mut slots: Vec<_> = data[IndexLayout::LEGACY.entries..]
+            .chunks_exact_mut(32)
+            .collect();
+        for (slot, key) in [3, 9, 10, 100].iter().zip(&keys) {
+            slots[*slot].copy_from_slice(key.as_ref());
+        }
+
+        assert_eq!(AccountIndex::compact_raw(&mut data), Ok(4));
+
+        let index = AccountIndex::unpack_from_slice(&data).unwrap();
+        let compacted: Vec<_> = index.datasets[..4]
+            .iter()
+            .map(|entry| entry.unwrap().dataset)
+            .collect();
+        assert_eq!(compacted, keys);
+        assert!(index.datasets[4..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn truncated_indexes_fail_to_unpack() {
+        let mut index = index(2);
+        index.insert(Pubkey::new_unique(), [1; 32]).unwrap();
+        let data = packed(&index, AccountIndex::space(2).unwrap());
+
+        assert_truncations_fail(&data, AccountIndex::unpack_from_slice);
+        assert_truncations_fail(&data, AccountIndex::capacity_raw);
+    }
+
+    #[test]
+    fn truncated_datasets_fail_to_unpack() {
+        let mut state = dataset();
+        state.tiers = vec![PriceTier {
+            min_seats: 5,
+            unit_price: 80,
+        }];
+        state.claimable = vec![Claimable {
+            mint: Pubkey::new_unique(),
+            amount: 10,
+        }];
+        state.prices = vec![MintPrice {
+            mint: Pubkey::new_unique(),
+            amount: 20,
+        }];
+        let data = packed(&state, DatasetState::LEN);
+
+        assert_truncations_fail(&data, DatasetState::unpack_from_slice);
+        assert_truncations_fail(&data[1..], DatasetState::unpack_fields);
+    }
+
+    #[test]
+    fn truncated_access_fails_to_unpack() {
+        // Entries past the count are never read, a full account has none
+        let mut state = access_state(&[[1; 32]; MAX_ACCESS_ENTRIES]);
+        state.pending = vec![
+            PendingShare {
+                access: access([2; 32]),
+                shared_at: 3,
+            };
+            MAX_PENDING_SHARES
+        ];
+        let data = packed(&state, AccessState::LEN);
+
+        assert_truncations_fail(&data, AccessState::unpack_from_slice);
+        assert_truncations_fail(&data[1..], |src| {
+            AccessState::unpack_fields(src, AccessState::LAYOUT_VERSION)
+        });
+        assert_truncations_fail(
+            &packed(&access([1; 32]), AccessInfo::LEN),
+            AccessInfo::unpack_from_slice,
+        );
+    }
+
+    #[test]
+    fn truncated_collections_fail_to_unpack() {
+        let collection = CollectionState {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            hash: [1; 32],
+            value: Some(100),
+            members: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let access = CollectionAccess {
+            is_initialized: true,
+            collection: Pubkey::new_unique(),
+            purchased: true,
+            cursor: 1,
+        };
+
+        assert_truncations_fail(
+            &packed(&collection, CollectionState::LEN),
+            CollectionState::unpack_from_slice,
+        );
+        assert_truncations_fail(
+            &packed(&access, CollectionAccess::LEN),
+            CollectionAccess::unpack_from_slice,
+        );
+    }
+
+    #[test]
+    fn registry_pages_survive_packing() {
+        let mut page = registry(&[[1; 32], [2; 32], [3; 32]]);
+        page.page = 2;
+        page.next = Some(Pubkey::new_unique());
+        let data = packed(&page, RegistryState::space(3).unwrap());
+
+        assert_eq!(RegistryState::unpack_from_slice(&data), Ok(page.clone()));
+        assert_eq!(page.find(&[2; 32]), Some(page.entries[1].dataset));
+        assert_eq!(page.find(&[4; 32]), None);
+        assert!(!page.is_full());
+        assert_truncations_fail(&data, RegistryState::unpack_from_slice);
+
+        // A page holds no more entries than its length has room for
+        let mut overcounted = data;
+        overcounted[38..40].copy_from_slice(&4u16.to_le_bytes());
+        assert_eq!(
+            RegistryState::unpack_from_slice(&overcounted),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn packing_into_a_short_buffer_leaves_out_what_does_not_fit() {
+        let mut data = vec![0; 40];
+        dataset().pack_into_slice(&mut data);
+
+        assert_eq!(data.first(), Some(&DatasetState::LAYOUT_VERSION));
+        assert_eq!(
+            DatasetState::unpack_from_slice(&data).err(),
+            Some(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn every_account_decodes_to_its_own_kind() {
+        let mut index = index(MAX_INDEX_ENTRIES);
+        let key = Pubkey::new_unique();
+        index.insert(key, [3; 32]).unwrap();
+        let decoded = DataNexusAccount::decode(&packed(&index, AccountIndex::LEN)).unwrap();
+        assert_eq!(decoded.kind(), "index");
+        assert_eq!(
+            decoded.as_index().unwrap().datasets[0].unwrap().dataset,
+            key
+        );
+        assert!(decoded.as_dataset().is_none());
+        let legacy = DataNexusAccount::decode(&vec![0; AccountIndex::LEGACY_LEN]).unwrap();
+        assert_eq!(legacy.kind(), "index");
+
+        let state = dataset();
+        let decoded = DataNexusAccount::decode(&packed(&state, DatasetState::LEN)).unwrap();
+        assert_eq!(decoded.kind(), "dataset");
+        assert_eq!(decoded.as_dataset().unwrap().owner, state.owner);
+        assert!(decoded.as_access().is_none());
+
+        let decoded =
+            DataNexusAccount::decode(&packed(&access_state(&[[5; 32]]), AccessState::LEN)).unwrap();
+        assert_eq!(decoded.kind(), "access");
+        assert_eq!(decoded.as_access().unwrap().find_entry(&[5; 32]), Some(0));
+        assert!(decoded.as_index().is_none());
+
+        let collection = CollectionState {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            hash: [1; 32],
+            value: Some(100),
+            members: vec![Pubkey::new_unique()],
+        };
+        let decoded = DataNexusAccount::decode(&packed(&collection, CollectionState::LEN)).unwrap();
+        assert_eq!(decoded.kind(), "collection");
+        assert_eq!(decoded.as_collection().unwrap().members, collection.members);
+        assert!(decoded.as_collection_access().is_none());
+
+        let collection_access = CollectionAccess {
+            is_initialized: true,
+            collection: Pubkey::new_unique(),
+            purchased: true,
+            cursor: 1,
+        };
+        let decoded =
+            DataNexusAccount::decode(&packed(&collection_access, CollectionAccess::LEN)).unwrap();
+        assert_eq!(decoded.kind(), "collection access");
+        assert_eq!(decoded.as_collection_access(), Some(&collection_access));
+        assert!(decoded.as_collection().is_none());
+
+        for hashes in [&[][..], &[[1; 32]], &[[1; 32]; MAX_REGISTRY_ENTRIES]] {
+            let page = registry(hashes);
+            let data = packed(&page, RegistryState::space(hashes.len()).unwrap());
+            let decoded = DataNexusAccount::decode(&data).unwrap();
+            assert_eq!(decoded.kind(), "registry");
+            assert_eq!(decoded.as_registry(), Some(&page));
+            assert!(decoded.as_index().is_none());
+        }
+    }
+
+    #[test]
+    fn outdated_accounts_fail_to_decode_until_migrated() {
+        for len in [DatasetState::LEGACY_LEN, DatasetState::V6_LEN] {
+            let mut data = packed(&dataset(), DatasetState::LEN);
+            data.truncate(len);
+            if len == DatasetState::V6_LEN {
+                data[0] = 6;
+            }
+            assert_eq!(
+                DataNexusAccount::decode(&data).err(),
+                Some(DataNexusError::OutdatedLayout.into())
+            );
+        }
+        let mut v1_access = vec![0; AccessState::V1_LEN];
+        v1_access[0] = 1;
+        for data in [vec![0; AccessState::LEGACY_LEN], v1_access] {
+            assert_eq!(
+                DataNexusAccount::decode(&data).err(),
+                Some(DataNexusError::OutdatedLayout.into())
+            );
+        }
+    }
+
+    #[test]
+    fn shares_remaining_are_the_quota_less_those_used() {
+        let mut access = access([1; 32]);
+        access.share_quota = 3;
+        access.shares_used = 1;
+        assert_eq!(access.shares_remaining(), 2);
+
+        // Quotas are fixed at grant time, so may fall below the shares used
+        access.shares_used = 4;
+        assert_eq!(access.shares_remaining(), 0);
+    }
+
+    #[test]
+    fn v1_access_entries_migrate_with_their_shares_left_as_quota() {
+        let mut entry = access([1; 32]);
+        entry.share_quota = 5;
+        entry.expires_at = Some(1_000);
+        let mut src = vec![0; AccessState::V1_LEN];
+        src[0] = 1;
+        src[1] = 1;
+        src[34] = 1;
+        src[35..35 + AccessInfo::V1_LEN]
+            .copy_from_slice(&packed(&entry, AccessInfo::LEN)[..AccessInfo::V1_LEN]);
+
+        assert_eq!(migrated_len(&src), Some(AccessState::LEN));
+        let mut dst = vec![0; AccessState::LEN];
+        migrate_layout(&src, &mut dst).unwrap();
+
+        let migrated = AccessState::unpack_from_slice(&dst).unwrap();
+        assert_eq!(migrated.datasets, vec![entry]);
+        assert_eq!(migrated.datasets[0].shares_remaining(), 5);
+        assert_eq!(migrated_len(&dst), None);
+    }
+
+    #[test]
+    fn garbage_decodes_to_no_account() {
+        let mut unknown_version = packed(&dataset(), DatasetState::LEN);
+        unknown_version[0] = DatasetState::LAYOUT_VERSION + 1;
+        let mut unflagged_collection = vec![0; CollectionState::LEN];
+        unflagged_collection[1] = 1;
+        let mut oversized_index = packed(&index(MAX_INDEX_ENTRIES), AccountIndex::LEN);
+        oversized_index[36..38].copy_from_slice(&u16::MAX.to_le_bytes());
+
+        for data in [
+            vec![],
+            vec![1],
+            vec![0xff; 7],
+            vec![0xff; 5_000],
+            unknown_version,
+            unflagged_collection,
+            oversized_index,
+        ] {
+            assert_eq!(
+                DataNexusAccount::decode(&data).err(),
+                Some(ProgramError::InvalidAccountData),
+                "{} bytes",
+                data.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,576 @@
+//! Fixed-width views over the packed account layouts
+//!
+//! Each view is `#[repr(C)]` with an alignment of one and matches the `Pack`
+//! encoding of its state byte for byte, so handlers can read and write single
+//! fields in place instead of repacking the whole account. Optional fields use
+//! the `Pack` sentinels, all-zero is `None`.
+
+use bytemuck::{Pod, Zeroable};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::DataNexusError,
+    state::{
+        AccessState, DatasetState, MintPrice, PriceTier, Tag, CONTENT_TYPE_LEN, MAX_ACCESS_ENTRIES,
+        MAX_PENDING_SHARES, MAX_PRICES, MAX_PRICE_TIERS, MAX_TAGS, MAX_VAULTS, PENDING_SHARE_TTL,
+        TAG_LEN, URI_LEN,
+    },
+};
+
+macro_rules! pod_int {
+    ($name:ident, $int:ty, $len:expr) => {
+        /// Little-endian integer with an alignment of one
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+        #[repr(transparent)]
+        pub struct $name(pub [u8; $len]);
+
+        impl $name {
+            pub fn get(self) -> $int {
+                <$int>::from_le_bytes(self.0)
+            }
+
+            pub fn set(&mut self, n: $int) {
+                self.0 = n.to_le_bytes();
+            }
+        }
+
+        impl From<$int> for $name {
+            fn from(n: $int) -> Self {
+                Self(n.to_le_bytes())
+            }
+        }
+    };
+}
+
+pod_int!(PodU16, u16, 2);
+pod_int!(PodU32, u32, 4);
+pod_int!(PodU64, u64, 8);
+pod_int!(PodI64, i64, 8);
+
+fn key_or_none(key: [u8; 32]) -> Option<[u8; 32]> {
+    match key {
+        n if n == [0u8; 32] => None,
+        n => Some(n),
+    }
+}
+
+fn load<T: Pod>(data: &[u8]) -> Result<&T, ProgramError> {
+    data.get(..std::mem::size_of::<T>())
+        .and_then(|data| bytemuck::try_from_bytes(data).ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn load_mut<T: Pod>(data: &mut [u8]) -> Result<&mut T, ProgramError> {
+    data.get_mut(..std::mem::size_of::<T>())
+        .and_then(|data| bytemuck::try_from_bytes_mut(data).ok())
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// In-place view of a `PriceTier`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct PriceTierZC {
+    pub min_seats: PodU16,
+    pub unit_price: PodU64,
+}
+
+impl From<PriceTier> for PriceTierZC {
+    fn from(tier: PriceTier) -> Self {
+        Self {
+            min_seats: tier.min_seats.into(),
+            unit_price: tier.unit_price.into(),
+        }
+    }
+}
+
+/// In-place view of a `Claimable`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ClaimableZC {
+    pub mint: [u8; 32],
+    pub amount: PodU64,
+}
+
+impl ClaimableZC {
+    pub fn mint(&self) -> Pubkey {
+        Pubkey::new_from_array(self.mint)
+    }
+}
+
+/// In-place view of a `MintPrice`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct MintPriceZC {
+    pub mint: [u8; 32],
+    pub amount: PodU64,
+}
+
+impl MintPriceZC {
+    pub fn mint(&self) -> Pubkey {
+        Pubkey::new_from_array(self.mint)
+    }
+}
+
+impl From<MintPrice> for MintPriceZC {
+    fn from(price: MintPrice) -> Self {
+        Self {
+            mint: price.mint.to_bytes(),
+            amount: price.amount.into(),
+        }
+    }
+}
+
+/// In-place view of a `DatasetState`
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct DatasetStateZC {
+    pub version: u8,
+    pub is_initialized: u8,
+    pub flag: u8,
+    pub owner: [u8; 32],
+    pub hash: [u8; 32],
+    pub key: [u8; 32],
+    pub value: PodU64,
+    pub share_limit: PodU16,
+    pub purchase_count: PodU32,
+    pub referral_bps: PodU16,
+    pub access_duration: PodI64,
+    pub uri: [u8; URI_LEN],
+    pub size_bytes: PodU64,
+    pub content_type: [u8; CONTENT_TYPE_LEN],
+    pub total_revenue: PodU64,
+    pub share_count: PodU32,
+    pub ref_data: [u8; 32],
+    pub resharable: u8,
+    pub max_share_depth: u8,
+    pub tier_count: u8,
+    pub tiers: [PriceTierZC; MAX_PRICE_TIERS],
+    pub vault_count: u8,
+    pub claimable: [ClaimableZC; MAX_VAULTS],
+    pub holder_count: PodU32,
+    pub max_holders: PodU32,
+    pub pending_owner: [u8; 32],
+    pub price_count: u8,
+    pub prices: [MintPriceZC; MAX_PRICES],
+    pub sale_start: PodI64,
+    pub sale_end: PodI64,
+    pub prune_bounty: PodU64,
+    pub bounty_pool: PodU64,
+    pub share_fee: MintPriceZC,
+    pub tags: [Tag; MAX_TAGS],
+    pub derived_discount_bps: PodU16,
+    pub receipt_count: PodU32,
+}
+
+impl DatasetStateZC {
+    pub fn is_initialized(&self) -> bool {
+        self.is_initialized == 1
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(self.owner)
+    }
+
+    pub fn key(&self) -> Option<[u8; 32]> {
+        key_or_none(self.key)
+    }
+
+    pub fn set_key(&mut self, key: Option<[u8; 32]>) {
+        self.key = key.unwrap_or([0u8; 32]);
+    }
+
+    pub fn value(&self) -> Option<u64> {
+        match self.value.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_value(&mut self, value: Option<u64>) {
+        self.value.set(value.unwrap_or(0));
+    }
+
+    pub fn share_limit(&self) -> Option<u16> {
+        match self.share_limit.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_share_limit(&mut self, share_limit: Option<u16>) {
+        self.share_limit.set(share_limit.unwrap_or(0));
+    }
+
+    pub fn access_duration(&self) -> Option<i64> {
+        match self.access_duration.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_access_duration(&mut self, access_duration: Option<i64>) {
+        self.access_duration.set(access_duration.unwrap_or(0));
+    }
+
+    pub fn ref_data(&self) -> Option<Pubkey> {
+        key_or_none(self.ref_data).map(Pubkey::new_from_array)
+    }
+
+    pub fn set_ref_data(&mut self, ref_data: Option<Pubkey>) {
+        self.ref_data = ref_data.map_or([0u8; 32], |ref_data| ref_data.to_bytes());
+    }
+
+    pub fn resharable(&self) -> bool {
+        self.resharable == 1
+    }
+
+    pub fn set_resharable(&mut self, resharable: bool) {
+        self.resharable = resharable as u8;
+    }
+
+    pub fn max_share_depth(&self) -> Option<u8> {
+        match self.max_share_depth {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_max_share_depth(&mut self, max_share_depth: Option<u8>) {
+        self.max_share_depth = max_share_depth.unwrap_or(0);
+    }
+
+    /// Tiers in use
+    pub fn tiers(&self) -> &[PriceTierZC] {
+        let count = (self.tier_count as usize).min(MAX_PRICE_TIERS);
+        self.tiers.get(..count).unwrap_or_default()
+    }
+
+    /// Replaces the tier table, clearing the slots `tiers` leaves unused
+    pub fn set_tiers(&mut self, tiers: &[PriceTier]) -> Result<(), DataNexusError> {
+        if tiers.len() > MAX_PRICE_TIERS {
+            return Err(DataNexusError::InvalidPriceTiers);
+        }
+
+        self.tiers = [PriceTierZC::default(); MAX_PRICE_TIERS];
+        for (dst, tier) in self.tiers.iter_mut().zip(tiers) {
+            *dst = (*tier).into();
+        }
+        self.tier_count = tiers.len() as u8;
+
+        Ok(())
+    }
+
+    /// Proceeds of the vaults in use
+    pub fn claimable(&self) -> &[ClaimableZC] {
+        let count = (self.vault_count as usize).min(MAX_VAULTS);
+        self.claimable.get(..count).unwrap_or_default()
+    }
+
+    pub fn claimable_mut(&mut self) -> &mut [ClaimableZC] {
+        let count = (self.vault_count as usize).min(MAX_VAULTS);
+        self.claimable.get_mut(..count).unwrap_or_default()
+    }
+
+    pub fn max_holders(&self) -> Option<u32> {
+        match self.max_holders.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_max_holders(&mut self, max_holders: Option<u32>) {
+        self.max_holders.set(max_holders.unwrap_or(0));
+    }
+
+    pub fn pending_owner(&self) -> Option<Pubkey> {
+        key_or_none(self.pending_owner).map(Pubkey::new_from_array)
+    }
+
+    /// Entries of the price table in use
+    pub fn prices(&self) -> &[MintPriceZC] {
+        let count = (self.price_count as usize).min(MAX_PRICES);
+        self.prices.get(..count).unwrap_or_default()
+    }
+
+    /// Same as `DatasetState::price`
+    pub fn price(&self, mint: &Pubkey) -> Option<u64> {
+        if self.prices().is_empty() {
+            return self.value();
+        }
+
+        self.prices()
+            .iter()
+            .find(|price| price.mint() == *mint)
+            .map(|price| price.amount.get())
+    }
+
+    /// Same as `DatasetState::set_price`
+    pub fn set_price(&mut self, mint: Pubkey, amount: u64) -> Result<(), DataNexusError> {
+        let count = self.prices().len();
+        let index = self
+            .prices()
+            .iter()
+            .position(|price| price.mint() == mint)
+            .unwrap_or(count);
+        let price = self
+            .prices
+            .get_mut(index)
+            .ok_or(DataNexusError::PriceTableFull)?;
+        *price = MintPriceZC {
+            mint: mint.to_bytes(),
+            amount: amount.into(),
+        };
+        self.price_count = count.max(index.saturating_add(1)) as u8;
+
+        Ok(())
+    }
+
+    pub fn sale_start(&self) -> Option<i64> {
+        match self.sale_start.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn sale_end(&self) -> Option<i64> {
+        match self.sale_end.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    pub fn set_sale_end(&mut self, sale_end: Option<i64>) {
+        self.sale_end.set(sale_end.unwrap_or(0));
+    }
+
+    pub fn share_fee(&self) -> Option<MintPrice> {
+        match self.share_fee.amount.get() {
+            0 => None,
+            amount => Some(MintPrice {
+                mint: self.share_fee.mint(),
+                amount,
+            }),
+        }
+    }
+
+    pub fn set_share_fee(&mut self, share_fee: Option<MintPrice>) {
+        self.share_fee = share_fee.map(MintPriceZC::from).unwrap_or_default();
+    }
+
+    /// Tags in use, the slots up to the first all-zero one
+    pub fn tags(&self) -> &[Tag] {
+        let count = self
+            .tags
+            .iter()
+            .position(|tag| *tag == [0u8; TAG_LEN])
+            .unwrap_or(MAX_TAGS);
+        self.tags.get(..count).unwrap_or(&[])
+    }
+
+    /// Same as `DatasetState::add_tag`
+    pub fn add_tag(&mut self, tag: Tag) -> Result<(), DataNexusError> {
+        if tag == [0u8; TAG_LEN] {
+            return Err(DataNexusError::InvalidTag);
+        }
+        if self.tags().contains(&tag) {
+            return Err(DataNexusError::DuplicateTag);
+        }
+        let count = self.tags().len();
+        *self.tags.get_mut(count).ok_or(DataNexusError::TagsFull)? = tag;
+
+        Ok(())
+    }
+
+    /// Same as `DatasetState::remove_tag`, the tags after the removed one
+    /// move up in its place
+    pub fn remove_tag(&mut self, tag: &Tag) -> Result<(), DataNexusError> {
+        let count = self.tags().len();
+        let index = self
+            .tags()
+            .iter()
+            .position(|t| t == tag)
+            .ok_or(DataNexusError::TagNotFound)?;
+        if let Some(tags) = self.tags.get_mut(index..count) {
+            tags.rotate_left(1);
+        }
+        if let Some(last) = self.tags.get_mut(count.saturating_sub(1)) {
+            *last = [0u8; TAG_LEN];
+        }
+
+        Ok(())
+    }
+
+    /// Same as `DatasetState::remove_price`, the entries after the removed
+    /// one move up in its place
+    pub fn remove_price(&mut self, mint: &Pubkey) -> Result<(), DataNexusError> {
+        let count = self.prices().len();
+        let index = self
+            .prices()
+            .iter()
+            .position(|price| price.mint() == *mint)
+            .ok_or(DataNexusError::UnsupportedMint)?;
+        if let Some(prices) = self.prices.get_mut(index..count) {
+            prices.rotate_left(1);
+        }
+        let count = count.saturating_sub(1);
+        if let Some(last) = self.prices.get_mut(count) {
+            *last = MintPriceZC::default();
+        }
+        self.price_count = count as u8;
+
+        Ok(())
+    }
+}
+
+impl DatasetState {
+    /// Borrows the packed dataset in `data` without unpacking it, datasets
+    /// of an older layout fail with `OutdatedLayout`
+    pub fn load(data: &[u8]) -> Result<&DatasetStateZC, ProgramError> {
+        Self::check_layout(data)?;
+        load(data)
+    }
+
+    /// Mutably borrows the packed dataset in `data` without unpacking it
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut DatasetStateZC, ProgramError> {
+        Self::check_layout(data)?;
+        load_mut(data)
+    }
+}
+
+/// In-place view of an `AccessInfo`
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct AccessInfoZC {
+    pub hash: [u8; 32],
+    pub key: [u8; 32],
+    pub shared_from: [u8; 32],
+    pub share_quota: PodU16,
+    pub expires_at: PodI64,
+    pub depth: u8,
+    pub shares_used: PodU16,
+}
+
+impl AccessInfoZC {
+    pub fn key(&self) -> Option<[u8; 32]> {
+        key_or_none(self.key)
+    }
+
+    pub fn shared_from(&self) -> Option<Pubkey> {
+        key_or_none(self.shared_from).map(Pubkey::new_from_array)
+    }
+
+    pub fn expires_at(&self) -> Option<i64> {
+        match self.expires_at.get() {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    /// Shares the grant has left
+    pub fn shares_remaining(&self) -> u16 {
+        self.share_quota
+            .get()
+            .saturating_sub(self.shares_used.get())
+    }
+
+    /// Whether the grant has lapsed at unix timestamp `now`, perpetual
+    /// grants never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        match self.expires_at() {
+            Some(expires_at) => expires_at <= now,
+            None => false,
+        }
+    }
+}
+
+/// In-place view of a `PendingShare`
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct PendingShareZC {
+    pub access: AccessInfoZC,
+    pub shared_at: PodI64,
+}
+
+impl PendingShareZC {
+    /// Whether the share has waited `PENDING_SHARE_TTL` at unix timestamp
+    /// `now`, lapsed shares can no longer be accepted
+    pub fn is_lapsed(&self, now: i64) -> bool {
+        self.shared_at.get().saturating_add(PENDING_SHARE_TTL) <= now
+    }
+}
+
+/// In-place view of an `AccessState`
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct AccessStateZC {
+    pub version: u8,
+    pub is_initialized: u8,
+    pub pointer: [u8; 32],
+    pub count: u8,
+    pub datasets: [AccessInfoZC; MAX_ACCESS_ENTRIES],
+    pub pending_count: u8,
+    pub pending: [PendingShareZC; MAX_PENDING_SHARES],
+}
+
+impl AccessStateZC {
+    pub fn is_initialized(&self) -> bool {
+        self.is_initialized == 1
+    }
+
+    /// Entries in use
+    pub fn entries(&self) -> &[AccessInfoZC] {
+        let count = (self.count as usize).min(MAX_ACCESS_ENTRIES);
+        self.datasets.get(..count).unwrap_or_default()
+    }
+
+    pub fn entries_mut(&mut self) -> &mut [AccessInfoZC] {
+        let count = (self.count as usize).min(MAX_ACCESS_ENTRIES);
+        self.datasets.get_mut(..count).unwrap_or_default()
+    }
+
+    /// Shares awaiting approval
+    pub fn pending_shares(&self) -> &[PendingShareZC] {
+        let count = (self.pending_count as usize).min(MAX_PENDING_SHARES);
+        self.pending.get(..count).unwrap_or_default()
+    }
+
+    /// Position of the entry granting access to `hash`
+    pub fn find_entry(&self, hash: &[u8; 32]) -> Option<usize> {
+        self.entries()
+            .iter()
+            .position(|access| access.hash == *hash)
+    }
+
+    /// Appends `access` after the entries in use, returning its position
+    pub fn push(&mut self, access: AccessInfoZC) -> Result<usize, DataNexusError> {
+        let position = self.count as usize;
+        let slot = self
+            .datasets
+            .get_mut(position)
+            .ok_or(DataNexusError::IndexFull)?;
+        *slot = access;
+
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(DataNexusError::NumericalOverflow)?;
+
+        Ok(position)
+    }
+}
+
+impl AccessState {
+    /// Borrows the packed access entries in `data` without unpacking them,
+    /// accounts of an older layout fail with `OutdatedLayout`
+    pub fn load(data: &[u8]) -> Result<&AccessStateZC, ProgramError> {
+        Self::check_layout(data)?;
+        load(data)
+    }
+
+    /// Mutably borrows the packed access entries in `data` without
+    /// unpacking them
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut AccessStateZC, ProgramError> {
+        Self::check_layout(data)?;
+        load_mut(data)
+    }
+}
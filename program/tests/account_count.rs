@@ -0,0 +1,461 @@
+//! Every instruction checks its number of accounts before reading any, so
+//! one missing or one extra fails with `InvalidAccountCount`
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{
+        AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitDatasetAccounts, InitReceiptMintAccounts,
+        InitVaultAccounts, MemberAccounts, MigrateAccountAccounts, OwnershipTransferAccounts,
+        PurchaseAccessAccounts, PurchaseCollectionAccounts, ReceiptAccounts, RecipientAccounts,
+        RegistryAccounts, RejectShareAccounts, RenewAccessAccounts, ResizeIndexAccounts,
+        RevokeAccessAccounts, SetAccessPreferencesAccounts, SetDataParamsAccounts,
+        ShareAccessAccounts, ShareAccessMultiAccounts, ShareFeeAccounts, WithdrawProceedsAccounts,
+    },
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{
+        accept_ownership, accept_share, add_to_collection, cancel_ownership_transfer,
+        claim_collection_access, close_dataset, compact_index, crank_prune, create_collection,
+        fund_bounty, init_account, init_associated_access, init_dataset_account, init_receipt_mint,
+        init_vault, migrate_account, propose_ownership_transfer, purchase_access,
+        purchase_collection, purchase_seats, reject_share, renew_access, resize_index,
+        revoke_access, set_access_preferences, set_data_params, share_access, share_access_multi,
+        withdraw_proceeds, AccountType, Params,
+    },
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+fn key() -> Pubkey {
+    Pubkey::new_unique()
+}
+
+fn receipt() -> ReceiptAccounts<Pubkey> {
+    ReceiptAccounts {
+        receipt_mint: key(),
+        receipt_token_account: key(),
+        token_program: key(),
+    }
+}
+
+fn purchase_accounts(signer: Pubkey, most: bool) -> PurchaseAccessAccounts<Pubkey> {
+    PurchaseAccessAccounts {
+        user_authority: signer,
+        user_access_index: key(),
+        user_access_account: key(),
+        user_token_account: key(),
+        owner_authority: key(),
+        proceeds_account: key(),
+        token_mint: key(),
+        dataset_account: key(),
+        token_program: key(),
+        referrer_token_account: Some(key()).filter(|_| most),
+        receipt: Some(receipt()).filter(|_| most),
+        reference_access: Some(key()).filter(|_| most),
+    }
+}
+
+fn member() -> MemberAccounts<Pubkey> {
+    MemberAccounts {
+        dataset_account: key(),
+        access_account: key(),
+    }
+}
+
+/// Every instruction signed by `signer`, with the fewest accounts it takes
+/// when `most` is false and the most otherwise. Instructions taking groups
+/// of accounts get one group
+fn instructions(signer: Pubkey, collection_account: Pubkey, most: bool) -> Vec<Instruction> {
+    let program_id = datanexus_program::id();
+
+    vec![
+        init_account(
+            program_id,
+            None,
+            signer,
+            key(),
+            None,
+            key(),
+            AccountType::DatasetIndex,
+        )
+        .unwrap(),
+        init_dataset_account(
+            program_id,
+            InitDatasetAccounts {
+                authority: signer,
+                owner_index: key(),
+                dataset_account: key(),
+                system_program: key(),
+                registry: Some(RegistryAccounts {
+                    registry_account: key(),
+                    overflow_account: Some(key()),
+                })
+                .filter(|_| most),
+            },
+            HASH,
+        )
+        .unwrap(),
+        set_data_params(
+            program_id,
+            SetDataParamsAccounts {
+                authority: signer,
+                dataset_account: key(),
+                reference_dataset: Some(key()).filter(|_| most),
+            },
+            HASH,
+            Params::Value(1),
+            &[],
+        )
+        .unwrap(),
+        purchase_access(program_id, purchase_accounts(signer, most), HASH, 1).unwrap(),
+        purchase_seats(program_id, purchase_accounts(signer, most), HASH, 2, 1).unwrap(),
+        share_access(
+            program_id,
+            ShareAccessAccounts {
+                user_authority: signer,
+                user_access_account: key(),
+                recipient_authority: key(),
+                recipient_access_index: key(),
+                recipient_access_account: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            None,
+            Some(ShareFeeAccounts {
+                sharer_token_account: key(),
+                owner_token_account: key(),
+                token_mint: key(),
+                token_program: key(),
+            })
+            .filter(|_| most),
+        )
+        .unwrap(),
+        close_dataset(
+            program_id,
+            CloseDatasetAccounts {
+                authority: signer,
+                owner_index: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            false,
+        )
+        .unwrap(),
+        renew_access(
+            program_id,
+            RenewAccessAccounts {
+                user_authority: signer,
+                user_access_account: key(),
+                user_token_account: key(),
+                proceeds_account: key(),
+                token_mint: key(),
+                dataset_account: key(),
+                token_program: key(),
+                referrer_token_account: Some(key()).filter(|_| most),
+            },
+            HASH,
+            1,
+        )
+        .unwrap(),
+        share_access_multi(
+            program_id,
+            ShareAccessMultiAccounts {
+                user_authority: signer,
+                user_access_account: key(),
+                dataset_account: key(),
+                recipients: vec![RecipientAccounts {
+                    authority: key(),
+                    access_index: key(),
+                    access_account: key(),
+                }],
+            },
+            HASH,
+        )
+        .unwrap(),
+        init_associated_access(
+            program_id,
+            InitAssociatedAccessAccounts {
+                payer: signer,
+                authority: signer,
+                access_index: key(),
+                associated_access_account: key(),
+                dataset_account: key(),
+                system_program: key(),
+            },
+            HASH,
+        )
+        .unwrap(),
+        resize_index(
+            program_id,
+            ResizeIndexAccounts {
+                payer: signer,
+                authority: signer,
+                index_account: key(),
+                system_program: key(),
+            },
+            1,
+        )
+        .unwrap(),
+        create_collection(
+            program_id,
+            CreateCollectionAccounts {
+                authority: signer,
+                collection_account: key(),
+                system_program: key(),
+            },
+            HASH,
+            1,
+        )
+        .unwrap(),
+        add_to_collection(
+            program_id,
+            AddToCollectionAccounts {
+                authority: signer,
+                collection_account: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            HASH,
+        )
+        .unwrap(),
+        // Sent as `PurchaseAccess`, only told apart by the collection
+        purchase_collection(
+            program_id,
+            PurchaseCollectionAccounts {
+                user_authority: signer,
+                user_access_index: key(),
+                collection_access_account: key(),
+                user_token_account: key(),
+                owner_authority: key(),
+                owner_token_account: key(),
+                token_mint: key(),
+                collection_account,
+                token_program: key(),
+                members: vec![member()],
+            },
+            HASH,
+            1,
+        )
+        .unwrap(),
+        claim_collection_access(
+            program_id,
+            ClaimCollectionAccessAccounts {
+                user_authority: signer,
+                user_access_index: key(),
+                collection_access_account: key(),
+                collection_account: key(),
+                members: vec![member()],
+            },
+            HASH,
+        )
+        .unwrap(),
+        init_vault(
+            program_id,
+            InitVaultAccounts {
+                payer: signer,
+                owner_authority: signer,
+                dataset_account: key(),
+                token_mint: key(),
+                vault_account: key(),
+                token_program: key(),
+                system_program: key(),
+            },
+            HASH,
+        )
+        .unwrap(),
+        withdraw_proceeds(
+            program_id,
+            WithdrawProceedsAccounts {
+                owner_authority: signer,
+                dataset_account: key(),
+                vault_account: key(),
+                token_mint: key(),
+                destination_token_account: key(),
+                token_program: key(),
+            },
+            HASH,
+            1,
+            &[],
+        )
+        .unwrap(),
+        init_receipt_mint(
+            program_id,
+            InitReceiptMintAccounts {
+                payer: signer,
+                dataset_account: key(),
+                receipt_mint: key(),
+                token_program: key(),
+                system_program: key(),
+            },
+            HASH,
+        )
+        .unwrap(),
+        revoke_access(
+            program_id,
+            RevokeAccessAccounts {
+                owner_authority: signer,
+                dataset_account: key(),
+                holder_authority: key(),
+                holder_access_account: key(),
+                receipt: Some(receipt()).filter(|_| most),
+            },
+            HASH,
+        )
+        .unwrap(),
+        migrate_account(
+            program_id,
+            MigrateAccountAccounts {
+                payer: signer,
+                account: key(),
+                system_program: key(),
+            },
+        )
+        .unwrap(),
+        propose_ownership_transfer(
+            program_id,
+            OwnershipTransferAccounts {
+                authority: signer,
+                dataset_account: key(),
+            },
+            HASH,
+            key(),
+            &[],
+        )
+        .unwrap(),
+        accept_ownership(
+            program_id,
+            AcceptOwnershipAccounts {
+                authority: signer,
+                old_owner_index: key(),
+                new_owner_index: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            &[],
+        )
+        .unwrap(),
+        cancel_ownership_transfer(
+            program_id,
+            OwnershipTransferAccounts {
+                authority: signer,
+                dataset_account: key(),
+            },
+            HASH,
+            &[],
+        )
+        .unwrap(),
+        crank_prune(
+            program_id,
+            CrankPruneAccounts {
+                cranker: signer,
+                dataset_account: key(),
+                holder_access_account: key(),
+            },
+            HASH,
+            key(),
+        )
+        .unwrap(),
+        fund_bounty(
+            program_id,
+            FundBountyAccounts {
+                authority: signer,
+                dataset_account: key(),
+                system_program: key(),
+            },
+            HASH,
+            1,
+        )
+        .unwrap(),
+        compact_index(
+            program_id,
+            CompactIndexAccounts {
+                authority: signer,
+                index_account: key(),
+            },
+            false,
+            0,
+        )
+        .unwrap(),
+        set_access_preferences(
+            program_id,
+            SetAccessPreferencesAccounts {
+                authority: signer,
+                access_index: key(),
+            },
+            true,
+        )
+        .unwrap(),
+        accept_share(
+            program_id,
+            AcceptShareAccounts {
+                authority: signer,
+                access_index: key(),
+                access_account: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            key(),
+        )
+        .unwrap(),
+        reject_share(
+            program_id,
+            RejectShareAccounts {
+                authority: signer,
+                recipient_authority: key(),
+                recipient_access_account: key(),
+                sharer_access_account: key(),
+                dataset_account: key(),
+            },
+            HASH,
+            key(),
+        )
+        .unwrap(),
+    ]
+}
+
+/// A bank holding a collection, so that purchases naming it are handled as
+/// collection purchases
+async fn setup() -> (Harness, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_collection(&owner, HASH, 100).await.unwrap();
+    let signer = harness.fund(LAMPORTS_PER_SOL).await;
+
+    (harness, signer)
+}
+
+#[tokio::test]
+async fn every_instruction_rejects_a_missing_account() {
+    let (mut harness, signer) = setup().await;
+
+    for mut instruction in instructions(signer.pubkey(), collection(&HASH), false) {
+        // The last account is never the signer
+        instruction.accounts.pop();
+        let result = harness.process(&[instruction], &[&signer]).await;
+        assert_error(result, DataNexusError::InvalidAccountCount);
+    }
+}
+
+#[tokio::test]
+async fn every_instruction_rejects_an_extra_account() {
+    let (mut harness, signer) = setup().await;
+
+    for mut instruction in instructions(signer.pubkey(), collection(&HASH), true) {
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(key(), false));
+        let result = harness.process(&[instruction], &[&signer]).await;
+        assert_error(result, DataNexusError::InvalidAccountCount);
+    }
+}
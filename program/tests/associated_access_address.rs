@@ -0,0 +1,222 @@
+//! Associated access accounts live at `[ASSOCIATED_ACCESS_MARKER, authority,
+//! dataset]`, and every handler writing to one checks it is the authority's
+//! own for that dataset. Accounts at the bare derivation used before the
+//! marker are only accepted with `legacy-derivation`, and never created
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{InitAssociatedAccessAccounts, RevokeAccessAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{init_associated_access, revoke_access},
+    pda::find_legacy_associated_access_address,
+    state::{AccessInfo, AccessState},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_program,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    holder: Keypair,
+}
+
+/// A registered dataset and a holder with an access index to record access
+/// accounts in
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let holder = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_access_index(&holder.pubkey()).await.unwrap();
+
+    Setup {
+        harness,
+        owner,
+        holder,
+    }
+}
+
+fn legacy_associated_access(authority: &Pubkey, hash: &[u8; 32]) -> Pubkey {
+    find_legacy_associated_access_address(&datanexus_program::id(), authority, &dataset(hash)).0
+}
+
+/// Like `setup`, with the holder's access to the dataset held at its legacy
+/// associated access address
+async fn setup_legacy_holder() -> Setup {
+    let holder = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        legacy_associated_access(&holder.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: Some([7; 32]),
+                shared_from: None,
+                share_quota: 2,
+                expires_at: None,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    Setup {
+        harness,
+        owner,
+        holder,
+    }
+}
+
+/// Revokes the holder's access through its legacy associated access account
+async fn revoke_legacy(setup: &mut Setup) -> Result<(), TransportError> {
+    let instruction = revoke_access(
+        datanexus_program::id(),
+        RevokeAccessAccounts {
+            owner_authority: setup.owner.pubkey(),
+            dataset_account: dataset(&HASH),
+            holder_authority: setup.holder.pubkey(),
+            holder_access_account: legacy_associated_access(&setup.holder.pubkey(), &HASH),
+            receipt: None,
+        },
+        HASH,
+    )
+    .unwrap();
+    setup.harness.process(&[instruction], &[&setup.owner]).await
+}
+
+#[tokio::test]
+async fn associated_access_is_created_at_the_marked_address() {
+    let Setup {
+        mut harness,
+        holder,
+        ..
+    } = setup().await;
+    let legacy = find_legacy_associated_access_address(
+        &datanexus_program::id(),
+        &holder.pubkey(),
+        &dataset(&HASH),
+    )
+    .0;
+    assert_ne!(associated_access(&holder.pubkey(), &HASH), legacy);
+
+    harness
+        .create_associated_access(&holder, HASH)
+        .await
+        .unwrap();
+
+    let state: AccessState = harness
+        .state(associated_access(&holder.pubkey(), &HASH))
+        .await;
+    assert!(state.is_initialized);
+}
+
+#[tokio::test]
+async fn legacy_associated_access_addresses_are_refused() {
+    let Setup {
+        mut harness,
+        holder,
+        ..
+    } = setup().await;
+    let legacy = find_legacy_associated_access_address(
+        &datanexus_program::id(),
+        &holder.pubkey(),
+        &dataset(&HASH),
+    )
+    .0;
+
+    let instruction = init_associated_access(
+        datanexus_program::id(),
+        InitAssociatedAccessAccounts {
+            payer: harness.payer.pubkey(),
+            authority: holder.pubkey(),
+            access_index: access_index(&holder.pubkey()),
+            associated_access_account: legacy,
+            dataset_account: dataset(&HASH),
+            system_program: system_program::id(),
+        },
+        HASH,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&holder]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn revoking_through_another_holders_access_account_fails() {
+    let Setup {
+        mut harness,
+        owner,
+        holder,
+    } = setup().await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_access_index(&other.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&holder, HASH)
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&other, HASH)
+        .await
+        .unwrap();
+
+    // The other holder's account is program-owned but not the holder's
+    let instruction = revoke_access(
+        datanexus_program::id(),
+        RevokeAccessAccounts {
+            owner_authority: owner.pubkey(),
+            dataset_account: dataset(&HASH),
+            holder_authority: holder.pubkey(),
+            holder_access_account: associated_access(&other.pubkey(), &HASH),
+            receipt: None,
+        },
+        HASH,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&owner]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[cfg(not(feature = "legacy-derivation"))]
+#[tokio::test]
+async fn revoking_through_a_legacy_access_account_is_refused() {
+    let mut setup = setup_legacy_holder().await;
+
+    let result = revoke_legacy(&mut setup).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[cfg(feature = "legacy-derivation")]
+#[tokio::test]
+async fn revoking_through_a_legacy_access_account_is_accepted() {
+    let mut setup = setup_legacy_holder().await;
+
+    revoke_legacy(&mut setup).await.unwrap();
+
+    let access: AccessState = setup
+        .harness
+        .state(legacy_associated_access(&setup.holder.pubkey(), &HASH))
+        .await;
+    assert!(access.datasets.is_empty());
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::{DataNexusInstruction, FieldMask, Params},
+    state::DatasetState,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::{
+    instruction::InstructionError, signature::Signer, transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const REFERENCE_HASH: [u8; 32] = [2; 32];
+
+#[test]
+fn clear_roundtrips() {
+    for mask in &[
+        FieldMask::KEY,
+        FieldMask::VALUE | FieldMask::REF_DATA,
+        FieldMask::ALL,
+    ] {
+        let instruction = DataNexusInstruction::SetDataParams {
+            hash: HASH,
+            params: Params::Clear(*mask),
+        };
+
+        let packed = instruction.pack();
+        assert_eq!(
+            DataNexusInstruction::unpack(&packed).unwrap().pack(),
+            packed
+        );
+        #[cfg(feature = "borsh-encoding")]
+        {
+            let packed = instruction.pack_borsh().unwrap();
+            assert_eq!(
+                DataNexusInstruction::unpack(&packed)
+                    .unwrap()
+                    .pack_borsh()
+                    .unwrap(),
+                packed
+            );
+        }
+    }
+}
+
+#[test]
+fn clear_requires_its_mask() {
+    let mut packed = DataNexusInstruction::SetDataParams {
+        hash: HASH,
+        params: Params::Clear(FieldMask::VALUE),
+    }
+    .pack();
+    packed.pop();
+
+    assert!(DataNexusInstruction::unpack(&packed).is_err());
+}
+
+#[tokio::test]
+async fn cleared_fields_are_unset() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .create_dataset_at(&owner, REFERENCE_HASH, dataset(&REFERENCE_HASH))
+        .await
+        .unwrap();
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::ReferenceData(dataset(&REFERENCE_HASH)),
+        )
+        .await
+        .unwrap();
+    harness
+        .set_params(&owner, HASH, Params::Uri(b"https://example.com".to_vec()))
+        .await
+        .unwrap();
+
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::Clear(FieldMask::SHARE_LIMIT | FieldMask::REF_DATA | FieldMask::URI),
+        )
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.share_limit, None);
+    assert_eq!(state.ref_data, None);
+    assert_eq!(state.uri(), None);
+    assert_eq!(state.key, Some([7; 32]));
+    assert_eq!(state.value, Some(100));
+}
+
+#[tokio::test]
+async fn unknown_fields_are_rejected() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    for mask in &[FieldMask(0), FieldMask(1 << 5)] {
+        let result = harness.set_params(&owner, HASH, Params::Clear(*mask)).await;
+
+        match result {
+            Err(TransportError::TransactionError(TransactionError::InstructionError(
+                _,
+                InstructionError::InvalidArgument,
+            ))) => {}
+            other => panic!("expected InvalidArgument, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn datasets_with_no_value_cannot_be_purchased() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .set_params(&owner, HASH, Params::Clear(FieldMask::VALUE))
+        .await
+        .unwrap();
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 100)
+        .await;
+    let owner_tokens = harness
+        .create_token_account(&mint, &owner.pubkey(), 0)
+        .await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let result = harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            owner_tokens,
+            mint,
+            HASH,
+            0,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::DatasetNotPriced);
+    assert_eq!(harness.token_balance(buyer_tokens).await, 100);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::CloseDatasetAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::close_dataset,
+    state::{AccountIndex, DatasetState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, rent::Rent};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// An owner with `HASH` registered
+async fn setup() -> (Harness, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    (harness, owner)
+}
+
+#[tokio::test]
+async fn closed_datasets_are_left_as_tombstones() {
+    let (mut harness, owner) = setup().await;
+    let dataset_lamports = harness.lamports(dataset(&HASH)).await;
+    let owner_lamports = harness.lamports(owner.pubkey()).await;
+
+    harness.close_dataset(&owner, HASH, false).await.unwrap();
+
+    let account = harness
+        .banks
+        .get_account(dataset(&HASH))
+        .await
+        .unwrap()
+        .unwrap();
+    let tombstone_lamports = Rent::default().minimum_balance(DatasetState::CLOSED_LEN);
+    assert_eq!(account.owner, datanexus_program::id());
+    assert_eq!(account.data, vec![0; DatasetState::CLOSED_LEN]);
+    assert_eq!(account.lamports, tombstone_lamports);
+    assert_eq!(
+        harness.lamports(owner.pubkey()).await,
+        owner_lamports + dataset_lamports - tombstone_lamports
+    );
+
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert_eq!(index.position(&dataset(&HASH)), None);
+}
+
+#[tokio::test]
+async fn closed_datasets_are_not_created_again() {
+    let (mut harness, owner) = setup().await;
+    harness.close_dataset(&owner, HASH, false).await.unwrap();
+
+    let result = harness
+        .create_dataset_at(&owner, HASH, dataset(&HASH))
+        .await;
+
+    assert_error(result, DataNexusError::AccountAlreadyInitialized);
+}
+
+#[tokio::test]
+async fn closing_needs_the_owner_index_of_the_authority() {
+    let (mut harness, owner) = setup().await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&other.pubkey()).await.unwrap();
+
+    let instruction = close_dataset(
+        datanexus_program::id(),
+        CloseDatasetAccounts {
+            authority: owner.pubkey(),
+            owner_index: owner_index(&other.pubkey()),
+            dataset_account: dataset(&HASH),
+        },
+        HASH,
+        false,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&owner]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert!(state.is_initialized);
+}
@@ -0,0 +1,249 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessState, AccountIndex, CollectionAccess, CollectionState, MAX_COLLECTION_GRANTS},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const COLLECTION: [u8; 32] = [100; 32];
+const PRICE: u64 = 500;
+
+/// More members than a single transaction can grant
+const MEMBERS: usize = MAX_COLLECTION_GRANTS + 2;
+
+fn member_hashes() -> Vec<[u8; 32]> {
+    (0..MEMBERS).map(|i| [i as u8 + 1; 32]).collect()
+}
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+    owner_tokens: Pubkey,
+}
+
+/// An owner selling `MEMBERS` datasets as one collection, and a buyer with
+/// the access accounts for each of them already created
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+
+    let hashes = member_hashes();
+    harness.register_dataset(&owner, hashes[0], 100, 1).await;
+    for hash in hashes[1..].iter() {
+        harness
+            .create_dataset_at(&owner, *hash, dataset(hash))
+            .await
+            .unwrap();
+        harness
+            .set_params(&owner, *hash, Params::Init([7; 32], 100, 1, None))
+            .await
+            .unwrap();
+    }
+
+    harness
+        .create_collection(&owner, COLLECTION, PRICE)
+        .await
+        .unwrap();
+    for hash in hashes.iter() {
+        harness
+            .add_to_collection(&owner, COLLECTION, *hash)
+            .await
+            .unwrap();
+    }
+
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_collection_access(&buyer, COLLECTION)
+        .await
+        .unwrap();
+    for hash in hashes.iter() {
+        harness
+            .create_associated_access(&buyer, *hash)
+            .await
+            .unwrap();
+    }
+
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), PRICE)
+        .await;
+    let owner_tokens = harness
+        .create_token_account(&mint, &owner.pubkey(), 0)
+        .await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        mint,
+        buyer_tokens,
+        owner_tokens,
+    }
+}
+
+impl Setup {
+    async fn purchase(&mut self, member_hashes: &[[u8; 32]]) -> Result<(), TransportError> {
+        self.harness
+            .purchase_collection(
+                &self.buyer,
+                self.buyer_tokens,
+                &self.owner.pubkey(),
+                self.owner_tokens,
+                self.mint,
+                COLLECTION,
+                PRICE,
+                member_hashes,
+            )
+            .await
+    }
+
+    async fn has_access(&mut self, hash: &[u8; 32]) -> bool {
+        let access: AccessState = self
+            .harness
+            .state(associated_access(&self.buyer.pubkey(), hash))
+            .await;
+        access.find_entry(hash).is_some()
+    }
+}
+
+#[tokio::test]
+async fn claims_resume_from_the_cursor() {
+    let mut setup = setup().await;
+    let hashes = member_hashes();
+
+    let collection: CollectionState = setup.harness.state(collection(&COLLECTION)).await;
+    assert_eq!(collection.members.len(), MEMBERS);
+    assert_eq!(collection.value, Some(PRICE));
+
+    setup
+        .purchase(&hashes[..MAX_COLLECTION_GRANTS])
+        .await
+        .unwrap();
+    assert_eq!(setup.harness.token_balance(setup.owner_tokens).await, PRICE);
+    let access: CollectionAccess = setup
+        .harness
+        .state(collection_access(&setup.buyer.pubkey(), &COLLECTION))
+        .await;
+    assert!(access.purchased);
+    assert_eq!(usize::from(access.cursor), MAX_COLLECTION_GRANTS);
+    assert_eq!(access.pending(&collection), MEMBERS - MAX_COLLECTION_GRANTS);
+    assert!(setup.has_access(&hashes[0]).await);
+    assert!(!setup.has_access(&hashes[MEMBERS - 1]).await);
+
+    setup
+        .harness
+        .claim_collection(&setup.buyer, COLLECTION, &hashes[MAX_COLLECTION_GRANTS..])
+        .await
+        .unwrap();
+    let access: CollectionAccess = setup
+        .harness
+        .state(collection_access(&setup.buyer.pubkey(), &COLLECTION))
+        .await;
+    assert_eq!(access.pending(&collection), 0);
+    for hash in hashes.iter() {
+        assert!(setup.has_access(hash).await);
+    }
+    let index: AccountIndex = setup
+        .harness
+        .state(access_index(&setup.buyer.pubkey()))
+        .await;
+    assert_eq!(
+        index.find_by_hash(&hashes[MEMBERS - 1]),
+        Some(dataset(&hashes[MEMBERS - 1]))
+    );
+}
+
+#[tokio::test]
+async fn members_must_be_granted_in_order() {
+    let mut setup = setup().await;
+    let hashes = member_hashes();
+
+    let result = setup.purchase(&[hashes[1]]).await;
+
+    assert_error(result, DataNexusError::CollectionMemberMismatch);
+    assert_eq!(setup.harness.token_balance(setup.owner_tokens).await, 0);
+}
+
+#[tokio::test]
+async fn claims_need_a_purchase() {
+    let mut setup = setup().await;
+    let hashes = member_hashes();
+
+    let result = setup
+        .harness
+        .claim_collection(&setup.buyer, COLLECTION, &hashes[..1])
+        .await;
+
+    assert_error(result, DataNexusError::NoExistingAccess);
+    assert!(!setup.has_access(&hashes[0]).await);
+}
+
+#[tokio::test]
+async fn only_the_owner_adds_members() {
+    let mut setup = setup().await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let hash = [200; 32];
+    setup.harness.register_dataset(&other, hash, 100, 1).await;
+
+    let result = setup
+        .harness
+        .add_to_collection(&other, COLLECTION, hash)
+        .await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+}
+
+#[tokio::test]
+async fn members_are_added_once() {
+    let mut setup = setup().await;
+    let hashes = member_hashes();
+
+    let result = setup
+        .harness
+        .add_to_collection(&setup.owner, COLLECTION, hashes[0])
+        .await;
+
+    assert_error(result, DataNexusError::AlreadyInCollection);
+}
+
+#[tokio::test]
+async fn datasets_with_a_holder_quota_are_not_added() {
+    let mut setup = setup().await;
+    let hash = [200; 32];
+    let owner = &setup.owner;
+    setup
+        .harness
+        .create_dataset_at(owner, hash, dataset(&hash))
+        .await
+        .unwrap();
+    setup
+        .harness
+        .set_params(owner, hash, Params::MaxHolders(10))
+        .await
+        .unwrap();
+
+    let result = setup
+        .harness
+        .add_to_collection(owner, COLLECTION, hash)
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(TransportError::TransactionError(
+            TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+        ))
+    ));
+}
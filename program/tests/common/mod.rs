@@ -0,0 +1,1080 @@
+//! Shared setup for the processor integration tests
+//!
+//! Each helper sends one step of the dataset lifecycle through
+//! `solana-program-test`, so tests read as the sequence of steps they cover
+//! and assert on the unpacked account state in between.
+
+#![allow(dead_code)]
+
+use datanexus::{
+    accounts::{
+        AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitDatasetAccounts, InitReceiptMintAccounts,
+        InitVaultAccounts, MemberAccounts, MigrateAccountAccounts, OwnershipTransferAccounts,
+        PurchaseAccessAccounts, PurchaseCollectionAccounts, ReceiptAccounts, RegistryAccounts,
+        RejectShareAccounts, ResizeIndexAccounts, RevokeAccessAccounts,
+        SetAccessPreferencesAccounts, SetDataParamsAccounts, ShareAccessAccounts, ShareFeeAccounts,
+        WithdrawProceedsAccounts,
+    },
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{
+        accept_ownership, accept_share, add_to_collection, cancel_ownership_transfer,
+        claim_collection_access, close_dataset, compact_index, crank_prune, create_collection,
+        fund_bounty, init_account, init_associated_access, init_dataset_account, init_receipt_mint,
+        init_vault, migrate_account, propose_ownership_transfer, purchase_access,
+        purchase_collection, purchase_seats, reject_share, resize_index, revoke_access,
+        set_access_preferences, set_data_params, share_access, withdraw_proceeds, AccountType,
+        Params,
+    },
+    pda::{
+        find_access_address, find_associated_access_address, find_collection_address,
+        find_dataset_address, find_owner_address, find_receipt_mint_address, find_registry_address,
+        find_vault_address, registry_prefix,
+    },
+    processor::Processor,
+};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, instruction::Instruction,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+
+pub const DECIMALS: u8 = 6;
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    Processor::process_instruction(*program_id, accounts, data)
+}
+
+pub fn owner_index(authority: &Pubkey) -> Pubkey {
+    find_owner_address(&datanexus_program::id(), authority).0
+}
+
+pub fn access_index(authority: &Pubkey) -> Pubkey {
+    find_access_address(&datanexus_program::id(), authority).0
+}
+
+pub fn dataset(hash: &[u8; 32]) -> Pubkey {
+    find_dataset_address(&datanexus_program::id(), hash).0
+}
+
+pub fn associated_access(authority: &Pubkey, hash: &[u8; 32]) -> Pubkey {
+    find_associated_access_address(&datanexus_program::id(), authority, &dataset(hash)).0
+}
+
+pub fn receipt_mint(hash: &[u8; 32]) -> Pubkey {
+    find_receipt_mint_address(&datanexus_program::id(), &dataset(hash)).0
+}
+
+pub fn vault(hash: &[u8; 32], mint: &Pubkey) -> Pubkey {
+    find_vault_address(&datanexus_program::id(), &dataset(hash), mint).0
+}
+
+pub fn collection(hash: &[u8; 32]) -> Pubkey {
+    find_collection_address(&datanexus_program::id(), hash).0
+}
+
+pub fn collection_access(authority: &Pubkey, hash: &[u8; 32]) -> Pubkey {
+    find_associated_access_address(&datanexus_program::id(), authority, &collection(hash)).0
+}
+
+/// Registry page `page` of the prefix `hash` is listed under
+pub fn registry(hash: &[u8; 32], page: u8) -> Pubkey {
+    find_registry_address(&datanexus_program::id(), &registry_prefix(hash), page).0
+}
+
+/// Member accounts of `authority` for the datasets of `hashes`, in order
+pub fn members(authority: &Pubkey, hashes: &[[u8; 32]]) -> Vec<MemberAccounts<Pubkey>> {
+    hashes
+        .iter()
+        .map(|hash| MemberAccounts {
+            dataset_account: dataset(hash),
+            access_account: associated_access(authority, hash),
+        })
+        .collect()
+}
+
+/// The receipt group of `hash`, minting to or burning from
+/// `receipt_token_account`
+pub fn receipt_accounts(hash: &[u8; 32], receipt_token_account: Pubkey) -> ReceiptAccounts<Pubkey> {
+    ReceiptAccounts {
+        receipt_mint: receipt_mint(hash),
+        receipt_token_account,
+        token_program: spl_token::id(),
+    }
+}
+
+/// Seeds `program_test` with an account holding the packed `state`
+pub fn add_packed_account<T: Pack>(
+    program_test: &mut ProgramTest,
+    address: Pubkey,
+    lamports: u64,
+    state: &T,
+    owner: &Pubkey,
+) {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    program_test.add_account(
+        address,
+        Account {
+            lamports,
+            data,
+            owner: *owner,
+            ..Account::default()
+        },
+    );
+}
+
+/// Fails unless `result` is the DataNexus `error`
+pub fn assert_error(result: Result<(), TransportError>, error: DataNexusError) {
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(DataNexusError::from_code(code), Some(error)),
+        other => panic!("expected {:?}, got {:?}", error, other),
+    }
+}
+
+/// A running bank with the DataNexus program and spl-token loaded, `payer`
+/// funds every account the tests create
+pub struct Harness {
+    pub banks: BanksClient,
+    pub payer: Keypair,
+    context: ProgramTestContext,
+}
+
+impl Harness {
+    pub async fn start() -> Self {
+        Self::start_with(Self::program_test()).await
+    }
+
+    /// A bank running the program through the builtin processor, for tests
+    /// that seed accounts before starting it
+    pub fn program_test() -> ProgramTest {
+        ProgramTest::new(
+            "datanexus",
+            datanexus_program::id(),
+            processor!(process_instruction),
+        )
+    }
+
+    /// A bank loading the BPF build of the program, which unlike the
+    /// builtin processor is compute metered, `cargo build-bpf` must have
+    /// been run first
+    pub fn bpf_program_test() -> ProgramTest {
+        ProgramTest::new("datanexus", datanexus_program::id(), None)
+    }
+
+    pub async fn start_with(program_test: ProgramTest) -> Self {
+        let context = program_test.start_with_context().await;
+        let banks = context.banks_client.clone();
+        let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+
+        Harness {
+            banks,
+            payer,
+            context,
+        }
+    }
+
+    /// Moves the bank's clock to `unix_timestamp`, leaving the slot as is
+    pub async fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.banks.get_sysvar().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Sends `instructions` in one transaction paid by `payer` and signed by
+    /// `signers`
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        let blockhash = self.banks.get_latest_blockhash().await?;
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.banks
+            .process_transaction(transaction)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Fetches `address` and unpacks it as `T`
+    pub async fn state<T: Pack>(&mut self, address: Pubkey) -> T {
+        let account = self
+            .banks
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("no account at {}", address));
+        T::unpack_from_slice(&account.data).unwrap()
+    }
+
+    /// Creates a system account holding `lamports`
+    pub async fn fund(&mut self, lamports: u64) -> Keypair {
+        let account = Keypair::new();
+        self.transfer(&account.pubkey(), lamports).await;
+        account
+    }
+
+    /// Sends `lamports` from the payer to `address`, which need not exist yet
+    pub async fn transfer(&mut self, address: &Pubkey, lamports: u64) {
+        self.process(
+            &[system_instruction::transfer(
+                &self.payer.pubkey(),
+                address,
+                lamports,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Lamports held by `address`, zero when there is no account
+    pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.banks
+            .get_account(address)
+            .await
+            .unwrap()
+            .map_or(0, |account| account.lamports)
+    }
+
+    pub async fn create_owner_index(&mut self, authority: &Pubkey) -> Result<(), TransportError> {
+        let instruction = init_account(
+            datanexus_program::id(),
+            Some(self.payer.pubkey()),
+            *authority,
+            owner_index(authority),
+            None,
+            system_program::id(),
+            AccountType::DatasetIndex,
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).await
+    }
+
+    pub async fn create_access_index(&mut self, authority: &Pubkey) -> Result<(), TransportError> {
+        let instruction = init_account(
+            datanexus_program::id(),
+            Some(self.payer.pubkey()),
+            *authority,
+            access_index(authority),
+            None,
+            system_program::id(),
+            AccountType::AccessIndex,
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).await
+    }
+
+    /// Grows `index` of `authority` by `additional_slots` entries
+    pub async fn resize_index(
+        &mut self,
+        authority: &Keypair,
+        index: Pubkey,
+        additional_slots: u16,
+    ) -> Result<(), TransportError> {
+        let instruction = resize_index(
+            datanexus_program::id(),
+            ResizeIndexAccounts {
+                payer: self.payer.pubkey(),
+                authority: authority.pubkey(),
+                index_account: index,
+                system_program: system_program::id(),
+            },
+            additional_slots,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Compacts `index` of `authority`, shrinking it to its entries plus
+    /// `headroom` slots when `shrink` is set
+    pub async fn compact_index(
+        &mut self,
+        authority: &Keypair,
+        index: Pubkey,
+        shrink: bool,
+        headroom: u16,
+    ) -> Result<(), TransportError> {
+        let instruction = compact_index(
+            datanexus_program::id(),
+            CompactIndexAccounts {
+                authority: authority.pubkey(),
+                index_account: index,
+            },
+            shrink,
+            headroom,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Grows the account at `address` by one layout version, paid for by the
+    /// payer
+    pub async fn migrate(&mut self, address: Pubkey) -> Result<(), TransportError> {
+        let instruction = migrate_account(
+            datanexus_program::id(),
+            MigrateAccountAccounts {
+                payer: self.payer.pubkey(),
+                account: address,
+                system_program: system_program::id(),
+            },
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).await
+    }
+
+    /// Creates the dataset account of `hash` at `address`, which is its PDA
+    /// unless a test is checking the derivation
+    pub async fn create_dataset_at(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+        address: Pubkey,
+    ) -> Result<(), TransportError> {
+        let instruction = init_account(
+            datanexus_program::id(),
+            None,
+            authority.pubkey(),
+            address,
+            Some(owner_index(&authority.pubkey())),
+            system_program::id(),
+            AccountType::Dataset(hash),
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Creates the dataset of `hash` and lists it on the registry page of
+    /// `registry`
+    pub async fn create_listed_dataset(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+        registry: RegistryAccounts<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let instruction = init_dataset_account(
+            datanexus_program::id(),
+            InitDatasetAccounts {
+                authority: authority.pubkey(),
+                owner_index: owner_index(&authority.pubkey()),
+                dataset_account: dataset(&hash),
+                system_program: system_program::id(),
+                registry: Some(registry),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    pub async fn set_params(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+        params: Params,
+    ) -> Result<(), TransportError> {
+        let instruction = set_data_params(
+            datanexus_program::id(),
+            SetDataParamsAccounts {
+                authority: authority.pubkey(),
+                dataset_account: dataset(&hash),
+                reference_dataset: params.reference_data(),
+            },
+            hash,
+            params,
+            &[],
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Creates the owner index of `authority` and registers `hash` to it
+    /// with the given price and share limit
+    pub async fn register_dataset(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+        value: u64,
+        share_limit: u16,
+    ) {
+        self.create_owner_index(&authority.pubkey()).await.unwrap();
+        self.create_dataset_at(authority, hash, dataset(&hash))
+            .await
+            .unwrap();
+        self.set_params(
+            authority,
+            hash,
+            Params::Init([7; 32], value, share_limit, None),
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Creates the associated access account of `authority` for `hash`, the
+    /// access index it is recorded in must already exist
+    pub async fn create_associated_access(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = init_associated_access(
+            datanexus_program::id(),
+            InitAssociatedAccessAccounts {
+                payer: self.payer.pubkey(),
+                authority: authority.pubkey(),
+                access_index: access_index(&authority.pubkey()),
+                associated_access_account: associated_access(&authority.pubkey(), &hash),
+                dataset_account: dataset(&hash),
+                system_program: system_program::id(),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Creates an spl-token mint whose authority is the payer
+    pub async fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &mint.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &self.payer.pubkey(),
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Creates a token account of `mint` owned by `owner` holding `amount`
+    pub async fn create_token_account(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let account = Keypair::new();
+        let mut instructions = vec![
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                Rent::default().minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        if amount > 0 {
+            instructions.push(
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    mint,
+                    &account.pubkey(),
+                    &self.payer.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        self.process(&instructions, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    /// Opens the vault of `hash` for `mint` as its `owner` and returns its
+    /// address
+    pub async fn create_vault(&mut self, owner: &Keypair, hash: [u8; 32], mint: Pubkey) -> Pubkey {
+        self.init_vault(owner, hash, mint).await.unwrap();
+        vault(&hash, &mint)
+    }
+
+    pub async fn init_vault(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+        mint: Pubkey,
+    ) -> Result<(), TransportError> {
+        let instruction = init_vault(
+            datanexus_program::id(),
+            InitVaultAccounts {
+                payer: self.payer.pubkey(),
+                owner_authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+                token_mint: mint,
+                vault_account: vault(&hash, &mint),
+                token_program: spl_token::id(),
+                system_program: system_program::id(),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    pub async fn withdraw(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+        mint: Pubkey,
+        destination_token_account: Pubkey,
+        amount: u64,
+    ) -> Result<(), TransportError> {
+        let instruction = withdraw_proceeds(
+            datanexus_program::id(),
+            WithdrawProceedsAccounts {
+                owner_authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+                vault_account: vault(&hash, &mint),
+                token_mint: mint,
+                destination_token_account,
+                token_program: spl_token::id(),
+            },
+            hash,
+            amount,
+            &[],
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    pub async fn close_dataset(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+        force: bool,
+    ) -> Result<(), TransportError> {
+        let instruction = close_dataset(
+            datanexus_program::id(),
+            CloseDatasetAccounts {
+                authority: owner.pubkey(),
+                owner_index: owner_index(&owner.pubkey()),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            force,
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    pub async fn propose_ownership_transfer(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+        new_owner: Pubkey,
+    ) -> Result<(), TransportError> {
+        let instruction = propose_ownership_transfer(
+            datanexus_program::id(),
+            OwnershipTransferAccounts {
+                authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            new_owner,
+            &[],
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    /// Accepts the dataset of `hash` from `old_owner` as `new_owner`
+    pub async fn accept_ownership(
+        &mut self,
+        new_owner: &Keypair,
+        old_owner: &Pubkey,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = accept_ownership(
+            datanexus_program::id(),
+            AcceptOwnershipAccounts {
+                authority: new_owner.pubkey(),
+                old_owner_index: owner_index(old_owner),
+                new_owner_index: owner_index(&new_owner.pubkey()),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            &[],
+        )
+        .unwrap();
+        self.process(&[instruction], &[new_owner]).await
+    }
+
+    pub async fn cancel_ownership_transfer(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = cancel_ownership_transfer(
+            datanexus_program::id(),
+            OwnershipTransferAccounts {
+                authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            &[],
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    /// Prunes `holder`'s access to `hash`, cranked and paid to `cranker`
+    pub async fn crank_prune(
+        &mut self,
+        cranker: &Keypair,
+        holder: &Pubkey,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = crank_prune(
+            datanexus_program::id(),
+            CrankPruneAccounts {
+                cranker: cranker.pubkey(),
+                dataset_account: dataset(&hash),
+                holder_access_account: associated_access(holder, &hash),
+            },
+            hash,
+            *holder,
+        )
+        .unwrap();
+        self.process(&[instruction], &[cranker]).await
+    }
+
+    pub async fn fund_bounty(
+        &mut self,
+        owner: &Keypair,
+        hash: [u8; 32],
+        amount: u64,
+    ) -> Result<(), TransportError> {
+        let instruction = fund_bounty(
+            datanexus_program::id(),
+            FundBountyAccounts {
+                authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+                system_program: system_program::id(),
+            },
+            hash,
+            amount,
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    /// Creates the receipt mint of `hash` and returns its address
+    pub async fn create_receipt_mint(&mut self, hash: [u8; 32]) -> Pubkey {
+        let instruction = init_receipt_mint(
+            datanexus_program::id(),
+            InitReceiptMintAccounts {
+                payer: self.payer.pubkey(),
+                dataset_account: dataset(&hash),
+                receipt_mint: receipt_mint(&hash),
+                token_program: spl_token::id(),
+                system_program: system_program::id(),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[]).await.unwrap();
+        receipt_mint(&hash)
+    }
+
+    pub async fn revoke(
+        &mut self,
+        owner: &Keypair,
+        holder: &Pubkey,
+        hash: [u8; 32],
+        receipt_token_account: Option<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let instruction = revoke_access(
+            datanexus_program::id(),
+            RevokeAccessAccounts {
+                owner_authority: owner.pubkey(),
+                dataset_account: dataset(&hash),
+                holder_authority: *holder,
+                holder_access_account: associated_access(holder, &hash),
+                receipt: receipt_token_account
+                    .map(|receipt_token_account| receipt_accounts(&hash, receipt_token_account)),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[owner]).await
+    }
+
+    pub async fn token_balance(&mut self, account: Pubkey) -> u64 {
+        self.state::<spl_token::state::Account>(account)
+            .await
+            .amount
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn purchase(
+        &mut self,
+        buyer: &Keypair,
+        buyer_token_account: Pubkey,
+        owner: &Pubkey,
+        proceeds_account: Pubkey,
+        mint: Pubkey,
+        hash: [u8; 32],
+        amount: u64,
+    ) -> Result<(), TransportError> {
+        self.purchase_with_receipt(
+            buyer,
+            buyer_token_account,
+            owner,
+            proceeds_account,
+            mint,
+            hash,
+            amount,
+            None,
+        )
+        .await
+    }
+
+    /// As `purchase`, minting a receipt to `receipt_token_account` if given
+    #[allow(clippy::too_many_arguments)]
+    pub async fn purchase_with_receipt(
+        &mut self,
+        buyer: &Keypair,
+        buyer_token_account: Pubkey,
+        owner: &Pubkey,
+        proceeds_account: Pubkey,
+        mint: Pubkey,
+        hash: [u8; 32],
+        amount: u64,
+        receipt_token_account: Option<Pubkey>,
+    ) -> Result<(), TransportError> {
+        let instruction = purchase_access(
+            datanexus_program::id(),
+            PurchaseAccessAccounts {
+                user_authority: buyer.pubkey(),
+                user_access_index: access_index(&buyer.pubkey()),
+                user_access_account: associated_access(&buyer.pubkey(), &hash),
+                user_token_account: buyer_token_account,
+                owner_authority: *owner,
+                proceeds_account,
+                token_mint: mint,
+                dataset_account: dataset(&hash),
+                token_program: spl_token::id(),
+                referrer_token_account: None,
+                receipt: receipt_token_account
+                    .map(|receipt_token_account| receipt_accounts(&hash, receipt_token_account)),
+                reference_access: None,
+            },
+            hash,
+            amount,
+        )
+        .unwrap();
+        self.process(&[instruction], &[buyer]).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn purchase_seats(
+        &mut self,
+        buyer: &Keypair,
+        buyer_token_account: Pubkey,
+        owner: &Pubkey,
+        proceeds_account: Pubkey,
+        mint: Pubkey,
+        hash: [u8; 32],
+        seats: u16,
+        amount: u64,
+    ) -> Result<(), TransportError> {
+        let instruction = purchase_seats(
+            datanexus_program::id(),
+            PurchaseAccessAccounts {
+                user_authority: buyer.pubkey(),
+                user_access_index: access_index(&buyer.pubkey()),
+                user_access_account: associated_access(&buyer.pubkey(), &hash),
+                user_token_account: buyer_token_account,
+                owner_authority: *owner,
+                proceeds_account,
+                token_mint: mint,
+                dataset_account: dataset(&hash),
+                token_program: spl_token::id(),
+                referrer_token_account: None,
+                receipt: None,
+                reference_access: None,
+            },
+            hash,
+            seats,
+            amount,
+        )
+        .unwrap();
+        self.process(&[instruction], &[buyer]).await
+    }
+
+    pub async fn share(
+        &mut self,
+        sharer: &Keypair,
+        recipient: &Pubkey,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        self.share_with_fee(sharer, recipient, hash, None).await
+    }
+
+    /// Shares with the fee group appended, paying the dataset's share fee
+    pub async fn share_with_fee(
+        &mut self,
+        sharer: &Keypair,
+        recipient: &Pubkey,
+        hash: [u8; 32],
+        fee_accounts: Option<ShareFeeAccounts<Pubkey>>,
+    ) -> Result<(), TransportError> {
+        self.share_expiring(sharer, recipient, hash, None, fee_accounts)
+            .await
+    }
+
+    /// Shares a grant expiring `expires_in` seconds from now, or with the
+    /// sharer's grant when that expires first
+    pub async fn share_expiring(
+        &mut self,
+        sharer: &Keypair,
+        recipient: &Pubkey,
+        hash: [u8; 32],
+        expires_in: Option<i64>,
+        fee_accounts: Option<ShareFeeAccounts<Pubkey>>,
+    ) -> Result<(), TransportError> {
+        let instruction = share_access(
+            datanexus_program::id(),
+            ShareAccessAccounts {
+                user_authority: sharer.pubkey(),
+                user_access_account: associated_access(&sharer.pubkey(), &hash),
+                recipient_authority: *recipient,
+                recipient_access_index: access_index(recipient),
+                recipient_access_account: associated_access(recipient, &hash),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            expires_in,
+            fee_accounts,
+        )
+        .unwrap();
+        self.process(&[instruction], &[sharer]).await
+    }
+
+    /// Sets whether shares to `authority` wait for it to accept them
+    pub async fn set_access_preferences(
+        &mut self,
+        authority: &Keypair,
+        require_share_approval: bool,
+    ) -> Result<(), TransportError> {
+        let instruction = set_access_preferences(
+            datanexus_program::id(),
+            SetAccessPreferencesAccounts {
+                authority: authority.pubkey(),
+                access_index: access_index(&authority.pubkey()),
+            },
+            require_share_approval,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Accepts the share of `hash` from `from` pending for `recipient`
+    pub async fn accept_share(
+        &mut self,
+        recipient: &Keypair,
+        from: &Pubkey,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = accept_share(
+            datanexus_program::id(),
+            AcceptShareAccounts {
+                authority: recipient.pubkey(),
+                access_index: access_index(&recipient.pubkey()),
+                access_account: associated_access(&recipient.pubkey(), &hash),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            *from,
+        )
+        .unwrap();
+        self.process(&[instruction], &[recipient]).await
+    }
+
+    /// Rejects the share of `hash` from `from` pending for `recipient`,
+    /// signed by `authority`
+    pub async fn reject_share(
+        &mut self,
+        authority: &Keypair,
+        recipient: &Pubkey,
+        from: &Pubkey,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = reject_share(
+            datanexus_program::id(),
+            RejectShareAccounts {
+                authority: authority.pubkey(),
+                recipient_authority: *recipient,
+                recipient_access_account: associated_access(recipient, &hash),
+                sharer_access_account: associated_access(from, &hash),
+                dataset_account: dataset(&hash),
+            },
+            hash,
+            *from,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    pub async fn create_collection(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+        value: u64,
+    ) -> Result<(), TransportError> {
+        let instruction = create_collection(
+            datanexus_program::id(),
+            CreateCollectionAccounts {
+                authority: authority.pubkey(),
+                collection_account: collection(&hash),
+                system_program: system_program::id(),
+            },
+            hash,
+            value,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    pub async fn add_to_collection(
+        &mut self,
+        authority: &Keypair,
+        collection_hash: [u8; 32],
+        dataset_hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = add_to_collection(
+            datanexus_program::id(),
+            AddToCollectionAccounts {
+                authority: authority.pubkey(),
+                collection_account: collection(&collection_hash),
+                dataset_account: dataset(&dataset_hash),
+            },
+            collection_hash,
+            dataset_hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Creates the account tracking which members of the collection of
+    /// `hash` have been granted to `authority`
+    pub async fn create_collection_access(
+        &mut self,
+        authority: &Keypair,
+        hash: [u8; 32],
+    ) -> Result<(), TransportError> {
+        let instruction = init_associated_access(
+            datanexus_program::id(),
+            InitAssociatedAccessAccounts {
+                payer: self.payer.pubkey(),
+                authority: authority.pubkey(),
+                access_index: access_index(&authority.pubkey()),
+                associated_access_account: collection_access(&authority.pubkey(), &hash),
+                dataset_account: collection(&hash),
+                system_program: system_program::id(),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Purchases the collection of `hash`, granting the members of
+    /// `member_hashes` in the same transaction
+    #[allow(clippy::too_many_arguments)]
+    pub async fn purchase_collection(
+        &mut self,
+        buyer: &Keypair,
+        buyer_token_account: Pubkey,
+        owner: &Pubkey,
+        owner_token_account: Pubkey,
+        mint: Pubkey,
+        hash: [u8; 32],
+        amount: u64,
+        member_hashes: &[[u8; 32]],
+    ) -> Result<(), TransportError> {
+        let instruction = purchase_collection(
+            datanexus_program::id(),
+            PurchaseCollectionAccounts {
+                user_authority: buyer.pubkey(),
+                user_access_index: access_index(&buyer.pubkey()),
+                collection_access_account: collection_access(&buyer.pubkey(), &hash),
+                user_token_account: buyer_token_account,
+                owner_authority: *owner,
+                owner_token_account,
+                token_mint: mint,
+                collection_account: collection(&hash),
+                token_program: spl_token::id(),
+                members: members(&buyer.pubkey(), member_hashes),
+            },
+            hash,
+            amount,
+        )
+        .unwrap();
+        self.process(&[instruction], &[buyer]).await
+    }
+
+    /// Grants the members of `member_hashes` from a purchased collection
+    pub async fn claim_collection(
+        &mut self,
+        buyer: &Keypair,
+        hash: [u8; 32],
+        member_hashes: &[[u8; 32]],
+    ) -> Result<(), TransportError> {
+        let instruction = claim_collection_access(
+            datanexus_program::id(),
+            ClaimCollectionAccessAccounts {
+                user_authority: buyer.pubkey(),
+                user_access_index: access_index(&buyer.pubkey()),
+                collection_access_account: collection_access(&buyer.pubkey(), &hash),
+                collection_account: collection(&hash),
+                members: members(&buyer.pubkey(), member_hashes),
+            },
+            hash,
+        )
+        .unwrap();
+        self.process(&[instruction], &[buyer]).await
+    }
+}
@@ -0,0 +1,206 @@
+//! Indexes left with holes by removals are compacted in place and may be
+//! shrunk back, refunding the rent they no longer need
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    state::{AccountIndex, MAX_INDEX_ENTRIES},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+};
+
+/// Offset of the first slot in a packed index
+const ENTRIES_OFFSET: usize = 38;
+
+/// Numbers from a fixed xorshift sequence, so hole patterns vary across
+/// cases but every run sees the same ones
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Packed index of `capacity` slots whose first `len` slots held entries,
+/// with the slots in `holes` zeroed as removals leave them
+fn holed_index(capacity: usize, len: usize, holes: &HashSet<usize>) -> Vec<u8> {
+    let mut index = AccountIndex {
+        is_initialized: true,
+        pointer: None,
+        require_share_approval: false,
+        datasets: vec![None; capacity],
+    };
+    for slot in 0..len {
+        index
+            .insert(Pubkey::new_unique(), [slot as u8; 32])
+            .unwrap();
+    }
+    let mut data = vec![0; AccountIndex::space(capacity).unwrap()];
+    index.pack_into_slice(&mut data);
+    for slot in holes {
+        let offset = ENTRIES_OFFSET + slot * AccountIndex::ENTRY_LEN;
+        data[offset..offset + AccountIndex::ENTRY_LEN].fill(0);
+    }
+    data
+}
+
+/// Starts a bank where `authority`'s owner index holds `data`, funded for
+/// its size only
+async fn start_with_index(authority: &Keypair, data: Vec<u8>) -> Harness {
+    let mut program_test = Harness::program_test();
+    program_test.add_account(
+        owner_index(&authority.pubkey()),
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: datanexus_program::id(),
+            ..Account::default()
+        },
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    harness
+        .transfer(&authority.pubkey(), LAMPORTS_PER_SOL)
+        .await;
+    harness
+}
+
+async fn index_account(harness: &mut Harness, authority: &Keypair) -> Account {
+    harness
+        .banks
+        .get_account(owner_index(&authority.pubkey()))
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn entries(data: &[u8]) -> HashSet<(Pubkey, [u8; 32])> {
+    AccountIndex::unpack_from_slice(data)
+        .unwrap()
+        .datasets
+        .into_iter()
+        .flatten()
+        .map(|entry| (entry.dataset, entry.hash))
+        .collect()
+}
+
+#[tokio::test]
+async fn compaction_keeps_every_entry() {
+    let mut rng = Xorshift(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..4 {
+        let capacity = MAX_INDEX_ENTRIES + (rng.next() % 128) as usize;
+        let len = (rng.next() as usize) % capacity;
+        let holes = (0..len).filter(|_| rng.next() % 3 == 2).collect();
+        let data = holed_index(capacity, len, &holes);
+        let before = entries(&data);
+        let authority = Keypair::new();
+        let mut harness = start_with_index(&authority, data.clone()).await;
+
+        harness
+            .compact_index(&authority, owner_index(&authority.pubkey()), false, 0)
+            .await
+            .unwrap();
+
+        let account = index_account(&mut harness, &authority).await;
+        assert_eq!(account.data.len(), data.len());
+        assert_eq!(entries(&account.data), before);
+        let index = AccountIndex::unpack_from_slice(&account.data).unwrap();
+        let kept = len - holes.len();
+        assert!(index.datasets[..kept].iter().all(Option::is_some));
+        assert!(index.datasets[kept..].iter().all(Option::is_none));
+    }
+}
+
+#[tokio::test]
+async fn shrinking_refunds_the_rent_freed() {
+    let capacity = 300;
+    let holes = (0..150).filter(|slot| slot % 5 == 0).collect();
+    let data = holed_index(capacity, 150, &holes);
+    let before = entries(&data);
+    let authority = Keypair::new();
+    let mut harness = start_with_index(&authority, data).await;
+
+    harness
+        .compact_index(&authority, owner_index(&authority.pubkey()), true, 20)
+        .await
+        .unwrap();
+
+    // 120 entries are kept, with room for 20 more
+    let new_len = AccountIndex::space(140).unwrap();
+    let account = index_account(&mut harness, &authority).await;
+    assert_eq!(account.data.len(), new_len);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(new_len));
+    assert_eq!(entries(&account.data), before);
+    assert_eq!(AccountIndex::capacity_raw(&account.data), Ok(140));
+    let freed = Rent::default().minimum_balance(AccountIndex::space(capacity).unwrap())
+        - Rent::default().minimum_balance(new_len);
+    assert_eq!(
+        harness.lamports(authority.pubkey()).await,
+        LAMPORTS_PER_SOL + freed
+    );
+}
+
+#[tokio::test]
+async fn shrinking_stops_at_the_created_size() {
+    let capacity = 200;
+    let authority = Keypair::new();
+    let mut harness =
+        start_with_index(&authority, holed_index(capacity, 10, &HashSet::new())).await;
+
+    harness
+        .compact_index(&authority, owner_index(&authority.pubkey()), true, 0)
+        .await
+        .unwrap();
+
+    let account = index_account(&mut harness, &authority).await;
+    assert_eq!(account.data.len(), AccountIndex::LEN);
+    assert_eq!(
+        AccountIndex::capacity_raw(&account.data),
+        Ok(MAX_INDEX_ENTRIES)
+    );
+}
+
+#[tokio::test]
+async fn shrinking_never_grows_an_index() {
+    let authority = Keypair::new();
+    let data = holed_index(MAX_INDEX_ENTRIES, 100, &HashSet::new());
+    let len = data.len();
+    let mut harness = start_with_index(&authority, data).await;
+
+    harness
+        .compact_index(&authority, owner_index(&authority.pubkey()), true, 64)
+        .await
+        .unwrap();
+
+    let account = index_account(&mut harness, &authority).await;
+    assert_eq!(account.data.len(), len);
+    assert_eq!(harness.lamports(authority.pubkey()).await, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn only_the_authority_compacts_its_index() {
+    let authority = Keypair::new();
+    let data = holed_index(MAX_INDEX_ENTRIES, 4, &[1].iter().copied().collect());
+    let mut harness = start_with_index(&authority, data.clone()).await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+
+    let result = harness
+        .compact_index(&other, owner_index(&authority.pubkey()), true, 0)
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(index_account(&mut harness, &authority).await.data, data);
+}
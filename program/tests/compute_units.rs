@@ -0,0 +1,419 @@
+//! Compute units used by each instruction of the BPF build
+//!
+//! The bank only reports whether an instruction fit its budget, so units
+//! are found by bisecting `set_bpf_compute_max_units`. The figure for a step
+//! is the smallest budget the lifecycle up to and including that step fits
+//! in, an upper bound on the step itself. The buyer's access index is
+//! seeded nearly full so the purchase pays for a realistic index.
+//!
+//! Purchases used to unpack and repack the whole access index, they now
+//! append to it in place. `MigrateAccount` still does the full repack when
+//! it moves a legacy index to the current layout, which prices what the
+//! previous purchase paid on top of today's. Run with
+//! `cargo test-bpf --test compute_units -- --nocapture` to print them.
+
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{
+        CloseDatasetAccounts, RecipientAccounts, RenewAccessAccounts, ShareAccessMultiAccounts,
+    },
+    datanexus_program,
+    instruction::{close_dataset, renew_access, share_access_multi, Params},
+    state::{AccountIndex, IndexEntry, MAX_INDEX_ENTRIES},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+    rent::Rent, system_program,
+};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+/// Default per-instruction budget
+const MAX_UNITS: u64 = 200_000;
+
+/// Bisection stops once the bounds are this close
+const PRECISION: u64 = 100;
+
+/// Purchase must fit in half of the default budget
+const PURCHASE_MAX_UNITS: u64 = MAX_UNITS / 2;
+
+/// Entries already in the buyer's access index
+const SEEDED_ENTRIES: usize = MAX_INDEX_ENTRIES - 1;
+
+/// Layout the buyer's access index is seeded in
+#[derive(Clone, Copy, Debug)]
+enum Layout {
+    /// Dataset keys only, as written before the layout version byte
+    Legacy,
+    Current,
+}
+
+impl Layout {
+    /// The packed access index holding `SEEDED_ENTRIES` unrelated datasets
+    fn seeded_index(self) -> Vec<u8> {
+        let datasets = (0..SEEDED_ENTRIES).map(|_| Pubkey::new_unique());
+        match self {
+            Layout::Legacy => {
+                let mut data = vec![0; AccountIndex::LEGACY_LEN];
+                data[0] = 1;
+                for (slot, dataset) in data[33..].chunks_exact_mut(32).zip(datasets) {
+                    slot.copy_from_slice(dataset.as_ref());
+                }
+                data
+            }
+            Layout::Current => {
+                let mut datasets: Vec<_> = datasets
+                    .map(|dataset| {
+                        Some(IndexEntry {
+                            dataset,
+                            hash: [9; 32],
+                        })
+                    })
+                    .collect();
+                datasets.resize(MAX_INDEX_ENTRIES, None);
+
+                let mut data = vec![0; AccountIndex::LEN];
+                AccountIndex {
+                    is_initialized: true,
+                    pointer: None,
+                    require_share_approval: false,
+                    datasets,
+                }
+                .pack_into_slice(&mut data);
+                data
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum Step {
+    OwnerIndex,
+    Dataset,
+    SetParams,
+    Vault,
+    AccessIndexes,
+    AssociatedAccess,
+    Purchase,
+    Renew,
+    Share,
+    ShareMulti,
+    Withdraw,
+    Close,
+}
+
+const STEPS: [Step; 12] = [
+    Step::OwnerIndex,
+    Step::Dataset,
+    Step::SetParams,
+    Step::Vault,
+    Step::AccessIndexes,
+    Step::AssociatedAccess,
+    Step::Purchase,
+    Step::Renew,
+    Step::Share,
+    Step::ShareMulti,
+    Step::Withdraw,
+    Step::Close,
+];
+
+/// Accounts of one lifecycle run, the mint and token accounts are written
+/// at genesis so only DataNexus instructions run under the budget
+struct Fixture {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    recipients: [Keypair; 2],
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+    owner_tokens: Pubkey,
+}
+
+impl Fixture {
+    async fn start(max_units: u64, layout: Layout) -> Self {
+        let owner = Keypair::new();
+        let buyer = Keypair::new();
+        let recipients = [Keypair::new(), Keypair::new()];
+        let mint = Pubkey::new_unique();
+        let buyer_tokens = Pubkey::new_unique();
+        let owner_tokens = Pubkey::new_unique();
+
+        let mut program_test = Harness::bpf_program_test();
+        program_test.set_bpf_compute_max_units(max_units);
+
+        for authority in [&owner, &buyer, &recipients[0], &recipients[1]].iter() {
+            program_test.add_account(
+                authority.pubkey(),
+                Account::new(LAMPORTS_PER_SOL, 0, &system_program::id()),
+            );
+        }
+
+        add_packed_account(
+            &mut program_test,
+            mint,
+            LAMPORTS_PER_SOL,
+            &Mint {
+                mint_authority: COption::None,
+                supply: 10 * VALUE,
+                decimals: DECIMALS,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &spl_token::id(),
+        );
+        // Funded for the current layout, so migrating a legacy index needs
+        // no transfer and only the repack is measured
+        program_test.add_account(
+            access_index(&buyer.pubkey()),
+            Account {
+                lamports: Rent::default().minimum_balance(AccountIndex::LEN),
+                data: layout.seeded_index(),
+                owner: datanexus_program::id(),
+                ..Account::default()
+            },
+        );
+        for (address, authority, amount) in [
+            (buyer_tokens, buyer.pubkey(), 10 * VALUE),
+            (owner_tokens, owner.pubkey(), 0),
+        ]
+        .iter()
+        {
+            add_packed_account(
+                &mut program_test,
+                *address,
+                LAMPORTS_PER_SOL,
+                &TokenAccount {
+                    mint,
+                    owner: *authority,
+                    amount: *amount,
+                    state: AccountState::Initialized,
+                    ..TokenAccount::default()
+                },
+                &spl_token::id(),
+            );
+        }
+
+        Fixture {
+            harness: Harness::start_with(program_test).await,
+            owner,
+            buyer,
+            recipients,
+            mint,
+            buyer_tokens,
+            owner_tokens,
+        }
+    }
+
+    async fn run(&mut self, step: Step) -> Result<(), TransportError> {
+        let harness = &mut self.harness;
+        let owner = self.owner.pubkey();
+        let buyer = self.buyer.pubkey();
+
+        match step {
+            Step::OwnerIndex => harness.create_owner_index(&owner).await,
+            Step::Dataset => {
+                harness
+                    .create_dataset_at(&self.owner, HASH, dataset(&HASH))
+                    .await
+            }
+            Step::SetParams => {
+                harness
+                    .set_params(&self.owner, HASH, Params::Init([7; 32], VALUE, 3, None))
+                    .await?;
+                harness
+                    .set_params(&self.owner, HASH, Params::AccessDuration(3600))
+                    .await
+            }
+            Step::Vault => harness.init_vault(&self.owner, HASH, self.mint).await,
+            Step::AccessIndexes => {
+                for recipient in self.recipients.iter() {
+                    harness.create_access_index(&recipient.pubkey()).await?;
+                }
+                Ok(())
+            }
+            Step::AssociatedAccess => {
+                harness.create_associated_access(&self.buyer, HASH).await?;
+                for recipient in self.recipients.iter() {
+                    harness.create_associated_access(recipient, HASH).await?;
+                }
+                Ok(())
+            }
+            Step::Purchase => {
+                harness
+                    .purchase(
+                        &self.buyer,
+                        self.buyer_tokens,
+                        &owner,
+                        vault(&HASH, &self.mint),
+                        self.mint,
+                        HASH,
+                        VALUE,
+                    )
+                    .await
+            }
+            Step::Renew => {
+                let instruction = renew_access(
+                    datanexus_program::id(),
+                    RenewAccessAccounts {
+                        user_authority: buyer,
+                        user_access_account: associated_access(&buyer, &HASH),
+                        user_token_account: self.buyer_tokens,
+                        proceeds_account: vault(&HASH, &self.mint),
+                        token_mint: self.mint,
+                        dataset_account: dataset(&HASH),
+                        token_program: spl_token::id(),
+                        referrer_token_account: None,
+                    },
+                    HASH,
+                    VALUE,
+                )
+                .unwrap();
+                harness.process(&[instruction], &[&self.buyer]).await
+            }
+            Step::Share => {
+                harness
+                    .share(&self.buyer, &self.recipients[0].pubkey(), HASH)
+                    .await
+            }
+            Step::ShareMulti => {
+                let recipient = self.recipients[1].pubkey();
+                let instruction = share_access_multi(
+                    datanexus_program::id(),
+                    ShareAccessMultiAccounts {
+                        user_authority: buyer,
+                        user_access_account: associated_access(&buyer, &HASH),
+                        dataset_account: dataset(&HASH),
+                        recipients: vec![RecipientAccounts {
+                            authority: recipient,
+                            access_index: access_index(&recipient),
+                            access_account: associated_access(&recipient, &HASH),
+                        }],
+                    },
+                    HASH,
+                )
+                .unwrap();
+                harness.process(&[instruction], &[&self.buyer]).await
+            }
+            Step::Withdraw => {
+                harness
+                    .withdraw(&self.owner, HASH, self.mint, self.owner_tokens, 2 * VALUE)
+                    .await
+            }
+            Step::Close => {
+                let instruction = close_dataset(
+                    datanexus_program::id(),
+                    CloseDatasetAccounts {
+                        authority: owner,
+                        owner_index: owner_index(&owner),
+                        dataset_account: dataset(&HASH),
+                    },
+                    HASH,
+                    true,
+                )
+                .unwrap();
+                harness.process(&[instruction], &[&self.owner]).await
+            }
+        }
+    }
+}
+
+/// Runs every step up to and including `last` with each instruction
+/// limited to `max_units`
+async fn run_until(max_units: u64, last: Step) -> Result<(), TransportError> {
+    let mut fixture = Fixture::start(max_units, Layout::Current).await;
+    for step in STEPS.iter().take_while(|step| **step <= last) {
+        fixture.run(*step).await?;
+    }
+
+    Ok(())
+}
+
+/// What a budget is bisected for
+#[derive(Clone, Copy, Debug)]
+enum Measure {
+    /// The lifecycle up to and including a step
+    Lifecycle(Step),
+    /// Unpacking and repacking the seeded access index, by migrating it
+    /// from the legacy layout
+    IndexRepack,
+}
+
+impl Measure {
+    async fn run(self, max_units: u64) -> Result<(), TransportError> {
+        match self {
+            Measure::Lifecycle(step) => run_until(max_units, step).await,
+            Measure::IndexRepack => {
+                let mut fixture = Fixture::start(max_units, Layout::Legacy).await;
+                let index = access_index(&fixture.buyer.pubkey());
+                fixture.harness.migrate(index).await
+            }
+        }
+    }
+}
+
+/// Smallest budget, within `PRECISION`, `measure` fits in
+async fn units(measure: Measure) -> u64 {
+    if let Err(err) = measure.run(MAX_UNITS).await {
+        panic!("{:?} fails within the default budget: {:?}", measure, err);
+    }
+
+    let (mut low, mut high) = (0, MAX_UNITS);
+    while high - low > PRECISION {
+        let mid = low + (high - low) / 2;
+        match measure.run(mid).await {
+            Ok(()) => high = mid,
+            Err(_) => low = mid,
+        }
+    }
+
+    high
+}
+
+#[tokio::test]
+async fn report_compute_units() {
+    for step in STEPS.iter() {
+        println!(
+            "{:?}: {} units",
+            step,
+            units(Measure::Lifecycle(*step)).await
+        );
+    }
+    println!("IndexRepack: {} units", units(Measure::IndexRepack).await);
+}
+
+#[tokio::test]
+async fn purchase_fits_half_the_budget() {
+    let purchase = units(Measure::Lifecycle(Step::Purchase)).await;
+
+    assert!(
+        purchase <= PURCHASE_MAX_UNITS,
+        "purchase used {} units, more than {}",
+        purchase,
+        PURCHASE_MAX_UNITS
+    );
+}
+
+#[tokio::test]
+async fn purchase_halves_the_repacking_layout() {
+    let purchase = units(Measure::Lifecycle(Step::Purchase)).await;
+    let repacking_purchase = purchase + units(Measure::IndexRepack).await;
+
+    assert!(
+        purchase * 2 <= repacking_purchase,
+        "purchase used {} units, more than half of the {} it would repacking its index",
+        purchase,
+        repacking_purchase
+    );
+}
@@ -0,0 +1,413 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::InitAssociatedAccessAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{init_account, init_associated_access, AccountType, DataNexusInstruction},
+    state::{AccountIndex, DatasetState},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+#[tokio::test]
+async fn zero_balance_accounts_are_created() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+
+    let account = harness
+        .banks
+        .get_account(dataset(&HASH))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, datanexus_program::id());
+    assert_eq!(account.data.len(), DatasetState::LEN);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(DatasetState::LEN)
+    );
+}
+
+#[tokio::test]
+async fn pre_funded_accounts_are_topped_up() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    // The least a system account may hold, short of either account's rent
+    let funded = Rent::default().minimum_balance(0);
+    harness.transfer(&dataset(&HASH), funded).await;
+    harness.transfer(&owner_index(&owner.pubkey()), funded).await;
+
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert!(state.is_initialized);
+    assert_eq!(
+        harness.lamports(dataset(&HASH)).await,
+        Rent::default().minimum_balance(DatasetState::LEN)
+    );
+    assert_eq!(
+        harness.lamports(owner_index(&owner.pubkey())).await,
+        Rent::default().minimum_balance(AccountIndex::LEN)
+    );
+}
+
+#[tokio::test]
+async fn pre_funded_accounts_keep_a_surplus() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let surplus = Rent::default().minimum_balance(AccountIndex::LEN) + 1;
+    harness
+        .transfer(&access_index(&owner.pubkey()), surplus)
+        .await;
+
+    harness.create_access_index(&owner.pubkey()).await.unwrap();
+
+    let account = harness
+        .banks
+        .get_account(access_index(&owner.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, datanexus_program::id());
+    assert_eq!(account.lamports, surplus);
+}
+
+#[tokio::test]
+async fn allocated_accounts_are_rejected() {
+    let owner = Pubkey::new_unique();
+    let mut program_test = Harness::program_test();
+    program_test.add_account(
+        access_index(&owner),
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data: vec![0; 8],
+            owner: system_program::id(),
+            ..Account::default()
+        },
+    );
+    let mut harness = Harness::start_with(program_test).await;
+
+    let result = harness.create_access_index(&owner).await;
+
+    assert_error(result, DataNexusError::AccountAlreadyInitialized);
+}
+
+#[test]
+fn init_account_roundtrips() {
+    for account_type in &[
+        AccountType::DatasetIndex,
+        AccountType::AccessIndex,
+        AccountType::Dataset(HASH),
+        AccountType::Access(HASH),
+    ] {
+        let instruction = DataNexusInstruction::InitAccount(*account_type);
+
+        let packed = instruction.pack();
+        assert_eq!(
+            DataNexusInstruction::unpack(&packed).unwrap().pack(),
+            packed
+        );
+        #[cfg(feature = "borsh-encoding")]
+        {
+            let packed = instruction.pack_borsh().unwrap();
+            assert_eq!(
+                DataNexusInstruction::unpack(&packed)
+                    .unwrap()
+                    .pack_borsh()
+                    .unwrap(),
+                packed
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn index_types_create_their_own_index() {
+    let mut harness = Harness::start().await;
+    let authority = Pubkey::new_unique();
+
+    harness.create_owner_index(&authority).await.unwrap();
+    let index: AccountIndex = harness.state(owner_index(&authority)).await;
+    assert!(index.is_initialized);
+    assert_eq!(harness.lamports(access_index(&authority)).await, 0);
+
+    harness.create_access_index(&authority).await.unwrap();
+    let index: AccountIndex = harness.state(access_index(&authority)).await;
+    assert!(index.is_initialized);
+}
+
+#[tokio::test]
+async fn index_types_are_bound_to_their_seeds() {
+    let mut harness = Harness::start().await;
+    let authority = Pubkey::new_unique();
+
+    let instruction = init_account(
+        datanexus_program::id(),
+        Some(harness.payer.pubkey()),
+        authority,
+        owner_index(&authority),
+        None,
+        system_program::id(),
+        AccountType::AccessIndex,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn access_account_type_is_rejected() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    let authority = Pubkey::new_unique();
+    harness.create_access_index(&authority).await.unwrap();
+
+    assert!(init_account(
+        datanexus_program::id(),
+        Some(harness.payer.pubkey()),
+        authority,
+        associated_access(&authority, &HASH),
+        Some(access_index(&authority)),
+        system_program::id(),
+        AccountType::Access(HASH),
+    )
+    .is_err());
+
+    // Access accounts only come from `InitAssociatedAccess`
+    let instruction = Instruction {
+        program_id: datanexus_program::id(),
+        accounts: vec![
+            AccountMeta::new(harness.payer.pubkey(), true),
+            AccountMeta::new(authority, false),
+            AccountMeta::new(associated_access(&authority, &HASH), false),
+            AccountMeta::new(access_index(&authority), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: DataNexusInstruction::InitAccount(AccountType::Access(HASH)).pack(),
+    };
+    let result = harness.process(&[instruction], &[]).await;
+
+    assert_error(result, DataNexusError::InvalidInstruction);
+    assert_eq!(
+        harness.lamports(associated_access(&authority, &HASH)).await,
+        0
+    );
+}
+
+/// Registers `HASH` to `authority` recorded in `index`, the authority pays
+/// for the dataset and signs when `signed`
+async fn register_into(
+    harness: &mut Harness,
+    authority: &Keypair,
+    index: Pubkey,
+    signed: bool,
+) -> Result<(), TransportError> {
+    let mut instruction = init_account(
+        datanexus_program::id(),
+        None,
+        authority.pubkey(),
+        dataset(&HASH),
+        Some(index),
+        system_program::id(),
+        AccountType::Dataset(HASH),
+    )
+    .unwrap();
+    instruction.accounts[0].is_signer = signed;
+    let signers: &[&Keypair] = if signed { &[authority] } else { &[] };
+    harness.process(&[instruction], signers).await
+}
+
+/// Creates the associated access account of `authority` for `HASH` recorded
+/// in `index`, paid for by the harness payer and signed when `signed`
+async fn init_access_into(
+    harness: &mut Harness,
+    authority: &Keypair,
+    index: Pubkey,
+    signed: bool,
+) -> Result<(), TransportError> {
+    let mut instruction = init_associated_access(
+        datanexus_program::id(),
+        InitAssociatedAccessAccounts {
+            payer: harness.payer.pubkey(),
+            authority: authority.pubkey(),
+            access_index: index,
+            associated_access_account: associated_access(&authority.pubkey(), &HASH),
+            dataset_account: dataset(&HASH),
+            system_program: system_program::id(),
+        },
+        HASH,
+    )
+    .unwrap();
+    instruction.accounts[1].is_signer = signed;
+    let signers: &[&Keypair] = if signed { &[authority] } else { &[] };
+    harness.process(&[instruction], signers).await
+}
+
+async fn index_entries(harness: &mut Harness, index: Pubkey) -> usize {
+    let index: AccountIndex = harness.state(index).await;
+    index.datasets.iter().flatten().count()
+}
+
+#[tokio::test]
+async fn datasets_are_registered_into_the_owner_index_only() {
+    let mut harness = Harness::start().await;
+    let victim = harness.fund(LAMPORTS_PER_SOL).await;
+    let attacker = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&victim, &attacker] {
+        harness
+            .create_owner_index(&authority.pubkey())
+            .await
+            .unwrap();
+    }
+
+    let result = register_into(
+        &mut harness,
+        &attacker,
+        owner_index(&victim.pubkey()),
+        true,
+    )
+    .await;
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(
+        index_entries(&mut harness, owner_index(&victim.pubkey())).await,
+        0
+    );
+
+    register_into(
+        &mut harness,
+        &attacker,
+        owner_index(&attacker.pubkey()),
+        true,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        index_entries(&mut harness, owner_index(&attacker.pubkey())).await,
+        1
+    );
+}
+
+#[tokio::test]
+async fn unsigned_dataset_registrations_fail() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+
+    let result = register_into(&mut harness, &owner, owner_index(&owner.pubkey()), false).await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+    assert_eq!(
+        index_entries(&mut harness, owner_index(&owner.pubkey())).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn associated_access_is_recorded_in_the_access_index_only() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    let victim = harness.fund(LAMPORTS_PER_SOL).await;
+    let attacker = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&victim, &attacker] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+    }
+
+    let result = init_access_into(
+        &mut harness,
+        &attacker,
+        access_index(&victim.pubkey()),
+        true,
+    )
+    .await;
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(
+        index_entries(&mut harness, access_index(&victim.pubkey())).await,
+        0
+    );
+
+    init_access_into(
+        &mut harness,
+        &attacker,
+        access_index(&attacker.pubkey()),
+        true,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        index_entries(&mut harness, access_index(&attacker.pubkey())).await,
+        1
+    );
+}
+
+#[tokio::test]
+async fn unsigned_associated_access_fails() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    let victim = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_access_index(&victim.pubkey())
+        .await
+        .unwrap();
+
+    let result = init_access_into(
+        &mut harness,
+        &victim,
+        access_index(&victim.pubkey()),
+        false,
+    )
+    .await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+    assert_eq!(
+        index_entries(&mut harness, access_index(&victim.pubkey())).await,
+        0
+    );
+    assert_eq!(
+        harness.lamports(associated_access(&victim.pubkey(), &HASH)).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn associated_access_needs_a_created_access_index() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    // Funded at its address, but still a system account
+    harness
+        .transfer(&access_index(&buyer.pubkey()), LAMPORTS_PER_SOL)
+        .await;
+
+    let result = init_access_into(&mut harness, &buyer, access_index(&buyer.pubkey()), true).await;
+
+    assert_error(result, DataNexusError::InvalidAccountOwner);
+    assert_eq!(
+        harness.lamports(associated_access(&buyer.pubkey(), &HASH)).await,
+        0
+    );
+}
@@ -0,0 +1,66 @@
+//! Datasets live at `[DATASET_MARKER, hash]`, the bare hash derivation used
+//! before the marker is only accepted with `legacy-dataset-address`
+
+mod common;
+
+use common::*;
+use datanexus::{datanexus_program, pda::find_legacy_dataset_address, state::DatasetState};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+fn legacy_dataset(hash: &[u8; 32]) -> Pubkey {
+    find_legacy_dataset_address(&datanexus_program::id(), hash).0
+}
+
+async fn setup() -> (Harness, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+    (harness, owner)
+}
+
+#[tokio::test]
+async fn datasets_are_created_at_the_marked_address() {
+    let (mut harness, owner) = setup().await;
+    assert_ne!(dataset(&HASH), legacy_dataset(&HASH));
+
+    harness
+        .create_dataset_at(&owner, HASH, dataset(&HASH))
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.hash, HASH);
+    assert_eq!(state.owner, owner.pubkey());
+}
+
+#[cfg(not(feature = "legacy-dataset-address"))]
+#[tokio::test]
+async fn legacy_dataset_addresses_are_refused() {
+    use datanexus::error::DataNexusError;
+
+    let (mut harness, owner) = setup().await;
+
+    let result = harness
+        .create_dataset_at(&owner, HASH, legacy_dataset(&HASH))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[cfg(feature = "legacy-dataset-address")]
+#[tokio::test]
+async fn legacy_dataset_addresses_are_accepted() {
+    let (mut harness, owner) = setup().await;
+
+    harness
+        .create_dataset_at(&owner, HASH, legacy_dataset(&HASH))
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(legacy_dataset(&HASH)).await;
+    assert_eq!(state.hash, HASH);
+    assert_eq!(state.owner, owner.pubkey());
+}
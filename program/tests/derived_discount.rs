@@ -0,0 +1,260 @@
+//! Buyers holding access to a dataset's reference dataset may pass their
+//! associated access account of it to purchase at the derived discount
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::PurchaseAccessAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{purchase_access, Params},
+    state::{AccessInfo, AccessState, DatasetState},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const REFERENCE_HASH: [u8; 32] = [2; 32];
+
+const OTHER_HASH: [u8; 32] = [3; 32];
+
+const VALUE: u64 = 101;
+
+const DISCOUNT_BPS: u16 = 2_500;
+
+/// `VALUE` less `DISCOUNT_BPS` of it, the quarter of a unit rounded off the
+/// discount
+const DISCOUNTED: u64 = 76;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+}
+
+impl Setup {
+    /// Purchase by the buyer paying from `buyer_tokens` into the vault of
+    /// `mint`, claiming the discount with `reference_access`
+    fn accounts(
+        &self,
+        mint: Pubkey,
+        buyer_tokens: Pubkey,
+        reference_access: Option<Pubkey>,
+    ) -> PurchaseAccessAccounts<Pubkey> {
+        PurchaseAccessAccounts {
+            user_authority: self.buyer.pubkey(),
+            user_access_index: access_index(&self.buyer.pubkey()),
+            user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+            user_token_account: buyer_tokens,
+            owner_authority: self.owner.pubkey(),
+            proceeds_account: vault(&HASH, &mint),
+            token_mint: mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+            receipt: None,
+            reference_access,
+        }
+    }
+
+    /// Purchase paying `amount` from accounts no check before the discount
+    /// reads
+    async fn purchase_unfunded(
+        &mut self,
+        reference_access: Option<Pubkey>,
+        amount: u64,
+    ) -> Result<(), TransportError> {
+        let accounts = self.accounts(Pubkey::new_unique(), Pubkey::new_unique(), reference_access);
+        purchase(&mut self.harness, accounts, &self.buyer, amount).await
+    }
+}
+
+async fn purchase(
+    harness: &mut Harness,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    buyer: &Keypair,
+    amount: u64,
+) -> Result<(), TransportError> {
+    let instruction = purchase_access(datanexus_program::id(), accounts, HASH, amount).unwrap();
+    harness.process(&[instruction], &[buyer]).await
+}
+
+/// Access account of the buyer granting `hash` until `expires_at`
+fn grant(hash: [u8; 32], expires_at: Option<i64>) -> AccessState {
+    AccessState {
+        is_initialized: true,
+        pointer: None,
+        datasets: vec![AccessInfo {
+            hash,
+            key: None,
+            shared_from: None,
+            share_quota: 0,
+            expires_at,
+            depth: 0,
+            shares_used: 0,
+        }],
+        pending: vec![],
+    }
+}
+
+/// A dataset worth `VALUE` referencing another at `DISCOUNT_BPS` off, and a
+/// buyer holding a grant of the reference dataset until `expires_at`, and
+/// of `OTHER_HASH` for good
+async fn setup(expires_at: Option<i64>) -> Setup {
+    let buyer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    for (hash, expires_at) in [(REFERENCE_HASH, expires_at), (OTHER_HASH, None)].iter() {
+        add_packed_account(
+            &mut program_test,
+            associated_access(&buyer.pubkey(), hash),
+            Rent::default().minimum_balance(AccessState::LEN),
+            &grant(*hash, *expires_at),
+            &datanexus_program::id(),
+        );
+    }
+    let mut harness = Harness::start_with(program_test).await;
+
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    harness
+        .create_dataset_at(&owner, REFERENCE_HASH, dataset(&REFERENCE_HASH))
+        .await
+        .unwrap();
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::ReferenceData(dataset(&REFERENCE_HASH)),
+        )
+        .await
+        .unwrap();
+    harness
+        .set_params(&owner, HASH, Params::DerivedDiscountBps(DISCOUNT_BPS))
+        .await
+        .unwrap();
+
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+    }
+}
+
+#[tokio::test]
+async fn reference_holders_pay_the_derived_price() {
+    let mut setup = setup(None).await;
+    let mint = setup.harness.create_mint().await;
+    let buyer_tokens = setup
+        .harness
+        .create_token_account(&mint, &setup.buyer.pubkey(), VALUE)
+        .await;
+    setup.harness.create_vault(&setup.owner, HASH, mint).await;
+
+    let reference_access = associated_access(&setup.buyer.pubkey(), &REFERENCE_HASH);
+    let accounts = setup.accounts(mint, buyer_tokens, Some(reference_access));
+    purchase(&mut setup.harness, accounts, &setup.buyer, DISCOUNTED)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        setup.harness.token_balance(buyer_tokens).await,
+        VALUE - DISCOUNTED
+    );
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.claimable(&mint), Some(DISCOUNTED));
+    let access: AccessState = setup
+        .harness
+        .state(associated_access(&setup.buyer.pubkey(), &HASH))
+        .await;
+    assert!(access.find_entry(&HASH).is_some());
+}
+
+#[tokio::test]
+async fn discounts_round_in_the_owners_favour() {
+    let mut setup = setup(None).await;
+    let reference_access = associated_access(&setup.buyer.pubkey(), &REFERENCE_HASH);
+
+    let result = setup
+        .purchase_unfunded(Some(reference_access), DISCOUNTED - 1)
+        .await;
+
+    assert_error(result, DataNexusError::InsufficientPayment);
+}
+
+#[tokio::test]
+async fn the_discount_needs_the_reference_access() {
+    let mut setup = setup(None).await;
+
+    let result = setup.purchase_unfunded(None, DISCOUNTED).await;
+
+    assert_error(result, DataNexusError::InsufficientPayment);
+}
+
+#[tokio::test]
+async fn access_to_another_dataset_is_refused() {
+    let mut setup = setup(None).await;
+    let forged = associated_access(&setup.buyer.pubkey(), &OTHER_HASH);
+
+    let result = setup.purchase_unfunded(Some(forged), VALUE).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn another_holders_reference_access_is_refused() {
+    let mut setup = setup(None).await;
+    let holder = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup
+        .harness
+        .create_access_index(&holder.pubkey())
+        .await
+        .unwrap();
+    setup
+        .harness
+        .create_associated_access(&holder, REFERENCE_HASH)
+        .await
+        .unwrap();
+    let forged = associated_access(&holder.pubkey(), &REFERENCE_HASH);
+
+    let result = setup.purchase_unfunded(Some(forged), VALUE).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn expired_reference_access_is_refused() {
+    let mut setup = setup(Some(1_000)).await;
+    setup.harness.set_unix_timestamp(1_000).await;
+    let reference_access = associated_access(&setup.buyer.pubkey(), &REFERENCE_HASH);
+
+    let result = setup.purchase_unfunded(Some(reference_access), VALUE).await;
+
+    assert_error(result, DataNexusError::NoExistingAccess);
+}
+
+#[tokio::test]
+async fn discounts_are_at_most_the_whole_price() {
+    let mut setup = setup(None).await;
+
+    let result = setup
+        .harness
+        .set_params(&setup.owner, HASH, Params::DerivedDiscountBps(10_001))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidBasisPoints);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.derived_discount_bps, DISCOUNT_BPS);
+}
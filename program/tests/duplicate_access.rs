@@ -0,0 +1,229 @@
+//! A dataset is granted at most once per access account, only lapsed grants
+//! are bought again and they can't be shared on
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{RecipientAccounts, ShareAccessMultiAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::share_access_multi,
+    state::{AccessInfo, AccessState},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+const SHARE_LIMIT: u16 = 2;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    recipient: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+}
+
+impl Setup {
+    async fn purchase(&mut self) -> Result<(), TransportError> {
+        self.harness
+            .purchase(
+                &self.buyer,
+                self.buyer_tokens,
+                &self.owner.pubkey(),
+                vault(&HASH, &self.mint),
+                self.mint,
+                HASH,
+                VALUE,
+            )
+            .await
+    }
+
+    async fn access(&mut self, authority: &Pubkey) -> AccessState {
+        self.harness
+            .state(associated_access(authority, &HASH))
+            .await
+    }
+}
+
+/// A dataset, a buyer holding enough for two purchases and a recipient,
+/// both with their access accounts. `buyer_access` is written to the
+/// buyer's associated access account in place of an empty one
+async fn setup(buyer_access: Option<AccessInfo>) -> Setup {
+    let buyer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    if let Some(access) = buyer_access {
+        add_packed_account(
+            &mut program_test,
+            associated_access(&buyer.pubkey(), &HASH),
+            Rent::default().minimum_balance(AccessState::LEN),
+            &AccessState {
+                is_initialized: true,
+                pointer: None,
+                datasets: vec![access],
+                pending: vec![],
+            },
+            &datanexus_program::id(),
+        );
+    }
+    let mut harness = Harness::start_with(program_test).await;
+    harness.transfer(&buyer.pubkey(), LAMPORTS_PER_SOL).await;
+
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .register_dataset(&owner, HASH, VALUE, SHARE_LIMIT)
+        .await;
+    let mint = harness.create_mint().await;
+    harness.create_vault(&owner, HASH, mint).await;
+
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&buyer, &recipient] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+    }
+    if buyer_access.is_none() {
+        harness
+            .create_associated_access(&buyer, HASH)
+            .await
+            .unwrap();
+    }
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 2 * VALUE)
+        .await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        recipient,
+        mint,
+        buyer_tokens,
+    }
+}
+
+/// A grant of the dataset that lapsed at the start of the epoch
+fn expired_access() -> AccessInfo {
+    AccessInfo {
+        hash: HASH,
+        key: Some([7; 32]),
+        shared_from: None,
+        share_quota: SHARE_LIMIT,
+        expires_at: Some(1),
+        depth: 0,
+        shares_used: 0,
+    }
+}
+
+#[tokio::test]
+async fn purchasing_twice_fails() {
+    let mut setup = setup(None).await;
+    setup.purchase().await.unwrap();
+
+    // Another fee payer keeps the repeated purchase a distinct transaction
+    setup.harness.payer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let result = setup.purchase().await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    assert_eq!(setup.harness.token_balance(setup.buyer_tokens).await, VALUE);
+    let access = setup.access(&setup.buyer.pubkey()).await;
+    assert_eq!(access.datasets.len(), 1);
+}
+
+#[tokio::test]
+async fn sharing_with_a_purchaser_fails() {
+    let mut setup = setup(None).await;
+    setup.purchase().await.unwrap();
+    let recipient = setup.recipient.pubkey();
+    let recipient_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &recipient, VALUE)
+        .await;
+    setup
+        .harness
+        .purchase(
+            &setup.recipient,
+            recipient_tokens,
+            &setup.owner.pubkey(),
+            vault(&HASH, &setup.mint),
+            setup.mint,
+            HASH,
+            VALUE,
+        )
+        .await
+        .unwrap();
+
+    let result = setup.harness.share(&setup.buyer, &recipient, HASH).await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    let access = setup.access(&recipient).await;
+    assert_eq!(access.datasets.len(), 1);
+    assert_eq!(access.datasets[0].shared_from, None);
+}
+
+#[tokio::test]
+async fn sharing_twice_in_one_instruction_fails() {
+    let mut setup = setup(None).await;
+    setup.purchase().await.unwrap();
+    let recipient = setup.recipient.pubkey();
+    let recipient_accounts = || RecipientAccounts {
+        authority: recipient,
+        access_index: access_index(&recipient),
+        access_account: associated_access(&recipient, &HASH),
+    };
+
+    let instruction = share_access_multi(
+        datanexus_program::id(),
+        ShareAccessMultiAccounts {
+            user_authority: setup.buyer.pubkey(),
+            user_access_account: associated_access(&setup.buyer.pubkey(), &HASH),
+            dataset_account: dataset(&HASH),
+            recipients: vec![recipient_accounts(), recipient_accounts()],
+        },
+        HASH,
+    )
+    .unwrap();
+    let result = setup.harness.process(&[instruction], &[&setup.buyer]).await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    assert_eq!(setup.access(&recipient).await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn expired_access_is_bought_again_in_place() {
+    let mut setup = setup(Some(expired_access())).await;
+
+    setup.purchase().await.unwrap();
+
+    let access = setup.access(&setup.buyer.pubkey()).await;
+    assert_eq!(access.datasets.len(), 1);
+    assert_eq!(access.datasets[0].expires_at, None);
+}
+
+#[tokio::test]
+async fn expired_access_cannot_be_shared() {
+    let mut setup = setup(Some(expired_access())).await;
+    let recipient = setup.recipient.pubkey();
+
+    let result = setup.harness.share(&setup.buyer, &recipient, HASH).await;
+
+    assert_error(result, DataNexusError::AccessExpired);
+    assert_eq!(setup.access(&recipient).await.find_entry(&HASH), None);
+    let access = setup.access(&setup.buyer.pubkey()).await;
+    assert_eq!(access.datasets[0].shares_remaining(), SHARE_LIMIT);
+}
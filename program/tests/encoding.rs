@@ -0,0 +1,151 @@
+//! The hand-packed and borsh encodings of the same instructions leave
+//! identical account state behind
+#![cfg(feature = "borsh-encoding")]
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{InitAssociatedAccessAccounts, SetDataParamsAccounts},
+    datanexus_program,
+    instruction::{
+        init_account, init_associated_access, set_data_params, AccountType, DataNexusInstruction,
+        Params, BORSH_ENCODING,
+    },
+    state::PriceTier,
+};
+use solana_program::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, system_program,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Re-encodes the data of a hand-packed `instruction` with borsh
+fn to_borsh(mut instruction: Instruction) -> Instruction {
+    instruction.data = DataNexusInstruction::unpack(&instruction.data)
+        .unwrap()
+        .pack_borsh()
+        .unwrap();
+    instruction
+}
+
+fn steps(owner: &Pubkey) -> Vec<Instruction> {
+    let set_params = |params: Params| {
+        set_data_params(
+            datanexus_program::id(),
+            SetDataParamsAccounts {
+                authority: *owner,
+                dataset_account: dataset(&HASH),
+                reference_dataset: None,
+            },
+            HASH,
+            params,
+            &[],
+        )
+        .unwrap()
+    };
+
+    vec![
+        init_account(
+            datanexus_program::id(),
+            None,
+            *owner,
+            owner_index(owner),
+            None,
+            system_program::id(),
+            AccountType::DatasetIndex,
+        )
+        .unwrap(),
+        init_account(
+            datanexus_program::id(),
+            None,
+            *owner,
+            access_index(owner),
+            None,
+            system_program::id(),
+            AccountType::AccessIndex,
+        )
+        .unwrap(),
+        init_account(
+            datanexus_program::id(),
+            None,
+            *owner,
+            dataset(&HASH),
+            Some(owner_index(owner)),
+            system_program::id(),
+            AccountType::Dataset(HASH),
+        )
+        .unwrap(),
+        set_params(Params::Init([7; 32], 100, 3, None)),
+        set_params(Params::Uri(b"ipfs://dataset".to_vec())),
+        set_params(Params::Resharable(true)),
+        set_params(Params::Tiers(vec![PriceTier {
+            min_seats: 10,
+            unit_price: 80,
+        }])),
+        init_associated_access(
+            datanexus_program::id(),
+            InitAssociatedAccessAccounts {
+                payer: *owner,
+                authority: *owner,
+                access_index: access_index(owner),
+                associated_access_account: associated_access(owner, &HASH),
+                dataset_account: dataset(&HASH),
+                system_program: system_program::id(),
+            },
+            HASH,
+        )
+        .unwrap(),
+    ]
+}
+
+/// Raw data of every account `steps` writes, after sending them through
+/// `encode`
+async fn run(owner: &Keypair, encode: fn(Instruction) -> Instruction) -> Vec<Vec<u8>> {
+    let mut harness = Harness::start().await;
+    harness.transfer(&owner.pubkey(), LAMPORTS_PER_SOL).await;
+
+    for instruction in steps(&owner.pubkey()) {
+        harness
+            .process(&[encode(instruction)], &[owner])
+            .await
+            .unwrap();
+    }
+
+    let mut accounts = Vec::new();
+    for address in [
+        owner_index(&owner.pubkey()),
+        access_index(&owner.pubkey()),
+        dataset(&HASH),
+        associated_access(&owner.pubkey(), &HASH),
+    ] {
+        let account = harness.banks.get_account(address).await.unwrap().unwrap();
+        accounts.push(account.data);
+    }
+    accounts
+}
+
+#[tokio::test]
+async fn both_encodings_write_the_same_state() {
+    let owner = Keypair::new();
+
+    let legacy = run(&owner, |instruction| instruction).await;
+    let borsh = run(&owner, to_borsh).await;
+
+    assert_eq!(legacy, borsh);
+}
+
+#[test]
+fn legacy_data_is_unprefixed() {
+    for instruction in steps(&Pubkey::new_unique()) {
+        assert_ne!(instruction.data[0], BORSH_ENCODING);
+
+        let borsh = to_borsh(instruction.clone());
+        assert_eq!(borsh.data[0], BORSH_ENCODING);
+        assert_eq!(
+            DataNexusInstruction::unpack(&instruction.data).unwrap(),
+            DataNexusInstruction::unpack(&borsh.data).unwrap()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Instructions carry the dataset hash and the dataset account separately,
+//! each of these passes a cheap dataset registered under another hash
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{PurchaseAccessAccounts, SetDataParamsAccounts, ShareAccessAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{purchase_access, set_data_params, share_access, Params},
+    state::{AccessState, DatasetState},
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Hash of the dataset the attacker registers
+const OTHER_HASH: [u8; 32] = [2; 32];
+
+/// Owner of `HASH` and an attacker owning `OTHER_HASH`, priced at 1
+async fn setup() -> (Harness, Keypair, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let attacker = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness.register_dataset(&attacker, OTHER_HASH, 1, 2).await;
+
+    (harness, owner, attacker)
+}
+
+#[tokio::test]
+async fn set_params_rejects_another_dataset() {
+    let (mut harness, _, attacker) = setup().await;
+
+    let instruction = set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: attacker.pubkey(),
+            dataset_account: dataset(&OTHER_HASH),
+            reference_dataset: None,
+        },
+        HASH,
+        Params::Value(5),
+        &[],
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&attacker]).await;
+
+    assert_error(result, DataNexusError::HashMismatch);
+    let state: DatasetState = harness.state(dataset(&OTHER_HASH)).await;
+    assert_eq!(state.value, Some(1));
+}
+
+#[tokio::test]
+async fn purchase_rejects_another_dataset() {
+    let (mut harness, owner, attacker) = setup().await;
+    let mint = harness.create_mint().await;
+    let attacker_tokens = harness
+        .create_token_account(&mint, &attacker.pubkey(), 1)
+        .await;
+    let owner_tokens = harness
+        .create_token_account(&mint, &owner.pubkey(), 0)
+        .await;
+    harness
+        .create_access_index(&attacker.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&attacker, OTHER_HASH)
+        .await
+        .unwrap();
+
+    let instruction = purchase_access(
+        datanexus_program::id(),
+        PurchaseAccessAccounts {
+            user_authority: attacker.pubkey(),
+            user_access_index: access_index(&attacker.pubkey()),
+            user_access_account: associated_access(&attacker.pubkey(), &OTHER_HASH),
+            user_token_account: attacker_tokens,
+            owner_authority: owner.pubkey(),
+            proceeds_account: owner_tokens,
+            token_mint: mint,
+            dataset_account: dataset(&OTHER_HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+            receipt: None,
+            reference_access: None,
+        },
+        HASH,
+        1,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&attacker]).await;
+
+    assert_error(result, DataNexusError::HashMismatch);
+    let access: AccessState = harness
+        .state(associated_access(&attacker.pubkey(), &OTHER_HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+    assert_eq!(harness.token_balance(attacker_tokens).await, 1);
+}
+
+#[tokio::test]
+async fn share_rejects_another_dataset() {
+    let (mut harness, _, attacker) = setup().await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&attacker, &recipient] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, OTHER_HASH)
+            .await
+            .unwrap();
+    }
+
+    let instruction = share_access(
+        datanexus_program::id(),
+        ShareAccessAccounts {
+            user_authority: attacker.pubkey(),
+            user_access_account: associated_access(&attacker.pubkey(), &OTHER_HASH),
+            recipient_authority: recipient.pubkey(),
+            recipient_access_index: access_index(&recipient.pubkey()),
+            recipient_access_account: associated_access(&recipient.pubkey(), &OTHER_HASH),
+            dataset_account: dataset(&OTHER_HASH),
+        },
+        HASH,
+        None,
+        None,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&attacker]).await;
+
+    assert_error(result, DataNexusError::HashMismatch);
+    let shared: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &OTHER_HASH))
+        .await;
+    assert_eq!(shared.find_entry(&HASH), None);
+}
@@ -0,0 +1,514 @@
+#![cfg(feature = "idl")]
+
+use datanexus::{
+    accounts::{
+        AcceptOwnershipAccounts, AcceptShareAccounts, AddToCollectionAccounts,
+        ClaimCollectionAccessAccounts, CloseDatasetAccounts, CompactIndexAccounts,
+        CrankPruneAccounts, CreateCollectionAccounts, FundBountyAccounts,
+        InitAssociatedAccessAccounts, InitReceiptMintAccounts, InitVaultAccounts, MemberAccounts,
+        MigrateAccountAccounts, OwnershipTransferAccounts, PurchaseAccessAccounts,
+        PurchaseCollectionAccounts, RecipientAccounts, RejectShareAccounts, RenewAccessAccounts,
+        ResizeIndexAccounts, RevokeAccessAccounts, SetAccessPreferencesAccounts,
+        SetDataParamsAccounts, ShareAccessAccounts, ShareAccessMultiAccounts,
+        WithdrawProceedsAccounts,
+    },
+    idl::{type_def, Type, INSTRUCTIONS},
+    instruction::{
+        accept_ownership, accept_ownership_borsh, accept_share, accept_share_borsh,
+        add_to_collection, add_to_collection_borsh, cancel_ownership_transfer,
+        cancel_ownership_transfer_borsh, claim_collection_access, claim_collection_access_borsh,
+        close_dataset, close_dataset_borsh, compact_index, compact_index_borsh, crank_prune,
+        crank_prune_borsh, create_collection, create_collection_borsh, fund_bounty,
+        fund_bounty_borsh, init_account, init_account_borsh, init_associated_access,
+        init_associated_access_borsh, init_receipt_mint, init_receipt_mint_borsh, init_vault,
+        init_vault_borsh, migrate_account, migrate_account_borsh, propose_ownership_transfer,
+        propose_ownership_transfer_borsh, purchase_access, purchase_access_borsh,
+        purchase_collection, purchase_collection_borsh, purchase_seats, purchase_seats_borsh,
+        reject_share, reject_share_borsh, renew_access, renew_access_borsh, resize_index,
+        resize_index_borsh, revoke_access, revoke_access_borsh, set_access_preferences,
+        set_access_preferences_borsh, set_data_params, set_data_params_borsh, share_access,
+        share_access_borsh, share_access_multi, share_access_multi_borsh, withdraw_proceeds,
+        withdraw_proceeds_borsh, AccountType, Params,
+    },
+    state::{
+        AccessInfo, AccessState, AccountIndex, CollectionAccess, CollectionState, DatasetState,
+        PendingShare, RegistryState,
+    },
+};
+use solana_program::{
+    instruction::Instruction, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+fn key() -> Pubkey {
+    Pubkey::new_unique()
+}
+
+/// The legacy and borsh encoded instruction of each IDL entry
+fn built(name: &str) -> (Instruction, Instruction) {
+    type Builder = fn(Pubkey) -> Result<Instruction, ProgramError>;
+    let (legacy, borsh): (Builder, Builder) = match name {
+        "initIndex" => (
+            |id| {
+                init_account(
+                    id,
+                    None,
+                    key(),
+                    key(),
+                    None,
+                    key(),
+                    AccountType::AccessIndex,
+                )
+            },
+            |id| {
+                init_account_borsh(
+                    id,
+                    None,
+                    key(),
+                    key(),
+                    None,
+                    key(),
+                    AccountType::AccessIndex,
+                )
+            },
+        ),
+        "initDataset" => (
+            |id| {
+                let account_type = AccountType::Dataset(HASH);
+                init_account(id, None, key(), key(), Some(key()), key(), account_type)
+            },
+            |id| {
+                let account_type = AccountType::Dataset(HASH);
+                init_account_borsh(id, None, key(), key(), Some(key()), key(), account_type)
+            },
+        ),
+        "setDataParams" => (
+            |id| set_data_params(id, set_data_params_accounts(), HASH, Params::Value(1), &[]),
+            |id| set_data_params_borsh(id, set_data_params_accounts(), HASH, Params::Value(1), &[]),
+        ),
+        "purchaseAccess" => (
+            |id| purchase_access(id, purchase_access_accounts(), HASH, 1),
+            |id| purchase_access_borsh(id, purchase_access_accounts(), HASH, 1),
+        ),
+        "shareAccess" => (
+            |id| share_access(id, share_access_accounts(), HASH, Some(1), None),
+            |id| share_access_borsh(id, share_access_accounts(), HASH, Some(1), None),
+        ),
+        "closeDataset" => (
+            |id| close_dataset(id, close_dataset_accounts(), HASH, false),
+            |id| close_dataset_borsh(id, close_dataset_accounts(), HASH, false),
+        ),
+        "renewAccess" => (
+            |id| renew_access(id, renew_access_accounts(), HASH, 1),
+            |id| renew_access_borsh(id, renew_access_accounts(), HASH, 1),
+        ),
+        "shareAccessMulti" => (
+            |id| share_access_multi(id, share_access_multi_accounts(), HASH),
+            |id| share_access_multi_borsh(id, share_access_multi_accounts(), HASH),
+        ),
+        "initAssociatedAccess" => (
+            |id| init_associated_access(id, init_associated_access_accounts(), HASH),
+            |id| init_associated_access_borsh(id, init_associated_access_accounts(), HASH),
+        ),
+        "resizeIndex" => (
+            |id| resize_index(id, resize_index_accounts(), 1),
+            |id| resize_index_borsh(id, resize_index_accounts(), 1),
+        ),
+        "purchaseCollection" => (
+            |id| purchase_collection(id, purchase_collection_accounts(), HASH, 1),
+            |id| purchase_collection_borsh(id, purchase_collection_accounts(), HASH, 1),
+        ),
+        "createCollection" => (
+            |id| create_collection(id, create_collection_accounts(), HASH, 1),
+            |id| create_collection_borsh(id, create_collection_accounts(), HASH, 1),
+        ),
+        "addToCollection" => (
+            |id| add_to_collection(id, add_to_collection_accounts(), HASH, HASH),
+            |id| add_to_collection_borsh(id, add_to_collection_accounts(), HASH, HASH),
+        ),
+        "claimCollectionAccess" => (
+            |id| claim_collection_access(id, claim_collection_access_accounts(), HASH),
+            |id| claim_collection_access_borsh(id, claim_collection_access_accounts(), HASH),
+        ),
+        "purchaseSeats" => (
+            |id| purchase_seats(id, purchase_access_accounts(), HASH, 2, 1),
+            |id| purchase_seats_borsh(id, purchase_access_accounts(), HASH, 2, 1),
+        ),
+        "initVault" => (
+            |id| init_vault(id, init_vault_accounts(), HASH),
+            |id| init_vault_borsh(id, init_vault_accounts(), HASH),
+        ),
+        "initReceiptMint" => (
+            |id| init_receipt_mint(id, init_receipt_mint_accounts(), HASH),
+            |id| init_receipt_mint_borsh(id, init_receipt_mint_accounts(), HASH),
+        ),
+        "migrateAccount" => (
+            |id| migrate_account(id, migrate_account_accounts()),
+            |id| migrate_account_borsh(id, migrate_account_accounts()),
+        ),
+        "revokeAccess" => (
+            |id| revoke_access(id, revoke_access_accounts(), HASH),
+            |id| revoke_access_borsh(id, revoke_access_accounts(), HASH),
+        ),
+        "withdrawProceeds" => (
+            |id| withdraw_proceeds(id, withdraw_proceeds_accounts(), HASH, 1, &[]),
+            |id| withdraw_proceeds_borsh(id, withdraw_proceeds_accounts(), HASH, 1, &[]),
+        ),
+        "proposeOwnershipTransfer" => (
+            |id| propose_ownership_transfer(id, ownership_transfer_accounts(), HASH, key(), &[]),
+            |id| {
+                propose_ownership_transfer_borsh(
+                    id,
+                    ownership_transfer_accounts(),
+                    HASH,
+                    key(),
+                    &[],
+                )
+            },
+        ),
+        "acceptOwnership" => (
+            |id| accept_ownership(id, accept_ownership_accounts(), HASH, &[]),
+            |id| accept_ownership_borsh(id, accept_ownership_accounts(), HASH, &[]),
+        ),
+        "cancelOwnershipTransfer" => (
+            |id| cancel_ownership_transfer(id, ownership_transfer_accounts(), HASH, &[]),
+            |id| cancel_ownership_transfer_borsh(id, ownership_transfer_accounts(), HASH, &[]),
+        ),
+        "crankPrune" => (
+            |id| crank_prune(id, crank_prune_accounts(), HASH, key()),
+            |id| crank_prune_borsh(id, crank_prune_accounts(), HASH, key()),
+        ),
+        "fundBounty" => (
+            |id| fund_bounty(id, fund_bounty_accounts(), HASH, 1),
+            |id| fund_bounty_borsh(id, fund_bounty_accounts(), HASH, 1),
+        ),
+        "compactIndex" => (
+            |id| compact_index(id, compact_index_accounts(), true, 8),
+            |id| compact_index_borsh(id, compact_index_accounts(), true, 8),
+        ),
+        "setAccessPreferences" => (
+            |id| set_access_preferences(id, set_access_preferences_accounts(), true),
+            |id| set_access_preferences_borsh(id, set_access_preferences_accounts(), true),
+        ),
+        "acceptShare" => (
+            |id| accept_share(id, accept_share_accounts(), HASH, key()),
+            |id| accept_share_borsh(id, accept_share_accounts(), HASH, key()),
+        ),
+        "rejectShare" => (
+            |id| reject_share(id, reject_share_accounts(), HASH, key()),
+            |id| reject_share_borsh(id, reject_share_accounts(), HASH, key()),
+        ),
+        name => panic!("no builder for {}", name),
+    };
+
+    let id = datanexus::datanexus_program::id();
+    (legacy(id).unwrap(), borsh(id).unwrap())
+}
+
+fn set_data_params_accounts() -> SetDataParamsAccounts<Pubkey> {
+    SetDataParamsAccounts {
+        authority: key(),
+        dataset_account: key(),
+        reference_dataset: Some(key()),
+    }
+}
+
+fn purchase_access_accounts() -> PurchaseAccessAccounts<Pubkey> {
+    PurchaseAccessAccounts {
+        user_authority: key(),
+        user_access_index: key(),
+        user_access_account: key(),
+        user_token_account: key(),
+        owner_authority: key(),
+        proceeds_account: key(),
+        token_mint: key(),
+        dataset_account: key(),
+        token_program: key(),
+        referrer_token_account: None,
+        receipt: None,
+        reference_access: None,
+    }
+}
+
+fn share_access_accounts() -> ShareAccessAccounts<Pubkey> {
+    ShareAccessAccounts {
+        user_authority: key(),
+        user_access_account: key(),
+        recipient_authority: key(),
+        recipient_access_index: key(),
+        recipient_access_account: key(),
+        dataset_account: key(),
+    }
+}
+
+fn close_dataset_accounts() -> CloseDatasetAccounts<Pubkey> {
+    CloseDatasetAccounts {
+        authority: key(),
+        owner_index: key(),
+        dataset_account: key(),
+    }
+}
+
+fn renew_access_accounts() -> RenewAccessAccounts<Pubkey> {
+    RenewAccessAccounts {
+        user_authority: key(),
+        user_access_account: key(),
+        user_token_account: key(),
+        proceeds_account: key(),
+        token_mint: key(),
+        dataset_account: key(),
+        token_program: key(),
+        referrer_token_account: None,
+    }
+}
+
+fn share_access_multi_accounts() -> ShareAccessMultiAccounts<Pubkey> {
+    ShareAccessMultiAccounts {
+        user_authority: key(),
+        user_access_account: key(),
+        dataset_account: key(),
+        recipients: vec![RecipientAccounts {
+            authority: key(),
+            access_index: key(),
+            access_account: key(),
+        }],
+    }
+}
+
+fn init_associated_access_accounts() -> InitAssociatedAccessAccounts<Pubkey> {
+    InitAssociatedAccessAccounts {
+        payer: key(),
+        authority: key(),
+        access_index: key(),
+        associated_access_account: key(),
+        dataset_account: key(),
+        system_program: key(),
+    }
+}
+
+fn resize_index_accounts() -> ResizeIndexAccounts<Pubkey> {
+    ResizeIndexAccounts {
+        payer: key(),
+        authority: key(),
+        index_account: key(),
+        system_program: key(),
+    }
+}
+
+fn member_accounts() -> Vec<MemberAccounts<Pubkey>> {
+    vec![MemberAccounts {
+        dataset_account: key(),
+        access_account: key(),
+    }]
+}
+
+fn purchase_collection_accounts() -> PurchaseCollectionAccounts<Pubkey> {
+    PurchaseCollectionAccounts {
+        user_authority: key(),
+        user_access_index: key(),
+        collection_access_account: key(),
+        user_token_account: key(),
+        owner_authority: key(),
+        owner_token_account: key(),
+        token_mint: key(),
+        collection_account: key(),
+        token_program: key(),
+        members: member_accounts(),
+    }
+}
+
+fn create_collection_accounts() -> CreateCollectionAccounts<Pubkey> {
+    CreateCollectionAccounts {
+        authority: key(),
+        collection_account: key(),
+        system_program: key(),
+    }
+}
+
+fn add_to_collection_accounts() -> AddToCollectionAccounts<Pubkey> {
+    AddToCollectionAccounts {
+        authority: key(),
+        collection_account: key(),
+        dataset_account: key(),
+    }
+}
+
+fn claim_collection_access_accounts() -> ClaimCollectionAccessAccounts<Pubkey> {
+    ClaimCollectionAccessAccounts {
+        user_authority: key(),
+        user_access_index: key(),
+        collection_access_account: key(),
+        collection_account: key(),
+        members: member_accounts(),
+    }
+}
+
+fn init_vault_accounts() -> InitVaultAccounts<Pubkey> {
+    InitVaultAccounts {
+        payer: key(),
+        owner_authority: key(),
+        dataset_account: key(),
+        token_mint: key(),
+        vault_account: key(),
+        token_program: key(),
+        system_program: key(),
+    }
+}
+
+fn withdraw_proceeds_accounts() -> WithdrawProceedsAccounts<Pubkey> {
+    WithdrawProceedsAccounts {
+        owner_authority: key(),
+        dataset_account: key(),
+        vault_account: key(),
+        token_mint: key(),
+        destination_token_account: key(),
+        token_program: key(),
+    }
+}
+
+fn init_receipt_mint_accounts() -> InitReceiptMintAccounts<Pubkey> {
+    InitReceiptMintAccounts {
+        payer: key(),
+        dataset_account: key(),
+        receipt_mint: key(),
+        token_program: key(),
+        system_program: key(),
+    }
+}
+
+fn revoke_access_accounts() -> RevokeAccessAccounts<Pubkey> {
+    RevokeAccessAccounts {
+        owner_authority: key(),
+        dataset_account: key(),
+        holder_authority: key(),
+        holder_access_account: key(),
+        receipt: None,
+    }
+}
+
+fn migrate_account_accounts() -> MigrateAccountAccounts<Pubkey> {
+    MigrateAccountAccounts {
+        payer: key(),
+        account: key(),
+        system_program: key(),
+    }
+}
+
+fn ownership_transfer_accounts() -> OwnershipTransferAccounts<Pubkey> {
+    OwnershipTransferAccounts {
+        authority: key(),
+        dataset_account: key(),
+    }
+}
+
+fn accept_ownership_accounts() -> AcceptOwnershipAccounts<Pubkey> {
+    AcceptOwnershipAccounts {
+        authority: key(),
+        old_owner_index: key(),
+        new_owner_index: key(),
+        dataset_account: key(),
+    }
+}
+
+fn crank_prune_accounts() -> CrankPruneAccounts<Pubkey> {
+    CrankPruneAccounts {
+        cranker: key(),
+        dataset_account: key(),
+        holder_access_account: key(),
+    }
+}
+
+fn fund_bounty_accounts() -> FundBountyAccounts<Pubkey> {
+    FundBountyAccounts {
+        authority: key(),
+        dataset_account: key(),
+        system_program: key(),
+    }
+}
+
+fn compact_index_accounts() -> CompactIndexAccounts<Pubkey> {
+    CompactIndexAccounts {
+        authority: key(),
+        index_account: key(),
+    }
+}
+
+fn set_access_preferences_accounts() -> SetAccessPreferencesAccounts<Pubkey> {
+    SetAccessPreferencesAccounts {
+        authority: key(),
+        access_index: key(),
+    }
+}
+
+fn accept_share_accounts() -> AcceptShareAccounts<Pubkey> {
+    AcceptShareAccounts {
+        authority: key(),
+        access_index: key(),
+        access_account: key(),
+        dataset_account: key(),
+    }
+}
+
+fn reject_share_accounts() -> RejectShareAccounts<Pubkey> {
+    RejectShareAccounts {
+        authority: key(),
+        recipient_authority: key(),
+        recipient_access_account: key(),
+        sharer_access_account: key(),
+        dataset_account: key(),
+    }
+}
+
+fn size_of(name: &str) -> Option<usize> {
+    type_def(name).unwrap().size()
+}
+
+#[test]
+fn account_sizes_match_pack() {
+    assert_eq!(size_of("AccountIndex"), Some(AccountIndex::LEN));
+    assert_eq!(size_of("DatasetState"), Some(DatasetState::LEN));
+    assert_eq!(size_of("AccessState"), Some(AccessState::LEN));
+    assert_eq!(size_of("AccessInfo"), Some(AccessInfo::LEN));
+    assert_eq!(size_of("PendingShare"), Some(PendingShare::LEN));
+    assert_eq!(size_of("CollectionState"), Some(CollectionState::LEN));
+    assert_eq!(size_of("CollectionAccess"), Some(CollectionAccess::LEN));
+    assert_eq!(size_of("RegistryState"), Some(RegistryState::LEN));
+}
+
+#[test]
+fn discriminants_match_pack_tags() {
+    for instruction in INSTRUCTIONS {
+        let (legacy, borsh) = built(instruction.name);
+        let discriminant = instruction.discriminant();
+
+        assert_eq!(legacy.data[0], discriminant[1], "{}", instruction.name);
+        assert_eq!(borsh.data[..2], discriminant, "{}", instruction.name);
+    }
+}
+
+#[test]
+fn fixed_size_args_match_borsh() {
+    for instruction in INSTRUCTIONS {
+        let args: Option<usize> = instruction.args.iter().map(|(_, ty)| ty.size()).sum();
+        if let Some(args) = args {
+            let (_, borsh) = built(instruction.name);
+            assert_eq!(borsh.data.len(), 2 + args, "{}", instruction.name);
+        }
+    }
+}
+
+#[test]
+fn defined_types_exist() {
+    fn check(ty: &Type) {
+        match ty {
+            Type::Defined(name) => assert!(type_def(name).is_some(), "{} is undefined", name),
+            Type::Array(ty, _) | Type::Vec(ty) | Type::Option(ty) => check(ty),
+            _ => {}
+        }
+    }
+
+    for instruction in INSTRUCTIONS {
+        instruction.args.iter().for_each(|(_, ty)| check(ty));
+    }
+}
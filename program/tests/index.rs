@@ -0,0 +1,158 @@
+use datanexus::state::{AccountIndex, IndexEntry, INDEX_LAYOUT_VERSION, MAX_INDEX_ENTRIES};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+fn empty_index() -> AccountIndex {
+    AccountIndex {
+        is_initialized: true,
+        pointer: None,
+        require_share_approval: false,
+        datasets: vec![None; MAX_INDEX_ENTRIES],
+    }
+}
+
+fn packed(index: &AccountIndex, len: usize) -> Vec<u8> {
+    let mut data = vec![0; len];
+    index.pack_into_slice(&mut data);
+    data
+}
+
+#[test]
+fn entries_keep_their_hashes() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut index = empty_index();
+    index.insert(first, [1; 32]).unwrap();
+    index.insert(second, [2; 32]).unwrap();
+
+    let data = packed(&index, AccountIndex::LEN);
+    assert_eq!(data[0], INDEX_LAYOUT_VERSION);
+
+    let index = AccountIndex::unpack_from_slice(&data).unwrap();
+    assert_eq!(index.find_by_hash(&[2; 32]), Some(second));
+    assert_eq!(index.find_by_hash(&[3; 32]), None);
+    assert_eq!(
+        index.datasets[0],
+        Some(IndexEntry {
+            dataset: first,
+            hash: [1; 32]
+        })
+    );
+}
+
+#[test]
+fn append_raw_matches_insert() {
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut index = empty_index();
+    let mut data = packed(&index, AccountIndex::LEN);
+
+    assert_eq!(AccountIndex::append_raw(&mut data, &first, &[1; 32]), Ok(0));
+    assert_eq!(
+        AccountIndex::append_raw(&mut data, &second, &[2; 32]),
+        Ok(1)
+    );
+    assert_eq!(AccountIndex::append_raw(&mut data, &first, &[1; 32]), Ok(0));
+    index.insert(first, [1; 32]).unwrap();
+    index.insert(second, [2; 32]).unwrap();
+
+    assert_eq!(data, packed(&index, AccountIndex::LEN));
+    assert!(AccountIndex::contains_raw(&data, &second));
+    assert!(!AccountIndex::contains_raw(&data, &Pubkey::new_unique()));
+}
+
+#[test]
+fn append_raw_fails_when_full() {
+    let mut index = empty_index();
+    for _ in 0..MAX_INDEX_ENTRIES {
+        index.insert(Pubkey::new_unique(), [1; 32]).unwrap();
+    }
+    let mut data = packed(&index, AccountIndex::LEN);
+
+    assert!(AccountIndex::append_raw(&mut data, &Pubkey::new_unique(), &[1; 32]).is_err());
+    assert_eq!(data, packed(&index, AccountIndex::LEN));
+}
+
+#[test]
+fn grown_indexes_take_more_entries() {
+    let mut index = empty_index();
+    for _ in 0..MAX_INDEX_ENTRIES {
+        index.insert(Pubkey::new_unique(), [1; 32]).unwrap();
+    }
+    let mut data = packed(&index, AccountIndex::LEN);
+    assert_eq!(AccountIndex::capacity_raw(&data), Ok(MAX_INDEX_ENTRIES));
+
+    data.resize(AccountIndex::LEN + 2 * AccountIndex::ENTRY_LEN, 0);
+    AccountIndex::set_capacity_raw(&mut data, MAX_INDEX_ENTRIES as u16 + 2).unwrap();
+    let key = Pubkey::new_unique();
+    assert_eq!(
+        AccountIndex::append_raw(&mut data, &key, &[2; 32]),
+        Ok(MAX_INDEX_ENTRIES)
+    );
+
+    let index = AccountIndex::unpack_from_slice(&data).unwrap();
+    assert_eq!(index.capacity(), MAX_INDEX_ENTRIES + 2);
+    assert_eq!(index.find_by_hash(&[2; 32]), Some(key));
+    assert_eq!(packed(&index, data.len()), data);
+}
+
+#[test]
+fn capacity_must_fit_the_account() {
+    let mut data = packed(&empty_index(), AccountIndex::LEN);
+
+    assert!(AccountIndex::set_capacity_raw(&mut data, MAX_INDEX_ENTRIES as u16 + 1).is_err());
+    assert!(AccountIndex::set_capacity_raw(
+        &mut vec![0; AccountIndex::LEGACY_LEN],
+        MAX_INDEX_ENTRIES as u16
+    )
+    .is_err());
+}
+
+#[test]
+fn append_raw_requires_an_initialized_index() {
+    let mut index = empty_index();
+    index.is_initialized = false;
+    let mut data = packed(&index, AccountIndex::LEN);
+
+    assert!(AccountIndex::append_raw(&mut data, &Pubkey::new_unique(), &[1; 32]).is_err());
+}
+
+#[test]
+fn legacy_indexes_still_decode() {
+    let (first, second, third) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    // Initialized flag, pointer and 32 byte keys, the second slot freed
+    let mut data = vec![0; AccountIndex::LEGACY_LEN];
+    data[0] = 1;
+    data[33..65].copy_from_slice(first.as_ref());
+    data[97..129].copy_from_slice(third.as_ref());
+
+    let index = AccountIndex::unpack_from_slice(&data).unwrap();
+    assert!(index.is_initialized);
+    assert_eq!(index.position(&first), Some(0));
+    assert_eq!(index.position(&third), Some(2));
+    assert_eq!(index.datasets[0].unwrap().hash, [0; 32]);
+    assert_eq!(index.find_by_hash(&[1; 32]), None);
+
+    assert_eq!(
+        AccountIndex::append_raw(&mut data, &second, &[2; 32]),
+        Ok(1)
+    );
+    assert!(AccountIndex::contains_raw(&data, &second));
+    assert_eq!(&data[65..97], second.as_ref());
+
+    let index = AccountIndex::unpack_from_slice(&data).unwrap();
+    let repacked = packed(&index, AccountIndex::LEGACY_LEN);
+    let index = AccountIndex::unpack_from_slice(&repacked).unwrap();
+    assert_eq!(index.datasets.iter().flatten().count(), 3);
+}
+
+#[test]
+fn unknown_layouts_do_not_decode() {
+    let mut data = packed(&empty_index(), AccountIndex::LEN);
+    data[0] = INDEX_LAYOUT_VERSION + 1;
+
+    assert!(AccountIndex::unpack_from_slice(&data).is_err());
+    assert!(AccountIndex::unpack_from_slice(&data[..AccountIndex::LEN - 1]).is_err());
+}
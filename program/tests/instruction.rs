@@ -0,0 +1,165 @@
+use datanexus::{
+    instruction::{AccountType, DataNexusInstruction, FieldMask, Params},
+    state::{PriceTier, MAX_PRICE_TIERS},
+};
+use solana_program::pubkey::Pubkey;
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Every params variant, with its boundary values
+fn params() -> Vec<Params> {
+    vec![
+        Params::Init([2; 32], 100, 3, None),
+        Params::Init([2; 32], 100, 3, Some(Pubkey::new_unique())),
+        Params::Key([3; 32]),
+        Params::Value(0),
+        Params::Value(u64::MAX),
+        Params::ShareLimit(7),
+        Params::ShareLimit(u16::MAX),
+        Params::ReferenceData(Pubkey::new_unique()),
+        Params::ReferralBps(250),
+        Params::AccessDuration(86_400),
+        Params::AccessDuration(i64::MIN),
+        Params::Uri(b"ipfs://dataset".to_vec()),
+        Params::Uri(vec![]),
+        Params::Uri(vec![0xff; 300]),
+        Params::Size(1 << 40),
+        Params::ContentType(b"text/csv".to_vec()),
+        Params::Clear(FieldMask::VALUE | FieldMask::REF_DATA),
+        Params::Clear(FieldMask::ALL),
+        Params::Resharable(true),
+        Params::Resharable(false),
+        Params::MaxShareDepth(0),
+        Params::MaxShareDepth(u8::MAX),
+        Params::Tiers(vec![
+            PriceTier {
+                min_seats: 5,
+                unit_price: 90,
+            },
+            PriceTier {
+                min_seats: 20,
+                unit_price: 75,
+            },
+        ]),
+        Params::Tiers(vec![]),
+        Params::Tiers(
+            (1..=MAX_PRICE_TIERS as u16)
+                .map(|min_seats| PriceTier {
+                    min_seats,
+                    unit_price: u64::MAX,
+                })
+                .collect(),
+        ),
+        Params::SetPrice(Pubkey::new_unique(), 250),
+        Params::SetPrice(Pubkey::new_unique(), u64::MAX),
+        Params::RemovePrice(Pubkey::new_unique()),
+        Params::SaleWindow {
+            start: 1_700_000_000,
+            end: Some(1_800_000_000),
+        },
+        Params::SaleWindow {
+            start: 0,
+            end: None,
+        },
+        Params::PruneBounty(5_000),
+        Params::ShareFee {
+            mint: Pubkey::new_unique(),
+            amount: 10,
+        },
+        Params::AddTag(*b"finance\0\0\0\0\0\0\0\0\0"),
+        Params::RemoveTag([7; 16]),
+        Params::DerivedDiscountBps(2_500),
+    ]
+}
+
+/// One instruction of every variant, and every `SetDataParams` param
+fn instructions() -> Vec<DataNexusInstruction> {
+    let mut instructions = vec![
+        DataNexusInstruction::InitAccount(AccountType::DatasetIndex),
+        DataNexusInstruction::InitAccount(AccountType::AccessIndex),
+        DataNexusInstruction::InitAccount(AccountType::Dataset(HASH)),
+        DataNexusInstruction::InitAccount(AccountType::Access(HASH)),
+        DataNexusInstruction::PurchaseAccess {
+            hash: HASH,
+            amount: 100,
+        },
+        DataNexusInstruction::ShareAccess {
+            hash: HASH,
+            expires_in: None,
+        },
+        DataNexusInstruction::ShareAccess {
+            hash: HASH,
+            expires_in: Some(7 * 86_400),
+        },
+        DataNexusInstruction::CloseDataset {
+            hash: HASH,
+            force: true,
+        },
+        DataNexusInstruction::RenewAccess {
+            hash: HASH,
+            amount: 100,
+        },
+        DataNexusInstruction::ShareAccessMulti { hash: HASH },
+        DataNexusInstruction::InitAssociatedAccess { hash: HASH },
+        DataNexusInstruction::ResizeIndex {
+            additional_slots: 64,
+        },
+        DataNexusInstruction::CreateCollection {
+            hash: HASH,
+            value: 500,
+        },
+        DataNexusInstruction::AddToCollection {
+            collection_hash: HASH,
+            dataset_hash: [4; 32],
+        },
+        DataNexusInstruction::ClaimCollectionAccess { hash: HASH },
+        DataNexusInstruction::PurchaseSeats {
+            hash: HASH,
+            seats: 10,
+            amount: 900,
+        },
+        DataNexusInstruction::InitVault { hash: HASH },
+        DataNexusInstruction::WithdrawProceeds {
+            hash: HASH,
+            amount: 50,
+        },
+        DataNexusInstruction::InitReceiptMint { hash: HASH },
+        DataNexusInstruction::RevokeAccess { hash: HASH },
+        DataNexusInstruction::MigrateAccount,
+    ];
+    instructions.extend(
+        params()
+            .into_iter()
+            .map(|params| DataNexusInstruction::SetDataParams { hash: HASH, params }),
+    );
+    instructions
+}
+
+#[test]
+fn legacy_roundtrips() {
+    for instruction in instructions() {
+        let packed = instruction.pack();
+        assert_eq!(DataNexusInstruction::unpack(&packed).unwrap(), instruction);
+    }
+}
+
+#[cfg(feature = "borsh-encoding")]
+#[test]
+fn borsh_roundtrips() {
+    for instruction in instructions() {
+        let packed = instruction.pack_borsh().unwrap();
+        assert_eq!(DataNexusInstruction::unpack(&packed).unwrap(), instruction);
+    }
+}
+
+#[test]
+fn truncated_is_rejected() {
+    for instruction in instructions() {
+        let packed = instruction.pack();
+        for len in 0..packed.len() {
+            if let Ok(unpacked) = DataNexusInstruction::unpack(&packed[..len]) {
+                assert_ne!(unpacked, instruction, "{:?} at {} bytes", instruction, len);
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+#![cfg(feature = "serde")]
+
+//! The JSON shape of the public types, pinned by the files under `golden/`
+
+use datanexus::{
+    instruction::{AccountType, FieldMask, Params},
+    state::{
+        pack_tag, AccessInfo, AccessState, AccountFlag, AccountIndex, Claimable, DatasetState,
+        IndexEntry, MintPrice, PendingShare, PriceTier, CONTENT_TYPE_LEN, URI_LEN,
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+use solana_program::pubkey::Pubkey;
+
+fn key(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+fn padded<const N: usize>(text: &str) -> [u8; N] {
+    let mut padded = [0; N];
+    padded[..text.len()].copy_from_slice(text.as_bytes());
+    padded
+}
+
+/// Fails unless `value` serializes to `golden` and `golden` deserializes
+/// back to the same JSON
+fn assert_golden<T: Serialize + DeserializeOwned>(value: &T, golden: &str) {
+    let json = serde_json::to_string_pretty(value).unwrap();
+    assert_eq!(json, golden.trim_end());
+
+    let decoded: T = serde_json::from_str(golden).unwrap();
+    assert_eq!(serde_json::to_string_pretty(&decoded).unwrap(), json);
+}
+
+fn dataset() -> DatasetState {
+    DatasetState {
+        is_initialized: true,
+        flag: AccountFlag::Dataset,
+        owner: key(1),
+        hash: [2; 32],
+        key: Some([3; 32]),
+        value: Some(4),
+        share_limit: Some(5),
+        purchase_count: 6,
+        referral_bps: 700,
+        access_duration: Some(8),
+        uri: padded::<URI_LEN>("https://example.com/data.csv"),
+        size_bytes: 9,
+        content_type: padded::<CONTENT_TYPE_LEN>("text/csv"),
+        total_revenue: 10,
+        share_count: 11,
+        ref_data: Some(key(12)),
+        resharable: true,
+        max_share_depth: Some(13),
+        tiers: vec![PriceTier {
+            min_seats: 14,
+            unit_price: 15,
+        }],
+        claimable: vec![Claimable {
+            mint: key(16),
+            amount: 17,
+        }],
+        holder_count: 18,
+        max_holders: Some(19),
+        pending_owner: Some(key(20)),
+        prices: vec![MintPrice {
+            mint: key(21),
+            amount: 22,
+        }],
+        sale_start: Some(23),
+        sale_end: Some(24),
+        prune_bounty: 25,
+        bounty_pool: 26,
+        share_fee: Some(MintPrice {
+            mint: key(27),
+            amount: 28,
+        }),
+        tags: vec![pack_tag("finance").unwrap()],
+        derived_discount_bps: 2_900,
+        receipt_count: 30,
+    }
+}
+
+#[test]
+fn datasets() {
+    assert_golden(&dataset(), include_str!("golden/dataset.json"));
+}
+
+#[test]
+fn unset_dataset_fields_are_left_out() {
+    let dataset = DatasetState {
+        key: None,
+        value: None,
+        share_limit: None,
+        access_duration: None,
+        uri: [0; URI_LEN],
+        content_type: [0; CONTENT_TYPE_LEN],
+        ref_data: None,
+        max_share_depth: None,
+        max_holders: None,
+        pending_owner: None,
+        sale_start: None,
+        sale_end: None,
+        share_fee: None,
+        tiers: vec![],
+        claimable: vec![],
+        prices: vec![],
+        tags: vec![],
+        ..dataset()
+    };
+
+    assert_golden(&dataset, include_str!("golden/dataset_unset.json"));
+}
+
+#[test]
+fn access_accounts() {
+    let access = AccessState {
+        is_initialized: true,
+        pointer: None,
+        datasets: vec![
+            AccessInfo {
+                hash: [1; 32],
+                key: Some([2; 32]),
+                shared_from: None,
+                share_quota: 3,
+                expires_at: None,
+                depth: 0,
+                shares_used: 1,
+            },
+            AccessInfo {
+                hash: [4; 32],
+                key: None,
+                shared_from: Some(key(5)),
+                share_quota: 6,
+                expires_at: Some(7),
+                depth: 1,
+                shares_used: 0,
+            },
+        ],
+        pending: vec![PendingShare {
+            access: AccessInfo {
+                hash: [8; 32],
+                key: None,
+                shared_from: Some(key(9)),
+                share_quota: 10,
+                expires_at: None,
+                depth: 1,
+                shares_used: 0,
+            },
+            shared_at: 11,
+        }],
+    };
+
+    assert_golden(&access, include_str!("golden/access.json"));
+}
+
+#[test]
+fn indexes() {
+    let index = AccountIndex {
+        is_initialized: true,
+        pointer: Some(key(1)),
+        require_share_approval: false,
+        datasets: vec![
+            Some(IndexEntry {
+                dataset: key(2),
+                hash: [3; 32],
+            }),
+            None,
+        ],
+    };
+
+    assert_golden(&index, include_str!("golden/index.json"));
+}
+
+#[test]
+fn params() {
+    let params = vec![
+        Params::Init([1; 32], 2, 3, Some(key(4))),
+        Params::Init([1; 32], 2, 3, None),
+        Params::Key([5; 32]),
+        Params::ReferenceData(key(6)),
+        Params::Uri(b"https://example.com".to_vec()),
+        Params::Clear(FieldMask::KEY | FieldMask::URI),
+        Params::SetPrice(key(7), 8),
+        Params::SaleWindow {
+            start: 9,
+            end: None,
+        },
+        Params::ShareFee {
+            mint: key(10),
+            amount: 11,
+        },
+        Params::AddTag(pack_tag("finance").unwrap()),
+    ];
+
+    assert_golden(&params, include_str!("golden/params.json"));
+}
+
+#[test]
+fn account_types() {
+    let account_types = vec![
+        AccountType::DatasetIndex,
+        AccountType::AccessIndex,
+        AccountType::Dataset([1; 32]),
+        AccountType::Access([2; 32]),
+    ];
+
+    assert_golden(&account_types, include_str!("golden/account_type.json"));
+}
+
+#[test]
+fn malformed_fields_are_rejected() {
+    let golden: serde_json::Value =
+        serde_json::from_str(include_str!("golden/index.json")).unwrap();
+    let malformed = [
+        ("/pointer", serde_json::json!("not base58")),
+        ("/datasets/0/hash", serde_json::json!("0303")),
+        ("/datasets/0/hash", serde_json::json!("zz".repeat(32))),
+    ];
+
+    for (field, value) in malformed.iter() {
+        let mut json = golden.clone();
+        *json.pointer_mut(field).unwrap() = value.clone();
+        assert!(serde_json::from_value::<AccountIndex>(json).is_err());
+    }
+}
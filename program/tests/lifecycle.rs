@@ -0,0 +1,265 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::SetDataParamsAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{set_data_params, Params},
+    state::{AccessState, AccountIndex, DatasetState},
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Owner, buyer and recipient each funded with enough lamports to pay for
+/// their own accounts
+async fn setup() -> (Harness, Keypair, Keypair, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+
+    (harness, owner, buyer, recipient)
+}
+
+#[tokio::test]
+async fn register_purchase_and_share() {
+    let (mut harness, owner, buyer, recipient) = setup().await;
+
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert!(index.is_initialized);
+    assert_eq!(index.position(&dataset(&HASH)), None);
+
+    harness
+        .create_dataset_at(&owner, HASH, dataset(&HASH))
+        .await
+        .unwrap();
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert_eq!(index.position(&dataset(&HASH)), Some(0));
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert!(state.is_initialized);
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.hash, HASH);
+    assert_eq!(state.value, None);
+
+    harness
+        .set_params(&owner, HASH, Params::Init([7; 32], 100, 2, None))
+        .await
+        .unwrap();
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.key, Some([7; 32]));
+    assert_eq!(state.value, Some(100));
+    assert_eq!(state.share_limit, Some(2));
+
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 250)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert!(access.is_initialized);
+    assert!(access.datasets.is_empty());
+
+    harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await
+        .unwrap();
+    assert_eq!(harness.token_balance(buyer_tokens).await, 150);
+    assert_eq!(harness.token_balance(vault).await, 100);
+
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    let grant = access.datasets[access.find_entry(&HASH).unwrap()];
+    assert_eq!(grant.key, Some([7; 32]));
+    assert_eq!(grant.shared_from, None);
+    assert_eq!(grant.share_quota, 2);
+    assert_eq!(grant.shares_used, 0);
+    let index: AccountIndex = harness.state(access_index(&buyer.pubkey())).await;
+    assert!(index.position(&dataset(&HASH)).is_some());
+    assert_eq!(index.find_by_hash(&HASH), Some(dataset(&HASH)));
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.purchase_count, 1);
+    assert_eq!(state.total_revenue, 100);
+
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    harness
+        .share(&buyer, &recipient.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    let shared: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &HASH))
+        .await;
+    let grant = shared.datasets[shared.find_entry(&HASH).unwrap()];
+    assert_eq!(grant.shared_from, Some(buyer.pubkey()));
+    assert_eq!(grant.share_quota, 0);
+    let index: AccountIndex = harness.state(access_index(&recipient.pubkey())).await;
+    assert!(index.position(&dataset(&HASH)).is_some());
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    let grant = access.datasets[access.find_entry(&HASH).unwrap()];
+    assert_eq!((grant.share_quota, grant.shares_used), (2, 1));
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.share_count, 1);
+}
+
+#[tokio::test]
+async fn set_params_requires_the_owner() {
+    let (mut harness, owner, buyer, _) = setup().await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    let result = harness.set_params(&buyer, HASH, Params::Value(1)).await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.value, Some(100));
+}
+
+#[tokio::test]
+async fn set_params_requires_the_owner_signature() {
+    let (mut harness, owner, _, _) = setup().await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    let mut instruction = set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: owner.pubkey(),
+            dataset_account: dataset(&HASH),
+            reference_dataset: None,
+        },
+        HASH,
+        Params::Value(1),
+        &[],
+    )
+    .unwrap();
+    instruction.accounts[0].is_signer = false;
+    let result = harness.process(&[instruction], &[]).await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+}
+
+#[tokio::test]
+async fn dataset_must_live_at_its_pda() {
+    let (mut harness, owner, _, _) = setup().await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+
+    let result = harness
+        .create_dataset_at(&owner, HASH, dataset(&[2; 32]))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert!(index.datasets.iter().all(Option::is_none));
+}
+
+#[tokio::test]
+async fn purchase_below_the_dataset_value_fails() {
+    let (mut harness, owner, buyer, _) = setup().await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 250)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let result = harness
+        .purchase(&buyer, buyer_tokens, &owner.pubkey(), vault, mint, HASH, 50)
+        .await;
+
+    assert_error(result, DataNexusError::InsufficientPayment);
+    assert_eq!(harness.token_balance(buyer_tokens).await, 250);
+}
+
+#[tokio::test]
+async fn underfunded_purchase_grants_nothing() {
+    let (mut harness, owner, buyer, _) = setup().await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 50)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let result = harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await;
+
+    assert!(result.is_err());
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.purchase_count, 0);
+    assert_eq!(harness.token_balance(vault).await, 0);
+}
+
+#[tokio::test]
+async fn share_requires_access() {
+    let (mut harness, owner, buyer, recipient) = setup().await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    for authority in &[&buyer, &recipient] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+
+    let result = harness.share(&buyer, &recipient.pubkey(), HASH).await;
+
+    assert!(result.is_err());
+    let shared: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &HASH))
+        .await;
+    assert_eq!(shared.find_entry(&HASH), None);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessState, DatasetState},
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// A resharable dataset limited to two hops, a buyer holding access to it
+/// and three recipients with access accounts ready to receive it
+async fn setup() -> (Harness, Keypair, Keypair, [Keypair; 3]) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipients = [
+        harness.fund(LAMPORTS_PER_SOL).await,
+        harness.fund(LAMPORTS_PER_SOL).await,
+        harness.fund(LAMPORTS_PER_SOL).await,
+    ];
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    for params in [Params::Resharable(true), Params::MaxShareDepth(2)] {
+        harness.set_params(&owner, HASH, params).await.unwrap();
+    }
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 100)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    for authority in [&buyer, &recipients[0], &recipients[1], &recipients[2]].iter() {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+    harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await
+        .unwrap();
+
+    (harness, owner, buyer, recipients)
+}
+
+#[tokio::test]
+async fn shares_record_their_depth() {
+    let (mut harness, _, buyer, [first, second, _]) = setup().await;
+    harness.share(&buyer, &first.pubkey(), HASH).await.unwrap();
+    harness.share(&first, &second.pubkey(), HASH).await.unwrap();
+
+    for (authority, shared_from, depth) in [
+        (buyer.pubkey(), None, 0),
+        (first.pubkey(), Some(buyer.pubkey()), 1),
+        (second.pubkey(), Some(first.pubkey()), 2),
+    ]
+    .iter()
+    {
+        let access: AccessState = harness.state(associated_access(authority, &HASH)).await;
+        assert_eq!(access.datasets[0].shared_from, *shared_from);
+        assert_eq!(access.datasets[0].depth, *depth);
+    }
+}
+
+#[tokio::test]
+async fn shares_past_the_depth_limit_fail() {
+    let (mut harness, _, buyer, [first, second, third]) = setup().await;
+    harness.share(&buyer, &first.pubkey(), HASH).await.unwrap();
+    harness.share(&first, &second.pubkey(), HASH).await.unwrap();
+
+    let result = harness.share(&second, &third.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::ShareDepthExceeded);
+    let access: AccessState = harness
+        .state(associated_access(&second.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].shares_used, 0);
+    let access: AccessState = harness
+        .state(associated_access(&third.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn zero_depth_limit_removes_it() {
+    let (mut harness, owner, buyer, [first, second, third]) = setup().await;
+    harness
+        .set_params(&owner, HASH, Params::MaxShareDepth(0))
+        .await
+        .unwrap();
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.max_share_depth, None);
+
+    harness.share(&buyer, &first.pubkey(), HASH).await.unwrap();
+    harness.share(&first, &second.pubkey(), HASH).await.unwrap();
+    harness.share(&second, &third.pubkey(), HASH).await.unwrap();
+
+    let access: AccessState = harness
+        .state(associated_access(&third.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].depth, 3);
+}
@@ -0,0 +1,90 @@
+//! With `verbose-logs` every instruction logs its name and key parameters,
+//! and failed account checks name the offending account. Without it only
+//! the plain check messages are logged
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::SetDataParamsAccounts,
+    datanexus_program,
+    instruction::{set_data_params, Params},
+};
+use solana_program::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Program logs of simulating `instruction` signed by `signer`
+async fn logs(harness: &mut Harness, instruction: Instruction, signer: &Keypair) -> Vec<String> {
+    let blockhash = harness.banks.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&harness.payer.pubkey()),
+        &[&harness.payer, signer],
+        blockhash,
+    );
+    harness
+        .banks
+        .simulate_transaction(transaction)
+        .await
+        .unwrap()
+        .simulation_details
+        .unwrap()
+        .logs
+}
+
+fn set_value(owner: &Keypair, dataset_account: Pubkey) -> Instruction {
+    set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: owner.pubkey(),
+            dataset_account,
+            reference_dataset: None,
+        },
+        HASH,
+        Params::Value(200),
+        &[],
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn instructions_log_their_name_and_parameters() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    let logs = logs(&mut harness, set_value(&owner, dataset(&HASH)), &owner).await;
+
+    let line = "Program log: DataNexus instruction: SetDataParams hash=01010101 params=Value";
+    assert_eq!(
+        logs.iter().any(|log| log == line),
+        cfg!(feature = "verbose-logs"),
+        "{:#?}",
+        logs
+    );
+}
+
+#[tokio::test]
+async fn failed_checks_name_the_offending_account() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let stray = Pubkey::new_unique();
+
+    let logs = logs(&mut harness, set_value(&owner, stray), &owner).await;
+
+    let line = if cfg!(feature = "verbose-logs") {
+        format!(
+            "Program log: DataNexus check failed: Dataset account not owned by DataNexus, \
+             account={}",
+            stray
+        )
+    } else {
+        "Program log: Dataset account not owned by DataNexus".to_string()
+    };
+    assert!(logs.contains(&line), "{:#?}", logs);
+}
@@ -0,0 +1,159 @@
+//! Datasets with a holder quota admit that many purchasers and recipients,
+//! revoking a holder frees their slot
+
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessState, DatasetState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    mint: Pubkey,
+}
+
+/// A dataset with a vault admitting `max_holders` holders, each of them
+/// given 2 shares
+async fn setup(max_holders: u32) -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 2).await;
+    harness
+        .set_params(&owner, HASH, Params::MaxHolders(max_holders))
+        .await
+        .unwrap();
+    let mint = harness.create_mint().await;
+    harness.create_vault(&owner, HASH, mint).await;
+
+    Setup {
+        harness,
+        owner,
+        mint,
+    }
+}
+
+impl Setup {
+    /// A new authority with its access index and access account of `HASH`
+    async fn holder(&mut self) -> Keypair {
+        let holder = self.harness.fund(LAMPORTS_PER_SOL).await;
+        self.harness
+            .create_access_index(&holder.pubkey())
+            .await
+            .unwrap();
+        self.harness
+            .create_associated_access(&holder, HASH)
+            .await
+            .unwrap();
+        holder
+    }
+
+    async fn purchase(&mut self, buyer: &Keypair) -> Result<(), TransportError> {
+        let tokens = self
+            .harness
+            .create_token_account(&self.mint, &buyer.pubkey(), VALUE)
+            .await;
+        self.harness
+            .purchase(
+                buyer,
+                tokens,
+                &self.owner.pubkey(),
+                vault(&HASH, &self.mint),
+                self.mint,
+                HASH,
+                VALUE,
+            )
+            .await
+    }
+
+    async fn dataset(&mut self) -> DatasetState {
+        self.harness.state(dataset(&HASH)).await
+    }
+}
+
+#[tokio::test]
+async fn purchases_past_the_quota_fail() {
+    let mut setup = setup(3).await;
+    for _ in 0..3 {
+        let buyer = setup.holder().await;
+        setup.purchase(&buyer).await.unwrap();
+    }
+    let state = setup.dataset().await;
+    assert_eq!(state.holder_count, 3);
+    assert_eq!(state.slots_remaining(), Some(0));
+
+    let late = setup.holder().await;
+    let result = setup.purchase(&late).await;
+
+    assert_error(result, DataNexusError::MaxHoldersReached);
+    let access: AccessState = setup
+        .harness
+        .state(associated_access(&late.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+    assert_eq!(setup.dataset().await.holder_count, 3);
+}
+
+#[tokio::test]
+async fn shares_count_against_the_quota() {
+    let mut setup = setup(2).await;
+    let buyer = setup.holder().await;
+    let (first, second) = (setup.holder().await, setup.holder().await);
+    setup.purchase(&buyer).await.unwrap();
+
+    setup
+        .harness
+        .share(&buyer, &first.pubkey(), HASH)
+        .await
+        .unwrap();
+    let result = setup.harness.share(&buyer, &second.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::MaxHoldersReached);
+    assert_eq!(setup.dataset().await.holder_count, 2);
+}
+
+#[tokio::test]
+async fn revoking_a_holder_frees_their_slot() {
+    let mut setup = setup(1).await;
+    let (first, second) = (setup.holder().await, setup.holder().await);
+    setup.purchase(&first).await.unwrap();
+    assert_error(
+        setup.purchase(&second).await,
+        DataNexusError::MaxHoldersReached,
+    );
+
+    setup
+        .harness
+        .revoke(&setup.owner, &first.pubkey(), HASH, None)
+        .await
+        .unwrap();
+    assert_eq!(setup.dataset().await.slots_remaining(), Some(1));
+
+    setup.purchase(&second).await.unwrap();
+    assert_eq!(setup.dataset().await.holder_count, 1);
+}
+
+#[tokio::test]
+async fn datasets_without_a_quota_count_holders() {
+    let mut setup = setup(0).await;
+    let buyer = setup.holder().await;
+
+    setup.purchase(&buyer).await.unwrap();
+
+    let state = setup.dataset().await;
+    assert_eq!(state.max_holders, None);
+    assert_eq!(state.slots_remaining(), None);
+    assert_eq!(state.holder_count, 1);
+}
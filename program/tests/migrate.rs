@@ -0,0 +1,612 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::MigrateAccountAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::migrate_account,
+    state::{
+        AccessInfo, AccessState, AccountIndex, Claimable, DatasetState, MintPrice, PriceTier,
+        INDEX_LAYOUT_VERSION, MAX_INDEX_ENTRIES,
+    },
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_error::ProgramError, program_pack::Pack,
+    pubkey::Pubkey, rent::Rent, system_program,
+};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Starts a bank holding `data` at `address`, owned by the program and
+/// funded for its size only
+async fn start_with_account(address: Pubkey, data: Vec<u8>) -> Harness {
+    let mut program_test = Harness::program_test();
+    program_test.add_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: datanexus_program::id(),
+            ..Account::default()
+        },
+    );
+    Harness::start_with(program_test).await
+}
+
+async fn data_len(harness: &mut Harness, address: Pubkey) -> usize {
+    harness
+        .banks
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+        .len()
+}
+
+/// Migrates `address` once more, paid by a new payer so the transaction is
+/// not a duplicate of the first migration
+async fn migrate_again(harness: &mut Harness, address: Pubkey) -> Result<(), TransportError> {
+    let payer = harness.fund(LAMPORTS_PER_SOL).await;
+    let instruction = migrate_account(
+        datanexus_program::id(),
+        MigrateAccountAccounts {
+            payer: payer.pubkey(),
+            account: address,
+            system_program: system_program::id(),
+        },
+    )
+    .unwrap();
+    harness.process(&[instruction], &[&payer]).await
+}
+
+fn assert_invalid_account_data(result: Result<(), TransportError>) {
+    assert!(matches!(
+        result,
+        Err(TransportError::TransactionError(
+            TransactionError::InstructionError(_, InstructionError::InvalidAccountData)
+        ))
+    ));
+}
+
+/// A dataset as written before the version header, laid out by hand
+fn v0_dataset(owner: &Pubkey, ref_data: &Pubkey, mint: &Pubkey) -> Vec<u8> {
+    let mut data = vec![0; DatasetState::LEGACY_LEN];
+    data[0] = 1;
+    data[1] = 1;
+    data[2..34].copy_from_slice(owner.as_ref());
+    data[34..66].copy_from_slice(&HASH);
+    data[66..98].copy_from_slice(&[7; 32]);
+    data[98..106].copy_from_slice(&250u64.to_le_bytes());
+    data[106..108].copy_from_slice(&3u16.to_le_bytes());
+    data[108..112].copy_from_slice(&5u32.to_le_bytes());
+    data[122..127].copy_from_slice(b"ar://");
+    data[290..298].copy_from_slice(&1250u64.to_le_bytes());
+    data[298..302].copy_from_slice(&2u32.to_le_bytes());
+    data[302..334].copy_from_slice(ref_data.as_ref());
+    data[334] = 1;
+    data[335] = 4;
+    data[336] = 1;
+    data[337..339].copy_from_slice(&10u16.to_le_bytes());
+    data[339..347].copy_from_slice(&90u64.to_le_bytes());
+    data[377] = 1;
+    data[378..410].copy_from_slice(mint.as_ref());
+    data[410..418].copy_from_slice(&40u64.to_le_bytes());
+    data
+}
+
+#[tokio::test]
+async fn v0_datasets_gain_the_version_header() {
+    let owner = Pubkey::new_unique();
+    let ref_data = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let address = dataset(&HASH);
+    let data = v0_dataset(&owner, &ref_data, &mint);
+    assert_eq!(DatasetState::layout_version(&data), Some(0));
+    assert!(matches!(
+        DatasetState::unpack_from_slice(&data),
+        Err(e) if e == DataNexusError::OutdatedLayout.into()
+    ));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    let account = harness.banks.get_account(address).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), DatasetState::LEN);
+    assert_eq!(account.data[0], DatasetState::LAYOUT_VERSION);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(DatasetState::LEN)
+    );
+    let state: DatasetState = harness.state(address).await;
+    assert!(state.is_initialized);
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.hash, HASH);
+    assert_eq!(state.key, Some([7; 32]));
+    assert_eq!(state.value, Some(250));
+    assert_eq!(state.share_limit, Some(3));
+    assert_eq!(state.purchase_count, 5);
+    assert_eq!(state.uri(), Some("ar://"));
+    assert_eq!(state.total_revenue, 1250);
+    assert_eq!(state.share_count, 2);
+    assert_eq!(state.ref_data, Some(ref_data));
+    assert!(state.resharable);
+    assert_eq!(state.max_share_depth, Some(4));
+    assert_eq!(
+        state.tiers,
+        vec![PriceTier {
+            min_seats: 10,
+            unit_price: 90,
+        }]
+    );
+    assert_eq!(state.claimable, vec![Claimable { mint, amount: 40 }]);
+    assert_eq!(state.holder_count, 7);
+    assert_eq!(state.max_holders, None);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v1_datasets_gain_the_holder_quota() {
+    let owner = Pubkey::new_unique();
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(&owner, &Pubkey::new_unique(), &Pubkey::new_unique());
+    data.insert(0, 1);
+    assert_eq!(data.len(), DatasetState::V1_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(1));
+    assert!(matches!(
+        DatasetState::unpack_from_slice(&data),
+        Err(e) if e == DataNexusError::OutdatedLayout.into()
+    ));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.value, Some(250));
+    assert_eq!(state.max_share_depth, Some(4));
+    assert_eq!(state.holder_count, 7);
+    assert_eq!(state.max_holders, None);
+    assert_eq!(state.slots_remaining(), None);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v2_datasets_keep_their_holder_count() {
+    let owner = Pubkey::new_unique();
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(&owner, &Pubkey::new_unique(), &Pubkey::new_unique());
+    data.insert(0, 2);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    assert_eq!(data.len(), DatasetState::V2_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(2));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.holder_count, 3);
+    assert_eq!(state.max_holders, Some(5));
+    assert_eq!(state.pending_owner, None);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v3_datasets_keep_their_value_for_every_mint() {
+    let owner = Pubkey::new_unique();
+    let pending_owner = Pubkey::new_unique();
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(&owner, &Pubkey::new_unique(), &Pubkey::new_unique());
+    data.insert(0, 3);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(pending_owner.as_ref());
+    assert_eq!(data.len(), DatasetState::V3_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(3));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.holder_count, 3);
+    assert_eq!(state.pending_owner, Some(pending_owner));
+    assert!(state.prices.is_empty());
+    assert_eq!(state.price(&Pubkey::new_unique()), Some(250));
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v4_datasets_stay_on_sale() {
+    let owner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(&owner, &Pubkey::new_unique(), &Pubkey::new_unique());
+    data.insert(0, 4);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(&[0; 32]);
+    data.push(1);
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&40u64.to_le_bytes());
+    data.extend_from_slice(&[0; 120]);
+    assert_eq!(data.len(), DatasetState::V4_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(4));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.price(&mint), Some(40));
+    assert_eq!((state.sale_start, state.sale_end), (None, None));
+    assert!(state.is_on_sale(0));
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v5_datasets_start_without_a_bounty() {
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, 5);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(&[0; 193]);
+    data.extend_from_slice(&20i64.to_le_bytes());
+    data.extend_from_slice(&30i64.to_le_bytes());
+    assert_eq!(data.len(), DatasetState::V5_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(5));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!((state.sale_start, state.sale_end), (Some(20), Some(30)));
+    assert_eq!((state.prune_bounty, state.bounty_pool), (0, 0));
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v6_datasets_start_without_a_share_fee() {
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, 6);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(&[0; 193]);
+    data.extend_from_slice(&20i64.to_le_bytes());
+    data.extend_from_slice(&30i64.to_le_bytes());
+    data.extend_from_slice(&40u64.to_le_bytes());
+    data.extend_from_slice(&50u64.to_le_bytes());
+    assert_eq!(data.len(), DatasetState::V6_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(6));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!((state.prune_bounty, state.bounty_pool), (40, 50));
+    assert_eq!(state.share_fee, None);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v7_datasets_start_without_tags() {
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, 7);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(&[0; 193]);
+    data.extend_from_slice(&20i64.to_le_bytes());
+    data.extend_from_slice(&30i64.to_le_bytes());
+    data.extend_from_slice(&40u64.to_le_bytes());
+    data.extend_from_slice(&50u64.to_le_bytes());
+    let mint = Pubkey::new_unique();
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(&60u64.to_le_bytes());
+    assert_eq!(data.len(), DatasetState::V7_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(7));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.share_fee, Some(MintPrice { mint, amount: 60 }));
+    assert!(state.tags.is_empty());
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v8_datasets_start_without_a_derived_discount() {
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, 8);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(&[0; 193]);
+    data.extend_from_slice(&20i64.to_le_bytes());
+    data.extend_from_slice(&30i64.to_le_bytes());
+    data.extend_from_slice(&40u64.to_le_bytes());
+    data.extend_from_slice(&50u64.to_le_bytes());
+    data.extend_from_slice(&[0; 40]);
+    let mut tags = [0; 128];
+    tags[..7].copy_from_slice(b"finance");
+    data.extend_from_slice(&tags);
+    assert_eq!(data.len(), DatasetState::V8_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(8));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.tags, vec![*b"finance\0\0\0\0\0\0\0\0\0"]);
+    assert_eq!(state.derived_discount_bps, 0);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v9_datasets_start_without_receipts() {
+    let address = dataset(&HASH);
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, 9);
+    data.resize(DatasetState::V8_LEN, 0);
+    data.extend_from_slice(&2_500u16.to_le_bytes());
+    assert_eq!(data.len(), DatasetState::V9_LEN);
+    assert_eq!(DatasetState::layout_version(&data), Some(9));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_eq!(data_len(&mut harness, address).await, DatasetState::LEN);
+    let state: DatasetState = harness.state(address).await;
+    assert_eq!(state.derived_discount_bps, 2_500);
+    assert_eq!(state.receipt_count, 0);
+
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[test]
+fn unknown_dataset_versions_do_not_unpack() {
+    let mut data = v0_dataset(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    data.insert(0, DatasetState::LAYOUT_VERSION);
+    data.resize(DatasetState::LEN, 0);
+    assert!(DatasetState::unpack_from_slice(&data).is_ok());
+
+    data[0] = DatasetState::LAYOUT_VERSION + 1;
+
+    assert_eq!(DatasetState::layout_version(&data), None);
+    assert!(matches!(
+        DatasetState::unpack_from_slice(&data),
+        Err(ProgramError::InvalidAccountData)
+    ));
+}
+
+/// An access account as written before the version header, laid out by
+/// hand with a purchased and a shared entry of 4 shares left each
+fn v0_access() -> Vec<u8> {
+    let mut data = vec![0; AccessState::LEGACY_LEN];
+    data[0] = 1;
+    data[33] = 2;
+    for (entry, shared_from) in data[34..]
+        .chunks_exact_mut(AccessInfo::V1_LEN)
+        .zip([None, Some(Pubkey::new_unique())])
+    {
+        entry[..32].copy_from_slice(&HASH);
+        if let Some(shared_from) = shared_from {
+            entry[64..96].copy_from_slice(shared_from.as_ref());
+            entry[106] = 1;
+        }
+        entry[96..98].copy_from_slice(&4u16.to_le_bytes());
+    }
+    data
+}
+
+/// Checks the entries of `v0_access` survived migration, their shares left
+/// now the quota with none used
+async fn assert_migrated_access(harness: &mut Harness, address: Pubkey) {
+    assert_eq!(data_len(harness, address).await, AccessState::LEN);
+    let state: AccessState = harness.state(address).await;
+    assert!(state.is_initialized);
+    assert_eq!(state.datasets.len(), 2);
+    for access in &state.datasets {
+        assert_eq!(access.hash, HASH);
+        assert_eq!(access.share_quota, 4);
+        assert_eq!(access.shares_used, 0);
+    }
+    assert_eq!(state.datasets[0].shared_from, None);
+    assert_eq!(state.datasets[0].depth, 0);
+    assert!(state.datasets[1].shared_from.is_some());
+    assert_eq!(state.datasets[1].depth, 1);
+}
+
+#[tokio::test]
+async fn legacy_access_accounts_only_unpack_once_migrated() {
+    let authority = Pubkey::new_unique();
+    let address = associated_access(&authority, &HASH);
+    let data = v0_access();
+    assert!(matches!(
+        AccessState::unpack_from_slice(&data),
+        Err(e) if e == DataNexusError::OutdatedLayout.into()
+    ));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_migrated_access(&mut harness, address).await;
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v1_access_accounts_count_their_shares_left_as_quota() {
+    let authority = Pubkey::new_unique();
+    let address = associated_access(&authority, &HASH);
+    let mut data = v0_access();
+    data.insert(0, 1);
+    assert_eq!(data.len(), AccessState::V1_LEN);
+    assert_eq!(AccessState::layout_version(&data), Some(1));
+    assert!(matches!(
+        AccessState::unpack_from_slice(&data),
+        Err(e) if e == DataNexusError::OutdatedLayout.into()
+    ));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_migrated_access(&mut harness, address).await;
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v2_access_accounts_start_without_pending_shares() {
+    let authority = Pubkey::new_unique();
+    let address = associated_access(&authority, &HASH);
+    let state = AccessState {
+        is_initialized: true,
+        pointer: None,
+        datasets: [(None, 0), (Some(Pubkey::new_unique()), 1)]
+            .iter()
+            .map(|&(shared_from, depth)| AccessInfo {
+                hash: HASH,
+                key: None,
+                shared_from,
+                share_quota: 4,
+                expires_at: None,
+                depth,
+                shares_used: 0,
+            })
+            .collect(),
+        pending: vec![],
+    };
+    // Version 2 is the current layout short of the pending shares
+    let mut data = vec![0; AccessState::LEN];
+    state.pack_into_slice(&mut data);
+    data.truncate(AccessState::V2_LEN);
+    data[0] = 2;
+    assert_eq!(AccessState::layout_version(&data), Some(2));
+    assert!(matches!(
+        AccessState::unpack_from_slice(&data),
+        Err(e) if e == DataNexusError::OutdatedLayout.into()
+    ));
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    assert_migrated_access(&mut harness, address).await;
+    let migrated: AccessState = harness.state(address).await;
+    assert!(migrated.pending.is_empty());
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn legacy_indexes_gain_the_layout_version() {
+    let authority = Pubkey::new_unique();
+    let address = owner_index(&authority);
+    let recorded = Pubkey::new_unique();
+    let mut data = vec![0; AccountIndex::LEGACY_LEN];
+    data[0] = 1;
+    data[33 + 32 * 5..][..32].copy_from_slice(recorded.as_ref());
+    let mut harness = start_with_account(address, data).await;
+
+    harness.migrate(address).await.unwrap();
+
+    let account = harness.banks.get_account(address).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), AccountIndex::LEN);
+    assert_eq!(account.data[0], INDEX_LAYOUT_VERSION);
+    let index: AccountIndex = harness.state(address).await;
+    assert_eq!(index.capacity(), MAX_INDEX_ENTRIES);
+    assert_eq!(index.position(&recorded), Some(0));
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn v1_indexes_gain_the_share_approval_preference() {
+    let authority = Keypair::new();
+    let address = access_index(&authority.pubkey());
+    let recorded = Pubkey::new_unique();
+    let mut index = AccountIndex {
+        is_initialized: true,
+        pointer: None,
+        require_share_approval: false,
+        datasets: vec![None; MAX_INDEX_ENTRIES],
+    };
+    index.insert(recorded, HASH).unwrap();
+    // Version 1 is the current layout without the preferences byte
+    let mut data = vec![0; AccountIndex::LEN];
+    index.pack_into_slice(&mut data);
+    data.remove(38);
+    data[0] = 1;
+    assert_eq!(AccountIndex::layout_version(&data), Some(1));
+    let mut harness = start_with_account(address, data).await;
+
+    let result = harness.set_access_preferences(&authority, true).await;
+    assert_error(result, DataNexusError::OutdatedLayout);
+    harness.migrate(address).await.unwrap();
+
+    let account = harness.banks.get_account(address).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), AccountIndex::LEN);
+    assert_eq!(account.data[0], INDEX_LAYOUT_VERSION);
+    let index: AccountIndex = harness.state(address).await;
+    assert!(!index.require_share_approval);
+    assert_eq!(index.position(&recorded), Some(0));
+    assert_invalid_account_data(migrate_again(&mut harness, address).await);
+}
+
+#[tokio::test]
+async fn accounts_of_other_programs_are_not_migrated() {
+    let mut harness = Harness::start().await;
+    let payer = harness.payer.pubkey();
+
+    let result = harness.migrate(payer).await;
+
+    assert_error(result, DataNexusError::InvalidAccountOwner);
+}
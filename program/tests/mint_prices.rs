@@ -0,0 +1,197 @@
+//! Datasets priced per mint charge a purchase the price of the mint it pays
+//! in, and refuse mints outside the table
+
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{DatasetState, MintPrice, MAX_PRICES},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Charged in any mint while the table is empty
+const VALUE: u64 = 100;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+}
+
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 2).await;
+
+    Setup { harness, owner }
+}
+
+impl Setup {
+    /// A new mint the dataset has a vault of
+    async fn mint(&mut self) -> Pubkey {
+        let mint = self.harness.create_mint().await;
+        self.harness.create_vault(&self.owner, HASH, mint).await;
+        mint
+    }
+
+    async fn set_price(&mut self, mint: Pubkey, amount: u64) -> Result<(), TransportError> {
+        self.harness
+            .set_params(&self.owner, HASH, Params::SetPrice(mint, amount))
+            .await
+    }
+
+    async fn remove_price(&mut self, mint: Pubkey) -> Result<(), TransportError> {
+        self.harness
+            .set_params(&self.owner, HASH, Params::RemovePrice(mint))
+            .await
+    }
+
+    /// A new buyer paying `amount` of `mint`
+    async fn purchase(&mut self, mint: Pubkey, amount: u64) -> Result<(), TransportError> {
+        let buyer = self.harness.fund(LAMPORTS_PER_SOL).await;
+        self.harness
+            .create_access_index(&buyer.pubkey())
+            .await
+            .unwrap();
+        self.harness
+            .create_associated_access(&buyer, HASH)
+            .await
+            .unwrap();
+        let tokens = self
+            .harness
+            .create_token_account(&mint, &buyer.pubkey(), amount)
+            .await;
+        self.harness
+            .purchase(
+                &buyer,
+                tokens,
+                &self.owner.pubkey(),
+                vault(&HASH, &mint),
+                mint,
+                HASH,
+                amount,
+            )
+            .await
+    }
+
+    async fn prices(&mut self) -> Vec<MintPrice> {
+        let state: DatasetState = self.harness.state(dataset(&HASH)).await;
+        state.prices
+    }
+}
+
+#[tokio::test]
+async fn purchases_pay_the_price_of_their_mint() {
+    let mut setup = setup().await;
+    let (cheap, dear) = (setup.mint().await, setup.mint().await);
+    setup.set_price(cheap, 40).await.unwrap();
+    setup.set_price(dear, 7_000).await.unwrap();
+
+    setup.purchase(cheap, 40).await.unwrap();
+    setup.purchase(dear, 7_000).await.unwrap();
+    assert_error(
+        setup.purchase(dear, 6_999).await,
+        DataNexusError::InsufficientPayment,
+    );
+
+    assert_eq!(setup.harness.token_balance(vault(&HASH, &cheap)).await, 40);
+    assert_eq!(
+        setup.harness.token_balance(vault(&HASH, &dear)).await,
+        7_000
+    );
+}
+
+#[tokio::test]
+async fn mints_outside_the_table_are_refused() {
+    let mut setup = setup().await;
+    let (priced, unpriced) = (setup.mint().await, setup.mint().await);
+    // Before any price is set the value is charged in any mint
+    setup.purchase(unpriced, VALUE).await.unwrap();
+    setup.set_price(priced, 40).await.unwrap();
+
+    let result = setup.purchase(unpriced, VALUE).await;
+
+    assert_error(result, DataNexusError::UnsupportedMint);
+}
+
+#[tokio::test]
+async fn the_table_holds_a_price_per_mint() {
+    let mut setup = setup().await;
+    let mints: Vec<_> = (0..MAX_PRICES).map(|_| Pubkey::new_unique()).collect();
+    for (amount, mint) in (1..).zip(&mints) {
+        setup.set_price(*mint, amount).await.unwrap();
+    }
+
+    let result = setup.set_price(Pubkey::new_unique(), 1).await;
+
+    assert_error(result, DataNexusError::PriceTableFull);
+    assert_eq!(setup.prices().await.len(), MAX_PRICES);
+}
+
+#[tokio::test]
+async fn setting_a_priced_mint_again_replaces_its_price() {
+    let mut setup = setup().await;
+    let mint = setup.mint().await;
+    setup.set_price(mint, 40).await.unwrap();
+
+    setup.set_price(mint, 60).await.unwrap();
+
+    assert_eq!(setup.prices().await, vec![MintPrice { mint, amount: 60 }]);
+    assert_error(
+        setup.purchase(mint, 40).await,
+        DataNexusError::InsufficientPayment,
+    );
+    setup.purchase(mint, 60).await.unwrap();
+}
+
+#[tokio::test]
+async fn removing_every_price_charges_the_value_again() {
+    let mut setup = setup().await;
+    let (first, second) = (setup.mint().await, setup.mint().await);
+    setup.set_price(first, 40).await.unwrap();
+    setup.set_price(second, 50).await.unwrap();
+
+    setup.remove_price(first).await.unwrap();
+    assert_eq!(
+        setup.prices().await,
+        vec![MintPrice {
+            mint: second,
+            amount: 50
+        }]
+    );
+    assert_error(
+        setup.purchase(first, 40).await,
+        DataNexusError::UnsupportedMint,
+    );
+    assert_error(
+        setup.remove_price(first).await,
+        DataNexusError::UnsupportedMint,
+    );
+
+    setup.remove_price(second).await.unwrap();
+    setup.purchase(first, VALUE).await.unwrap();
+}
+
+#[tokio::test]
+async fn zero_prices_are_rejected() {
+    let mut setup = setup().await;
+
+    let result = setup.set_price(Pubkey::new_unique(), 0).await;
+
+    assert!(matches!(
+        result,
+        Err(TransportError::TransactionError(
+            TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+        ))
+    ));
+    assert!(setup.prices().await.is_empty());
+}
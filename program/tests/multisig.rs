@@ -0,0 +1,185 @@
+//! Datasets owned by a 2 of 3 spl-token `Multisig`, administered by its
+//! members signing in its place
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{AcceptOwnershipAccounts, OwnershipTransferAccounts, SetDataParamsAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{accept_ownership, propose_ownership_transfer, set_data_params, Params},
+    state::DatasetState,
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+use spl_token::{instruction::MAX_SIGNERS, state::Multisig};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// A dataset accepted by a multisig of three members, two of whom must sign
+async fn setup() -> (Harness, Pubkey, Vec<Keypair>) {
+    let multisig = Pubkey::new_unique();
+    let members = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut signers = [Pubkey::default(); MAX_SIGNERS];
+    for (signer, member) in signers.iter_mut().zip(members.iter()) {
+        *signer = member.pubkey();
+    }
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        multisig,
+        Rent::default().minimum_balance(Multisig::LEN),
+        &Multisig {
+            m: 2,
+            n: 3,
+            is_initialized: true,
+            signers,
+        },
+        &spl_token::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .propose_ownership_transfer(&owner, HASH, multisig)
+        .await
+        .unwrap();
+    harness.create_owner_index(&multisig).await.unwrap();
+    let instruction = accept_ownership(
+        datanexus_program::id(),
+        AcceptOwnershipAccounts {
+            authority: multisig,
+            old_owner_index: owner_index(&owner.pubkey()),
+            new_owner_index: owner_index(&multisig),
+            dataset_account: dataset(&HASH),
+        },
+        HASH,
+        &[members[0].pubkey(), members[1].pubkey()],
+    )
+    .unwrap();
+    harness
+        .process(&[instruction], &[&members[0], &members[1]])
+        .await
+        .unwrap();
+
+    (harness, multisig, members)
+}
+
+/// Sets the dataset's value as `multisig`, signed by `signers`
+async fn set_value(
+    harness: &mut Harness,
+    multisig: Pubkey,
+    signers: &[&Keypair],
+    value: u64,
+) -> Result<(), TransportError> {
+    let keys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let instruction = set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: multisig,
+            dataset_account: dataset(&HASH),
+            reference_dataset: None,
+        },
+        HASH,
+        Params::Value(value),
+        &keys,
+    )
+    .unwrap();
+    harness.process(&[instruction], signers).await
+}
+
+#[tokio::test]
+async fn multisigs_accept_ownership() {
+    let (mut harness, multisig, _) = setup().await;
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!((state.owner, state.pending_owner), (multisig, None));
+}
+
+#[tokio::test]
+async fn m_members_signing_administer_the_dataset() {
+    let (mut harness, multisig, members) = setup().await;
+
+    set_value(&mut harness, multisig, &[&members[1], &members[2]], 300)
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.value, Some(300));
+
+    let new_owner = Pubkey::new_unique();
+    let instruction = propose_ownership_transfer(
+        datanexus_program::id(),
+        OwnershipTransferAccounts {
+            authority: multisig,
+            dataset_account: dataset(&HASH),
+        },
+        HASH,
+        new_owner,
+        &[members[0].pubkey(), members[2].pubkey()],
+    )
+    .unwrap();
+    harness
+        .process(&[instruction], &[&members[0], &members[2]])
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.pending_owner, Some(new_owner));
+}
+
+#[tokio::test]
+async fn fewer_than_m_members_are_rejected() {
+    let (mut harness, multisig, members) = setup().await;
+
+    let result = set_value(&mut harness, multisig, &[&members[0]], 300).await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.value, Some(100));
+}
+
+#[tokio::test]
+async fn non_members_do_not_count_towards_m() {
+    let (mut harness, multisig, members) = setup().await;
+    let outsider = Keypair::new();
+
+    let result = set_value(&mut harness, multisig, &[&members[0], &outsider], 300).await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.value, Some(100));
+}
+
+#[tokio::test]
+async fn owners_that_are_not_multisigs_take_no_signers() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let member = Keypair::new();
+
+    let mut instruction = set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: owner.pubkey(),
+            dataset_account: dataset(&HASH),
+            reference_dataset: None,
+        },
+        HASH,
+        Params::Value(300),
+        &[member.pubkey()],
+    )
+    .unwrap();
+    // The builder leaves the authority unsigned once it is given signers
+    instruction.accounts[0].is_signer = true;
+    let result = harness.process(&[instruction], &[&owner, &member]).await;
+
+    assert_error(result, DataNexusError::InvalidAccountCount);
+}
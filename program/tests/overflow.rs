@@ -0,0 +1,139 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    state::{AccessState, AccountFlag, DatasetState, CONTENT_TYPE_LEN, URI_LEN},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, rent::Rent};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+#[tokio::test]
+async fn share_without_remaining_shares_fails() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 0).await;
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 100)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    for authority in &[&buyer, &recipient] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+    harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await
+        .unwrap();
+
+    let result = harness.share(&buyer, &recipient.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::ShareLimitExceeded);
+    let shared: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &HASH))
+        .await;
+    assert_eq!(shared.find_entry(&HASH), None);
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(
+        access.datasets[access.find_entry(&HASH).unwrap()].shares_remaining(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn purchase_overflowing_total_revenue_fails() {
+    let owner = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        dataset(&HASH),
+        Rent::default().minimum_balance(DatasetState::LEN),
+        &DatasetState {
+            is_initialized: true,
+            flag: AccountFlag::Dataset,
+            owner: owner.pubkey(),
+            hash: HASH,
+            key: Some([7; 32]),
+            value: Some(100),
+            share_limit: None,
+            purchase_count: 1,
+            referral_bps: 0,
+            access_duration: None,
+            uri: [0; URI_LEN],
+            size_bytes: 0,
+            content_type: [0; CONTENT_TYPE_LEN],
+            total_revenue: u64::MAX - 50,
+            share_count: 0,
+            ref_data: None,
+            resharable: false,
+            max_share_depth: None,
+            tiers: vec![],
+            claimable: vec![],
+            holder_count: 1,
+            max_holders: None,
+            pending_owner: None,
+            prices: vec![],
+            sale_start: None,
+            sale_end: None,
+            prune_bounty: 0,
+            bounty_pool: 0,
+            share_fee: None,
+            tags: vec![],
+            derived_discount_bps: 0,
+            receipt_count: 0,
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 100)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let result = harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::NumericalOverflow);
+    assert_eq!(harness.token_balance(buyer_tokens).await, 100);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.total_revenue, u64::MAX - 50);
+    assert_eq!(state.purchase_count, 1);
+}
@@ -0,0 +1,165 @@
+//! Owners have access to their own datasets, so they can't buy it and
+//! can't be shared it
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{RecipientAccounts, ShareAccessMultiAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::share_access_multi,
+    state::{AccessInfo, AccessState, DatasetState},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// A dataset whose owner has access accounts of their own, and a holder
+/// granted access to it
+async fn setup() -> (Harness, Keypair, Keypair) {
+    let holder = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&holder.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: None,
+                shared_from: None,
+                share_quota: 2,
+                expires_at: None,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness.create_access_index(&owner.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&owner, HASH)
+        .await
+        .unwrap();
+
+    (harness, owner, holder)
+}
+
+async fn owner_access(harness: &mut Harness, owner: &Keypair) -> AccessState {
+    harness
+        .state(associated_access(&owner.pubkey(), &HASH))
+        .await
+}
+
+#[tokio::test]
+async fn owners_cannot_purchase_their_datasets() {
+    let (mut harness, owner, _) = setup().await;
+
+    // Rejected before any token account is read
+    let result = harness
+        .purchase(
+            &owner,
+            Pubkey::new_unique(),
+            &owner.pubkey(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            HASH,
+            100,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::OwnerCannotPurchase);
+    assert!(owner_access(&mut harness, &owner).await.datasets.is_empty());
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.purchase_count, 0);
+}
+
+#[tokio::test]
+async fn owners_cannot_purchase_their_collections() {
+    let (mut harness, owner, _) = setup().await;
+    harness.create_collection(&owner, HASH, 100).await.unwrap();
+    harness
+        .create_collection_access(&owner, HASH)
+        .await
+        .unwrap();
+
+    let result = harness
+        .purchase_collection(
+            &owner,
+            Pubkey::new_unique(),
+            &owner.pubkey(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            HASH,
+            100,
+            &[],
+        )
+        .await;
+
+    assert_error(result, DataNexusError::OwnerCannotPurchase);
+}
+
+#[tokio::test]
+async fn shares_with_the_owner_fail() {
+    let (mut harness, owner, holder) = setup().await;
+
+    let result = harness.share(&holder, &owner.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    assert!(owner_access(&mut harness, &owner).await.datasets.is_empty());
+    let access: AccessState = harness
+        .state(associated_access(&holder.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].shares_used, 0);
+}
+
+#[tokio::test]
+async fn multi_shares_with_the_owner_fail() {
+    let (mut harness, owner, holder) = setup().await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+
+    let recipients = [recipient.pubkey(), owner.pubkey()]
+        .iter()
+        .map(|authority| RecipientAccounts {
+            authority: *authority,
+            access_index: access_index(authority),
+            access_account: associated_access(authority, &HASH),
+        })
+        .collect();
+    let instruction = share_access_multi(
+        datanexus_program::id(),
+        ShareAccessMultiAccounts {
+            user_authority: holder.pubkey(),
+            user_access_account: associated_access(&holder.pubkey(), &HASH),
+            dataset_account: dataset(&HASH),
+            recipients,
+        },
+        HASH,
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&holder]).await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    let access: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &HASH))
+        .await;
+    assert!(access.datasets.is_empty());
+}
@@ -0,0 +1,129 @@
+//! Datasets change owner in two steps, the owner proposes a new owner and
+//! the dataset moves once that owner accepts
+
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccountIndex, DatasetState},
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// An owner with `HASH` registered and a new owner with an owner index
+async fn setup() -> (Harness, Keypair, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let new_owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_owner_index(&new_owner.pubkey())
+        .await
+        .unwrap();
+
+    (harness, owner, new_owner)
+}
+
+#[tokio::test]
+async fn accepted_transfers_move_the_dataset() {
+    let (mut harness, owner, new_owner) = setup().await;
+
+    harness
+        .propose_ownership_transfer(&owner, HASH, new_owner.pubkey())
+        .await
+        .unwrap();
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.pending_owner, Some(new_owner.pubkey()));
+
+    harness
+        .accept_ownership(&new_owner, &owner.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.owner, new_owner.pubkey());
+    assert_eq!(state.pending_owner, None);
+    let old_index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert_eq!(old_index.position(&dataset(&HASH)), None);
+    let new_index: AccountIndex = harness.state(owner_index(&new_owner.pubkey())).await;
+    assert!(new_index.position(&dataset(&HASH)).is_some());
+
+    assert_error(
+        harness.set_params(&owner, HASH, Params::Value(1)).await,
+        DataNexusError::IncorrectOwner,
+    );
+    harness
+        .set_params(&new_owner, HASH, Params::Value(1))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn only_the_pending_owner_accepts() {
+    let (mut harness, owner, new_owner) = setup().await;
+    let stranger = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_owner_index(&stranger.pubkey())
+        .await
+        .unwrap();
+    harness
+        .propose_ownership_transfer(&owner, HASH, new_owner.pubkey())
+        .await
+        .unwrap();
+
+    let result = harness
+        .accept_ownership(&stranger, &owner.pubkey(), HASH)
+        .await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.pending_owner, Some(new_owner.pubkey()));
+}
+
+#[tokio::test]
+async fn cancelled_transfers_cannot_be_accepted() {
+    let (mut harness, owner, new_owner) = setup().await;
+    harness
+        .propose_ownership_transfer(&owner, HASH, new_owner.pubkey())
+        .await
+        .unwrap();
+
+    harness
+        .cancel_ownership_transfer(&owner, HASH)
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.pending_owner, None);
+    let result = harness
+        .accept_ownership(&new_owner, &owner.pubkey(), HASH)
+        .await;
+    assert_error(result, DataNexusError::IncorrectOwner);
+    // The bank drops a transaction identical to an earlier one, so change
+    // payers
+    harness.payer = harness.fund(LAMPORTS_PER_SOL).await;
+    assert_error(
+        harness.cancel_ownership_transfer(&owner, HASH).await,
+        DataNexusError::NoPendingTransfer,
+    );
+}
+
+#[tokio::test]
+async fn only_the_owner_proposes() {
+    let (mut harness, owner, new_owner) = setup().await;
+
+    let result = harness
+        .propose_ownership_transfer(&new_owner, HASH, new_owner.pubkey())
+        .await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.pending_owner, None);
+}
@@ -0,0 +1,181 @@
+//! Anyone may prune a holder's expired access, collecting the dataset's
+//! prune bounty out of the pool its owner funds
+
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessInfo, AccessState, DatasetState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, rent::Rent};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+const BOUNTY: u64 = 5_000;
+
+const EXPIRES_AT: i64 = 1_000_000;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    holder: Keypair,
+}
+
+/// A dataset with a prune bounty of `BOUNTY` and `pool` lamports funded,
+/// and a holder whose access to it lapses at `expires_at`
+async fn setup(expires_at: Option<i64>, pool: u64) -> Setup {
+    let holder = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&holder.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: Some([7; 32]),
+                shared_from: None,
+                share_quota: 2,
+                expires_at,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .set_params(&owner, HASH, Params::PruneBounty(BOUNTY))
+        .await
+        .unwrap();
+    if pool > 0 {
+        harness.fund_bounty(&owner, HASH, pool).await.unwrap();
+    }
+    harness.set_unix_timestamp(EXPIRES_AT).await;
+
+    Setup {
+        harness,
+        owner,
+        holder,
+    }
+}
+
+impl Setup {
+    async fn dataset(&mut self) -> DatasetState {
+        self.harness.state(dataset(&HASH)).await
+    }
+
+    async fn access(&mut self) -> AccessState {
+        self.harness
+            .state(associated_access(&self.holder.pubkey(), &HASH))
+            .await
+    }
+}
+
+#[tokio::test]
+async fn expired_access_is_pruned_for_the_bounty() {
+    let mut setup = setup(Some(EXPIRES_AT), 3 * BOUNTY).await;
+    let cranker = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let dataset_lamports = setup.harness.lamports(dataset(&HASH)).await;
+
+    setup
+        .harness
+        .crank_prune(&cranker, &setup.holder.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    assert_eq!(setup.access().await.find_entry(&HASH), None);
+    assert_eq!(setup.dataset().await.bounty_pool, 2 * BOUNTY);
+    assert_eq!(
+        setup.harness.lamports(cranker.pubkey()).await,
+        LAMPORTS_PER_SOL + BOUNTY
+    );
+    assert_eq!(
+        setup.harness.lamports(dataset(&HASH)).await,
+        dataset_lamports - BOUNTY
+    );
+}
+
+#[tokio::test]
+async fn unexpired_access_is_not_pruned() {
+    for expires_at in [Some(EXPIRES_AT + 1), None] {
+        let mut setup = setup(expires_at, BOUNTY).await;
+        let cranker = setup.harness.fund(LAMPORTS_PER_SOL).await;
+
+        let result = setup
+            .harness
+            .crank_prune(&cranker, &setup.holder.pubkey(), HASH)
+            .await;
+
+        assert_error(result, DataNexusError::AccessNotExpired);
+        assert_eq!(setup.access().await.datasets.len(), 1);
+        assert_eq!(setup.dataset().await.bounty_pool, BOUNTY);
+    }
+}
+
+#[tokio::test]
+async fn bounties_are_claimed_once() {
+    let mut setup = setup(Some(EXPIRES_AT), 3 * BOUNTY).await;
+    let first = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let second = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup
+        .harness
+        .crank_prune(&first, &setup.holder.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    let result = setup
+        .harness
+        .crank_prune(&second, &setup.holder.pubkey(), HASH)
+        .await;
+
+    assert_error(result, DataNexusError::AccessNotFound);
+    assert_eq!(setup.dataset().await.bounty_pool, 2 * BOUNTY);
+    assert_eq!(
+        setup.harness.lamports(second.pubkey()).await,
+        LAMPORTS_PER_SOL
+    );
+}
+
+#[tokio::test]
+async fn a_short_pool_pays_what_it_has_left() {
+    let mut setup = setup(Some(EXPIRES_AT), BOUNTY - 1).await;
+    let cranker = setup.harness.fund(LAMPORTS_PER_SOL).await;
+
+    setup
+        .harness
+        .crank_prune(&cranker, &setup.holder.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    assert_eq!(setup.dataset().await.bounty_pool, 0);
+    assert_eq!(
+        setup.harness.lamports(cranker.pubkey()).await,
+        LAMPORTS_PER_SOL + BOUNTY - 1
+    );
+}
+
+#[tokio::test]
+async fn only_the_owner_funds_the_pool() {
+    let mut setup = setup(None, 0).await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+
+    let result = setup.harness.fund_bounty(&other, HASH, BOUNTY).await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    setup
+        .harness
+        .fund_bounty(&setup.owner, HASH, BOUNTY)
+        .await
+        .unwrap();
+    assert_eq!(setup.dataset().await.bounty_pool, BOUNTY);
+}
@@ -0,0 +1,134 @@
+//! Purchases write the grant into the buyer's associated access account and
+//! record the dataset in the buyer's access index
+
+mod common;
+
+use common::*;
+use datanexus::{
+    instruction::Params,
+    state::{AccessState, AccountIndex},
+};
+use solana_program::{clock::Clock, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+const OTHER_HASH: [u8; 32] = [2; 32];
+
+const VALUE: u64 = 100;
+
+const SHARE_LIMIT: u16 = 3;
+
+/// Registers the dataset of `hash` under a new owner and opens its vault of
+/// `mint`
+async fn register(harness: &mut Harness, hash: [u8; 32], mint: Pubkey) -> Keypair {
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .register_dataset(&owner, hash, VALUE, SHARE_LIMIT)
+        .await;
+    harness.create_vault(&owner, hash, mint).await;
+    owner
+}
+
+/// Creates the buyer's accounts of `hash` and buys it
+async fn purchase(
+    harness: &mut Harness,
+    buyer: &Keypair,
+    owner: &Keypair,
+    hash: [u8; 32],
+    mint: Pubkey,
+) {
+    harness.create_associated_access(buyer, hash).await.unwrap();
+    let tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), VALUE)
+        .await;
+    harness
+        .purchase(
+            buyer,
+            tokens,
+            &owner.pubkey(),
+            vault(&hash, &mint),
+            mint,
+            hash,
+            VALUE,
+        )
+        .await
+        .unwrap();
+}
+
+/// A harness with a mint and a buyer holding an access index
+async fn setup() -> (Harness, Keypair, Pubkey) {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint().await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    (harness, buyer, mint)
+}
+
+#[tokio::test]
+async fn purchases_store_the_grant() {
+    let (mut harness, buyer, mint) = setup().await;
+    let owner = register(&mut harness, HASH, mint).await;
+
+    purchase(&mut harness, &buyer, &owner, HASH, mint).await;
+
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets.len(), 1);
+    let grant = access.datasets[0];
+    assert_eq!(grant.hash, HASH);
+    assert_eq!(grant.key, Some([7; 32]));
+    assert_eq!(grant.shared_from, None);
+    assert_eq!(grant.share_quota, SHARE_LIMIT);
+    assert_eq!(grant.shares_used, 0);
+    assert_eq!(grant.expires_at, None);
+    assert_eq!(grant.depth, 0);
+
+    let index: AccountIndex = harness.state(access_index(&buyer.pubkey())).await;
+    assert_eq!(index.datasets.iter().flatten().count(), 1);
+    assert_eq!(index.find_by_hash(&HASH), Some(dataset(&HASH)));
+}
+
+#[tokio::test]
+async fn purchases_of_timed_datasets_store_the_expiry() {
+    let (mut harness, buyer, mint) = setup().await;
+    let owner = register(&mut harness, HASH, mint).await;
+    harness
+        .set_params(&owner, HASH, Params::AccessDuration(3600))
+        .await
+        .unwrap();
+
+    purchase(&mut harness, &buyer, &owner, HASH, mint).await;
+
+    let clock: Clock = harness.banks.get_sysvar().await.unwrap();
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(
+        access.datasets[0].expires_at,
+        Some(clock.unix_timestamp + 3600)
+    );
+}
+
+#[tokio::test]
+async fn purchases_of_two_datasets_are_indexed_apart() {
+    let (mut harness, buyer, mint) = setup().await;
+    let owner = register(&mut harness, HASH, mint).await;
+    let other_owner = register(&mut harness, OTHER_HASH, mint).await;
+
+    purchase(&mut harness, &buyer, &owner, HASH, mint).await;
+    purchase(&mut harness, &buyer, &other_owner, OTHER_HASH, mint).await;
+
+    for hash in &[HASH, OTHER_HASH] {
+        let access: AccessState = harness
+            .state(associated_access(&buyer.pubkey(), hash))
+            .await;
+        assert_eq!(access.datasets.len(), 1);
+        assert_eq!(access.datasets[0].hash, *hash);
+    }
+    let index: AccountIndex = harness.state(access_index(&buyer.pubkey())).await;
+    assert_eq!(index.datasets.iter().flatten().count(), 2);
+    assert_eq!(index.find_by_hash(&HASH), Some(dataset(&HASH)));
+    assert_eq!(index.find_by_hash(&OTHER_HASH), Some(dataset(&OTHER_HASH)));
+}
@@ -0,0 +1,136 @@
+//! Purchases must name the buyer's own access accounts and the dataset's
+//! owner, each of these swaps one for another user's
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::PurchaseAccessAccounts, datanexus_program, error::DataNexusError,
+    instruction::purchase_access, state::AccessState,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    /// Another user with access accounts of their own
+    other: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+}
+
+impl Setup {
+    fn accounts(&self) -> PurchaseAccessAccounts<Pubkey> {
+        PurchaseAccessAccounts {
+            user_authority: self.buyer.pubkey(),
+            user_access_index: access_index(&self.buyer.pubkey()),
+            user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+            user_token_account: self.buyer_tokens,
+            owner_authority: self.owner.pubkey(),
+            proceeds_account: vault(&HASH, &self.mint),
+            token_mint: self.mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+            receipt: None,
+            reference_access: None,
+        }
+    }
+
+    async fn assert_no_access(&mut self, authority: Pubkey) {
+        let access: AccessState = self
+            .harness
+            .state(associated_access(&authority, &HASH))
+            .await;
+        assert_eq!(access.find_entry(&HASH), None);
+    }
+}
+
+async fn purchase(
+    harness: &mut Harness,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    buyer: &Keypair,
+) -> Result<(), TransportError> {
+    let instruction = purchase_access(datanexus_program::id(), accounts, HASH, VALUE).unwrap();
+    harness.process(&[instruction], &[buyer]).await
+}
+
+/// A dataset with a vault, and a buyer and another user who both have
+/// their access accounts of it
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    let mint = harness.create_mint().await;
+    harness.create_vault(&owner, HASH, mint).await;
+
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&buyer, &other] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), VALUE)
+        .await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        other,
+        mint,
+        buyer_tokens,
+    }
+}
+
+#[tokio::test]
+async fn purchases_into_another_access_account_fail() {
+    let mut setup = setup().await;
+
+    let mut accounts = setup.accounts();
+    accounts.user_access_account = associated_access(&setup.other.pubkey(), &HASH);
+    let result = purchase(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    setup.assert_no_access(setup.other.pubkey()).await;
+}
+
+#[tokio::test]
+async fn purchases_into_another_access_index_fail() {
+    let mut setup = setup().await;
+
+    let mut accounts = setup.accounts();
+    accounts.user_access_index = access_index(&setup.other.pubkey());
+    let result = purchase(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    setup.assert_no_access(setup.buyer.pubkey()).await;
+}
+
+#[tokio::test]
+async fn purchases_naming_another_owner_fail() {
+    let mut setup = setup().await;
+
+    let mut accounts = setup.accounts();
+    accounts.owner_authority = setup.other.pubkey();
+    let result = purchase(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    setup.assert_no_access(setup.buyer.pubkey()).await;
+}
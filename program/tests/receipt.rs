@@ -0,0 +1,305 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{PurchaseAccessAccounts, ReceiptAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::purchase_access,
+    state::{AccessState, DatasetState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_option::COption, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+    vault: Pubkey,
+    receipt_mint: Pubkey,
+    receipt_tokens: Pubkey,
+}
+
+/// A dataset with a receipt mint, and a buyer with an access account and
+/// an empty token account of the receipt mint
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 2 * VALUE)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    let receipt_mint = harness.create_receipt_mint(HASH).await;
+    let receipt_tokens = harness
+        .create_token_account(&receipt_mint, &buyer.pubkey(), 0)
+        .await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        mint,
+        buyer_tokens,
+        vault,
+        receipt_mint,
+        receipt_tokens,
+    }
+}
+
+impl Setup {
+    async fn purchase(&mut self, receipt_tokens: Option<Pubkey>) -> Result<(), TransportError> {
+        self.harness
+            .purchase_with_receipt(
+                &self.buyer,
+                self.buyer_tokens,
+                &self.owner.pubkey(),
+                self.vault,
+                self.mint,
+                HASH,
+                VALUE,
+                receipt_tokens,
+            )
+            .await
+    }
+
+    async fn has_access(&mut self) -> bool {
+        let access: AccessState = self
+            .harness
+            .state(associated_access(&self.buyer.pubkey(), &HASH))
+            .await;
+        access.find_entry(&HASH).is_some()
+    }
+
+    async fn receipt_count(&mut self) -> u32 {
+        let dataset: DatasetState = self.harness.state(dataset(&HASH)).await;
+        dataset.receipt_count
+    }
+
+    async fn receipt_supply(&mut self) -> u64 {
+        self.harness
+            .state::<spl_token::state::Mint>(self.receipt_mint)
+            .await
+            .supply
+    }
+}
+
+#[tokio::test]
+async fn purchases_without_receipt_accounts_mint_nothing() {
+    let mut setup = setup().await;
+
+    setup.purchase(None).await.unwrap();
+
+    assert!(setup.has_access().await);
+    assert_eq!(setup.harness.token_balance(setup.receipt_tokens).await, 0);
+    assert_eq!(setup.receipt_supply().await, 0);
+}
+
+#[tokio::test]
+async fn purchases_mint_a_frozen_receipt() {
+    let mut setup = setup().await;
+
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+
+    assert!(setup.has_access().await);
+    let receipt: spl_token::state::Account = setup.harness.state(setup.receipt_tokens).await;
+    assert_eq!(receipt.amount, 1);
+    assert!(receipt.is_frozen());
+    assert_eq!(receipt.delegate, COption::Some(setup.receipt_mint));
+    assert_eq!(setup.receipt_supply().await, 1);
+    assert_eq!(setup.harness.token_balance(setup.vault).await, VALUE);
+}
+
+#[tokio::test]
+async fn receipts_cannot_be_passed_on() {
+    let mut setup = setup().await;
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+    let other_tokens = setup
+        .harness
+        .create_token_account(&setup.receipt_mint, &setup.owner.pubkey(), 0)
+        .await;
+
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &setup.receipt_tokens,
+        &other_tokens,
+        &setup.buyer.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    let result = setup.harness.process(&[transfer], &[&setup.buyer]).await;
+
+    assert!(result.is_err());
+    assert_eq!(setup.harness.token_balance(setup.receipt_tokens).await, 1);
+}
+
+#[tokio::test]
+async fn receipts_are_minted_to_the_buyer_only() {
+    let mut setup = setup().await;
+    let other_tokens = setup
+        .harness
+        .create_token_account(&setup.receipt_mint, &setup.owner.pubkey(), 0)
+        .await;
+
+    let result = setup.purchase(Some(other_tokens)).await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    assert!(!setup.has_access().await);
+}
+
+#[tokio::test]
+async fn receipts_come_from_the_dataset_receipt_mint() {
+    let mut setup = setup().await;
+    let other_mint = setup.harness.create_mint().await;
+    let other_tokens = setup
+        .harness
+        .create_token_account(&other_mint, &setup.buyer.pubkey(), 0)
+        .await;
+
+    let instruction = purchase_access(
+        datanexus_program::id(),
+        PurchaseAccessAccounts {
+            user_authority: setup.buyer.pubkey(),
+            user_access_index: access_index(&setup.buyer.pubkey()),
+            user_access_account: associated_access(&setup.buyer.pubkey(), &HASH),
+            user_token_account: setup.buyer_tokens,
+            owner_authority: setup.owner.pubkey(),
+            proceeds_account: setup.vault,
+            token_mint: setup.mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+            receipt: Some(ReceiptAccounts {
+                receipt_mint: other_mint,
+                receipt_token_account: other_tokens,
+                token_program: spl_token::id(),
+            }),
+            reference_access: None,
+        },
+        HASH,
+        VALUE,
+    )
+    .unwrap();
+    let result = setup.harness.process(&[instruction], &[&setup.buyer]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn revoking_burns_the_receipt() {
+    let mut setup = setup().await;
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+
+    setup
+        .harness
+        .revoke(
+            &setup.owner,
+            &setup.buyer.pubkey(),
+            HASH,
+            Some(setup.receipt_tokens),
+        )
+        .await
+        .unwrap();
+
+    assert!(!setup.has_access().await);
+    let receipt: spl_token::state::Account = setup.harness.state(setup.receipt_tokens).await;
+    assert_eq!(receipt.amount, 0);
+    assert!(!receipt.is_frozen());
+    assert_eq!(setup.receipt_supply().await, 0);
+    assert_eq!(setup.receipt_count().await, 0);
+}
+
+#[tokio::test]
+async fn revoking_needs_the_receipt_accounts_once_receipts_are_out() {
+    let mut setup = setup().await;
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+    assert_eq!(setup.receipt_count().await, 1);
+
+    let result = setup
+        .harness
+        .revoke(&setup.owner, &setup.buyer.pubkey(), HASH, None)
+        .await;
+
+    assert_error(result, DataNexusError::ReceiptRequired);
+    assert!(setup.has_access().await);
+    assert_eq!(setup.harness.token_balance(setup.receipt_tokens).await, 1);
+    assert_eq!(setup.receipt_count().await, 1);
+}
+
+#[tokio::test]
+async fn revoking_without_a_receipt() {
+    let mut setup = setup().await;
+    setup.purchase(None).await.unwrap();
+
+    setup
+        .harness
+        .revoke(&setup.owner, &setup.buyer.pubkey(), HASH, None)
+        .await
+        .unwrap();
+
+    assert!(!setup.has_access().await);
+
+    // The bank drops a transaction identical to the last, so change payers
+    setup.harness.payer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let result = setup
+        .harness
+        .revoke(&setup.owner, &setup.buyer.pubkey(), HASH, None)
+        .await;
+    assert_error(result, DataNexusError::AccessNotFound);
+}
+
+#[tokio::test]
+async fn revoked_access_can_be_bought_again_with_a_new_receipt() {
+    let mut setup = setup().await;
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+    setup
+        .harness
+        .revoke(
+            &setup.owner,
+            &setup.buyer.pubkey(),
+            HASH,
+            Some(setup.receipt_tokens),
+        )
+        .await
+        .unwrap();
+
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+
+    assert!(setup.has_access().await);
+    assert_eq!(setup.harness.token_balance(setup.receipt_tokens).await, 1);
+    assert_eq!(setup.receipt_supply().await, 1);
+}
+
+#[tokio::test]
+async fn only_the_owner_revokes() {
+    let mut setup = setup().await;
+    setup.purchase(Some(setup.receipt_tokens)).await.unwrap();
+    let buyer = setup.buyer.pubkey();
+
+    let result = setup
+        .harness
+        .revoke(&setup.buyer, &buyer, HASH, Some(setup.receipt_tokens))
+        .await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    assert!(setup.has_access().await);
+    assert_eq!(setup.harness.token_balance(setup.receipt_tokens).await, 1);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::SetDataParamsAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{set_data_params, Params},
+    state::DatasetState,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+const REFERENCE_HASH: [u8; 32] = [2; 32];
+
+/// Owner with two datasets registered, the second to be referenced by the
+/// first
+async fn setup() -> (Harness, Keypair) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .create_dataset_at(&owner, REFERENCE_HASH, dataset(&REFERENCE_HASH))
+        .await
+        .unwrap();
+
+    (harness, owner)
+}
+
+#[tokio::test]
+async fn reference_data_points_at_a_dataset() {
+    let (mut harness, owner) = setup().await;
+
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::ReferenceData(dataset(&REFERENCE_HASH)),
+        )
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.ref_data, Some(dataset(&REFERENCE_HASH)));
+}
+
+#[tokio::test]
+async fn init_stores_its_reference_data() {
+    let (mut harness, owner) = setup().await;
+
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::Init([7; 32], 300, 4, Some(dataset(&REFERENCE_HASH))),
+        )
+        .await
+        .unwrap();
+
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.key, Some([7; 32]));
+    assert_eq!(state.value, Some(300));
+    assert_eq!(state.share_limit, Some(4));
+    assert_eq!(state.ref_data, Some(dataset(&REFERENCE_HASH)));
+}
+
+#[tokio::test]
+async fn reference_dataset_account_is_required() {
+    let (mut harness, owner) = setup().await;
+
+    let instruction = set_data_params(
+        datanexus_program::id(),
+        SetDataParamsAccounts {
+            authority: owner.pubkey(),
+            dataset_account: dataset(&HASH),
+            reference_dataset: None,
+        },
+        HASH,
+        Params::ReferenceData(dataset(&REFERENCE_HASH)),
+        &[],
+    )
+    .unwrap();
+    let result = harness.process(&[instruction], &[&owner]).await;
+
+    assert_error(result, DataNexusError::InvalidReferenceDataset);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.ref_data, None);
+}
+
+#[tokio::test]
+async fn reference_must_be_a_dataset() {
+    let (mut harness, owner) = setup().await;
+
+    let result = harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::ReferenceData(owner_index(&owner.pubkey())),
+        )
+        .await;
+
+    assert_error(result, DataNexusError::InvalidReferenceDataset);
+    let result = harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::Init([7; 32], 100, 2, Some(Pubkey::new_unique())),
+        )
+        .await;
+
+    assert_error(result, DataNexusError::InvalidReferenceDataset);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.ref_data, None);
+}
+
+#[tokio::test]
+async fn dataset_cannot_reference_itself() {
+    let (mut harness, owner) = setup().await;
+
+    let result = harness
+        .set_params(&owner, HASH, Params::ReferenceData(dataset(&HASH)))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidReferenceDataset);
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!(state.ref_data, None);
+}
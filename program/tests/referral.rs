@@ -0,0 +1,145 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::PurchaseAccessAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{purchase_access, Params},
+    state::DatasetState,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+/// Share of each payment referrers receive
+const REFERRAL_BPS: u16 = 1_000;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+}
+
+impl Setup {
+    /// Purchase by the buyer paying into the vault, through `referrer`
+    fn accounts(&self, referrer: Option<Pubkey>) -> PurchaseAccessAccounts<Pubkey> {
+        PurchaseAccessAccounts {
+            user_authority: self.buyer.pubkey(),
+            user_access_index: access_index(&self.buyer.pubkey()),
+            user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+            user_token_account: self.buyer_tokens,
+            owner_authority: self.owner.pubkey(),
+            proceeds_account: vault(&HASH, &self.mint),
+            token_mint: self.mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: referrer,
+            receipt: None,
+            reference_access: None,
+        }
+    }
+}
+
+async fn purchase(
+    harness: &mut Harness,
+    accounts: PurchaseAccessAccounts<Pubkey>,
+    buyer: &Keypair,
+) -> Result<(), TransportError> {
+    let instruction = purchase_access(datanexus_program::id(), accounts, HASH, VALUE).unwrap();
+    harness.process(&[instruction], &[buyer]).await
+}
+
+/// A dataset paying referrers `REFERRAL_BPS`, with a vault of the mint the
+/// buyer holds `VALUE` of
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    harness
+        .set_params(&owner, HASH, Params::ReferralBps(REFERRAL_BPS))
+        .await
+        .unwrap();
+
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), VALUE)
+        .await;
+    harness.create_vault(&owner, HASH, mint).await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        mint,
+        buyer_tokens,
+    }
+}
+
+#[tokio::test]
+async fn referrers_receive_their_share() {
+    let mut setup = setup().await;
+    let referrer = Pubkey::new_unique();
+    let referrer_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &referrer, 0)
+        .await;
+
+    let accounts = setup.accounts(Some(referrer_tokens));
+    purchase(&mut setup.harness, accounts, &setup.buyer)
+        .await
+        .unwrap();
+
+    assert_eq!(setup.harness.token_balance(referrer_tokens).await, 10);
+    let vault = vault(&HASH, &setup.mint);
+    assert_eq!(setup.harness.token_balance(vault).await, 90);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.claimable(&setup.mint), Some(90));
+}
+
+#[tokio::test]
+async fn buyers_cannot_refer_themselves() {
+    let mut setup = setup().await;
+    let own_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &setup.buyer.pubkey(), 0)
+        .await;
+
+    let accounts = setup.accounts(Some(own_tokens));
+    let result = purchase(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::SelfReferral);
+    assert_eq!(setup.harness.token_balance(setup.buyer_tokens).await, VALUE);
+}
+
+#[tokio::test]
+async fn purchases_paying_the_buyer_fail() {
+    let mut setup = setup().await;
+    let own_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &setup.buyer.pubkey(), 0)
+        .await;
+
+    let mut accounts = setup.accounts(None);
+    accounts.proceeds_account = own_tokens;
+    let result = purchase(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.harness.token_balance(setup.buyer_tokens).await, VALUE);
+    assert_eq!(setup.harness.token_balance(own_tokens).await, 0);
+}
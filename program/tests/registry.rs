@@ -0,0 +1,199 @@
+//! Datasets listed in the global registry on creation, under the first two
+//! bytes of their hash, with full pages chained to overflow pages
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::RegistryAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    state::{DatasetState, RegistryEntry, RegistryState, MAX_REGISTRY_ENTRIES},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+/// Hashes sharing the `[1, 1]` prefix
+const HASH: [u8; 32] = [1; 32];
+const COLLIDING: [u8; 32] = {
+    let mut hash = [1; 32];
+    hash[2] = 2;
+    hash
+};
+
+fn first_page(hash: &[u8; 32]) -> RegistryAccounts<Pubkey> {
+    RegistryAccounts {
+        registry_account: registry(hash, 0),
+        overflow_account: None,
+    }
+}
+
+/// Starts a bank where the first page of `HASH`'s prefix is full, and an
+/// owner with an index ready to create datasets
+async fn start_with_full_page() -> (Harness, Keypair) {
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        registry(&HASH, 0),
+        Rent::default().minimum_balance(RegistryState::LEN),
+        &RegistryState {
+            is_initialized: true,
+            prefix: [1, 1],
+            page: 0,
+            next: None,
+            entries: vec![
+                RegistryEntry {
+                    hash: [9; 32],
+                    dataset: Pubkey::new_unique(),
+                };
+                MAX_REGISTRY_ENTRIES
+            ],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+    (harness, owner)
+}
+
+#[tokio::test]
+async fn colliding_prefixes_share_a_page_the_owner_grows() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+
+    for hash in [HASH, COLLIDING] {
+        harness
+            .create_listed_dataset(&owner, hash, first_page(&hash))
+            .await
+            .unwrap();
+    }
+
+    let page: RegistryState = harness.state(registry(&HASH, 0)).await;
+    assert_eq!(page.prefix, [1, 1]);
+    assert_eq!(page.next, None);
+    assert_eq!(page.find(&HASH), Some(dataset(&HASH)));
+    assert_eq!(page.find(&COLLIDING), Some(dataset(&COLLIDING)));
+
+    // The page grew by one entry and the owner paid the rent of both
+    let rent = Rent::default();
+    let page_rent = rent.minimum_balance(RegistryState::space(2).unwrap());
+    assert_eq!(harness.lamports(registry(&HASH, 0)).await, page_rent);
+    assert_eq!(
+        harness.lamports(owner.pubkey()).await,
+        LAMPORTS_PER_SOL - 2 * rent.minimum_balance(DatasetState::LEN) - page_rent
+    );
+}
+
+#[tokio::test]
+async fn other_prefixes_get_their_own_page() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_owner_index(&owner.pubkey()).await.unwrap();
+    let other = [2; 32];
+
+    harness
+        .create_listed_dataset(&owner, HASH, first_page(&HASH))
+        .await
+        .unwrap();
+    // The page of another prefix is not where this hash is listed
+    let result = harness
+        .create_listed_dataset(&owner, other, first_page(&HASH))
+        .await;
+    assert_error(result, DataNexusError::InvalidSeeds);
+
+    harness
+        .create_listed_dataset(&owner, other, first_page(&other))
+        .await
+        .unwrap();
+    let page: RegistryState = harness.state(registry(&other, 0)).await;
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.find(&other), Some(dataset(&other)));
+}
+
+#[tokio::test]
+async fn a_full_page_takes_no_more_entries() {
+    let (mut harness, owner) = start_with_full_page().await;
+
+    let result = harness
+        .create_listed_dataset(&owner, HASH, first_page(&HASH))
+        .await;
+
+    assert_error(result, DataNexusError::RegistryFull);
+}
+
+#[tokio::test]
+async fn full_pages_chain_to_an_overflow_page() {
+    let (mut harness, owner) = start_with_full_page().await;
+
+    harness
+        .create_listed_dataset(
+            &owner,
+            HASH,
+            RegistryAccounts {
+                registry_account: registry(&HASH, 0),
+                overflow_account: Some(registry(&HASH, 1)),
+            },
+        )
+        .await
+        .unwrap();
+    harness
+        .create_listed_dataset(
+            &owner,
+            COLLIDING,
+            RegistryAccounts {
+                registry_account: registry(&HASH, 1),
+                overflow_account: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let first: RegistryState = harness.state(registry(&HASH, 0)).await;
+    assert_eq!(first.entries.len(), MAX_REGISTRY_ENTRIES);
+    assert_eq!(first.next, Some(registry(&HASH, 1)));
+    assert_eq!(first.find(&HASH), None);
+    let overflow: RegistryState = harness.state(registry(&HASH, 1)).await;
+    assert_eq!(overflow.page, 1);
+    assert_eq!(overflow.next, None);
+    assert_eq!(overflow.find(&HASH), Some(dataset(&HASH)));
+    assert_eq!(overflow.find(&COLLIDING), Some(dataset(&COLLIDING)));
+}
+
+#[tokio::test]
+async fn overflow_pages_are_derived_from_the_page_they_follow() {
+    let (mut harness, owner) = start_with_full_page().await;
+
+    let result = harness
+        .create_listed_dataset(
+            &owner,
+            HASH,
+            RegistryAccounts {
+                registry_account: registry(&HASH, 0),
+                overflow_account: Some(registry(&HASH, 2)),
+            },
+        )
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(
+        harness
+            .state::<RegistryState>(registry(&HASH, 0))
+            .await
+            .next,
+        None
+    );
+}
+
+#[tokio::test]
+async fn datasets_created_without_a_page_are_not_listed() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    assert_eq!(harness.lamports(registry(&HASH, 0)).await, 0);
+}
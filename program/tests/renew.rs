@@ -0,0 +1,171 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::RenewAccessAccounts,
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{renew_access, Params},
+    state::{AccessState, DatasetState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+const DURATION: i64 = 3600;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+}
+
+impl Setup {
+    /// Renewal of the buyer's access paid into the vault
+    fn accounts(&self) -> RenewAccessAccounts<Pubkey> {
+        RenewAccessAccounts {
+            user_authority: self.buyer.pubkey(),
+            user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+            user_token_account: self.buyer_tokens,
+            proceeds_account: vault(&HASH, &self.mint),
+            token_mint: self.mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+        }
+    }
+}
+
+async fn renew(
+    harness: &mut Harness,
+    accounts: RenewAccessAccounts<Pubkey>,
+    signer: &Keypair,
+) -> Result<(), TransportError> {
+    let instruction = renew_access(datanexus_program::id(), accounts, HASH, VALUE).unwrap();
+    harness.process(&[instruction], &[signer]).await
+}
+
+/// A dataset with expiring access, bought once by the buyer who holds
+/// enough for a renewal
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    harness
+        .set_params(&owner, HASH, Params::AccessDuration(DURATION))
+        .await
+        .unwrap();
+
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 2 * VALUE)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            VALUE,
+        )
+        .await
+        .unwrap();
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        mint,
+        buyer_tokens,
+    }
+}
+
+#[tokio::test]
+async fn renewals_pay_into_the_vault() {
+    let mut setup = setup().await;
+    let access: AccessState = setup
+        .harness
+        .state(associated_access(&setup.buyer.pubkey(), &HASH))
+        .await;
+    let expires_at = access.datasets[0].expires_at.unwrap();
+
+    let accounts = setup.accounts();
+    renew(&mut setup.harness, accounts, &setup.buyer)
+        .await
+        .unwrap();
+
+    let vault = vault(&HASH, &setup.mint);
+    assert_eq!(setup.harness.token_balance(vault).await, 2 * VALUE);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.claimable(&setup.mint), Some(2 * VALUE));
+    assert_eq!(state.total_revenue, 2 * VALUE);
+    let access: AccessState = setup
+        .harness
+        .state(associated_access(&setup.buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].expires_at, Some(expires_at + DURATION));
+}
+
+#[tokio::test]
+async fn renewals_paying_the_owner_directly_fail() {
+    let mut setup = setup().await;
+    let owner_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &setup.owner.pubkey(), 0)
+        .await;
+
+    let mut accounts = setup.accounts();
+    accounts.proceeds_account = owner_tokens;
+    let result = renew(&mut setup.harness, accounts, &setup.buyer).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.harness.token_balance(owner_tokens).await, 0);
+}
+
+#[tokio::test]
+async fn renewals_of_another_access_account_fail() {
+    let mut setup = setup().await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let other_tokens = setup
+        .harness
+        .create_token_account(&setup.mint, &other.pubkey(), VALUE)
+        .await;
+
+    let mut accounts = setup.accounts();
+    accounts.user_authority = other.pubkey();
+    accounts.user_token_account = other_tokens;
+    let result = renew(&mut setup.harness, accounts, &other).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.harness.token_balance(other_tokens).await, VALUE);
+}
+
+#[tokio::test]
+async fn unsigned_renewals_fail() {
+    let mut setup = setup().await;
+
+    let mut instruction =
+        renew_access(datanexus_program::id(), setup.accounts(), HASH, VALUE).unwrap();
+    instruction.accounts[0].is_signer = false;
+    let result = setup.harness.process(&[instruction], &[]).await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+}
@@ -0,0 +1,141 @@
+//! Accounts written to must hold the rent-exempt minimum for their size,
+//! underfunded ones could be reaped along with the access they record
+
+mod common;
+
+use common::*;
+use datanexus::{datanexus_program, error::DataNexusError, state::AccessState};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+/// Whose associated access account is left short of rent exemption
+enum Underfunded {
+    Buyer,
+    Recipient,
+}
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    recipient: Keypair,
+    mint: Pubkey,
+}
+
+/// An empty access account of the dataset one lamport short of exempt
+fn underfunded_access(program_test: &mut ProgramTest, authority: &Pubkey) {
+    add_packed_account(
+        program_test,
+        associated_access(authority, &HASH),
+        Rent::default().minimum_balance(AccessState::LEN) - 1,
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+}
+
+/// A dataset with a vault, a buyer and a recipient with their access
+/// indexes. The associated access account of `underfunded` is written
+/// short of rent exemption, the other one is created
+async fn setup(underfunded: Underfunded) -> Setup {
+    let (buyer, recipient) = (Keypair::new(), Keypair::new());
+    let short = match underfunded {
+        Underfunded::Buyer => &buyer,
+        Underfunded::Recipient => &recipient,
+    };
+    let mut program_test = Harness::program_test();
+    underfunded_access(&mut program_test, &short.pubkey());
+    let mut harness = Harness::start_with(program_test).await;
+
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    let mint = harness.create_mint().await;
+    harness.create_vault(&owner, HASH, mint).await;
+
+    for authority in &[&buyer, &recipient] {
+        harness
+            .transfer(&authority.pubkey(), LAMPORTS_PER_SOL)
+            .await;
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        if authority.pubkey() != short.pubkey() {
+            harness
+                .create_associated_access(authority, HASH)
+                .await
+                .unwrap();
+        }
+    }
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        recipient,
+        mint,
+    }
+}
+
+impl Setup {
+    async fn purchase(&mut self) -> Result<(), TransportError> {
+        let tokens = self
+            .harness
+            .create_token_account(&self.mint, &self.buyer.pubkey(), VALUE)
+            .await;
+        self.harness
+            .purchase(
+                &self.buyer,
+                tokens,
+                &self.owner.pubkey(),
+                vault(&HASH, &self.mint),
+                self.mint,
+                HASH,
+                VALUE,
+            )
+            .await
+    }
+
+    async fn access(&mut self, authority: &Pubkey) -> AccessState {
+        self.harness
+            .state(associated_access(authority, &HASH))
+            .await
+    }
+}
+
+#[tokio::test]
+async fn purchases_into_underfunded_access_accounts_fail() {
+    let mut setup = setup(Underfunded::Buyer).await;
+
+    let result = setup.purchase().await;
+
+    assert_error(result, DataNexusError::NotRentExempt);
+    let buyer = setup.buyer.pubkey();
+    assert_eq!(setup.access(&buyer).await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn shares_into_underfunded_access_accounts_fail() {
+    let mut setup = setup(Underfunded::Recipient).await;
+    setup.purchase().await.unwrap();
+    let recipient = setup.recipient.pubkey();
+
+    let result = setup.harness.share(&setup.buyer, &recipient, HASH).await;
+
+    assert_error(result, DataNexusError::NotRentExempt);
+    assert_eq!(setup.access(&recipient).await.find_entry(&HASH), None);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::*;
+use datanexus::{error::DataNexusError, instruction::Params, state::AccessState};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// A buyer holding access to `HASH` with a share limit of 2, and two
+/// recipients with access accounts ready to receive it
+async fn setup(resharable: bool) -> (Harness, Keypair, [Keypair; 2]) {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipients = [
+        harness.fund(LAMPORTS_PER_SOL).await,
+        harness.fund(LAMPORTS_PER_SOL).await,
+    ];
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .set_params(&owner, HASH, Params::Resharable(resharable))
+        .await
+        .unwrap();
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 100)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+    for authority in [&buyer, &recipients[0], &recipients[1]].iter() {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+    harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &owner.pubkey(),
+            vault,
+            mint,
+            HASH,
+            100,
+        )
+        .await
+        .unwrap();
+
+    (harness, buyer, recipients)
+}
+
+#[tokio::test]
+async fn shared_access_is_not_resharable_by_default() {
+    let (mut harness, buyer, [first, second]) = setup(false).await;
+    harness.share(&buyer, &first.pubkey(), HASH).await.unwrap();
+
+    let result = harness.share(&first, &second.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::NotResharable);
+    let access: AccessState = harness
+        .state(associated_access(&first.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].share_quota, 0);
+    let access: AccessState = harness
+        .state(associated_access(&second.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn resharable_access_is_shared_on() {
+    let (mut harness, buyer, [first, second]) = setup(true).await;
+    harness.share(&buyer, &first.pubkey(), HASH).await.unwrap();
+
+    harness.share(&first, &second.pubkey(), HASH).await.unwrap();
+
+    let access: AccessState = harness
+        .state(associated_access(&first.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].shared_from, Some(buyer.pubkey()));
+    assert_eq!(access.datasets[0].shares_remaining(), 1);
+    let access: AccessState = harness
+        .state(associated_access(&second.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].shared_from, Some(first.pubkey()));
+    assert_eq!(access.datasets[0].shares_remaining(), 2);
+    let access: AccessState = harness
+        .state(associated_access(&buyer.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.datasets[0].shares_remaining(), 1);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    state::{AccountIndex, MAX_INDEX_ENTRIES},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+};
+
+/// An owner whose index already holds `MAX_INDEX_ENTRIES` datasets
+async fn setup() -> (Harness, Keypair) {
+    let owner = Keypair::new();
+    let mut index = AccountIndex {
+        is_initialized: true,
+        pointer: None,
+        require_share_approval: false,
+        datasets: vec![None; MAX_INDEX_ENTRIES],
+    };
+    for i in 0..MAX_INDEX_ENTRIES {
+        index.insert(Pubkey::new_unique(), [i as u8; 32]).unwrap();
+    }
+    let mut data = vec![0; AccountIndex::LEN];
+    index.pack_into_slice(&mut data);
+
+    let mut program_test = Harness::program_test();
+    program_test.add_account(
+        owner_index(&owner.pubkey()),
+        Account {
+            lamports: Rent::default().minimum_balance(AccountIndex::LEN),
+            data,
+            owner: datanexus_program::id(),
+            ..Account::default()
+        },
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    harness.transfer(&owner.pubkey(), LAMPORTS_PER_SOL).await;
+
+    (harness, owner)
+}
+
+#[tokio::test]
+async fn full_indexes_take_new_datasets_once_grown() {
+    let (mut harness, owner) = setup().await;
+    let hash = [200; 32];
+    let result = harness
+        .create_dataset_at(&owner, hash, dataset(&hash))
+        .await;
+    assert!(result.is_err());
+
+    // Distinct sizes, the bank drops a transaction identical to the last
+    for additional_slots in [120, 80] {
+        harness
+            .resize_index(&owner, owner_index(&owner.pubkey()), additional_slots)
+            .await
+            .unwrap();
+    }
+    harness
+        .create_dataset_at(&owner, hash, dataset(&hash))
+        .await
+        .unwrap();
+
+    let len = AccountIndex::LEN + 200 * AccountIndex::ENTRY_LEN;
+    let account = harness
+        .banks
+        .get_account(owner_index(&owner.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), len);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(len));
+
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert_eq!(index.capacity(), MAX_INDEX_ENTRIES + 200);
+    assert_eq!(index.position(&dataset(&hash)), Some(MAX_INDEX_ENTRIES));
+    assert_eq!(index.find_by_hash(&hash), Some(dataset(&hash)));
+}
+
+#[tokio::test]
+async fn growth_past_the_runtime_limit_fails() {
+    let (mut harness, owner) = setup().await;
+
+    let result = harness
+        .resize_index(&owner, owner_index(&owner.pubkey()), 161)
+        .await;
+
+    assert_error(result, DataNexusError::ResizeTooLarge);
+    let index: AccountIndex = harness.state(owner_index(&owner.pubkey())).await;
+    assert_eq!(index.capacity(), MAX_INDEX_ENTRIES);
+}
+
+#[tokio::test]
+async fn other_authorities_cannot_grow_an_index() {
+    let (mut harness, owner) = setup().await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+
+    let result = harness
+        .resize_index(&other, owner_index(&owner.pubkey()), 1)
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+}
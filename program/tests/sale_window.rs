@@ -0,0 +1,173 @@
+//! Datasets with a sale window sell access only from its start until its
+//! end, while the owner keeps setting params and holders keep sharing
+
+mod common;
+
+use common::*;
+use datanexus::{error::DataNexusError, instruction::Params, state::DatasetState};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+const START: i64 = 1_000_000;
+
+const END: i64 = 2_000_000;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    mint: Pubkey,
+}
+
+/// A dataset on sale from `START` until `END`, with the clock before both
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 2).await;
+    let mint = harness.create_mint().await;
+    harness.create_vault(&owner, HASH, mint).await;
+    harness
+        .set_params(
+            &owner,
+            HASH,
+            Params::SaleWindow {
+                start: START,
+                end: Some(END),
+            },
+        )
+        .await
+        .unwrap();
+    harness.set_unix_timestamp(START - 1).await;
+
+    Setup {
+        harness,
+        owner,
+        mint,
+    }
+}
+
+impl Setup {
+    /// A new buyer paying the value, returned with the result of the purchase
+    async fn purchase(&mut self) -> (Keypair, Result<(), TransportError>) {
+        let buyer = self.harness.fund(LAMPORTS_PER_SOL).await;
+        self.harness
+            .create_access_index(&buyer.pubkey())
+            .await
+            .unwrap();
+        self.harness
+            .create_associated_access(&buyer, HASH)
+            .await
+            .unwrap();
+        let tokens = self
+            .harness
+            .create_token_account(&self.mint, &buyer.pubkey(), VALUE)
+            .await;
+        let result = self
+            .harness
+            .purchase(
+                &buyer,
+                tokens,
+                &self.owner.pubkey(),
+                vault(&HASH, &self.mint),
+                self.mint,
+                HASH,
+                VALUE,
+            )
+            .await;
+        (buyer, result)
+    }
+}
+
+#[tokio::test]
+async fn purchases_are_refused_before_the_start() {
+    let mut setup = setup().await;
+
+    let (_, result) = setup.purchase().await;
+
+    assert_error(result, DataNexusError::SaleNotActive);
+}
+
+#[tokio::test]
+async fn purchases_are_accepted_from_the_start_until_the_end() {
+    let mut setup = setup().await;
+
+    setup.harness.set_unix_timestamp(START).await;
+    setup.purchase().await.1.unwrap();
+    setup.harness.set_unix_timestamp(END - 1).await;
+    setup.purchase().await.1.unwrap();
+    setup.harness.set_unix_timestamp(END).await;
+    let (_, result) = setup.purchase().await;
+
+    assert_error(result, DataNexusError::SaleNotActive);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.purchase_count, 2);
+}
+
+#[tokio::test]
+async fn params_are_set_and_access_shared_after_the_end() {
+    let mut setup = setup().await;
+    setup.harness.set_unix_timestamp(START).await;
+    let (buyer, result) = setup.purchase().await;
+    result.unwrap();
+    let recipient = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup
+        .harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    setup
+        .harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    setup.harness.set_unix_timestamp(END).await;
+
+    setup
+        .harness
+        .share(&buyer, &recipient.pubkey(), HASH)
+        .await
+        .unwrap();
+    setup
+        .harness
+        .set_params(
+            &setup.owner,
+            HASH,
+            Params::SaleWindow {
+                start: START,
+                end: None,
+            },
+        )
+        .await
+        .unwrap();
+    setup.purchase().await.1.unwrap();
+}
+
+#[tokio::test]
+async fn windows_ending_before_they_start_are_rejected() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 2).await;
+
+    for (start, end) in [(START, Some(START)), (START, Some(START - 1)), (-1, None)] {
+        let result = harness
+            .set_params(&owner, HASH, Params::SaleWindow { start, end })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TransportError::TransactionError(
+                TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+            ))
+        ));
+    }
+    let state: DatasetState = harness.state(dataset(&HASH)).await;
+    assert_eq!((state.sale_start, state.sale_end), (None, None));
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessState, DatasetState, PriceTier, MAX_PRICE_TIERS},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Price per seat below the first tier
+const VALUE: u64 = 100;
+
+fn tiers() -> Vec<PriceTier> {
+    vec![
+        PriceTier {
+            min_seats: 5,
+            unit_price: 80,
+        },
+        PriceTier {
+            min_seats: 10,
+            unit_price: 60,
+        },
+    ]
+}
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+    vault: Pubkey,
+}
+
+/// A dataset with a share limit of 1 priced by `tiers`, and a buyer with an
+/// access account ready to receive it
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+    harness
+        .set_params(&owner, HASH, Params::Tiers(tiers()))
+        .await
+        .unwrap();
+
+    harness.create_access_index(&buyer.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+    let mint = harness.create_mint().await;
+    let buyer_tokens = harness
+        .create_token_account(&mint, &buyer.pubkey(), 10 * VALUE)
+        .await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+
+    Setup {
+        harness,
+        owner,
+        buyer,
+        mint,
+        buyer_tokens,
+        vault,
+    }
+}
+
+impl Setup {
+    async fn purchase_seats(&mut self, seats: u16, amount: u64) -> Result<(), TransportError> {
+        self.harness
+            .purchase_seats(
+                &self.buyer,
+                self.buyer_tokens,
+                &self.owner.pubkey(),
+                self.vault,
+                self.mint,
+                HASH,
+                seats,
+                amount,
+            )
+            .await
+    }
+
+    async fn seats_left(&mut self) -> u16 {
+        let access: AccessState = self
+            .harness
+            .state(associated_access(&self.buyer.pubkey(), &HASH))
+            .await;
+        access.datasets[0].shares_remaining()
+    }
+}
+
+#[tokio::test]
+async fn seats_below_the_first_tier_pay_the_value() {
+    let mut setup = setup().await;
+
+    let result = setup.purchase_seats(4, 4 * VALUE - 1).await;
+    assert_error(result, DataNexusError::InsufficientPayment);
+
+    setup.purchase_seats(4, 4 * VALUE).await.unwrap();
+    assert_eq!(setup.harness.token_balance(setup.vault).await, 4 * VALUE);
+    assert_eq!(setup.seats_left().await, 4);
+}
+
+#[tokio::test]
+async fn seats_at_a_tier_pay_its_unit_price() {
+    let mut setup = setup().await;
+
+    setup.purchase_seats(5, 5 * 80).await.unwrap();
+
+    assert_eq!(setup.harness.token_balance(setup.vault).await, 400);
+    assert_eq!(setup.seats_left().await, 5);
+}
+
+#[tokio::test]
+async fn the_largest_tier_reached_applies() {
+    let mut setup = setup().await;
+
+    let result = setup.purchase_seats(10, 10 * 60 - 1).await;
+    assert_error(result, DataNexusError::InsufficientPayment);
+    assert_eq!(setup.harness.token_balance(setup.vault).await, 0);
+
+    setup.purchase_seats(10, 10 * 60).await.unwrap();
+    assert_eq!(setup.harness.token_balance(setup.vault).await, 600);
+}
+
+#[tokio::test]
+async fn seats_are_shares_beyond_the_share_limit() {
+    let mut setup = setup().await;
+    let mut recipients = vec![];
+    for _ in 0..3 {
+        let recipient = setup.harness.fund(LAMPORTS_PER_SOL).await;
+        setup
+            .harness
+            .create_access_index(&recipient.pubkey())
+            .await
+            .unwrap();
+        setup
+            .harness
+            .create_associated_access(&recipient, HASH)
+            .await
+            .unwrap();
+        recipients.push(recipient.pubkey());
+    }
+    setup.purchase_seats(2, 2 * VALUE).await.unwrap();
+
+    for recipient in recipients[..2].iter() {
+        setup
+            .harness
+            .share(&setup.buyer, recipient, HASH)
+            .await
+            .unwrap();
+    }
+    let result = setup
+        .harness
+        .share(&setup.buyer, &recipients[2], HASH)
+        .await;
+
+    assert_error(result, DataNexusError::ShareLimitExceeded);
+    assert_eq!(setup.seats_left().await, 0);
+}
+
+#[tokio::test]
+async fn zero_seats_are_rejected() {
+    let mut setup = setup().await;
+
+    let result = setup.purchase_seats(0, VALUE).await;
+
+    assert!(matches!(
+        result,
+        Err(TransportError::TransactionError(
+            TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+        ))
+    ));
+    assert_eq!(setup.harness.token_balance(setup.vault).await, 0);
+}
+
+#[tokio::test]
+async fn tiers_must_increase_in_seats() {
+    let mut setup = setup().await;
+    let mut unsorted = tiers();
+    unsorted.reverse();
+
+    let result = setup
+        .harness
+        .set_params(&setup.owner, HASH, Params::Tiers(unsorted))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidPriceTiers);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.tiers, tiers());
+}
+
+#[tokio::test]
+async fn at_most_four_tiers_are_set() {
+    let mut setup = setup().await;
+    let too_many = (1..=MAX_PRICE_TIERS as u16 + 1)
+        .map(|min_seats| PriceTier {
+            min_seats,
+            unit_price: VALUE,
+        })
+        .collect();
+
+    let result = setup
+        .harness
+        .set_params(&setup.owner, HASH, Params::Tiers(too_many))
+        .await;
+
+    assert_error(result, DataNexusError::InvalidPriceTiers);
+}
+
+#[test]
+fn tiers_round_trip_through_pack() {
+    let mut data = [0; DatasetState::LEN];
+    data[0] = DatasetState::LAYOUT_VERSION;
+    data[1] = 1;
+    data[2] = 1;
+    let mut state = DatasetState::unpack_from_slice(&data).unwrap();
+    assert!(state.tiers.is_empty());
+
+    state.tiers = tiers();
+    state.pack_into_slice(&mut data);
+
+    assert_eq!(
+        DatasetState::unpack_from_slice(&data).unwrap().tiers,
+        tiers()
+    );
+    assert_eq!(state.unit_price(4), None);
+    assert_eq!(state.unit_price(9), Some(80));
+    assert_eq!(state.unit_price(10), Some(60));
+}
+
+#[test]
+fn oversized_tier_tables_do_not_unpack() {
+    let mut data = [0; DatasetState::LEN];
+    data[0] = DatasetState::LAYOUT_VERSION;
+    data[1] = 1;
+    data[2] = 1;
+    // Tier count, behind the header and the fields before the tiers
+    data[337] = MAX_PRICE_TIERS as u8;
+    assert!(DatasetState::unpack_from_slice(&data).is_ok());
+
+    data[337] = MAX_PRICE_TIERS as u8 + 1;
+    assert!(DatasetState::unpack_from_slice(&data).is_err());
+}
@@ -0,0 +1,234 @@
+//! Shares must spend the signer's own grant and write into each recipient's
+//! own access accounts, and can't name the sharer as a recipient
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{RecipientAccounts, ShareAccessAccounts, ShareAccessMultiAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{share_access, share_access_multi},
+    state::AccessState,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+const SHARE_LIMIT: u16 = 2;
+
+struct Setup {
+    harness: Harness,
+    /// Bought access and shares it
+    buyer: Keypair,
+    /// Bought access as well, its grant must stay untouched
+    other: Keypair,
+    recipient: Keypair,
+}
+
+impl Setup {
+    /// Share of the buyer's access with the recipient
+    fn accounts(&self) -> ShareAccessAccounts<Pubkey> {
+        let recipient = self.recipient.pubkey();
+        ShareAccessAccounts {
+            user_authority: self.buyer.pubkey(),
+            user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+            recipient_authority: recipient,
+            recipient_access_index: access_index(&recipient),
+            recipient_access_account: associated_access(&recipient, &HASH),
+            dataset_account: dataset(&HASH),
+        }
+    }
+
+    fn recipient_accounts(&self, authority: &Pubkey) -> RecipientAccounts<Pubkey> {
+        RecipientAccounts {
+            authority: *authority,
+            access_index: access_index(authority),
+            access_account: associated_access(authority, &HASH),
+        }
+    }
+
+    async fn share(&mut self, accounts: ShareAccessAccounts<Pubkey>) -> Result<(), TransportError> {
+        let instruction =
+            share_access(datanexus_program::id(), accounts, HASH, None, None).unwrap();
+        self.harness.process(&[instruction], &[&self.buyer]).await
+    }
+
+    async fn share_multi(
+        &mut self,
+        recipients: Vec<RecipientAccounts<Pubkey>>,
+    ) -> Result<(), TransportError> {
+        let instruction = share_access_multi(
+            datanexus_program::id(),
+            ShareAccessMultiAccounts {
+                user_authority: self.buyer.pubkey(),
+                user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+                dataset_account: dataset(&HASH),
+                recipients,
+            },
+            HASH,
+        )
+        .unwrap();
+        self.harness.process(&[instruction], &[&self.buyer]).await
+    }
+
+    async fn access(&mut self, authority: &Pubkey) -> AccessState {
+        self.harness
+            .state(associated_access(authority, &HASH))
+            .await
+    }
+
+    /// A user with access accounts but no access of the dataset
+    async fn bystander(&mut self) -> Pubkey {
+        let bystander = self.harness.fund(LAMPORTS_PER_SOL).await;
+        self.harness
+            .create_access_index(&bystander.pubkey())
+            .await
+            .unwrap();
+        self.harness
+            .create_associated_access(&bystander, HASH)
+            .await
+            .unwrap();
+        bystander.pubkey()
+    }
+
+    async fn shares_remaining(&mut self, authority: &Pubkey) -> u16 {
+        let access = self.access(authority).await;
+        access.datasets[0].shares_remaining()
+    }
+}
+
+/// A dataset bought by the buyer and the other user, and a recipient with
+/// its access accounts
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .register_dataset(&owner, HASH, VALUE, SHARE_LIMIT)
+        .await;
+    let mint = harness.create_mint().await;
+    let vault = harness.create_vault(&owner, HASH, mint).await;
+
+    let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+    let other = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    for authority in &[&buyer, &other, &recipient] {
+        harness
+            .create_access_index(&authority.pubkey())
+            .await
+            .unwrap();
+        harness
+            .create_associated_access(authority, HASH)
+            .await
+            .unwrap();
+    }
+    for authority in &[&buyer, &other] {
+        let tokens = harness
+            .create_token_account(&mint, &authority.pubkey(), VALUE)
+            .await;
+        harness
+            .purchase(authority, tokens, &owner.pubkey(), vault, mint, HASH, VALUE)
+            .await
+            .unwrap();
+    }
+
+    Setup {
+        harness,
+        buyer,
+        other,
+        recipient,
+    }
+}
+
+#[tokio::test]
+async fn shares_from_another_access_account_fail() {
+    let mut setup = setup().await;
+
+    let mut accounts = setup.accounts();
+    accounts.user_access_account = associated_access(&setup.other.pubkey(), &HASH);
+    let result = setup.share(accounts).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(
+        setup.shares_remaining(&setup.other.pubkey()).await,
+        SHARE_LIMIT
+    );
+}
+
+#[tokio::test]
+async fn shares_into_another_access_account_fail() {
+    let mut setup = setup().await;
+    let bystander = setup.bystander().await;
+
+    let mut accounts = setup.accounts();
+    accounts.recipient_access_account = associated_access(&bystander, &HASH);
+    let result = setup.share(accounts).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.access(&bystander).await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn shares_into_another_access_index_fail() {
+    let mut setup = setup().await;
+
+    let mut accounts = setup.accounts();
+    accounts.recipient_access_index = access_index(&setup.other.pubkey());
+    let result = setup.share(accounts).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    let recipient = setup.recipient.pubkey();
+    assert_eq!(setup.access(&recipient).await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn shares_with_the_sharer_fail() {
+    let mut setup = setup().await;
+    let buyer = setup.buyer.pubkey();
+
+    let mut accounts = setup.accounts();
+    accounts.recipient_authority = buyer;
+    accounts.recipient_access_index = access_index(&buyer);
+    accounts.recipient_access_account = associated_access(&buyer, &HASH);
+    let result = setup.share(accounts).await;
+
+    assert_error(result, DataNexusError::CannotShareToSelf);
+    assert_eq!(setup.shares_remaining(&buyer).await, SHARE_LIMIT);
+}
+
+#[tokio::test]
+async fn multi_shares_with_the_sharer_fail() {
+    let mut setup = setup().await;
+    let buyer = setup.buyer.pubkey();
+    let recipient = setup.recipient.pubkey();
+
+    let recipients = vec![
+        setup.recipient_accounts(&recipient),
+        setup.recipient_accounts(&buyer),
+    ];
+    let result = setup.share_multi(recipients).await;
+
+    assert_error(result, DataNexusError::CannotShareToSelf);
+    assert_eq!(setup.shares_remaining(&buyer).await, SHARE_LIMIT);
+    assert_eq!(setup.access(&recipient).await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn multi_shares_into_another_access_account_fail() {
+    let mut setup = setup().await;
+    let bystander = setup.bystander().await;
+    let recipient = setup.recipient.pubkey();
+
+    let mut accounts = setup.recipient_accounts(&recipient);
+    accounts.access_account = associated_access(&bystander, &HASH);
+    let result = setup.share_multi(vec![accounts]).await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.access(&bystander).await.find_entry(&HASH), None);
+}
@@ -0,0 +1,265 @@
+//! Recipients requiring share approval hold shares as pending until they
+//! accept them, and pending shares the recipient lets lapse go back to
+//! their sharer
+
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    state::{AccessInfo, AccessState, AccountIndex, DatasetState, PENDING_SHARE_TTL},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// When shares in the tests are made
+const SHARED_AT: i64 = 1_000;
+
+struct Setup {
+    harness: Harness,
+    sharer: Keypair,
+    recipient: Keypair,
+}
+
+/// A sharer granted two shares of a dataset, and a recipient requiring
+/// approval of shares to it
+async fn setup() -> Setup {
+    let sharer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&sharer.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: None,
+                shared_from: None,
+                share_quota: 2,
+                expires_at: None,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness.set_unix_timestamp(SHARED_AT).await;
+
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    harness
+        .set_access_preferences(&recipient, true)
+        .await
+        .unwrap();
+
+    Setup {
+        harness,
+        sharer,
+        recipient,
+    }
+}
+
+impl Setup {
+    async fn share(&mut self) {
+        self.harness
+            .share(&self.sharer, &self.recipient.pubkey(), HASH)
+            .await
+            .unwrap();
+    }
+
+    async fn access(&mut self, authority: Pubkey) -> AccessState {
+        self.harness
+            .state(associated_access(&authority, &HASH))
+            .await
+    }
+
+    async fn shares_used(&mut self) -> u16 {
+        self.access(self.sharer.pubkey()).await.datasets[0].shares_used
+    }
+
+    async fn recipient_access(&mut self) -> AccessState {
+        self.access(self.recipient.pubkey()).await
+    }
+}
+
+#[tokio::test]
+async fn shares_wait_for_approval() {
+    let mut setup = setup().await;
+
+    setup.share().await;
+
+    let access = setup.recipient_access().await;
+    assert!(access.datasets.is_empty());
+    assert_eq!(access.pending.len(), 1);
+    assert_eq!(access.pending[0].sharer(), Some(setup.sharer.pubkey()));
+    assert_eq!(access.pending[0].shared_at, SHARED_AT);
+    // The share is taken out of the quota while pending
+    assert_eq!(setup.shares_used().await, 1);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.share_count, 0);
+    let index: AccountIndex = setup
+        .harness
+        .state(access_index(&setup.recipient.pubkey()))
+        .await;
+    assert!(index.require_share_approval);
+}
+
+#[tokio::test]
+async fn accepted_shares_are_granted() {
+    let mut setup = setup().await;
+    setup.share().await;
+
+    setup
+        .harness
+        .accept_share(&setup.recipient, &setup.sharer.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    let access = setup.recipient_access().await;
+    assert!(access.pending.is_empty());
+    assert_eq!(access.datasets.len(), 1);
+    assert_eq!(access.datasets[0].shared_from, Some(setup.sharer.pubkey()));
+    assert_eq!(setup.shares_used().await, 1);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.share_count, 1);
+}
+
+#[tokio::test]
+async fn rejected_shares_return_to_the_sharers_quota() {
+    let mut setup = setup().await;
+    setup.share().await;
+
+    setup
+        .harness
+        .reject_share(
+            &setup.recipient,
+            &setup.recipient.pubkey(),
+            &setup.sharer.pubkey(),
+            HASH,
+        )
+        .await
+        .unwrap();
+
+    assert!(setup.recipient_access().await.pending.is_empty());
+    assert_eq!(setup.shares_used().await, 0);
+    let result = setup
+        .harness
+        .accept_share(&setup.recipient, &setup.sharer.pubkey(), HASH)
+        .await;
+    assert_error(result, DataNexusError::NoPendingShare);
+}
+
+#[tokio::test]
+async fn sharers_reclaim_shares_once_lapsed() {
+    let mut setup = setup().await;
+    setup.share().await;
+
+    let result = setup
+        .harness
+        .reject_share(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            &setup.sharer.pubkey(),
+            HASH,
+        )
+        .await;
+    assert_error(result, DataNexusError::AccessNotExpired);
+
+    setup
+        .harness
+        .set_unix_timestamp(SHARED_AT + PENDING_SHARE_TTL)
+        .await;
+    let result = setup
+        .harness
+        .accept_share(&setup.recipient, &setup.sharer.pubkey(), HASH)
+        .await;
+    assert_error(result, DataNexusError::AccessExpired);
+
+    setup
+        .harness
+        .reject_share(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            &setup.sharer.pubkey(),
+            HASH,
+        )
+        .await
+        .unwrap();
+    assert!(setup.recipient_access().await.pending.is_empty());
+    assert_eq!(setup.shares_used().await, 0);
+}
+
+#[tokio::test]
+async fn only_the_recipient_or_sharer_may_reject() {
+    let mut setup = setup().await;
+    setup.share().await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+
+    let result = setup
+        .harness
+        .reject_share(
+            &other,
+            &setup.recipient.pubkey(),
+            &setup.sharer.pubkey(),
+            HASH,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::MissingSigner);
+    assert_eq!(setup.recipient_access().await.pending.len(), 1);
+}
+
+#[tokio::test]
+async fn a_sharer_holds_one_pending_share_per_recipient() {
+    let mut setup = setup().await;
+    setup.share().await;
+
+    // Expiring, so the transaction differs from the first share's
+    let result = setup
+        .harness
+        .share_expiring(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            HASH,
+            Some(60),
+            None,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::AccessAlreadyGranted);
+    assert_eq!(setup.shares_used().await, 1);
+}
+
+#[tokio::test]
+async fn shares_are_granted_once_approval_is_no_longer_required() {
+    let mut setup = setup().await;
+    setup
+        .harness
+        .set_access_preferences(&setup.recipient, false)
+        .await
+        .unwrap();
+
+    setup.share().await;
+
+    let access = setup.recipient_access().await;
+    assert!(access.pending.is_empty());
+    assert_eq!(access.datasets.len(), 1);
+}
@@ -0,0 +1,156 @@
+//! Shares may expire before the sharer's own access, the recipient's grant
+//! expiring `expires_in` seconds from the share or with the sharer's grant,
+//! whichever comes first
+
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    state::{AccessInfo, AccessState},
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const NOW: i64 = 1_000_000;
+
+const HOUR: i64 = 3_600;
+
+/// A sharer holding a grant of `HASH` until `expires_at` with shares left,
+/// and a recipient with an access account ready to receive it, at `NOW`
+async fn setup(expires_at: Option<i64>) -> (Harness, Keypair, Keypair) {
+    let sharer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&sharer.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: None,
+                shared_from: None,
+                share_quota: 2,
+                expires_at,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    harness.set_unix_timestamp(NOW).await;
+
+    (harness, sharer, recipient)
+}
+
+async fn recipient_expiry(harness: &mut Harness, recipient: &Pubkey) -> Option<i64> {
+    let access: AccessState = harness.state(associated_access(recipient, &HASH)).await;
+    access.datasets[0].expires_at
+}
+
+#[tokio::test]
+async fn shares_expire_after_expires_in() {
+    let (mut harness, sharer, recipient) = setup(None).await;
+
+    harness
+        .share_expiring(&sharer, &recipient.pubkey(), HASH, Some(HOUR), None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recipient_expiry(&mut harness, &recipient.pubkey()).await,
+        Some(NOW + HOUR)
+    );
+}
+
+#[tokio::test]
+async fn shares_expire_no_later_than_the_sharer() {
+    let (mut harness, sharer, recipient) = setup(Some(NOW + HOUR)).await;
+
+    harness
+        .share_expiring(&sharer, &recipient.pubkey(), HASH, Some(2 * HOUR), None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recipient_expiry(&mut harness, &recipient.pubkey()).await,
+        Some(NOW + HOUR)
+    );
+}
+
+#[tokio::test]
+async fn shares_may_expire_before_the_sharer() {
+    let (mut harness, sharer, recipient) = setup(Some(NOW + 2 * HOUR)).await;
+
+    harness
+        .share_expiring(&sharer, &recipient.pubkey(), HASH, Some(HOUR), None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recipient_expiry(&mut harness, &recipient.pubkey()).await,
+        Some(NOW + HOUR)
+    );
+}
+
+#[tokio::test]
+async fn shares_without_expires_in_expire_with_the_sharer() {
+    let (mut harness, sharer, recipient) = setup(Some(NOW + HOUR)).await;
+
+    harness
+        .share(&sharer, &recipient.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        recipient_expiry(&mut harness, &recipient.pubkey()).await,
+        Some(NOW + HOUR)
+    );
+}
+
+#[tokio::test]
+async fn zero_and_negative_durations_are_rejected() {
+    let (mut harness, sharer, recipient) = setup(None).await;
+
+    for expires_in in [0, -1, i64::MIN].iter() {
+        let result = harness
+            .share_expiring(&sharer, &recipient.pubkey(), HASH, Some(*expires_in), None)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TransportError::TransactionError(
+                TransactionError::InstructionError(_, InstructionError::InvalidArgument)
+            ))
+        ));
+    }
+    let access: AccessState = harness
+        .state(associated_access(&recipient.pubkey(), &HASH))
+        .await;
+    assert_eq!(access.find_entry(&HASH), None);
+}
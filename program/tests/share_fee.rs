@@ -0,0 +1,285 @@
+//! Owners may charge a fee for each share, paid by the sharer in the
+//! owner's mint of choice before the recipient is granted, and forfeited
+//! if the recipient rejects the share
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{RecipientAccounts, ShareAccessMultiAccounts, ShareFeeAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{share_access_multi, Params},
+    state::{AccessInfo, AccessState, DatasetState, MintPrice},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, rent::Rent};
+use solana_sdk::{
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const FEE: u64 = 25;
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    sharer: Keypair,
+    recipient: Keypair,
+}
+
+/// A dataset, a sharer holding access to it with a share limit of 2 and a
+/// recipient ready to receive it
+async fn setup() -> Setup {
+    let sharer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&sharer.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: Some([7; 32]),
+                shared_from: None,
+                share_quota: 2,
+                expires_at: None,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+
+    Setup {
+        harness,
+        owner,
+        sharer,
+        recipient,
+    }
+}
+
+impl Setup {
+    async fn set_share_fee(&mut self, mint: Pubkey, amount: u64) {
+        self.harness
+            .set_params(&self.owner, HASH, Params::ShareFee { mint, amount })
+            .await
+            .unwrap();
+    }
+
+    async fn recipient_access(&mut self) -> AccessState {
+        self.harness
+            .state(associated_access(&self.recipient.pubkey(), &HASH))
+            .await
+    }
+}
+
+#[tokio::test]
+async fn sharing_without_a_fee_needs_no_token_accounts() {
+    let mut setup = setup().await;
+
+    setup
+        .harness
+        .share(&setup.sharer, &setup.recipient.pubkey(), HASH)
+        .await
+        .unwrap();
+
+    assert!(setup.recipient_access().await.find_entry(&HASH).is_some());
+}
+
+#[tokio::test]
+async fn sharing_with_a_fee_requires_the_fee_accounts() {
+    let mut setup = setup().await;
+    setup.set_share_fee(Pubkey::new_unique(), FEE).await;
+
+    let result = setup
+        .harness
+        .share(&setup.sharer, &setup.recipient.pubkey(), HASH)
+        .await;
+
+    assert_error(result, DataNexusError::ShareFeeRequired);
+    assert_eq!(setup.recipient_access().await.find_entry(&HASH), None);
+}
+
+#[tokio::test]
+async fn the_fee_is_paid_in_its_own_mint() {
+    let mut setup = setup().await;
+    setup.set_share_fee(Pubkey::new_unique(), FEE).await;
+
+    let result = setup
+        .harness
+        .share_with_fee(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            HASH,
+            Some(ShareFeeAccounts {
+                sharer_token_account: Pubkey::new_unique(),
+                owner_token_account: Pubkey::new_unique(),
+                token_mint: Pubkey::new_unique(),
+                token_program: spl_token::id(),
+            }),
+        )
+        .await;
+
+    assert_error(result, DataNexusError::UnsupportedMint);
+}
+
+#[tokio::test]
+async fn datasets_with_a_fee_are_not_shared_in_bulk() {
+    let mut setup = setup().await;
+    setup.set_share_fee(Pubkey::new_unique(), FEE).await;
+    let recipient = setup.recipient.pubkey();
+
+    let instruction = share_access_multi(
+        datanexus_program::id(),
+        ShareAccessMultiAccounts {
+            user_authority: setup.sharer.pubkey(),
+            user_access_account: associated_access(&setup.sharer.pubkey(), &HASH),
+            dataset_account: dataset(&HASH),
+            recipients: vec![RecipientAccounts {
+                authority: recipient,
+                access_index: access_index(&recipient),
+                access_account: associated_access(&recipient, &HASH),
+            }],
+        },
+        HASH,
+    )
+    .unwrap();
+    let result = setup
+        .harness
+        .process(&[instruction], &[&setup.sharer])
+        .await;
+
+    assert_error(result, DataNexusError::ShareFeeRequired);
+}
+
+#[tokio::test]
+async fn a_zero_fee_makes_sharing_free_again() {
+    let mut setup = setup().await;
+    let mint = Pubkey::new_unique();
+    setup.set_share_fee(mint, FEE).await;
+    assert_eq!(
+        setup
+            .harness
+            .state::<DatasetState>(dataset(&HASH))
+            .await
+            .share_fee,
+        Some(MintPrice { mint, amount: FEE })
+    );
+
+    setup.set_share_fee(mint, 0).await;
+
+    setup
+        .harness
+        .share(&setup.sharer, &setup.recipient.pubkey(), HASH)
+        .await
+        .unwrap();
+    assert!(setup.recipient_access().await.find_entry(&HASH).is_some());
+}
+
+#[tokio::test]
+async fn the_fee_is_paid_to_the_owner_before_granting() {
+    let mut setup = setup().await;
+    let mint = setup.harness.create_mint().await;
+    let sharer_tokens = setup
+        .harness
+        .create_token_account(&mint, &setup.sharer.pubkey(), 100)
+        .await;
+    let owner_tokens = setup
+        .harness
+        .create_token_account(&mint, &setup.owner.pubkey(), 0)
+        .await;
+    setup.set_share_fee(mint, FEE).await;
+
+    setup
+        .harness
+        .share_with_fee(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            HASH,
+            Some(ShareFeeAccounts {
+                sharer_token_account: sharer_tokens,
+                owner_token_account: owner_tokens,
+                token_mint: mint,
+                token_program: spl_token::id(),
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(setup.harness.token_balance(sharer_tokens).await, 100 - FEE);
+    assert_eq!(setup.harness.token_balance(owner_tokens).await, FEE);
+    assert!(setup.recipient_access().await.find_entry(&HASH).is_some());
+}
+
+#[tokio::test]
+async fn rejected_shares_forfeit_the_fee() {
+    let mut setup = setup().await;
+    let mint = setup.harness.create_mint().await;
+    let sharer_tokens = setup
+        .harness
+        .create_token_account(&mint, &setup.sharer.pubkey(), 100)
+        .await;
+    let owner_tokens = setup
+        .harness
+        .create_token_account(&mint, &setup.owner.pubkey(), 0)
+        .await;
+    setup.set_share_fee(mint, FEE).await;
+    setup
+        .harness
+        .set_access_preferences(&setup.recipient, true)
+        .await
+        .unwrap();
+
+    setup
+        .harness
+        .share_with_fee(
+            &setup.sharer,
+            &setup.recipient.pubkey(),
+            HASH,
+            Some(ShareFeeAccounts {
+                sharer_token_account: sharer_tokens,
+                owner_token_account: owner_tokens,
+                token_mint: mint,
+                token_program: spl_token::id(),
+            }),
+        )
+        .await
+        .unwrap();
+    setup
+        .harness
+        .reject_share(
+            &setup.recipient,
+            &setup.recipient.pubkey(),
+            &setup.sharer.pubkey(),
+            HASH,
+        )
+        .await
+        .unwrap();
+
+    // The owner keeps the fee, only the share goes back to the sharer
+    assert_eq!(setup.harness.token_balance(sharer_tokens).await, 100 - FEE);
+    assert_eq!(setup.harness.token_balance(owner_tokens).await, FEE);
+    let sharer_access: AccessState = setup
+        .harness
+        .state(associated_access(&setup.sharer.pubkey(), &HASH))
+        .await;
+    assert_eq!(sharer_access.datasets[0].shares_used, 0);
+    assert!(setup.recipient_access().await.pending.is_empty());
+}
@@ -0,0 +1,107 @@
+//! Grants keep the share quota they were granted with and count the shares
+//! made from it, whatever the dataset's share limit becomes
+
+mod common;
+
+use common::*;
+use datanexus::{
+    datanexus_program,
+    error::DataNexusError,
+    instruction::Params,
+    state::{AccessInfo, AccessState},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, rent::Rent};
+use solana_sdk::signature::{Keypair, Signer};
+
+const HASH: [u8; 32] = [1; 32];
+
+const QUOTA: u16 = 2;
+
+/// A sharer granted `QUOTA` shares of a dataset with a share limit of
+/// `QUOTA`, and an owner able to change that limit
+async fn setup() -> (Harness, Keypair, Keypair) {
+    let sharer = Keypair::new();
+    let mut program_test = Harness::program_test();
+    add_packed_account(
+        &mut program_test,
+        associated_access(&sharer.pubkey(), &HASH),
+        Rent::default().minimum_balance(AccessState::LEN),
+        &AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: vec![AccessInfo {
+                hash: HASH,
+                key: None,
+                shared_from: None,
+                share_quota: QUOTA,
+                expires_at: None,
+                depth: 0,
+                shares_used: 0,
+            }],
+            pending: vec![],
+        },
+        &datanexus_program::id(),
+    );
+    let mut harness = Harness::start_with(program_test).await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, QUOTA).await;
+
+    (harness, owner, sharer)
+}
+
+/// A recipient with an access account ready to receive `HASH`
+async fn recipient(harness: &mut Harness) -> Keypair {
+    let recipient = harness.fund(LAMPORTS_PER_SOL).await;
+    harness
+        .create_access_index(&recipient.pubkey())
+        .await
+        .unwrap();
+    harness
+        .create_associated_access(&recipient, HASH)
+        .await
+        .unwrap();
+    recipient
+}
+
+async fn grant(harness: &mut Harness, authority: &Keypair) -> AccessInfo {
+    let access: AccessState = harness
+        .state(associated_access(&authority.pubkey(), &HASH))
+        .await;
+    access.datasets[0]
+}
+
+#[tokio::test]
+async fn shares_are_counted_against_the_quota() {
+    let (mut harness, _, sharer) = setup().await;
+    let first = recipient(&mut harness).await;
+
+    harness.share(&sharer, &first.pubkey(), HASH).await.unwrap();
+
+    let grant = grant(&mut harness, &sharer).await;
+    assert_eq!((grant.share_quota, grant.shares_used), (QUOTA, 1));
+    assert_eq!(grant.shares_remaining(), QUOTA - 1);
+}
+
+#[tokio::test]
+async fn quotas_are_fixed_when_granted() {
+    let (mut harness, owner, sharer) = setup().await;
+    harness
+        .set_params(&owner, HASH, Params::ShareLimit(QUOTA + 3))
+        .await
+        .unwrap();
+
+    for _ in 0..QUOTA {
+        let recipient = recipient(&mut harness).await;
+        harness
+            .share(&sharer, &recipient.pubkey(), HASH)
+            .await
+            .unwrap();
+    }
+    let last = recipient(&mut harness).await;
+    let result = harness.share(&sharer, &last.pubkey(), HASH).await;
+
+    assert_error(result, DataNexusError::ShareLimitExceeded);
+    let grant = grant(&mut harness, &sharer).await;
+    assert_eq!((grant.share_quota, grant.shares_used), (QUOTA, QUOTA));
+    assert_eq!(grant.shares_remaining(), 0);
+}
@@ -0,0 +1,210 @@
+//! Every instruction rejects program-owned accounts too small to hold the
+//! state it reads, rather than panicking on them
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{RecipientAccounts, RenewAccessAccounts, ShareAccessMultiAccounts},
+    datanexus_program,
+    instruction::{renew_access, share_access_multi, Params},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, rent::Rent};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Bytes held by every seeded account, short of any state's layout
+const SHORT_LEN: usize = 3;
+
+fn add_short_account(program_test: &mut ProgramTest, address: Pubkey) {
+    program_test.add_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(SHORT_LEN),
+            data: vec![1; SHORT_LEN],
+            owner: datanexus_program::id(),
+            ..Account::default()
+        },
+    );
+}
+
+/// Starts a bank where every account of `owner` and `buyer` for `HASH` is
+/// program-owned but `SHORT_LEN` bytes long
+async fn start(owner: &Keypair, buyer: &Keypair) -> Harness {
+    let mut program_test = Harness::program_test();
+    for address in [
+        owner_index(&owner.pubkey()),
+        access_index(&buyer.pubkey()),
+        dataset(&HASH),
+        associated_access(&owner.pubkey(), &HASH),
+        associated_access(&buyer.pubkey(), &HASH),
+        collection(&HASH),
+        collection_access(&buyer.pubkey(), &HASH),
+    ] {
+        add_short_account(&mut program_test, address);
+    }
+
+    let mut harness = Harness::start_with(program_test).await;
+    harness.transfer(&owner.pubkey(), LAMPORTS_PER_SOL).await;
+    harness.transfer(&buyer.pubkey(), LAMPORTS_PER_SOL).await;
+    harness
+}
+
+/// Fails unless `result` is an error returned by the instruction, a panic
+/// fails the transaction differently
+fn assert_rejected(result: Result<(), TransportError>) {
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(_, error))) => {
+            assert_ne!(error, InstructionError::ProgramFailedToComplete)
+        }
+        other => panic!("expected the instruction to fail, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn short_accounts_are_rejected_by_every_instruction() {
+    let (owner, buyer) = (Keypair::new(), Keypair::new());
+    let mut harness = start(&owner, &buyer).await;
+    // Nothing gets as far as a token transfer, so no token accounts exist
+    let (mint, buyer_tokens, owner_tokens) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    assert_rejected(harness.create_owner_index(&owner.pubkey()).await);
+    assert_rejected(harness.create_access_index(&buyer.pubkey()).await);
+    assert_rejected(
+        harness
+            .create_dataset_at(&owner, HASH, dataset(&HASH))
+            .await,
+    );
+    assert_rejected(harness.set_params(&owner, HASH, Params::Value(100)).await);
+    assert_rejected(
+        harness
+            .resize_index(&owner, owner_index(&owner.pubkey()), 1)
+            .await,
+    );
+    assert_rejected(
+        harness
+            .compact_index(&owner, owner_index(&owner.pubkey()), true, 0)
+            .await,
+    );
+    assert_rejected(harness.migrate(dataset(&HASH)).await);
+    assert_rejected(harness.migrate(access_index(&buyer.pubkey())).await);
+    assert_rejected(harness.create_associated_access(&buyer, HASH).await);
+    assert_rejected(harness.init_vault(&owner, HASH, mint).await);
+    assert_rejected(harness.withdraw(&owner, HASH, mint, owner_tokens, 1).await);
+    assert_rejected(harness.close_dataset(&owner, HASH, true).await);
+    assert_rejected(
+        harness
+            .propose_ownership_transfer(&owner, HASH, buyer.pubkey())
+            .await,
+    );
+    assert_rejected(
+        harness
+            .accept_ownership(&buyer, &owner.pubkey(), HASH)
+            .await,
+    );
+    assert_rejected(harness.cancel_ownership_transfer(&owner, HASH).await);
+    assert_rejected(harness.revoke(&owner, &buyer.pubkey(), HASH, None).await);
+    assert_rejected(harness.crank_prune(&owner, &buyer.pubkey(), HASH).await);
+    assert_rejected(harness.fund_bounty(&owner, HASH, 1).await);
+    assert_rejected(
+        harness
+            .purchase(
+                &buyer,
+                buyer_tokens,
+                &owner.pubkey(),
+                owner_tokens,
+                mint,
+                HASH,
+                100,
+            )
+            .await,
+    );
+    assert_rejected(
+        harness
+            .purchase_seats(
+                &buyer,
+                buyer_tokens,
+                &owner.pubkey(),
+                owner_tokens,
+                mint,
+                HASH,
+                2,
+                200,
+            )
+            .await,
+    );
+    assert_rejected(harness.share(&owner, &buyer.pubkey(), HASH).await);
+    assert_rejected(harness.set_access_preferences(&buyer, true).await);
+    assert_rejected(harness.accept_share(&buyer, &owner.pubkey(), HASH).await);
+    assert_rejected(
+        harness
+            .reject_share(&buyer, &buyer.pubkey(), &owner.pubkey(), HASH)
+            .await,
+    );
+
+    let renew = renew_access(
+        datanexus_program::id(),
+        RenewAccessAccounts {
+            user_authority: buyer.pubkey(),
+            user_access_account: associated_access(&buyer.pubkey(), &HASH),
+            user_token_account: buyer_tokens,
+            proceeds_account: owner_tokens,
+            token_mint: mint,
+            dataset_account: dataset(&HASH),
+            token_program: spl_token::id(),
+            referrer_token_account: None,
+        },
+        HASH,
+        100,
+    )
+    .unwrap();
+    assert_rejected(harness.process(&[renew], &[&buyer]).await);
+
+    let share_multi = share_access_multi(
+        datanexus_program::id(),
+        ShareAccessMultiAccounts {
+            user_authority: owner.pubkey(),
+            user_access_account: associated_access(&owner.pubkey(), &HASH),
+            dataset_account: dataset(&HASH),
+            recipients: vec![RecipientAccounts {
+                authority: buyer.pubkey(),
+                access_index: access_index(&buyer.pubkey()),
+                access_account: associated_access(&buyer.pubkey(), &HASH),
+            }],
+        },
+        HASH,
+    )
+    .unwrap();
+    assert_rejected(harness.process(&[share_multi], &[&owner]).await);
+
+    assert_rejected(harness.create_collection(&owner, HASH, 100).await);
+    assert_rejected(harness.add_to_collection(&owner, HASH, HASH).await);
+    assert_rejected(harness.create_collection_access(&buyer, HASH).await);
+    assert_rejected(
+        harness
+            .purchase_collection(
+                &buyer,
+                buyer_tokens,
+                &owner.pubkey(),
+                owner_tokens,
+                mint,
+                HASH,
+                100,
+                &[HASH],
+            )
+            .await,
+    );
+    assert_rejected(harness.claim_collection(&buyer, HASH, &[HASH]).await);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::*;
+use datanexus::state::{
+    AccessInfo, AccessState, AccountIndex, DatasetState, MAX_ACCESS_ENTRIES, MAX_INDEX_ENTRIES,
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::signature::Signer;
+
+const HASH: [u8; 32] = [1; 32];
+
+#[test]
+fn space_matches_the_packed_lengths() {
+    assert_eq!(
+        AccountIndex::space(MAX_INDEX_ENTRIES),
+        Ok(AccountIndex::LEN)
+    );
+    assert_eq!(
+        AccountIndex::space(MAX_INDEX_ENTRIES + 3),
+        Ok(AccountIndex::LEN + 3 * AccountIndex::ENTRY_LEN)
+    );
+    assert!(AccountIndex::space(usize::MAX).is_err());
+    assert_eq!(DatasetState::space(), DatasetState::LEN);
+    assert_eq!(AccessState::space(MAX_ACCESS_ENTRIES), Ok(AccessState::LEN));
+    assert_eq!(
+        AccessState::space(MAX_ACCESS_ENTRIES - 3),
+        Ok(AccessState::LEN - 3 * AccessInfo::LEN)
+    );
+    assert!(AccessState::space(MAX_ACCESS_ENTRIES + 1).is_err());
+}
+
+#[tokio::test]
+async fn created_accounts_take_up_their_space() {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 1).await;
+    harness.create_access_index(&owner.pubkey()).await.unwrap();
+    harness
+        .create_associated_access(&owner, HASH)
+        .await
+        .unwrap();
+
+    let index_space = AccountIndex::space(MAX_INDEX_ENTRIES).unwrap();
+    for (address, space) in [
+        (owner_index(&owner.pubkey()), index_space),
+        (access_index(&owner.pubkey()), index_space),
+        (dataset(&HASH), DatasetState::space()),
+        (
+            associated_access(&owner.pubkey(), &HASH),
+            AccessState::space(MAX_ACCESS_ENTRIES).unwrap(),
+        ),
+    ]
+    .iter()
+    {
+        assert_eq!(account_len(&mut harness, *address).await, *space);
+    }
+}
+
+async fn account_len(harness: &mut Harness, address: Pubkey) -> usize {
+    harness
+        .banks
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+        .len()
+}
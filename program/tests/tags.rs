@@ -0,0 +1,121 @@
+//! Datasets labelled with up to `MAX_TAGS` distinct tags, packed into the
+//! first slots at `DatasetState::TAGS_OFFSET`
+
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    instruction::Params,
+    state::{pack_tag, DatasetState, Tag, MAX_TAGS, TAG_LEN},
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::{signature::Keypair, transport::TransportError};
+
+const HASH: [u8; 32] = [1; 32];
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+}
+
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, 100, 2).await;
+
+    Setup { harness, owner }
+}
+
+impl Setup {
+    async fn add_tag(&mut self, tag: Tag) -> Result<(), TransportError> {
+        self.harness
+            .set_params(&self.owner, HASH, Params::AddTag(tag))
+            .await
+    }
+
+    async fn remove_tag(&mut self, tag: Tag) -> Result<(), TransportError> {
+        self.harness
+            .set_params(&self.owner, HASH, Params::RemoveTag(tag))
+            .await
+    }
+
+    async fn tag_names(&mut self) -> Vec<String> {
+        let state: DatasetState = self.harness.state(dataset(&HASH)).await;
+        state.tag_names().into_iter().map(String::from).collect()
+    }
+
+    /// Raw tag slots of the packed dataset
+    async fn tag_slots(&mut self) -> Vec<u8> {
+        let account = self
+            .harness
+            .banks
+            .get_account(dataset(&HASH))
+            .await
+            .unwrap()
+            .unwrap();
+        account.data[DatasetState::TAGS_OFFSET..].to_vec()
+    }
+}
+
+#[tokio::test]
+async fn tags_are_added_and_removed_in_order() {
+    let mut setup = setup().await;
+    let (finance, weekly) = (pack_tag("finance").unwrap(), pack_tag("weekly").unwrap());
+
+    setup.add_tag(finance).await.unwrap();
+    setup.add_tag(weekly).await.unwrap();
+    assert_eq!(setup.tag_names().await, ["finance", "weekly"]);
+
+    setup.remove_tag(finance).await.unwrap();
+    assert_eq!(setup.tag_names().await, ["weekly"]);
+    // The remaining tag moved up to the first slot
+    let slots = setup.tag_slots().await;
+    assert_eq!(slots[..TAG_LEN], weekly);
+    assert!(slots[TAG_LEN..].iter().all(|b| *b == 0));
+
+    // The bank drops a transaction identical to an earlier one, so change
+    // payers
+    setup.harness.payer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let result = setup.remove_tag(finance).await;
+    assert_error(result, DataNexusError::TagNotFound);
+}
+
+#[tokio::test]
+async fn duplicate_tags_are_refused() {
+    let mut setup = setup().await;
+    let finance = pack_tag("finance").unwrap();
+    setup.add_tag(finance).await.unwrap();
+    setup.harness.payer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+
+    let result = setup.add_tag(finance).await;
+
+    assert_error(result, DataNexusError::DuplicateTag);
+    assert_eq!(setup.tag_names().await, ["finance"]);
+}
+
+#[tokio::test]
+async fn a_dataset_holds_at_most_eight_tags() {
+    let mut setup = setup().await;
+    for byte in 1..=MAX_TAGS as u8 {
+        setup.add_tag([byte; TAG_LEN]).await.unwrap();
+    }
+
+    let result = setup.add_tag(pack_tag("finance").unwrap()).await;
+
+    assert_error(result, DataNexusError::TagsFull);
+    // Removing one frees a slot
+    setup.remove_tag([1; TAG_LEN]).await.unwrap();
+    setup.harness.payer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup.add_tag(pack_tag("finance").unwrap()).await.unwrap();
+    assert_eq!(setup.tag_names().await.last().unwrap(), "finance");
+}
+
+#[tokio::test]
+async fn empty_tags_are_refused() {
+    let mut setup = setup().await;
+
+    let result = setup.add_tag([0; TAG_LEN]).await;
+
+    assert_error(result, DataNexusError::InvalidTag);
+}
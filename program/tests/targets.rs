@@ -0,0 +1,43 @@
+//! The crate builds for the host as a client library with `no-entrypoint`
+//! or `exclude_entrypoint`, and for BPF with the entrypoint
+//!
+//! Each build runs a nested cargo into its own target directory, so the
+//! first run compiles the dependencies once more. The BPF build only runs
+//! under `cargo test-bpf`, which provides the toolchain.
+
+use std::{path::Path, process::Command};
+
+/// Runs cargo with `args` on this crate's manifest, building into
+/// `target/<target_dir>` of the workspace
+fn cargo(args: &[&str], target_dir: &str) -> bool {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    Command::new(env!("CARGO"))
+        .args(args)
+        .arg("--manifest-path")
+        .arg(crate_dir.join("Cargo.toml"))
+        .env(
+            "CARGO_TARGET_DIR",
+            crate_dir.join("../target").join(target_dir),
+        )
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[test]
+fn builds_for_the_host_without_the_entrypoint() {
+    // Both features share a target directory, only the crate is checked
+    // again for each
+    for features in ["no-entrypoint", "exclude_entrypoint"] {
+        assert!(
+            cargo(&["check", "--lib", "--features", features], "no-entrypoint"),
+            "{}",
+            features
+        );
+    }
+}
+
+#[cfg(feature = "test-bpf")]
+#[test]
+fn builds_for_bpf_with_the_entrypoint() {
+    assert!(cargo(&["build-bpf"], "bpf"));
+}
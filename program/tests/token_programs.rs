@@ -0,0 +1,332 @@
+//! Purchases paid and withdrawn through spl-token, spl-token-2022 and a
+//! spl-token-2022 mint charging a transfer fee
+
+mod common;
+
+use common::*;
+use datanexus::{
+    accounts::{InitVaultAccounts, PurchaseAccessAccounts, WithdrawProceedsAccounts},
+    datanexus_program,
+    error::DataNexusError,
+    instruction::{init_vault, purchase_access, withdraw_proceeds},
+    state::DatasetState,
+};
+use solana_program::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+        StateWithExtensions,
+    },
+    state::{Account, Mint},
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 10_000;
+
+/// Transfer fee of the fee charging mint, 1% of every transfer
+const FEE_BPS: u16 = 100;
+
+/// What the vault keeps of `VALUE` once the fee is withheld
+const VALUE_AFTER_FEE: u64 = VALUE - VALUE / 100;
+
+/// Mints the tests pay with
+#[derive(Clone, Copy)]
+enum Token {
+    Spl,
+    Spl2022,
+    Spl2022TransferFee,
+}
+
+impl Token {
+    fn program_id(self) -> Pubkey {
+        match self {
+            Token::Spl => spl_token::id(),
+            Token::Spl2022 | Token::Spl2022TransferFee => spl_token_2022::id(),
+        }
+    }
+
+    fn mint_extensions(self) -> Vec<ExtensionType> {
+        match self {
+            Token::Spl | Token::Spl2022 => vec![],
+            Token::Spl2022TransferFee => vec![ExtensionType::TransferFeeConfig],
+        }
+    }
+}
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    buyer: Keypair,
+    token: Token,
+    mint: Pubkey,
+    buyer_tokens: Pubkey,
+    owner_tokens: Pubkey,
+}
+
+impl Setup {
+    /// A dataset worth `VALUE` of a new mint of `token`, with its vault
+    /// opened and a buyer holding `VALUE`
+    async fn start(token: Token) -> Self {
+        let mut harness = Harness::start().await;
+        let owner = harness.fund(LAMPORTS_PER_SOL).await;
+        harness.register_dataset(&owner, HASH, VALUE, 1).await;
+
+        let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+        harness.create_access_index(&buyer.pubkey()).await.unwrap();
+        harness
+            .create_associated_access(&buyer, HASH)
+            .await
+            .unwrap();
+
+        let mint = create_mint(&mut harness, token).await;
+        let buyer_tokens = create_token_account(&mut harness, token, &mint, &buyer.pubkey()).await;
+        let owner_tokens = create_token_account(&mut harness, token, &mint, &owner.pubkey()).await;
+        let instruction = spl_token_2022::instruction::mint_to(
+            &token.program_id(),
+            &mint,
+            &buyer_tokens,
+            &harness.payer.pubkey(),
+            &[],
+            VALUE,
+        )
+        .unwrap();
+        harness.process(&[instruction], &[]).await.unwrap();
+
+        let instruction = init_vault(
+            datanexus_program::id(),
+            InitVaultAccounts {
+                payer: harness.payer.pubkey(),
+                owner_authority: owner.pubkey(),
+                dataset_account: dataset(&HASH),
+                token_mint: mint,
+                vault_account: vault(&HASH, &mint),
+                token_program: token.program_id(),
+                system_program: system_program::id(),
+            },
+            HASH,
+        )
+        .unwrap();
+        harness.process(&[instruction], &[&owner]).await.unwrap();
+
+        Setup {
+            harness,
+            owner,
+            buyer,
+            token,
+            mint,
+            buyer_tokens,
+            owner_tokens,
+        }
+    }
+
+    async fn purchase(&mut self) -> Result<(), TransportError> {
+        let instruction = purchase_access(
+            datanexus_program::id(),
+            PurchaseAccessAccounts {
+                user_authority: self.buyer.pubkey(),
+                user_access_index: access_index(&self.buyer.pubkey()),
+                user_access_account: associated_access(&self.buyer.pubkey(), &HASH),
+                user_token_account: self.buyer_tokens,
+                owner_authority: self.owner.pubkey(),
+                proceeds_account: vault(&HASH, &self.mint),
+                token_mint: self.mint,
+                dataset_account: dataset(&HASH),
+                token_program: self.token.program_id(),
+                referrer_token_account: None,
+                receipt: None,
+                reference_access: None,
+            },
+            HASH,
+            VALUE,
+        )
+        .unwrap();
+        self.harness.process(&[instruction], &[&self.buyer]).await
+    }
+
+    async fn withdraw(&mut self, amount: u64) -> Result<(), TransportError> {
+        let instruction = withdraw_proceeds(
+            datanexus_program::id(),
+            WithdrawProceedsAccounts {
+                owner_authority: self.owner.pubkey(),
+                dataset_account: dataset(&HASH),
+                vault_account: vault(&HASH, &self.mint),
+                token_mint: self.mint,
+                destination_token_account: self.owner_tokens,
+                token_program: self.token.program_id(),
+            },
+            HASH,
+            amount,
+            &[],
+        )
+        .unwrap();
+        self.harness.process(&[instruction], &[&self.owner]).await
+    }
+
+    /// Tokens held by `account`, which may carry extensions
+    async fn balance(&mut self, account: Pubkey) -> u64 {
+        let account = self
+            .harness
+            .banks
+            .get_account(account)
+            .await
+            .unwrap()
+            .unwrap();
+        StateWithExtensions::<Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    async fn claimable(&mut self) -> Option<u64> {
+        let state: DatasetState = self.harness.state(dataset(&HASH)).await;
+        state.claimable(&self.mint)
+    }
+}
+
+/// Creates a mint of `token` whose authority is the payer
+async fn create_mint(harness: &mut Harness, token: Token) -> Pubkey {
+    let mint = Keypair::new();
+    let payer = harness.payer.pubkey();
+    let space = ExtensionType::get_account_len::<Mint>(&token.mint_extensions());
+
+    let mut instructions = vec![system_instruction::create_account(
+        &payer,
+        &mint.pubkey(),
+        Rent::default().minimum_balance(space),
+        space as u64,
+        &token.program_id(),
+    )];
+    if let Token::Spl2022TransferFee = token {
+        instructions.push(
+            initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                Some(&payer),
+                Some(&payer),
+                FEE_BPS,
+                u64::MAX,
+            )
+            .unwrap(),
+        );
+    }
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint(
+            &token.program_id(),
+            &mint.pubkey(),
+            &payer,
+            None,
+            DECIMALS,
+        )
+        .unwrap(),
+    );
+    harness.process(&instructions, &[&mint]).await.unwrap();
+
+    mint.pubkey()
+}
+
+/// Creates an empty token account of `mint` owned by `owner`
+async fn create_token_account(
+    harness: &mut Harness,
+    token: Token,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let space = ExtensionType::get_account_len::<Account>(
+        &ExtensionType::get_required_init_account_extensions(&token.mint_extensions()),
+    );
+
+    let instructions = [
+        system_instruction::create_account(
+            &harness.payer.pubkey(),
+            &account.pubkey(),
+            Rent::default().minimum_balance(space),
+            space as u64,
+            &token.program_id(),
+        ),
+        spl_token_2022::instruction::initialize_account3(
+            &token.program_id(),
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    harness.process(&instructions, &[&account]).await.unwrap();
+
+    account.pubkey()
+}
+
+#[tokio::test]
+async fn spl_token_purchases_fill_the_vault() {
+    let mut setup = Setup::start(Token::Spl).await;
+
+    setup.purchase().await.unwrap();
+
+    assert_eq!(setup.balance(vault(&HASH, &setup.mint)).await, VALUE);
+    assert_eq!(setup.claimable().await, Some(VALUE));
+
+    setup.withdraw(VALUE).await.unwrap();
+    assert_eq!(setup.balance(setup.owner_tokens).await, VALUE);
+    assert_eq!(setup.claimable().await, Some(0));
+}
+
+#[tokio::test]
+async fn spl_token_2022_purchases_fill_the_vault() {
+    let mut setup = Setup::start(Token::Spl2022).await;
+
+    setup.purchase().await.unwrap();
+
+    assert_eq!(setup.balance(vault(&HASH, &setup.mint)).await, VALUE);
+    assert_eq!(setup.claimable().await, Some(VALUE));
+
+    setup.withdraw(VALUE).await.unwrap();
+    assert_eq!(setup.balance(setup.owner_tokens).await, VALUE);
+    assert_eq!(setup.claimable().await, Some(0));
+}
+
+#[tokio::test]
+async fn transfer_fees_credit_what_the_vault_receives() {
+    let mut setup = Setup::start(Token::Spl2022TransferFee).await;
+
+    setup.purchase().await.unwrap();
+
+    // The fee is withheld in the vault, which credits only what it received
+    assert_eq!(
+        setup.balance(vault(&HASH, &setup.mint)).await,
+        VALUE_AFTER_FEE
+    );
+    assert_eq!(setup.claimable().await, Some(VALUE_AFTER_FEE));
+
+    let result = setup.withdraw(VALUE).await;
+    assert_error(result, DataNexusError::InsufficientProceeds);
+
+    setup.withdraw(VALUE_AFTER_FEE).await.unwrap();
+    assert_eq!(setup.balance(vault(&HASH, &setup.mint)).await, 0);
+    assert_eq!(setup.claimable().await, Some(0));
+}
+
+#[tokio::test]
+async fn transfer_fee_datasets_close_once_withdrawn() {
+    let mut setup = Setup::start(Token::Spl2022TransferFee).await;
+
+    setup.purchase().await.unwrap();
+    setup.withdraw(VALUE_AFTER_FEE).await.unwrap();
+
+    let Setup { harness, owner, .. } = &mut setup;
+    harness.close_dataset(owner, HASH, true).await.unwrap();
+    let account = harness
+        .banks
+        .get_account(dataset(&HASH))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data, vec![0; DatasetState::CLOSED_LEN]);
+}
@@ -0,0 +1,336 @@
+mod common;
+
+use common::*;
+use datanexus::{
+    error::DataNexusError,
+    state::{DatasetState, MAX_VAULTS},
+};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transport::TransportError,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const VALUE: u64 = 100;
+
+/// A mint the dataset has sold access for, with the vault holding its
+/// proceeds and a token account of the owner to withdraw them to
+struct Mint {
+    mint: Pubkey,
+    vault: Pubkey,
+    owner_tokens: Pubkey,
+}
+
+struct Setup {
+    harness: Harness,
+    owner: Keypair,
+    mints: [Mint; 2],
+}
+
+/// A dataset bought once with each of two mints, so each vault holds and
+/// has `VALUE` claimable
+async fn setup() -> Setup {
+    let mut harness = Harness::start().await;
+    let owner = harness.fund(LAMPORTS_PER_SOL).await;
+    harness.register_dataset(&owner, HASH, VALUE, 1).await;
+
+    let mut mints = vec![];
+    for _ in 0..2 {
+        let buyer = harness.fund(LAMPORTS_PER_SOL).await;
+        harness.create_access_index(&buyer.pubkey()).await.unwrap();
+        harness
+            .create_associated_access(&buyer, HASH)
+            .await
+            .unwrap();
+
+        let mint = harness.create_mint().await;
+        let buyer_tokens = harness
+            .create_token_account(&mint, &buyer.pubkey(), VALUE)
+            .await;
+        let owner_tokens = harness
+            .create_token_account(&mint, &owner.pubkey(), 0)
+            .await;
+        let vault = harness.create_vault(&owner, HASH, mint).await;
+        harness
+            .purchase(
+                &buyer,
+                buyer_tokens,
+                &owner.pubkey(),
+                vault,
+                mint,
+                HASH,
+                VALUE,
+            )
+            .await
+            .unwrap();
+
+        mints.push(Mint {
+            mint,
+            vault,
+            owner_tokens,
+        });
+    }
+
+    let mut mints = mints.into_iter();
+    Setup {
+        harness,
+        owner,
+        mints: [mints.next().unwrap(), mints.next().unwrap()],
+    }
+}
+
+impl Setup {
+    /// Withdraws `amount` from the vault of the `index`th mint
+    async fn withdraw(&mut self, index: usize, amount: u64) -> Result<(), TransportError> {
+        let Mint {
+            mint, owner_tokens, ..
+        } = self.mints[index];
+        self.harness
+            .withdraw(&self.owner, HASH, mint, owner_tokens, amount)
+            .await
+    }
+
+    /// Claimable proceeds of the `index`th mint
+    async fn claimable(&mut self, index: usize) -> u64 {
+        let state: DatasetState = self.harness.state(dataset(&HASH)).await;
+        state.claimable(&self.mints[index].mint).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn each_mint_is_paid_into_its_own_vault() {
+    let mut setup = setup().await;
+
+    for i in 0..2 {
+        let vault = setup.mints[i].vault;
+        assert_eq!(setup.harness.token_balance(vault).await, VALUE);
+        let owner_tokens = setup.mints[i].owner_tokens;
+        assert_eq!(setup.harness.token_balance(owner_tokens).await, 0);
+    }
+    assert_ne!(setup.mints[0].vault, setup.mints[1].vault);
+    assert_eq!(setup.claimable(0).await, VALUE);
+    assert_eq!(setup.claimable(1).await, VALUE);
+}
+
+#[tokio::test]
+async fn the_owner_withdraws_from_each_vault() {
+    let mut setup = setup().await;
+
+    setup.withdraw(0, 40).await.unwrap();
+    assert_eq!(setup.harness.token_balance(setup.mints[0].vault).await, 60);
+    assert_eq!(
+        setup
+            .harness
+            .token_balance(setup.mints[0].owner_tokens)
+            .await,
+        40
+    );
+    assert_eq!(setup.claimable(0).await, VALUE - 40);
+    assert_eq!(setup.claimable(1).await, VALUE);
+
+    setup.withdraw(1, VALUE).await.unwrap();
+    setup.withdraw(0, 60).await.unwrap();
+    assert_eq!(
+        setup
+            .harness
+            .token_balance(setup.mints[1].owner_tokens)
+            .await,
+        VALUE
+    );
+    assert_eq!(setup.claimable(0).await, 0);
+    assert_eq!(setup.claimable(1).await, 0);
+}
+
+#[tokio::test]
+async fn withdrawals_are_bounded_by_the_proceeds_of_the_mint() {
+    let mut setup = setup().await;
+
+    // Within what both mints took together, but the first only took `VALUE`
+    let result = setup.withdraw(0, VALUE + 1).await;
+
+    assert_error(result, DataNexusError::InsufficientProceeds);
+    assert_eq!(setup.claimable(0).await, VALUE);
+    assert_eq!(setup.claimable(1).await, VALUE);
+}
+
+#[tokio::test]
+async fn withdrawals_are_bounded_by_the_claimable_balance() {
+    let mut setup = setup().await;
+    setup.withdraw(0, VALUE).await.unwrap();
+    setup.withdraw(1, VALUE).await.unwrap();
+
+    // Tokens sent straight to a vault are not proceeds of a purchase
+    let Mint { mint, vault, .. } = setup.mints[0];
+    let donor = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let donor_tokens = setup
+        .harness
+        .create_token_account(&mint, &donor.pubkey(), VALUE)
+        .await;
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &donor_tokens,
+        &vault,
+        &donor.pubkey(),
+        &[],
+        VALUE,
+    )
+    .unwrap();
+    setup.harness.process(&[transfer], &[&donor]).await.unwrap();
+
+    let result = setup.withdraw(0, 1).await;
+
+    assert_error(result, DataNexusError::InsufficientProceeds);
+    assert_eq!(setup.harness.token_balance(vault).await, VALUE);
+}
+
+#[tokio::test]
+async fn only_the_owner_withdraws() {
+    let mut setup = setup().await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let Mint { mint, .. } = setup.mints[0];
+    let other_tokens = setup
+        .harness
+        .create_token_account(&mint, &other.pubkey(), 0)
+        .await;
+
+    let result = setup
+        .harness
+        .withdraw(&other, HASH, mint, other_tokens, 1)
+        .await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    assert_eq!(setup.claimable(0).await, VALUE);
+}
+
+#[tokio::test]
+async fn purchases_pay_into_the_vault_only() {
+    let mut setup = setup().await;
+    let buyer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup
+        .harness
+        .create_access_index(&buyer.pubkey())
+        .await
+        .unwrap();
+    setup
+        .harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+    let Mint {
+        mint, owner_tokens, ..
+    } = setup.mints[0];
+    let buyer_tokens = setup
+        .harness
+        .create_token_account(&mint, &buyer.pubkey(), VALUE)
+        .await;
+
+    let result = setup
+        .harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &setup.owner.pubkey(),
+            owner_tokens,
+            mint,
+            HASH,
+            VALUE,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::InvalidSeeds);
+    assert_eq!(setup.harness.token_balance(owner_tokens).await, 0);
+}
+
+#[tokio::test]
+async fn only_the_owner_opens_vaults() {
+    let mut setup = setup().await;
+    let other = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    let mint = setup.harness.create_mint().await;
+
+    let result = setup.harness.init_vault(&other, HASH, mint).await;
+
+    assert_error(result, DataNexusError::IncorrectOwner);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.claimable(&mint), None);
+}
+
+#[tokio::test]
+async fn purchases_in_a_mint_without_a_vault_fail() {
+    let mut setup = setup().await;
+    let buyer = setup.harness.fund(LAMPORTS_PER_SOL).await;
+    setup
+        .harness
+        .create_access_index(&buyer.pubkey())
+        .await
+        .unwrap();
+    setup
+        .harness
+        .create_associated_access(&buyer, HASH)
+        .await
+        .unwrap();
+    let mint = setup.harness.create_mint().await;
+    let buyer_tokens = setup
+        .harness
+        .create_token_account(&mint, &buyer.pubkey(), VALUE)
+        .await;
+
+    let result = setup
+        .harness
+        .purchase(
+            &buyer,
+            buyer_tokens,
+            &setup.owner.pubkey(),
+            vault(&HASH, &mint),
+            mint,
+            HASH,
+            VALUE,
+        )
+        .await;
+
+    assert_error(result, DataNexusError::AccountNotInitialized);
+    assert_eq!(setup.harness.token_balance(buyer_tokens).await, VALUE);
+}
+
+#[tokio::test]
+async fn datasets_hold_a_limited_number_of_vaults() {
+    let mut setup = setup().await;
+    for _ in 2..MAX_VAULTS {
+        let mint = setup.harness.create_mint().await;
+        setup.harness.create_vault(&setup.owner, HASH, mint).await;
+    }
+    let mint = setup.harness.create_mint().await;
+
+    let result = setup.harness.init_vault(&setup.owner, HASH, mint).await;
+
+    assert_error(result, DataNexusError::VaultLimitReached);
+    let state: DatasetState = setup.harness.state(dataset(&HASH)).await;
+    assert_eq!(state.claimable.len(), MAX_VAULTS);
+}
+
+#[tokio::test]
+async fn datasets_close_once_every_vault_is_withdrawn() {
+    let mut setup = setup().await;
+    setup.withdraw(0, VALUE).await.unwrap();
+
+    let result = setup.harness.close_dataset(&setup.owner, HASH, true).await;
+
+    assert_error(result, DataNexusError::UnclaimedProceeds);
+    assert_eq!(setup.claimable(1).await, VALUE);
+
+    setup.withdraw(1, VALUE).await.unwrap();
+    setup
+        .harness
+        .close_dataset(&setup.owner, HASH, true)
+        .await
+        .unwrap();
+    let account = setup
+        .harness
+        .banks
+        .get_account(dataset(&HASH))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), DatasetState::CLOSED_LEN);
+}
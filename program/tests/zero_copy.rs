@@ -0,0 +1,367 @@
+#![cfg(feature = "zero-copy")]
+
+use datanexus::{
+    state::{
+        AccessInfo, AccessState, AccountFlag, Claimable, DatasetState, MintPrice, PriceTier,
+        CONTENT_TYPE_LEN, MAX_ACCESS_ENTRIES, MAX_PRICES, MAX_PRICE_TIERS, MAX_TAGS, TAG_LEN,
+        URI_LEN,
+    },
+    zero_copy::{AccessInfoZC, AccessStateZC, ClaimableZC, DatasetStateZC, PriceTierZC},
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+fn dataset() -> DatasetState {
+    DatasetState {
+        is_initialized: true,
+        flag: AccountFlag::Dataset,
+        owner: Pubkey::new_unique(),
+        hash: [1; 32],
+        key: Some([2; 32]),
+        value: Some(u64::MAX - 1),
+        share_limit: Some(3),
+        purchase_count: 4,
+        referral_bps: 500,
+        access_duration: Some(-6),
+        uri: [7; URI_LEN],
+        size_bytes: 8,
+        content_type: [9; CONTENT_TYPE_LEN],
+        total_revenue: 10,
+        share_count: 11,
+        ref_data: Some(Pubkey::new_unique()),
+        resharable: true,
+        max_share_depth: Some(12),
+        tiers: vec![PriceTier {
+            min_seats: 13,
+            unit_price: 14,
+        }],
+        claimable: vec![Claimable {
+            mint: Pubkey::new_unique(),
+            amount: 15,
+        }],
+        holder_count: 16,
+        max_holders: Some(17),
+        pending_owner: Some(Pubkey::new_unique()),
+        prices: vec![
+            MintPrice {
+                mint: Pubkey::new_unique(),
+                amount: 18,
+            },
+            MintPrice {
+                mint: Pubkey::new_unique(),
+                amount: 19,
+            },
+        ],
+        sale_start: Some(20),
+        sale_end: Some(21),
+        prune_bounty: 22,
+        bounty_pool: 23,
+        share_fee: Some(MintPrice {
+            mint: Pubkey::new_unique(),
+            amount: 24,
+        }),
+        tags: vec![*b"finance\0\0\0\0\0\0\0\0\0", [25; TAG_LEN]],
+        derived_discount_bps: 2_600,
+        receipt_count: 28,
+    }
+}
+
+fn access(hash: u8, shared_from: Option<Pubkey>, expires_at: Option<i64>) -> AccessInfo {
+    AccessInfo {
+        hash: [hash; 32],
+        key: Some([hash + 1; 32]),
+        shared_from,
+        share_quota: hash as u16,
+        expires_at,
+        depth: shared_from.is_some() as u8,
+        shares_used: hash as u16 / 2,
+    }
+}
+
+/// A full tier table
+fn tiers() -> Vec<PriceTier> {
+    (1..=MAX_PRICE_TIERS as u16)
+        .map(|i| PriceTier {
+            min_seats: i * 10,
+            unit_price: 100 - u64::from(i),
+        })
+        .collect()
+}
+
+fn packed<T: Pack>(state: &T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    data
+}
+
+#[test]
+fn views_have_the_packed_size() {
+    assert_eq!(std::mem::size_of::<DatasetStateZC>(), DatasetState::LEN);
+    assert_eq!(std::mem::size_of::<AccessInfoZC>(), AccessInfo::LEN);
+    assert_eq!(std::mem::size_of::<AccessStateZC>(), AccessState::LEN);
+}
+
+#[test]
+fn dataset_view_reads_packed_fields() {
+    let state = dataset();
+    let data = packed(&state);
+    let view = DatasetState::load(&data).unwrap();
+
+    assert!(view.is_initialized());
+    assert_eq!(view.flag, 1);
+    assert_eq!(view.owner(), state.owner);
+    assert_eq!(view.hash, state.hash);
+    assert_eq!(view.key(), state.key);
+    assert_eq!(view.value(), state.value);
+    assert_eq!(view.share_limit(), state.share_limit);
+    assert_eq!(view.purchase_count.get(), state.purchase_count);
+    assert_eq!(view.referral_bps.get(), state.referral_bps);
+    assert_eq!(view.access_duration(), state.access_duration);
+    assert_eq!(view.uri, state.uri);
+    assert_eq!(view.size_bytes.get(), state.size_bytes);
+    assert_eq!(view.content_type, state.content_type);
+    assert_eq!(view.total_revenue.get(), state.total_revenue);
+    assert_eq!(view.share_count.get(), state.share_count);
+    assert_eq!(view.ref_data(), state.ref_data);
+    assert_eq!(view.resharable(), state.resharable);
+    assert_eq!(view.max_share_depth(), state.max_share_depth);
+    let tiers: Vec<PriceTierZC> = state.tiers.iter().map(|tier| (*tier).into()).collect();
+    assert_eq!(view.tiers(), &tiers[..]);
+    assert_eq!(view.claimable().len(), state.claimable.len());
+    for (view, claimable) in view.claimable().iter().zip(&state.claimable) {
+        assert_eq!(view.mint(), claimable.mint);
+        assert_eq!(view.amount.get(), claimable.amount);
+    }
+    assert_eq!(view.holder_count.get(), state.holder_count);
+    assert_eq!(view.max_holders(), state.max_holders);
+    assert_eq!(view.pending_owner(), state.pending_owner);
+    assert_eq!(view.prices().len(), state.prices.len());
+    for price in &state.prices {
+        assert_eq!(view.price(&price.mint), Some(price.amount));
+    }
+    assert_eq!(view.sale_start(), state.sale_start);
+    assert_eq!(view.sale_end(), state.sale_end);
+    assert_eq!(view.prune_bounty.get(), state.prune_bounty);
+    assert_eq!(view.bounty_pool.get(), state.bounty_pool);
+    assert_eq!(view.share_fee(), state.share_fee);
+    assert_eq!(view.tags(), state.tags.as_slice());
+    assert_eq!(view.derived_discount_bps.get(), state.derived_discount_bps);
+    assert_eq!(view.receipt_count.get(), state.receipt_count);
+}
+
+#[test]
+fn dataset_view_writes_match_pack() {
+    let mut state = dataset();
+    let mut data = packed(&state);
+
+    {
+        let view = DatasetState::load_mut(&mut data).unwrap();
+        view.set_key(None);
+        view.set_value(Some(12));
+        view.set_share_limit(None);
+        view.set_access_duration(None);
+        view.total_revenue.set(u64::MAX);
+        view.share_count.set(13);
+        view.set_ref_data(None);
+        view.set_resharable(false);
+        view.set_max_share_depth(None);
+        view.set_tiers(&tiers()).unwrap();
+        view.claimable_mut()[0].amount.set(16);
+        view.claimable[1] = ClaimableZC {
+            mint: [17; 32],
+            amount: 18.into(),
+        };
+        view.vault_count = 2;
+        view.holder_count.set(19);
+        view.set_max_holders(None);
+        view.sale_start.set(0);
+        view.set_sale_end(Some(22));
+        view.prune_bounty.set(24);
+        view.set_share_fee(None);
+        view.remove_tag(&[25; TAG_LEN]).unwrap();
+        view.add_tag([26; TAG_LEN]).unwrap();
+        view.derived_discount_bps.set(27);
+        view.receipt_count.set(29);
+    }
+    state.key = None;
+    state.value = Some(12);
+    state.share_limit = None;
+    state.access_duration = None;
+    state.total_revenue = u64::MAX;
+    state.share_count = 13;
+    state.ref_data = None;
+    state.resharable = false;
+    state.max_share_depth = None;
+    state.tiers = tiers();
+    state.claimable[0].amount = 16;
+    state.claimable.push(Claimable {
+        mint: Pubkey::new_from_array([17; 32]),
+        amount: 18,
+    });
+    state.holder_count = 19;
+    state.max_holders = None;
+    state.sale_start = None;
+    state.sale_end = Some(22);
+    state.prune_bounty = 24;
+    state.share_fee = None;
+    state.remove_tag(&[25; TAG_LEN]).unwrap();
+    state.add_tag([26; TAG_LEN]).unwrap();
+    state.derived_discount_bps = 27;
+    state.receipt_count = 29;
+
+    assert_eq!(data, packed(&state));
+    assert_eq!(
+        packed(&DatasetState::unpack_from_slice(&data).unwrap()),
+        data
+    );
+}
+
+#[test]
+fn dataset_view_rejects_too_many_tiers() {
+    let state = dataset();
+    let mut data = packed(&state);
+    let view = DatasetState::load_mut(&mut data).unwrap();
+
+    let mut too_many = tiers();
+    too_many.push(PriceTier {
+        min_seats: 100,
+        unit_price: 1,
+    });
+    assert!(view.set_tiers(&too_many).is_err());
+    assert_eq!(data, packed(&state));
+}
+
+#[test]
+fn dataset_view_price_table_matches_pack() {
+    let mut state = dataset();
+    let mut data = packed(&state);
+    let (first, second) = (state.prices[0].mint, state.prices[1].mint);
+    let added: Vec<_> = (0..MAX_PRICES - 2).map(|_| Pubkey::new_unique()).collect();
+
+    {
+        let view = DatasetState::load_mut(&mut data).unwrap();
+        view.set_price(second, 20).unwrap();
+        for mint in &added {
+            view.set_price(*mint, 21).unwrap();
+        }
+        view.remove_price(&first).unwrap();
+        assert!(view.remove_price(&first).is_err());
+    }
+    state.set_price(second, 20).unwrap();
+    for mint in &added {
+        state.set_price(*mint, 21).unwrap();
+    }
+    state.remove_price(&first).unwrap();
+
+    assert_eq!(data, packed(&state));
+    assert_eq!(
+        DatasetState::unpack_from_slice(&data).unwrap().prices,
+        state.prices
+    );
+}
+
+#[test]
+fn dataset_view_rejects_a_price_past_the_table() {
+    let mut state = dataset();
+    while state.prices.len() < MAX_PRICES {
+        state.set_price(Pubkey::new_unique(), 1).unwrap();
+    }
+    let mut data = packed(&state);
+    let view = DatasetState::load_mut(&mut data).unwrap();
+
+    assert!(view.set_price(Pubkey::new_unique(), 1).is_err());
+    assert_eq!(data, packed(&state));
+}
+
+#[test]
+fn dataset_view_rejects_a_tag_past_the_slots() {
+    let mut state = dataset();
+    while state.tags.len() < MAX_TAGS {
+        state
+            .add_tag([state.tags.len() as u8 + 100; TAG_LEN])
+            .unwrap();
+    }
+    let mut data = packed(&state);
+    let view = DatasetState::load_mut(&mut data).unwrap();
+
+    assert!(view.add_tag([0xff; TAG_LEN]).is_err());
+    assert!(view.add_tag(state.tags[0]).is_err());
+    assert_eq!(data, packed(&state));
+}
+
+#[test]
+fn access_view_reads_packed_entries() {
+    let sharer = Pubkey::new_unique();
+    let state = AccessState {
+        is_initialized: true,
+        pointer: None,
+        datasets: vec![access(1, None, None), access(2, Some(sharer), Some(100))],
+        pending: vec![],
+    };
+    let data = packed(&state);
+    let view = AccessState::load(&data).unwrap();
+
+    assert!(view.is_initialized());
+    assert_eq!(view.entries().len(), 2);
+    for (entry, access) in view.entries().iter().zip(&state.datasets) {
+        assert_eq!(entry.hash, access.hash);
+        assert_eq!(entry.key(), access.key);
+        assert_eq!(entry.shared_from(), access.shared_from);
+        assert_eq!(entry.share_quota.get(), access.share_quota);
+        assert_eq!(entry.expires_at(), access.expires_at);
+        assert_eq!(entry.depth, access.depth);
+        assert_eq!(entry.shares_used.get(), access.shares_used);
+        assert_eq!(entry.shares_remaining(), access.shares_remaining());
+    }
+    assert_eq!(view.find_entry(&[2; 32]), state.find_entry(&[2; 32]));
+    assert_eq!(view.find_entry(&[3; 32]), None);
+}
+
+#[test]
+fn access_view_push_matches_pack() {
+    let mut state = AccessState {
+        is_initialized: true,
+        pointer: Some(Pubkey::new_unique()),
+        datasets: vec![access(1, None, Some(5))],
+        pending: vec![],
+    };
+    let mut data = packed(&state);
+
+    let pushed = access(2, Some(Pubkey::new_unique()), None);
+    let view = AccessState::load_mut(&mut data).unwrap();
+    let mut entry = view.entries()[0];
+    entry.hash = pushed.hash;
+    entry.key = pushed.key.unwrap();
+    entry.shared_from = pushed.shared_from.unwrap().to_bytes();
+    entry.share_quota.set(pushed.share_quota);
+    entry.expires_at.set(0);
+    entry.depth = pushed.depth;
+    entry.shares_used.set(pushed.shares_used);
+    assert_eq!(view.push(entry), Ok(1));
+    state.datasets.push(pushed);
+
+    assert_eq!(data, packed(&state));
+}
+
+#[test]
+fn access_view_push_fails_when_full() {
+    let state = AccessState {
+        is_initialized: true,
+        pointer: None,
+        datasets: (0..MAX_ACCESS_ENTRIES as u8)
+            .map(|hash| access(hash, None, None))
+            .collect(),
+        pending: vec![],
+    };
+    let mut data = packed(&state);
+    let view = AccessState::load_mut(&mut data).unwrap();
+    let entry = view.entries()[0];
+
+    assert!(view.push(entry).is_err());
+    assert_eq!(data, packed(&state));
+}
+
+#[test]
+fn short_accounts_do_not_load() {
+    assert!(DatasetState::load(&[0; DatasetState::LEN - 1]).is_err());
+    assert!(AccessState::load(&[0; AccessState::LEN - 1]).is_err());
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+
+use crate::client::{AccountFetcher, FetchError};
+
+#[derive(Debug, PartialEq)]
+pub enum AmountError {
+    /// Not a plain decimal number, signs and exponents are rejected
+    Invalid(String),
+    /// More fractional digits than the mint's decimals
+    TooPrecise { amount: String, decimals: u8 },
+    /// Larger than a `u64` of base units
+    Overflow(String),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmountError::Invalid(amount) => write!(f, "{} is not a decimal amount", amount),
+            AmountError::TooPrecise { amount, decimals } => write!(
+                f,
+                "{} has more than the {} decimal places the mint supports",
+                amount, decimals
+            ),
+            AmountError::Overflow(amount) => write!(f, "{} is too large", amount),
+        }
+    }
+}
+
+/// Converts a decimal `amount` of whole tokens, such as `100` or `0.5`, to
+/// base units of a mint with `decimals`
+///
+/// Unlike `spl_token::ui_amount_to_amount` this never goes through a float,
+/// so every amount up to `u64::MAX` base units converts exactly.
+pub fn parse_ui_amount(amount: &str, decimals: u8) -> Result<u64, AmountError> {
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount, ""),
+    };
+
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty()
+        || !is_digits(whole)
+        || !is_digits(fraction)
+        || (amount.contains('.') && fraction.is_empty())
+    {
+        return Err(AmountError::Invalid(amount.to_string()));
+    }
+    if fraction.len() > decimals as usize {
+        return Err(AmountError::TooPrecise {
+            amount: amount.to_string(),
+            decimals,
+        });
+    }
+
+    let overflow = || AmountError::Overflow(amount.to_string());
+    let unit = 10u64.checked_pow(decimals as u32);
+    let whole = whole
+        .parse::<u64>()
+        .ok()
+        .and_then(|whole| match unit {
+            Some(unit) => whole.checked_mul(unit),
+            // Past 19 decimals only a zero whole part fits
+            None if whole == 0 => Some(0),
+            None => None,
+        })
+        .ok_or_else(overflow)?;
+
+    // Right padded to the mint's decimals, "5" of 3 decimals is 500 units
+    let fraction = format!("{:0<width$}", fraction, width = decimals as usize);
+    let fraction = match fraction.trim_start_matches('0') {
+        "" => 0,
+        digits => digits.parse::<u64>().map_err(|_| overflow())?,
+    };
+
+    whole.checked_add(fraction).ok_or_else(overflow)
+}
+
+/// Formats `amount` base units in whole tokens of a mint with `decimals`
+pub fn format_ui_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", whole, fraction)
+}
+
+/// Fetches the decimals of `mint`, a mint of spl-token or spl-token-2022
+pub fn fetch_mint_decimals<R: AccountFetcher>(rpc: &R, mint: &Pubkey) -> Result<u8, FetchError> {
+    let account = rpc
+        .fetch_account(mint)?
+        .ok_or(FetchError::AccountMissing(*mint))?;
+
+    Ok(StateWithExtensions::<Mint>::unpack(&account.data)?
+        .base
+        .decimals)
+}
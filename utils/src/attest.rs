@@ -0,0 +1,236 @@
+use std::convert::TryInto;
+
+use datanexus::state::AccessState;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    account::from_account,
+    clock::Clock,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    sysvar,
+};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    find_associated_access_address_with_program_id, find_dataset_address_with_program_id,
+};
+
+/// Prefix of every attestation message, so an attestation signature can't
+/// be passed off as one over a transaction or another protocol's message
+pub const ATTESTATION_DOMAIN: &[u8] = b"datanexus access attestation v1";
+
+/// Slots an attestation's slot may be away from the cluster's before it is
+/// refused, about a minute either way
+pub const MAX_ATTESTATION_AGE: u64 = 150;
+
+/// Length of an attestation encoded with `SignedAttestation::to_bytes`
+pub const ATTESTATION_LEN: usize = 32 + 32 + 8 + 64;
+
+/// A claim by `authority` that it holds access to the dataset registered
+/// under `hash`, signed with its key at `slot`
+///
+/// Anyone holding the attestation can present it until it goes stale, so it
+/// should only travel over channels the authority trusts.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignedAttestation {
+    pub authority: Pubkey,
+    pub hash: [u8; 32],
+    pub slot: u64,
+    pub signature: Signature,
+}
+
+impl SignedAttestation {
+    /// The bytes the signature is over: the domain, then the authority, the
+    /// hash and the little endian slot
+    pub fn message(&self) -> Vec<u8> {
+        attestation_message(&self.authority, &self.hash, self.slot)
+    }
+
+    pub fn verify_signature(&self) -> bool {
+        self.signature
+            .verify(self.authority.as_ref(), &self.message())
+    }
+
+    /// The authority, hash, little endian slot and signature laid end to end
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ATTESTATION_LEN);
+        bytes.extend_from_slice(self.authority.as_ref());
+        bytes.extend_from_slice(&self.hash);
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(self.signature.as_ref());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AttestationError> {
+        if bytes.len() != ATTESTATION_LEN {
+            return Err(AttestationError::Malformed);
+        }
+
+        Ok(Self {
+            authority: Pubkey::new_from_array(bytes[..32].try_into().unwrap()),
+            hash: bytes[32..64].try_into().unwrap(),
+            slot: u64::from_le_bytes(bytes[64..72].try_into().unwrap()),
+            signature: Signature::new(&bytes[72..]),
+        })
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(self.to_bytes())
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self, AttestationError> {
+        let bytes = base64::decode(encoded).map_err(|_| AttestationError::Malformed)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// An attestation found valid against the cluster, for a gateway to cache
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccessProof {
+    pub authority: Pubkey,
+    pub hash: [u8; 32],
+    /// Slot the attestation was signed at
+    pub attested_slot: u64,
+    /// Slot and unix timestamp of the cluster clock the access was read with
+    pub checked_slot: u64,
+    pub checked_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+impl AccessProof {
+    /// Unix timestamp the proof can be trusted until without checking the
+    /// cluster again, `ttl` seconds after it was checked unless the access
+    /// expires first
+    pub fn cache_until(&self, ttl: i64) -> i64 {
+        let until = self.checked_at.saturating_add(ttl);
+        match self.expires_at {
+            Some(expires_at) => until.min(expires_at),
+            None => until,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AttestationError {
+    Fetch(FetchError),
+    /// The bytes don't decode to an attestation
+    Malformed,
+    /// The signature isn't the authority's over the attestation's message
+    InvalidSignature,
+    /// The attested slot is more than `MAX_ATTESTATION_AGE` slots away from
+    /// the cluster's
+    Stale {
+        slot: u64,
+        current: u64,
+    },
+    /// The authority holds no access, it was never granted or was revoked
+    NoAccess,
+    /// The access expired at the given unix timestamp
+    Expired(i64),
+}
+
+impl From<FetchError> for AttestationError {
+    fn from(e: FetchError) -> Self {
+        AttestationError::Fetch(e)
+    }
+}
+
+fn attestation_message(authority: &Pubkey, hash: &[u8; 32], slot: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(ATTESTATION_DOMAIN.len() + 72);
+    message.extend_from_slice(ATTESTATION_DOMAIN);
+    message.extend_from_slice(authority.as_ref());
+    message.extend_from_slice(hash);
+    message.extend_from_slice(&slot.to_le_bytes());
+    message
+}
+
+/// Signs a claim that `authority` holds access to the dataset registered
+/// under `hash`, as of the recent `slot`
+pub fn build_access_attestation<S: Signer + ?Sized>(
+    authority: &S,
+    hash: [u8; 32],
+    slot: u64,
+) -> SignedAttestation {
+    let authority_key = authority.pubkey();
+    SignedAttestation {
+        authority: authority_key,
+        hash,
+        slot,
+        signature: authority.sign_message(&attestation_message(&authority_key, &hash, slot)),
+    }
+}
+
+/// Checks `attestation` was signed by its authority, then reads its
+/// associated access account along with the clock in a single request to
+/// confirm the attested access is held and unexpired
+///
+/// The signature is checked before anything is fetched, so forged
+/// attestations cost no request.
+pub fn verify_access_attestation<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    attestation: &SignedAttestation,
+) -> Result<AccessProof, AttestationError> {
+    if !attestation.verify_signature() {
+        return Err(AttestationError::InvalidSignature);
+    }
+
+    let dataset_address = find_dataset_address_with_program_id(program_id, &attestation.hash).0;
+    let access_address = find_associated_access_address_with_program_id(
+        program_id,
+        attestation.authority,
+        dataset_address,
+    )
+    .0;
+    let (_, accounts) = rpc
+        .fetch_multiple_accounts(&[access_address, sysvar::clock::id()])
+        .map_err(FetchError::from)?;
+    let (access_account, clock_account) = match accounts.as_slice() {
+        [access_account, clock_account] => (access_account, clock_account),
+        _ => return Err(FetchError::AccountMissing(access_address).into()),
+    };
+
+    let clock: Clock = clock_account
+        .as_ref()
+        .and_then(from_account)
+        .ok_or(FetchError::AccountMissing(sysvar::clock::id()))?;
+    if clock.slot.saturating_sub(attestation.slot) > MAX_ATTESTATION_AGE
+        || attestation.slot.saturating_sub(clock.slot) > MAX_ATTESTATION_AGE
+    {
+        return Err(AttestationError::Stale {
+            slot: attestation.slot,
+            current: clock.slot,
+        });
+    }
+
+    let access_account = match access_account {
+        Some(access_account) => access_account,
+        None => return Err(AttestationError::NoAccess),
+    };
+    if access_account.owner != *program_id {
+        return Err(FetchError::WrongOwner(access_address).into());
+    }
+    let access_state =
+        AccessState::unpack_from_slice(&access_account.data).map_err(FetchError::from)?;
+    let access = access_state
+        .find_entry(&attestation.hash)
+        .map(|index| access_state.datasets[index])
+        .ok_or(AttestationError::NoAccess)?;
+    if access.is_expired(clock.unix_timestamp) {
+        return Err(AttestationError::Expired(
+            access.expires_at.unwrap_or_default(),
+        ));
+    }
+
+    Ok(AccessProof {
+        authority: attestation.authority,
+        hash: attestation.hash,
+        attested_slot: attestation.slot,
+        checked_slot: clock.slot,
+        checked_at: clock.unix_timestamp,
+        expires_at: access.expires_at,
+    })
+}
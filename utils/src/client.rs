@@ -0,0 +1,1120 @@
+use datanexus::{
+    accounts::{
+        ClaimCollectionAccessAccounts, InitAssociatedAccessAccounts, InitVaultAccounts,
+        MemberAccounts, PurchaseAccessAccounts, PurchaseCollectionAccounts, SetDataParamsAccounts,
+        ShareAccessAccounts, ShareFeeAccounts,
+    },
+    instruction::{
+        claim_collection_access, init_account, init_associated_access, init_vault, purchase_access,
+        purchase_collection, set_data_params, share_access, AccountType, Params,
+    },
+    state::{
+        AccessInfo, AccessState, AccountIndex, CollectionAccess, CollectionState, DatasetState,
+        MAX_COLLECTION_GRANTS,
+    },
+};
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    signer::unique_signers,
+    system_program,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{extension::StateWithExtensions, state};
+
+use crate::{
+    find_access_address_with_program_id, find_associated_access_address_with_program_id,
+    find_collection_address_with_program_id, find_dataset_address_with_program_id,
+    find_owner_address_with_program_id, find_vault_address_with_program_id,
+    packer::{PackError, PackItem, TransactionPacker},
+};
+
+/// The part of the RPC surface the fetch helpers rely on, so they can run
+/// against a test bank as well as a live cluster
+#[allow(clippy::result_large_err)]
+pub trait AccountFetcher {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+
+    /// Fetches `addresses` in order, along with the slot they were read at
+    ///
+    /// Fetchers without batching read them one by one and report slot 0,
+    /// which is never fresh enough to be cached
+    fn fetch_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<(u64, Vec<Option<Account>>), ClientError> {
+        let accounts = addresses
+            .iter()
+            .map(|address| self.fetch_account(address))
+            .collect::<Result<_, _>>()?;
+        Ok((0, accounts))
+    }
+}
+
+impl<R: AccountFetcher + ?Sized> AccountFetcher for &R {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        (**self).fetch_account(address)
+    }
+
+    fn fetch_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<(u64, Vec<Option<Account>>), ClientError> {
+        (**self).fetch_multiple_accounts(addresses)
+    }
+}
+
+impl AccountFetcher for RpcClient {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self
+            .get_account_with_commitment(address, self.commitment())?
+            .value)
+    }
+
+    fn fetch_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<(u64, Vec<Option<Account>>), ClientError> {
+        let response = self.get_multiple_accounts_with_commitment(addresses, self.commitment())?;
+        Ok((response.context.slot, response.value))
+    }
+}
+
+/// The part of the RPC surface `DataNexusClient` sends through, so it can
+/// run against a test bank as well as a live cluster
+#[allow(clippy::result_large_err)]
+pub trait TransactionSender: AccountFetcher {
+    fn latest_blockhash(&self) -> Result<Hash, ClientError>;
+
+    /// Sends `transaction` and waits for it to be confirmed
+    fn send_and_confirm(&self, transaction: &Transaction) -> Result<Signature, ClientError>;
+}
+
+impl<R: TransactionSender + ?Sized> TransactionSender for &R {
+    fn latest_blockhash(&self) -> Result<Hash, ClientError> {
+        (**self).latest_blockhash()
+    }
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        (**self).send_and_confirm(transaction)
+    }
+}
+
+impl TransactionSender for RpcClient {
+    fn latest_blockhash(&self) -> Result<Hash, ClientError> {
+        self.get_latest_blockhash()
+    }
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        self.send_and_confirm_transaction(transaction)
+    }
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+    Client(Box<ClientError>),
+    AccountMissing(Pubkey),
+    /// An account the call would create is already there
+    AccountExists(Pubkey),
+    WrongOwner(Pubkey),
+    /// An account the caller gave is not the one derived or found for it
+    AccountMismatch {
+        given: Pubkey,
+        expected: Pubkey,
+    },
+    DecodeFailed(ProgramError),
+    /// The instructions can't be packed into transactions
+    Pack(PackError),
+}
+
+impl From<ClientError> for FetchError {
+    fn from(e: ClientError) -> Self {
+        FetchError::Client(Box::new(e))
+    }
+}
+
+impl From<ProgramError> for FetchError {
+    fn from(e: ProgramError) -> Self {
+        FetchError::DecodeFailed(e)
+    }
+}
+
+impl From<PackError> for FetchError {
+    fn from(e: PackError) -> Self {
+        FetchError::Pack(e)
+    }
+}
+
+/// Fetches `address` and decodes it as `T`, checking it is owned by the
+/// DataNexus program deployed at `program_id`
+fn fetch_state<R: AccountFetcher, T: Pack>(
+    rpc: &R,
+    program_id: &Pubkey,
+    address: &Pubkey,
+) -> Result<T, FetchError> {
+    let account = rpc
+        .fetch_account(address)?
+        .ok_or(FetchError::AccountMissing(*address))?;
+
+    if account.owner != *program_id {
+        return Err(FetchError::WrongOwner(*address));
+    }
+
+    Ok(T::unpack_from_slice(&account.data)?)
+}
+
+/// Fetches the dataset registered under `hash`
+pub fn fetch_dataset<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<DatasetState, FetchError> {
+    fetch_state(
+        rpc,
+        program_id,
+        &find_dataset_address_with_program_id(program_id, hash).0,
+    )
+}
+
+/// Fetches the collection registered under `hash`
+pub fn fetch_collection<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<CollectionState, FetchError> {
+    fetch_state(
+        rpc,
+        program_id,
+        &find_collection_address_with_program_id(program_id, hash).0,
+    )
+}
+
+/// Fetches the index of datasets owned by `authority`
+pub fn fetch_owner_index<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+) -> Result<AccountIndex, FetchError> {
+    fetch_state(
+        rpc,
+        program_id,
+        &find_owner_address_with_program_id(program_id, authority).0,
+    )
+}
+
+/// Fetches the index of datasets `authority` holds access to
+pub fn fetch_access_index<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+) -> Result<AccountIndex, FetchError> {
+    fetch_state(
+        rpc,
+        program_id,
+        &find_access_address_with_program_id(program_id, authority).0,
+    )
+}
+
+/// Fetches the associated access account of `authority` for the dataset
+/// registered under `hash`
+pub fn fetch_access_state<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+    hash: &[u8; 32],
+) -> Result<AccessState, FetchError> {
+    let dataset_address = find_dataset_address_with_program_id(program_id, hash).0;
+    fetch_state(
+        rpc,
+        program_id,
+        &find_associated_access_address_with_program_id(program_id, authority, dataset_address).0,
+    )
+}
+
+/// Fetches the access entry `authority` holds for the dataset registered
+/// under `hash`, `None` when no access has been granted
+pub fn fetch_access_for<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+    hash: &[u8; 32],
+) -> Result<Option<AccessInfo>, FetchError> {
+    let access_state = match fetch_access_state(rpc, program_id, authority, hash) {
+        Ok(access_state) => access_state,
+        Err(FetchError::AccountMissing(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    Ok(access_state
+        .find_entry(hash)
+        .map(|index| access_state.datasets[index]))
+}
+
+/// Instructions to send in one transaction, with the accounts they create
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub instructions: Vec<Instruction>,
+    pub created: Vec<Pubkey>,
+}
+
+impl Plan {
+    fn push(&mut self, instruction: Instruction, created: Option<Pubkey>) {
+        self.instructions.push(instruction);
+        self.created.extend(created);
+    }
+
+    fn extend(&mut self, other: Plan) {
+        self.instructions.extend(other.instructions);
+        self.created.extend(other.created);
+    }
+}
+
+/// Adds the creation of the index of `account_type` for `authority` to
+/// `plan`, unless it already exists, and returns its address
+fn plan_index<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    payer: Pubkey,
+    authority: Pubkey,
+    account_type: AccountType,
+    plan: &mut Plan,
+) -> Result<Pubkey, FetchError> {
+    let index_account = if account_type == AccountType::DatasetIndex {
+        find_owner_address_with_program_id(program_id, authority).0
+    } else {
+        find_access_address_with_program_id(program_id, authority).0
+    };
+
+    if rpc.fetch_account(&index_account)?.is_none() {
+        plan.push(
+            init_account(
+                *program_id,
+                Some(payer),
+                authority,
+                index_account,
+                None,
+                system_program::id(),
+                account_type,
+            )?,
+            Some(index_account),
+        );
+    }
+
+    Ok(index_account)
+}
+
+/// Assembles the instructions registering the dataset under `hash` to
+/// `authority`, creating its owner index first when it is missing
+pub fn register_dataset_plan<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    payer: Pubkey,
+    authority: Pubkey,
+    hash: &[u8; 32],
+) -> Result<Plan, FetchError> {
+    let mut plan = Plan::default();
+    let owner_index = plan_index(
+        rpc,
+        program_id,
+        payer,
+        authority,
+        AccountType::DatasetIndex,
+        &mut plan,
+    )?;
+
+    let dataset_address = find_dataset_address_with_program_id(program_id, hash).0;
+    plan.push(
+        init_account(
+            *program_id,
+            None,
+            authority,
+            dataset_address,
+            Some(owner_index),
+            system_program::id(),
+            AccountType::Dataset(*hash),
+        )?,
+        Some(dataset_address),
+    );
+
+    Ok(plan)
+}
+
+/// Assembles the instructions for `authority` to create its associated
+/// access account of the dataset registered under `hash`, creating its access
+/// index first when it is missing
+///
+/// Access can only be shared with recipients that created the account, as
+/// it needs their signature
+pub fn associated_access_plan<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    payer: Pubkey,
+    authority: Pubkey,
+    hash: &[u8; 32],
+) -> Result<Plan, FetchError> {
+    let mut plan = Plan::default();
+    let access_index = plan_index(
+        rpc,
+        program_id,
+        payer,
+        authority,
+        AccountType::AccessIndex,
+        &mut plan,
+    )?;
+
+    let dataset_address = find_dataset_address_with_program_id(program_id, hash).0;
+    let associated_access_account =
+        find_associated_access_address_with_program_id(program_id, authority, dataset_address).0;
+    if rpc.fetch_account(&associated_access_account)?.is_some() {
+        return Err(FetchError::AccountExists(associated_access_account));
+    }
+    plan.push(
+        init_associated_access(
+            *program_id,
+            InitAssociatedAccessAccounts {
+                payer,
+                authority,
+                access_index,
+                associated_access_account,
+                dataset_account: dataset_address,
+                system_program: system_program::id(),
+            },
+            *hash,
+        )?,
+        Some(associated_access_account),
+    );
+
+    Ok(plan)
+}
+
+/// Assembles the instructions for `user_authority` to share its access to
+/// the dataset registered under `hash` with `recipient_authority`, the
+/// recipient's access expiring `expires_in` seconds after the share when set
+///
+/// When the dataset charges a share fee it is paid from
+/// `fee_token_account`, or the sharer's associated token account of the fee
+/// mint when not given, to the owner's associated token account.
+///
+/// Fails with `AccountMissing` when the recipient has not created its
+/// associated access account with `associated_access_plan`
+pub fn share_access_plan<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    user_authority: Pubkey,
+    hash: &[u8; 32],
+    recipient_authority: Pubkey,
+    expires_in: Option<i64>,
+    fee_token_account: Option<Pubkey>,
+) -> Result<Plan, FetchError> {
+    let dataset_address = find_dataset_address_with_program_id(program_id, hash).0;
+    let recipient_access_index =
+        find_access_address_with_program_id(program_id, recipient_authority).0;
+    let recipient_access_account = find_associated_access_address_with_program_id(
+        program_id,
+        recipient_authority,
+        dataset_address,
+    )
+    .0;
+    if rpc.fetch_account(&recipient_access_account)?.is_none() {
+        return Err(FetchError::AccountMissing(recipient_access_account));
+    }
+
+    let dataset_state = fetch_dataset(rpc, program_id, hash)?;
+    let fee_accounts = match dataset_state.share_fee {
+        Some(share_fee) => {
+            // The fee mint's owning program decides whether the fee goes
+            // through spl-token or spl-token-2022
+            let token_program = rpc
+                .fetch_account(&share_fee.mint)?
+                .ok_or(FetchError::AccountMissing(share_fee.mint))?
+                .owner;
+            Some(ShareFeeAccounts {
+                sharer_token_account: fee_token_account.unwrap_or_else(|| {
+                    get_associated_token_address_with_program_id(
+                        &user_authority,
+                        &share_fee.mint,
+                        &token_program,
+                    )
+                }),
+                owner_token_account: get_associated_token_address_with_program_id(
+                    &dataset_state.owner,
+                    &share_fee.mint,
+                    &token_program,
+                ),
+                token_mint: share_fee.mint,
+                token_program,
+            })
+        }
+        None => None,
+    };
+
+    let mut plan = Plan::default();
+    plan.push(
+        share_access(
+            *program_id,
+            ShareAccessAccounts {
+                user_authority,
+                user_access_account: find_associated_access_address_with_program_id(
+                    program_id,
+                    user_authority,
+                    dataset_address,
+                )
+                .0,
+                recipient_authority,
+                recipient_access_index,
+                recipient_access_account,
+                dataset_account: dataset_address,
+            },
+            *hash,
+            expires_in,
+            fee_accounts,
+        )?,
+        None,
+    );
+
+    Ok(plan)
+}
+
+/// Assembles the instructions for `user_authority` to purchase access to the
+/// dataset registered under `hash`, paying from `user_token_account`
+///
+/// Like an associated token account, the associated access account is
+/// created alongside the first purchase. Fails with `AccountMissing` when the
+/// owner has not opened the dataset's vault of the payment mint.
+/// `reference_access`, the buyer's associated access account of the
+/// dataset's reference, claims the derived discount
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_access_instructions<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    user_authority: Pubkey,
+    hash: &[u8; 32],
+    user_token_account: Pubkey,
+    amount: u64,
+    referrer_token_account: Option<Pubkey>,
+    reference_access: Option<Pubkey>,
+) -> Result<Vec<Instruction>, FetchError> {
+    Ok(purchase_access_plan(
+        rpc,
+        program_id,
+        user_authority,
+        hash,
+        user_token_account,
+        amount,
+        referrer_token_account,
+        reference_access,
+        &KnownPurchaseAccounts::default(),
+    )?
+    .instructions)
+}
+
+/// Accounts of a purchase the caller already knows, taken as given instead
+/// of looked up
+///
+/// Each one left `None` is discovered from the cluster. Given accounts are
+/// checked against those derived or found for them where that costs no
+/// request, failing with `AccountMismatch`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KnownPurchaseAccounts {
+    /// The dataset's account, checked against the one derived from its hash
+    pub dataset_account: Option<Pubkey>,
+    /// The mint of the buyer's token account, which along with
+    /// `token_program` skips fetching the token account
+    pub token_mint: Option<Pubkey>,
+    /// The token program owning the buyer's token account and its mint
+    pub token_program: Option<Pubkey>,
+    /// The dataset's vault of the mint, which skips checking it is open
+    pub proceeds_account: Option<Pubkey>,
+}
+
+/// Checks the account the caller gave, if any, is `expected`
+fn check_given(given: Option<Pubkey>, expected: Pubkey) -> Result<(), FetchError> {
+    match given {
+        Some(given) if given != expected => Err(FetchError::AccountMismatch { given, expected }),
+        _ => Ok(()),
+    }
+}
+
+/// `purchase_access_instructions`, along with the associated access account
+/// the purchase creates, looking up only the accounts not in `known`
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_access_plan<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    user_authority: Pubkey,
+    hash: &[u8; 32],
+    user_token_account: Pubkey,
+    amount: u64,
+    referrer_token_account: Option<Pubkey>,
+    reference_access: Option<Pubkey>,
+    known: &KnownPurchaseAccounts,
+) -> Result<Plan, FetchError> {
+    let dataset_address = find_dataset_address_with_program_id(program_id, hash).0;
+    check_given(known.dataset_account, dataset_address)?;
+    let user_access_index = find_access_address_with_program_id(program_id, user_authority).0;
+    let user_access_account =
+        find_associated_access_address_with_program_id(program_id, user_authority, dataset_address)
+            .0;
+
+    // The owning program of the buyer's token account decides whether the
+    // purchase goes through spl-token or spl-token-2022
+    let (token_mint, token_program) = match (known.token_mint, known.token_program) {
+        (Some(token_mint), Some(token_program)) => (token_mint, token_program),
+        (given_mint, given_program) => {
+            let user_token_account_info = rpc
+                .fetch_account(&user_token_account)?
+                .ok_or(FetchError::AccountMissing(user_token_account))?;
+            let token_mint =
+                StateWithExtensions::<state::Account>::unpack(&user_token_account_info.data)?
+                    .base
+                    .mint;
+            check_given(given_mint, token_mint)?;
+            check_given(given_program, user_token_account_info.owner)?;
+            (token_mint, user_token_account_info.owner)
+        }
+    };
+
+    let dataset_state = fetch_dataset(rpc, program_id, hash)?;
+
+    let mut plan = Plan::default();
+
+    // Proceeds are collected in the dataset's vault of the mint, which only
+    // the owner can open
+    let proceeds_account =
+        find_vault_address_with_program_id(program_id, dataset_address, token_mint).0;
+    if known.proceeds_account.is_some() {
+        check_given(known.proceeds_account, proceeds_account)?;
+    } else if rpc.fetch_account(&proceeds_account)?.is_none() {
+        return Err(FetchError::AccountMissing(proceeds_account));
+    }
+
+    if let Err(FetchError::AccountMissing(_)) =
+        fetch_access_state(rpc, program_id, user_authority, hash)
+    {
+        plan.push(
+            init_associated_access(
+                *program_id,
+                InitAssociatedAccessAccounts {
+                    payer: user_authority,
+                    authority: user_authority,
+                    access_index: user_access_index,
+                    associated_access_account: user_access_account,
+                    dataset_account: dataset_address,
+                    system_program: system_program::id(),
+                },
+                *hash,
+            )?,
+            Some(user_access_account),
+        );
+    }
+
+    plan.push(
+        purchase_access(
+            *program_id,
+            PurchaseAccessAccounts {
+                user_authority,
+                user_access_index,
+                user_access_account,
+                user_token_account,
+                owner_authority: dataset_state.owner,
+                proceeds_account,
+                token_mint,
+                dataset_account: dataset_address,
+                token_program,
+                referrer_token_account,
+                receipt: None,
+                reference_access,
+            },
+            *hash,
+            amount,
+        )?,
+        None,
+    );
+
+    Ok(plan)
+}
+
+/// Assembles the transactions for `user_authority` to purchase the
+/// collection registered under `hash`, paying from `user_token_account`
+///
+/// Each transaction grants at most `MAX_COLLECTION_GRANTS` members, the
+/// first one pays and the rest claim the remaining members in order. The
+/// plan resumes from the buyer's cursor, so it can be rebuilt and resent
+/// after any transaction fails
+pub fn purchase_collection_transactions<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    user_authority: Pubkey,
+    hash: &[u8; 32],
+    user_token_account: Pubkey,
+    amount: u64,
+) -> Result<Vec<Vec<Instruction>>, FetchError> {
+    let collection_address = find_collection_address_with_program_id(program_id, hash).0;
+    let user_access_index = find_access_address_with_program_id(program_id, user_authority).0;
+    let collection_access_account = find_associated_access_address_with_program_id(
+        program_id,
+        user_authority,
+        collection_address,
+    )
+    .0;
+    let collection_state = fetch_collection(rpc, program_id, hash)?;
+
+    let mut setup = vec![];
+    let collection_access =
+        match fetch_state::<R, CollectionAccess>(rpc, program_id, &collection_access_account) {
+            Ok(collection_access) => Some(collection_access),
+            Err(FetchError::AccountMissing(_)) => {
+                setup.push(init_associated_access(
+                    *program_id,
+                    InitAssociatedAccessAccounts {
+                        payer: user_authority,
+                        authority: user_authority,
+                        access_index: user_access_index,
+                        associated_access_account: collection_access_account,
+                        dataset_account: collection_address,
+                        system_program: system_program::id(),
+                    },
+                    *hash,
+                )?);
+                None
+            }
+            Err(e) => return Err(e),
+        };
+    let purchased = collection_access.is_some_and(|access| access.purchased);
+    let cursor = collection_access.map_or(0, |access| access.cursor as usize);
+
+    // Members still to be granted need an associated access account, closed
+    // datasets are skipped on chain and only have their address passed
+    let mut members = vec![];
+    for dataset_account in collection_state.members.iter().skip(cursor) {
+        let access_account = find_associated_access_address_with_program_id(
+            program_id,
+            user_authority,
+            *dataset_account,
+        )
+        .0;
+        let dataset = match rpc.fetch_account(dataset_account)? {
+            Some(account)
+                if account.owner == *program_id && !DatasetState::is_closed(&account.data) =>
+            {
+                Some(DatasetState::unpack_from_slice(&account.data)?)
+            }
+            _ => None,
+        };
+        if let Some(dataset) = dataset {
+            if rpc.fetch_account(&access_account)?.is_none() {
+                setup.push(init_associated_access(
+                    *program_id,
+                    InitAssociatedAccessAccounts {
+                        payer: user_authority,
+                        authority: user_authority,
+                        access_index: user_access_index,
+                        associated_access_account: access_account,
+                        dataset_account: *dataset_account,
+                        system_program: system_program::id(),
+                    },
+                    dataset.hash,
+                )?);
+            }
+        }
+        members.push((*dataset_account, access_account));
+    }
+
+    let mut transactions: Vec<Vec<Instruction>> = setup
+        .chunks(MAX_COLLECTION_GRANTS)
+        .map(|chunk| chunk.to_vec())
+        .collect();
+    let member_accounts = |chunk: &[(Pubkey, Pubkey)]| {
+        chunk
+            .iter()
+            .map(|(dataset_account, access_account)| MemberAccounts {
+                dataset_account: *dataset_account,
+                access_account: *access_account,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut chunks = members.chunks(MAX_COLLECTION_GRANTS);
+    if !purchased {
+        // The owner is paid through the associated token account of the mint
+        // the buyer pays with, as with single datasets
+        let user_token_account_info = rpc
+            .fetch_account(&user_token_account)?
+            .ok_or(FetchError::AccountMissing(user_token_account))?;
+        let token_program = user_token_account_info.owner;
+        let token_mint =
+            StateWithExtensions::<state::Account>::unpack(&user_token_account_info.data)?
+                .base
+                .mint;
+        let owner_token_account = get_associated_token_address_with_program_id(
+            &collection_state.owner,
+            &token_mint,
+            &token_program,
+        );
+
+        transactions.push(vec![purchase_collection(
+            *program_id,
+            PurchaseCollectionAccounts {
+                user_authority,
+                user_access_index,
+                collection_access_account,
+                user_token_account,
+                owner_authority: collection_state.owner,
+                owner_token_account,
+                token_mint,
+                collection_account: collection_address,
+                token_program,
+                members: member_accounts(chunks.next().unwrap_or(&[])),
+            },
+            *hash,
+            amount,
+        )?]);
+    }
+
+    for chunk in chunks {
+        transactions.push(vec![claim_collection_access(
+            *program_id,
+            ClaimCollectionAccessAccounts {
+                user_authority,
+                user_access_index,
+                collection_access_account,
+                collection_account: collection_address,
+                members: member_accounts(chunk),
+            },
+            *hash,
+        )?]);
+    }
+
+    Ok(transactions)
+}
+
+/// What a `DataNexusClient` call created and the transaction that did it
+#[derive(Debug)]
+pub struct TransactionReceipt {
+    pub signature: Signature,
+    /// Accounts the transaction created, prerequisites first
+    pub created: Vec<Pubkey>,
+}
+
+/// Sends each step of the dataset lifecycle as one transaction signed by
+/// `payer`, which is also the authority the step acts for unless another
+/// one is set with `with_authority`
+///
+/// Missing index and associated access accounts are created in the same
+/// transaction as the step that needs them. Each `plan_*` method returns
+/// the instructions of a step without sending them, for callers that sign
+/// and send on their own.
+pub struct DataNexusClient<R = RpcClient> {
+    rpc: R,
+    program_id: Pubkey,
+    payer: Box<dyn Signer>,
+    authority: Option<Box<dyn Signer>>,
+}
+
+impl DataNexusClient {
+    pub fn new<S: Signer + 'static>(rpc_url: &str, payer: S) -> Self {
+        Self::with_sender(RpcClient::new(rpc_url.to_string()), datanexus::id(), payer)
+    }
+}
+
+impl<R: TransactionSender> DataNexusClient<R> {
+    /// A client sending through `rpc` to the program deployed at
+    /// `program_id`
+    pub fn with_sender<S: Signer + 'static>(rpc: R, program_id: Pubkey, payer: S) -> Self {
+        Self {
+            rpc,
+            program_id,
+            payer: Box::new(payer),
+            authority: None,
+        }
+    }
+
+    /// Acts for `authority`, which signs every step along with the payer
+    pub fn with_authority<S: Signer + 'static>(mut self, authority: S) -> Self {
+        self.authority = Some(Box::new(authority));
+        self
+    }
+
+    pub fn rpc(&self) -> &R {
+        &self.rpc
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.authority
+            .as_ref()
+            .map_or_else(|| self.payer(), |authority| authority.pubkey())
+    }
+
+    /// Signs `plan` with the payer and authority and sends it
+    pub fn send(&self, plan: Plan) -> Result<TransactionReceipt, FetchError> {
+        let mut transaction = Transaction::new_with_payer(&plan.instructions, Some(&self.payer()));
+        let signers = unique_signers(
+            std::iter::once(&*self.payer)
+                .chain(self.authority.as_deref())
+                .collect(),
+        );
+        transaction
+            .try_sign(&signers, self.rpc.latest_blockhash()?)
+            .map_err(ClientError::from)?;
+
+        Ok(TransactionReceipt {
+            signature: self.rpc.send_and_confirm(&transaction)?,
+            created: plan.created,
+        })
+    }
+
+    /// Sends `plans` in as few transactions as they fit in, each signed like
+    /// `send`, and returns a receipt per transaction in the order sent
+    ///
+    /// Plans creating an account another one uses are sent first. Each plan
+    /// is built against the accounts as they are, so two plans creating the
+    /// same missing account can't be sent together.
+    pub fn send_all(&self, plans: Vec<Plan>) -> Result<Vec<TransactionReceipt>, FetchError> {
+        let items: Vec<PackItem> = plans.into_iter().map(PackItem::from).collect();
+        let transactions = TransactionPacker::new(self.payer()).pack(&items)?;
+
+        transactions
+            .into_iter()
+            .map(|transaction| {
+                let created = transaction
+                    .items
+                    .iter()
+                    .flat_map(|index| items[*index].created.iter().copied())
+                    .collect();
+                self.send(Plan {
+                    instructions: transaction.instructions,
+                    created,
+                })
+            })
+            .collect()
+    }
+
+    /// Plans the creation of the authority's index of `account_type`,
+    /// failing with `AccountExists` when it is already there
+    pub fn plan_create_index(&self, account_type: AccountType) -> Result<Plan, FetchError> {
+        let mut plan = Plan::default();
+        let index_account = plan_index(
+            &self.rpc,
+            &self.program_id,
+            self.payer(),
+            self.authority(),
+            account_type,
+            &mut plan,
+        )?;
+        if plan.created.is_empty() {
+            return Err(FetchError::AccountExists(index_account));
+        }
+
+        Ok(plan)
+    }
+
+    /// Creates the authority's owner index
+    pub fn create_owner(&self) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_create_index(AccountType::DatasetIndex)?)
+    }
+
+    /// Creates the authority's access index
+    pub fn create_access(&self) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_create_index(AccountType::AccessIndex)?)
+    }
+
+    pub fn plan_register_dataset(&self, hash: [u8; 32]) -> Result<Plan, FetchError> {
+        register_dataset_plan(
+            &self.rpc,
+            &self.program_id,
+            self.payer(),
+            self.authority(),
+            &hash,
+        )
+    }
+
+    /// Registers the dataset under `hash` to the authority, parameters are
+    /// set separately with `set_params`
+    pub fn register_dataset(&self, hash: [u8; 32]) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_register_dataset(hash)?)
+    }
+
+    /// Plans one `SetDataParams` instruction per entry of `params`
+    pub fn plan_set_params(&self, hash: [u8; 32], params: Vec<Params>) -> Result<Plan, FetchError> {
+        let dataset_account = find_dataset_address_with_program_id(&self.program_id, &hash).0;
+        let instructions = params
+            .into_iter()
+            .map(|params| {
+                set_data_params(
+                    self.program_id,
+                    SetDataParamsAccounts {
+                        authority: self.authority(),
+                        dataset_account,
+                        reference_dataset: params.reference_data(),
+                    },
+                    hash,
+                    params,
+                    &[],
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Plan {
+            instructions,
+            created: vec![],
+        })
+    }
+
+    pub fn set_params(
+        &self,
+        hash: [u8; 32],
+        params: Params,
+    ) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_set_params(hash, vec![params])?)
+    }
+
+    /// Opens the vault of `mint` of the authority's dataset registered under
+    /// `hash`, after which purchases can be paid in the mint
+    pub fn init_vault(
+        &self,
+        hash: [u8; 32],
+        mint: Pubkey,
+    ) -> Result<TransactionReceipt, FetchError> {
+        let token_program = self
+            .rpc
+            .fetch_account(&mint)?
+            .ok_or(FetchError::AccountMissing(mint))?
+            .owner;
+        let dataset_address = find_dataset_address_with_program_id(&self.program_id, &hash).0;
+        let vault_account =
+            find_vault_address_with_program_id(&self.program_id, dataset_address, mint).0;
+        let instruction = init_vault(
+            self.program_id,
+            InitVaultAccounts {
+                payer: self.payer(),
+                owner_authority: self.authority(),
+                dataset_account: dataset_address,
+                token_mint: mint,
+                vault_account,
+                token_program,
+                system_program: system_program::id(),
+            },
+            hash,
+        )?;
+
+        self.send(Plan {
+            instructions: vec![instruction],
+            created: vec![vault_account],
+        })
+    }
+
+    /// Plans the authority's purchase of access to the dataset registered
+    /// under `hash`, creating its access index first when it is missing and
+    /// looking up only the accounts not in `known`
+    pub fn plan_purchase(
+        &self,
+        hash: [u8; 32],
+        token_account: Pubkey,
+        amount: u64,
+        referrer_token_account: Option<Pubkey>,
+        reference_access: Option<Pubkey>,
+        known: &KnownPurchaseAccounts,
+    ) -> Result<Plan, FetchError> {
+        let authority = self.authority();
+        let mut plan = Plan::default();
+        plan_index(
+            &self.rpc,
+            &self.program_id,
+            self.payer(),
+            authority,
+            AccountType::AccessIndex,
+            &mut plan,
+        )?;
+        plan.extend(purchase_access_plan(
+            &self.rpc,
+            &self.program_id,
+            authority,
+            &hash,
+            token_account,
+            amount,
+            referrer_token_account,
+            reference_access,
+            known,
+        )?);
+
+        Ok(plan)
+    }
+
+    /// Purchases access to the dataset registered under `hash`, paying
+    /// `amount` from `token_account`
+    pub fn purchase(
+        &self,
+        hash: [u8; 32],
+        token_account: Pubkey,
+        amount: u64,
+    ) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_purchase(
+            hash,
+            token_account,
+            amount,
+            None,
+            None,
+            &KnownPurchaseAccounts::default(),
+        )?)
+    }
+
+    pub fn plan_create_associated_access(&self, hash: [u8; 32]) -> Result<Plan, FetchError> {
+        associated_access_plan(
+            &self.rpc,
+            &self.program_id,
+            self.payer(),
+            self.authority(),
+            &hash,
+        )
+    }
+
+    /// Creates the authority's associated access account of the dataset
+    /// registered under `hash`, after which access can be shared with it
+    pub fn create_associated_access(
+        &self,
+        hash: [u8; 32],
+    ) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_create_associated_access(hash)?)
+    }
+
+    /// Any share fee is paid from `fee_token_account`, or the authority's
+    /// associated token account of the fee mint when not given
+    pub fn plan_share(
+        &self,
+        hash: [u8; 32],
+        recipient: Pubkey,
+        expires_in: Option<i64>,
+        fee_token_account: Option<Pubkey>,
+    ) -> Result<Plan, FetchError> {
+        share_access_plan(
+            &self.rpc,
+            &self.program_id,
+            self.authority(),
+            &hash,
+            recipient,
+            expires_in,
+            fee_token_account,
+        )
+    }
+
+    /// Shares the authority's access to the dataset registered under `hash`
+    /// with `recipient`, whose associated access account must exist
+    pub fn share(
+        &self,
+        hash: [u8; 32],
+        recipient: Pubkey,
+    ) -> Result<TransactionReceipt, FetchError> {
+        self.send(self.plan_share(hash, recipient, None, None)?)
+    }
+}
@@ -0,0 +1,35 @@
+use datanexus::state::DatasetState;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    find_dataset_address_versioned_with_program_id, DerivationVersion,
+};
+
+/// Derivation the dataset of `hash` owned by `authority` was created under
+///
+/// Probes the latest derivation first, then the older ones, returning the
+/// first holding a dataset of `authority`. The same hash may have datasets
+/// of different owners under each derivation, so a dataset of someone else
+/// is passed over. `LATEST` when `authority` owns none, as that is where a
+/// new one would be created.
+pub fn detect_derivation_version<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<DerivationVersion, FetchError> {
+    for version in [DerivationVersion::V2, DerivationVersion::V1] {
+        let address = find_dataset_address_versioned_with_program_id(program_id, version, hash).0;
+        let account = match rpc.fetch_account(&address)? {
+            Some(account) if account.owner == *program_id => account,
+            _ => continue,
+        };
+
+        if DatasetState::unpack_from_slice(&account.data)?.owner == *authority {
+            return Ok(version);
+        }
+    }
+
+    Ok(DerivationVersion::LATEST)
+}
@@ -0,0 +1,186 @@
+use std::collections::{HashSet, VecDeque};
+
+use datanexus::state::{AccountIndex, DatasetState};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    find_owner_address_with_program_id,
+};
+
+/// Most accounts `getMultipleAccounts` returns per request
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Dataset accounts kept between enumerations, so listing an owner again
+/// doesn't refetch every dataset
+///
+/// Entries are keyed by address and carry the slot they were fetched at,
+/// an LRU evicting old slots is the expected implementation.
+pub trait Cache {
+    /// The account at `address`, if it was fetched at `min_slot` or later
+    fn get(&mut self, address: &Pubkey, min_slot: u64) -> Option<Account>;
+
+    fn insert(&mut self, address: Pubkey, slot: u64, account: Account);
+}
+
+/// Lazily walks the owner index of an authority and the indexes chained to
+/// it through `pointer`, yielding each dataset they record
+///
+/// Datasets are fetched `MAX_MULTIPLE_ACCOUNTS` at a time and the next
+/// index only once the datasets already known run short of a batch.
+/// Entries of datasets closed since they were recorded are skipped.
+pub struct DatasetIter<'a, R> {
+    rpc: &'a R,
+    program_id: Pubkey,
+    next_index: Option<Pubkey>,
+    visited: HashSet<Pubkey>,
+    pending: VecDeque<Pubkey>,
+    ready: VecDeque<Result<(Pubkey, DatasetState), FetchError>>,
+    cache: Option<(&'a mut dyn Cache, u64)>,
+    discovered: usize,
+    fetched: usize,
+}
+
+/// Enumerates the datasets recorded in the owner index of `authority`
+pub fn enumerate_datasets<'a, R: AccountFetcher>(
+    rpc: &'a R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+) -> DatasetIter<'a, R> {
+    DatasetIter {
+        rpc,
+        program_id: *program_id,
+        next_index: Some(find_owner_address_with_program_id(program_id, authority).0),
+        visited: HashSet::new(),
+        pending: VecDeque::new(),
+        ready: VecDeque::new(),
+        cache: None,
+        discovered: 0,
+        fetched: 0,
+    }
+}
+
+impl<'a, R: AccountFetcher> DatasetIter<'a, R> {
+    /// Reuses datasets `cache` holds from `min_slot` on, and records the
+    /// ones fetched into it
+    pub fn with_cache(mut self, cache: &'a mut dyn Cache, min_slot: u64) -> Self {
+        self.cache = Some((cache, min_slot));
+        self
+    }
+
+    /// Datasets recorded in the indexes walked so far
+    pub fn discovered(&self) -> usize {
+        self.discovered
+    }
+
+    /// Datasets fetched or taken from the cache so far
+    pub fn fetched(&self) -> usize {
+        self.fetched
+    }
+
+    /// Whether every index in the chain has been walked
+    pub fn indexes_walked(&self) -> bool {
+        self.next_index.is_none()
+    }
+
+    fn load_index(&mut self, address: Pubkey) -> Result<(), FetchError> {
+        let account = self
+            .rpc
+            .fetch_account(&address)?
+            .ok_or(FetchError::AccountMissing(address))?;
+        if account.owner != self.program_id {
+            return Err(FetchError::WrongOwner(address));
+        }
+        let index = AccountIndex::unpack_from_slice(&account.data)?;
+
+        self.visited.insert(address);
+        for entry in index.datasets.iter().flatten() {
+            self.pending.push_back(entry.dataset);
+            self.discovered += 1;
+        }
+        // A pointer back into the chain would otherwise loop forever
+        self.next_index = index
+            .pointer
+            .filter(|pointer| !self.visited.contains(pointer));
+
+        Ok(())
+    }
+
+    fn decode(&self, address: Pubkey, account: Account) -> Result<DatasetState, FetchError> {
+        if account.owner != self.program_id {
+            return Err(FetchError::WrongOwner(address));
+        }
+        Ok(DatasetState::unpack_from_slice(&account.data)?)
+    }
+
+    fn load_batch(&mut self) -> Result<(), FetchError> {
+        let batch_len = self.pending.len().min(MAX_MULTIPLE_ACCOUNTS);
+        let batch: Vec<Pubkey> = self.pending.drain(..batch_len).collect();
+
+        let mut accounts: Vec<Option<Account>> = vec![None; batch.len()];
+        let mut missing = vec![];
+        for (address, account) in batch.iter().zip(accounts.iter_mut()) {
+            *account = match &mut self.cache {
+                Some((cache, min_slot)) => cache.get(address, *min_slot),
+                None => None,
+            };
+            if account.is_none() {
+                missing.push(*address);
+            }
+        }
+
+        if !missing.is_empty() {
+            let (slot, fetched) = self.rpc.fetch_multiple_accounts(&missing)?;
+            let mut fetched = fetched.into_iter();
+            for (address, account) in batch.iter().zip(accounts.iter_mut()) {
+                if account.is_some() {
+                    continue;
+                }
+                *account = fetched.next().flatten();
+                if let (Some((cache, _)), Some(account)) = (&mut self.cache, account.as_ref()) {
+                    cache.insert(*address, slot, account.clone());
+                }
+            }
+        }
+
+        for (address, account) in batch.into_iter().zip(accounts) {
+            self.fetched += 1;
+            // Closed datasets are left as tombstones, or gone entirely
+            if let Some(account) = account.filter(|account| !DatasetState::is_closed(&account.data))
+            {
+                let dataset = self.decode(address, account);
+                self.ready
+                    .push_back(dataset.map(|dataset| (address, dataset)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, R: AccountFetcher> Iterator for DatasetIter<'a, R> {
+    type Item = Result<(Pubkey, DatasetState), FetchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+
+            let result = match self.next_index {
+                Some(address) if self.pending.len() < MAX_MULTIPLE_ACCOUNTS => {
+                    self.load_index(address)
+                }
+                _ if !self.pending.is_empty() => self.load_batch(),
+                _ => return None,
+            };
+
+            // Nothing after a failed request can be trusted to be complete
+            if let Err(e) = result {
+                self.next_index = None;
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use borsh::BorshDeserialize;
+use datanexus::events::{DataNexusEvent, PurchaseEvent, ShareEvent};
+use solana_sdk::pubkey::Pubkey;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Decodes the events logged through `sol_log_data` from a transaction's
+/// log messages, skipping data that does not decode as a `DataNexusEvent`
+pub fn parse_events(logs: &[String]) -> Vec<DataNexusEvent> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix(PROGRAM_DATA_PREFIX))
+        .filter_map(|data| base64::decode(data).ok())
+        .filter_map(|bytes| DataNexusEvent::try_from_slice(&bytes).ok())
+        .collect()
+}
+
+/// The purchases of `dataset` logged in a transaction's log messages
+pub fn parse_purchases(logs: &[String], dataset: &Pubkey) -> Vec<PurchaseEvent> {
+    parse_events(logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            DataNexusEvent::Purchase(purchase) if purchase.dataset == *dataset => Some(purchase),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The shares of `dataset` logged in a transaction's log messages
+pub fn parse_shares(logs: &[String], dataset: &Pubkey) -> Vec<ShareEvent> {
+    parse_events(logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            DataNexusEvent::Share(share) if share.dataset == *dataset => Some(share),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The shares of `dataset` held for their recipient's approval logged in a
+/// transaction's log messages
+pub fn parse_pending_shares(logs: &[String], dataset: &Pubkey) -> Vec<ShareEvent> {
+    parse_events(logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            DataNexusEvent::SharePending(share) if share.dataset == *dataset => Some(share),
+            _ => None,
+        })
+        .collect()
+}
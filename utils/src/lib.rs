@@ -1,58 +1,286 @@
-use datanexus::datanexus_program;
-use solana_sdk::pubkey::Pubkey;
+use datanexus::{
+    accounts::{RecipientAccounts, ShareAccessMultiAccounts},
+    instruction, pda,
+};
+use solana_sdk::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
 
-const OWNER_MARKER: &[u8; 5] = b"owner";
-const ACCESS_MARKER: &[u8; 6] = b"access";
+pub mod amount;
+pub mod attest;
+pub mod client;
+pub mod derivation;
+pub mod enumerate;
+pub mod events;
+pub mod lineage;
+pub mod migrate;
+#[cfg(feature = "nonblocking")]
+pub mod nonblocking;
+pub mod packer;
+#[cfg(feature = "pubsub")]
+pub mod pubsub;
+pub mod registry;
+pub mod rent;
+pub mod scan;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod stats;
 
-pub fn create_owner_address(authority: Pubkey) -> Pubkey {
-    Pubkey::create_program_address(
-        &[OWNER_MARKER, authority.as_ref()],
-        datanexus_program.as_ref(),
-    )
+pub use amount::{fetch_mint_decimals, format_ui_amount, parse_ui_amount, AmountError};
+pub use attest::{
+    build_access_attestation, verify_access_attestation, AccessProof, AttestationError,
+    SignedAttestation,
+};
+pub use derivation::detect_derivation_version;
+pub use enumerate::{enumerate_datasets, Cache, DatasetIter, MAX_MULTIPLE_ACCOUNTS};
+pub use events::{parse_events, parse_pending_shares, parse_purchases, parse_shares};
+pub use lineage::trace_share_chain;
+pub use migrate::{find_outdated_accounts, migrate_instructions, needs_migration};
+pub use packer::{PackError, PackItem, PackLimit, PackedTransaction, TransactionPacker};
+#[cfg(feature = "pubsub")]
+pub use pubsub::{subscribe_purchases, PurchaseStream, PurchaseSubscription};
+pub use registry::{lookup_dataset_by_hash, registry_accounts};
+pub use rent::{estimate_rent, AccountKind};
+pub use scan::{find_access_holders, find_datasets_by_owner, find_datasets_by_tag};
+#[cfg(feature = "snapshot")]
+pub use snapshot::{diff_snapshots, take_snapshot, Divergence, Snapshot, SNAPSHOT_VERSION};
+pub use stats::{fetch_dataset_stats, DatasetStats};
+
+pub use datanexus::pda::{
+    ACCESS_MARKER, ASSOCIATED_ACCESS_MARKER, COLLECTION_MARKER, DATASET_MARKER, OWNER_MARKER,
+    REGISTRY_MARKER,
+};
+
+/// Seeds an address is derived from
+///
+/// `V1` derives datasets from the bare hash and associated access accounts
+/// from the bare authority and dataset keys, `V2` prefixes both with their
+/// marker. Every other derivation has always been marker-prefixed and is the
+/// same under both.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DerivationVersion {
+    V1,
+    V2,
+}
+
+impl DerivationVersion {
+    /// Derivation new accounts are created at, used by the unversioned
+    /// functions
+    pub const LATEST: Self = DerivationVersion::V2;
+}
+
+impl Default for DerivationVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+pub fn find_owner_address_with_program_id(program_id: &Pubkey, authority: Pubkey) -> (Pubkey, u8) {
+    pda::find_owner_address(program_id, &authority)
 }
 
 pub fn get_owner_address(authority: Pubkey) -> Pubkey {
-    Pubkey::find_program_address(
-        &[OWNER_MARKER, authority.as_ref()],
-        datanexus_program.as_ref(),
-    )
-    .0
+    find_owner_address_with_program_id(&datanexus::id(), authority).0
 }
 
-pub fn create_access_address(authority: Pubkey) -> Pubkey {
-    Pubkey::create_program_address(
-        &[ACCESS_MARKER, authority.as_ref()],
-        datanexus_program.as_ref(),
-    )
+pub fn find_access_address_with_program_id(program_id: &Pubkey, authority: Pubkey) -> (Pubkey, u8) {
+    pda::find_access_address(program_id, &authority)
 }
 
 pub fn get_access_address(authority: Pubkey) -> Pubkey {
-    Pubkey::find_program_address(
-        &[ACCESS_MARKER, authority.as_ref()],
-        datanexus_program.as_ref(),
-    )
-    .0
+    find_access_address_with_program_id(&datanexus::id(), authority).0
 }
 
-pub fn create_dataset_address(hash: &[u8; 32]) -> Pubkey {
-    Pubkey::create_program_address(&[hash], datanexus_program.as_ref())
+pub fn find_dataset_address_with_program_id(program_id: &Pubkey, hash: &[u8; 32]) -> (Pubkey, u8) {
+    find_dataset_address_versioned_with_program_id(program_id, DerivationVersion::LATEST, hash)
 }
 
 pub fn get_dataset_address(hash: &[u8; 32]) -> Pubkey {
-    Pubkey::find_program_address(&[hash], datanexus_program.as_ref()).0
+    find_dataset_address_with_program_id(&datanexus::id(), hash).0
+}
+
+pub fn find_dataset_address_versioned_with_program_id(
+    program_id: &Pubkey,
+    version: DerivationVersion,
+    hash: &[u8; 32],
+) -> (Pubkey, u8) {
+    match version {
+        DerivationVersion::V1 => pda::find_legacy_dataset_address(program_id, hash),
+        DerivationVersion::V2 => pda::find_dataset_address(program_id, hash),
+    }
+}
+
+pub fn get_dataset_address_versioned(version: DerivationVersion, hash: &[u8; 32]) -> Pubkey {
+    find_dataset_address_versioned_with_program_id(&datanexus::id(), version, hash).0
+}
+
+pub fn find_collection_address_with_program_id(
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> (Pubkey, u8) {
+    pda::find_collection_address(program_id, hash)
+}
+
+pub fn get_collection_address(hash: &[u8; 32]) -> Pubkey {
+    find_collection_address_with_program_id(&datanexus::id(), hash).0
+}
+
+pub fn find_registry_address_with_program_id(
+    program_id: &Pubkey,
+    prefix: &[u8; 2],
+    page: u8,
+) -> (Pubkey, u8) {
+    pda::find_registry_address(program_id, prefix, page)
+}
+
+/// Registry page `page` of the prefix `hash` is listed under
+pub fn get_registry_address(hash: &[u8; 32], page: u8) -> Pubkey {
+    find_registry_address_with_program_id(&datanexus::id(), &pda::registry_prefix(hash), page).0
 }
 
-pub fn create_associated_access_address(authority: Pubkey, dataset_address: Pubkey) -> Pubkey {
-    Pubkey::create_program_address(
-        &[authority.as_ref(), dataset_address.as_ref()],
-        datanexus_program.as_ref(),
+/// Locates datasets created before `DATASET_MARKER` was added to the seeds
+#[deprecated(note = "use `find_dataset_address_versioned_with_program_id` with `V1`")]
+pub fn find_legacy_dataset_address_with_program_id(
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> (Pubkey, u8) {
+    find_dataset_address_versioned_with_program_id(program_id, DerivationVersion::V1, hash)
+}
+
+/// Locates datasets created before `DATASET_MARKER` was added to the seeds
+#[deprecated(note = "use `get_dataset_address_versioned` with `V1`")]
+pub fn get_legacy_dataset_address(hash: &[u8; 32]) -> Pubkey {
+    get_dataset_address_versioned(DerivationVersion::V1, hash)
+}
+
+pub fn find_associated_access_address_with_program_id(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    dataset_address: Pubkey,
+) -> (Pubkey, u8) {
+    find_associated_access_address_versioned_with_program_id(
+        program_id,
+        DerivationVersion::LATEST,
+        authority,
+        dataset_address,
     )
 }
 
 pub fn get_associated_access_address(authority: Pubkey, dataset_address: Pubkey) -> Pubkey {
-    Pubkey::find_program_address(
-        &[authority.as_ref(), dataset_address.as_ref()],
-        datanexus_program.as_ref(),
+    find_associated_access_address_with_program_id(&datanexus::id(), authority, dataset_address).0
+}
+
+pub fn find_associated_access_address_versioned_with_program_id(
+    program_id: &Pubkey,
+    version: DerivationVersion,
+    authority: Pubkey,
+    dataset_address: Pubkey,
+) -> (Pubkey, u8) {
+    match version {
+        DerivationVersion::V1 => {
+            pda::find_legacy_associated_access_address(program_id, &authority, &dataset_address)
+        }
+        DerivationVersion::V2 => {
+            pda::find_associated_access_address(program_id, &authority, &dataset_address)
+        }
+    }
+}
+
+pub fn get_associated_access_address_versioned(
+    version: DerivationVersion,
+    authority: Pubkey,
+    dataset_address: Pubkey,
+) -> Pubkey {
+    find_associated_access_address_versioned_with_program_id(
+        &datanexus::id(),
+        version,
+        authority,
+        dataset_address,
     )
     .0
 }
+
+/// Locates associated access accounts created before
+/// `ASSOCIATED_ACCESS_MARKER` was added to the seeds
+#[deprecated(note = "use `find_associated_access_address_versioned_with_program_id` with `V1`")]
+pub fn find_legacy_associated_access_address_with_program_id(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    dataset_address: Pubkey,
+) -> (Pubkey, u8) {
+    find_associated_access_address_versioned_with_program_id(
+        program_id,
+        DerivationVersion::V1,
+        authority,
+        dataset_address,
+    )
+}
+
+/// Locates associated access accounts created before
+/// `ASSOCIATED_ACCESS_MARKER` was added to the seeds
+#[deprecated(note = "use `get_associated_access_address_versioned` with `V1`")]
+pub fn get_legacy_associated_access_address(authority: Pubkey, dataset_address: Pubkey) -> Pubkey {
+    get_associated_access_address_versioned(DerivationVersion::V1, authority, dataset_address)
+}
+
+pub fn find_vault_address_with_program_id(
+    program_id: &Pubkey,
+    dataset_address: Pubkey,
+    mint: Pubkey,
+) -> (Pubkey, u8) {
+    pda::find_vault_address(program_id, &dataset_address, &mint)
+}
+
+pub fn get_vault_address(dataset_address: Pubkey, mint: Pubkey) -> Pubkey {
+    find_vault_address_with_program_id(&datanexus::id(), dataset_address, mint).0
+}
+
+pub fn find_receipt_mint_address_with_program_id(
+    program_id: &Pubkey,
+    dataset_address: Pubkey,
+) -> (Pubkey, u8) {
+    pda::find_receipt_mint_address(program_id, &dataset_address)
+}
+
+pub fn get_receipt_mint_address(dataset_address: Pubkey) -> Pubkey {
+    find_receipt_mint_address_with_program_id(&datanexus::id(), dataset_address).0
+}
+
+/// Creates a `ShareAccessMulti` instruction, deriving the access index and
+/// associated access account of each recipient
+pub fn share_access_multi(
+    program_id: Pubkey,
+    user_authority: Pubkey,
+    hash: &[u8; 32],
+    recipients: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let dataset_address = find_dataset_address_with_program_id(&program_id, hash).0;
+    let user_access_account = find_associated_access_address_with_program_id(
+        &program_id,
+        user_authority,
+        dataset_address,
+    )
+    .0;
+    let recipients = recipients
+        .iter()
+        .map(|recipient| RecipientAccounts {
+            authority: *recipient,
+            access_index: find_access_address_with_program_id(&program_id, *recipient).0,
+            access_account: find_associated_access_address_with_program_id(
+                &program_id,
+                *recipient,
+                dataset_address,
+            )
+            .0,
+        })
+        .collect();
+
+    instruction::share_access_multi(
+        program_id,
+        ShareAccessMultiAccounts {
+            user_authority,
+            user_access_account,
+            dataset_account: dataset_address,
+            recipients,
+        },
+        *hash,
+    )
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::client::{fetch_access_for, fetch_dataset, AccountFetcher, FetchError};
+
+/// Links followed before giving up on a chain, one more than the deepest
+/// grant the program can record
+pub const MAX_TRACE_DEPTH: usize = u8::MAX as usize + 1;
+
+#[derive(Debug)]
+pub enum TraceError {
+    Fetch(FetchError),
+    /// The authority, or a sharer along the chain, holds no access
+    NoAccess(Pubkey),
+    /// The chain leads back to an authority already visited
+    Cycle(Pubkey),
+    TooDeep,
+}
+
+impl From<FetchError> for TraceError {
+    fn from(e: FetchError) -> Self {
+        TraceError::Fetch(e)
+    }
+}
+
+/// Walks the `shared_from` links of the grants to the dataset registered
+/// under `hash`, starting at `authority` and ending at the purchaser, or at
+/// the dataset owner when the owner shared it directly
+///
+/// The chain starts with `authority` itself, so a purchased grant traces to
+/// a chain of one
+pub fn trace_share_chain<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+    hash: &[u8; 32],
+) -> Result<Vec<Pubkey>, TraceError> {
+    let owner = fetch_dataset(rpc, program_id, hash)?.owner;
+
+    let mut chain = vec![authority];
+    let mut visited = HashSet::new();
+    visited.insert(authority);
+    let mut current = authority;
+    while current != owner {
+        let access = fetch_access_for(rpc, program_id, current, hash)?
+            .ok_or(TraceError::NoAccess(current))?;
+        let shared_from = match access.shared_from {
+            Some(shared_from) => shared_from,
+            None => break,
+        };
+
+        if !visited.insert(shared_from) {
+            return Err(TraceError::Cycle(shared_from));
+        }
+        if chain.len() > MAX_TRACE_DEPTH {
+            return Err(TraceError::TooDeep);
+        }
+        chain.push(shared_from);
+        current = shared_from;
+    }
+
+    Ok(chain)
+}
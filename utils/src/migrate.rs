@@ -0,0 +1,123 @@
+use std::collections::HashSet;
+
+use datanexus::{
+    accounts::MigrateAccountAccounts,
+    instruction::migrate_account,
+    state::{migrate_layout, migrated_len, AccountIndex},
+};
+use solana_sdk::{
+    account::Account, instruction::Instruction, program_error::ProgramError, program_pack::Pack,
+    pubkey::Pubkey, system_program,
+};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    enumerate::MAX_MULTIPLE_ACCOUNTS,
+    find_access_address_with_program_id, find_associated_access_address_with_program_id,
+    find_owner_address_with_program_id,
+};
+
+/// Whether the account holding `data` was created under an outdated layout
+/// that `MigrateAccount` can bring up to date
+pub fn needs_migration(data: &[u8]) -> bool {
+    migrated_len(data).is_some()
+}
+
+/// The `MigrateAccount` instructions taking the account at `address`, which
+/// holds `data`, to its newest layout, one per layout version it is behind
+///
+/// Empty when the account is already at its newest layout.
+pub fn migrate_instructions(
+    program_id: Pubkey,
+    payer: Pubkey,
+    address: Pubkey,
+    data: &[u8],
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut data = data.to_vec();
+    let mut instructions = vec![];
+
+    // Each step is replayed locally so the next one sees the size it grew to
+    while let Some(len) = migrated_len(&data) {
+        let mut migrated = vec![0; len];
+        migrate_layout(&data, &mut migrated)?;
+        data = migrated;
+
+        instructions.push(migrate_account(
+            program_id,
+            MigrateAccountAccounts {
+                payer,
+                account: address,
+                system_program: system_program::id(),
+            },
+        )?);
+    }
+
+    Ok(instructions)
+}
+
+/// Fetches the accounts of `authority` left at an outdated layout: its owner
+/// and access indexes and the indexes chained to them, the datasets its
+/// owner indexes record and its associated access accounts for the datasets
+/// its access indexes record
+pub fn find_outdated_accounts<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+) -> Result<Vec<(Pubkey, Account)>, FetchError> {
+    let mut candidates = vec![];
+    let mut visited = HashSet::new();
+
+    for (index, is_owner) in [
+        (
+            find_owner_address_with_program_id(program_id, authority).0,
+            true,
+        ),
+        (
+            find_access_address_with_program_id(program_id, authority).0,
+            false,
+        ),
+    ] {
+        let mut next = Some(index);
+        while let Some(address) = next.take() {
+            if !visited.insert(address) {
+                break;
+            }
+            let account = match rpc.fetch_account(&address)? {
+                Some(account) if account.owner == *program_id => account,
+                _ => break,
+            };
+            let index = AccountIndex::unpack_from_slice(&account.data)?;
+
+            for entry in index.datasets.iter().flatten() {
+                candidates.push(if is_owner {
+                    entry.dataset
+                } else {
+                    find_associated_access_address_with_program_id(
+                        program_id,
+                        authority,
+                        entry.dataset,
+                    )
+                    .0
+                });
+            }
+            candidates.push(address);
+            next = index.pointer;
+        }
+    }
+
+    let mut outdated = vec![];
+    for batch in candidates.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let (_, accounts) = rpc.fetch_multiple_accounts(batch)?;
+        outdated.extend(
+            batch
+                .iter()
+                .zip(accounts)
+                .filter_map(|(address, account)| Some((*address, account?)))
+                .filter(|(_, account)| {
+                    account.owner == *program_id && needs_migration(&account.data)
+                }),
+        );
+    }
+
+    Ok(outdated)
+}
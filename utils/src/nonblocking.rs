@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use datanexus::state::{AccessInfo, AccessState, AccountIndex, CollectionState, DatasetState};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use crate::client::{self, FetchError, TransactionSender};
+
+/// Async counterpart of the `client` helpers for services running on tokio
+///
+/// solana-client 1.7 has no nonblocking `RpcClient`, so each RPC round trip
+/// runs on tokio's blocking pool instead of stalling the calling task
+pub struct DataNexusClient<R = RpcClient> {
+    rpc: Arc<R>,
+    program_id: Pubkey,
+}
+
+impl<R> Clone for DataNexusClient<R> {
+    fn clone(&self) -> Self {
+        Self {
+            rpc: self.rpc.clone(),
+            program_id: self.program_id,
+        }
+    }
+}
+
+impl DataNexusClient {
+    pub fn new(rpc: RpcClient) -> Self {
+        Self::with_program_id(rpc, datanexus::id())
+    }
+}
+
+impl<R: TransactionSender + Send + Sync + 'static> DataNexusClient<R> {
+    /// A client sending through `rpc` to the program deployed at
+    /// `program_id`
+    pub fn with_program_id(rpc: R, program_id: Pubkey) -> Self {
+        Self {
+            rpc: Arc::new(rpc),
+            program_id,
+        }
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, FetchError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&R, &Pubkey) -> Result<T, FetchError> + Send + 'static,
+    {
+        let rpc = self.rpc.clone();
+        let program_id = self.program_id;
+        tokio::task::spawn_blocking(move || f(&rpc, &program_id))
+            .await
+            .expect("RPC task panicked")
+    }
+
+    pub async fn fetch_dataset(&self, hash: [u8; 32]) -> Result<DatasetState, FetchError> {
+        self.run(move |rpc, program_id| client::fetch_dataset(rpc, program_id, &hash)).await
+    }
+
+    pub async fn fetch_collection(&self, hash: [u8; 32]) -> Result<CollectionState, FetchError> {
+        self.run(move |rpc, program_id| client::fetch_collection(rpc, program_id, &hash))
+            .await
+    }
+
+    pub async fn fetch_owner_index(&self, authority: Pubkey) -> Result<AccountIndex, FetchError> {
+        self.run(move |rpc, program_id| client::fetch_owner_index(rpc, program_id, authority))
+            .await
+    }
+
+    pub async fn fetch_access_index(&self, authority: Pubkey) -> Result<AccountIndex, FetchError> {
+        self.run(move |rpc, program_id| client::fetch_access_index(rpc, program_id, authority))
+            .await
+    }
+
+    pub async fn fetch_access_state(
+        &self,
+        authority: Pubkey,
+        hash: [u8; 32],
+    ) -> Result<AccessState, FetchError> {
+        self.run(move |rpc, program_id| {
+            client::fetch_access_state(rpc, program_id, authority, &hash)
+        })
+        .await
+    }
+
+    pub async fn fetch_access_for(
+        &self,
+        authority: Pubkey,
+        hash: [u8; 32],
+    ) -> Result<Option<AccessInfo>, FetchError> {
+        self.run(move |rpc, program_id| {
+            client::fetch_access_for(rpc, program_id, authority, &hash)
+        })
+        .await
+    }
+
+    /// Signs `instructions` with `payer` and waits for confirmation
+    pub async fn send_instructions<S: Signer + Sync>(
+        &self,
+        payer: &S,
+        instructions: Vec<Instruction>,
+    ) -> Result<Signature, FetchError> {
+        let recent_blockhash = self.run(|rpc, _| Ok(rpc.latest_blockhash()?)).await?;
+
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[payer], recent_blockhash);
+
+        self.run(move |rpc, _| Ok(rpc.send_and_confirm(&transaction)?))
+            .await
+    }
+
+    /// Purchases access to the dataset registered under `hash`, creating the
+    /// payer's associated access account first when it is missing
+    pub async fn purchase_access<S: Signer + Sync>(
+        &self,
+        payer: &S,
+        hash: [u8; 32],
+        token_account: Pubkey,
+        amount: u64,
+    ) -> Result<Signature, FetchError> {
+        let user_authority = payer.pubkey();
+        let instructions = self
+            .run(move |rpc, program_id| {
+                client::purchase_access_instructions(
+                    rpc,
+                    program_id,
+                    user_authority,
+                    &hash,
+                    token_account,
+                    amount,
+                    None,
+                    None,
+                )
+            })
+            .await?;
+
+        self.send_instructions(payer, instructions).await
+    }
+
+    /// Purchases the collection registered under `hash`, sending one
+    /// transaction per batch of members and returning their signatures
+    pub async fn purchase_collection<S: Signer + Sync>(
+        &self,
+        payer: &S,
+        hash: [u8; 32],
+        token_account: Pubkey,
+        amount: u64,
+    ) -> Result<Vec<Signature>, FetchError> {
+        let user_authority = payer.pubkey();
+        let transactions = self
+            .run(move |rpc, program_id| {
+                client::purchase_collection_transactions(
+                    rpc,
+                    program_id,
+                    user_authority,
+                    &hash,
+                    token_account,
+                    amount,
+                )
+            })
+            .await?;
+
+        let mut signatures = vec![];
+        for instructions in transactions {
+            signatures.push(self.send_instructions(payer, instructions).await?);
+        }
+
+        Ok(signatures)
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use solana_sdk::{
+    instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    signature::Signature, transaction::Transaction,
+};
+
+use crate::client::Plan;
+
+/// Most distinct accounts a transaction may lock
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+
+/// Compute units a transaction may use without a compute budget instruction
+/// raising its limit
+pub const DEFAULT_TRANSACTION_UNITS: u64 = 200_000;
+
+/// Rough compute cost of a DataNexus instruction, the estimate of items
+/// built without one
+pub const ESTIMATED_UNITS_PER_INSTRUCTION: u64 = 40_000;
+
+/// Instructions that must land in the same transaction, along with what
+/// the packer needs to know to place them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackItem {
+    pub instructions: Vec<Instruction>,
+    /// Compute units the instructions are estimated to use
+    pub units: u64,
+    /// Accounts the instructions create, items using any of them are
+    /// packed after this one
+    pub created: Vec<Pubkey>,
+}
+
+impl PackItem {
+    /// `instructions` creating no accounts, estimated at
+    /// `ESTIMATED_UNITS_PER_INSTRUCTION` each
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        let units = ESTIMATED_UNITS_PER_INSTRUCTION.saturating_mul(instructions.len() as u64);
+        Self {
+            instructions,
+            units,
+            created: vec![],
+        }
+    }
+
+    pub fn with_units(mut self, units: u64) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn with_created(mut self, created: Vec<Pubkey>) -> Self {
+        self.created = created;
+        self
+    }
+
+    /// Keys the instructions need a signature from
+    pub fn signers(&self) -> Vec<Pubkey> {
+        let mut signers = vec![];
+        for meta in self.instructions.iter().flat_map(|ix| &ix.accounts) {
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                signers.push(meta.pubkey);
+            }
+        }
+        signers
+    }
+
+    /// Whether any instruction passes `address` or runs it as a program
+    fn uses(&self, address: &Pubkey) -> bool {
+        self.instructions.iter().any(|instruction| {
+            instruction.program_id == *address
+                || instruction
+                    .accounts
+                    .iter()
+                    .any(|meta| meta.pubkey == *address)
+        })
+    }
+}
+
+impl From<Plan> for PackItem {
+    fn from(plan: Plan) -> Self {
+        Self::new(plan.instructions).with_created(plan.created)
+    }
+}
+
+/// Transaction limit an item exceeds on its own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PackLimit {
+    /// Serialized size, `PACKET_DATA_SIZE` unless set otherwise
+    Size,
+    /// Distinct accounts, `MAX_TRANSACTION_ACCOUNTS` unless set otherwise
+    Accounts,
+    /// Estimated compute units, `DEFAULT_TRANSACTION_UNITS` unless set
+    /// otherwise
+    Units,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PackError {
+    /// The item at `item` doesn't fit in a transaction even alone
+    TooLarge { item: usize, limit: PackLimit },
+    /// The items at these indices can't be ordered, some of them use
+    /// accounts the others create in a cycle
+    DependencyCycle(Vec<usize>),
+}
+
+/// Items to send together as one transaction
+#[derive(Debug, PartialEq)]
+pub struct PackedTransaction {
+    /// Indices of the items packed, in the order their instructions run
+    pub items: Vec<usize>,
+    /// The items' instructions, without the packer's prefix and suffix
+    pub instructions: Vec<Instruction>,
+    /// Keys that must sign the transaction, the fee payer first
+    pub signers: Vec<Pubkey>,
+    pub units: u64,
+}
+
+/// Packs items of instructions into as few transactions as fit within the
+/// size, account and compute limits set
+///
+/// Items are never split, so each lands or fails as a whole. They keep the
+/// order they are given in except where an item uses an account a later one
+/// creates, which moves the creating item ahead of it.
+#[derive(Clone, Debug)]
+pub struct TransactionPacker {
+    fee_payer: Pubkey,
+    prefix: Vec<Instruction>,
+    suffix: Vec<Instruction>,
+    max_size: usize,
+    max_accounts: usize,
+    max_units: u64,
+}
+
+impl TransactionPacker {
+    /// A packer for transactions paid for by `fee_payer`, within the
+    /// cluster's default limits
+    pub fn new(fee_payer: Pubkey) -> Self {
+        Self {
+            fee_payer,
+            prefix: vec![],
+            suffix: vec![],
+            max_size: PACKET_DATA_SIZE,
+            max_accounts: MAX_TRANSACTION_ACCOUNTS,
+            max_units: DEFAULT_TRANSACTION_UNITS,
+        }
+    }
+
+    /// Leaves room for `prefix` in every transaction, such as compute budget
+    /// instructions the sender adds. Its size and accounts count towards the
+    /// limits but its units don't
+    pub fn with_prefix(mut self, prefix: Vec<Instruction>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Leaves room for `suffix` after every transaction's instructions, such
+    /// as a memo the sender appends. It counts towards the limits like the
+    /// prefix
+    pub fn with_suffix(mut self, suffix: Vec<Instruction>) -> Self {
+        self.suffix = suffix;
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn with_max_accounts(mut self, max_accounts: usize) -> Self {
+        self.max_accounts = max_accounts;
+        self
+    }
+
+    pub fn with_max_units(mut self, max_units: u64) -> Self {
+        self.max_units = max_units;
+        self
+    }
+
+    /// Packs `items` into transactions in the order they are to be sent
+    ///
+    /// Each transaction takes as many of the next items as fit, which for
+    /// items kept in order makes the fewest transactions. Fails with the
+    /// first item that doesn't fit in a transaction alone.
+    pub fn pack(&self, items: &[PackItem]) -> Result<Vec<PackedTransaction>, PackError> {
+        let order = dependency_order(items)?;
+
+        let mut transactions = vec![];
+        let mut current: Option<PackedTransaction> = None;
+        for index in order {
+            let item = &items[index];
+            if let Some(limit) = self.exceeded(&item.instructions, item.units) {
+                return Err(PackError::TooLarge { item: index, limit });
+            }
+
+            if let Some(transaction) = current.as_mut() {
+                let mut instructions = transaction.instructions.clone();
+                instructions.extend(item.instructions.iter().cloned());
+                let units = transaction.units.saturating_add(item.units);
+                if self.exceeded(&instructions, units).is_none() {
+                    transaction.items.push(index);
+                    transaction.instructions = instructions;
+                    transaction.units = units;
+                    continue;
+                }
+                transactions.extend(current.take());
+            }
+
+            current = Some(PackedTransaction {
+                items: vec![index],
+                instructions: item.instructions.clone(),
+                signers: vec![],
+                units: item.units,
+            });
+        }
+        transactions.extend(current);
+
+        for transaction in &mut transactions {
+            let message = Message::new(&transaction.instructions, Some(&self.fee_payer));
+            transaction.signers = message.account_keys
+                [..usize::from(message.header.num_required_signatures)]
+                .to_vec();
+        }
+
+        Ok(transactions)
+    }
+
+    /// Limit a transaction carrying `instructions` between the prefix and
+    /// the suffix would exceed
+    fn exceeded(&self, instructions: &[Instruction], units: u64) -> Option<PackLimit> {
+        if units > self.max_units {
+            return Some(PackLimit::Units);
+        }
+
+        let instructions: Vec<Instruction> = self
+            .prefix
+            .iter()
+            .chain(instructions)
+            .chain(&self.suffix)
+            .cloned()
+            .collect();
+        let message = Message::new(&instructions, Some(&self.fee_payer));
+        if message.account_keys.len() > self.max_accounts {
+            Some(PackLimit::Accounts)
+        } else if message_size(message) > self.max_size {
+            Some(PackLimit::Size)
+        } else {
+            None
+        }
+    }
+}
+
+/// Serialized size of a transaction carrying `instructions` paid for by
+/// `fee_payer`, with placeholder signatures
+pub fn transaction_size(instructions: &[Instruction], fee_payer: &Pubkey) -> usize {
+    message_size(Message::new(instructions, Some(fee_payer)))
+}
+
+/// Serialized size of a transaction of `message`, with placeholder
+/// signatures
+pub fn message_size(message: Message) -> usize {
+    let transaction = Transaction {
+        signatures: vec![Signature::default(); usize::from(message.header.num_required_signatures)],
+        message,
+    };
+    bincode::serialized_size(&transaction).map_or(usize::MAX, |size| size as usize)
+}
+
+/// Indices of `items` in the order they run, each after the items creating
+/// the accounts it uses and otherwise in the order given
+fn dependency_order(items: &[PackItem]) -> Result<Vec<usize>, PackError> {
+    let mut creators: HashMap<Pubkey, Vec<usize>> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        for address in &item.created {
+            creators.entry(*address).or_default().push(index);
+        }
+    }
+    let after: Vec<Vec<usize>> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let mut after: Vec<usize> = creators
+                .iter()
+                .filter(|(address, _)| item.uses(address))
+                .flat_map(|(_, creators)| creators.iter().copied())
+                .filter(|creator| *creator != index)
+                .collect();
+            after.sort_unstable();
+            after.dedup();
+            after
+        })
+        .collect();
+
+    let mut placed = vec![false; items.len()];
+    let mut order = Vec::with_capacity(items.len());
+    while order.len() < items.len() {
+        let next = (0..items.len())
+            .find(|index| !placed[*index] && after[*index].iter().all(|creator| placed[*creator]));
+        match next {
+            Some(index) => {
+                placed[index] = true;
+                order.push(index);
+            }
+            None => {
+                return Err(PackError::DependencyCycle(
+                    (0..items.len()).filter(|index| !placed[*index]).collect(),
+                ))
+            }
+        }
+    }
+
+    Ok(order)
+}
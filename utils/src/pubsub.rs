@@ -0,0 +1,135 @@
+use std::{collections::VecDeque, thread};
+
+use datanexus::events::PurchaseEvent;
+use solana_client::{
+    pubsub_client::{LogsSubscription, PubsubClient, PubsubClientError},
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::events::parse_purchases;
+
+/// Purchases of one dataset as the cluster confirms them, read from the
+/// logs of every transaction that mentions the program
+///
+/// Iterating blocks until the next purchase arrives and ends when the
+/// websocket closes. Failed transactions are skipped.
+pub struct PurchaseSubscription {
+    subscription: LogsSubscription,
+    dataset: Pubkey,
+    pending: VecDeque<PurchaseEvent>,
+}
+
+#[allow(clippy::result_large_err)]
+impl PurchaseSubscription {
+    pub fn new(ws_url: &str, dataset: Pubkey) -> Result<Self, PubsubClientError> {
+        Self::with_program_id(
+            ws_url,
+            &datanexus::id(),
+            dataset,
+            CommitmentConfig::confirmed(),
+        )
+    }
+
+    /// Subscribes to purchases of `dataset` from the program deployed at
+    /// `program_id`, as they reach `commitment`
+    pub fn with_program_id(
+        ws_url: &str,
+        program_id: &Pubkey,
+        dataset: Pubkey,
+        commitment: CommitmentConfig,
+    ) -> Result<Self, PubsubClientError> {
+        let subscription = PubsubClient::logs_subscribe(
+            ws_url,
+            RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )?;
+
+        Ok(Self {
+            subscription,
+            dataset,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+impl Iterator for PurchaseSubscription {
+    type Item = PurchaseEvent;
+
+    fn next(&mut self) -> Option<PurchaseEvent> {
+        loop {
+            if let Some(purchase) = self.pending.pop_front() {
+                return Some(purchase);
+            }
+
+            let response = self.subscription.1.recv().ok()?;
+            if response.value.err.is_none() {
+                self.pending
+                    .extend(parse_purchases(&response.value.logs, &self.dataset));
+            }
+        }
+    }
+}
+
+impl Drop for PurchaseSubscription {
+    fn drop(&mut self) {
+        let _ = self.subscription.0.send_unsubscribe();
+        let _ = self.subscription.0.shutdown();
+    }
+}
+
+/// Calls `callback` with each purchase of `dataset` until the websocket at
+/// `ws_url` closes
+#[allow(clippy::result_large_err)]
+pub fn subscribe_purchases<F>(
+    ws_url: &str,
+    dataset: Pubkey,
+    mut callback: F,
+) -> Result<(), PubsubClientError>
+where
+    F: FnMut(PurchaseEvent),
+{
+    for purchase in PurchaseSubscription::new(ws_url, dataset)? {
+        callback(purchase);
+    }
+
+    Ok(())
+}
+
+/// Async counterpart of `PurchaseSubscription` for services running on tokio
+///
+/// solana-client 1.7 has no nonblocking pubsub client, so the subscription
+/// is read on its own thread, which exits at the first purchase after the
+/// stream is dropped
+pub struct PurchaseStream {
+    receiver: tokio::sync::mpsc::UnboundedReceiver<PurchaseEvent>,
+}
+
+#[allow(clippy::result_large_err)]
+impl PurchaseStream {
+    pub fn new(ws_url: &str, dataset: Pubkey) -> Result<Self, PubsubClientError> {
+        Ok(Self::from_subscription(PurchaseSubscription::new(
+            ws_url, dataset,
+        )?))
+    }
+
+    pub fn from_subscription(subscription: PurchaseSubscription) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        thread::spawn(move || {
+            for purchase in subscription {
+                if sender.send(purchase).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+
+    /// The next purchase, `None` once the websocket closes
+    pub async fn next(&mut self) -> Option<PurchaseEvent> {
+        self.receiver.recv().await
+    }
+}
@@ -0,0 +1,111 @@
+use datanexus::{
+    accounts::RegistryAccounts, error::DataNexusError, pda::registry_prefix, state::RegistryState,
+};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    find_registry_address_with_program_id,
+};
+
+/// Fetches the registry page at `address`, `None` when it doesn't exist
+fn fetch_page<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    address: &Pubkey,
+) -> Result<Option<RegistryState>, FetchError> {
+    let account = match rpc.fetch_account(address)? {
+        Some(account) => account,
+        None => return Ok(None),
+    };
+
+    if account.owner != *program_id {
+        return Err(FetchError::WrongOwner(*address));
+    }
+
+    Ok(Some(RegistryState::unpack_from_slice(&account.data)?))
+}
+
+/// Looks up the dataset registered under `hash` in the registry, without
+/// knowing its address or owner
+///
+/// Fetches the first page of the hash's prefix and scans it, following the
+/// chain to overflow pages until the hash is found. `None` when the dataset
+/// was never listed, including when its prefix has no page yet.
+pub fn lookup_dataset_by_hash<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<Option<Pubkey>, FetchError> {
+    let mut address =
+        find_registry_address_with_program_id(program_id, &registry_prefix(hash), 0).0;
+
+    // Pages are numbered by a single byte, so no chain is longer than this
+    for _ in 0..=u8::MAX {
+        let page = match fetch_page(rpc, program_id, &address)? {
+            Some(page) => page,
+            None => return Ok(None),
+        };
+        if let Some(dataset) = page.find(hash) {
+            return Ok(Some(dataset));
+        }
+        match page.next {
+            Some(next) => address = next,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Registry accounts to pass when creating the dataset of `hash`, so that
+/// it is listed on the last page of its prefix
+///
+/// That is the first page when the prefix has none yet, which the program
+/// creates. When the last page is full the overflow page to create after it
+/// is passed along with it.
+pub fn registry_accounts<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<RegistryAccounts<Pubkey>, FetchError> {
+    let prefix = registry_prefix(hash);
+    let mut registry_account = find_registry_address_with_program_id(program_id, &prefix, 0).0;
+
+    // The last page chains to the one numbered after it, so a prefix runs
+    // out of pages once page `u8::MAX` fills up
+    for _ in 0..=u8::MAX {
+        let page = match fetch_page(rpc, program_id, &registry_account)? {
+            Some(page) => page,
+            None => {
+                return Ok(RegistryAccounts {
+                    registry_account,
+                    overflow_account: None,
+                })
+            }
+        };
+        if let Some(next) = page.next {
+            registry_account = next;
+            continue;
+        }
+
+        let overflow_account = if page.is_full() {
+            match page.page.checked_add(1) {
+                Some(next_page) => {
+                    Some(find_registry_address_with_program_id(program_id, &prefix, next_page).0)
+                }
+                None => break,
+            }
+        } else {
+            None
+        };
+        return Ok(RegistryAccounts {
+            registry_account,
+            overflow_account,
+        });
+    }
+
+    Err(FetchError::DecodeFailed(
+        DataNexusError::RegistryFull.into(),
+    ))
+}
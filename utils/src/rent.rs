@@ -0,0 +1,60 @@
+use datanexus::state::{
+    AccessState, AccountIndex, CollectionAccess, CollectionState, DatasetState,
+};
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_sdk::program_error::ProgramError;
+
+/// DataNexus accounts a client can ask users to pay for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountKind {
+    OwnerIndex,
+    AccessIndex,
+    Dataset,
+    AssociatedAccess,
+    Collection,
+    CollectionAccess,
+}
+
+impl AccountKind {
+    /// Bytes the account takes up, `capacity` is the number of index slots
+    /// or access entries and is ignored for the fixed size accounts
+    pub fn space(self, capacity: usize) -> Result<usize, ProgramError> {
+        match self {
+            AccountKind::OwnerIndex | AccountKind::AccessIndex => AccountIndex::space(capacity),
+            AccountKind::Dataset => Ok(DatasetState::space()),
+            AccountKind::AssociatedAccess => AccessState::space(capacity),
+            AccountKind::Collection => Ok(CollectionState::space()),
+            AccountKind::CollectionAccess => Ok(CollectionAccess::space()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RentError {
+    Client(Box<ClientError>),
+    Space(ProgramError),
+}
+
+impl From<ClientError> for RentError {
+    fn from(e: ClientError) -> Self {
+        RentError::Client(Box::new(e))
+    }
+}
+
+impl From<ProgramError> for RentError {
+    fn from(e: ProgramError) -> Self {
+        RentError::Space(e)
+    }
+}
+
+/// Lamports needed to create an account of `kind` rent exempt, at the rent
+/// the cluster currently charges
+pub fn estimate_rent(
+    rpc: &RpcClient,
+    kind: AccountKind,
+    capacity: usize,
+) -> Result<u64, RentError> {
+    let space = kind.space(capacity)?;
+
+    Ok(rpc.get_minimum_balance_for_rent_exemption(space)?)
+}
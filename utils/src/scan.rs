@@ -0,0 +1,139 @@
+use datanexus::state::{
+    AccessInfo, AccessState, DataNexusAccount, DatasetState, Tag, MAX_TAGS, TAG_LEN,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+
+use crate::client::FetchError;
+
+/// Offset of the `AccountFlag` byte in a `DatasetState`, behind the
+/// version header and `is_initialized`
+const DATASET_FLAG_OFFSET: usize = 2;
+
+/// `AccountFlag::Dataset` as packed
+const DATASET_FLAG: u8 = 1;
+
+/// Offset of the owner pubkey in a `DatasetState`
+const DATASET_OWNER_OFFSET: usize = 3;
+
+// The filters above depend on this layout, fail the build if it moves
+const _: [(); 946] = [(); DatasetState::LEN];
+const _: [(); 812] = [(); DatasetState::TAGS_OFFSET];
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes.to_vec()))
+}
+
+fn program_accounts(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, FetchError> {
+    let accounts = rpc.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(rpc.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: None,
+        },
+    )?;
+
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| (address, account.data))
+        .collect())
+}
+
+/// Lists every dataset registered by `owner`, datasets of an older layout
+/// are left out until migrated
+pub fn find_datasets_by_owner(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    owner: Pubkey,
+) -> Result<Vec<(Pubkey, DatasetState)>, FetchError> {
+    let filters = vec![
+        RpcFilterType::DataSize(DatasetState::LEN as u64),
+        memcmp(0, &[DatasetState::LAYOUT_VERSION]),
+        memcmp(DATASET_FLAG_OFFSET, &[DATASET_FLAG]),
+        memcmp(DATASET_OWNER_OFFSET, owner.as_ref()),
+    ];
+
+    program_accounts(rpc, program_id, filters)?
+        .into_iter()
+        .map(|(address, data)| match DataNexusAccount::decode(&data)? {
+            DataNexusAccount::Dataset(dataset) => Ok((address, *dataset)),
+            _ => Err(ProgramError::InvalidAccountData.into()),
+        })
+        .collect()
+}
+
+/// Lists every dataset tagged with `tag`, datasets of an older layout are
+/// left out until migrated
+///
+/// A tag can sit in any of the `MAX_TAGS` slots from `TAGS_OFFSET`, so one
+/// memcmp query is made per slot and a dataset is listed once whichever slot
+/// matched
+pub fn find_datasets_by_tag(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    tag: &Tag,
+) -> Result<Vec<(Pubkey, DatasetState)>, FetchError> {
+    let mut datasets: Vec<(Pubkey, DatasetState)> = vec![];
+    for slot in 0..MAX_TAGS {
+        let filters = vec![
+            RpcFilterType::DataSize(DatasetState::LEN as u64),
+            memcmp(0, &[DatasetState::LAYOUT_VERSION]),
+            memcmp(DATASET_FLAG_OFFSET, &[DATASET_FLAG]),
+            memcmp(DatasetState::TAGS_OFFSET + slot * TAG_LEN, tag),
+        ];
+        for (address, data) in program_accounts(rpc, program_id, filters)? {
+            if datasets.iter().any(|(listed, _)| *listed == address) {
+                continue;
+            }
+            match DataNexusAccount::decode(&data)? {
+                DataNexusAccount::Dataset(dataset) => datasets.push((address, *dataset)),
+                _ => return Err(ProgramError::InvalidAccountData.into()),
+            }
+        }
+    }
+
+    Ok(datasets)
+}
+
+/// Lists the associated access accounts holding access to the dataset
+/// registered under `hash`
+///
+/// A grant can sit in any entry, so every access account of the current
+/// layout is fetched and its entries are searched here
+pub fn find_access_holders(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<Vec<(Pubkey, AccessInfo)>, FetchError> {
+    let filters = vec![
+        RpcFilterType::DataSize(AccessState::LEN as u64),
+        memcmp(0, &[AccessState::LAYOUT_VERSION]),
+    ];
+
+    let mut holders = vec![];
+    for (address, data) in program_accounts(rpc, program_id, filters)? {
+        let decoded = DataNexusAccount::decode(&data)?;
+        let access = decoded
+            .as_access()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if let Some(entry) = access.find_entry(hash) {
+            holders.push((address, access.datasets[entry]));
+        }
+    }
+
+    Ok(holders)
+}
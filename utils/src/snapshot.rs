@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashSet};
+
+use datanexus::{
+    serde_fields,
+    state::{AccessState, AccountIndex, DatasetState},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+use crate::{
+    client::{AccountFetcher, FetchError},
+    enumerate::MAX_MULTIPLE_ACCOUNTS,
+    find_access_address_with_program_id, find_associated_access_address_with_program_id,
+    find_owner_address_with_program_id,
+};
+
+/// Version of the `Snapshot` document, bumped whenever its shape changes
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// An account decoded as `T` and the address it was read from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotAccount<T> {
+    #[serde(with = "serde_fields::pubkey")]
+    pub address: Pubkey,
+    pub state: T,
+}
+
+/// The decoded accounts of an authority at one point in time: its owner and
+/// access indexes and the indexes chained to them, every dataset those
+/// indexes record and its associated access accounts for the datasets its
+/// access indexes record
+///
+/// Accounts missing when the snapshot is taken are left out, so a dataset
+/// closed since it was indexed has no entry.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    #[serde(with = "serde_fields::pubkey")]
+    pub program_id: Pubkey,
+    #[serde(with = "serde_fields::pubkey")]
+    pub authority: Pubkey,
+    pub owner_indexes: Vec<SnapshotAccount<AccountIndex>>,
+    pub access_indexes: Vec<SnapshotAccount<AccountIndex>>,
+    pub datasets: Vec<SnapshotAccount<DatasetState>>,
+    pub associated_access: Vec<SnapshotAccount<AccessState>>,
+}
+
+/// A field of an account differing between two snapshots, `None` on the
+/// side the account or field is missing from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Dot separated path of the field, starting with the snapshot section
+    /// and the account address
+    pub path: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+/// Walks the index at `first` and the indexes chained to it, none when
+/// `first` doesn't exist
+fn fetch_index_chain<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    first: Pubkey,
+) -> Result<Vec<SnapshotAccount<AccountIndex>>, FetchError> {
+    let mut indexes = vec![];
+    let mut visited = HashSet::new();
+
+    let mut next = Some(first);
+    // A pointer back into the chain would otherwise loop forever
+    while let Some(address) = next.take().filter(|address| visited.insert(*address)) {
+        let account = match rpc.fetch_account(&address)? {
+            Some(account) => account,
+            None => break,
+        };
+        let state: AccountIndex = decode(program_id, &address, &account)?;
+        next = state.pointer;
+        indexes.push(SnapshotAccount { address, state });
+    }
+
+    Ok(indexes)
+}
+
+fn decode<T: Pack>(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    account: &Account,
+) -> Result<T, FetchError> {
+    if account.owner != *program_id {
+        return Err(FetchError::WrongOwner(*address));
+    }
+    Ok(T::unpack_from_slice(&account.data)?)
+}
+
+/// Fetches and decodes the accounts at `addresses` that exist,
+/// `MAX_MULTIPLE_ACCOUNTS` at a time
+fn fetch_existing<R: AccountFetcher, T: Pack>(
+    rpc: &R,
+    program_id: &Pubkey,
+    addresses: &[Pubkey],
+) -> Result<Vec<SnapshotAccount<T>>, FetchError> {
+    let mut fetched = vec![];
+    for batch in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let (_, accounts) = rpc.fetch_multiple_accounts(batch)?;
+        for (address, account) in batch.iter().zip(accounts) {
+            if let Some(account) = account {
+                fetched.push(SnapshotAccount {
+                    address: *address,
+                    state: decode(program_id, address, &account)?,
+                });
+            }
+        }
+    }
+    Ok(fetched)
+}
+
+/// Fetches and decodes every account of `authority` a `Snapshot` records
+pub fn take_snapshot<R: AccountFetcher>(
+    rpc: &R,
+    program_id: &Pubkey,
+    authority: Pubkey,
+) -> Result<Snapshot, FetchError> {
+    let owner_indexes = fetch_index_chain(
+        rpc,
+        program_id,
+        find_owner_address_with_program_id(program_id, authority).0,
+    )?;
+    let access_indexes = fetch_index_chain(
+        rpc,
+        program_id,
+        find_access_address_with_program_id(program_id, authority).0,
+    )?;
+
+    let entries = |indexes: &[SnapshotAccount<AccountIndex>]| -> Vec<Pubkey> {
+        indexes
+            .iter()
+            .flat_map(|index| index.state.datasets.iter().flatten())
+            .map(|entry| entry.dataset)
+            .collect()
+    };
+    let accessed = entries(&access_indexes);
+
+    // Datasets both owned and accessed are recorded once
+    let mut seen = HashSet::new();
+    let datasets: Vec<Pubkey> = entries(&owner_indexes)
+        .into_iter()
+        .chain(accessed.iter().copied())
+        .filter(|dataset| seen.insert(*dataset))
+        .collect();
+    let associated_access: Vec<Pubkey> = accessed
+        .iter()
+        .map(|dataset| {
+            find_associated_access_address_with_program_id(program_id, authority, *dataset).0
+        })
+        .collect();
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        program_id: *program_id,
+        authority,
+        owner_indexes,
+        access_indexes,
+        datasets: fetch_existing(rpc, program_id, &datasets)?,
+        associated_access: fetch_existing(rpc, program_id, &associated_access)?,
+    })
+}
+
+/// The decoded states of `accounts` as JSON, keyed by address
+fn by_address<T: Serialize>(accounts: &[SnapshotAccount<T>]) -> BTreeMap<String, Value> {
+    accounts
+        .iter()
+        .map(|account| {
+            (
+                account.address.to_string(),
+                serde_json::to_value(&account.state).expect("state serializes to JSON"),
+            )
+        })
+        .collect()
+}
+
+/// Records where `expected` and `actual` differ below `path`, descending
+/// into objects and into arrays of the same length. Unset fields are left
+/// out of the JSON, so a field only one side has is a divergence.
+fn diff_values(path: String, expected: &Value, actual: &Value, divergences: &mut Vec<Divergence>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (field, value) in expected {
+                let path = format!("{}.{}", path, field);
+                match actual.get(field) {
+                    Some(actual) => diff_values(path, value, actual, divergences),
+                    None => divergences.push(Divergence {
+                        path,
+                        expected: Some(value.clone()),
+                        actual: None,
+                    }),
+                }
+            }
+            for (field, value) in actual {
+                if !expected.contains_key(field) {
+                    divergences.push(Divergence {
+                        path: format!("{}.{}", path, field),
+                        expected: None,
+                        actual: Some(value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual))
+            if expected.len() == actual.len() && !expected.iter().all(Value::is_number) =>
+        {
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff_values(format!("{}.{}", path, i), expected, actual, divergences);
+            }
+        }
+        _ if expected != actual => divergences.push(Divergence {
+            path,
+            expected: Some(expected.clone()),
+            actual: Some(actual.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_section(
+    section: &str,
+    expected: BTreeMap<String, Value>,
+    mut actual: BTreeMap<String, Value>,
+    divergences: &mut Vec<Divergence>,
+) {
+    for (address, expected) in expected {
+        let path = format!("{}.{}", section, address);
+        match actual.remove(&address) {
+            Some(actual) => diff_values(path, &expected, &actual, divergences),
+            None => divergences.push(Divergence {
+                path,
+                expected: Some(expected),
+                actual: None,
+            }),
+        }
+    }
+    divergences.extend(actual.into_iter().map(|(address, actual)| Divergence {
+        path: format!("{}.{}", section, address),
+        expected: None,
+        actual: Some(actual),
+    }));
+}
+
+/// Every field of every account differing between `expected` and
+/// `actual`, accounts are matched by address
+pub fn diff_snapshots(expected: &Snapshot, actual: &Snapshot) -> Vec<Divergence> {
+    let mut divergences = vec![];
+    diff_section(
+        "owner_indexes",
+        by_address(&expected.owner_indexes),
+        by_address(&actual.owner_indexes),
+        &mut divergences,
+    );
+    diff_section(
+        "access_indexes",
+        by_address(&expected.access_indexes),
+        by_address(&actual.access_indexes),
+        &mut divergences,
+    );
+    diff_section(
+        "datasets",
+        by_address(&expected.datasets),
+        by_address(&actual.datasets),
+        &mut divergences,
+    );
+    diff_section(
+        "associated_access",
+        by_address(&expected.associated_access),
+        by_address(&actual.associated_access),
+        &mut divergences,
+    );
+    divergences
+}
@@ -0,0 +1,66 @@
+use datanexus::state::DatasetState;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_sdk::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+
+use crate::find_dataset_address_with_program_id;
+
+/// On-chain sales counters of a dataset
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DatasetStats {
+    pub total_revenue: u64,
+    pub purchase_count: u32,
+    pub share_count: u32,
+}
+
+impl From<&DatasetState> for DatasetStats {
+    fn from(state: &DatasetState) -> Self {
+        Self {
+            total_revenue: state.total_revenue,
+            purchase_count: state.purchase_count,
+            share_count: state.share_count,
+        }
+    }
+}
+
+impl DatasetStats {
+    /// Sums the counters of several datasets, saturating on overflow
+    pub fn aggregate<'a, I>(stats: I) -> Self
+    where
+        I: IntoIterator<Item = &'a DatasetStats>,
+    {
+        stats.into_iter().fold(Self::default(), |total, stats| Self {
+            total_revenue: total.total_revenue.saturating_add(stats.total_revenue),
+            purchase_count: total.purchase_count.saturating_add(stats.purchase_count),
+            share_count: total.share_count.saturating_add(stats.share_count),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    Client(Box<ClientError>),
+    Decode(ProgramError),
+}
+
+impl From<ClientError> for StatsError {
+    fn from(e: ClientError) -> Self {
+        StatsError::Client(Box::new(e))
+    }
+}
+
+impl From<ProgramError> for StatsError {
+    fn from(e: ProgramError) -> Self {
+        StatsError::Decode(e)
+    }
+}
+
+/// Fetches the sales counters of the dataset registered under `hash`
+pub fn fetch_dataset_stats(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    hash: &[u8; 32],
+) -> Result<DatasetStats, StatsError> {
+    let data = rpc.get_account_data(&find_dataset_address_with_program_id(program_id, hash).0)?;
+    let state = DatasetState::unpack_from_slice(&data)?;
+    Ok(DatasetStats::from(&state))
+}
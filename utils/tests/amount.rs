@@ -0,0 +1,78 @@
+//! Conversions between decimal token amounts and base units
+
+use datanexus_utils::{format_ui_amount, parse_ui_amount, AmountError};
+
+#[test]
+fn test_parse_whole_and_fractional_amounts() {
+    assert_eq!(parse_ui_amount("100", 6), Ok(100_000_000));
+    assert_eq!(parse_ui_amount("0.5", 6), Ok(500_000));
+    assert_eq!(parse_ui_amount("1.000001", 6), Ok(1_000_001));
+    assert_eq!(parse_ui_amount("0", 9), Ok(0));
+    assert_eq!(parse_ui_amount("007.10", 2), Ok(710));
+    assert_eq!(parse_ui_amount("42", 0), Ok(42));
+}
+
+#[test]
+fn test_parse_rejects_malformed_amounts() {
+    for amount in &[
+        "", ".", ".5", "5.", "-1", "+1", "1e6", "1E6", "1.5e2", " 1", "1,000", "0x10",
+    ] {
+        assert_eq!(
+            parse_ui_amount(amount, 6),
+            Err(AmountError::Invalid(amount.to_string())),
+            "{}",
+            amount
+        );
+    }
+}
+
+#[test]
+fn test_parse_rejects_excess_precision() {
+    assert_eq!(
+        parse_ui_amount("0.0000001", 6),
+        Err(AmountError::TooPrecise {
+            amount: "0.0000001".to_string(),
+            decimals: 6,
+        })
+    );
+    assert!(matches!(
+        parse_ui_amount("1.5", 0),
+        Err(AmountError::TooPrecise { decimals: 0, .. })
+    ));
+}
+
+#[test]
+fn test_parse_u64_boundaries() {
+    assert_eq!(parse_ui_amount("18446744073709551615", 0), Ok(u64::MAX));
+    assert_eq!(parse_ui_amount("18446744073709.551615", 6), Ok(u64::MAX));
+    assert_eq!(parse_ui_amount("0.18446744073709551615", 20), Ok(u64::MAX));
+
+    for (amount, decimals) in &[
+        ("18446744073709551616", 0),
+        ("18446744073709.551616", 6),
+        ("18446744073710", 6),
+        ("1", 20),
+        ("0.18446744073709551616", 20),
+    ] {
+        assert_eq!(
+            parse_ui_amount(amount, *decimals),
+            Err(AmountError::Overflow(amount.to_string())),
+            "{} with {} decimals",
+            amount,
+            decimals
+        );
+    }
+}
+
+#[test]
+fn test_format_round_trips() {
+    assert_eq!(format_ui_amount(100_000_000, 6), "100.000000");
+    assert_eq!(format_ui_amount(5, 3), "0.005");
+    assert_eq!(format_ui_amount(42, 0), "42");
+    assert_eq!(format_ui_amount(u64::MAX, 20), "0.18446744073709551615");
+
+    for (amount, decimals) in &[(0, 6), (1, 9), (123_456_789, 4), (u64::MAX, 6)] {
+        let formatted = format_ui_amount(*amount, *decimals);
+        assert_eq!(parse_ui_amount(&formatted, *decimals), Ok(*amount));
+    }
+}
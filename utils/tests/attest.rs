@@ -0,0 +1,223 @@
+//! Attestations verified against an in-memory cluster holding the clock
+//! and the attesting authority's associated access account
+
+use std::{cell::RefCell, collections::HashMap};
+
+use datanexus::state::{AccessInfo, AccessState};
+use datanexus_utils::{
+    attest::MAX_ATTESTATION_AGE, build_access_attestation, client::AccountFetcher,
+    get_associated_access_address, get_dataset_address, verify_access_attestation,
+    AttestationError, SignedAttestation,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{
+    account::{create_account_for_test, Account},
+    clock::Clock,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    sysvar,
+};
+
+const HASH: [u8; 32] = [1; 32];
+
+const SLOT: u64 = 1_000;
+
+const NOW: i64 = 1_700_000_000;
+
+/// Accounts held in memory, counting the requests made for them
+#[derive(Default)]
+struct Cluster {
+    accounts: HashMap<Pubkey, Account>,
+    requests: RefCell<usize>,
+}
+
+impl AccountFetcher for Cluster {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        *self.requests.borrow_mut() += 1;
+        Ok(self.accounts.get(address).cloned())
+    }
+
+    fn fetch_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<(u64, Vec<Option<Account>>), ClientError> {
+        *self.requests.borrow_mut() += 1;
+        let accounts = addresses
+            .iter()
+            .map(|address| self.accounts.get(address).cloned())
+            .collect();
+        Ok((SLOT, accounts))
+    }
+}
+
+impl Cluster {
+    /// A cluster at `SLOT` and `NOW` where `holder` holds the `access`
+    /// entries given
+    fn new(holder: &Pubkey, access: Vec<AccessInfo>) -> Self {
+        let mut cluster = Cluster::default();
+        cluster.accounts.insert(
+            sysvar::clock::id(),
+            create_account_for_test(&Clock {
+                slot: SLOT,
+                unix_timestamp: NOW,
+                ..Clock::default()
+            }),
+        );
+
+        let state = AccessState {
+            is_initialized: true,
+            pointer: None,
+            datasets: access,
+            pending: vec![],
+        };
+        let mut data = vec![0; AccessState::LEN];
+        state.pack_into_slice(&mut data);
+        cluster.accounts.insert(
+            get_associated_access_address(*holder, get_dataset_address(&HASH)),
+            Account {
+                lamports: 1,
+                data,
+                owner: datanexus::id(),
+                ..Account::default()
+            },
+        );
+        cluster
+    }
+}
+
+fn grant(hash: [u8; 32], expires_at: Option<i64>) -> AccessInfo {
+    AccessInfo {
+        hash,
+        key: Some([7; 32]),
+        shared_from: None,
+        share_quota: 0,
+        expires_at,
+        depth: 0,
+        shares_used: 0,
+    }
+}
+
+#[test]
+fn held_access_is_proven_with_one_request() {
+    let holder = Keypair::new();
+    let cluster = Cluster::new(&holder.pubkey(), vec![grant(HASH, Some(NOW + 60))]);
+    let attestation = build_access_attestation(&holder, HASH, SLOT - 2);
+
+    let proof = verify_access_attestation(&cluster, &datanexus::id(), &attestation).unwrap();
+
+    assert_eq!(*cluster.requests.borrow(), 1);
+    assert_eq!(proof.authority, holder.pubkey());
+    assert_eq!(proof.hash, HASH);
+    assert_eq!((proof.attested_slot, proof.checked_slot), (SLOT - 2, SLOT));
+    assert_eq!(proof.checked_at, NOW);
+    // The cache lifetime stops at the expiry
+    assert_eq!(proof.cache_until(30), NOW + 30);
+    assert_eq!(proof.cache_until(300), NOW + 60);
+}
+
+#[test]
+fn forged_signatures_are_refused_before_fetching() {
+    let holder = Keypair::new();
+    let forger = Keypair::new();
+    let cluster = Cluster::new(&holder.pubkey(), vec![grant(HASH, None)]);
+
+    let mut signed_by_another = build_access_attestation(&forger, HASH, SLOT);
+    signed_by_another.authority = holder.pubkey();
+    let mut other_hash = build_access_attestation(&holder, [2; 32], SLOT);
+    other_hash.hash = HASH;
+    let mut other_slot = build_access_attestation(&holder, HASH, SLOT - 1);
+    other_slot.slot = SLOT;
+
+    for attestation in [signed_by_another, other_hash, other_slot] {
+        assert!(matches!(
+            verify_access_attestation(&cluster, &datanexus::id(), &attestation),
+            Err(AttestationError::InvalidSignature)
+        ));
+    }
+    assert_eq!(*cluster.requests.borrow(), 0);
+}
+
+#[test]
+fn revoked_access_is_refused() {
+    let holder = Keypair::new();
+    // Revoking removes the entry, leaving the account with others
+    let cluster = Cluster::new(&holder.pubkey(), vec![grant([2; 32], None)]);
+    let attestation = build_access_attestation(&holder, HASH, SLOT);
+
+    assert!(matches!(
+        verify_access_attestation(&cluster, &datanexus::id(), &attestation),
+        Err(AttestationError::NoAccess)
+    ));
+
+    let stranger = Keypair::new();
+    let attestation = build_access_attestation(&stranger, HASH, SLOT);
+    assert!(matches!(
+        verify_access_attestation(&cluster, &datanexus::id(), &attestation),
+        Err(AttestationError::NoAccess)
+    ));
+}
+
+#[test]
+fn expired_access_is_refused() {
+    let holder = Keypair::new();
+    let cluster = Cluster::new(&holder.pubkey(), vec![grant(HASH, Some(NOW))]);
+    let attestation = build_access_attestation(&holder, HASH, SLOT);
+
+    assert!(matches!(
+        verify_access_attestation(&cluster, &datanexus::id(), &attestation),
+        Err(AttestationError::Expired(NOW))
+    ));
+}
+
+#[test]
+fn stale_attestations_are_refused() {
+    let holder = Keypair::new();
+    let cluster = Cluster::new(&holder.pubkey(), vec![grant(HASH, None)]);
+
+    for slot in [
+        SLOT - MAX_ATTESTATION_AGE - 1,
+        SLOT + MAX_ATTESTATION_AGE + 1,
+        u64::MAX,
+    ] {
+        let attestation = build_access_attestation(&holder, HASH, slot);
+        assert!(matches!(
+            verify_access_attestation(&cluster, &datanexus::id(), &attestation),
+            Err(AttestationError::Stale { current: SLOT, .. })
+        ));
+    }
+    let attestation = build_access_attestation(&holder, HASH, SLOT - MAX_ATTESTATION_AGE);
+    verify_access_attestation(&cluster, &datanexus::id(), &attestation).unwrap();
+}
+
+#[test]
+fn attestations_round_trip_through_base64() {
+    let holder = Keypair::new();
+    let attestation = build_access_attestation(&holder, HASH, SLOT);
+
+    let decoded = SignedAttestation::from_base64(&attestation.to_base64()).unwrap();
+    assert_eq!(decoded, attestation);
+    assert!(decoded.verify_signature());
+
+    assert!(matches!(
+        SignedAttestation::from_base64(&base64::encode([0; 10])),
+        Err(AttestationError::Malformed)
+    ));
+    assert!(matches!(
+        SignedAttestation::from_base64("not base64!"),
+        Err(AttestationError::Malformed)
+    ));
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn attestations_round_trip_through_json() {
+    let holder = Keypair::new();
+    let attestation = build_access_attestation(&holder, HASH, SLOT);
+
+    let json = serde_json::to_string(&attestation).unwrap();
+    assert_eq!(
+        serde_json::from_str::<SignedAttestation>(&json).unwrap(),
+        attestation
+    );
+}
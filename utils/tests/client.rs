@@ -0,0 +1,132 @@
+//! `DataNexusClient` against `solana-program-test`, through an adapter
+//! running the async `BanksClient` on its own runtime
+
+mod common;
+
+use common::*;
+use datanexus::{instruction::Params, state::DatasetState};
+use datanexus_utils::{
+    client::{fetch_access_for, fetch_dataset, DataNexusClient, FetchError},
+    get_access_address, get_associated_access_address, get_dataset_address, get_owner_address,
+    get_vault_address,
+};
+
+#[test]
+fn registering_creates_the_owner_index() {
+    let bank = Bank::start();
+    let owner = bank.client();
+
+    let receipt = owner.register_dataset(HASH).unwrap();
+
+    assert_eq!(
+        receipt.created,
+        [get_owner_address(owner.payer()), get_dataset_address(&HASH)]
+    );
+    owner
+        .set_params(HASH, Params::Init([7; 32], VALUE, 1, None))
+        .unwrap();
+    let dataset: DatasetState = fetch_dataset(owner.rpc(), &datanexus::id(), &HASH).unwrap();
+    assert_eq!(dataset.owner, owner.payer());
+    assert_eq!(dataset.value, Some(VALUE));
+
+    // The owner index is reused by later registrations
+    let receipt = owner.register_dataset([2; 32]).unwrap();
+    assert_eq!(receipt.created, [get_dataset_address(&[2; 32])]);
+}
+
+#[test]
+fn steps_act_for_the_authority_the_payer_pays_for() {
+    let bank = Bank::start();
+    let client = DataNexusClient::with_sender(bank.clone(), datanexus::id(), bank.fund())
+        .with_authority(bank.fund());
+    assert_ne!(client.authority(), client.payer());
+
+    let plan = client.plan_register_dataset(HASH).unwrap();
+    assert_eq!(
+        plan.created,
+        [
+            get_owner_address(client.authority()),
+            get_dataset_address(&HASH)
+        ]
+    );
+    client.send(plan).unwrap();
+    client
+        .set_params(HASH, Params::Init([7; 32], VALUE, 1, None))
+        .unwrap();
+
+    let dataset: DatasetState = fetch_dataset(client.rpc(), &datanexus::id(), &HASH).unwrap();
+    assert_eq!(dataset.owner, client.authority());
+}
+
+#[test]
+fn indexes_are_created_once() {
+    let bank = Bank::start();
+    let client = bank.client();
+
+    let receipt = client.create_owner().unwrap();
+    assert_eq!(receipt.created, [get_owner_address(client.payer())]);
+    let receipt = client.create_access().unwrap();
+    assert_eq!(receipt.created, [get_access_address(client.payer())]);
+
+    match client.create_owner() {
+        Err(FetchError::AccountExists(address)) => {
+            assert_eq!(address, get_owner_address(client.payer()))
+        }
+        other => panic!("expected AccountExists, got {:?}", other),
+    }
+}
+
+#[test]
+fn purchasing_creates_the_buyer_accounts() {
+    let (bank, _, mint) = setup();
+
+    let buyer = buyer(&bank, &mint);
+
+    let access = fetch_access_for(buyer.rpc(), &datanexus::id(), buyer.payer(), &HASH).unwrap();
+    assert!(access.is_some());
+}
+
+#[test]
+fn purchases_need_the_owner_to_open_a_vault_of_the_mint() {
+    let (bank, _, _) = setup();
+    let buyer = bank.client();
+    let mint = bank.create_mint();
+    let tokens = bank.create_token_account(&mint, &buyer.payer(), VALUE);
+
+    match buyer.purchase(HASH, tokens, VALUE) {
+        Err(FetchError::AccountMissing(address)) => {
+            assert_eq!(address, get_vault_address(get_dataset_address(&HASH), mint))
+        }
+        other => panic!("expected AccountMissing, got {:?}", other),
+    }
+}
+
+#[test]
+fn sharing_needs_the_recipient_access_account() {
+    let (bank, _, mint) = setup();
+    let buyer = buyer(&bank, &mint);
+    let recipient = bank.client();
+
+    match buyer.share(HASH, recipient.payer()) {
+        Err(FetchError::AccountMissing(address)) => assert_eq!(
+            address,
+            get_associated_access_address(recipient.payer(), get_dataset_address(&HASH))
+        ),
+        other => panic!("expected AccountMissing, got {:?}", other),
+    }
+
+    let receipt = recipient.create_associated_access(HASH).unwrap();
+    assert_eq!(
+        receipt.created,
+        [
+            get_access_address(recipient.payer()),
+            get_associated_access_address(recipient.payer(), get_dataset_address(&HASH)),
+        ]
+    );
+
+    buyer.share(HASH, recipient.payer()).unwrap();
+    let access = fetch_access_for(buyer.rpc(), &datanexus::id(), recipient.payer(), &HASH)
+        .unwrap()
+        .unwrap();
+    assert_eq!(access.shared_from, Some(buyer.payer()));
+}
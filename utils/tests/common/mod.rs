@@ -0,0 +1,348 @@
+//! Shared setup for the utils integration tests, a running
+//! `solana-program-test` bank behind the blocking `DataNexusClient` traits
+
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex, Once},
+};
+
+use datanexus::{instruction::Params, processor::Processor};
+use datanexus_utils::{
+    client::{AccountFetcher, DataNexusClient, TransactionSender},
+    get_access_address, get_associated_access_address, get_dataset_address, get_vault_address,
+};
+use solana_client::client_error::ClientError;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::Instruction,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+    transport::TransportError,
+};
+use tokio::runtime::Runtime;
+
+pub const HASH: [u8; 32] = [1; 32];
+
+pub const VALUE: u64 = 100;
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    Processor::process_instruction(*program_id, accounts, data)
+}
+
+/// Prefix of the program log lines `LogDataStubs` writes event data to
+const LOG_DATA_PREFIX: &str = "Program log: data: ";
+
+/// Wraps the syscall stubs `solana-program-test` runs builtin programs with,
+/// which print `sol_log_data` to stdout instead of the transaction log. The
+/// data is logged as a message that `Bank` rewrites to the `Program data: `
+/// line the runtime writes for on-chain programs
+struct LogDataStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(base64::encode).collect();
+        self.0.sol_log(&format!("data: {}", fields.join(" ")))
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+}
+
+/// Holds the stubs' place while `LogDataStubs` takes over the ones it wraps
+struct Placeholder;
+
+impl SyscallStubs for Placeholder {}
+
+fn client_error<E: Into<TransportError>>(e: E) -> ClientError {
+    e.into().into()
+}
+
+struct Inner {
+    runtime: Runtime,
+    banks: Mutex<BanksClient>,
+    payer: Keypair,
+    /// Program logs of every transaction sent, by signature
+    logs: Mutex<HashMap<Signature, Vec<String>>>,
+}
+
+/// A running bank shared by every client of a test, `payer` funds the
+/// accounts the tests set up
+#[derive(Clone)]
+pub struct Bank(Arc<Inner>);
+
+impl AccountFetcher for Bank {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        let mut banks = self.0.banks.lock().unwrap();
+        self.0
+            .runtime
+            .block_on(banks.get_account(*address))
+            .map_err(client_error)
+    }
+}
+
+impl TransactionSender for Bank {
+    fn latest_blockhash(&self) -> Result<Hash, ClientError> {
+        let mut banks = self.0.banks.lock().unwrap();
+        self.0
+            .runtime
+            .block_on(banks.get_latest_blockhash())
+            .map_err(client_error)
+    }
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        let mut banks = self.0.banks.lock().unwrap();
+        // The banks client only returns logs from a simulation, which runs
+        // against the same bank the transaction is then processed by
+        let simulated = self
+            .0
+            .runtime
+            .block_on(banks.simulate_transaction(transaction.clone()))
+            .map_err(client_error)?;
+        self.0
+            .runtime
+            .block_on(banks.process_transaction(transaction.clone()))
+            .map_err(client_error)?;
+
+        let signature = transaction.signatures[0];
+        if let Some(details) = simulated.simulation_details {
+            let logs = details
+                .logs
+                .into_iter()
+                .map(|log| match log.strip_prefix(LOG_DATA_PREFIX) {
+                    Some(data) => format!("Program data: {}", data),
+                    None => log,
+                })
+                .collect();
+            self.0.logs.lock().unwrap().insert(signature, logs);
+        }
+        Ok(signature)
+    }
+}
+
+impl Bank {
+    pub fn start() -> Self {
+        Self::start_with(vec![])
+    }
+
+    /// Starts a bank holding `accounts` from genesis
+    pub fn start_with(accounts: Vec<(Pubkey, Account)>) -> Self {
+        let runtime = Runtime::new().unwrap();
+        let mut program_test = ProgramTest::new(
+            "datanexus",
+            datanexus::id(),
+            processor!(process_instruction),
+        );
+        for (address, account) in accounts {
+            program_test.add_account(address, account);
+        }
+        let (banks, payer, _) = runtime.block_on(program_test.start());
+
+        // Starting the first bank installed the stubs being wrapped, every
+        // other bank waits for the swap before it processes anything
+        static LOG_DATA: Once = Once::new();
+        LOG_DATA.call_once(|| {
+            let stubs = set_syscall_stubs(Box::new(Placeholder));
+            set_syscall_stubs(Box::new(LogDataStubs(stubs)));
+        });
+
+        Bank(Arc::new(Inner {
+            runtime,
+            banks: Mutex::new(banks),
+            payer,
+            logs: Mutex::default(),
+        }))
+    }
+
+    /// Sends `instructions` paid by the bank's payer and signed by `signers`
+    pub fn process(&self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let payer = &self.0.payer;
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            self.latest_blockhash().unwrap(),
+        );
+        self.send_and_confirm(&transaction).unwrap();
+    }
+
+    /// A new keypair funded with enough lamports to pay for its accounts
+    pub fn fund(&self) -> Keypair {
+        let keypair = Keypair::new();
+        self.process(
+            &[system_instruction::transfer(
+                &self.0.payer.pubkey(),
+                &keypair.pubkey(),
+                LAMPORTS_PER_SOL,
+            )],
+            &[],
+        );
+        keypair
+    }
+
+    /// A client for a new funded keypair
+    pub fn client(&self) -> DataNexusClient<Bank> {
+        DataNexusClient::with_sender(self.clone(), datanexus::id(), self.fund())
+    }
+
+    /// Runs `future` on the bank's runtime, the async client spawns its RPC
+    /// calls there
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.0.runtime.block_on(future)
+    }
+
+    /// Program logs of the transaction with `signature`
+    pub fn logs(&self, signature: &Signature) -> Vec<String> {
+        self.0.logs.lock().unwrap()[signature].clone()
+    }
+
+    pub fn create_mint(&self) -> Pubkey {
+        let mint = Keypair::new();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &self.0.payer.pubkey(),
+                    &mint.pubkey(),
+                    Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &self.0.payer.pubkey(),
+                    None,
+                    0,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        );
+        mint.pubkey()
+    }
+
+    /// Creates a token account of `mint` owned by `owner` holding `amount`
+    pub fn create_token_account(&self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = Keypair::new();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &self.0.payer.pubkey(),
+                    &account.pubkey(),
+                    Rent::default().minimum_balance(spl_token::state::Account::LEN),
+                    spl_token::state::Account::LEN as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_account(
+                    &spl_token::id(),
+                    &account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    mint,
+                    &account.pubkey(),
+                    &self.0.payer.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            ],
+            &[&account],
+        );
+        account.pubkey()
+    }
+}
+
+/// A bank with the dataset of `HASH` registered and priced by `owner`, who
+/// accepts payment in the returned mint
+pub fn setup() -> (Bank, DataNexusClient<Bank>, Pubkey) {
+    let bank = Bank::start();
+    let owner = bank.client();
+    owner.register_dataset(HASH).unwrap();
+    owner
+        .set_params(HASH, Params::Init([7; 32], VALUE, 1, None))
+        .unwrap();
+    let mint = bank.create_mint();
+    let receipt = owner.init_vault(HASH, mint).unwrap();
+    assert_eq!(
+        receipt.created,
+        [get_vault_address(get_dataset_address(&HASH), mint)]
+    );
+    (bank, owner, mint)
+}
+
+/// A client holding access to the dataset of `HASH`, paid in `mint`
+pub fn buyer(bank: &Bank, mint: &Pubkey) -> DataNexusClient<Bank> {
+    let buyer = bank.client();
+    let tokens = bank.create_token_account(mint, &buyer.payer(), VALUE);
+    let receipt = buyer.purchase(HASH, tokens, VALUE).unwrap();
+
+    let dataset = get_dataset_address(&HASH);
+    assert_eq!(
+        receipt.created,
+        [
+            get_access_address(buyer.payer()),
+            get_associated_access_address(buyer.payer(), dataset),
+        ]
+    );
+    buyer
+}
@@ -0,0 +1,100 @@
+//! Datasets found under whichever derivation their owner created them at,
+//! with owners holding datasets under both and hashes registered under
+//! both by different owners
+
+use std::collections::HashMap;
+
+use datanexus::state::DatasetState;
+use datanexus_utils::{
+    client::AccountFetcher, detect_derivation_version, get_dataset_address_versioned,
+    DerivationVersion,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+const HASH: [u8; 32] = [1; 32];
+const OTHER_HASH: [u8; 32] = [2; 32];
+
+/// Accounts held in memory
+#[derive(Default)]
+struct Cluster {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl AccountFetcher for Cluster {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+impl Cluster {
+    /// Adds the dataset of `hash` owned by `owner` at its `version` address
+    fn add_dataset(&mut self, version: DerivationVersion, hash: [u8; 32], owner: Pubkey) {
+        let mut data = vec![0; DatasetState::LEN];
+        data[0] = DatasetState::LAYOUT_VERSION;
+        let mut state = DatasetState::unpack_from_slice(&data).unwrap();
+        state.is_initialized = true;
+        state.owner = owner;
+        state.hash = hash;
+        state.pack_into_slice(&mut data);
+        self.accounts.insert(
+            get_dataset_address_versioned(version, &hash),
+            Account {
+                lamports: 1,
+                data,
+                owner: datanexus::id(),
+                ..Account::default()
+            },
+        );
+    }
+
+    fn detect(&self, owner: &Pubkey, hash: &[u8; 32]) -> DerivationVersion {
+        detect_derivation_version(self, &datanexus::id(), owner, hash).unwrap()
+    }
+}
+
+#[test]
+fn owners_with_datasets_under_both_derivations_find_each() {
+    let owner = Pubkey::new_unique();
+    let mut cluster = Cluster::default();
+    cluster.add_dataset(DerivationVersion::V1, HASH, owner);
+    cluster.add_dataset(DerivationVersion::V2, OTHER_HASH, owner);
+
+    assert_eq!(cluster.detect(&owner, &HASH), DerivationVersion::V1);
+    assert_eq!(cluster.detect(&owner, &OTHER_HASH), DerivationVersion::V2);
+}
+
+#[test]
+fn datasets_of_other_owners_are_passed_over() {
+    let (legacy_owner, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut cluster = Cluster::default();
+    cluster.add_dataset(DerivationVersion::V1, HASH, legacy_owner);
+    cluster.add_dataset(DerivationVersion::V2, HASH, owner);
+
+    assert_eq!(cluster.detect(&legacy_owner, &HASH), DerivationVersion::V1);
+    assert_eq!(cluster.detect(&owner, &HASH), DerivationVersion::V2);
+}
+
+#[test]
+fn owners_without_the_dataset_get_the_latest() {
+    let owner = Pubkey::new_unique();
+    let mut cluster = Cluster::default();
+    assert_eq!(cluster.detect(&owner, &HASH), DerivationVersion::LATEST);
+
+    cluster.add_dataset(DerivationVersion::V1, HASH, Pubkey::new_unique());
+    assert_eq!(cluster.detect(&owner, &HASH), DerivationVersion::LATEST);
+}
+
+#[test]
+fn accounts_of_other_programs_are_not_datasets() {
+    let owner = Pubkey::new_unique();
+    let mut cluster = Cluster::default();
+    cluster.add_dataset(DerivationVersion::V1, HASH, owner);
+    cluster
+        .accounts
+        .get_mut(&get_dataset_address_versioned(DerivationVersion::V1, &HASH))
+        .unwrap()
+        .owner = Pubkey::new_unique();
+
+    assert_eq!(cluster.detect(&owner, &HASH), DerivationVersion::LATEST);
+}
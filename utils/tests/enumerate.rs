@@ -0,0 +1,247 @@
+//! `enumerate_datasets` over synthetic owner indexes chained through their
+//! `pointer`, served from memory
+
+use std::{cell::RefCell, collections::HashMap};
+
+use datanexus::state::{AccountIndex, DatasetState, IndexEntry};
+use datanexus_utils::{
+    client::{AccountFetcher, FetchError},
+    enumerate_datasets, get_owner_address, Cache, MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+const SLOT: u64 = 5;
+
+/// Accounts held in memory, recording the requests made for them
+#[derive(Default)]
+struct Cluster {
+    accounts: HashMap<Pubkey, Account>,
+    single: RefCell<Vec<Pubkey>>,
+    batches: RefCell<Vec<usize>>,
+}
+
+impl AccountFetcher for Cluster {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        self.single.borrow_mut().push(*address);
+        Ok(self.accounts.get(address).cloned())
+    }
+
+    fn fetch_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<(u64, Vec<Option<Account>>), ClientError> {
+        assert!(addresses.len() <= MAX_MULTIPLE_ACCOUNTS);
+        self.batches.borrow_mut().push(addresses.len());
+        let accounts = addresses
+            .iter()
+            .map(|address| self.accounts.get(address).cloned())
+            .collect();
+        Ok((SLOT, accounts))
+    }
+}
+
+impl Cluster {
+    fn add(&mut self, address: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(
+            address,
+            Account {
+                lamports: 1,
+                data,
+                owner: datanexus::id(),
+                ..Account::default()
+            },
+        );
+    }
+
+    /// Adds an index at `address` recording `datasets` and an account for
+    /// each of them
+    fn add_index(&mut self, address: Pubkey, datasets: &[Pubkey], pointer: Option<Pubkey>) {
+        let index = AccountIndex {
+            is_initialized: true,
+            pointer,
+            require_share_approval: false,
+            datasets: datasets
+                .iter()
+                .map(|dataset| {
+                    Some(IndexEntry {
+                        dataset: *dataset,
+                        hash: dataset.to_bytes(),
+                    })
+                })
+                .collect(),
+        };
+        let mut data = vec![0; AccountIndex::space(datasets.len()).unwrap()];
+        index.pack_into_slice(&mut data);
+        self.add(address, data);
+
+        for dataset in datasets {
+            let mut data = vec![0; DatasetState::LEN];
+            data[0] = DatasetState::LAYOUT_VERSION;
+            data[1] = 1;
+            data[2] = 1;
+            data[35..67].copy_from_slice(dataset.as_ref());
+            self.add(*dataset, data);
+        }
+    }
+
+    /// An owner whose index is followed by further pages of `page_lens`
+    /// datasets, returning the owner and every dataset in order
+    fn chain(&mut self, page_lens: &[usize]) -> (Pubkey, Vec<Pubkey>) {
+        let owner = Pubkey::new_unique();
+        let mut addresses = vec![get_owner_address(owner)];
+        addresses.extend(page_lens[1..].iter().map(|_| Pubkey::new_unique()));
+
+        let mut all = vec![];
+        for (page, len) in page_lens.iter().enumerate() {
+            let datasets: Vec<Pubkey> = (0..*len).map(|_| Pubkey::new_unique()).collect();
+            let pointer = addresses.get(page + 1).copied();
+            self.add_index(addresses[page], &datasets, pointer);
+            all.extend(datasets);
+        }
+
+        (owner, all)
+    }
+}
+
+/// Every account fetched at or after the slot asked for
+#[derive(Default)]
+struct MapCache(HashMap<Pubkey, (u64, Account)>);
+
+impl Cache for MapCache {
+    fn get(&mut self, address: &Pubkey, min_slot: u64) -> Option<Account> {
+        match self.0.get(address) {
+            Some((slot, account)) if *slot >= min_slot => Some(account.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, address: Pubkey, slot: u64, account: Account) {
+        self.0.insert(address, (slot, account));
+    }
+}
+
+fn addresses(
+    datasets: impl Iterator<Item = Result<(Pubkey, DatasetState), FetchError>>,
+) -> Vec<Pubkey> {
+    datasets
+        .map(|result| {
+            let (address, dataset) = result.unwrap();
+            assert_eq!(dataset.hash, address.to_bytes());
+            address
+        })
+        .collect()
+}
+
+#[test]
+fn chained_indexes_are_fetched_in_full_batches() {
+    let mut cluster = Cluster::default();
+    let (owner, datasets) = cluster.chain(&[150, 120, 30]);
+
+    let listed = addresses(enumerate_datasets(&cluster, &datanexus::id(), owner));
+
+    assert_eq!(listed, datasets);
+    assert_eq!(*cluster.batches.borrow(), [100, 100, 100]);
+    assert_eq!(cluster.single.borrow().len(), 3);
+}
+
+#[test]
+fn enumeration_is_lazy() {
+    let mut cluster = Cluster::default();
+    let (owner, datasets) = cluster.chain(&[150, 120, 30]);
+
+    let listed = addresses(enumerate_datasets(&cluster, &datanexus::id(), owner).take(10));
+
+    assert_eq!(listed, datasets[..10]);
+    assert_eq!(*cluster.single.borrow(), [get_owner_address(owner)]);
+    assert_eq!(*cluster.batches.borrow(), [100]);
+}
+
+#[test]
+fn progress_counts_the_indexes_walked() {
+    let mut cluster = Cluster::default();
+    let (owner, _) = cluster.chain(&[150, 120]);
+    let mut iter = enumerate_datasets(&cluster, &datanexus::id(), owner);
+
+    iter.next().unwrap().unwrap();
+    assert_eq!((iter.fetched(), iter.discovered()), (100, 150));
+    assert!(!iter.indexes_walked());
+
+    let rest = iter.by_ref().count();
+    assert_eq!(rest, 269);
+    assert_eq!((iter.fetched(), iter.discovered()), (270, 270));
+    assert!(iter.indexes_walked());
+}
+
+#[test]
+fn cycles_end_the_chain() {
+    let mut cluster = Cluster::default();
+    let owner = Pubkey::new_unique();
+    let head = get_owner_address(owner);
+    let next = Pubkey::new_unique();
+    let first = [Pubkey::new_unique()];
+    let second = [Pubkey::new_unique()];
+    cluster.add_index(head, &first, Some(next));
+    cluster.add_index(next, &second, Some(head));
+
+    let listed = addresses(enumerate_datasets(&cluster, &datanexus::id(), owner));
+
+    assert_eq!(listed, [first[0], second[0]]);
+}
+
+#[test]
+fn closed_datasets_are_skipped() {
+    let mut cluster = Cluster::default();
+    let (owner, datasets) = cluster.chain(&[4]);
+    cluster.accounts.remove(&datasets[1]);
+    cluster.accounts.get_mut(&datasets[2]).unwrap().data = vec![0; DatasetState::CLOSED_LEN];
+
+    let listed = addresses(enumerate_datasets(&cluster, &datanexus::id(), owner));
+
+    assert_eq!(listed, [datasets[0], datasets[3]]);
+}
+
+#[test]
+fn a_missing_owner_index_is_reported() {
+    let cluster = Cluster::default();
+    let owner = Pubkey::new_unique();
+    let mut iter = enumerate_datasets(&cluster, &datanexus::id(), owner);
+
+    match iter.next() {
+        Some(Err(FetchError::AccountMissing(address))) => {
+            assert_eq!(address, get_owner_address(owner))
+        }
+        other => panic!(
+            "expected AccountMissing, got {:?}",
+            other.map(|r| r.is_ok())
+        ),
+    }
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn cached_datasets_are_not_refetched() {
+    let mut cluster = Cluster::default();
+    let (owner, datasets) = cluster.chain(&[150]);
+    let mut cache = MapCache::default();
+
+    let listed = addresses(
+        enumerate_datasets(&cluster, &datanexus::id(), owner).with_cache(&mut cache, SLOT),
+    );
+    assert_eq!(listed, datasets);
+    assert_eq!(*cluster.batches.borrow(), [100, 50]);
+
+    // Fresh enough, only the index is read again
+    let listed = addresses(
+        enumerate_datasets(&cluster, &datanexus::id(), owner).with_cache(&mut cache, SLOT),
+    );
+    assert_eq!(listed, datasets);
+    assert_eq!(*cluster.batches.borrow(), [100, 50]);
+
+    // Older than asked for, everything is fetched again
+    let listed = addresses(
+        enumerate_datasets(&cluster, &datanexus::id(), owner).with_cache(&mut cache, SLOT + 1),
+    );
+    assert_eq!(listed, datasets);
+    assert_eq!(*cluster.batches.borrow(), [100, 50, 100, 50]);
+}
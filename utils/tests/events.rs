@@ -0,0 +1,128 @@
+//! Decoding purchases from program logs, as `PurchaseSubscription` does with
+//! each transaction it is notified of, and from the logs `solana-program-test`
+//! records of the program's own transactions
+
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use datanexus::events::{DataNexusEvent, PurchaseEvent, ShareEvent};
+use datanexus_utils::{
+    get_dataset_address, parse_events, parse_pending_shares, parse_purchases, parse_shares,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn program_data(event: &DataNexusEvent) -> String {
+    format!("Program data: {}", base64::encode(event.try_to_vec().unwrap()))
+}
+
+fn purchase(dataset: Pubkey, amount: u64) -> PurchaseEvent {
+    PurchaseEvent {
+        dataset,
+        buyer: Pubkey::new_unique(),
+        amount,
+        mint: Pubkey::new_unique(),
+        slot: 7,
+    }
+}
+
+#[test]
+fn test_parse_purchases_of_dataset() {
+    let dataset = Pubkey::new_unique();
+    let first = purchase(dataset, 100);
+    let other = purchase(Pubkey::new_unique(), 200);
+    let second = purchase(dataset, 300);
+
+    let logs = vec![
+        format!("Program {} invoke [1]", datanexus::id()),
+        program_data(&DataNexusEvent::Purchase(first.clone())),
+        "Program log: Instruction: PurchaseAccess".to_string(),
+        program_data(&DataNexusEvent::Purchase(other)),
+        program_data(&DataNexusEvent::Share(ShareEvent {
+            dataset,
+            sharer: first.buyer,
+            recipient: Pubkey::new_unique(),
+            slot: 7,
+        })),
+        program_data(&DataNexusEvent::Purchase(second.clone())),
+        format!("Program {} success", datanexus::id()),
+    ];
+
+    assert_eq!(parse_events(&logs).len(), 4);
+    assert_eq!(parse_purchases(&logs, &dataset), vec![first, second]);
+    assert_eq!(parse_shares(&logs, &dataset).len(), 1);
+    assert!(parse_shares(&logs, &Pubkey::new_unique()).is_empty());
+}
+
+#[test]
+fn test_parse_pending_shares_apart_from_granted_ones() {
+    let dataset = Pubkey::new_unique();
+    let share = |recipient| ShareEvent {
+        dataset,
+        sharer: Pubkey::new_unique(),
+        recipient,
+        slot: 7,
+    };
+    let pending = share(Pubkey::new_unique());
+
+    let logs = vec![
+        program_data(&DataNexusEvent::Share(share(Pubkey::new_unique()))),
+        program_data(&DataNexusEvent::SharePending(pending.clone())),
+    ];
+
+    assert_eq!(parse_pending_shares(&logs, &dataset), vec![pending]);
+    assert_eq!(parse_shares(&logs, &dataset).len(), 1);
+    assert!(parse_pending_shares(&logs, &Pubkey::new_unique()).is_empty());
+}
+
+#[test]
+fn test_parse_purchases_skips_undecodable_data() {
+    let dataset = Pubkey::new_unique();
+    let event = purchase(dataset, 100);
+
+    let logs = vec![
+        "Program data: not base64".to_string(),
+        format!("Program data: {}", base64::encode([0xff; 8])),
+        program_data(&DataNexusEvent::Purchase(event.clone())),
+    ];
+
+    assert_eq!(parse_purchases(&logs, &dataset), vec![event]);
+    assert!(parse_purchases(&[], &dataset).is_empty());
+}
+
+#[test]
+fn test_purchase_events_of_program_test_transactions() {
+    let (bank, _, mint) = setup();
+    let buyer = bank.client();
+    let tokens = bank.create_token_account(&mint, &buyer.payer(), VALUE);
+
+    let receipt = buyer.purchase(HASH, tokens, VALUE).unwrap();
+
+    let dataset = get_dataset_address(&HASH);
+    let logs = bank.logs(&receipt.signature);
+    let purchases = parse_purchases(&logs, &dataset);
+    assert_eq!(purchases.len(), 1);
+    assert_eq!(purchases[0].buyer, buyer.payer());
+    assert_eq!(purchases[0].amount, VALUE);
+    assert_eq!(purchases[0].mint, mint);
+    assert!(parse_purchases(&logs, &Pubkey::new_unique()).is_empty());
+}
+
+#[test]
+fn test_share_events_of_program_test_transactions() {
+    let (bank, _, mint) = setup();
+    let buyer = buyer(&bank, &mint);
+    let recipient = bank.client();
+    recipient.create_associated_access(HASH).unwrap();
+
+    let receipt = buyer.share(HASH, recipient.payer()).unwrap();
+
+    match parse_events(&bank.logs(&receipt.signature)).as_slice() {
+        [DataNexusEvent::Share(share)] => {
+            assert_eq!(share.dataset, get_dataset_address(&HASH));
+            assert_eq!(share.sharer, buyer.payer());
+            assert_eq!(share.recipient, recipient.payer());
+        }
+        other => panic!("expected a single share event, got {:?}", other),
+    }
+}
@@ -0,0 +1,110 @@
+//! The fetch helpers against a `solana-program-test` bank behind the
+//! `AccountFetcher` trait, which `RpcClient` implements the same way
+
+mod common;
+
+use common::*;
+use datanexus::state::{AccessState, DatasetState};
+use datanexus_utils::{
+    client::{
+        fetch_access_for, fetch_access_index, fetch_access_state, fetch_dataset, fetch_owner_index,
+        FetchError,
+    },
+    get_access_address, get_dataset_address,
+};
+use solana_program::{program_pack::Pack, system_program};
+use solana_sdk::account::Account;
+
+const UNREGISTERED: [u8; 32] = [9; 32];
+
+#[test]
+fn registered_datasets_are_fetched_and_indexed() {
+    let (_, owner, _) = setup();
+
+    let dataset: DatasetState = fetch_dataset(owner.rpc(), &datanexus::id(), &HASH).unwrap();
+    assert_eq!(dataset.hash, HASH);
+    assert_eq!(dataset.owner, owner.payer());
+
+    let index = fetch_owner_index(owner.rpc(), &datanexus::id(), owner.payer()).unwrap();
+    assert_eq!(index.find_by_hash(&HASH), Some(get_dataset_address(&HASH)));
+}
+
+#[test]
+fn missing_accounts_are_reported_by_address() {
+    let (_, owner, _) = setup();
+
+    match fetch_dataset(owner.rpc(), &datanexus::id(), &UNREGISTERED) {
+        Err(FetchError::AccountMissing(address)) => {
+            assert_eq!(address, get_dataset_address(&UNREGISTERED))
+        }
+        other => panic!("expected AccountMissing, got {:?}", other.map(|_| ())),
+    }
+    match fetch_access_index(owner.rpc(), &datanexus::id(), owner.payer()) {
+        Err(FetchError::AccountMissing(address)) => {
+            assert_eq!(address, get_access_address(owner.payer()))
+        }
+        other => panic!("expected AccountMissing, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn accounts_of_another_program_are_refused() {
+    let address = get_dataset_address(&UNREGISTERED);
+    let bank = Bank::start_with(vec![(
+        address,
+        Account {
+            lamports: 1_000_000,
+            owner: system_program::id(),
+            ..Account::default()
+        },
+    )]);
+    let client = bank.client();
+
+    match fetch_dataset(client.rpc(), &datanexus::id(), &UNREGISTERED) {
+        Err(FetchError::WrongOwner(wrong)) => assert_eq!(wrong, address),
+        other => panic!("expected WrongOwner, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn undecodable_accounts_are_reported() {
+    let address = get_dataset_address(&UNREGISTERED);
+    let bank = Bank::start_with(vec![(
+        address,
+        Account {
+            lamports: 1_000_000,
+            data: vec![0xff; DatasetState::LEN],
+            owner: datanexus::id(),
+            ..Account::default()
+        },
+    )]);
+    let client = bank.client();
+
+    match fetch_dataset(client.rpc(), &datanexus::id(), &UNREGISTERED) {
+        Err(FetchError::DecodeFailed(_)) => {}
+        other => panic!("expected DecodeFailed, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn access_is_fetched_once_granted() {
+    let (bank, _, mint) = setup();
+    let client = bank.client();
+    assert_eq!(
+        fetch_access_for(client.rpc(), &datanexus::id(), client.payer(), &HASH).unwrap(),
+        None
+    );
+
+    let buyer = buyer(&bank, &mint);
+
+    let state: AccessState =
+        fetch_access_state(buyer.rpc(), &datanexus::id(), buyer.payer(), &HASH).unwrap();
+    assert_eq!(state.datasets.len(), 1);
+    let access = fetch_access_for(buyer.rpc(), &datanexus::id(), buyer.payer(), &HASH)
+        .unwrap()
+        .unwrap();
+    assert_eq!(access, state.datasets[0]);
+    assert_eq!(access.shared_from, None);
+    let index = fetch_access_index(buyer.rpc(), &datanexus::id(), buyer.payer()).unwrap();
+    assert_eq!(index.find_by_hash(&HASH), Some(get_dataset_address(&HASH)));
+}
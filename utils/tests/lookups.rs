@@ -0,0 +1,220 @@
+//! Purchases planned against an in-memory cluster counting the accounts
+//! fetched, with the accounts the buyer already knows taken as given
+
+use std::{cell::RefCell, collections::HashMap};
+
+use datanexus::state::DatasetState;
+use datanexus_utils::{
+    client::{purchase_access_plan, AccountFetcher, FetchError, KnownPurchaseAccounts, Plan},
+    get_dataset_address, get_vault_address,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token_2022::state::{Account as TokenAccount, AccountState};
+
+const HASH: [u8; 32] = [1; 32];
+
+/// Accounts held in memory, recording each address fetched
+#[derive(Default)]
+struct Cluster {
+    accounts: HashMap<Pubkey, Account>,
+    fetched: RefCell<Vec<Pubkey>>,
+}
+
+impl AccountFetcher for Cluster {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        self.fetched.borrow_mut().push(*address);
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+/// A dataset of `HASH` with its vault of `mint` open, and a buyer's token
+/// account of `mint`
+struct Purchase {
+    cluster: Cluster,
+    buyer: Pubkey,
+    token_account: Pubkey,
+    mint: Pubkey,
+    vault: Pubkey,
+}
+
+impl Purchase {
+    fn new() -> Self {
+        let (buyer, token_account, mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let dataset = get_dataset_address(&HASH);
+        let vault = get_vault_address(dataset, mint);
+        let mut cluster = Cluster::default();
+
+        let mut data = vec![0; DatasetState::LEN];
+        data[0] = DatasetState::LAYOUT_VERSION;
+        let mut state = DatasetState::unpack_from_slice(&data).unwrap();
+        state.is_initialized = true;
+        state.owner = Pubkey::new_unique();
+        state.hash = HASH;
+        state.pack_into_slice(&mut data);
+        cluster.accounts.insert(
+            dataset,
+            Account {
+                lamports: 1,
+                data,
+                owner: datanexus::id(),
+                ..Account::default()
+            },
+        );
+
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint,
+                owner: buyer,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        for (address, data) in [(token_account, data), (vault, vec![])].iter() {
+            cluster.accounts.insert(
+                *address,
+                Account {
+                    lamports: 1,
+                    data: data.clone(),
+                    owner: spl_token_2022::id(),
+                    ..Account::default()
+                },
+            );
+        }
+
+        Purchase {
+            cluster,
+            buyer,
+            token_account,
+            mint,
+            vault,
+        }
+    }
+
+    fn plan(&self, known: KnownPurchaseAccounts) -> Result<Plan, FetchError> {
+        purchase_access_plan(
+            &self.cluster,
+            &datanexus::id(),
+            self.buyer,
+            &HASH,
+            self.token_account,
+            100,
+            None,
+            None,
+            &known,
+        )
+    }
+
+    fn fetched(&self, address: &Pubkey) -> bool {
+        self.cluster.fetched.borrow().contains(address)
+    }
+}
+
+/// Whether the purchase, the plan's last instruction, names `address`
+fn purchase_names(plan: &Plan, address: &Pubkey) -> bool {
+    plan.instructions
+        .last()
+        .unwrap()
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == *address)
+}
+
+#[test]
+fn unknown_accounts_are_looked_up() {
+    let purchase = Purchase::new();
+
+    let plan = purchase.plan(KnownPurchaseAccounts::default()).unwrap();
+
+    assert!(purchase.fetched(&purchase.token_account));
+    assert!(purchase.fetched(&purchase.vault));
+    assert!(purchase_names(&plan, &purchase.mint));
+    assert!(purchase_names(&plan, &purchase.vault));
+}
+
+#[test]
+fn known_accounts_are_not_looked_up() {
+    let mut purchase = Purchase::new();
+    let requests = {
+        purchase.plan(KnownPurchaseAccounts::default()).unwrap();
+        purchase.cluster.fetched.borrow_mut().drain(..).count()
+    };
+    // Neither account needs to exist for the plan to be assembled
+    purchase.cluster.accounts.remove(&purchase.token_account);
+    purchase.cluster.accounts.remove(&purchase.vault);
+
+    let plan = purchase
+        .plan(KnownPurchaseAccounts {
+            dataset_account: Some(get_dataset_address(&HASH)),
+            token_mint: Some(purchase.mint),
+            token_program: Some(spl_token_2022::id()),
+            proceeds_account: Some(purchase.vault),
+        })
+        .unwrap();
+
+    assert!(!purchase.fetched(&purchase.token_account));
+    assert!(!purchase.fetched(&purchase.vault));
+    assert_eq!(purchase.cluster.fetched.borrow().len(), requests - 2);
+    assert!(purchase_names(&plan, &purchase.mint));
+    assert!(purchase_names(&plan, &purchase.vault));
+}
+
+#[test]
+fn a_mint_without_its_token_program_is_still_looked_up() {
+    let purchase = Purchase::new();
+
+    purchase
+        .plan(KnownPurchaseAccounts {
+            token_mint: Some(purchase.mint),
+            ..KnownPurchaseAccounts::default()
+        })
+        .unwrap();
+
+    assert!(purchase.fetched(&purchase.token_account));
+}
+
+#[test]
+fn given_accounts_are_checked_against_those_derived_or_found() {
+    let purchase = Purchase::new();
+    let other = Pubkey::new_unique();
+    let mismatched = [
+        (
+            KnownPurchaseAccounts {
+                dataset_account: Some(other),
+                ..KnownPurchaseAccounts::default()
+            },
+            get_dataset_address(&HASH),
+        ),
+        (
+            KnownPurchaseAccounts {
+                token_mint: Some(other),
+                ..KnownPurchaseAccounts::default()
+            },
+            purchase.mint,
+        ),
+        (
+            KnownPurchaseAccounts {
+                proceeds_account: Some(other),
+                ..KnownPurchaseAccounts::default()
+            },
+            purchase.vault,
+        ),
+    ];
+
+    for (known, expected) in mismatched.iter() {
+        match purchase.plan(*known) {
+            Err(FetchError::AccountMismatch {
+                given,
+                expected: found,
+            }) => assert_eq!((given, found), (other, *expected)),
+            result => panic!("expected a mismatch, got {:?}", result.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Planning the `MigrateAccount` calls that bring an account up to date
+
+use datanexus::{
+    instruction::DataNexusInstruction,
+    state::{AccessState, AccountIndex, DatasetState, MAX_INDEX_ENTRIES},
+};
+use datanexus_utils::{migrate_instructions, needs_migration};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+
+/// An initialized dataset with no version header
+fn legacy_dataset() -> Vec<u8> {
+    let mut data = vec![0; DatasetState::LEGACY_LEN];
+    data[0] = 1;
+    data[1] = 1;
+    data
+}
+
+/// `legacy_dataset` behind the version 1 header
+fn v1_dataset() -> Vec<u8> {
+    let mut data = legacy_dataset();
+    data.insert(0, 1);
+    data
+}
+
+/// `legacy_dataset` in the current layout
+fn current_dataset() -> Vec<u8> {
+    let mut data = v1_dataset();
+    data[0] = DatasetState::LAYOUT_VERSION;
+    data.resize(DatasetState::LEN, 0);
+    data
+}
+
+#[test]
+fn test_needs_migration_by_layout() {
+    assert!(needs_migration(&legacy_dataset()));
+    assert!(needs_migration(&v1_dataset()));
+    assert!(!needs_migration(&current_dataset()));
+
+    assert!(needs_migration(&[0; AccessState::LEGACY_LEN]));
+    let mut access = vec![0; AccessState::LEN];
+    access[0] = AccessState::LAYOUT_VERSION;
+    assert!(!needs_migration(&access));
+
+    assert!(needs_migration(&[0; AccountIndex::LEGACY_LEN]));
+    let mut index = vec![0; AccountIndex::LEN];
+    AccountIndex {
+        is_initialized: true,
+        pointer: None,
+        require_share_approval: false,
+        datasets: vec![None; MAX_INDEX_ENTRIES],
+    }
+    .pack_into_slice(&mut index);
+    assert!(!needs_migration(&index));
+
+    assert!(!needs_migration(&[]));
+    assert!(!needs_migration(&[0; 42]));
+}
+
+#[test]
+fn test_migrate_instructions_once_for_legacy_layouts() {
+    let program_id = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let address = Pubkey::new_unique();
+
+    let instructions = migrate_instructions(program_id, payer, address, &legacy_dataset()).unwrap();
+
+    assert_eq!(instructions.len(), 1);
+    for instruction in &instructions {
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.accounts[0].pubkey, payer);
+        assert_eq!(instruction.accounts[1].pubkey, address);
+        assert_eq!(
+            instruction.data,
+            DataNexusInstruction::MigrateAccount.pack()
+        );
+    }
+    assert!(
+        migrate_instructions(program_id, payer, address, &current_dataset())
+            .unwrap()
+            .is_empty()
+    );
+}
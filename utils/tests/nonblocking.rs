@@ -0,0 +1,114 @@
+//! The async `DataNexusClient` on tokio, sending to a `solana-program-test`
+//! bank from the blocking pool it runs RPC calls on
+
+#![cfg(feature = "nonblocking")]
+
+mod common;
+
+use common::*;
+use datanexus::instruction::{init_account, AccountType};
+use datanexus_utils::{
+    client::FetchError, get_access_address, get_dataset_address, nonblocking::DataNexusClient,
+};
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn client(bank: &Bank) -> DataNexusClient<Bank> {
+    DataNexusClient::with_program_id(bank.clone(), datanexus::id())
+}
+
+/// Creates the access index of `authority` through the async client
+async fn create_access_index(client: &DataNexusClient<Bank>, authority: &Keypair) {
+    let instruction = init_account(
+        datanexus::id(),
+        Some(authority.pubkey()),
+        authority.pubkey(),
+        get_access_address(authority.pubkey()),
+        None,
+        system_program::id(),
+        AccountType::AccessIndex,
+    )
+    .unwrap();
+    client
+        .send_instructions(authority, vec![instruction])
+        .await
+        .unwrap();
+}
+
+#[test]
+fn datasets_are_fetched() {
+    let (bank, owner, _) = setup();
+    let client = client(&bank);
+
+    bank.block_on(async {
+        let dataset = client.fetch_dataset(HASH).await.unwrap();
+        assert_eq!(dataset.owner, owner.payer());
+        assert_eq!(dataset.value, Some(VALUE));
+
+        let index = client.fetch_owner_index(owner.payer()).await.unwrap();
+        assert_eq!(index.find_by_hash(&HASH), Some(get_dataset_address(&HASH)));
+
+        match client.fetch_dataset([9; 32]).await {
+            Err(FetchError::AccountMissing(address)) => {
+                assert_eq!(address, get_dataset_address(&[9; 32]))
+            }
+            other => panic!("expected AccountMissing, got {:?}", other.map(|_| ())),
+        }
+    });
+}
+
+#[test]
+fn purchases_create_the_associated_access_account() {
+    let (bank, _, mint) = setup();
+    let client = client(&bank);
+    let buyer = bank.fund();
+    let tokens = bank.create_token_account(&mint, &buyer.pubkey(), VALUE);
+
+    bank.block_on(async {
+        create_access_index(&client, &buyer).await;
+        assert_eq!(
+            client.fetch_access_for(buyer.pubkey(), HASH).await.unwrap(),
+            None
+        );
+
+        client
+            .purchase_access(&buyer, HASH, tokens, VALUE)
+            .await
+            .unwrap();
+
+        let access = client
+            .fetch_access_for(buyer.pubkey(), HASH)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(access.hash, HASH);
+        assert_eq!(access.shared_from, None);
+        let state = client
+            .fetch_access_state(buyer.pubkey(), HASH)
+            .await
+            .unwrap();
+        assert_eq!(state.datasets, vec![access]);
+        let index = client.fetch_access_index(buyer.pubkey()).await.unwrap();
+        assert_eq!(index.find_by_hash(&HASH), Some(get_dataset_address(&HASH)));
+    });
+}
+
+#[test]
+fn clients_are_shared_across_tasks() {
+    let (bank, owner, _) = setup();
+    let client = client(&bank);
+
+    bank.block_on(async {
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.fetch_dataset(HASH).await })
+            })
+            .collect();
+
+        for task in tasks {
+            let dataset = task.await.unwrap().unwrap();
+            assert_eq!(dataset.owner, owner.payer());
+        }
+    });
+}
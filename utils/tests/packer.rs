@@ -0,0 +1,228 @@
+//! Instructions packed into transactions within the size, account and
+//! compute limits, with accounts created before they are used
+
+use datanexus_utils::{
+    packer::{transaction_size, DEFAULT_TRANSACTION_UNITS, ESTIMATED_UNITS_PER_INSTRUCTION},
+    PackError, PackItem, PackLimit, TransactionPacker,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+};
+
+/// An instruction carrying `data_len` bytes of data and passing `accounts`
+fn instruction(data_len: usize, accounts: &[Pubkey]) -> Instruction {
+    Instruction::new_with_bytes(
+        Pubkey::new_unique(),
+        &vec![0; data_len],
+        accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect(),
+    )
+}
+
+fn item(data_len: usize) -> PackItem {
+    PackItem::new(vec![instruction(data_len, &[])])
+}
+
+fn groups(packer: &TransactionPacker, items: &[PackItem]) -> Vec<Vec<usize>> {
+    packer
+        .pack(items)
+        .unwrap()
+        .into_iter()
+        .map(|transaction| transaction.items)
+        .collect()
+}
+
+#[test]
+fn small_items_share_a_transaction() {
+    let fee_payer = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let mut signed = instruction(8, &[]);
+    signed
+        .accounts
+        .push(AccountMeta::new_readonly(authority, true));
+    let items = [item(8), PackItem::new(vec![signed])];
+
+    let transactions = TransactionPacker::new(fee_payer).pack(&items).unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].items, [0, 1]);
+    assert_eq!(transactions[0].instructions.len(), 2);
+    assert_eq!(transactions[0].signers, [fee_payer, authority]);
+    assert_eq!(transactions[0].units, 2 * ESTIMATED_UNITS_PER_INSTRUCTION);
+}
+
+#[test]
+fn transactions_stay_within_every_limit() {
+    let fee_payer = Pubkey::new_unique();
+
+    let per_transaction = (DEFAULT_TRANSACTION_UNITS / ESTIMATED_UNITS_PER_INSTRUCTION) as usize;
+    let items: Vec<_> = (0..=per_transaction).map(|_| item(8)).collect();
+    assert_eq!(
+        groups(&TransactionPacker::new(fee_payer), &items),
+        [(0..per_transaction).collect(), vec![per_transaction]]
+    );
+
+    let items: Vec<_> = (0..3).map(|_| item(PACKET_DATA_SIZE / 3)).collect();
+    let transactions = TransactionPacker::new(fee_payer).pack(&items).unwrap();
+    assert_eq!(transactions.len(), 2);
+    for transaction in transactions {
+        assert!(transaction_size(&transaction.instructions, &fee_payer) <= PACKET_DATA_SIZE);
+    }
+
+    // Each item passes three accounts to a program of its own, so along
+    // with the fee payer two items fit in ten accounts
+    let items: Vec<_> = (0..3)
+        .map(|_| {
+            let accounts: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+            PackItem::new(vec![instruction(8, &accounts)])
+        })
+        .collect();
+    let packer = TransactionPacker::new(fee_payer).with_max_accounts(10);
+    assert_eq!(groups(&packer, &items), [vec![0, 1], vec![2]]);
+}
+
+#[test]
+fn items_are_never_split() {
+    let items = [
+        PackItem::new(vec![instruction(8, &[]); 3]),
+        PackItem::new(vec![instruction(8, &[]); 3]),
+    ];
+
+    assert_eq!(
+        groups(&TransactionPacker::new(Pubkey::new_unique()), &items),
+        [vec![0], vec![1]]
+    );
+}
+
+#[test]
+fn items_exceeding_a_limit_alone_fail() {
+    let fee_payer = Pubkey::new_unique();
+    let packer = TransactionPacker::new(fee_payer);
+
+    let items = [item(8), item(PACKET_DATA_SIZE)];
+    assert_eq!(
+        packer.pack(&items),
+        Err(PackError::TooLarge {
+            item: 1,
+            limit: PackLimit::Size
+        })
+    );
+
+    let items = [item(8).with_units(DEFAULT_TRANSACTION_UNITS + 1)];
+    assert_eq!(
+        packer.pack(&items),
+        Err(PackError::TooLarge {
+            item: 0,
+            limit: PackLimit::Units
+        })
+    );
+    // Raising the limit lets it through
+    let packer = packer.with_max_units(1_400_000);
+    assert_eq!(groups(&packer, &items), [vec![0]]);
+
+    let accounts: Vec<_> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let items = [PackItem::new(vec![instruction(8, &accounts)])];
+    assert_eq!(
+        packer.with_max_accounts(6).pack(&items),
+        Err(PackError::TooLarge {
+            item: 0,
+            limit: PackLimit::Accounts
+        })
+    );
+}
+
+#[test]
+fn the_prefix_counts_towards_the_size() {
+    let fee_payer = Pubkey::new_unique();
+    let items = [item(PACKET_DATA_SIZE / 2)];
+
+    assert_eq!(
+        groups(&TransactionPacker::new(fee_payer), &items),
+        [vec![0]]
+    );
+    assert_eq!(
+        TransactionPacker::new(fee_payer)
+            .with_prefix(vec![instruction(PACKET_DATA_SIZE / 2, &[])])
+            .pack(&items),
+        Err(PackError::TooLarge {
+            item: 0,
+            limit: PackLimit::Size
+        })
+    );
+}
+
+#[test]
+fn the_suffix_counts_towards_the_size() {
+    let fee_payer = Pubkey::new_unique();
+    let items = [item(PACKET_DATA_SIZE / 3), item(PACKET_DATA_SIZE / 3)];
+    let memo = instruction(PACKET_DATA_SIZE / 3, &[]);
+
+    assert_eq!(
+        groups(&TransactionPacker::new(fee_payer), &items),
+        [vec![0, 1]]
+    );
+    let packer = TransactionPacker::new(fee_payer).with_suffix(vec![memo.clone()]);
+    assert_eq!(groups(&packer, &items), [vec![0], vec![1]]);
+    // Left out of the packed instructions, the sender appends it
+    assert_eq!(
+        packer.pack(&items).unwrap()[0].instructions,
+        items[0].instructions
+    );
+}
+
+#[test]
+fn accounts_are_created_before_they_are_used() {
+    let index = Pubkey::new_unique();
+    let access = Pubkey::new_unique();
+    let items = [
+        // Uses the access account created by the last item
+        PackItem::new(vec![instruction(8, &[access])]),
+        item(8),
+        // Creates the access account once the index exists
+        PackItem::new(vec![instruction(8, &[index, access])]).with_created(vec![access]),
+        PackItem::new(vec![instruction(8, &[index])]).with_created(vec![index]),
+    ];
+
+    let transactions = TransactionPacker::new(Pubkey::new_unique())
+        .pack(&items)
+        .unwrap();
+
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].items, [1, 3, 2, 0]);
+    assert_eq!(transactions[0].instructions[1], items[3].instructions[0]);
+}
+
+#[test]
+fn creations_land_in_earlier_transactions_when_split() {
+    let created = Pubkey::new_unique();
+    let items = [
+        PackItem::new(vec![instruction(PACKET_DATA_SIZE / 2, &[created])]),
+        PackItem::new(vec![instruction(PACKET_DATA_SIZE / 2, &[created])])
+            .with_created(vec![created]),
+    ];
+
+    assert_eq!(
+        groups(&TransactionPacker::new(Pubkey::new_unique()), &items),
+        [vec![1], vec![0]]
+    );
+}
+
+#[test]
+fn items_creating_what_each_other_uses_fail() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let items = [
+        item(8),
+        PackItem::new(vec![instruction(8, &[second])]).with_created(vec![first]),
+        PackItem::new(vec![instruction(8, &[first])]).with_created(vec![second]),
+    ];
+
+    assert_eq!(
+        TransactionPacker::new(Pubkey::new_unique()).pack(&items),
+        Err(PackError::DependencyCycle(vec![1, 2]))
+    );
+}
@@ -0,0 +1,234 @@
+//! The utils derivations agree with the program's over a corpus of random
+//! authorities and hashes, and the bumps they return re-derive the address.
+//! Legacy dataset and associated access addresses keep the derivations
+//! without a marker, which `DerivationVersion::V1` selects
+
+use datanexus::pda;
+use datanexus_utils::{
+    find_access_address_with_program_id, find_associated_access_address_with_program_id,
+    find_dataset_address_with_program_id, find_owner_address_with_program_id, get_access_address,
+    get_associated_access_address, get_associated_access_address_versioned, get_dataset_address,
+    get_dataset_address_versioned, get_owner_address, DerivationVersion, ACCESS_MARKER,
+    ASSOCIATED_ACCESS_MARKER, DATASET_MARKER, OWNER_MARKER,
+};
+// The legacy functions stay covered while they are deprecated
+#[allow(deprecated)]
+use datanexus_utils::{
+    find_legacy_associated_access_address_with_program_id,
+    find_legacy_dataset_address_with_program_id, get_legacy_associated_access_address,
+    get_legacy_dataset_address,
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+/// Authorities and hashes the derivations are checked over
+const CORPUS_LEN: usize = 64;
+
+fn authorities() -> Vec<Pubkey> {
+    (0..CORPUS_LEN).map(|_| Keypair::new().pubkey()).collect()
+}
+
+fn hashes() -> Vec<[u8; 32]> {
+    (0..CORPUS_LEN)
+        .map(|_| Keypair::new().pubkey().to_bytes())
+        .collect()
+}
+
+#[test]
+fn test_markers_are_the_seed_prefixes() {
+    assert_eq!(OWNER_MARKER, b"owner");
+    assert_eq!(ACCESS_MARKER, b"access");
+    assert_eq!(DATASET_MARKER, b"dataset");
+}
+
+#[test]
+fn test_owner_addresses_agree() {
+    let program_id = datanexus::id();
+    for authority in authorities() {
+        let (address, bump) = find_owner_address_with_program_id(&program_id, authority);
+
+        assert_eq!(
+            (address, bump),
+            pda::find_owner_address(&program_id, &authority)
+        );
+        assert_eq!(
+            Pubkey::find_program_address(&[b"owner", authority.as_ref()], &program_id),
+            (address, bump)
+        );
+        assert_eq!(
+            pda::create_owner_address_with_bump(&program_id, &authority, bump),
+            Ok(address)
+        );
+        assert_eq!(get_owner_address(authority), address);
+    }
+}
+
+#[test]
+fn test_access_addresses_agree() {
+    let program_id = datanexus::id();
+    for authority in authorities() {
+        let (address, bump) = find_access_address_with_program_id(&program_id, authority);
+
+        assert_eq!(
+            (address, bump),
+            pda::find_access_address(&program_id, &authority)
+        );
+        assert_eq!(
+            Pubkey::find_program_address(&[b"access", authority.as_ref()], &program_id),
+            (address, bump)
+        );
+        assert_eq!(
+            pda::create_access_address_with_bump(&program_id, &authority, bump),
+            Ok(address)
+        );
+        assert_eq!(get_access_address(authority), address);
+    }
+}
+
+#[test]
+fn test_dataset_addresses_agree() {
+    let program_id = datanexus::id();
+    for hash in hashes() {
+        let (address, bump) = find_dataset_address_with_program_id(&program_id, &hash);
+
+        assert_eq!(
+            (address, bump),
+            pda::find_dataset_address(&program_id, &hash)
+        );
+        assert_eq!(
+            Pubkey::find_program_address(&[b"dataset", &hash], &program_id),
+            (address, bump)
+        );
+        assert_eq!(
+            pda::create_dataset_address_with_bump(&program_id, &hash, bump),
+            Ok(address)
+        );
+        assert_eq!(get_dataset_address(&hash), address);
+    }
+}
+
+#[test]
+fn test_associated_access_addresses_agree() {
+    let program_id = datanexus::id();
+    for (authority, hash) in authorities().into_iter().zip(hashes()) {
+        let dataset = get_dataset_address(&hash);
+        let (address, bump) =
+            find_associated_access_address_with_program_id(&program_id, authority, dataset);
+
+        assert_eq!(
+            (address, bump),
+            pda::find_associated_access_address(&program_id, &authority, &dataset)
+        );
+        assert_eq!(
+            pda::create_associated_access_address_with_bump(
+                &program_id,
+                &authority,
+                &dataset,
+                bump
+            ),
+            Ok(address)
+        );
+        assert_eq!(get_associated_access_address(authority, dataset), address);
+    }
+}
+
+#[test]
+fn test_wrong_bumps_do_not_rederive_the_address() {
+    let program_id = datanexus::id();
+    for authority in authorities() {
+        let (address, bump) = find_owner_address_with_program_id(&program_id, authority);
+
+        // Lower bumps may still be off the curve, but never at `address`
+        for other in (0..bump).rev().take(4) {
+            assert_ne!(
+                pda::create_owner_address_with_bump(&program_id, &authority, other),
+                Ok(address)
+            );
+        }
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_legacy_dataset_addresses_derive_from_the_bare_hash() {
+    let program_id = datanexus::id();
+    for hash in hashes() {
+        let legacy = get_legacy_dataset_address(&hash);
+
+        assert_eq!(
+            legacy,
+            Pubkey::find_program_address(&[&hash], &program_id).0
+        );
+        assert_eq!(
+            (
+                legacy,
+                pda::find_legacy_dataset_address(&program_id, &hash).1
+            ),
+            find_legacy_dataset_address_with_program_id(&program_id, &hash)
+        );
+        assert_ne!(legacy, get_dataset_address(&hash));
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_associated_access_addresses_are_marked() {
+    let program_id = datanexus::id();
+    for (authority, hash) in authorities().into_iter().zip(hashes()) {
+        let dataset = get_dataset_address(&hash);
+        let legacy = get_legacy_associated_access_address(authority, dataset);
+
+        assert_eq!(
+            get_associated_access_address(authority, dataset),
+            Pubkey::find_program_address(
+                &[
+                    ASSOCIATED_ACCESS_MARKER,
+                    authority.as_ref(),
+                    dataset.as_ref()
+                ],
+                &program_id
+            )
+            .0
+        );
+        assert_eq!(
+            legacy,
+            Pubkey::find_program_address(&[authority.as_ref(), dataset.as_ref()], &program_id).0
+        );
+        assert_eq!(
+            (
+                legacy,
+                pda::find_legacy_associated_access_address(&program_id, &authority, &dataset).1
+            ),
+            find_legacy_associated_access_address_with_program_id(&program_id, authority, dataset)
+        );
+        assert_ne!(legacy, get_associated_access_address(authority, dataset));
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_versions_select_the_bare_or_marked_derivation() {
+    assert_eq!(DerivationVersion::default(), DerivationVersion::V2);
+    for (authority, hash) in authorities().into_iter().zip(hashes()) {
+        let dataset = get_dataset_address(&hash);
+
+        assert_eq!(
+            get_dataset_address_versioned(DerivationVersion::V1, &hash),
+            get_legacy_dataset_address(&hash)
+        );
+        assert_eq!(
+            get_dataset_address_versioned(DerivationVersion::LATEST, &hash),
+            dataset
+        );
+        assert_eq!(
+            get_associated_access_address_versioned(DerivationVersion::V1, authority, dataset),
+            get_legacy_associated_access_address(authority, dataset)
+        );
+        assert_eq!(
+            get_associated_access_address_versioned(DerivationVersion::LATEST, authority, dataset),
+            get_associated_access_address(authority, dataset)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Datasets looked up by hash through registry pages held in an in-memory
+//! cluster, including prefixes spilling onto overflow pages
+
+use std::{cell::RefCell, collections::HashMap};
+
+use datanexus::state::{RegistryEntry, RegistryState, MAX_REGISTRY_ENTRIES};
+use datanexus_utils::{
+    client::{AccountFetcher, FetchError},
+    get_registry_address, lookup_dataset_by_hash, registry_accounts,
+};
+use solana_client::client_error::ClientError;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+/// Hashes sharing the `[1, 1]` prefix
+const HASH: [u8; 32] = [1; 32];
+const COLLIDING: [u8; 32] = {
+    let mut hash = [1; 32];
+    hash[2] = 2;
+    hash
+};
+
+/// Accounts held in memory, counting the requests made for them
+#[derive(Default)]
+struct Cluster {
+    accounts: HashMap<Pubkey, Account>,
+    requests: RefCell<usize>,
+}
+
+impl AccountFetcher for Cluster {
+    fn fetch_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        *self.requests.borrow_mut() += 1;
+        Ok(self.accounts.get(address).cloned())
+    }
+}
+
+impl Cluster {
+    /// Adds page `page` of `HASH`'s prefix listing `entries`, linked to the
+    /// page after it when `chained`
+    fn add_page(&mut self, page: u8, entries: Vec<RegistryEntry>, chained: bool) {
+        let state = RegistryState {
+            is_initialized: true,
+            prefix: [1, 1],
+            page,
+            next: Some(get_registry_address(&HASH, page + 1)).filter(|_| chained),
+            entries,
+        };
+        let mut data = vec![0; RegistryState::space(state.entries.len()).unwrap()];
+        state.pack_into_slice(&mut data);
+        self.accounts.insert(
+            get_registry_address(&HASH, page),
+            Account {
+                lamports: 1,
+                data,
+                owner: datanexus::id(),
+                ..Account::default()
+            },
+        );
+    }
+}
+
+fn entry(hash: [u8; 32]) -> RegistryEntry {
+    RegistryEntry {
+        hash,
+        dataset: Pubkey::new_unique(),
+    }
+}
+
+/// A full page of entries none of the test hashes match
+fn full_page() -> Vec<RegistryEntry> {
+    vec![entry([9; 32]); MAX_REGISTRY_ENTRIES]
+}
+
+#[test]
+fn colliding_hashes_are_told_apart_on_their_page() {
+    let mut cluster = Cluster::default();
+    let (listed, colliding) = (entry(HASH), entry(COLLIDING));
+    cluster.add_page(0, vec![listed, colliding], false);
+
+    let found = lookup_dataset_by_hash(&cluster, &datanexus::id(), &COLLIDING).unwrap();
+
+    assert_eq!(found, Some(colliding.dataset));
+    assert_eq!(
+        lookup_dataset_by_hash(&cluster, &datanexus::id(), &HASH).unwrap(),
+        Some(listed.dataset)
+    );
+    let mut unlisted = HASH;
+    unlisted[31] = 0;
+    assert_eq!(
+        lookup_dataset_by_hash(&cluster, &datanexus::id(), &unlisted).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn prefixes_without_a_page_list_nothing() {
+    let cluster = Cluster::default();
+
+    assert_eq!(
+        lookup_dataset_by_hash(&cluster, &datanexus::id(), &HASH).unwrap(),
+        None
+    );
+    assert_eq!(*cluster.requests.borrow(), 1);
+}
+
+#[test]
+fn lookups_follow_the_chain_to_overflow_pages() {
+    let mut cluster = Cluster::default();
+    let listed = entry(HASH);
+    cluster.add_page(0, full_page(), true);
+    cluster.add_page(1, vec![listed], false);
+
+    let found = lookup_dataset_by_hash(&cluster, &datanexus::id(), &HASH).unwrap();
+
+    assert_eq!(found, Some(listed.dataset));
+    assert_eq!(*cluster.requests.borrow(), 2);
+}
+
+#[test]
+fn pages_of_another_program_are_rejected() {
+    let mut cluster = Cluster::default();
+    cluster.add_page(0, vec![entry(HASH)], false);
+    cluster
+        .accounts
+        .get_mut(&get_registry_address(&HASH, 0))
+        .unwrap()
+        .owner = Pubkey::new_unique();
+
+    assert!(matches!(
+        lookup_dataset_by_hash(&cluster, &datanexus::id(), &HASH),
+        Err(FetchError::WrongOwner(_))
+    ));
+}
+
+#[test]
+fn new_datasets_are_listed_on_the_last_page() {
+    let mut cluster = Cluster::default();
+    let first = get_registry_address(&HASH, 0);
+    let accounts = registry_accounts(&cluster, &datanexus::id(), &COLLIDING).unwrap();
+    assert_eq!(accounts.registry_account, first);
+    assert_eq!(accounts.overflow_account, None);
+
+    cluster.add_page(0, vec![entry(HASH)], false);
+    let accounts = registry_accounts(&cluster, &datanexus::id(), &COLLIDING).unwrap();
+    assert_eq!(accounts.registry_account, first);
+    assert_eq!(accounts.overflow_account, None);
+
+    cluster.add_page(0, full_page(), false);
+    let accounts = registry_accounts(&cluster, &datanexus::id(), &COLLIDING).unwrap();
+    assert_eq!(accounts.registry_account, first);
+    assert_eq!(
+        accounts.overflow_account,
+        Some(get_registry_address(&HASH, 1))
+    );
+
+    cluster.add_page(0, full_page(), true);
+    cluster.add_page(1, vec![entry(HASH)], false);
+    let accounts = registry_accounts(&cluster, &datanexus::id(), &COLLIDING).unwrap();
+    assert_eq!(accounts.registry_account, get_registry_address(&HASH, 1));
+    assert_eq!(accounts.overflow_account, None);
+}
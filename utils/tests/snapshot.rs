@@ -0,0 +1,81 @@
+//! Snapshots of an authority's accounts taken from a `solana-program-test`
+//! bank, written out as JSON and compared against the bank again
+
+#![cfg(feature = "snapshot")]
+
+mod common;
+
+use common::*;
+use datanexus_utils::{
+    diff_snapshots, get_access_address, get_associated_access_address, get_dataset_address,
+    get_owner_address, take_snapshot, Divergence, Snapshot, SNAPSHOT_VERSION,
+};
+use serde_json::json;
+
+#[test]
+fn snapshots_survive_a_json_round_trip() {
+    let (bank, _, mint) = setup();
+    let buyer = buyer(&bank, &mint);
+
+    let snapshot = take_snapshot(buyer.rpc(), &datanexus::id(), buyer.payer()).unwrap();
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    assert!(snapshot.owner_indexes.is_empty());
+    assert_eq!(snapshot.access_indexes.len(), 1);
+    assert_eq!(
+        snapshot.access_indexes[0].address,
+        get_access_address(buyer.payer())
+    );
+    assert_eq!(snapshot.datasets.len(), 1);
+    assert_eq!(snapshot.datasets[0].state.hash, HASH);
+    assert_eq!(
+        snapshot.associated_access[0].address,
+        get_associated_access_address(buyer.payer(), get_dataset_address(&HASH))
+    );
+
+    let document = serde_json::to_string(&snapshot).unwrap();
+    let restored: Snapshot = serde_json::from_str(&document).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&restored).unwrap(),
+        serde_json::to_value(&snapshot).unwrap()
+    );
+    let current = take_snapshot(buyer.rpc(), &datanexus::id(), buyer.payer()).unwrap();
+    assert_eq!(diff_snapshots(&restored, &current), vec![]);
+}
+
+#[test]
+fn changes_since_a_snapshot_diverge() {
+    let (bank, owner, mint) = setup();
+    let snapshot = take_snapshot(owner.rpc(), &datanexus::id(), owner.payer()).unwrap();
+    assert_eq!(
+        snapshot.owner_indexes[0].address,
+        get_owner_address(owner.payer())
+    );
+    assert!(snapshot.access_indexes.is_empty());
+
+    buyer(&bank, &mint);
+
+    let current = take_snapshot(owner.rpc(), &datanexus::id(), owner.payer()).unwrap();
+    let divergences = diff_snapshots(&snapshot, &current);
+    assert!(divergences.contains(&Divergence {
+        path: format!("datasets.{}.purchase_count", get_dataset_address(&HASH)),
+        expected: Some(json!(0)),
+        actual: Some(json!(1)),
+    }));
+    assert!(divergences
+        .iter()
+        .all(|divergence| divergence.path.starts_with("datasets.")));
+}
+
+#[test]
+fn authorities_without_accounts_have_empty_snapshots() {
+    let bank = Bank::start();
+    let client = bank.client();
+
+    let snapshot = take_snapshot(client.rpc(), &datanexus::id(), client.payer()).unwrap();
+
+    assert!(snapshot.owner_indexes.is_empty());
+    assert!(snapshot.access_indexes.is_empty());
+    assert!(snapshot.datasets.is_empty());
+    assert!(snapshot.associated_access.is_empty());
+}